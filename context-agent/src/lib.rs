@@ -9,32 +9,78 @@ This guide is for automation/LLM agents that call `context` to read and write pr
 - Fetch with metadata: `context get --project <id> --key <key> --json`.
 - Search: `context find --project <id> "<query>" --json`.
 - Remove: `context rm --project <id> --key <key>` (soft delete).
-- Cleanup: `context gc --project <id>` (vacuum tombstones).
+- Cleanup: `context gc --project <id>` (purge expired documents and tombstones, reindex, vacuum, analyze).
 
 ## Conventions for agents
 - Always pass `--json` when parsing output programmatically.
 - Provide `--project` for every command until `context project` subcommands land.
-- Include `--scenario` or `CONTEXT_SCENARIO` when running scripted sessions.
+- Include `--scenario` or `CONTEXT_SCENARIO` when running scripted sessions, or run `eval "$(context scenario start)"` once: every later `context` command (and process it starts) logs under that id until `context scenario end`.
 - Prefer stable keys like `notes/<topic>`; add `--tag` for filtering.
 - Use `context cat` when you only need body text (no JSON framing).
 
 ## Command cheatsheet
-- `context put [--project <id>] [--key <key>] [--tag <tag>...] [--json]` — reads stdin or `--file`, creates/updates a document.
-- `context get [--project <id>] (--key <key> | --id <id>) [--json]` — returns metadata + body (default markdown).
-- `context cat [--project <id>] (--key <key> | --id <id>)` — body only, no framing.
-- `context find [--project <id>] <query> [--limit N] [--all-projects] [--json]` — ranked search results.
-- `context ls [--project <id>] [--json]` — list documents for a project.
-- `context rm [--project <id>] (--key <key> | --id <id>) [--force] [--json]` — soft delete; use `gc` to purge.
-- `context gc [--project <id>] [--dry-run] [--json]` — vacuum/purge tombstones.
-- `context web|web-dev [--port <p>] [--json]` — launch server wrappers.
-- `context debug-bundle [--scenario <id>] [--out <path>] [--json]` — collect logs/traces.
+- `context put [--project <id>] [--key <key>] [--tag <tag>...] [--namespace <ns>] [--idempotency-key <k>] [--ttl 7d] [--title <t>] [--visibility private|project|public] [--meta name=value...] [--from-template <name> --var k=v...] [--json]` — reads stdin, `--file`, or a filled-in template, creates/updates a document (`--ttl` expires it that long after the write: `m`, `h`, `d`, `w`; `--visibility private` keeps it on this machine: exports, `context-web`, `context-grpc`, and `serve-readonly` leave it out, and later revisions keep it private until set otherwise; `--meta ticket=ABC-123` attaches a structured field that shows up as `metadata` in JSON output and is searchable, later revisions keep entries they don't set, and `--meta name=` removes one); a `---` front matter block at the top of a `--file` or template (`title`, `key`, `namespace`, `tags`, `ttl`, `visibility`) fills in what the flags leave out and is stripped from the body, and the title otherwise comes from the file's first `# heading`; `--batch` reads JSON Lines instead (`{"key", "body", "tags", "namespace", "title", "ttl", "visibility", "meta"}` per line, from stdin or `--file`), stores them in one transaction, and prints one `{line, status, id, key, version}` result per line (`status: "error"` with an `error` for lines that don't parse, which are skipped and make the command exit 1), and `--fast-import` fills in the search index once after the batch instead of per line; retrying with the same `--idempotency-key` within 24h replays the first result instead of writing again (web: `PUT /api/projects/<id>/docs/<key>` with an `Idempotency-Key` header; `GET`/`DELETE` on the same path too, with an `ETag` and `If-Match` for conflict-safe edits).
+- `context get [--project <id>] (--key <key> | --id <id>) [--as-of <ts> | --version N] [--max-tokens N] [--json]` — returns metadata + body (default markdown) with its token count; `--as-of` returns the revision current at that time, `--version` a specific one. With `remote.url` in `config.json` (a team `context-web` server; bearer token from `CONTEXT_REMOTE_TOKEN`), a key missing locally is read from the server and cached locally with `source_meta.fetched_from` set (`remote.cache: false` skips the copy).
+- `context touch [--project <id>] (--key <key> | --id <id>) (--ttl 30d | --no-ttl) [--json]` — make a document expire that long from now, or never; no new version is written.
+- `context history [--project <id>] (--key <key> | --id <id>) [--json]` — every revision, newest first, with version, time, source, title, and token count.
+- `context diff [--project <id>] (--key <key> | --id <id>) [--from N] [--to M] [--context 3] [--json]` — unified diff of the body between two versions (default: the latest and the one before), colored on a terminal unless `NO_COLOR` is set; `--json` gives the hunks as `{old_start, old_lines, new_start, new_lines, lines: [{change: context|added|removed, text}]}`.
+- `context blame [--project <id>] (--key <key> | --id <id>) [--json]` — per-line attribution (version, source, time) like `git blame`.
+- `context related [--project <id>] (--key <key> | --id <id>) [--limit N] [--json]` — documents ranked by tag overlap, links, and text similarity (web: `GET /api/related?project=&key=`).
+- `context cat [--project <id>] (--key <key> | --id <id>)` — body only, no framing; repeat `--key` or pass `--keys a,b,c` to print several bodies in that order, separated by `--delimiter` (default `\n\n---\n\n`), in one call (missing keys exit 3 after the rest print; with `--json`, an array of `{key, found, document}` in request order).
+- `context find [--project <id>] <query> [--limit N] [--all-projects] [--max-tokens N] [--fields a,b|--key-only] [--not-tag <tag>...] [--exclude-namespace <ns>...] [--within <key-prefix>] [--keys k1,k2] [--explain] [--code] [--timeout <ms>] [--json] [--format text|json|ndjson]` — ranked search results with per-document token counts and a combined `score` (unless `--fields` trims the output); `--max-tokens` caps their combined size; `--fields key,title,updated_at` keeps only those JSON fields; `-word` or `-"a phrase"` in the query excludes matches; `--within`/`--keys` search only a key subtree or set; `--explain` adds each hit's score breakdown (bm25, recency, tag bonus, and a pin boost that stays 0 until documents can be pinned), which adds up to its score; `--code` matches identifiers in fenced code blocks only, split so `HttpRequest` or `http_request` finds `parseHttpRequest`; `--timeout 500` stops a search still running after 500ms (exit 5), in place of `query_timeout_secs`. Web: `POST /api/projects/<id>/search` with a JSON search query body returns hits among its `project` and `public` documents; when `CONTEXT_WEB_TOKEN` is set, `/api/` and `/graphql` need `Authorization: Bearer <token>`.
+- `context ls [--project <id>] [--fields a,b|--key-only] [--tag <tag>...] [--not-tag <tag>...] [--namespace <ns>] [--exclude-namespace <ns>...] [--prefix <p>] [--since <t>] [--until <t>] [--include-deleted|--deleted] [--expiring-within 24h] [--visibility private,...] [--meta name=value...] [--sort updated|created|key] [--limit <n>] [--cursor <c>] [--count] [--json] [--format text|json|ndjson|csv|tsv]` — list documents for a project, most recently updated first (private ones marked `[private]`); `--sort created` lists newest-created first, `--sort key` A to Z (unkeyed documents first), and a cursor only resumes the sort it came from; `--count` prints only how many match; `--deleted` lists only soft-deleted documents; `--expiring-within` only those whose TTL runs out within that span; `--meta` only those with every given metadata entry; with `--limit` the next page's cursor is printed (stderr for json/ndjson); `ndjson` streams one document per line; `csv`/`tsv` print a spreadsheet table of key, title, tags, created_at, updated_at, size (body bytes), and version (`--fields` picks other columns such as id, namespace, source, visibility). The web API takes the same filters: `GET /api/projects/<id>/docs?tags=a,b&meta=ticket=ABC-123&key_prefix=..&sort=key&limit=..&cursor=..`; it never returns private documents (404 on get, 403 on put).
+- `context rm [--project <id>] (--key <key> | --id <id>) [--force] [--purge] [--json]` — soft delete; `restore` undoes it until `gc` purges it; `--purge` also erases earlier versions; `--force` deletes the document and its history for good, tombstone included, and succeeds if already missing.
+- `context restore [--project <id>] (--key <key> | --id <id>) [--json]` — undo `rm`: the document comes back as a new version and is searchable again; fails if another live document has taken its key.
+- `context gc [--project <id>] [--dry-run] [--check-only] [--retention 30d] [--json]` — permanently purge the project's documents whose TTL has run out (`expired`) and its deleted documents (`deleted`; no `restore` after this), keeping those deleted within `--retention` (or `tombstone_retention` in config.json); then remove orphaned search-index and history rows, rebuild the search index (`reindexed`), vacuum, and refresh the query planner's statistics (`analyzed` counts the tables and indexes covered); `--dry-run`/`--check-only` report the exact counts without changing anything.
+- `context db analyze [--json]` — refresh the query planner's statistics (SQLite `ANALYZE`) without purging anything; worth running once a store has grown by hundreds of thousands of documents. Batch writes of 1000+ documents (`put --batch`, `import`) already run `PRAGMA optimize`.
+- `context pack [--project <id>] [--key <key>...] [--query <q>] [--budget|--max-tokens N] [--json]` — concatenate the most relevant docs (deduped, with `## key (vN)` headers) into one markdown blob within a token budget. Like `export`, it reads one snapshot of the store, so a concurrent multi-document write shows up entirely or not at all.
+- `context summarize [--project <id>] (--key <key> | --id <id> | --query <q> [--limit N]) [--out-key <key>] [--json]` — condense via the LLM configured under `summarize` in `config.json`; stores the result tagged `summary` (default key `summary/<key>`).
+- `context digest [--project <id>] [--since 7d] [--json]` — compile recently changed docs into a `digest/<date>` document tagged `digest` (schedule with cron for a weekly view).
+- `context todos [--project <id>] [--open] [--decisions] [--all-projects] [--json]` — `TODO:` markers and `- [ ]`/`- [x]` checkboxes found in bodies (re-scanned on every put), with key and line; `--decisions` lists `DECISION:` markers instead.
+- `context remind [--project <id>] (--key <key> | --id <id>) --at <ts> [--note <text>] [--json]` — attach a follow-up to a document (`--at` is RFC3339 or `YYYY-MM-DD`, UTC).
+- `context reminders due|list [--done] [--all-projects] [--json]` / `context reminders done <id>` — reminders whose time has passed (or all pending ones, soonest first); due ones stay listed until marked done.
+- `context calendar [--project <id>] [--all-projects] [--out <path>] [--json]` — pending reminders and upcoming TTL expirations as an iCalendar feed (web: `GET /api/calendar.ics?project=<id>` to subscribe from a calendar app).
+- `context notify test [--project <id>] [--message <text>] [--json]` — send a message through the project's alert channels: `notify.channels` in `config.json` (e.g. `{"type": "slack"}` with `CONTEXT_SLACK_WEBHOOK_URL`, or `{"type": "smtp", "host": ..., "from": ..., "to": [...]}`), replaced per project under `notify.projects`.
+- `context ns list|create <ns>|rename <from> <to>|rm <ns> [--force]` — manage namespaces with document counts; rename rewrites documents in one transaction.
+- `context tag add|remove --key <key> <tag>... [--json]` / `context tag list --key <key>` — change one document's tags without re-putting its body (a new revision, reindexed for `find`; `"status": "unchanged"` when nothing changed). `context tags [--json]` lists the project's tags with live-document counts.
+- `context attach --key <key> --file <path> [--name <n>] [--media-type <t>] [--json]` — store a file (image, PDF, log; up to 25 MiB) with the document; it stays through later revisions, attaching the same name again replaces it, and purging the document removes it. `context attachments list --key <key> [--json]` shows name, media type, size, and sha256; `context attachments get --key <key> <name> [--out <path>]` writes the bytes to stdout or a file.
+- `context links (--key <key> | --id <id>) [--json]` — the documents its body links to with `[[key]]` (same project; `[[key|label]]` works too) or `context://<project>/<key>`, re-scanned on every put and shown as `(missing)` until something lives at the key; `context backlinks --key <key> [--json]` lists the live documents linking to it (including through a key it was renamed from).
+- `context policy apply [--project <id>] [--dry-run] [--json]` — set TTLs from `expiry` rules in `config.json` (e.g. `{"tag": "scratch", "after": "14d"}`) on existing docs; new docs get them at put time.
+- `context project list [--json]` — every project with live document count, total body size, and last update (web: `GET /api/projects`, whose totals leave out private documents).
+- `context project show [<project>]|edit [<project>] [--name <n>] [--description <d>] [--setting k=v...]` — display name, description, and settings from the project registry in the database.
+- `context project alias add <alias> <project>|rm <alias>|list` — short names (e.g. `ctx`) accepted anywhere `--project` is.
+- `context project export [--out <path>]` / `context project import [--file <path>] [--replace]` — copy known projects, aliases, and settings to another machine (import merges by default).
+- `context dedup report [--project <id>] [--threshold 0.8] [--merge keep-newest] [--json]` — groups of identical or near-identical documents; `--merge keep-newest` deletes the older copies and redirects their keys to the kept one.
+- `context template add <name> [--file <path>] [--description <d>]` / `template list` / `template render <name> [--var k=v...]` / `template rm <name>` [--json] — per-project Markdown skeletons (ADRs, postmortems); blanks are `{{name}}`, filled from `--var` (`{{date}}` defaults to today), and a blank without a value is an error naming it.
+- `context import <dir|file.jsonl> [--project <id>] [--glob <pattern>] [--dry-run] [--fast-import] [--json]` — load every `.md`/`.markdown` file under `<dir>` (or those matching `--glob`) as `Import`-sourced documents in one transaction, keyed by relative path without the extension unless front matter sets `key`; front matter also sets title, tags, namespace, ttl, and visibility; the summary counts created, updated, and skipped (unchanged) files. Given a JSON Lines file from `export --format jsonl` it merges by id instead: documents keep their ids, versions, and timestamps, the higher version wins (`kept` when the local one is newer), and a same-version edit or a key held by another document is reported as a `conflict` with the reason and left alone. `--fast-import` fills in the search index once after writing instead of per document, much faster for thousands of files.
+- `context export [--project <id>] [--out <dir|file>] [--format markdown|jsonl|csv|tsv] [--include-private] [--json]` — write the project's live documents (private ones only with `--include-private`; the summary counts those left out) as `<namespace>/<key>.md` files under a new or empty `--out` directory, with front matter (key, namespace, title, tags, visibility, timestamps, version) that `context import` reads back; `--format jsonl` writes one document JSON per line to `--out` or stdout, and `csv`/`tsv` the same metadata table as `ls --format csv`; `--exclude-tag <tag>` leaves documents with that tag out and `--redact-pattern <regex>` replaces matches in titles and bodies with `[REDACTED]`, and either writes a manifest of excluded/redacted ids, keys, and match counts (`manifest.json` in the directory, or `<file>.manifest.json`).
+- `context fixtures list|load <small|demo|large> [--project <id>] [--seed N] [--json]` — fill an empty project with a reproducible sample corpus (same seed, same documents, history, and timestamps); `CONTEXT_WEB_DEMO=demo context-web` serves one from a throwaway store.
+- `context keys normalize [--project <id>] [--dry-run] [--json]` — rewrite keys to NFC/lowercase (set `keys.normalize` in `config.json`); reports collisions.
+- `context keys rename <from> <to> [--project <id>] [--json]` — move a document to a new key; `get`/`cat` on the old key follow the redirect (notice on stderr), as do reads through `context-web`, GraphQL, and `context-grpc`. `context keys redirects` lists them.
+- `context mv --key <old> --to <new> [--no-alias] [--project <id>] [--json]` — the same rename as `keys rename`, written as one new revision; `--no-alias` frees the old key instead of redirecting it (older redirects still follow the document).
+- `context init [--project <id>] [--force] [--agent-config [all|codex|claude|copilot]] [--json]` — create `$CONTEXT_HOME`, migrate `db.sqlite`, and write a starter `config.json` whose current project is `--project` or the git repository's name; `--agent-config` also writes this guide to `AGENTS.md`/`CLAUDE.md`/`.github/copilot-instructions.md` at the repo root; `--force` re-initializes (old config kept as `config.json.bak`).
+- `context whoami [--json]` — resolved project (and where it came from), context home, DB path/size, config file, log file, sync remote, `CONTEXT_AGENT` identity, and this machine's id (generated once into `$CONTEXT_HOME/machine-id`).
+- `context web|web-dev [--port <p>] [--json]` — launch server wrappers. The `context-web` binary serves `$CONTEXT_HOME/db.sqlite`, or an existing database named by `--db <path>` or `CONTEXT_WEB_DB`. `GET /api/events[?project=<id>]` is a server-sent events stream of writes made through that server (`created`/`updated`/`deleted` events with `{kind, project, id, key, version, visibility, at, bytes}`, private documents left out; `lagged` means events were missed). CLI writes to the database file from another process do not appear there.
+- `context daemon [--socket <path>] [--socket-mode 600] [--notify]` — keep the store open on `$CONTEXT_HOME/daemon.sock` (owner-only by default); `put`/`get`/`find` proxy through it while it runs (`CONTEXT_NO_DAEMON=1` opts out); config.json (key rules, expiry, `log_filter`) is re-read on change or SIGHUP; `--notify` announces each due reminder once, as a desktop notification (`notify-send`, or `osascript` on macOS) and through the project's `notify` channels.
+- `context serve-readonly [--projects a,b] [--host 127.0.0.1] [--port 8079] [--public-only] [--json]` — publish a read-only HTML view with search of the listed projects (default: the current one), rendered once at startup, never including private documents (`--public-only`: only documents put with `--visibility public`); only `GET` routes exist, raw HTML in bodies is escaped, and restarting picks up new revisions.
+- `context scenario start [--label <name>] [--json]` / `context scenario end [--json]` — start a scenario, id `<RFC 3339 UTC time>-<label or random>` (the `@scenario(...)` value for plan claims), kept in `$CONTEXT_HOME/scenario.json` and printed as `export CONTEXT_SCENARIO=<id>`; starting ends any running one. Both log `scenario.started`/`scenario.ended` under the id; `end` exits 3 when none is running. `--scenario` and `CONTEXT_SCENARIO` override it.
+- `context debug-bundle [--scenario <id>] [--out <path>] [--json]` — collect logs/traces, crash reports, and a list of attachments (names, sizes, and hashes, never their bytes) (a panic in any context binary writes one to `<log dir>/crashes` and logs it with a backtrace).
+- `context logs [--app cli|web|grpc] [--pretty] [--tail N] [--scenario <id>] [--level warn] [--json]` — print a binary's JSONL log, filtered; `--pretty` renders each entry as one line (`time LEVEL span{fields}: message key=value`) for reading without `jq`. Every CLI invocation ends its log with one `command.completed` entry: `argv` (values of `--meta`/`--var` pairs and of token/password/secret flags redacted, long arguments cut), `duration_ms`, `exit_code`, and the `documents_written`/`bytes_written` by that process.
+- `context completions <bash|zsh|fish|powershell|elvish>` — print a shell completion script.
 - `context agent-config --target <all|codex|claude|copilot>` — emit agent configs.
 - `context agent-doc --format markdown` — emit this guide; redirect to `docs/agent-doc.md` to sync.
 
 ## Tips
 - STDIN vs `--file`: prefer piping for generated content; use `--file` for saved notes.
+- Tokens: counts use the `cl100k_base` tokenizer; budget prompts with `--max-tokens` rather than byte sizes.
 - Tags: pass multiple `--tag` flags or comma-separated values.
+- Keys: letters, digits, and `-_./:@` up to 128 characters by default; tune `keys.max_length`, `keys.allowed_punctuation`, and `keys.reserved_prefixes` in `config.json`.
 - Errors: non-zero exit codes indicate failure; stderr carries user-facing messages.
+- Logs: each command's `context` span records `git_branch`, `git_commit`, and `git_dirty` for the working directory; set `"log_git": false` in `config.json` to skip running git.
+- Store size: commands end with a stderr warning suggesting `gc` once the database passes `warnings.max_db_mb` (default 1024) or the project holds more than `warnings.max_deleted` (default 10000) deleted documents; `"warnings": {"enabled": false}` in `config.json` silences it.
+- Missing documents: `get`/`cat`/`rm` (and any command naming a document, namespace, or template that doesn't exist) exit with code 3; with `--json` stdout carries `{"error":{"code":"not_found","message":...}}`.
+- Conflicts: a write that loses to the store's current state (renaming or restoring onto a key another live document holds, reusing an `--idempotency-key` for different content) exits with code 4 and `"code":"conflict"`.
+- Timeouts: a `search` or `ls --count` still running after `query_timeout_secs` (default 30; `0` disables) in `config.json` is stopped and exits with code 5 and `"code":"timeout"`.
 
 ## Keeping docs in sync
 Run: `cargo run -p context-cli -- agent-doc --format markdown > docs/agent-doc.md`.
@@ -0,0 +1,118 @@
+//! `context attach` stores a file alongside a document; `context attachments list|get`
+//! shows what a document has attached and writes one back out.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+
+use context_core::{attachments::Attachment, Document};
+
+use crate::{fetch_document_exact, open_storage, runtime, AttachmentCommands, NotFound};
+
+pub fn handle_attach(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+    file: PathBuf,
+    name: Option<String>,
+    media_type: Option<String>,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let name = match name {
+        Some(name) => name,
+        None => file
+            .file_name()
+            .with_context(|| format!("{} has no file name; pass --name", file.display()))?
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let data =
+        fs::read(&file).with_context(|| format!("Failed to read input file {}", file.display()))?;
+
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let doc = runtime.block_on(fetch_document_exact(&storage, &project, &key, &id))?;
+    let attachment =
+        runtime.block_on(storage.put_attachment(&doc, &name, media_type.as_deref(), &data))?;
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&attachment)?);
+    } else {
+        println!(
+            "Attached {} to {} ({}, {} bytes)",
+            attachment.name,
+            label(&doc),
+            attachment.media_type,
+            attachment.size
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_attachments(
+    project: Option<String>,
+    json_output: bool,
+    action: AttachmentCommands,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    match action {
+        AttachmentCommands::List { key, id } => {
+            let doc = runtime.block_on(fetch_document_exact(&storage, &project, &key, &id))?;
+            let attachments = runtime.block_on(storage.attachments(&doc.id))?;
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&attachments)?);
+                return Ok(());
+            }
+            println!("Attachments on {}", label(&doc));
+            for attachment in &attachments {
+                println!("{}", summary(attachment));
+            }
+        }
+        AttachmentCommands::Get { key, id, name, out } => {
+            let doc = runtime.block_on(fetch_document_exact(&storage, &project, &key, &id))?;
+            let Some((attachment, data)) = runtime.block_on(storage.attachment(&doc.id, &name))?
+            else {
+                return Err(
+                    NotFound(format!("{} has no attachment named {name}.", label(&doc))).into(),
+                );
+            };
+            match out {
+                Some(path) => {
+                    fs::write(&path, &data)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    if json_output {
+                        println!("{}", serde_json::to_string_pretty(&attachment)?);
+                    } else {
+                        println!("Wrote {} to {}", attachment.name, path.display());
+                    }
+                }
+                None => {
+                    let mut stdout = io::stdout().lock();
+                    stdout.write_all(&data)?;
+                    stdout.flush()?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn summary(attachment: &Attachment) -> String {
+    format!(
+        "- {} ({}, {} bytes, sha256 {})",
+        attachment.name,
+        attachment.media_type,
+        attachment.size,
+        &attachment.sha256[..12]
+    )
+}
+
+fn label(doc: &Document) -> &str {
+    doc.key.as_deref().unwrap_or(&doc.id.0)
+}
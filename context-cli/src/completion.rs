@@ -0,0 +1,123 @@
+//! The `command.completed` event: one log entry per invocation saying what ran, for how
+//! long, how it exited, and how much it wrote.
+//!
+//! Writes are counted from the change feed of every store the command opened (see
+//! `watch`), so they cover `put`, `import`, `rm`, and the rest alike. Writes a daemon or
+//! remote server makes on the command's behalf happen in another process and are not
+//! counted here.
+
+use std::{
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use context_core::{changes::DocumentChange, sqlite::SqliteStorage};
+use tokio::sync::broadcast::{error::TryRecvError, Receiver};
+
+/// Flags whose values are recorded as `[redacted]`.
+const SECRET_FLAG_WORDS: [&str; 3] = ["token", "password", "secret"];
+/// Flags taking `name=value` pairs, whose values are redacted but names kept.
+const PAIR_FLAGS: [&str; 2] = ["--var", "--meta"];
+/// Arguments longer than this are cut, so a pasted body can't flood the log.
+const MAX_ARG_CHARS: usize = 200;
+const REDACTED: &str = "[redacted]";
+
+fn feeds() -> &'static Mutex<Vec<Receiver<DocumentChange>>> {
+    static FEEDS: OnceLock<Mutex<Vec<Receiver<DocumentChange>>>> = OnceLock::new();
+    FEEDS.get_or_init(Default::default)
+}
+
+/// Count the writes made through `storage` (and its clones) toward this invocation.
+pub fn watch(storage: &SqliteStorage) {
+    if let Ok(mut feeds) = feeds().lock() {
+        feeds.push(storage.subscribe());
+    }
+}
+
+/// Documents written and the body bytes they carried, since each store was watched.
+fn writes() -> (u64, u64) {
+    let Ok(mut feeds) = feeds().lock() else {
+        return (0, 0);
+    };
+    let (mut documents, mut bytes) = (0, 0);
+    for feed in feeds.iter_mut() {
+        loop {
+            match feed.try_recv() {
+                Ok(change) => {
+                    documents += 1;
+                    bytes += change.bytes;
+                }
+                // The buffer overflowed: the count stays exact, the byte total runs short.
+                Err(TryRecvError::Lagged(missed)) => documents += missed,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+    }
+    (documents, bytes)
+}
+
+/// `argv` fit for the log: the program's path reduced to `context`, the values of
+/// secret-looking flags and of `--var`/`--meta` pairs redacted, and long arguments cut.
+pub fn sanitized_argv(argv: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut sanitized = Vec::new();
+    let mut redact_next = false;
+    let mut pair_next = false;
+    for (index, arg) in argv.into_iter().enumerate() {
+        let arg = if index == 0 {
+            "context".to_string()
+        } else if redact_next {
+            REDACTED.to_string()
+        } else if pair_next {
+            redact_pair(&arg)
+        } else if let Some((flag, value)) = arg.split_once('=').filter(|_| arg.starts_with("--")) {
+            if is_secret_flag(flag) {
+                format!("{flag}={REDACTED}")
+            } else if PAIR_FLAGS.contains(&flag) {
+                format!("{flag}={}", redact_pair(value))
+            } else {
+                truncate(&arg)
+            }
+        } else {
+            truncate(&arg)
+        };
+        let takes_value = index > 0 && arg.starts_with("--") && !arg.contains('=');
+        redact_next = takes_value && is_secret_flag(&arg);
+        pair_next = takes_value && PAIR_FLAGS.contains(&arg.as_str());
+        sanitized.push(arg);
+    }
+    sanitized
+}
+
+fn is_secret_flag(flag: &str) -> bool {
+    flag.starts_with("--") && SECRET_FLAG_WORDS.iter().any(|word| flag.contains(word))
+}
+
+fn redact_pair(pair: &str) -> String {
+    match pair.split_once('=') {
+        Some((name, _)) => format!("{}={REDACTED}", truncate(name)),
+        None => truncate(pair),
+    }
+}
+
+fn truncate(arg: &str) -> String {
+    match arg.char_indices().nth(MAX_ARG_CHARS) {
+        Some((end, _)) => format!("{}…", &arg[..end]),
+        None => arg.to_string(),
+    }
+}
+
+/// Log the `command.completed` event for an invocation that ran for `duration` and
+/// exits with `exit_code`. It goes to the log file only, so stderr stays the command's own.
+pub fn record(argv: &[String], duration: Duration, exit_code: i32) {
+    let (documents, bytes) = writes();
+    tracing::info!(
+        target: "context_cli::file_only",
+        event = "command.completed",
+        argv = ?argv,
+        duration_ms = duration.as_secs_f64() * 1000.0,
+        exit_code,
+        documents_written = documents,
+        bytes_written = bytes,
+        "Command completed"
+    );
+}
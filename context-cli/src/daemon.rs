@@ -0,0 +1,547 @@
+//! `context daemon`: keep the database open behind a unix socket so the hot commands
+//! (`put`, `get`, `find`) skip opening the pool, migration checks, and config loading.
+//!
+//! The protocol is one JSON `Request` per line, answered by one JSON `Reply` per line.
+//! Commands reach storage through `Backend`, which talks to a running daemon when it
+//! finds one and opens the database itself otherwise; both paths answer through
+//! `answer`, so a proxied command behaves exactly like a local one.
+
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use context_core::{
+    config::configure_storage,
+    idempotency::IdempotentWrite,
+    reload::{self, ConfigWatcher},
+    reminders::ReminderQuery,
+    sqlite::SqliteStorage,
+    traced::TracedStorage,
+    Conflict, ContextError, Document, DocumentId, SearchHit, SearchQuery, Storage,
+};
+use context_telemetry::TelemetryGuard;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context_home, is_not_found,
+    notify::{self, Notification, NotifyConfig},
+    open_storage, project_config_path, reload_project_config, resolve_document, runtime, NotFound,
+};
+
+/// Set to skip the daemon and always open the database directly.
+const NO_DAEMON_ENV: &str = "CONTEXT_NO_DAEMON";
+
+/// Where the daemon listens unless `--socket` says otherwise.
+pub(crate) fn default_socket() -> Result<PathBuf> {
+    Ok(context_home()?.join("daemon.sock"))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum Request {
+    Store {
+        draft: Document,
+        idempotency_key: Option<String>,
+    },
+    Fetch {
+        project: String,
+        key: Option<String>,
+        id: Option<String>,
+    },
+    VersionAsOf {
+        id: String,
+        at: DateTime<Utc>,
+    },
+    History {
+        id: String,
+    },
+    Search {
+        query: SearchQuery,
+        /// Replaces the configured query timeout for this search.
+        #[serde(default)]
+        timeout: Option<Duration>,
+    },
+}
+
+impl Request {
+    fn name(&self) -> &'static str {
+        match self {
+            Request::Store { .. } => "store",
+            Request::Fetch { .. } => "fetch",
+            Request::VersionAsOf { .. } => "version_as_of",
+            Request::History { .. } => "history",
+            Request::Search { .. } => "search",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "reply", rename_all = "snake_case")]
+pub(crate) enum Reply {
+    Stored {
+        document: Document,
+        replayed: bool,
+    },
+    Fetched {
+        document: Document,
+        /// The key the requested one redirected to, if it did.
+        redirected_to: Option<String>,
+    },
+    Revision {
+        document: Option<Document>,
+    },
+    History {
+        revisions: Vec<Document>,
+    },
+    Hits {
+        hits: Vec<SearchHit>,
+    },
+    Failed {
+        kind: Option<FailureKind>,
+        message: String,
+    },
+}
+
+/// The failures a command reports with an exit code of their own; the client rebuilds
+/// the matching error so a proxied failure exits like a local one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FailureKind {
+    NotFound,
+    Conflict,
+    Timeout,
+}
+
+impl Reply {
+    fn failed(err: anyhow::Error) -> Self {
+        let kind = if is_not_found(&err) {
+            Some(FailureKind::NotFound)
+        } else {
+            match err.downcast_ref::<ContextError>() {
+                Some(ContextError::Conflict(_)) => Some(FailureKind::Conflict),
+                Some(ContextError::Timeout(_)) => Some(FailureKind::Timeout),
+                _ => None,
+            }
+        };
+        Reply::Failed {
+            kind,
+            message: err.to_string(),
+        }
+    }
+}
+
+/// Carry out `request` against `storage`; errors become a `Failed` reply.
+async fn answer(storage: &TracedStorage<SqliteStorage>, request: Request) -> Reply {
+    let result: Result<Reply> = match request {
+        Request::Store {
+            draft,
+            idempotency_key,
+        } => match idempotency_key {
+            Some(key) => storage.store_once(draft, &key).await,
+            None => storage.store(draft).await.map(|document| IdempotentWrite {
+                document,
+                replayed: false,
+            }),
+        }
+        .map(|write| Reply::Stored {
+            document: write.document,
+            replayed: write.replayed,
+        })
+        .map_err(Into::into),
+        Request::Fetch { project, key, id } => resolve_document(storage, &project, &key, &id)
+            .await
+            .map(|(document, redirected_to)| Reply::Fetched {
+                document,
+                redirected_to,
+            }),
+        Request::VersionAsOf { id, at } => storage
+            .version_as_of(&DocumentId(id), at)
+            .await
+            .map(|document| Reply::Revision { document })
+            .map_err(Into::into),
+        Request::History { id } => storage
+            .history(&DocumentId(id))
+            .await
+            .map(|revisions| Reply::History { revisions })
+            .map_err(Into::into),
+        Request::Search { query, timeout } => match timeout {
+            Some(limit) => {
+                TracedStorage::new(storage.clone().into_inner().with_query_timeout(limit))
+                    .search(query)
+                    .await
+            }
+            None => storage.search(query).await,
+        }
+        .map(|hits| Reply::Hits { hits })
+        .map_err(Into::into),
+    };
+    result.unwrap_or_else(Reply::failed)
+}
+
+/// Storage for one command: a running daemon if there is one, else the database itself.
+pub(crate) enum Backend {
+    Local {
+        runtime: tokio::runtime::Runtime,
+        storage: TracedStorage<SqliteStorage>,
+    },
+    #[cfg(unix)]
+    Daemon {
+        reader: BufReader<std::os::unix::net::UnixStream>,
+        writer: std::os::unix::net::UnixStream,
+    },
+}
+
+impl Backend {
+    pub(crate) fn open() -> Result<Self> {
+        #[cfg(unix)]
+        if std::env::var_os(NO_DAEMON_ENV).is_none() {
+            let socket = default_socket()?;
+            // A socket file nobody answers on is left over from a daemon that died.
+            if let Ok(stream) = std::os::unix::net::UnixStream::connect(&socket) {
+                tracing::info!(socket = %socket.display(), "Proxying to daemon");
+                return Ok(Backend::Daemon {
+                    reader: BufReader::new(stream.try_clone()?),
+                    writer: stream,
+                });
+            }
+        }
+        let runtime = runtime()?;
+        let storage = runtime.block_on(open_storage())?;
+        Ok(Backend::Local { runtime, storage })
+    }
+
+    fn call(&mut self, request: Request) -> Result<Reply> {
+        let reply = match self {
+            Backend::Local { runtime, storage } => runtime.block_on(answer(storage, request)),
+            #[cfg(unix)]
+            Backend::Daemon { reader, writer } => {
+                let mut line = serde_json::to_string(&request)?;
+                line.push('\n');
+                writer
+                    .write_all(line.as_bytes())
+                    .context("Failed to send request to the daemon")?;
+                let mut response = String::new();
+                reader
+                    .read_line(&mut response)
+                    .context("Failed to read the daemon's reply")?;
+                if response.is_empty() {
+                    bail!("The daemon closed the connection; is it shutting down?");
+                }
+                serde_json::from_str(&response).context("Malformed reply from the daemon")?
+            }
+        };
+        match reply {
+            Reply::Failed { kind, message } => Err(match kind {
+                Some(FailureKind::NotFound) => NotFound(message).into(),
+                // A reply keeps only the message, not which conflict it was; `Key` is
+                // the variant that carries a bare message.
+                Some(FailureKind::Conflict) => {
+                    ContextError::Conflict(Conflict::Key(message)).into()
+                }
+                Some(FailureKind::Timeout) => ContextError::Timeout(message).into(),
+                None => anyhow::anyhow!(message),
+            }),
+            reply => Ok(reply),
+        }
+    }
+
+    pub(crate) fn store(
+        &mut self,
+        draft: Document,
+        idempotency_key: Option<String>,
+    ) -> Result<IdempotentWrite> {
+        match self.call(Request::Store {
+            draft,
+            idempotency_key,
+        })? {
+            Reply::Stored { document, replayed } => Ok(IdempotentWrite { document, replayed }),
+            other => unexpected(other),
+        }
+    }
+
+    /// `fetch_document`, through the backend: follows key redirects and notes them.
+    pub(crate) fn fetch(
+        &mut self,
+        project: &str,
+        key: &Option<String>,
+        id: &Option<String>,
+    ) -> Result<Document> {
+        let request = Request::Fetch {
+            project: project.to_string(),
+            key: key.clone(),
+            id: id.clone(),
+        };
+        match self.call(request)? {
+            Reply::Fetched {
+                document,
+                redirected_to,
+            } => {
+                if let (Some(key), Some(target)) = (key, redirected_to) {
+                    eprintln!("note: key {key} now redirects to {target}");
+                }
+                Ok(document)
+            }
+            other => unexpected(other),
+        }
+    }
+
+    pub(crate) fn version_as_of(
+        &mut self,
+        id: &DocumentId,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Document>> {
+        match self.call(Request::VersionAsOf {
+            id: id.0.clone(),
+            at,
+        })? {
+            Reply::Revision { document } => Ok(document),
+            other => unexpected(other),
+        }
+    }
+
+    /// Every revision of a document, oldest first.
+    pub(crate) fn history(&mut self, id: &DocumentId) -> Result<Vec<Document>> {
+        match self.call(Request::History { id: id.0.clone() })? {
+            Reply::History { revisions } => Ok(revisions),
+            other => unexpected(other),
+        }
+    }
+
+    pub(crate) fn search(
+        &mut self,
+        query: SearchQuery,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<SearchHit>> {
+        match self.call(Request::Search { query, timeout })? {
+            Reply::Hits { hits } => Ok(hits),
+            other => unexpected(other),
+        }
+    }
+}
+
+fn unexpected<T>(reply: Reply) -> Result<T> {
+    bail!("Unexpected reply from the daemon: {reply:?}")
+}
+
+/// How often `--notify` checks for reminders that have fallen due.
+const REMINDER_POLL: Duration = Duration::from_secs(60);
+
+/// Serve requests on `socket` until interrupted, then remove the socket file.
+///
+/// config.json is re-read on SIGHUP or when it changes; key rules, expiry rules, and the
+/// log filter then apply to the next request without dropping connections. With
+/// `notify`, due reminders are announced once each as they fall due.
+#[cfg(unix)]
+pub(crate) fn run(
+    socket: PathBuf,
+    mode: u32,
+    notify: bool,
+    json_output: bool,
+    telemetry: &TelemetryGuard,
+) -> Result<()> {
+    use tokio::net::UnixListener;
+
+    context_core::socket::prepare(&socket)?;
+
+    let runtime = runtime()?;
+    runtime.block_on(async {
+        let opened = open_storage().await?;
+        telemetry.set_filter(reload_project_config()?.log_filter.as_deref())?;
+        let storage = Arc::new(RwLock::new(opened));
+        let mut watcher = ConfigWatcher::new(project_config_path()?, reload::POLL_INTERVAL)?;
+        let listener = UnixListener::from_std(context_core::socket::bind(&socket, mode)?)?;
+        if json_output {
+            let payload = serde_json::json!({
+                "status": "listening",
+                "socket": socket.display().to_string(),
+                "mode": format!("{mode:o}"),
+            });
+            println!("{payload}");
+        } else {
+            println!("Daemon listening on {} (Ctrl-C to stop)", socket.display());
+        }
+        std::io::stdout().flush()?;
+
+        let mut reminders = tokio::time::interval(REMINDER_POLL);
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let stream = match accepted {
+                        Ok((stream, _)) => stream,
+                        // Running out of file descriptors and the like passes; keep serving.
+                        Err(err) => {
+                            tracing::warn!(error = %err, "Failed to accept a daemon connection");
+                            tokio::time::sleep(context_core::socket::ACCEPT_RETRY_DELAY).await;
+                            continue;
+                        }
+                    };
+                    let storage = storage.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = serve_connection(&storage, stream).await {
+                            tracing::warn!(error = %err, "Daemon connection failed");
+                        }
+                    });
+                }
+                trigger = watcher.changed() => {
+                    let trigger = trigger.as_str();
+                    match reload_config(&storage, telemetry) {
+                        Ok(()) => tracing::info!(trigger, "Config reloaded"),
+                        Err(err) => tracing::error!(
+                            trigger,
+                            error = %err,
+                            "Config reload failed; keeping the previous settings"
+                        ),
+                    }
+                }
+                _ = reminders.tick(), if notify => {
+                    let current = storage
+                        .read()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .clone();
+                    if let Err(err) = announce_due_reminders(&current).await {
+                        tracing::warn!(error = %err, "Checking due reminders failed");
+                    }
+                }
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    })?;
+    fs::remove_file(&socket).ok();
+    Ok(())
+}
+
+/// Re-read config.json into the storage handle new requests use.
+#[cfg(unix)]
+fn reload_config(
+    storage: &RwLock<TracedStorage<SqliteStorage>>,
+    telemetry: &TelemetryGuard,
+) -> Result<()> {
+    let config = reload_project_config()?;
+    let log_filter = config.log_filter.clone();
+    let current = storage
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    let configured = TracedStorage::new(configure_storage(current.into_inner(), config.store)?);
+    telemetry.set_filter(log_filter.as_deref())?;
+    *storage.write().unwrap_or_else(|err| err.into_inner()) = configured;
+    Ok(())
+}
+
+/// Announce each due reminder not announced yet: as a desktop notification and through
+/// the project's `notify` channels in config.json.
+#[cfg(unix)]
+async fn announce_due_reminders(storage: &TracedStorage<SqliteStorage>) -> Result<()> {
+    let channels = match reload_project_config() {
+        Ok(config) => config.notify,
+        Err(err) => {
+            tracing::warn!(error = %err, "Skipping notify channels; config.json is unreadable");
+            NotifyConfig::default()
+        }
+    };
+    let now = Utc::now();
+    let due = storage
+        .reminders(&ReminderQuery {
+            due_by: Some(now),
+            unnotified: true,
+            ..ReminderQuery::default()
+        })
+        .await?;
+    for reminder in due {
+        let label = reminder.key.as_deref().unwrap_or(&reminder.document_id);
+        let title = format!("context: {}/{label}", reminder.project);
+        let body = reminder.note.as_deref().unwrap_or("Reminder due");
+        tracing::info!(id = reminder.id, project = %reminder.project, document = label, "Reminder due");
+        if let Err(err) = desktop_notification(&title, body) {
+            tracing::warn!(id = reminder.id, error = %err, "Desktop notification failed");
+        }
+        notify::broadcast(
+            &channels,
+            &Notification {
+                project: reminder.project.clone(),
+                title,
+                body: body.to_string(),
+            },
+        );
+        storage.mark_reminder_notified(reminder.id, now).await?;
+    }
+    Ok(())
+}
+
+/// Hand a notification to the desktop: `osascript` on macOS, `notify-send` elsewhere.
+#[cfg(unix)]
+fn desktop_notification(title: &str, body: &str) -> Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut command = if cfg!(target_os = "macos") {
+        let quote = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification \"{}\" with title \"{}\"",
+            quote(body),
+            quote(title)
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(body);
+        command
+    };
+    let status = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run {:?}", command.get_program()))?;
+    if !status.success() {
+        bail!("{:?} exited with {status}", command.get_program());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn run(
+    _socket: PathBuf,
+    _mode: u32,
+    _notify: bool,
+    _json_output: bool,
+    _telemetry: &TelemetryGuard,
+) -> Result<()> {
+    bail!("`context daemon` needs unix domain sockets, which this platform lacks.")
+}
+
+#[cfg(unix)]
+async fn serve_connection(
+    storage: &RwLock<TracedStorage<SqliteStorage>>,
+    stream: tokio::net::UnixStream,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let reply = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let op = request.name();
+                let current = storage
+                    .read()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .clone();
+                let reply = answer(&current, request).await;
+                tracing::info!(op, "Daemon request served");
+                reply
+            }
+            Err(err) => Reply::failed(anyhow::anyhow!("Malformed request: {err}")),
+        };
+        let mut out = serde_json::to_string(&reply)?;
+        out.push('\n');
+        writer.write_all(out.as_bytes()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,355 @@
+//! `context export`: write a project's live documents out as files.
+//!
+//! The Markdown format lays documents out in the tree `context import` reads back: one
+//! `<namespace>/<key>.md` file each, with front matter carrying what the path can't.
+//! The JSONL format is one serialized `Document` per line, and CSV/TSV a table of their
+//! metadata (see `table`) for reviewing in a spreadsheet.
+//!
+//! Private documents stay behind unless `--include-private` asks for them; only their
+//! count is reported. `--exclude-tag` and `--redact-pattern` make a dataset safe to hand
+//! on. A filtered export also writes a manifest of what it left out or changed — ids,
+//! keys, and counts, never the redacted text.
+//!
+//! The documents are read from a snapshot, so a batch written during the export is in it
+//! entirely or not at all.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use futures::TryStreamExt;
+use regex::Regex;
+use serde::Serialize;
+
+use context_core::{frontmatter, Document, Storage};
+
+use crate::{
+    open_snapshot, runtime,
+    table::{Delimiter, TableWriter},
+};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Which documents an export leaves out and what it blanks in the rest.
+pub struct ExportFilter {
+    exclude_tags: Vec<String>,
+    patterns: Vec<Regex>,
+    include_private: bool,
+}
+
+impl ExportFilter {
+    pub fn new(
+        exclude_tags: Vec<String>,
+        patterns: &[String],
+        include_private: bool,
+    ) -> Result<Self> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).with_context(|| format!("Invalid --redact-pattern {pattern:?}"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            exclude_tags: exclude_tags
+                .into_iter()
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            patterns,
+            include_private,
+        })
+    }
+
+    /// Drop private documents unless they were asked for, returning how many.
+    fn drop_private(&self, documents: &mut Vec<Document>) -> usize {
+        if self.include_private {
+            return 0;
+        }
+        let before = documents.len();
+        documents.retain(Document::is_shared);
+        before - documents.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.exclude_tags.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Drop the excluded documents from `documents` and redact the rest in place.
+    fn apply(&self, documents: &mut Vec<Document>) -> ExportManifest {
+        let mut manifest = ExportManifest {
+            exclude_tags: self.exclude_tags.clone(),
+            redact_patterns: self
+                .patterns
+                .iter()
+                .map(|pattern| pattern.as_str().to_string())
+                .collect(),
+            ..Default::default()
+        };
+        documents.retain(|doc| {
+            let tags: Vec<String> = doc
+                .tags
+                .iter()
+                .filter(|tag| self.exclude_tags.contains(tag))
+                .cloned()
+                .collect();
+            if tags.is_empty() {
+                return true;
+            }
+            manifest.excluded.push(ExcludedDocument {
+                id: doc.id.0.clone(),
+                key: doc.key.clone(),
+                tags,
+            });
+            false
+        });
+        for doc in documents.iter_mut() {
+            let mut matches = 0;
+            for pattern in &self.patterns {
+                matches += redact(pattern, &mut doc.body_markdown);
+                if let Some(title) = &mut doc.title {
+                    matches += redact(pattern, title);
+                }
+            }
+            if matches > 0 {
+                manifest.redacted.push(RedactedDocument {
+                    id: doc.id.0.clone(),
+                    key: doc.key.clone(),
+                    matches,
+                });
+            }
+        }
+        manifest
+    }
+}
+
+/// Replace every match of `pattern` in `text`, returning how many there were.
+fn redact(pattern: &Regex, text: &mut String) -> usize {
+    let matches = pattern.find_iter(text).count();
+    if matches > 0 {
+        *text = pattern.replace_all(text, REDACTED).into_owned();
+    }
+    matches
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ExportManifest {
+    exclude_tags: Vec<String>,
+    redact_patterns: Vec<String>,
+    excluded: Vec<ExcludedDocument>,
+    redacted: Vec<RedactedDocument>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExcludedDocument {
+    id: String,
+    key: Option<String>,
+    /// The excluded tags it carries.
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RedactedDocument {
+    id: String,
+    key: Option<String>,
+    matches: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportSummary {
+    project: String,
+    format: &'static str,
+    out: Option<PathBuf>,
+    documents: usize,
+    /// Private documents left behind; not in the manifest, which would name them.
+    private: usize,
+    excluded: usize,
+    redacted: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest: Option<PathBuf>,
+}
+
+pub fn handle_export(
+    project: Option<String>,
+    json_output: bool,
+    out: Option<PathBuf>,
+    format: &str,
+    filter: ExportFilter,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let format = match format {
+        "markdown" | "md" => "markdown",
+        "jsonl" | "ndjson" => "jsonl",
+        "csv" => "csv",
+        "tsv" => "tsv",
+        other => bail!("Unsupported format: {other}. Use --format markdown, jsonl, csv, or tsv"),
+    };
+    if format == "markdown" && out.is_none() {
+        bail!("Provide --out <dir> to export Markdown files, or use --format jsonl.");
+    }
+
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_snapshot())?;
+    let mut documents: Vec<Document> =
+        runtime.block_on(storage.stream_project(&project).try_collect())?;
+    documents.sort_by(|a, b| (&a.namespace, &a.key, &a.id.0).cmp(&(&b.namespace, &b.key, &b.id.0)));
+    let private = filter.drop_private(&mut documents);
+    let manifest = (!filter.is_empty()).then(|| filter.apply(&mut documents));
+
+    let manifest_path = match (format, &out) {
+        ("markdown", Some(dir)) => {
+            write_markdown(dir, &documents)?;
+            dir.join("manifest.json")
+        }
+        (_, Some(path)) => {
+            let file = fs::File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            write_documents(format, io::BufWriter::new(file), &documents)?;
+            let mut manifest_path = path.clone().into_os_string();
+            manifest_path.push(".manifest.json");
+            PathBuf::from(manifest_path)
+        }
+        (_, None) => {
+            // The documents are the output; a summary would corrupt the stream.
+            write_documents(format, io::stdout().lock(), &documents)?;
+            if private > 0 {
+                eprintln!("note: left out {private} private document(s); pass --include-private to export them");
+            }
+            if let Some(manifest) = &manifest {
+                eprintln!(
+                    "note: excluded {} and redacted {} document(s); pass --out to also write a manifest",
+                    manifest.excluded.len(),
+                    manifest.redacted.len()
+                );
+            }
+            return Ok(());
+        }
+    };
+    if let Some(manifest) = &manifest {
+        fs::write(
+            &manifest_path,
+            serde_json::to_string_pretty(manifest)? + "\n",
+        )
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+    }
+
+    let summary = ExportSummary {
+        project,
+        format,
+        out,
+        documents: documents.len(),
+        private,
+        excluded: manifest
+            .as_ref()
+            .map_or(0, |manifest| manifest.excluded.len()),
+        redacted: manifest
+            .as_ref()
+            .map_or(0, |manifest| manifest.redacted.len()),
+        manifest: manifest.is_some().then_some(manifest_path),
+    };
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else if let Some(out) = &summary.out {
+        println!(
+            "Exported {} document(s) from project {} to {}",
+            summary.documents,
+            summary.project,
+            out.display()
+        );
+        if summary.private > 0 {
+            println!(
+                "Left out {} private document(s); pass --include-private to export them",
+                summary.private
+            );
+        }
+        if let Some(manifest) = &summary.manifest {
+            println!(
+                "Excluded {} and redacted {} document(s); see {}",
+                summary.excluded,
+                summary.redacted,
+                manifest.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn write_documents(format: &str, writer: impl Write, documents: &[Document]) -> Result<()> {
+    let delimiter = match format {
+        "csv" => Delimiter::Comma,
+        "tsv" => Delimiter::Tab,
+        _ => return write_jsonl(writer, documents),
+    };
+    let mut table = TableWriter::new(writer, delimiter, None)?;
+    for doc in documents {
+        table.write(doc)?;
+    }
+    table.finish()
+}
+
+fn write_jsonl(mut writer: impl Write, documents: &[Document]) -> Result<()> {
+    for doc in documents {
+        serde_json::to_writer(&mut writer, doc)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_markdown(dir: &Path, documents: &[Document]) -> Result<()> {
+    if dir.exists() {
+        let mut entries =
+            fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+        if entries.next().is_some() {
+            bail!(
+                "{} is not empty; export into a new directory so files from an earlier export don't get mixed in.",
+                dir.display()
+            );
+        }
+    }
+    let mut used = HashSet::new();
+    for doc in documents {
+        let relative = file_path(doc, &used);
+        let path = dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, frontmatter::render(doc))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        used.insert(relative);
+    }
+    Ok(())
+}
+
+/// `<namespace>/<key>.md`, or `<namespace>/<id>.md` for a document whose key is missing,
+/// can't be a relative path, or lands on a file already written. Front matter keeps the
+/// real key either way.
+fn file_path(doc: &Document, used: &HashSet<PathBuf>) -> PathBuf {
+    let mut dir = PathBuf::new();
+    if let Some(namespace) = doc.namespace.as_deref().filter(|ns| is_safe_path(ns)) {
+        dir.push(namespace);
+    }
+    let by_key = doc
+        .key
+        .as_deref()
+        .filter(|key| is_safe_path(key))
+        .map(|key| dir.join(format!("{key}.md")))
+        .filter(|path| !used.contains(path));
+    by_key.unwrap_or_else(|| dir.join(format!("{}.md", doc.id.0)))
+}
+
+/// Whether `raw` is a relative path of ordinary `/`-separated names that stays inside
+/// the export directory, none of them hidden (which `import` would skip).
+fn is_safe_path(raw: &str) -> bool {
+    raw.split('/').all(|segment| {
+        !segment.is_empty()
+            && segment != "."
+            && segment != ".."
+            && !segment.starts_with('.')
+            && !segment.contains(['\\', '\0', ':'])
+    })
+}
@@ -0,0 +1,373 @@
+//! `context import <dir>`: load a directory of Markdown files as documents.
+//!
+//! Each file's path relative to `<dir>`, minus its `.md`/`.markdown` extension, is its
+//! key unless its front matter names one; the front matter also supplies the title,
+//! tags, namespace, TTL, and visibility. Everything is written in one transaction, so an import that
+//! fails part way leaves the project as it was.
+//!
+//! `context import <file>` reads JSON Lines from `context export --format jsonl` instead
+//! and merges them by id (see `context_core::merge`): documents keep their ids and
+//! versions, the higher version wins, and conflicts are reported rather than written.
+//!
+//! `--fast-import` (either form) has the store index the written documents for search in
+//! one pass at the end instead of one by one (see `SqliteStorage::with_deferred_indexing`).
+
+use std::{
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use context_core::{
+    frontmatter,
+    merge::{MergeOutcome, MergedDocument},
+    policy::parse_duration,
+    Document, DocumentId, SourceType, Storage,
+};
+
+use crate::{open_import_storage, runtime};
+
+const MARKDOWN_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ImportStatus {
+    Created,
+    Updated,
+    /// The live document at the key already has this content.
+    Skipped,
+    /// JSON Lines only: the local document is at a higher version.
+    Kept,
+    /// JSON Lines only: left alone; `conflict` says why.
+    Conflict,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportedFile {
+    /// The file's relative path, or `<file>:<line>` for JSON Lines.
+    path: String,
+    key: String,
+    status: ImportStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conflict: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportSummary {
+    project: String,
+    dry_run: bool,
+    created: usize,
+    updated: usize,
+    skipped: usize,
+    kept: usize,
+    conflicts: usize,
+    files: Vec<ImportedFile>,
+}
+
+pub fn handle_import(
+    project: Option<String>,
+    json_output: bool,
+    path: PathBuf,
+    glob: Option<String>,
+    dry_run: bool,
+    fast_import: bool,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    if path.is_file() {
+        if glob.is_some() {
+            bail!(
+                "--glob picks files from a directory; {} is a JSON Lines file.",
+                path.display()
+            );
+        }
+        let files = merge_jsonl(&project, &path, dry_run, fast_import)?;
+        return report(project, json_output, dry_run, files);
+    }
+    let dir = path;
+    if !dir.is_dir() {
+        bail!("{} is not a directory or a JSON Lines file.", dir.display());
+    }
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_import_storage(fast_import))?;
+
+    let mut files: Vec<ImportedFile> = Vec::new();
+    let mut drafts = Vec::new();
+    for (path, relative) in markdown_files(&dir, glob.as_deref())? {
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let draft = document_from_file(&project, &relative, &text)
+            .with_context(|| format!("Failed to import {}", path.display()))?;
+        let key = draft.key.clone().unwrap_or_default();
+        if let Some(earlier) = files.iter().find(|file| file.key == key) {
+            bail!(
+                "{} and {relative} both map to key {key}; give one a `key:` in its front matter.",
+                earlier.path
+            );
+        }
+        let status = match runtime.block_on(storage.get_by_key(&project, &key))? {
+            Some(existing) if same_content(&existing, &draft) => ImportStatus::Skipped,
+            Some(_) => ImportStatus::Updated,
+            None => ImportStatus::Created,
+        };
+        if status != ImportStatus::Skipped {
+            drafts.push(draft);
+        }
+        files.push(ImportedFile {
+            path: relative,
+            key,
+            status,
+            id: None,
+            conflict: None,
+        });
+    }
+    if !dry_run && !drafts.is_empty() {
+        runtime.block_on(storage.store_all(drafts))?;
+    }
+    report(project, json_output, dry_run, files)
+}
+
+/// Merge the documents in JSON Lines file `path` into `project`, one result per line.
+fn merge_jsonl(
+    project: &str,
+    path: &Path,
+    dry_run: bool,
+    fast_import: bool,
+) -> Result<Vec<ImportedFile>> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut documents: Vec<Document> = Vec::new();
+    let mut lines = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let doc = serde_json::from_str(&line).with_context(|| {
+            format!(
+                "{}:{} is not a document from `context export --format jsonl`",
+                path.display(),
+                index + 1
+            )
+        })?;
+        documents.push(doc);
+        lines.push(index + 1);
+    }
+
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_import_storage(fast_import))?;
+    let merged =
+        runtime.block_on(storage.merge_documents(&project.to_string(), documents, dry_run))?;
+    let name = path.display().to_string();
+    Ok(merged
+        .into_iter()
+        .zip(lines)
+        .map(|(merged, line)| imported_line(&name, line, merged))
+        .collect())
+}
+
+fn imported_line(path: &str, line: usize, merged: MergedDocument) -> ImportedFile {
+    let status = match merged.outcome {
+        MergeOutcome::Created => ImportStatus::Created,
+        MergeOutcome::Updated => ImportStatus::Updated,
+        MergeOutcome::Unchanged => ImportStatus::Skipped,
+        MergeOutcome::Older => ImportStatus::Kept,
+        MergeOutcome::Conflict => ImportStatus::Conflict,
+    };
+    ImportedFile {
+        path: format!("{path}:{line}"),
+        key: merged.key.unwrap_or_else(|| merged.id.clone()),
+        status,
+        id: Some(merged.id),
+        conflict: merged.conflict,
+    }
+}
+
+fn report(
+    project: String,
+    json_output: bool,
+    dry_run: bool,
+    files: Vec<ImportedFile>,
+) -> Result<()> {
+    let count = |status| files.iter().filter(|file| file.status == status).count();
+    let summary = ImportSummary {
+        project,
+        dry_run,
+        created: count(ImportStatus::Created),
+        updated: count(ImportStatus::Updated),
+        skipped: count(ImportStatus::Skipped),
+        kept: count(ImportStatus::Kept),
+        conflicts: count(ImportStatus::Conflict),
+        files,
+    };
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+    for file in &summary.files {
+        let status = match file.status {
+            ImportStatus::Created => "created",
+            ImportStatus::Updated => "updated",
+            ImportStatus::Skipped => "skipped",
+            ImportStatus::Kept => "kept",
+            ImportStatus::Conflict => "conflict",
+        };
+        let conflict = file
+            .conflict
+            .as_ref()
+            .map(|conflict| format!(" ({conflict})"))
+            .unwrap_or_default();
+        println!("{status:<8} {} -> {}{conflict}", file.path, file.key);
+    }
+    let merged = if summary.kept + summary.conflicts > 0 {
+        format!(
+            ", {} kept (newer here), {} conflict(s)",
+            summary.kept, summary.conflicts
+        )
+    } else {
+        String::new()
+    };
+    println!(
+        "{} {} file(s) into project {}: {} created, {} updated, {} skipped (unchanged){merged}",
+        if dry_run { "Would import" } else { "Imported" },
+        summary.files.len(),
+        summary.project,
+        summary.created,
+        summary.updated,
+        summary.skipped
+    );
+    Ok(())
+}
+
+/// The files under `dir` to import, sorted, with their paths relative to `dir` using `/`.
+/// Hidden files and directories are left out.
+fn markdown_files(dir: &Path, glob: Option<&str>) -> Result<Vec<(PathBuf, String)>> {
+    let mut files = Vec::new();
+    let walk = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        });
+    for entry in walk {
+        let entry = entry.with_context(|| format!("Failed to read {}", dir.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap_or(entry.path())
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let wanted = match glob {
+            Some(pattern) => glob_matches(pattern, &relative),
+            None => markdown_stem(&relative).is_some(),
+        };
+        if wanted {
+            files.push((entry.into_path(), relative));
+        }
+    }
+    Ok(files)
+}
+
+fn document_from_file(project: &str, relative: &str, text: &str) -> Result<Document> {
+    let (front_matter, body) = frontmatter::split(text)?;
+    let ttl_seconds = front_matter
+        .ttl
+        .as_deref()
+        .map(|raw| parse_duration(raw).context("Front matter `ttl`"))
+        .transpose()?
+        .map(|ttl| ttl.num_seconds());
+    let visibility = front_matter
+        .visibility
+        .as_deref()
+        .map(|raw| raw.parse().context("Front matter `visibility`"))
+        .transpose()?;
+    let now = Utc::now();
+    Ok(Document {
+        id: DocumentId(Uuid::new_v4().to_string()),
+        project: project.to_string(),
+        key: Some(
+            front_matter
+                .key
+                .unwrap_or_else(|| markdown_stem(relative).unwrap_or(relative).to_string()),
+        ),
+        namespace: front_matter.namespace,
+        title: front_matter
+            .title
+            .or_else(|| frontmatter::first_heading(body)),
+        tags: front_matter.tags,
+        body_markdown: body.to_string(),
+        created_at: now,
+        updated_at: now,
+        source: SourceType::Import,
+        source_meta: None,
+        version: 1,
+        ttl_seconds,
+        deleted_at: None,
+        visibility,
+        metadata: Default::default(),
+    })
+}
+
+/// `relative` without its Markdown extension, or `None` when it has another one.
+fn markdown_stem(relative: &str) -> Option<&str> {
+    let (stem, extension) = relative.rsplit_once('.')?;
+    let is_markdown = MARKDOWN_EXTENSIONS
+        .iter()
+        .any(|markdown| extension.eq_ignore_ascii_case(markdown));
+    (is_markdown && !stem.is_empty() && !stem.ends_with('/')).then_some(stem)
+}
+
+/// Whether re-importing `draft` over `existing` would change nothing a reader sees.
+fn same_content(existing: &Document, draft: &Document) -> bool {
+    existing.body_markdown == draft.body_markdown
+        && existing.title == draft.title
+        && existing.tags == draft.tags
+        && existing.namespace == draft.namespace
+        && draft
+            .visibility
+            .is_none_or(|visibility| visibility == existing.visibility())
+}
+
+/// Match `path` against a shell-style `pattern`: `*` and `?` stay within one path
+/// segment and `**` spans any number of them. A pattern without `/` matches file names
+/// anywhere in the tree, so `*.txt` works like `**/*.txt`.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let path: Vec<&str> = path.split('/').collect();
+    if !pattern.contains('/') {
+        return path
+            .last()
+            .is_some_and(|name| segment_matches(pattern.as_bytes(), name.as_bytes()));
+    }
+    let pattern: Vec<&str> = pattern.trim_start_matches("./").split('/').collect();
+    segments_match(&pattern, &path)
+}
+
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => path.split_first().is_some_and(|(name, path)| {
+            segment_matches(first.as_bytes(), name.as_bytes()) && segments_match(rest, path)
+        }),
+    }
+}
+
+fn segment_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| segment_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && segment_matches(rest, &name[1..]),
+        Some((byte, rest)) => name.first() == Some(byte) && segment_matches(rest, &name[1..]),
+    }
+}
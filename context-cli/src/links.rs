@@ -0,0 +1,66 @@
+//! `context links` lists the documents one document's body links to (`[[key]]`,
+//! `context://<project>/<key>`); `context backlinks` lists the documents linking to a key.
+
+use anyhow::Result;
+
+use context_core::links::LinkedDocument;
+
+use crate::{fetch_document_exact, open_storage, runtime};
+
+pub fn handle_links(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let doc = runtime.block_on(fetch_document_exact(&storage, &project, &key, &id))?;
+    let links = runtime.block_on(storage.links(&doc.id))?;
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&links)?);
+        return Ok(());
+    }
+    println!("Links from {}", doc.key.as_deref().unwrap_or(&doc.id.0));
+    for link in &links {
+        println!("{}", summary(link, &project));
+    }
+    Ok(())
+}
+
+pub fn handle_backlinks(project: Option<String>, json_output: bool, key: String) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let backlinks = runtime.block_on(storage.backlinks(&project, &key))?;
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&backlinks)?);
+        return Ok(());
+    }
+    println!("Links to {key}");
+    for link in &backlinks {
+        println!("{}", summary(link, &project));
+    }
+    Ok(())
+}
+
+/// `- key: title`, with the project in front when it isn't `project`, and `(missing)`
+/// for a link nothing lives at.
+fn summary(link: &LinkedDocument, project: &str) -> String {
+    let name = link
+        .key
+        .clone()
+        .or_else(|| link.document_id.clone())
+        .unwrap_or_default();
+    let name = if link.project == project {
+        name
+    } else {
+        format!("{}/{name}", link.project)
+    };
+    match (&link.document_id, &link.title) {
+        (None, _) => format!("- {name} (missing)"),
+        (Some(_), Some(title)) => format!("- {name}: {title}"),
+        (Some(_), None) => format!("- {name}"),
+    }
+}
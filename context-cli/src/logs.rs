@@ -0,0 +1,176 @@
+use std::{collections::VecDeque, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+
+/// Which entries of a JSONL log `context logs` shows.
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub scenario: Option<String>,
+    /// Minimum level, e.g. `warn` keeps WARN and ERROR.
+    pub level: Option<String>,
+    /// Keep only the last N matching entries.
+    pub tail: Option<usize>,
+}
+
+/// Log file name for `app`: `cli` and `context-cli` both mean `context-cli.jsonl`.
+pub fn log_file_name(app: &str) -> String {
+    let app = app.trim_end_matches(".jsonl");
+    if app.starts_with("context-") {
+        format!("{app}.jsonl")
+    } else {
+        format!("context-{app}.jsonl")
+    }
+}
+
+/// The raw lines of `path` that pass `filter`, oldest first. Lines written by `context
+/// logs` itself are skipped so reading the CLI log doesn't show the read.
+pub fn read_entries(path: &Path, filter: &LogFilter) -> Result<Vec<String>> {
+    if !path.exists() {
+        bail!("No log file at {} yet.", path.display());
+    }
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let min_rank = match filter.level.as_deref() {
+        Some(level) => Some(level_rank(level).with_context(|| {
+            format!("Unknown log level {level:?}; use trace, debug, info, warn, or error.")
+        })?),
+        None => None,
+    };
+
+    let mut kept = VecDeque::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let entry: Option<Value> = serde_json::from_str(line).ok();
+        if let Some(entry) = &entry {
+            if span_field(entry, "command").as_deref() == Some("logs") {
+                continue;
+            }
+            if let Some(min_rank) = min_rank {
+                let rank = entry["level"].as_str().and_then(level_rank);
+                if rank.is_some_and(|rank| rank < min_rank) {
+                    continue;
+                }
+            }
+            if let Some(scenario) = &filter.scenario {
+                if span_field(entry, "scenario_id").as_deref() != Some(scenario.as_str()) {
+                    continue;
+                }
+            }
+        } else if filter.scenario.is_some() {
+            continue;
+        }
+        kept.push_back(line.to_string());
+        if filter.tail.is_some_and(|tail| kept.len() > tail) {
+            kept.pop_front();
+        }
+    }
+    Ok(kept.into())
+}
+
+/// One JSONL entry as a line for people to read:
+/// `2024-06-01 09:30:00.123 INFO  cli.put{project=demo}: Document stored key=notes/a`.
+/// Lines that aren't JSON are returned unchanged.
+pub fn render_pretty(line: &str) -> String {
+    let Ok(Value::Object(entry)) = serde_json::from_str::<Value>(line) else {
+        return line.to_string();
+    };
+    let mut out = String::new();
+    if let Some(timestamp) = entry.get("timestamp").and_then(Value::as_str) {
+        match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(at) => out.push_str(
+                &at.with_timezone(&Utc)
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+                    .to_string(),
+            ),
+            Err(_) => out.push_str(timestamp),
+        }
+        out.push(' ');
+    }
+    let level = entry.get("level").and_then(Value::as_str).unwrap_or("-");
+    out.push_str(&format!("{level:<5} "));
+
+    let span = entry.get("span").and_then(Value::as_object);
+    if let Some(span) = span {
+        let name = span.get("name").and_then(Value::as_str).unwrap_or("span");
+        out.push_str(name);
+        let span_fields = render_fields(span, &["name"]);
+        if !span_fields.is_empty() {
+            out.push_str(&format!("{{{span_fields}}}"));
+        }
+        out.push_str(": ");
+    } else if let Some(target) = entry.get("target").and_then(Value::as_str) {
+        out.push_str(&format!("{target}: "));
+    }
+
+    let empty = Map::new();
+    let fields = entry
+        .get("fields")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+    if let Some(message) = fields.get("message") {
+        out.push_str(&plain(message));
+    }
+    // Fields already shown on the span only repeat it.
+    let mut skip = vec!["message"];
+    if let Some(span) = span {
+        skip.extend(
+            fields
+                .iter()
+                .filter(|(name, value)| span.get(name.as_str()) == Some(*value))
+                .map(|(name, _)| name.as_str()),
+        );
+    }
+    let rest = render_fields(fields, &skip);
+    if !rest.is_empty() {
+        out.push(' ');
+        out.push_str(&rest);
+    }
+    out
+}
+
+fn render_fields(fields: &Map<String, Value>, skip: &[&str]) -> String {
+    fields
+        .iter()
+        .filter(|(name, _)| !skip.contains(&name.as_str()))
+        .map(|(name, value)| {
+            let value = plain(value);
+            if value.is_empty() || value.chars().any(char::is_whitespace) {
+                format!("{name}={value:?}")
+            } else {
+                format!("{name}={value}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A field of the innermost span that has it, so the root `context` span's
+/// `scenario_id` counts for every entry under it.
+fn span_field(entry: &Value, name: &str) -> Option<String> {
+    let spans = entry.get("spans").and_then(Value::as_array)?;
+    spans
+        .iter()
+        .rev()
+        .chain(entry.get("span"))
+        .find_map(|span| span.get(name))
+        .map(plain)
+}
+
+fn level_rank(level: &str) -> Option<u8> {
+    match level.to_ascii_lowercase().as_str() {
+        "trace" => Some(0),
+        "debug" => Some(1),
+        "info" => Some(2),
+        "warn" | "warning" => Some(3),
+        "error" => Some(4),
+        _ => None,
+    }
+}
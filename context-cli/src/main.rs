@@ -1,20 +1,74 @@
 use std::{
+    collections::BTreeMap,
     env, fs,
-    io::{self, Read, Write},
+    io::{self, IsTerminal, Read, Write},
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 use anyhow::{bail, Context, Result};
-use chrono::Utc;
-use clap::{Parser, Subcommand};
-use context_core::{Document, DocumentId, SourceType};
-use context_telemetry::{context_span, init_tracing, LogContext};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::{CommandFactory, Parser, Subcommand};
+use context_core::{
+    blame::blame,
+    calendar::{to_ics, upcoming_events},
+    config::{configure_storage, StoreConfig},
+    dedup::find_duplicates,
+    diff::diff_revisions,
+    digest::{write_digest, Digest},
+    fixtures::{self, fixture, DEFAULT_SEED, FIXTURES},
+    frontmatter::{self, FrontMatter},
+    gc::{self, GcOptions},
+    idempotency::IdempotentWrite,
+    items::{ItemKind, ItemQuery},
+    jobs::JobSpec,
+    keys::KeyRedirect,
+    pack::pack,
+    parse_metadata_pair,
+    policy::{parse_duration, ExpiryPolicy},
+    related::rank_related,
+    reminders::ReminderQuery,
+    sqlite::SqliteStorage,
+    tokens::count_tokens,
+    traced::TracedStorage,
+    ContextError, DeleteMode, Document, DocumentId, ListQuery, ProjectInfo, SearchHit, SearchQuery,
+    SourceMeta, SourceType, Storage, Visibility,
+};
+use context_telemetry::{
+    context_span, init_tracing_in_home, resolve_log_dir, LogContext, TelemetryGuard,
+    WorkspaceState, CRASH_DIR,
+};
+use futures::{StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use tracing::Span;
 use uuid::Uuid;
 use walkdir::WalkDir;
 use zip::{write::FileOptions, CompressionMethod, ZipWriter};
 
+mod attachments;
+mod completion;
+mod daemon;
+mod export;
+mod import;
+mod links;
+mod logs;
+mod notify;
+mod remote;
+mod scenario;
+#[cfg(feature = "serve")]
+mod snapshot;
+mod summarize;
+mod table;
+mod tags;
+mod template;
+mod warnings;
+
+use daemon::Backend;
+use notify::{Notification, NotifyConfig};
+use remote::RemoteConfig;
+use summarize::SummarizeConfig;
+use warnings::WarningConfig;
+
 /// context – CLI entrypoint (skeleton)
 #[derive(Parser)]
 #[command(name = "context", version, about = "Context CLI (skeleton)", long_about = None)]
@@ -44,8 +98,18 @@ enum Commands {
         format: String,
     },
 
-    /// Initialize context configuration (stub)
-    Init,
+    /// Set up $CONTEXT_HOME: database, starter config.json, and the current project
+    Init {
+        /// Overwrite an existing config.json (kept as config.json.bak) and agent files
+        #[arg(long)]
+        force: bool,
+        /// Also write agent instructions for all, codex, claude, or copilot
+        #[arg(long, num_args = 0..=1, default_missing_value = "all")]
+        agent_config: Option<String>,
+    },
+
+    /// Show which project, store, config, and logs this invocation uses
+    Whoami,
 
     /// Store or update a document
     Put {
@@ -53,16 +117,81 @@ enum Commands {
         #[arg(long)]
         key: Option<String>,
 
-        /// Read body from file instead of stdin
+        /// Read body from file instead of stdin; a front matter block there (title, key,
+        /// namespace, tags, ttl, visibility) fills in what the flags leave out
         #[arg(long)]
         file: Option<PathBuf>,
 
+        /// Title to store (default: front matter `title`, then the file's first `# heading`)
+        #[arg(long)]
+        title: Option<String>,
+
         /// Optional tags for the document (repeatable or comma-separated)
         #[arg(long = "tag", short = 't', value_delimiter = ',')]
         tags: Vec<String>,
+
+        /// Namespace to file the document under (see `context ns`)
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Replay the first result instead of writing again when retried with this key (24h)
+        #[arg(long)]
+        idempotency_key: Option<String>,
+
+        /// Expire the document this long after the write (e.g. 90m, 24h, 7d, 2w)
+        #[arg(long)]
+        ttl: Option<String>,
+
+        /// Who the document is for: private (never leaves this machine: no exports,
+        /// `context-web`, or `serve-readonly`), project (the default), or public. A
+        /// revision without it keeps the previous one
+        #[arg(long)]
+        visibility: Option<String>,
+
+        /// Fill in this template (see `context template`) instead of reading a body;
+        /// front matter in it works as with --file
+        #[arg(long, conflicts_with_all = ["file", "batch"])]
+        from_template: Option<String>,
+
+        /// A value for one of the template's `{{name}}` blanks (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", requires = "from_template")]
+        vars: Vec<String>,
+
+        /// A metadata entry such as `ticket=ABC-123` (repeatable); later revisions keep
+        /// the entries they don't set, and `name=` removes one
+        #[arg(long = "meta", value_name = "NAME=VALUE", conflicts_with = "batch")]
+        meta: Vec<String>,
+
+        /// Read JSON Lines (`{"key", "body", "tags", "namespace", "title", "ttl",
+        /// "visibility", "meta"}` each) and store them in one transaction, printing one JSON result
+        /// per line; `--tag`, `--namespace`, and `--visibility` apply to every line
+        #[arg(long, conflicts_with_all = ["key", "title", "idempotency_key", "ttl"])]
+        batch: bool,
+
+        /// With --batch: fill in the search index once after writing instead of per
+        /// document, for batches of thousands
+        #[arg(long, requires = "batch")]
+        fast_import: bool,
+    },
+
+    /// Change when a document expires, counting from now
+    Touch {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Expire this long from now (e.g. 30d)
+        #[arg(long, required_unless_present = "no_ttl")]
+        ttl: Option<String>,
+
+        /// Keep the document until it is deleted
+        #[arg(long, conflicts_with = "ttl")]
+        no_ttl: bool,
     },
 
-    /// Retrieve a document (stub)
+    /// Retrieve a document
     Get {
         #[arg(long)]
         key: Option<String>,
@@ -72,20 +201,93 @@ enum Commands {
 
         #[arg(long, default_value = "markdown")]
         format: String,
+
+        /// Return the revision current at this time (RFC3339 or YYYY-MM-DD)
+        #[arg(long)]
+        as_of: Option<String>,
+
+        /// Return this version number (see `context history`)
+        #[arg(long, conflicts_with = "as_of")]
+        version: Option<u64>,
+
+        /// Fail instead of printing a body larger than this many tokens
+        #[arg(long)]
+        max_tokens: Option<usize>,
     },
 
-    /// Dump document content for agents (stub)
-    Cat {
+    /// List every revision of a document, newest first
+    History {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+    },
+
+    /// Show how a document's body changed between two versions, as a unified diff
+    Diff {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Older version (default: the one before --to)
+        #[arg(long)]
+        from: Option<u64>,
+
+        /// Newer version (default: the latest)
+        #[arg(long)]
+        to: Option<u64>,
+
+        /// Unchanged lines shown around each change
+        #[arg(long, default_value_t = 3)]
+        context: usize,
+    },
+
+    /// Show which revision last changed each line of a document
+    Blame {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+    },
+
+    /// Suggest documents related to one by tags, links, and text
+    Related {
         #[arg(long)]
         key: Option<String>,
 
         #[arg(long)]
         id: Option<String>,
+
+        /// Maximum number of suggestions
+        #[arg(long, default_value_t = 5)]
+        limit: usize,
+    },
+
+    /// Dump document content for agents
+    Cat {
+        /// Key to print; repeat it to print several documents in the order given
+        #[arg(long)]
+        key: Vec<String>,
+
+        /// Keys to print, comma-separated (after any `--key`)
+        #[arg(long, value_delimiter = ',', conflicts_with = "id")]
+        keys: Vec<String>,
+
+        #[arg(long, conflicts_with = "key")]
+        id: Option<String>,
+
+        /// Text printed between bodies when there are several (`\n` and `\t` are escapes)
+        #[arg(long, default_value = "\\n\\n---\\n\\n")]
+        delimiter: String,
     },
 
-    /// Search documents (stub)
+    /// Search documents
     Find {
-        /// Search query text
+        /// Search query text; prefix a word or "quoted phrase" with `-` to exclude it
         query: String,
 
         /// Optionally limit results
@@ -95,12 +297,138 @@ enum Commands {
         /// Search across all projects
         #[arg(long)]
         all_projects: bool,
+
+        /// Output format: text, json, or ndjson (one document per line)
+        #[arg(long, default_value = "text")]
+        format: String,
+
+        /// Stop adding results once their combined bodies would exceed this many tokens
+        #[arg(long)]
+        max_tokens: Option<usize>,
+
+        /// Only include these fields in JSON output (comma-separated, e.g. key,title,updated_at)
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+
+        /// Shorthand for `--fields key`
+        #[arg(long, conflicts_with = "fields")]
+        key_only: bool,
+
+        /// Skip documents carrying this tag (repeatable or comma-separated)
+        #[arg(long = "not-tag", value_delimiter = ',')]
+        not_tags: Vec<String>,
+
+        /// Skip documents in this namespace (repeatable or comma-separated)
+        #[arg(long = "exclude-namespace", value_delimiter = ',')]
+        exclude_namespaces: Vec<String>,
+
+        /// Only search documents whose key starts with this prefix (e.g. runbooks/)
+        #[arg(long, value_name = "KEY_PREFIX")]
+        within: Option<String>,
+
+        /// Only search these keys (repeatable or comma-separated)
+        #[arg(long = "keys", value_delimiter = ',')]
+        keys: Vec<String>,
+
+        /// Show how each hit's score was computed (bm25, recency, tag bonus)
+        #[arg(long)]
+        explain: bool,
+
+        /// Match identifiers in fenced code blocks only; `parseHttpRequest`, `HttpRequest`,
+        /// and `http_request` all find a note that pasted `fn parseHttpRequest`
+        #[arg(long)]
+        code: bool,
+
+        /// Give up once the search has run this many milliseconds, exiting with code 5;
+        /// overrides `query_timeout_secs` from config.json
+        #[arg(long, value_name = "MS")]
+        timeout: Option<u64>,
     },
 
-    /// List documents (stub)
-    Ls {},
+    /// List documents
+    Ls {
+        /// Output format: text, json, ndjson (one document per line), or csv/tsv (a
+        /// metadata table: key, title, tags, timestamps, size, version)
+        #[arg(long, default_value = "text")]
+        format: String,
+
+        /// Only include these fields in JSON output, or these columns in a csv/tsv table
+        /// (comma-separated, e.g. key,title,updated_at)
+        #[arg(long, value_delimiter = ',')]
+        fields: Vec<String>,
+
+        /// Shorthand for `--fields key`
+        #[arg(long, conflicts_with = "fields")]
+        key_only: bool,
+
+        /// Skip documents carrying this tag (repeatable or comma-separated)
+        #[arg(long = "not-tag", value_delimiter = ',')]
+        not_tags: Vec<String>,
+
+        /// Skip documents in this namespace (repeatable or comma-separated)
+        #[arg(long = "exclude-namespace", value_delimiter = ',')]
+        exclude_namespaces: Vec<String>,
+
+        /// Only documents carrying this tag (repeatable or comma-separated; all must match)
+        #[arg(long = "tag", short = 't', value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Only documents in this namespace
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Only keys starting with this prefix
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Only documents updated at or after this time (RFC3339 or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only documents updated before this time (RFC3339 or YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Also list deleted and expired documents
+        #[arg(long)]
+        include_deleted: bool,
+
+        /// List only deleted documents, the ones `restore` can bring back
+        #[arg(long, conflicts_with = "include_deleted")]
+        deleted: bool,
+
+        /// Only documents whose TTL runs out within this span (e.g. 24h, 7d)
+        #[arg(long)]
+        expiring_within: Option<String>,
+
+        /// Only documents with one of these visibilities (private, project, public;
+        /// repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        visibility: Vec<String>,
+
+        /// Only documents with this metadata entry, e.g. `ticket=ABC-123` (repeatable;
+        /// all must match)
+        #[arg(long = "meta", value_name = "NAME=VALUE")]
+        meta: Vec<String>,
+
+        /// Order: updated (newest first), created (newest first), or key (A to Z)
+        #[arg(long, default_value = "updated")]
+        sort: String,
 
-    /// Soft-delete a document (stub)
+        /// Page size; the next page's cursor is printed after the listing
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Resume after a previous page (listed with the same --sort)
+        #[arg(long)]
+        cursor: Option<String>,
+
+        /// Print how many documents match instead of listing them
+        #[arg(long, conflicts_with_all = ["fields", "key_only", "limit", "cursor"])]
+        count: bool,
+    },
+
+    /// Soft-delete a document; `restore` brings it back until `gc` purges it
     Rm {
         #[arg(long)]
         key: Option<String>,
@@ -108,14 +436,63 @@ enum Commands {
         #[arg(long)]
         id: Option<String>,
 
-        #[arg(long)]
+        /// Delete the document and its history for good, tombstone included, and succeed
+        /// even when it does not exist
+        #[arg(long, conflicts_with = "purge")]
         force: bool,
+
+        /// Also erase the document's earlier versions; it can still be restored as it was
+        #[arg(long)]
+        purge: bool,
+    },
+
+    /// Give a document a new key; the old key keeps resolving to it unless `--no-alias`
+    Mv {
+        #[arg(long)]
+        key: String,
+
+        #[arg(long)]
+        to: String,
+
+        /// Free the old key instead of leaving a redirect to the new one
+        #[arg(long)]
+        no_alias: bool,
+    },
+
+    /// Bring back a soft-deleted document and make it searchable again
+    Restore {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
     },
 
-    /// Garbage-collect tombstones, vacuum DB (stub)
+    /// Purge the project's expired and deleted documents, rebuild the search index,
+    /// vacuum the database, and refresh its query statistics
     Gc {
+        /// Report exactly what would be removed without changing anything
         #[arg(long)]
         dry_run: bool,
+        /// Report orphaned index and history rows without removing them
+        #[arg(long)]
+        check_only: bool,
+        /// Keep documents deleted within this span (e.g. 30d) restorable; overrides
+        /// `tombstone_retention` in config.json (default: purge them all)
+        #[arg(long)]
+        retention: Option<String>,
+    },
+
+    /// Maintain the database file
+    Db {
+        #[command(subcommand)]
+        action: DbCommands,
+    },
+
+    /// Start or end the scenario later commands log under
+    Scenario {
+        #[command(subcommand)]
+        action: ScenarioCommands,
     },
 
     /// Run user-facing web UI (stub wrapper)
@@ -124,6 +501,35 @@ enum Commands {
         port: u16,
     },
 
+    /// Keep the store open behind a unix socket; put/get/find proxy through it when running
+    Daemon {
+        /// Socket to listen on (default: $CONTEXT_HOME/daemon.sock)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// Socket permissions in octal; the default lets only you connect
+        #[arg(long, default_value = "600")]
+        socket_mode: String,
+        /// Show reminders as desktop notifications when they fall due
+        #[arg(long)]
+        notify: bool,
+    },
+
+    /// Publish a read-only HTML view (with search) of selected projects
+    ServeReadonly {
+        /// Projects to publish, comma-separated or repeated (default: the current project)
+        #[arg(long, value_delimiter = ',')]
+        projects: Vec<String>,
+        /// Address to listen on; use 0.0.0.0 to expose it beyond this machine
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        #[arg(long, default_value_t = 8079)]
+        port: u16,
+        /// Publish only documents put with `--visibility public` (private ones are never
+        /// published)
+        #[arg(long)]
+        public_only: bool,
+    },
+
     /// Run dev web UI (stub wrapper)
     WebDev {
         #[arg(long, default_value_t = 8078)]
@@ -139,729 +545,4691 @@ enum Commands {
         out: Option<String>,
     },
 
+    /// Show a log file, raw or rendered for reading
+    Logs {
+        /// Which binary's log: cli, web, grpc (or a full name such as context-web)
+        #[arg(long, default_value = "cli")]
+        app: String,
+        /// One readable line per entry instead of raw JSONL
+        #[arg(long)]
+        pretty: bool,
+        /// Only the last N entries
+        #[arg(long)]
+        tail: Option<usize>,
+        /// Only entries logged under this scenario id
+        #[arg(long)]
+        scenario: Option<String>,
+        /// Minimum level: trace, debug, info, warn, error
+        #[arg(long)]
+        level: Option<String>,
+    },
+
+    /// Print a shell completion script, e.g. `context completions zsh > _context`
+    Completions {
+        /// bash, zsh, fish, powershell, or elvish
+        shell: clap_complete::Shell,
+    },
+
     /// Emit agent configs for Codex / Claude / Copilot (stub)
     AgentConfig {
         #[arg(long, default_value = "all")]
         target: String,
     },
 
+    /// Assemble the most relevant documents into one markdown blob within a token budget
+    Pack {
+        /// Include the top search results for this query
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Include these documents first, in order (repeatable or comma-separated)
+        #[arg(long = "key", value_delimiter = ',')]
+        keys: Vec<String>,
+
+        /// Token budget for the assembled markdown
+        #[arg(long, visible_alias = "max-tokens", default_value_t = 4000)]
+        budget: usize,
+
+        /// Maximum number of search results to consider with --query
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Condense a document (or search results) with an LLM and store the summary
+    Summarize {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Summarize the top search results for this query instead of one document
+        #[arg(long)]
+        query: Option<String>,
+
+        /// Maximum number of search results to include with --query
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Key for the stored summary (defaults to `summary/<key>` for keyed documents)
+        #[arg(long)]
+        out_key: Option<String>,
+    },
+
+    /// Compile recently changed documents into a dated digest document
+    Digest {
+        /// Look-back window (e.g. 7d, 24h, 2w) or a timestamp
+        #[arg(long, default_value = "7d")]
+        since: String,
+    },
+
+    /// List TODO:/DECISION: markers and checkboxes found in document bodies
+    Todos {
+        /// Only items still to do (unticked checkboxes and TODO: markers)
+        #[arg(long)]
+        open: bool,
+
+        /// List DECISION: markers instead of action items
+        #[arg(long, conflicts_with = "open")]
+        decisions: bool,
+
+        /// Include every project
+        #[arg(long)]
+        all_projects: bool,
+    },
+
+    /// Attach a follow-up reminder to a document
+    Remind {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// When the reminder becomes due: RFC3339 or YYYY-MM-DD (UTC)
+        #[arg(long)]
+        at: String,
+
+        /// What to follow up on
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// List and complete document reminders
+    Reminders {
+        #[command(subcommand)]
+        action: RemindersCommands,
+    },
+
+    /// Export pending reminders and TTL expirations as an iCalendar (.ics) feed
+    Calendar {
+        /// Write the feed here instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Include every project
+        #[arg(long)]
+        all_projects: bool,
+    },
+
+    /// Send alerts through the Slack and email channels in config.json
+    Notify {
+        #[command(subcommand)]
+        action: NotifyCommands,
+    },
+
     /// Manage default project selection
     Project {
         #[command(subcommand)]
         action: ProjectCommands,
     },
-}
 
-#[derive(Subcommand)]
-enum ProjectCommands {
-    /// Show the current project in use
-    Current,
-    /// Set the default project for this workspace
-    Set {
-        /// Project identifier to set as default
-        project: String,
+    /// Inspect and maintain document keys
+    Keys {
+        #[command(subcommand)]
+        action: KeysCommands,
     },
-    /// List known projects
-    List,
-}
 
-fn main() {
-    if let Err(err) = run() {
-        eprintln!("Error: {err}");
-        std::process::exit(1);
-    }
-}
+    /// Manage document namespaces
+    Ns {
+        #[command(subcommand)]
+        action: NsCommands,
+    },
 
-fn run() -> Result<()> {
-    let _telemetry = init_tracing("context-cli", &["context_cli", "context_core"])?;
-    let Cli {
-        project,
-        json,
-        scenario,
-        command,
-    } = Cli::parse();
+    /// Store reusable Markdown skeletons (ADRs, postmortems) and fill them in
+    Template {
+        #[command(subcommand)]
+        action: TemplateCommands,
+    },
 
-    let command_name = command_name(&command).to_string();
-    let project_label = resolve_project(project.clone())?;
-    let scenario = scenario.or_else(|| env::var("CONTEXT_SCENARIO").ok());
+    /// Add, remove, or list one document's tags without re-putting its body
+    Tag {
+        #[command(subcommand)]
+        action: TagCommands,
+    },
 
-    let log_context = LogContext {
-        scenario_id: scenario.as_deref(),
-        project: Some(project_label.as_str()),
-        command: Some(command_name.as_str()),
-    };
+    /// List the project's tags with how many live documents carry each
+    Tags,
 
-    let span = context_span(log_context);
-    let _span_guard = span.enter();
-    let command_span = command_span(log_context, &command);
-    let _command_guard = command_span.enter();
-    let resolved_project = Some(project_label.clone());
-    tracing::info!(
-        scenario_id = log_context.scenario_id,
-        project = log_context.project,
-        command = log_context.command,
-        "Command start"
+    /// Attach a file (an image, a PDF, a log) to a document
+    Attach {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// The file to attach
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Name to store it under (default: the file's name); attaching the same name
+        /// again replaces it
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Media type to record (default: guessed from the name's extension)
+        #[arg(long)]
+        media_type: Option<String>,
+    },
+
+    /// List a document's attachments or write one out
+    Attachments {
+        #[command(subcommand)]
+        action: AttachmentCommands,
+    },
+
+    /// List the documents a document's body links to with `[[key]]` or
+    /// `context://<project>/<key>`
+    Links {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+    },
+
+    /// List the live documents whose bodies link to a key
+    Backlinks {
+        #[arg(long)]
+        key: String,
+    },
+
+    /// Apply tag-based expiry rules from config.json
+    Policy {
+        #[command(subcommand)]
+        action: PolicyCommands,
+    },
+
+    /// Find (and optionally merge) duplicate documents
+    Dedup {
+        #[command(subcommand)]
+        action: DedupCommands,
+    },
+
+    /// Load reproducible sample documents for demos and tests
+    Fixtures {
+        #[command(subcommand)]
+        action: FixturesCommands,
+    },
+
+    /// Import a directory of Markdown files, keyed by their relative paths, or an export's
+    /// JSON Lines
+    Import {
+        /// Directory to walk (hidden files and directories are skipped), or a JSON Lines
+        /// file from `export --format jsonl` to merge by id (the higher version wins)
+        path: PathBuf,
+
+        /// Only import files whose relative path matches this pattern (`*`, `?`, `**`;
+        /// without a `/` it matches file names), instead of every `.md`/`.markdown` file
+        #[arg(long)]
+        glob: Option<String>,
+
+        /// Report what would be created, updated, or skipped without writing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Fill in the search index once after writing instead of per document, for
+        /// imports of thousands of files
+        #[arg(long)]
+        fast_import: bool,
+    },
+
+    /// Export the project's live documents as Markdown files, JSON Lines, or a CSV/TSV table
+    Export {
+        /// Directory for Markdown files (must be new or empty), or the file for the other
+        /// formats (default: stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Output format: markdown (`<namespace>/<key>.md` with front matter), jsonl, or
+        /// csv/tsv (key, title, tags, timestamps, size, and version per document)
+        #[arg(long, default_value = "markdown")]
+        format: String,
+
+        /// Leave out documents with this tag (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        exclude_tag: Vec<String>,
+
+        /// Replace matches of this regex in titles and bodies with `[REDACTED]` (repeatable)
+        #[arg(long)]
+        redact_pattern: Vec<String>,
+
+        /// Also export documents put with `--visibility private`
+        #[arg(long)]
+        include_private: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Recompute the statistics the query planner uses (SQLite `ANALYZE`); worth running
+    /// after the store grows by a lot
+    Analyze,
+}
+
+#[derive(Subcommand)]
+enum ScenarioCommands {
+    /// Start a scenario (ending any running one) and print `export CONTEXT_SCENARIO=<id>`
+    /// for `eval`
+    Start {
+        /// Suffix for the id after its timestamp (default: random)
+        #[arg(long)]
+        label: Option<String>,
+    },
+    /// End the running scenario and print `unset CONTEXT_SCENARIO`
+    End,
+}
+
+#[derive(Subcommand)]
+enum FixturesCommands {
+    /// The fixtures that can be loaded
+    List,
+    /// Fill the (empty) project with a fixture's documents and their history
+    Load {
+        name: String,
+
+        /// Same seed, same documents, ids, and timestamps
+        #[arg(long, default_value_t = DEFAULT_SEED)]
+        seed: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum DedupCommands {
+    /// Report documents with identical or highly similar bodies
+    Report {
+        /// Minimum word-shingle similarity (0.0-1.0) for near-duplicates
+        #[arg(long, default_value_t = 0.8)]
+        threshold: f32,
+
+        /// Merge each group; `keep-newest` keeps the newest document, deletes the rest,
+        /// and redirects their keys to it
+        #[arg(long, value_name = "STRATEGY")]
+        merge: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemindersCommands {
+    /// Reminders whose time has come and that are not done yet
+    Due {
+        /// Include every project
+        #[arg(long)]
+        all_projects: bool,
+    },
+    /// Every pending reminder, soonest first
+    List {
+        /// Include every project
+        #[arg(long)]
+        all_projects: bool,
+
+        /// Also show reminders already marked done
+        #[arg(long)]
+        done: bool,
+    },
+    /// Mark a reminder done
+    Done { id: i64 },
+}
+
+#[derive(Subcommand)]
+enum NotifyCommands {
+    /// Send a test message through every channel configured for the project
+    Test {
+        #[arg(long, default_value = "Test notification from context")]
+        message: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NsCommands {
+    /// List namespaces with their document counts
+    List,
+    /// Register a namespace before any document uses it
+    Create { name: String },
+    /// Move every document from one namespace to another
+    Rename { from: String, to: String },
+    /// Unregister a namespace
+    Rm {
+        name: String,
+
+        /// Clear the namespace from documents that still use it
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    /// Store a template from --file or stdin, replacing one with the same name; mark
+    /// blanks as `{{name}}`
+    Add {
+        name: String,
+
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// What the template is for, shown by `template list`
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// List the project's templates and the variables each one asks for
+    List,
+    /// Print a template filled in (`{{date}}` defaults to today)
+    Render {
+        name: String,
+
+        /// A value for a blank (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+    },
+    /// Remove a template
+    Rm { name: String },
+}
+
+#[derive(Subcommand)]
+enum TagCommands {
+    /// Add tags (already present ones are left as they are)
+    Add {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Tags to add (space- or comma-separated)
+        #[arg(required = true, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+    /// Remove tags
+    Remove {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// Tags to remove (space- or comma-separated)
+        #[arg(required = true, value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+    /// Print the document's tags
+    List {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AttachmentCommands {
+    /// List the document's attachments
+    List {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+    },
+    /// Write an attachment's bytes to stdout or --out
+    Get {
+        #[arg(long)]
+        key: Option<String>,
+
+        #[arg(long)]
+        id: Option<String>,
+
+        /// The attachment's name
+        name: String,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PolicyCommands {
+    /// Set policy TTLs on existing documents that have none
+    Apply {
+        /// Only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommands {
+    /// Rewrite existing keys to their normalized form, reporting collisions
+    Normalize {
+        /// Only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Give a document a new key; the old key keeps resolving to it
+    Rename { from: String, to: String },
+    /// List retired keys and where they now point
+    Redirects,
+}
+
+#[derive(Subcommand)]
+enum ProjectCommands {
+    /// Show the current project in use
+    Current,
+    /// Set the default project for this workspace
+    Set {
+        /// Project identifier to set as default
+        project: String,
+    },
+    /// List known projects
+    List,
+    /// Show a project's display name, description, and settings
+    Show {
+        /// Project to show (defaults to the current project)
+        project: Option<String>,
+    },
+    /// Set a project's display name, description, or settings
+    Edit {
+        /// Project to edit (defaults to the current project)
+        project: Option<String>,
+
+        /// Human-friendly name shown in listings and the web UI
+        #[arg(long)]
+        name: Option<String>,
+
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Setting to store as `key=value` (repeatable; an empty value removes the key)
+        #[arg(long = "setting", value_name = "KEY=VALUE")]
+        settings: Vec<String>,
+    },
+    /// Manage short aliases for project names
+    Alias {
+        #[command(subcommand)]
+        action: AliasCommands,
+    },
+    /// Write config.json plus the known project names to stdout or a file
+    Export {
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Load a config exported from another machine
+    Import {
+        /// Exported config (reads stdin when omitted)
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Replace the local config instead of merging into it
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AliasCommands {
+    /// Map an alias to a project (e.g. `ctx` -> `acme-main-repo`)
+    Add { alias: String, project: String },
+    /// Remove an alias
+    Rm { alias: String },
+    /// List aliases
+    List,
+}
+
+/// Exit code for failures that are not covered by a more specific code.
+const EXIT_FAILURE: i32 = 1;
+/// Exit code when the requested document does not exist.
+const EXIT_NOT_FOUND: i32 = 3;
+/// Exit code when a write collided with the store: a stale version, a taken key, or a
+/// reused idempotency key.
+const EXIT_CONFLICT: i32 = 4;
+/// Exit code when a search or count ran past `query_timeout_secs` and was stopped.
+const EXIT_TIMEOUT: i32 = 5;
+
+/// The requested document (or revision) does not exist.
+///
+/// Surfaced with its own exit code and JSON error code so wrappers can tell
+/// "missing" apart from "broken" without parsing the message.
+#[derive(Debug)]
+struct NotFound(String);
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+fn main() {
+    let cli = Cli::parse();
+    let json = cli.json;
+    if let Err(err) = run(cli) {
+        std::process::exit(report_error(&err, json));
+    }
+}
+
+/// Print `err` for humans (and, in JSON mode, as a structured payload) and pick the exit code.
+fn report_error(err: &anyhow::Error, json_output: bool) -> i32 {
+    eprintln!("Error: {err}");
+    let (Some(code), exit) = classify_error(err) else {
+        return EXIT_FAILURE;
+    };
+    if json_output {
+        let payload = serde_json::json!({
+            "error": { "code": code, "message": err.to_string() }
+        });
+        println!("{payload}");
+    }
+    exit
+}
+
+/// The JSON error code and exit code for `err`; no code for a plain failure.
+fn classify_error(err: &anyhow::Error) -> (Option<&'static str>, i32) {
+    if is_not_found(err) {
+        (Some("not_found"), EXIT_NOT_FOUND)
+    } else if let Some(ContextError::Conflict(_)) = err.downcast_ref::<ContextError>() {
+        (Some("conflict"), EXIT_CONFLICT)
+    } else if let Some(ContextError::Timeout(_)) = err.downcast_ref::<ContextError>() {
+        (Some("timeout"), EXIT_TIMEOUT)
+    } else {
+        (None, EXIT_FAILURE)
+    }
+}
+
+/// Whether `err` is a missing document (or other named thing), from this crate or core.
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.is::<NotFound>()
+        || matches!(
+            err.downcast_ref::<ContextError>(),
+            Some(ContextError::NotFound(_))
+        )
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let telemetry = init_tracing_in_home(
+        "context-cli",
+        &["context_cli", "context_core", "context_telemetry"],
+        &context_home()?,
+    )?;
+    let Cli {
+        project,
+        json,
+        scenario,
+        command,
+    } = cli;
+
+    let command_name = command_name(&command).to_string();
+    let project_label = resolve_project(project.clone())?;
+    let scenario = scenario
+        .or_else(|| env::var("CONTEXT_SCENARIO").ok())
+        .or_else(|| scenario::current().map(|scenario| scenario.id));
+    // Processes this command starts (servers, hooks, editors) log under it too.
+    if let Some(scenario) = &scenario {
+        env::set_var("CONTEXT_SCENARIO", scenario);
+    }
+
+    let log_context = LogContext {
+        scenario_id: scenario.as_deref(),
+        project: Some(project_label.as_str()),
+        command: Some(command_name.as_str()),
+    };
+
+    let span = context_span(log_context);
+    if load_project_config()?.log_git != Some(false) {
+        if let Some(state) = env::current_dir()
+            .ok()
+            .and_then(|dir| WorkspaceState::detect(&dir))
+        {
+            state.record(&span);
+        }
+    }
+    let _span_guard = span.enter();
+    let command_span = command_span(log_context, &command);
+    let _command_guard = command_span.enter();
+    tracing::info!(
+        scenario_id = log_context.scenario_id,
+        project = log_context.project,
+        command = log_context.command,
+        "Command start"
+    );
+
+    let started = Instant::now();
+    let result = dispatch(
+        command,
+        json,
+        project,
+        &project_label,
+        log_context,
+        &telemetry,
+    );
+    let exit_code = match &result {
+        Ok(()) => 0,
+        Err(err) => classify_error(err).1,
+    };
+    completion::record(
+        &completion::sanitized_argv(env::args()),
+        started.elapsed(),
+        exit_code,
+    );
+    result
+}
+
+/// Run `command` for `run`, inside its spans.
+fn dispatch(
+    command: Commands,
+    json: bool,
+    project: Option<String>,
+    project_label: &str,
+    log_context: LogContext<'_>,
+    telemetry: &TelemetryGuard,
+) -> Result<()> {
+    let command_name = command_name(&command);
+    let resolved_project = Some(project_label.to_string());
+    match command {
+        Commands::AgentDoc { format } => match format.as_str() {
+            "markdown" | "md" => {
+                let md = context_agent::agent_doc_markdown();
+                print!("{md}");
+            }
+            other => {
+                eprintln!("Unsupported format: {other}. Try --format markdown");
+                std::process::exit(2);
+            }
+        },
+        Commands::Init {
+            force,
+            agent_config,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                force,
+                ?agent_config,
+                "Init command invoked"
+            );
+            handle_init(json, project, force, agent_config)?;
+        }
+        Commands::Whoami => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Whoami command invoked"
+            );
+            handle_whoami(json, &project, project_label, telemetry.log_path())?;
+        }
+        Commands::Put {
+            key,
+            file,
+            title,
+            tags,
+            namespace,
+            idempotency_key,
+            ttl,
+            visibility,
+            from_template,
+            vars,
+            meta,
+            batch,
+            fast_import,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?file,
+                ?title,
+                tags = ?tags,
+                ?namespace,
+                ?idempotency_key,
+                ?ttl,
+                ?visibility,
+                ?from_template,
+                vars = vars.len(),
+                meta = meta.len(),
+                batch,
+                fast_import,
+                "Put command invoked"
+            );
+            let visibility = visibility.as_deref().map(str::parse).transpose()?;
+            if batch {
+                handle_put_batch(
+                    resolved_project.clone(),
+                    file,
+                    tags,
+                    namespace,
+                    visibility,
+                    fast_import,
+                )?;
+            } else {
+                let options = PutOptions {
+                    title,
+                    namespace,
+                    idempotency_key,
+                    ttl: ttl.as_deref().map(parse_duration).transpose()?,
+                    visibility,
+                    from_template,
+                    vars,
+                    meta: meta
+                        .iter()
+                        .map(|pair| parse_metadata_pair(pair))
+                        .collect::<Result<_, _>>()?,
+                };
+                handle_put(resolved_project.clone(), json, key, file, tags, options)?;
+            }
+        }
+        Commands::Touch {
+            key,
+            id,
+            ttl,
+            no_ttl,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?ttl,
+                no_ttl,
+                "Touch command invoked"
+            );
+            let ttl = ttl.as_deref().map(parse_duration).transpose()?;
+            handle_touch(resolved_project.clone(), json, key, id, ttl)?;
+        }
+        Commands::Get {
+            key,
+            id,
+            format,
+            as_of,
+            version,
+            max_tokens,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?format,
+                ?as_of,
+                ?version,
+                ?max_tokens,
+                "Get command invoked"
+            );
+            let revision = match (as_of, version) {
+                (Some(at), _) => Revision::AsOf(parse_timestamp(&at)?),
+                (None, Some(version)) => Revision::Version(version),
+                (None, None) => Revision::Current,
+            };
+            handle_get(
+                resolved_project.clone(),
+                json,
+                key,
+                id,
+                format,
+                revision,
+                max_tokens,
+            )?;
+        }
+        Commands::History { key, id } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                "History command invoked"
+            );
+            handle_history(resolved_project.clone(), json, key, id)?;
+        }
+        Commands::Diff {
+            key,
+            id,
+            from,
+            to,
+            context,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?from,
+                ?to,
+                context,
+                "Diff command invoked"
+            );
+            handle_diff(resolved_project.clone(), json, key, id, (from, to), context)?;
+        }
+        Commands::Blame { key, id } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                "Blame command invoked"
+            );
+            handle_blame(resolved_project.clone(), json, key, id)?;
+        }
+        Commands::Related { key, id, limit } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?limit,
+                "Related command invoked"
+            );
+            handle_related(resolved_project.clone(), json, key, id, limit)?;
+        }
+        Commands::Cat {
+            mut key,
+            keys,
+            id,
+            delimiter,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?keys,
+                ?id,
+                "Cat command invoked"
+            );
+            // `--keys` asks for the list output even when it names one key.
+            if key.len() > 1 || !keys.is_empty() {
+                key.extend(keys);
+                handle_cat_keys(resolved_project.clone(), json, key, &delimiter)?;
+            } else {
+                handle_cat(resolved_project.clone(), json, key.pop(), id)?;
+            }
+        }
+        Commands::Find {
+            query,
+            limit,
+            all_projects,
+            format,
+            max_tokens,
+            fields,
+            key_only,
+            not_tags,
+            exclude_namespaces,
+            within,
+            keys,
+            explain,
+            code,
+            timeout,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %query,
+                ?limit,
+                ?all_projects,
+                %format,
+                ?max_tokens,
+                ?fields,
+                key_only,
+                ?not_tags,
+                ?exclude_namespaces,
+                ?within,
+                ?keys,
+                explain,
+                code,
+                ?timeout,
+                "Find command invoked"
+            );
+            let format = list_format(json, &format)?;
+            let selection = ListSelection {
+                fields: field_selection(format, fields, key_only, &["tokens"])?,
+                not_tags,
+                exclude_namespaces,
+                key_prefix: within,
+                keys,
+                explain,
+                code,
+                timeout: timeout.map(Duration::from_millis),
+            };
+            handle_find(
+                resolved_project.clone(),
+                format,
+                query,
+                limit,
+                all_projects,
+                max_tokens,
+                selection,
+            )?;
+        }
+        Commands::Ls {
+            format,
+            fields,
+            key_only,
+            not_tags,
+            exclude_namespaces,
+            tags,
+            namespace,
+            prefix,
+            since,
+            until,
+            include_deleted,
+            deleted,
+            expiring_within,
+            visibility,
+            meta,
+            sort,
+            limit,
+            cursor,
+            count,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %format,
+                ?fields,
+                key_only,
+                ?not_tags,
+                ?exclude_namespaces,
+                ?tags,
+                ?namespace,
+                ?prefix,
+                ?since,
+                ?until,
+                include_deleted,
+                deleted,
+                ?expiring_within,
+                ?visibility,
+                ?meta,
+                %sort,
+                ?limit,
+                count,
+                "Ls command invoked"
+            );
+            let format = list_format(json, &format)?;
+            if let Some(0) = limit {
+                bail!("Limit must be greater than 0.");
+            }
+            let selection = ListSelection {
+                fields: field_selection(format, fields, key_only, &[])?,
+                not_tags: Vec::new(),
+                exclude_namespaces: Vec::new(),
+                key_prefix: None,
+                keys: Vec::new(),
+                explain: false,
+                code: false,
+                timeout: None,
+            };
+            let query = ListQuery {
+                tags,
+                not_tags,
+                namespace,
+                exclude_namespaces,
+                key_prefix: prefix,
+                since: since.as_deref().map(parse_timestamp).transpose()?,
+                until: until.as_deref().map(parse_timestamp).transpose()?,
+                include_deleted,
+                only_deleted: deleted,
+                expiring_before: expiring_within
+                    .as_deref()
+                    .map(parse_duration)
+                    .transpose()?
+                    .map(|within| Utc::now() + within),
+                limit,
+                cursor,
+                visibility: visibility
+                    .iter()
+                    .map(|raw| raw.parse())
+                    .collect::<Result<_, _>>()?,
+                metadata: meta
+                    .iter()
+                    .map(|pair| parse_metadata_pair(pair))
+                    .collect::<Result<_, _>>()?,
+                sort: sort.parse()?,
+            };
+            if count {
+                handle_ls_count(resolved_project.clone(), format, &query)?;
+            } else {
+                handle_ls(resolved_project.clone(), format, selection, query)?;
+            }
+        }
+        Commands::Rm {
+            key,
+            id,
+            force,
+            purge,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?force,
+                ?purge,
+                "Rm command invoked"
+            );
+            handle_rm(resolved_project.clone(), json, key, id, force, purge)?;
+        }
+        Commands::Mv { key, to, no_alias } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %key,
+                %to,
+                no_alias,
+                "Mv command invoked"
+            );
+            handle_rename(resolved_project.clone(), json, key, to, !no_alias)?;
+        }
+        Commands::Restore { key, id } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                "Restore command invoked"
+            );
+            handle_restore(resolved_project.clone(), json, key, id)?;
+        }
+        Commands::Gc {
+            dry_run,
+            check_only,
+            retention,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?dry_run,
+                ?check_only,
+                ?retention,
+                "Gc command invoked"
+            );
+            handle_gc(
+                resolved_project.clone(),
+                json,
+                dry_run,
+                check_only,
+                retention,
+            )?;
+        }
+        Commands::Db { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Db command invoked"
+            );
+            match action {
+                DbCommands::Analyze => handle_db_analyze(json)?,
+            }
+        }
+        Commands::Scenario { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Scenario command invoked"
+            );
+            match action {
+                ScenarioCommands::Start { label } => handle_scenario_start(json, label)?,
+                ScenarioCommands::End => handle_scenario_end(json)?,
+            }
+        }
+        Commands::Web { port } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?port,
+                "Web command invoked"
+            );
+            handle_web(json, port)?;
+        }
+        Commands::Daemon {
+            socket,
+            socket_mode,
+            notify,
+        } => {
+            let socket = match socket {
+                Some(socket) => socket,
+                None => daemon::default_socket()?,
+            };
+            let mode = context_core::socket::parse_mode(&socket_mode)?;
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                socket = %socket.display(),
+                notify,
+                "Daemon command invoked"
+            );
+            daemon::run(socket, mode, notify, json, telemetry)?;
+        }
+        Commands::ServeReadonly {
+            projects,
+            host,
+            port,
+            public_only,
+        } => {
+            let projects = if projects.is_empty() {
+                vec![resolved_project
+                    .clone()
+                    .unwrap_or_else(|| "default".to_string())]
+            } else {
+                let config = load_project_config()?;
+                projects
+                    .iter()
+                    .map(|project| config.resolve_alias(project.trim()))
+                    .collect()
+            };
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?projects,
+                %host,
+                port,
+                public_only,
+                "Serve-readonly command invoked"
+            );
+            #[cfg(feature = "serve")]
+            snapshot::serve(projects, host, port, public_only, json)?;
+            #[cfg(not(feature = "serve"))]
+            return Err(not_built_with("serve-readonly", "serve"));
+        }
+        Commands::WebDev { port } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?port,
+                "WebDev command invoked"
+            );
+            handle_web_dev(json, port)?;
+        }
+        Commands::DebugBundle { scenario, out } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?scenario,
+                ?out,
+                "DebugBundle command invoked"
+            );
+            let scenario_value = scenario.or_else(|| log_context.scenario_id.map(str::to_string));
+            let bundle_path = create_debug_bundle(scenario_value.clone(), out)?;
+            if json {
+                let payload = serde_json::json!({
+                    "status": "ok",
+                    "path": bundle_path,
+                    "scenario": scenario_value,
+                });
+                println!("{}", serde_json::to_string_pretty(&payload)?);
+            } else {
+                println!("{}", bundle_path.display());
+            }
+        }
+        Commands::Logs {
+            app,
+            pretty,
+            tail,
+            scenario,
+            level,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %app,
+                pretty,
+                ?tail,
+                ?scenario,
+                ?level,
+                "Logs command invoked"
+            );
+            handle_logs(
+                json,
+                &app,
+                pretty,
+                logs::LogFilter {
+                    scenario,
+                    level,
+                    tail,
+                },
+            )?;
+        }
+        Commands::Completions { shell } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %shell,
+                "Completions command invoked"
+            );
+            let mut command = Cli::command();
+            clap_complete::generate(shell, &mut command, "context", &mut io::stdout());
+        }
+        Commands::AgentConfig { target } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %target,
+                "AgentConfig command invoked"
+            );
+            eprintln!("TODO: implement `context agent-config`");
+        }
+        Commands::Pack {
+            query,
+            keys,
+            budget,
+            limit,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?query,
+                ?keys,
+                ?budget,
+                ?limit,
+                "Pack command invoked"
+            );
+            handle_pack(resolved_project.clone(), json, query, keys, budget, limit)?;
+        }
+        Commands::Summarize {
+            key,
+            id,
+            query,
+            limit,
+            out_key,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?query,
+                ?limit,
+                ?out_key,
+                "Summarize command invoked"
+            );
+            handle_summarize(
+                resolved_project.clone(),
+                json,
+                SummarizeTarget {
+                    key,
+                    id,
+                    query,
+                    limit,
+                },
+                out_key,
+            )?;
+        }
+        Commands::Digest { since } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                %since,
+                "Digest command invoked"
+            );
+            handle_digest(resolved_project.clone(), json, since)?;
+        }
+        Commands::Todos {
+            open,
+            decisions,
+            all_projects,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                open,
+                decisions,
+                all_projects,
+                "Todos command invoked"
+            );
+            let kinds = if decisions {
+                vec![ItemKind::Decision]
+            } else {
+                vec![ItemKind::Todo, ItemKind::Task]
+            };
+            let project = resolved_project
+                .clone()
+                .unwrap_or_else(|| "default".to_string());
+            let query = ItemQuery {
+                project: (!all_projects).then_some(project),
+                kinds,
+                open_only: open,
+            };
+            handle_todos(json, query)?;
+        }
+        Commands::Remind { key, id, at, note } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                %at,
+                "Remind command invoked"
+            );
+            handle_remind(resolved_project.clone(), json, key, id, at, note)?;
+        }
+        Commands::Reminders { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Reminders command invoked"
+            );
+            handle_reminders(resolved_project.clone(), json, action)?;
+        }
+        Commands::Calendar { out, all_projects } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?out,
+                all_projects,
+                "Calendar command invoked"
+            );
+            let project = resolved_project
+                .clone()
+                .unwrap_or_else(|| "default".to_string());
+            handle_calendar((!all_projects).then_some(project), json, out)?;
+        }
+        Commands::Notify { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Notify command invoked"
+            );
+            match action {
+                NotifyCommands::Test { message } => {
+                    handle_notify_test(resolved_project.clone(), json, message)?
+                }
+            }
+        }
+        Commands::Project { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Project command invoked"
+            );
+            match action {
+                ProjectCommands::Current => handle_project_current(json, project)?,
+                ProjectCommands::Set {
+                    project: new_project,
+                } => handle_project_set(json, new_project)?,
+                ProjectCommands::List => handle_project_list(json)?,
+                ProjectCommands::Show { project: target } => {
+                    handle_project_show(json, target.unwrap_or_else(|| project_label.to_string()))?
+                }
+                ProjectCommands::Edit {
+                    project: target,
+                    name,
+                    description,
+                    settings,
+                } => handle_project_edit(
+                    json,
+                    target.unwrap_or_else(|| project_label.to_string()),
+                    name,
+                    description,
+                    settings,
+                )?,
+                ProjectCommands::Alias { action } => handle_project_alias(json, action)?,
+                ProjectCommands::Export { out } => handle_project_export(json, out)?,
+                ProjectCommands::Import { file, replace } => {
+                    handle_project_import(json, file, replace)?
+                }
+            }
+        }
+        Commands::Keys { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Keys command invoked"
+            );
+            match action {
+                KeysCommands::Normalize { dry_run } => {
+                    handle_keys_normalize(resolved_project.clone(), json, dry_run)?
+                }
+                KeysCommands::Rename { from, to } => {
+                    handle_rename(resolved_project.clone(), json, from, to, true)?
+                }
+                KeysCommands::Redirects => handle_keys_redirects(resolved_project.clone(), json)?,
+            }
+        }
+        Commands::Ns { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Ns command invoked"
+            );
+            handle_ns(resolved_project.clone(), json, action)?;
+        }
+        Commands::Template { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Template command invoked"
+            );
+            template::handle_template(resolved_project.clone(), json, action)?;
+        }
+        Commands::Tag { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Tag command invoked"
+            );
+            tags::handle_tag(resolved_project.clone(), json, action)?;
+        }
+        Commands::Tags => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Tags command invoked"
+            );
+            tags::handle_tags(resolved_project.clone(), json)?;
+        }
+        Commands::Attach {
+            key,
+            id,
+            file,
+            name,
+            media_type,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                ?file,
+                ?name,
+                "Attach command invoked"
+            );
+            attachments::handle_attach(
+                resolved_project.clone(),
+                json,
+                key,
+                id,
+                file,
+                name,
+                media_type,
+            )?;
+        }
+        Commands::Attachments { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Attachments command invoked"
+            );
+            attachments::handle_attachments(resolved_project.clone(), json, action)?;
+        }
+        Commands::Links { key, id } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                ?id,
+                "Links command invoked"
+            );
+            links::handle_links(resolved_project.clone(), json, key, id)?;
+        }
+        Commands::Backlinks { key } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?key,
+                "Backlinks command invoked"
+            );
+            links::handle_backlinks(resolved_project.clone(), json, key)?;
+        }
+        Commands::Policy { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Policy command invoked"
+            );
+            match action {
+                PolicyCommands::Apply { dry_run } => {
+                    handle_policy_apply(resolved_project.clone(), json, dry_run)?
+                }
+            }
+        }
+        Commands::Dedup { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Dedup command invoked"
+            );
+            match action {
+                DedupCommands::Report { threshold, merge } => {
+                    handle_dedup_report(resolved_project.clone(), json, threshold, merge)?
+                }
+            }
+        }
+        Commands::Fixtures { action } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                "Fixtures command invoked"
+            );
+            handle_fixtures(resolved_project.clone(), json, action)?;
+        }
+        Commands::Import {
+            path,
+            glob,
+            dry_run,
+            fast_import,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                path = %path.display(),
+                ?glob,
+                dry_run,
+                fast_import,
+                "Import command invoked"
+            );
+            import::handle_import(
+                resolved_project.clone(),
+                json,
+                path,
+                glob,
+                dry_run,
+                fast_import,
+            )?;
+        }
+        Commands::Export {
+            out,
+            format,
+            exclude_tag,
+            redact_pattern,
+            include_private,
+        } => {
+            tracing::info!(
+                scenario_id = log_context.scenario_id,
+                project = log_context.project,
+                command = log_context.command,
+                ?out,
+                format,
+                ?exclude_tag,
+                redact_patterns = redact_pattern.len(),
+                include_private,
+                "Export command invoked"
+            );
+            let filter = export::ExportFilter::new(exclude_tag, &redact_pattern, include_private)?;
+            export::handle_export(resolved_project.clone(), json, out, &format, filter)?;
+        }
+    }
+
+    // Servers run until stopped, and gc has just done what the warnings would suggest.
+    if !matches!(
+        command_name,
+        "gc" | "web" | "daemon" | "serve-readonly" | "web-dev" | "completions" | "agent-doc"
+    ) {
+        warn_about_store_size(project_label);
+    }
+
+    Ok(())
+}
+
+/// Print the `warnings` hints to stderr after a command's output. Best effort: a
+/// command that succeeded doesn't fail because the check couldn't run.
+fn warn_about_store_size(project: &str) {
+    let Ok(config) = load_project_config() else {
+        return;
+    };
+    if !config.warnings.enabled {
+        return;
+    }
+    let Ok(db) = db_path() else {
+        return;
+    };
+    let Ok(meta) = fs::metadata(&db) else {
+        return;
+    };
+    let wal_bytes = fs::metadata(db.with_extension("sqlite-wal")).map_or(0, |wal| wal.len());
+    let deleted = runtime().and_then(|runtime| {
+        runtime.block_on(async {
+            let storage = SqliteStorage::open(&db).await?;
+            Ok(storage
+                .count(&project.to_string(), &deleted_documents())
+                .await?)
+        })
+    });
+    let deleted = match deleted {
+        Ok(deleted) => deleted,
+        Err(err) => {
+            tracing::debug!(error = %err, "Skipping the deleted document check");
+            0
+        }
+    };
+    for warning in config
+        .warnings
+        .check(project, meta.len() + wal_bytes, deleted)
+    {
+        eprintln!("{warning}");
+    }
+}
+
+/// Tombstones only: what `ls --deleted` lists and `gc` purges once past `tombstone_retention`.
+fn deleted_documents() -> ListQuery {
+    ListQuery {
+        only_deleted: true,
+        ..Default::default()
+    }
+}
+
+fn handle_whoami(
+    json_output: bool,
+    project_arg: &Option<String>,
+    project: &str,
+    log_file: &Path,
+) -> Result<()> {
+    let project_source = if project_arg.is_some() {
+        "--project"
+    } else if env::var("CONTEXT_PROJECT").is_ok_and(|value| !value.trim().is_empty()) {
+        "CONTEXT_PROJECT"
+    } else if load_project_config()?.current.is_some() {
+        "config"
+    } else {
+        "default"
+    };
+    let home = context_home()?;
+    let db = db_path()?;
+    let db_bytes = fs::metadata(&db).map(|meta| meta.len()).ok();
+    let config = project_config_path()?;
+    let non_empty_env = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    let sync_remote = non_empty_env("CONTEXT_SYNC_REMOTE");
+    let agent = non_empty_env("CONTEXT_AGENT");
+    let user = non_empty_env("USER").or_else(|| non_empty_env("USERNAME"));
+    let machine_id = machine_id()?;
+
+    if json_output {
+        let payload = serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "project": project,
+            "project_source": project_source,
+            "context_home": home,
+            "db": { "path": db, "exists": db_bytes.is_some(), "bytes": db_bytes },
+            "config": { "path": config, "exists": config.exists() },
+            "log_file": log_file,
+            "sync_remote": sync_remote,
+            "agent": agent,
+            "user": user,
+            "machine_id": machine_id,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    let or_unset = |value: Option<String>| value.unwrap_or_else(|| "(not set)".to_string());
+    println!("context {}", env!("CARGO_PKG_VERSION"));
+    println!("Project:      {project} (from {project_source})");
+    println!("Context home: {}", home.display());
+    match db_bytes {
+        Some(bytes) => println!("Database:     {} ({bytes} bytes)", db.display()),
+        None => println!("Database:     {} (not created yet)", db.display()),
+    }
+    println!(
+        "Config:       {}{}",
+        config.display(),
+        if config.exists() {
+            ""
+        } else {
+            " (not created yet)"
+        }
+    );
+    println!("Log file:     {}", log_file.display());
+    println!("Sync remote:  {}", or_unset(sync_remote));
+    println!("Agent:        {}", or_unset(agent));
+    println!("User:         {}", or_unset(user));
+    println!("Machine id:   {machine_id}");
+    Ok(())
+}
+
+/// How `put` files a document, beyond its key, body, and tags.
+struct PutOptions {
+    title: Option<String>,
+    namespace: Option<String>,
+    idempotency_key: Option<String>,
+    ttl: Option<chrono::Duration>,
+    visibility: Option<Visibility>,
+    from_template: Option<String>,
+    vars: Vec<String>,
+    meta: Vec<(String, String)>,
+}
+
+/// One line of `put --batch` input: the parts of a `Document` a caller writes.
+#[derive(Debug, Deserialize)]
+struct BatchLine {
+    key: Option<String>,
+    #[serde(alias = "body_markdown")]
+    body: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    namespace: Option<String>,
+    title: Option<String>,
+    /// A span such as `7d`.
+    ttl: Option<String>,
+    ttl_seconds: Option<i64>,
+    visibility: Option<Visibility>,
+    #[serde(default)]
+    meta: serde_json::Map<String, serde_json::Value>,
+}
+
+/// What `put --batch` did with one input line.
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    line: usize,
+    /// `created`, `updated`, or `error`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn batch_document(
+    project: &str,
+    raw: &str,
+    tags: &[String],
+    namespace: &Option<String>,
+    visibility: Option<Visibility>,
+) -> Result<Document> {
+    let line: BatchLine = serde_json::from_str(raw)?;
+    if line.body.trim().is_empty() {
+        bail!("`body` is empty");
+    }
+    let mut all_tags: Vec<String> = Vec::new();
+    for tag in line.tags.iter().chain(tags) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !all_tags.iter().any(|seen| seen == tag) {
+            all_tags.push(tag.to_string());
+        }
+    }
+    let mut draft = new_document(
+        project.to_string(),
+        line.key,
+        all_tags,
+        line.body,
+        SourceType::User,
+    );
+    draft.title = line.title;
+    draft.namespace = line
+        .namespace
+        .or_else(|| namespace.clone())
+        .map(|ns| ns.trim().to_string());
+    draft.ttl_seconds = match (line.ttl, line.ttl_seconds) {
+        (Some(ttl), _) => Some(parse_duration(&ttl)?.num_seconds()),
+        (None, seconds) => seconds,
+    };
+    draft.visibility = line.visibility.or(visibility);
+    draft.metadata = line.meta;
+    Ok(draft)
+}
+
+/// `put --batch`: every line that parses is stored in one transaction, so a failing
+/// write leaves none of them behind; lines that don't parse are reported and skipped.
+fn handle_put_batch(
+    project: Option<String>,
+    file: Option<PathBuf>,
+    tags: Vec<String>,
+    namespace: Option<String>,
+    visibility: Option<Visibility>,
+    fast_import: bool,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let input = read_body(file)?;
+    let mut results = Vec::new();
+    let mut drafts = Vec::new();
+    for (index, raw) in input.lines().enumerate() {
+        if raw.trim().is_empty() {
+            continue;
+        }
+        match batch_document(&project, raw, &tags, &namespace, visibility) {
+            Ok(draft) => {
+                results.push((index + 1, None));
+                drafts.push(draft);
+            }
+            Err(err) => results.push((index + 1, Some(err.to_string()))),
+        }
+    }
+
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_import_storage(fast_import))?;
+    let mut stored = runtime
+        .block_on(storage.store_all(drafts))
+        .context("Nothing from the batch was written")?
+        .into_iter();
+    let mut failed = 0;
+    let mut stdout = io::stdout().lock();
+    for (line, error) in results {
+        let result = match error {
+            Some(error) => {
+                failed += 1;
+                BatchResult {
+                    line,
+                    status: "error",
+                    id: None,
+                    key: None,
+                    version: None,
+                    error: Some(error),
+                }
+            }
+            None => {
+                let doc = stored
+                    .next()
+                    .context("store_all returned too few documents")?;
+                BatchResult {
+                    line,
+                    status: if doc.version == 1 {
+                        "created"
+                    } else {
+                        "updated"
+                    },
+                    id: Some(doc.id.0),
+                    key: doc.key,
+                    version: Some(doc.version),
+                    error: None,
+                }
+            }
+        };
+        serde_json::to_writer(&mut stdout, &result)?;
+        writeln!(stdout)?;
+    }
+    if failed > 0 {
+        bail!("{failed} batch line(s) could not be read and were skipped; see their `error`.");
+    }
+    Ok(())
+}
+
+fn handle_put(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    file: Option<PathBuf>,
+    tags_arg: Vec<String>,
+    options: PutOptions,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    // Files and templates are documents as written; stdin is taken as a raw body.
+    let source = match (&options.from_template, &file) {
+        (Some(name), _) => Some(format!("template {name}")),
+        (None, Some(path)) => Some(path.display().to_string()),
+        (None, None) => None,
+    };
+    let body = match &options.from_template {
+        Some(name) => template::render(&project, name, &options.vars)?,
+        None => read_body(file)?,
+    };
+    let (front_matter, body) = match &source {
+        Some(source) => {
+            let (front_matter, body) = frontmatter::split(&body)
+                .with_context(|| format!("Failed to read front matter in {source}"))?;
+            (front_matter, body.to_string())
+        }
+        None => (FrontMatter::default(), body),
+    };
+    let mut tags: Vec<String> = front_matter.tags;
+    for tag in tags_arg {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    let ttl = match (options.ttl, &front_matter.ttl) {
+        (Some(ttl), _) => Some(ttl),
+        (None, Some(raw)) => Some(parse_duration(raw).context("Front matter `ttl`")?),
+        (None, None) => None,
+    };
+    let visibility = match (options.visibility, &front_matter.visibility) {
+        (Some(visibility), _) => Some(visibility),
+        (None, Some(raw)) => Some(raw.parse().context("Front matter `visibility`")?),
+        (None, None) => None,
+    };
+    let title = options.title.or(front_matter.title).or_else(|| {
+        source
+            .as_ref()
+            .and_then(|_| frontmatter::first_heading(&body))
+    });
+
+    let mut backend = Backend::open()?;
+    let mut draft = new_document(
+        project,
+        key.or(front_matter.key),
+        tags,
+        body,
+        SourceType::User,
+    );
+    draft.title = title;
+    draft.namespace = options
+        .namespace
+        .or(front_matter.namespace)
+        .map(|ns| ns.trim().to_string());
+    draft.ttl_seconds = ttl.map(|ttl| ttl.num_seconds());
+    draft.visibility = visibility;
+    draft.metadata = options
+        .meta
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value.is_empty() {
+                true => serde_json::Value::Null,
+                false => serde_json::Value::String(value),
+            };
+            (name, value)
+        })
+        .collect();
+    let expires_at = draft.expires_at();
+    let IdempotentWrite {
+        mut document,
+        replayed,
+    } = backend.store(draft, options.idempotency_key)?;
+    if expires_at.is_some() && !replayed && document.expires_at() != expires_at {
+        // A new revision keeps the first one's creation time, which the TTL counts from.
+        let runtime = runtime()?;
+        let storage = runtime.block_on(open_storage())?;
+        if let Some(updated) = runtime.block_on(storage.set_expiry(&document.id, expires_at))? {
+            document = updated;
+        }
+    }
+
+    if json_output {
+        let serialized = serde_json::to_string_pretty(&document)?;
+        println!("{serialized}");
+    } else {
+        println!(
+            "Stored document {} in project {}",
+            document.id.0, document.project
+        );
+        if replayed {
+            println!("Replayed: idempotency key already used; nothing new was written");
+        }
+        if let Some(key) = &document.key {
+            println!("Key: {key}");
+        }
+        if let Some(title) = &document.title {
+            println!("Title: {title}");
+        }
+        if let Some(namespace) = &document.namespace {
+            println!("Namespace: {namespace}");
+        }
+        if !document.tags.is_empty() {
+            println!("Tags: {}", document.tags.join(", "));
+        }
+        for (name, value) in &document.metadata {
+            println!("Meta: {name}={}", metadata_text(value));
+        }
+        if let Some(visibility) = document.visibility {
+            println!("Visibility: {}", visibility.as_str());
+        }
+        if let Some(expires_at) = document.expires_at() {
+            println!("Expires: {}", expires_at.to_rfc3339());
+        }
+    }
+
+    Ok(())
+}
+
+/// A metadata value as `--meta` takes it: strings bare, anything else as JSON.
+fn metadata_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn handle_touch(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+    ttl: Option<chrono::Duration>,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to change when a document expires.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let document = runtime.block_on(fetch_document(&storage, &project, &key, &id))?;
+    let expires_at = ttl.map(|ttl| Utc::now() + ttl);
+    let Some(document) = runtime.block_on(storage.set_expiry(&document.id, expires_at))? else {
+        return Err(NotFound(format!(
+            "Document {} expired meanwhile.",
+            document_label(&document)
+        ))
+        .into());
+    };
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&document)?);
+        return Ok(());
+    }
+    match document.expires_at() {
+        Some(at) => println!("{} expires {}", document_label(&document), at.to_rfc3339()),
+        None => println!("{} no longer expires", document_label(&document)),
+    }
+    Ok(())
+}
+
+/// A fresh, unsaved version-1 document.
+fn new_document(
+    project: String,
+    key: Option<String>,
+    tags: Vec<String>,
+    body: String,
+    source: SourceType,
+) -> Document {
+    let now = Utc::now();
+    Document {
+        id: DocumentId(Uuid::new_v4().to_string()),
+        project,
+        key,
+        namespace: None,
+        title: None,
+        tags,
+        body_markdown: body,
+        created_at: now,
+        updated_at: now,
+        source,
+        source_meta: None,
+        version: 1,
+        ttl_seconds: None,
+        deleted_at: None,
+        visibility: None,
+        metadata: Default::default(),
+    }
+}
+
+/// Which revision of a document `context get` returns.
+enum Revision {
+    Current,
+    AsOf(DateTime<Utc>),
+    Version(u64),
+}
+
+fn handle_get(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+    format: String,
+    revision: Revision,
+    max_tokens: Option<usize>,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to retrieve a document.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let mut backend = Backend::open()?;
+    let current = match backend.fetch(&project, &key, &id) {
+        Err(err) if err.is::<NotFound>() => {
+            match key.as_deref().map(|key| read_through(&project, key)) {
+                Some(Some(document)) => document,
+                _ => return Err(err),
+            }
+        }
+        other => other?,
+    };
+    let label = key.as_deref().or(id.as_deref()).unwrap_or_default();
+
+    let document = match revision {
+        Revision::AsOf(at) => match backend.version_as_of(&current.id, at)? {
+            Some(version) => version,
+            None => {
+                return Err(NotFound(format!(
+                    "Document {label} had no revision at or before {}.",
+                    at.to_rfc3339()
+                ))
+                .into())
+            }
+        },
+        Revision::Version(wanted) => {
+            let latest = current.version;
+            match backend
+                .history(&current.id)?
+                .into_iter()
+                .find(|doc| doc.version == wanted)
+            {
+                Some(version) => version,
+                None => {
+                    return Err(NotFound(format!(
+                        "Document {label} has no version {wanted}; the latest is {latest}."
+                    ))
+                    .into())
+                }
+            }
+        }
+        Revision::Current => current,
+    };
+
+    let tokens = count_tokens(&document.body_markdown);
+    if let Some(max) = max_tokens {
+        if tokens > max {
+            bail!(
+                "Document {label} is {tokens} tokens, over --max-tokens {max}. Try `context summarize` or `context pack`."
+            );
+        }
+    }
+
+    if json_output {
+        let serialized = serde_json::to_string_pretty(&TokenCounted {
+            document: &document,
+            tokens,
+        })?;
+        println!("{serialized}");
+        return Ok(());
+    }
+
+    match format.as_str() {
+        "markdown" | "md" => {
+            println!("Project: {}", document.project);
+            println!("Document ID: {}", document.id.0);
+            if let Some(key) = &document.key {
+                println!("Key: {key}");
+            }
+            println!("Version: {}", document.version);
+            println!("Tokens: {tokens}");
+            if let Revision::AsOf(at) = revision {
+                println!(
+                    "As of: {} (revision written {})",
+                    at.to_rfc3339(),
+                    document.updated_at.to_rfc3339()
+                );
+            }
+            println!();
+            println!("{}", document.body_markdown);
+        }
+        other => {
+            bail!("Unsupported format: {other}. Use --format markdown or --json");
+        }
+    }
+
+    Ok(())
+}
+
+/// One line of `context history`.
+#[derive(Serialize)]
+struct HistoryEntry<'a> {
+    version: u64,
+    updated_at: DateTime<Utc>,
+    source: &'a SourceType,
+    title: Option<&'a str>,
+    tags: &'a [String],
+    tokens: usize,
+    deleted: bool,
+}
+
+fn handle_history(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to show a document's history.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let mut backend = Backend::open()?;
+    let document = backend.fetch(&project, &key, &id)?;
+    let revisions = backend.history(&document.id)?;
+    let entries: Vec<HistoryEntry<'_>> = revisions
+        .iter()
+        .rev()
+        .map(|revision| HistoryEntry {
+            version: revision.version,
+            updated_at: revision.updated_at,
+            source: &revision.source,
+            title: revision.title.as_deref(),
+            tags: &revision.tags,
+            tokens: count_tokens(&revision.body_markdown),
+            deleted: revision.deleted_at.is_some(),
+        })
+        .collect();
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    println!("History of {}", document_label(&document));
+    for entry in &entries {
+        let mut line = format!(
+            "v{:<3} {}  {:<6} {:>6} tokens",
+            entry.version,
+            entry.updated_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.source.as_str(),
+            entry.tokens
+        );
+        if let Some(title) = entry.title {
+            line.push_str(&format!("  {title}"));
+        }
+        if entry.deleted {
+            line.push_str("  [deleted]");
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn handle_diff(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+    (from, to): (Option<u64>, Option<u64>),
+    context: usize,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to diff a document.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let mut backend = Backend::open()?;
+    let document = backend.fetch(&project, &key, &id)?;
+    let label = document_label(&document);
+    let latest = document.version;
+    let to = to.unwrap_or(latest);
+    let from = match from {
+        Some(from) => from,
+        None if to > 1 => to - 1,
+        None => bail!("{label} v{to} has no earlier version; pass --from."),
+    };
+    let revisions = backend.history(&document.id)?;
+    let revision = |wanted: u64| {
+        revisions
+            .iter()
+            .find(|revision| revision.version == wanted)
+            .ok_or_else(|| {
+                NotFound(format!(
+                    "Document {label} has no version {wanted}; the latest is {latest}."
+                ))
+            })
+    };
+    let diff = diff_revisions(revision(from)?, revision(to)?, context);
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
+    let unified = diff.unified();
+    if unified.is_empty() {
+        println!("{label}: v{from} and v{to} have the same body.");
+    } else if use_color() {
+        print!("{}", colorize_diff(&unified));
+    } else {
+        print!("{unified}");
+    }
+    Ok(())
+}
+
+/// Color terminal output unless `NO_COLOR` is set (https://no-color.org).
+fn use_color() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+fn colorize_diff(unified: &str) -> String {
+    let mut out = String::with_capacity(unified.len());
+    for line in unified.lines() {
+        let color = if line.starts_with("+++") || line.starts_with("---") {
+            "\x1b[1m"
+        } else if line.starts_with("@@") {
+            "\x1b[36m"
+        } else if line.starts_with('+') {
+            "\x1b[32m"
+        } else if line.starts_with('-') {
+            "\x1b[31m"
+        } else {
+            ""
+        };
+        if color.is_empty() {
+            out.push_str(line);
+        } else {
+            out.push_str(&format!("{color}{line}\x1b[0m"));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn handle_blame(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to blame a document.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let document = runtime.block_on(fetch_document(&storage, &project, &key, &id))?;
+    let history = runtime.block_on(storage.history(&document.id))?;
+    let lines = blame(&history);
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&lines)?);
+        return Ok(());
+    }
+
+    let width = lines.len().to_string().len();
+    for line in &lines {
+        println!(
+            "v{:<3} ({:<6} {}) {:>width$}| {}",
+            line.version,
+            format!("{:?}", line.source),
+            line.changed_at.format("%Y-%m-%d %H:%M:%S"),
+            line.line,
+            line.text,
+        );
+    }
+    Ok(())
+}
+
+fn handle_related(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+    limit: usize,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to find related documents.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+    if limit == 0 {
+        bail!("Limit must be greater than 0.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let target = runtime.block_on(fetch_document(&storage, &project, &key, &id))?;
+    let candidates: Vec<Document> =
+        runtime.block_on(storage.stream_project(&target.project).try_collect())?;
+    let related = rank_related(&target, candidates, limit);
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&related)?);
+        return Ok(());
+    }
+
+    println!(
+        "Related to {} in project {}",
+        document_label(&target),
+        target.project
     );
+    for (idx, hit) in related.iter().enumerate() {
+        println!(
+            "{}. {} (score {:.2}: tags {:.2}, link {:.0}, text {:.2})",
+            idx + 1,
+            document_label(&hit.document),
+            hit.score,
+            hit.tag_overlap,
+            hit.link,
+            hit.text_similarity
+        );
+    }
+    Ok(())
+}
 
-    match command {
-        Commands::AgentDoc { format } => match format.as_str() {
-            "markdown" | "md" => {
-                let md = context_agent::agent_doc_markdown();
-                print!("{md}");
+/// `get`'s fallback for a key missing locally: ask the configured remote and keep a copy,
+/// marked with where it came from. Best effort, like a cache miss: any failure is a note
+/// and the key stays not found.
+fn read_through(project: &str, key: &str) -> Option<Document> {
+    let config = load_project_config().ok()?.remote;
+    let url = config.url.clone()?;
+    let mut document = match config.fetch(&url, project, key) {
+        Ok(document) => document?,
+        Err(err) => {
+            eprintln!("note: remote lookup of {key} failed: {err:#}");
+            return None;
+        }
+    };
+    eprintln!("note: {key} is not stored locally; read it from {url}");
+    document.project = project.to_string();
+    document.source_meta = Some(SourceMeta {
+        fetched_from: Some(url),
+        ..document.source_meta.unwrap_or_default()
+    });
+    if !config.cache {
+        return Some(document);
+    }
+    let stored = runtime().and_then(|runtime| {
+        runtime.block_on(async { Ok(open_storage().await?.put(document.clone()).await?) })
+    });
+    match stored {
+        Ok(stored) => Some(stored),
+        Err(err) => {
+            eprintln!("note: could not keep a local copy of {key}: {err:#}");
+            Some(document)
+        }
+    }
+}
+
+/// Look up a live document by `--key` (within `project`) or `--id`, failing when absent.
+/// A key retired by a merge or rename leads to the document it redirects to, with a
+/// notice on stderr so stdout stays parseable.
+async fn fetch_document(
+    storage: &TracedStorage<SqliteStorage>,
+    project: &str,
+    key: &Option<String>,
+    id: &Option<String>,
+) -> Result<Document> {
+    let (document, redirected_to) = resolve_document(storage, project, key, id).await?;
+    if let (Some(key), Some(target)) = (key, redirected_to) {
+        eprintln!("note: key {key} now redirects to {target}");
+    }
+    Ok(document)
+}
+
+/// `fetch_document` without the notice: the document plus the key a redirect led to.
+async fn resolve_document(
+    storage: &TracedStorage<SqliteStorage>,
+    project: &str,
+    key: &Option<String>,
+    id: &Option<String>,
+) -> Result<(Document, Option<String>)> {
+    if let Some(key) = key {
+        if let Some(found) = storage.resolve_key(&project.to_string(), key).await? {
+            return Ok(found);
+        }
+    }
+    Ok((fetch_document_exact(storage, project, key, id).await?, None))
+}
+
+/// Look up a document by exactly the key or id given, without following redirects.
+async fn fetch_document_exact(
+    storage: &TracedStorage<SqliteStorage>,
+    project: &str,
+    key: &Option<String>,
+    id: &Option<String>,
+) -> Result<Document> {
+    let found = match (key, id) {
+        (Some(key), _) => storage.get_by_key(&project.to_string(), key).await?,
+        (None, Some(id)) => storage.get_by_id(&DocumentId(id.clone())).await?,
+        (None, None) => bail!("Provide --key or --id."),
+    };
+    let label = key.as_deref().or(id.as_deref()).unwrap_or_default();
+    found
+        .ok_or_else(|| NotFound(format!("Document {label} not found in project {project}.")).into())
+}
+
+fn handle_cat(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to retrieve content.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let document = runtime.block_on(fetch_document(&storage, &project, &key, &id))?;
+
+    if json_output {
+        let serialized = serde_json::to_string_pretty(&document)?;
+        println!("{serialized}");
+        return Ok(());
+    }
+
+    println!("{}", document.body_markdown);
+    Ok(())
+}
+
+/// One requested key in `cat --keys` JSON output.
+#[derive(Serialize)]
+struct CatEntry {
+    key: String,
+    found: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document: Option<Document>,
+}
+
+/// `cat` with several keys: the bodies in the order asked for, `delimiter` between them.
+/// Missing keys are listed in JSON output, or named on stderr with exit code 3.
+fn handle_cat_keys(
+    project: Option<String>,
+    json_output: bool,
+    keys: Vec<String>,
+    delimiter: &str,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let mut entries = Vec::with_capacity(keys.len());
+    for key in keys {
+        let document = match runtime.block_on(fetch_document(
+            &storage,
+            &project,
+            &Some(key.clone()),
+            &None,
+        )) {
+            Ok(document) => Some(document),
+            Err(err) if err.is::<NotFound>() => None,
+            Err(err) => return Err(err),
+        };
+        entries.push(CatEntry {
+            key,
+            found: document.is_some(),
+            document,
+        });
+    }
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let delimiter = delimiter.replace("\\n", "\n").replace("\\t", "\t");
+    let bodies: Vec<&str> = entries
+        .iter()
+        .filter_map(|entry| entry.document.as_ref())
+        .map(|document| document.body_markdown.as_str())
+        .collect();
+    if !bodies.is_empty() {
+        println!("{}", bodies.join(&delimiter));
+    }
+    let missing: Vec<&str> = entries
+        .iter()
+        .filter(|entry| !entry.found)
+        .map(|entry| entry.key.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(NotFound(format!(
+            "No document at key(s) {} in project {project}",
+            missing.join(", ")
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// A document in JSON output, annotated with its body's token count.
+#[derive(Serialize)]
+struct TokenCounted<'a> {
+    #[serde(flatten)]
+    document: &'a Document,
+    tokens: usize,
+}
+
+/// Output shape for commands that emit a list of documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ListFormat {
+    Text,
+    Json,
+    Ndjson,
+    /// A spreadsheet table of metadata (see `table`); `ls` only.
+    Csv,
+    Tsv,
+}
+
+impl ListFormat {
+    fn delimiter(self) -> Option<table::Delimiter> {
+        match self {
+            ListFormat::Csv => Some(table::Delimiter::Comma),
+            ListFormat::Tsv => Some(table::Delimiter::Tab),
+            _ => None,
+        }
+    }
+}
+
+fn list_format(json_output: bool, format: &str) -> Result<ListFormat> {
+    match format {
+        "ndjson" | "jsonl" => Ok(ListFormat::Ndjson),
+        "json" => Ok(ListFormat::Json),
+        "text" if json_output => Ok(ListFormat::Json),
+        "text" => Ok(ListFormat::Text),
+        "csv" => Ok(ListFormat::Csv),
+        "tsv" => Ok(ListFormat::Tsv),
+        other => bail!("Unsupported format: {other}. Use --format text, json, ndjson, csv, or tsv"),
+    }
+}
+
+/// Fields of a serialized `Document`, accepted by `--fields`.
+const DOCUMENT_FIELDS: &[&str] = &[
+    "id",
+    "project",
+    "key",
+    "namespace",
+    "title",
+    "tags",
+    "metadata",
+    "body_markdown",
+    "created_at",
+    "updated_at",
+    "source",
+    "version",
+    "ttl_seconds",
+    "deleted_at",
+];
+/// Resolve `--fields`/`--key-only` into the fields to keep; `None` keeps them all.
+///
+/// `extra` names fields a command adds on top of the document's own (e.g. `tokens`).
+fn field_selection(
+    format: ListFormat,
+    fields: Vec<String>,
+    key_only: bool,
+    extra: &[&str],
+) -> Result<Option<Vec<String>>> {
+    let fields: Vec<String> = if key_only {
+        vec!["key".to_string()]
+    } else {
+        fields
+            .into_iter()
+            .map(|field| field.trim().to_string())
+            .filter(|field| !field.is_empty())
+            .collect()
+    };
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    if format == ListFormat::Text {
+        bail!("--fields and --key-only shape JSON output; add --json or --format json|ndjson.");
+    }
+    let available: Vec<&str> = if format.delimiter().is_some() {
+        table::COLUMNS.to_vec()
+    } else {
+        DOCUMENT_FIELDS.iter().chain(extra).copied().collect()
+    };
+    if let Some(unknown) = fields
+        .iter()
+        .find(|field| !available.contains(&field.as_str()))
+    {
+        bail!(
+            "Unknown field {unknown:?} for --fields. Available: {}",
+            available.join(", ")
+        );
+    }
+    Ok(Some(fields))
+}
+
+/// Which documents `find`/`ls` leave out, and which of their fields JSON output keeps.
+struct ListSelection {
+    fields: Option<Vec<String>>,
+    not_tags: Vec<String>,
+    exclude_namespaces: Vec<String>,
+    /// Search only keys under this prefix (`find --within`).
+    key_prefix: Option<String>,
+    /// Search only these keys when non-empty (`find --keys`).
+    keys: Vec<String>,
+    /// Attach each hit's score breakdown (`find --explain`).
+    explain: bool,
+    /// Search code blocks only (`find --code`).
+    code: bool,
+    /// Stop the search after this long instead of `query_timeout_secs` (`find --timeout`).
+    timeout: Option<Duration>,
+}
+
+impl ListSelection {
+    fn render<T: Serialize>(&self, value: &T) -> Result<serde_json::Value> {
+        select_fields(value, self.fields.as_deref())
+    }
+}
+
+/// Serialize `value`, keeping only `fields` when a selection was requested.
+fn select_fields<T: Serialize>(value: &T, fields: Option<&[String]>) -> Result<serde_json::Value> {
+    match (fields, serde_json::to_value(value)?) {
+        (Some(fields), serde_json::Value::Object(mut map)) => Ok(serde_json::Value::Object(
+            fields
+                .iter()
+                .filter_map(|field| map.remove_entry(field))
+                .collect(),
+        )),
+        (_, value) => Ok(value),
+    }
+}
+
+/// Write one compact JSON value per line, flushing so downstream pipes see it immediately.
+fn write_ndjson_line<T: Serialize>(out: &mut impl Write, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+fn handle_find(
+    project: Option<String>,
+    format: ListFormat,
+    query: String,
+    limit: Option<usize>,
+    all_projects: bool,
+    max_tokens: Option<usize>,
+    selection: ListSelection,
+) -> Result<()> {
+    if query.trim().is_empty() {
+        bail!("Query cannot be empty.");
+    }
+    if let Some(0) = limit {
+        bail!("Limit must be greater than 0.");
+    }
+
+    let base_project = project.unwrap_or_else(|| "default".to_string());
+    let hits = Backend::open()?.search(
+        SearchQuery {
+            project: (!all_projects).then(|| base_project.clone()),
+            text: query.clone(),
+            limit: Some(limit.unwrap_or(10)),
+            exclude_tags: selection.not_tags.clone(),
+            exclude_namespaces: selection.exclude_namespaces.clone(),
+            key_prefix: selection.key_prefix.clone(),
+            keys: selection.keys.clone(),
+            code: selection.code,
+            visibility: Vec::new(),
+        },
+        selection.timeout,
+    )?;
+    let mut budget_left = max_tokens.unwrap_or(usize::MAX);
+    let documents: Vec<TokenCounted> = hits
+        .iter()
+        .map(|hit| TokenCounted {
+            document: &hit.document,
+            tokens: count_tokens(&hit.document.body_markdown),
+        })
+        .take_while(|counted| match budget_left.checked_sub(counted.tokens) {
+            Some(rest) => {
+                budget_left = rest;
+                true
             }
-            other => {
-                eprintln!("Unsupported format: {other}. Try --format markdown");
-                std::process::exit(2);
+            None => false,
+        })
+        .collect();
+
+    // `documents` is a prefix of `hits`, so the two zip up hit for hit.
+    let render = |(doc, hit): (&TokenCounted, &SearchHit)| -> Result<serde_json::Value> {
+        let mut value = selection.render(doc)?;
+        if selection.fields.is_none() {
+            value["score"] = serde_json::json!(hit.score);
+        }
+        if selection.explain {
+            value["explain"] = serde_json::json!({
+                "score": hit.score,
+                "bm25": hit.breakdown.bm25,
+                "recency": hit.breakdown.recency,
+                "tag_bonus": hit.breakdown.tag_bonus,
+                "pin_boost": hit.breakdown.pin_boost,
+            });
+        }
+        Ok(value)
+    };
+    match format {
+        ListFormat::Ndjson => {
+            let mut out = io::stdout().lock();
+            for pair in documents.iter().zip(&hits) {
+                write_ndjson_line(&mut out, &render(pair)?)?;
             }
-        },
-        Commands::Init => {
-            println!("context init (stub): configuration will be set up here.");
+            return Ok(());
         }
-        Commands::Put { key, file, tags } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?key,
-                ?file,
-                tags = ?tags,
-                "Put command invoked"
-            );
-            handle_put(resolved_project.clone(), json, key, file, tags)?;
+        ListFormat::Json => {
+            let selected = documents
+                .iter()
+                .zip(&hits)
+                .map(render)
+                .collect::<Result<Vec<_>>>()?;
+            println!("{}", serde_json::to_string_pretty(&selected)?);
+            return Ok(());
         }
-        Commands::Get { key, id, format } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?key,
-                ?id,
-                ?format,
-                "Get command invoked"
-            );
-            handle_get(resolved_project.clone(), json, key, id, format)?;
+        ListFormat::Csv | ListFormat::Tsv => {
+            bail!("--format csv|tsv is for ls and export; use --format json or ndjson with find.")
         }
-        Commands::Cat { key, id } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?key,
-                ?id,
-                "Cat command invoked"
-            );
-            handle_cat(resolved_project.clone(), json, key, id)?;
+        ListFormat::Text => {}
+    }
+
+    println!(
+        "Found {} result(s) for '{}' in project {}{}",
+        documents.len(),
+        query,
+        base_project,
+        if all_projects { " (all projects)" } else { "" }
+    );
+    for (
+        idx,
+        (
+            TokenCounted {
+                document: doc,
+                tokens,
+            },
+            hit,
+        ),
+    ) in documents.iter().zip(&hits).enumerate()
+    {
+        println!(
+            "{}. {} [{}] ({tokens} tokens)",
+            idx + 1,
+            doc.id.0,
+            doc.project
+        );
+        if let Some(key) = &doc.key {
+            println!("   Key: {key}");
         }
-        Commands::Find {
-            query,
-            limit,
-            all_projects,
-        } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                %query,
-                ?limit,
-                ?all_projects,
-                "Find command invoked"
-            );
-            handle_find(resolved_project.clone(), json, query, limit, all_projects)?;
+        if let Some(title) = &doc.title {
+            println!("   Title: {title}");
         }
-        Commands::Ls {} => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                "Ls command invoked"
+        if selection.explain {
+            let parts = hit.breakdown;
+            println!(
+                "   Score: {:.3} = bm25 {:.3} + recency {:.3} + tags {:.3} + pin {:.3}",
+                hit.score, parts.bm25, parts.recency, parts.tag_bonus, parts.pin_boost
             );
-            handle_ls(resolved_project.clone(), json)?;
         }
-        Commands::Rm { key, id, force } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?key,
-                ?id,
-                ?force,
-                "Rm command invoked"
-            );
-            handle_rm(resolved_project.clone(), json, key, id, force)?;
+        println!("   {}", doc.body_markdown);
+    }
+
+    Ok(())
+}
+
+fn handle_ls_count(project: Option<String>, format: ListFormat, query: &ListQuery) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let count = runtime.block_on(storage.count(&project, query))?;
+    if !matches!(format, ListFormat::Json | ListFormat::Ndjson) {
+        println!("{count}");
+    } else {
+        let payload = serde_json::json!({ "project": project, "count": count });
+        println!("{}", serde_json::to_string(&payload)?);
+    }
+    Ok(())
+}
+
+fn handle_ls(
+    project: Option<String>,
+    format: ListFormat,
+    selection: ListSelection,
+    query: ListQuery,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+
+    if format == ListFormat::Ndjson && query.limit.is_none() {
+        return runtime.block_on(async {
+            let mut out = io::stdout().lock();
+            let mut documents = storage.list(&project, &query);
+            while let Some(doc) = documents.next().await {
+                write_ndjson_line(&mut out, &selection.render(&doc?)?)?;
+            }
+            Ok(())
+        });
+    }
+    if let (Some(delimiter), None) = (format.delimiter(), query.limit) {
+        return runtime.block_on(async {
+            let mut table =
+                table::TableWriter::new(io::stdout().lock(), delimiter, selection.fields)?;
+            let mut documents = storage.list(&project, &query);
+            while let Some(doc) = documents.next().await {
+                table.write(&doc?)?;
+            }
+            table.finish()
+        });
+    }
+
+    let page = runtime.block_on(storage.list_page(&project, &query))?;
+    match format {
+        ListFormat::Ndjson => {
+            let mut out = io::stdout().lock();
+            for doc in &page.documents {
+                write_ndjson_line(&mut out, &selection.render(doc)?)?;
+            }
+        }
+        ListFormat::Json => {
+            let selected = page
+                .documents
+                .iter()
+                .map(|doc| selection.render(doc))
+                .collect::<Result<Vec<_>>>()?;
+            println!("{}", serde_json::to_string_pretty(&selected)?);
+        }
+        ListFormat::Csv | ListFormat::Tsv => {
+            let delimiter = format.delimiter().expect("csv and tsv have a delimiter");
+            let mut table =
+                table::TableWriter::new(io::stdout().lock(), delimiter, selection.fields.clone())?;
+            for doc in &page.documents {
+                table.write(doc)?;
+            }
+            table.finish()?;
         }
-        Commands::Gc { dry_run } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?dry_run,
-                "Gc command invoked"
-            );
-            handle_gc(resolved_project.clone(), json, dry_run)?;
+        ListFormat::Text => {
+            println!("Documents in project {project}");
+            for doc in &page.documents {
+                let deleted = if doc.deleted_at.is_some() {
+                    " [deleted]"
+                } else {
+                    ""
+                };
+                let expires = doc
+                    .expires_at()
+                    .map(|at| format!(" [expires {}]", at.to_rfc3339()))
+                    .unwrap_or_default();
+                let title = doc
+                    .title
+                    .as_ref()
+                    .map(|title| format!(" {title:?}"))
+                    .unwrap_or_default();
+                let private = if doc.visibility() == Visibility::Private {
+                    " [private]"
+                } else {
+                    ""
+                };
+                println!(
+                    "- {} (Key: {}){title}{private}{deleted}{expires}",
+                    doc.id.0,
+                    doc.key.as_deref().unwrap_or("")
+                );
+            }
         }
-        Commands::Web { port } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?port,
-                "Web command invoked"
-            );
-            handle_web(json, port)?;
+    }
+    // Machine-readable output stays a bare list; the cursor goes to stderr there.
+    if let Some(cursor) = &page.next_cursor {
+        if format == ListFormat::Text {
+            println!("More: context ls --cursor {cursor}");
+        } else {
+            eprintln!("note: more documents follow; pass --cursor {cursor}");
         }
-        Commands::WebDev { port } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?port,
-                "WebDev command invoked"
-            );
-            handle_web_dev(json, port)?;
+    }
+
+    Ok(())
+}
+
+fn handle_web(json_output: bool, port: u16) -> Result<()> {
+    let host = "127.0.0.1";
+    let addr = format!("http://{host}:{port}");
+
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "starting",
+            "host": host,
+            "port": port,
+            "url": addr,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    println!("Starting context web server on {addr} (wrapper).");
+    Ok(())
+}
+
+fn handle_web_dev(json_output: bool, port: u16) -> Result<()> {
+    let host = "127.0.0.1";
+    let addr = format!("http://{host}:{port}");
+
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "starting",
+            "host": host,
+            "port": port,
+            "url": addr,
+            "mode": "dev",
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    println!("Starting context web-dev server on {addr} (wrapper).");
+    Ok(())
+}
+
+fn handle_rm(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+    force: bool,
+    purge: bool,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to delete a document.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+
+    let mode = if force {
+        DeleteMode::Hard
+    } else if purge {
+        DeleteMode::Purge
+    } else {
+        DeleteMode::Soft
+    };
+    // `delete_by_key` doesn't follow redirects: deleting through one would remove the
+    // document the old key was merged into.
+    let deleted = match (&key, &id) {
+        (Some(key), _) => runtime.block_on(storage.delete_by_key(&project, key, mode))?,
+        (None, Some(id)) => {
+            runtime.block_on(storage.delete_by_id(&DocumentId(id.clone()), mode))?
         }
-        Commands::DebugBundle { scenario, out } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                ?scenario,
-                ?out,
-                "DebugBundle command invoked"
-            );
-            let scenario_value = scenario.or_else(|| log_context.scenario_id.map(str::to_string));
-            let bundle_path = create_debug_bundle(scenario_value.clone(), out)?;
-            if json {
-                let payload = serde_json::json!({
-                    "status": "ok",
-                    "path": bundle_path,
-                    "scenario": scenario_value,
-                });
-                println!("{}", serde_json::to_string_pretty(&payload)?);
-            } else {
-                println!("{}", bundle_path.display());
+        (None, None) => bail!("Provide --key or --id to delete a document."),
+    };
+    // Like `rm -f`, --force treats an already-missing document as success.
+    if deleted.is_none() && !force {
+        let label = key.as_deref().or(id.as_deref()).unwrap_or_default();
+        return Err(NotFound(format!("Document {label} not found in project {project}.")).into());
+    }
+
+    if json_output {
+        let payload = serde_json::json!({
+            "status": if deleted.is_some() { "deleted" } else { "missing" },
+            "project": project,
+            "id": deleted.as_ref().map(|doc| doc.id.0.clone()).or(id),
+            "key": key,
+            "force": force,
+            "mode": mode,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    match deleted {
+        Some(doc) => {
+            match mode {
+                DeleteMode::Hard => {
+                    println!(
+                        "Permanently deleted document {} in project {project}",
+                        doc.id.0
+                    )
+                }
+                DeleteMode::Purge => println!(
+                    "Deleted document {} in project {project} and erased its earlier versions",
+                    doc.id.0
+                ),
+                DeleteMode::Soft => println!("Deleted document {} in project {project}", doc.id.0),
+            }
+            if let Some(key) = &doc.key {
+                println!("Key: {key}");
+            }
+        }
+        None => println!(
+            "Nothing to delete in project {project}: {} does not exist",
+            key.as_deref().or(id.as_deref()).unwrap_or_default()
+        ),
+    }
+
+    Ok(())
+}
+
+fn handle_restore(
+    project: Option<String>,
+    json_output: bool,
+    key: Option<String>,
+    id: Option<String>,
+) -> Result<()> {
+    if key.is_none() && id.is_none() {
+        bail!("Provide --key or --id to restore a document.");
+    }
+    if key.is_some() && id.is_some() {
+        bail!("Provide only one of --key or --id.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let id = match (&key, id) {
+        (Some(key), _) => runtime
+            .block_on(storage.deleted_by_key(&project, key))?
+            .map(|tombstone| tombstone.id),
+        (None, id) => id.map(DocumentId),
+    };
+    let restored = match &id {
+        Some(id) => runtime.block_on(storage.restore(id))?,
+        None => None,
+    };
+    let Some(doc) = restored else {
+        let label = key.or(id.map(|id| id.0)).unwrap_or_default();
+        return Err(NotFound(format!(
+            "No deleted document {label} in project {project}; `context ls --deleted` lists them."
+        ))
+        .into());
+    };
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+    } else {
+        println!(
+            "Restored {} in project {project} (v{})",
+            document_label(&doc),
+            doc.version
+        );
+    }
+    Ok(())
+}
+
+fn handle_gc(
+    project: Option<String>,
+    json_output: bool,
+    dry_run: bool,
+    check_only: bool,
+    retention: Option<String>,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let read_only = dry_run || check_only;
+    let retention = match retention.or(load_project_config()?.tombstone_retention) {
+        Some(span) => parse_duration(&span)?,
+        None => chrono::Duration::zero(),
+    };
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let report = runtime.block_on(gc::collect(
+        storage.untraced(),
+        &project,
+        GcOptions {
+            retention,
+            dry_run: read_only,
+        },
+        Utc::now(),
+    ))?;
+    let consistency = report.consistency;
+
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "project": project,
+            "dry_run": dry_run,
+            "check_only": check_only,
+            "retention_seconds": retention.num_seconds(),
+            "expired": report.expired,
+            "deleted": report.tombstones,
+            "reindexed": report.reindexed,
+            "vacuumed": report.vacuumed,
+            "analyzed": report.analyzed,
+            "consistency": consistency,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    if check_only {
+        println!("Consistency check (no changes made)");
+    } else {
+        println!("Garbage collection complete for project {project}");
+    }
+    if read_only {
+        println!("Expired documents to purge: {}", report.expired);
+        println!("Deleted documents to purge: {}", report.tombstones);
+        println!("Documents to reindex: {}", report.reindexed);
+    } else {
+        println!("Expired documents purged: {}", report.expired);
+        println!("Deleted documents purged: {}", report.tombstones);
+        println!("Documents reindexed: {}", report.reindexed);
+    }
+    let verb = if consistency.repaired {
+        "removed"
+    } else {
+        "found"
+    };
+    println!(
+        "Orphaned FTS rows {verb}: {}",
+        consistency.orphaned_fts_rows
+    );
+    println!(
+        "Orphaned version rows {verb}: {}",
+        consistency.orphaned_versions
+    );
+    if dry_run {
+        println!("dry-run (no changes made)");
+    } else if !check_only {
+        println!("vacuumed");
+    }
+    if let Some(analyzed) = report.analyzed {
+        println!("Query statistics refreshed for {analyzed} table(s) and index(es)");
+    }
+    Ok(())
+}
+
+fn handle_db_analyze(json_output: bool) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let started = Instant::now();
+    let analyzed = runtime.block_on(storage.analyze())?;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "analyzed": analyzed,
+            "duration_ms": duration_ms,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "Query statistics refreshed for {analyzed} table(s) and index(es) in {duration_ms} ms"
+        );
+    }
+    Ok(())
+}
+
+fn handle_scenario_start(json_output: bool, label: Option<String>) -> Result<()> {
+    let (started, replaced) = scenario::start(label.as_deref())?;
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "scenario_id": started.id,
+            "started_at": started.started_at,
+            "replaced": replaced.map(|scenario| scenario.id),
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        if let Some(replaced) = replaced {
+            eprintln!("Ended scenario {}", replaced.id);
+        }
+        eprintln!("Started scenario {}", started.id);
+        println!("export CONTEXT_SCENARIO={}", started.id);
+    }
+    Ok(())
+}
+
+fn handle_scenario_end(json_output: bool) -> Result<()> {
+    let (ended, ended_at) = scenario::end()?;
+    let duration_ms = (ended_at - ended.started_at).num_milliseconds();
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "scenario_id": ended.id,
+            "started_at": ended.started_at,
+            "ended_at": ended_at,
+            "duration_ms": duration_ms,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        eprintln!("Ended scenario {} after {duration_ms} ms", ended.id);
+        println!("unset CONTEXT_SCENARIO");
+    }
+    Ok(())
+}
+
+fn handle_pack(
+    project: Option<String>,
+    json_output: bool,
+    query: Option<String>,
+    keys: Vec<String>,
+    budget: usize,
+    limit: usize,
+) -> Result<()> {
+    let keys: Vec<String> = keys
+        .into_iter()
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .collect();
+    if query.is_none() && keys.is_empty() {
+        bail!("Provide --query and/or --key to pack documents.");
+    }
+    if budget == 0 || limit == 0 {
+        bail!("Budget and limit must be greater than 0.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_snapshot())?;
+
+    let mut documents = Vec::new();
+    for key in &keys {
+        let doc = runtime.block_on(fetch_document(
+            &storage,
+            &project,
+            &Some(key.clone()),
+            &None,
+        ))?;
+        documents.push(doc);
+    }
+    if let Some(text) = &query {
+        let hits = runtime.block_on(storage.search(SearchQuery {
+            project: Some(project.clone()),
+            text: text.clone(),
+            limit: Some(limit),
+            ..Default::default()
+        }))?;
+        documents.extend(hits.into_iter().map(|hit| hit.document));
+    }
+
+    let packed = pack(documents, budget);
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&packed)?);
+        return Ok(());
+    }
+
+    print!("{}", packed.markdown);
+    eprintln!(
+        "Packed {} document(s), ~{} of {} tokens ({} skipped for budget)",
+        packed.included.len(),
+        packed.tokens,
+        packed.budget,
+        packed.skipped.len()
+    );
+    Ok(())
+}
+
+/// What `context summarize` should condense: one document, or the hits for a query.
+struct SummarizeTarget {
+    key: Option<String>,
+    id: Option<String>,
+    query: Option<String>,
+    limit: Option<usize>,
+}
+
+fn handle_summarize(
+    project: Option<String>,
+    json_output: bool,
+    target: SummarizeTarget,
+    out_key: Option<String>,
+) -> Result<()> {
+    let SummarizeTarget {
+        key,
+        id,
+        query,
+        limit,
+    } = target;
+    let selectors = [key.is_some(), id.is_some(), query.is_some()];
+    match selectors.iter().filter(|set| **set).count() {
+        0 => bail!("Provide --key, --id, or --query to summarize."),
+        1 => {}
+        _ => bail!("Provide only one of --key, --id, or --query."),
+    }
+    if let Some(0) = limit {
+        bail!("Limit must be greater than 0.");
+    }
+
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let config = load_project_config()?;
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+
+    let (label, sources) = match &query {
+        Some(text) => {
+            let hits = runtime.block_on(storage.search(SearchQuery {
+                project: Some(project.clone()),
+                text: text.clone(),
+                limit: Some(limit.unwrap_or(5)),
+                ..Default::default()
+            }))?;
+            if hits.is_empty() {
+                return Err(
+                    NotFound(format!("No documents match '{text}' in project {project}.")).into(),
+                );
             }
+            let docs: Vec<Document> = hits.into_iter().map(|hit| hit.document).collect();
+            (format!("search '{text}'"), docs)
         }
-        Commands::AgentConfig { target } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                %target,
-                "AgentConfig command invoked"
-            );
-            eprintln!("TODO: implement `context agent-config`");
-        }
-        Commands::Project { action } => {
-            tracing::info!(
-                scenario_id = log_context.scenario_id,
-                project = log_context.project,
-                command = log_context.command,
-                "Project command invoked"
-            );
-            match action {
-                ProjectCommands::Current => handle_project_current(json, project)?,
-                ProjectCommands::Set {
-                    project: new_project,
-                } => handle_project_set(json, new_project)?,
-                ProjectCommands::List => handle_project_list(json)?,
-            }
+        None => {
+            let doc = runtime.block_on(fetch_document(&storage, &project, &key, &id))?;
+            (document_label(&doc), vec![doc])
         }
+    };
+
+    let material: String = sources
+        .iter()
+        .map(|doc| format!("## {}\n\n{}\n\n", document_label(doc), doc.body_markdown))
+        .collect();
+    let summary = summarize::summarizer(&config.summarize)?.summarize(&material)?;
+
+    let out_key = out_key.or_else(|| match (&query, &sources[0].key) {
+        (None, Some(source_key)) => Some(format!("summary/{source_key}")),
+        _ => None,
+    });
+    let footer: Vec<String> = sources
+        .iter()
+        .map(|doc| format!("`{}` (v{})", document_label(doc), doc.version))
+        .collect();
+    let body = format!(
+        "# Summary of {label}\n\n{summary}\n\n---\nSources: {}\n",
+        footer.join(", ")
+    );
+    let mut tags = vec!["summary".to_string()];
+    tags.extend(sources.iter().map(|doc| format!("summary-of:{}", doc.id.0)));
+
+    let mut draft = new_document(project, out_key, tags, body, SourceType::Agent);
+    draft.title = Some(format!("Summary of {label}"));
+    let stored = runtime.block_on(storage.store(draft))?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&stored)?);
+        return Ok(());
     }
 
+    println!(
+        "Stored summary {} in project {}",
+        stored.id.0, stored.project
+    );
+    if let Some(key) = &stored.key {
+        println!("Key: {key}");
+    }
+    println!();
+    println!("{summary}");
     Ok(())
 }
 
-fn handle_put(
+fn handle_digest(project: Option<String>, json_output: bool, since: String) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let now = Utc::now();
+    let from = parse_since(&since, now)?;
+
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let Digest {
+        document: stored,
+        covered,
+    } = runtime.block_on(write_digest(storage.untraced(), &project, from, now))?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&stored)?);
+        return Ok(());
+    }
+
+    println!(
+        "Stored digest of {} document(s) as {} in project {}",
+        covered,
+        stored.key.as_deref().unwrap_or_default(),
+        stored.project
+    );
+    Ok(())
+}
+
+fn handle_todos(json_output: bool, query: ItemQuery) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let items = runtime.block_on(storage.items(&query))?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+    if items.is_empty() {
+        println!("No items found.");
+        return Ok(());
+    }
+    for entry in &items {
+        let marker = match (entry.item.kind, entry.item.done) {
+            (ItemKind::Decision, _) => "*  ",
+            (_, true) => "[x]",
+            (_, false) => "[ ]",
+        };
+        let label = entry.key.as_deref().unwrap_or(&entry.document_id);
+        let location = if query.project.is_none() {
+            format!("{}/{label}:{}", entry.project, entry.item.line)
+        } else {
+            format!("{label}:{}", entry.item.line)
+        };
+        println!("{marker} {location}  {}", entry.item.text);
+    }
+    Ok(())
+}
+
+fn handle_remind(
     project: Option<String>,
     json_output: bool,
     key: Option<String>,
-    file: Option<PathBuf>,
-    tags: Vec<String>,
+    id: Option<String>,
+    at: String,
+    note: Option<String>,
 ) -> Result<()> {
     let project = project.unwrap_or_else(|| "default".to_string());
-    let tags: Vec<String> = tags
-        .into_iter()
-        .map(|tag| tag.trim().to_string())
-        .filter(|tag| !tag.is_empty())
-        .collect();
-    let body = read_body(file)?;
-    let now = Utc::now();
-
-    let document = Document {
-        id: DocumentId(Uuid::new_v4().to_string()),
-        project,
-        key,
-        namespace: None,
-        title: None,
-        tags,
-        body_markdown: body,
-        created_at: now,
-        updated_at: now,
-        source: SourceType::User,
-        version: 1,
-        ttl_seconds: None,
-        deleted_at: None,
-    };
+    let remind_at = parse_timestamp(at.trim())?;
+    let note = note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let document = runtime.block_on(fetch_document(&storage, &project, &key, &id))?;
+    let reminder = runtime.block_on(storage.add_reminder(&document.id, remind_at, note))?;
 
     if json_output {
-        let serialized = serde_json::to_string_pretty(&document)?;
-        println!("{serialized}");
+        println!("{}", serde_json::to_string_pretty(&reminder)?);
     } else {
         println!(
-            "Stored document {} in project {}",
-            document.id.0, document.project
+            "Reminder {} set for {} on {}",
+            reminder.id,
+            reminder.remind_at.to_rfc3339(),
+            document_label(&document)
         );
-        if let Some(key) = &document.key {
-            println!("Key: {key}");
+    }
+    Ok(())
+}
+
+fn handle_reminders(
+    project: Option<String>,
+    json_output: bool,
+    action: RemindersCommands,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+
+    let query = match action {
+        RemindersCommands::Due { all_projects } => ReminderQuery {
+            project: (!all_projects).then_some(project),
+            due_by: Some(Utc::now()),
+            ..ReminderQuery::default()
+        },
+        RemindersCommands::List { all_projects, done } => ReminderQuery {
+            project: (!all_projects).then_some(project),
+            include_done: done,
+            ..ReminderQuery::default()
+        },
+        RemindersCommands::Done { id } => {
+            if !runtime.block_on(storage.complete_reminder(id))? {
+                return Err(NotFound(format!("No pending reminder with id {id}.")).into());
+            }
+            if json_output {
+                println!("{}", serde_json::json!({ "status": "done", "id": id }));
+            } else {
+                println!("Reminder {id} marked done");
+            }
+            return Ok(());
         }
-        if !document.tags.is_empty() {
-            println!("Tags: {}", document.tags.join(", "));
+    };
+    let reminders = runtime.block_on(storage.reminders(&query))?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&reminders)?);
+        return Ok(());
+    }
+    if reminders.is_empty() {
+        println!("No reminders found.");
+        return Ok(());
+    }
+    for reminder in &reminders {
+        let marker = if reminder.done_at.is_some() {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let label = reminder.key.as_deref().unwrap_or(&reminder.document_id);
+        let location = if query.project.is_none() {
+            format!("{}/{label}", reminder.project)
+        } else {
+            label.to_string()
+        };
+        let when = reminder.remind_at.format("%Y-%m-%d %H:%M");
+        match &reminder.note {
+            Some(note) => println!("{marker} #{} {when}  {location}  {note}", reminder.id),
+            None => println!("{marker} #{} {when}  {location}", reminder.id),
         }
     }
+    Ok(())
+}
+
+fn handle_calendar(project: Option<String>, json_output: bool, out: Option<PathBuf>) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let events = runtime.block_on(upcoming_events(storage.untraced(), project.as_ref()))?;
+    let ics = to_ics(&events, Utc::now());
 
+    let Some(path) = out else {
+        if json_output {
+            println!("{}", serde_json::to_string_pretty(&events)?);
+        } else {
+            print!("{ics}");
+        }
+        return Ok(());
+    };
+    fs::write(&path, ics)
+        .with_context(|| format!("Failed to write calendar to {}", path.display()))?;
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "path": path,
+            "events": events.len(),
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!("Wrote {} event(s) to {}", events.len(), path.display());
+    }
     Ok(())
 }
 
-fn handle_get(
+fn handle_notify_test(project: Option<String>, json_output: bool, message: String) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let config = load_project_config()?.notify;
+    let channels = config.channels_for(&project).len();
+    if channels == 0 {
+        bail!("No notify channels configured for project {project} in config.json.");
+    }
+    let delivered = notify::broadcast(
+        &config,
+        &Notification {
+            project: project.clone(),
+            title: format!("context: {project}"),
+            body: message,
+        },
+    );
+    if json_output {
+        let payload = serde_json::json!({
+            "project": project,
+            "channels": channels,
+            "delivered": delivered,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "Delivered through {} of {channels} channel(s): {}",
+            delivered.len(),
+            delivered.join(", ")
+        );
+    }
+    if delivered.len() < channels {
+        bail!(
+            "{} of {channels} notify channel(s) failed; see the log for details.",
+            channels - delivered.len()
+        );
+    }
+    Ok(())
+}
+
+/// Human-facing name for a document: its key when it has one, otherwise its id.
+fn document_label(doc: &Document) -> String {
+    doc.key.clone().unwrap_or_else(|| doc.id.0.clone())
+}
+
+/// `keys rename` and `mv`: rename `from` to `to`, leaving a redirect when `alias`.
+fn handle_rename(
     project: Option<String>,
     json_output: bool,
-    key: Option<String>,
-    id: Option<String>,
-    format: String,
+    from: String,
+    to: String,
+    alias: bool,
 ) -> Result<()> {
-    if key.is_none() && id.is_none() {
-        bail!("Provide --key or --id to retrieve a document.");
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let document = runtime.block_on(fetch_document_exact(
+        &storage,
+        &project,
+        &Some(from.clone()),
+        &None,
+    ))?;
+    let renamed = runtime.block_on(storage.rename_key(document, &to, alias))?;
+    let to = renamed.key.clone().unwrap_or_default();
+
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "renamed",
+            "project": project,
+            "id": renamed.id.0,
+            "from": from,
+            "to": to,
+            "alias": alias,
+            "version": renamed.version,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else if alias {
+        println!("Renamed {from} -> {to} in project {project} (old key redirects)");
+    } else {
+        println!("Renamed {from} -> {to} in project {project} (old key freed)");
     }
-    if key.is_some() && id.is_some() {
-        bail!("Provide only one of --key or --id.");
+    Ok(())
+}
+
+fn handle_keys_redirects(project: Option<String>, json_output: bool) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let redirects = runtime.block_on(storage.redirects(&project))?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&redirects)?);
+        return Ok(());
+    }
+    println!("Key redirects in project {project}");
+    for redirect in &redirects {
+        println!("- {} -> {}", redirect.from, redirect.to);
     }
+    Ok(())
+}
 
+fn handle_keys_normalize(project: Option<String>, json_output: bool, dry_run: bool) -> Result<()> {
     let project = project.unwrap_or_else(|| "default".to_string());
-    let now = Utc::now();
-    let doc_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
-    let body = match &key {
-        Some(key) => format!("Retrieved document for key {key}"),
-        None => format!("Retrieved document {doc_id}"),
-    };
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let report = runtime.block_on(storage.normalize_existing_keys(&project, dry_run))?;
 
-    let document = Document {
-        id: DocumentId(doc_id),
-        project,
-        key,
-        namespace: None,
-        title: None,
-        tags: Vec::new(),
-        body_markdown: body,
-        created_at: now,
-        updated_at: now,
-        source: SourceType::System,
-        version: 1,
-        ttl_seconds: None,
-        deleted_at: None,
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "project": project,
+            "dry_run": dry_run,
+            "renamed": report.renamed,
+            "collisions": report.collisions,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    let verb = if dry_run { "Would rename" } else { "Renamed" };
+    println!(
+        "{verb} {} key(s) in project {project}",
+        report.renamed.len()
+    );
+    for rename in &report.renamed {
+        println!("- {} -> {}", rename.from, rename.to);
+    }
+    if !report.collisions.is_empty() {
+        println!("Collisions (left unchanged):");
+        for collision in &report.collisions {
+            println!("- {}: {}", collision.normalized, collision.keys.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn handle_ns(project: Option<String>, json_output: bool, action: NsCommands) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+
+    let payload = match action {
+        NsCommands::List => {
+            let namespaces = runtime.block_on(storage.namespaces(&project))?;
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&namespaces)?);
+                return Ok(());
+            }
+            println!("Namespaces in project {project}");
+            for ns in &namespaces {
+                println!("- {} ({} document(s))", ns.name, ns.documents);
+            }
+            return Ok(());
+        }
+        NsCommands::Create { name } => {
+            let name = name.trim().to_string();
+            let created = runtime.block_on(storage.create_namespace(&project, &name))?;
+            if !json_output {
+                if created {
+                    println!("Created namespace {name} in project {project}");
+                } else {
+                    println!("Namespace {name} already exists in project {project}");
+                }
+            }
+            serde_json::json!({
+                "status": if created { "created" } else { "exists" },
+                "project": project,
+                "namespace": name,
+            })
+        }
+        NsCommands::Rename { from, to } => {
+            let to = to.trim().to_string();
+            let moved = runtime.block_on(storage.rename_namespace(&project, &from, &to))?;
+            if !json_output {
+                println!(
+                    "Renamed namespace {from} to {to} in project {project} ({moved} document(s) moved)"
+                );
+            }
+            serde_json::json!({
+                "status": "renamed",
+                "project": project,
+                "from": from,
+                "to": to,
+                "documents": moved,
+            })
+        }
+        NsCommands::Rm { name, force } => {
+            let cleared = runtime.block_on(storage.remove_namespace(&project, &name, force))?;
+            if !json_output {
+                println!("Removed namespace {name} from project {project}");
+                if cleared > 0 {
+                    println!("Cleared it from {cleared} document(s).");
+                }
+            }
+            serde_json::json!({
+                "status": "removed",
+                "project": project,
+                "namespace": name,
+                "documents": cleared,
+            })
+        }
     };
 
     if json_output {
-        let serialized = serde_json::to_string_pretty(&document)?;
-        println!("{serialized}");
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    }
+    Ok(())
+}
+
+fn handle_policy_apply(project: Option<String>, json_output: bool, dry_run: bool) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let changes = runtime.block_on(storage.apply_expiry_policy(&project, dry_run))?;
+
+    if json_output {
+        let payload = serde_json::json!({
+            "status": "ok",
+            "project": project,
+            "dry_run": dry_run,
+            "changed": changes,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
         return Ok(());
     }
 
-    match format.as_str() {
-        "markdown" | "md" => {
-            println!("Project: {}", document.project);
-            println!("Document ID: {}", document.id.0);
-            if let Some(key) = &document.key {
-                println!("Key: {key}");
-            }
-            println!();
-            println!("{}", document.body_markdown);
-        }
-        other => {
-            bail!("Unsupported format: {other}. Use --format markdown or --json");
-        }
+    let verb = if dry_run { "Would set" } else { "Set" };
+    println!(
+        "{verb} expiry on {} document(s) in project {project}",
+        changes.len()
+    );
+    for change in &changes {
+        let label = change.key.as_deref().unwrap_or(&change.id);
+        println!("- {label}: ttl {}s", change.ttl_seconds);
     }
-
     Ok(())
 }
 
-fn handle_cat(
+fn handle_fixtures(
     project: Option<String>,
     json_output: bool,
-    key: Option<String>,
-    id: Option<String>,
+    action: FixturesCommands,
 ) -> Result<()> {
-    if key.is_none() && id.is_none() {
-        bail!("Provide --key or --id to retrieve content.");
-    }
-    if key.is_some() && id.is_some() {
-        bail!("Provide only one of --key or --id.");
-    }
-
-    let project = project.unwrap_or_else(|| "default".to_string());
-    let now = Utc::now();
-    let doc_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
-    let body = match &key {
-        Some(key) => format!("Content for key {key}"),
-        None => format!("Content for document {doc_id}"),
+    let (name, seed) = match action {
+        FixturesCommands::List => {
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(FIXTURES)?);
+            } else {
+                for fixture in FIXTURES {
+                    println!(
+                        "{:<6} {:>5} docs  {}",
+                        fixture.name, fixture.documents, fixture.description
+                    );
+                }
+            }
+            return Ok(());
+        }
+        FixturesCommands::Load { name, seed } => (name, seed),
     };
 
-    let document = Document {
-        id: DocumentId(doc_id),
-        project,
-        key,
-        namespace: None,
-        title: None,
-        tags: Vec::new(),
-        body_markdown: body,
-        created_at: now,
-        updated_at: now,
-        source: SourceType::System,
-        version: 1,
-        ttl_seconds: None,
-        deleted_at: None,
-    };
+    let fixture = fixture(&name)?;
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let report = runtime.block_on(fixtures::load(&storage, fixture, &project, seed))?;
 
     if json_output {
-        let serialized = serde_json::to_string_pretty(&document)?;
-        println!("{serialized}");
-        return Ok(());
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "Loaded fixture {} (seed {}) into project {}: {} document(s), {} revision(s)",
+            report.fixture, report.seed, report.project, report.documents, report.revisions
+        );
     }
-
-    println!("{}", document.body_markdown);
     Ok(())
 }
 
-fn handle_find(
+fn handle_dedup_report(
     project: Option<String>,
     json_output: bool,
-    query: String,
-    limit: Option<usize>,
-    all_projects: bool,
+    threshold: f32,
+    merge: Option<String>,
 ) -> Result<()> {
-    if query.trim().is_empty() {
-        bail!("Query cannot be empty.");
+    if !(0.0..=1.0).contains(&threshold) {
+        bail!("--threshold must be between 0.0 and 1.0.");
     }
-    if let Some(0) = limit {
-        bail!("Limit must be greater than 0.");
+    if let Some(strategy) = merge
+        .as_deref()
+        .filter(|strategy| *strategy != "keep-newest")
+    {
+        bail!("Unsupported merge strategy: {strategy}. Use --merge keep-newest");
     }
 
-    let count = limit.unwrap_or(3);
-    let base_project = project.unwrap_or_else(|| "default".to_string());
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let documents: Vec<Document> =
+        runtime.block_on(storage.stream_project(&project).try_collect())?;
+    let groups = find_duplicates(&documents, threshold);
 
-    let mut documents = Vec::with_capacity(count);
-    for i in 0..count {
+    let mut removed = Vec::new();
+    let mut redirects = Vec::new();
+    if merge.is_some() {
         let now = Utc::now();
-        let doc_project = if all_projects {
-            format!("project-{i}")
-        } else {
-            base_project.clone()
-        };
-        let doc_id = Uuid::new_v4().to_string();
-        let body = format!("Result {} for '{}'", i + 1, query);
-        let key = Some(format!("hit-{}", i + 1));
-
-        documents.push(Document {
-            id: DocumentId(doc_id),
-            project: doc_project,
-            key,
-            namespace: None,
-            title: None,
-            tags: Vec::new(),
-            body_markdown: body,
-            created_at: now,
-            updated_at: now,
-            source: SourceType::System,
-            version: 1,
-            ttl_seconds: None,
-            deleted_at: None,
-        });
+        for group in &groups {
+            let kept = &group.members[0];
+            for member in &group.members[1..] {
+                let Some(document) = documents.iter().find(|doc| doc.id.0 == member.id) else {
+                    continue;
+                };
+                runtime.block_on(storage.put(Document {
+                    updated_at: now,
+                    deleted_at: Some(now),
+                    version: document.version + 1,
+                    ..document.clone()
+                }))?;
+                removed.push(member.id.clone());
+                if let (Some(from), Some(to)) = (&member.key, &kept.key) {
+                    runtime.block_on(storage.record_redirect(&project, from, to))?;
+                    redirects.push(KeyRedirect {
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                }
+            }
+        }
     }
 
     if json_output {
-        let serialized = serde_json::to_string_pretty(&documents)?;
-        println!("{serialized}");
+        let payload = serde_json::json!({
+            "project": project,
+            "threshold": threshold,
+            "groups": groups,
+            "merge": merge,
+            "removed": removed,
+            "redirects": redirects,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
         return Ok(());
     }
 
     println!(
-        "Found {} result(s) for '{}' in project {}{}",
-        documents.len(),
-        query,
-        base_project,
-        if all_projects { " (all projects)" } else { "" }
+        "{} duplicate group(s) in project {project} (threshold {threshold})",
+        groups.len()
     );
-    for (idx, doc) in documents.iter().enumerate() {
-        println!("{}. {} [{}]", idx + 1, doc.id.0, doc.project);
-        if let Some(key) = &doc.key {
-            println!("   Key: {key}");
+    for (idx, group) in groups.iter().enumerate() {
+        let kind = if group.exact { "exact" } else { "near" };
+        println!("{}. {kind}, {} documents", idx + 1, group.members.len());
+        for (position, member) in group.members.iter().enumerate() {
+            let label = member.key.as_deref().unwrap_or(&member.id);
+            let role = if position == 0 { " (newest)" } else { "" };
+            println!(
+                "   - {label} v{} {:.0}%{role}",
+                member.version,
+                member.similarity * 100.0
+            );
+        }
+    }
+    if merge.is_some() {
+        println!(
+            "Merged: removed {} document(s), recorded {} redirect(s)",
+            removed.len(),
+            redirects.len()
+        );
+        for redirect in &redirects {
+            println!("- {} -> {}", redirect.from, redirect.to);
         }
-        println!("   {}", doc.body_markdown);
     }
-
     Ok(())
 }
 
-fn handle_ls(project: Option<String>, json_output: bool) -> Result<()> {
-    let project = project.unwrap_or_else(|| "default".to_string());
-    let now = Utc::now();
-    let mut documents = Vec::new();
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProjectConfig {
+    current: Option<String>,
+    /// Project names from before the registry moved into the database. Read once,
+    /// registered in the `projects` table, and dropped from config.json; exports
+    /// fill it from the registry so another machine can import the list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    known: Vec<String>,
+    #[serde(flatten)]
+    store: StoreConfig,
+    #[serde(default)]
+    summarize: SummarizeConfig,
+    /// Short names accepted anywhere a project is, mapped to the real project name.
+    #[serde(default)]
+    aliases: BTreeMap<String, String>,
+    /// Maintenance jobs `context web` runs on a schedule, e.g. `[{"kind": "backup", "every": "1d"}]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    jobs: Vec<JobSpec>,
+    /// Tracing filter for `context daemon` and `context web`, e.g. `context_cli=debug`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_filter: Option<String>,
+    /// Record the git branch, commit, and dirty flag of the working directory on every
+    /// command's log span (default true); false skips running git.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log_git: Option<bool>,
+    /// How long `gc` keeps deleted documents restorable, e.g. `30d` (default: it purges
+    /// them all).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tombstone_retention: Option<String>,
+    /// Slack and email channels for alerts such as due reminders.
+    #[serde(default, skip_serializing_if = "NotifyConfig::is_empty")]
+    notify: NotifyConfig,
+    /// Server `get` falls back to for keys missing locally.
+    #[serde(default, skip_serializing_if = "RemoteConfig::is_unset")]
+    remote: RemoteConfig,
+    /// Limits past which commands suggest `context gc`.
+    #[serde(default, skip_serializing_if = "WarningConfig::is_default")]
+    warnings: WarningConfig,
+}
 
-    for i in 1..=3 {
-        let id = Uuid::new_v4().to_string();
-        let key = format!("doc-{i}");
-        let body = format!("This is listed document {i} in {project}");
-        documents.push(Document {
-            id: DocumentId(id),
-            project: project.clone(),
-            key: Some(key.clone()),
-            namespace: None,
-            title: None,
-            tags: Vec::new(),
-            body_markdown: body,
-            created_at: now,
-            updated_at: now,
-            source: SourceType::System,
-            version: 1,
-            ttl_seconds: None,
-            deleted_at: None,
-        });
+impl ProjectConfig {
+    /// The project an alias stands for, or `name` itself when it is not an alias.
+    fn resolve_alias(&self, name: &str) -> String {
+        self.aliases
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
     }
+}
 
-    if json_output {
-        let serialized = serde_json::to_string_pretty(&documents)?;
-        println!("{serialized}");
-        return Ok(());
+fn handle_init(
+    json_output: bool,
+    project_arg: Option<String>,
+    force: bool,
+    agent_config: Option<String>,
+) -> Result<()> {
+    let home = context_home()?;
+    let config_path = project_config_path()?;
+    let replaced = config_path.exists();
+    if replaced && !force {
+        bail!(
+            "context is already initialized in {} ({} exists); pass --force to start over.",
+            home.display(),
+            config_path.display()
+        );
     }
 
-    println!("Documents in project {project}");
-    for doc in &documents {
-        println!("- {} (Key: {})", doc.id.0, doc.key.as_deref().unwrap_or(""));
+    let workspace = env::current_dir()?;
+    let repo_root = git_toplevel(&workspace);
+    let project = match project_arg {
+        Some(project) => project.trim().to_string(),
+        None => repo_root
+            .as_deref()
+            .unwrap_or(&workspace)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "default".to_string()),
+    };
+    if project.is_empty() {
+        bail!("Project name cannot be empty.");
     }
 
-    Ok(())
-}
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    runtime.block_on(storage.register_project(&project))?;
+    let lock = lock_project_config()?;
+    let config = ProjectConfig {
+        current: Some(project.clone()),
+        ..ProjectConfig::default()
+    };
+    save_project_config(&lock, &config)?;
 
-fn handle_web(json_output: bool, port: u16) -> Result<()> {
-    let host = "127.0.0.1";
-    let addr = format!("http://{host}:{port}");
+    let agent_files = match agent_config {
+        Some(target) => {
+            write_agent_configs(&target, repo_root.as_deref().unwrap_or(&workspace), force)?
+        }
+        None => Vec::new(),
+    };
 
     if json_output {
         let payload = serde_json::json!({
-            "status": "starting",
-            "host": host,
-            "port": port,
-            "url": addr,
+            "status": "ok",
+            "project": project,
+            "home": home,
+            "db_path": db_path()?,
+            "config_path": config_path,
+            "replaced_config": replaced,
+            "agent_files": agent_files,
         });
         println!("{}", serde_json::to_string_pretty(&payload)?);
-        return Ok(());
+    } else {
+        println!("Initialized context in {}", home.display());
+        println!("  project: {project}");
+        println!("  database: {}", db_path()?.display());
+        println!("  config: {}", config_path.display());
+        if replaced {
+            println!("  previous config saved as config.json.bak");
+        }
+        for file in &agent_files {
+            println!("  wrote {}", file.display());
+        }
     }
-
-    println!("Starting context web server on {addr} (wrapper).");
     Ok(())
 }
 
-fn handle_web_dev(json_output: bool, port: u16) -> Result<()> {
-    let host = "127.0.0.1";
-    let addr = format!("http://{host}:{port}");
-
-    if json_output {
-        let payload = serde_json::json!({
-            "status": "starting",
-            "host": host,
-            "port": port,
-            "url": addr,
-            "mode": "dev",
-        });
-        println!("{}", serde_json::to_string_pretty(&payload)?);
-        return Ok(());
+fn git_toplevel(dir: &Path) -> Option<PathBuf> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
     }
+    let top = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!top.is_empty()).then(|| PathBuf::from(top))
+}
 
-    println!("Starting context web-dev server on {addr} (wrapper).");
-    Ok(())
+/// Write the agent guide where each tool looks for instructions, returning the files
+/// written. Existing files are left alone unless `force` is set.
+fn write_agent_configs(target: &str, dir: &Path, force: bool) -> Result<Vec<PathBuf>> {
+    let files: &[&str] = match target {
+        "all" => &["AGENTS.md", "CLAUDE.md", ".github/copilot-instructions.md"],
+        "codex" => &["AGENTS.md"],
+        "claude" => &["CLAUDE.md"],
+        "copilot" => &[".github/copilot-instructions.md"],
+        other => {
+            bail!("Unknown agent config target {other:?}; use all, codex, claude, or copilot.")
+        }
+    };
+    let mut written = Vec::new();
+    for file in files {
+        let path = dir.join(file);
+        if path.exists() && !force {
+            eprintln!(
+                "Keeping existing {}; pass --force to overwrite.",
+                path.display()
+            );
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, context_agent::agent_doc_markdown())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
 }
 
-fn handle_rm(
-    project: Option<String>,
-    json_output: bool,
-    key: Option<String>,
-    id: Option<String>,
-    force: bool,
-) -> Result<()> {
-    if key.is_none() && id.is_none() {
-        bail!("Provide --key or --id to delete a document.");
+fn resolve_project(project_arg: Option<String>) -> Result<String> {
+    let config = load_project_config()?;
+    if let Some(explicit) = project_arg {
+        return Ok(config.resolve_alias(&explicit));
     }
-    if key.is_some() && id.is_some() {
-        bail!("Provide only one of --key or --id.");
+
+    if let Ok(env_project) = env::var("CONTEXT_PROJECT") {
+        if !env_project.trim().is_empty() {
+            return Ok(config.resolve_alias(&env_project));
+        }
     }
 
-    let project = project.unwrap_or_else(|| "default".to_string());
-    let doc_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    Ok(config
+        .current
+        .as_deref()
+        .map(|current| config.resolve_alias(current))
+        .unwrap_or_else(|| "default".to_string()))
+}
 
+fn handle_project_current(json_output: bool, project_arg: Option<String>) -> Result<()> {
+    let project = resolve_project(project_arg)?;
     if json_output {
-        let payload = serde_json::json!({
-            "status": "deleted",
-            "project": project,
-            "id": doc_id,
-            "key": key,
-            "force": force,
-        });
+        let payload = serde_json::json!({ "project": project });
         println!("{}", serde_json::to_string_pretty(&payload)?);
-        return Ok(());
+    } else {
+        println!("Current project: {project}");
     }
+    Ok(())
+}
 
-    println!("Deleted document {doc_id} in project {project}");
-    if let Some(key) = key {
-        println!("Key: {key}");
-    }
-    if force {
-        println!("Force flag respected.");
+fn handle_project_set(json_output: bool, project: String) -> Result<()> {
+    let project = project.trim().to_string();
+    if project.is_empty() {
+        bail!("Project name cannot be empty.");
     }
 
-    Ok(())
-}
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let lock = lock_project_config()?;
+    let mut config = load_project_config()?;
+    let project = config.resolve_alias(&project);
+    runtime.block_on(storage.register_project(&project))?;
+    config.current = Some(project.clone());
+    save_project_config(&lock, &config)?;
 
-fn handle_gc(project: Option<String>, json_output: bool, dry_run: bool) -> Result<()> {
-    let project = project.unwrap_or_else(|| "default".to_string());
     if json_output {
         let payload = serde_json::json!({
             "status": "ok",
             "project": project,
-            "dry_run": dry_run,
-            "deleted": 0,
-            "vacuumed": !dry_run,
         });
         println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!("Set current project to {project}");
+    }
+
+    Ok(())
+}
+
+fn handle_project_list(json_output: bool) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let config = load_project_config()?;
+    runtime.block_on(storage.register_project(&"default".to_string()))?;
+    let projects = runtime.block_on(storage.project_stats(&[]))?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&projects)?);
         return Ok(());
     }
 
-    println!("Garbage collection complete for project {project}");
-    if dry_run {
-        println!("dry-run (no changes made)");
-    } else {
-        println!("vacuumed");
+    let width = projects
+        .iter()
+        .map(|stats| stats.project.id.len())
+        .max()
+        .unwrap_or(0)
+        .max("PROJECT".len());
+    println!(
+        "{:<width$}  {:>6}  {:>9}  UPDATED",
+        "PROJECT", "DOCS", "SIZE"
+    );
+    for stats in &projects {
+        let info = &stats.project;
+        let updated = stats
+            .last_updated
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let mut line = format!(
+            "{:<width$}  {:>6}  {:>9}  {updated}",
+            info.id,
+            stats.documents,
+            format_size(stats.bytes)
+        );
+        if let Some(name) = &info.display_name {
+            line.push_str(&format!("  {name}"));
+        }
+        let aliases: Vec<&str> = config
+            .aliases
+            .iter()
+            .filter(|(_, target)| **target == info.id)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        if !aliases.is_empty() {
+            line.push_str(&format!(" (alias: {})", aliases.join(", ")));
+        }
+        println!("{line}");
     }
     Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ProjectConfig {
-    current: Option<String>,
-    known: Vec<String>,
+/// Render a byte count as `512 B`, `1.2 KiB`, or `3.4 MiB`.
+fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let value = bytes as f64;
+    if value < KIB {
+        format!("{bytes} B")
+    } else if value < KIB * KIB {
+        format!("{:.1} KiB", value / KIB)
+    } else {
+        format!("{:.1} MiB", value / (KIB * KIB))
+    }
 }
 
-impl Default for ProjectConfig {
-    fn default() -> Self {
-        Self {
-            current: None,
-            known: vec!["default".to_string()],
-        }
-    }
+fn handle_project_show(json_output: bool, project: String) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let project = load_project_config()?.resolve_alias(project.trim());
+    let Some(info) = runtime.block_on(storage.project(&project))? else {
+        return Err(NotFound(format!("Project not found: {project}")).into());
+    };
+    print_project_info(json_output, &info)
 }
 
-impl ProjectConfig {
-    fn ensure_known(&mut self, project: &str) {
-        if project.trim().is_empty() {
-            return;
+fn handle_project_edit(
+    json_output: bool,
+    project: String,
+    name: Option<String>,
+    description: Option<String>,
+    settings: Vec<String>,
+) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let project = load_project_config()?.resolve_alias(project.trim());
+    runtime.block_on(storage.register_project(&project))?;
+    let mut info = runtime
+        .block_on(storage.project(&project))?
+        .context("Project registry is missing a project it just registered")?;
+
+    // An empty value clears the field, so `--name ""` undoes a display name.
+    if let Some(name) = name {
+        info.display_name = Some(name.trim().to_string()).filter(|name| !name.is_empty());
+    }
+    if let Some(description) = description {
+        info.description =
+            Some(description.trim().to_string()).filter(|description| !description.is_empty());
+    }
+    for setting in settings {
+        let Some((key, value)) = setting.split_once('=') else {
+            bail!("Invalid --setting {setting:?}. Use key=value.");
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("Invalid --setting {setting:?}. The key cannot be empty.");
         }
-        if !self.known.contains(&project.to_string()) {
-            self.known.push(project.to_string());
+        if value.is_empty() {
+            info.settings.remove(key);
+        } else {
+            info.settings.insert(key.to_string(), value.to_string());
         }
     }
+
+    let info = runtime.block_on(storage.update_project(&info))?;
+    print_project_info(json_output, &info)
 }
 
-fn resolve_project(project_arg: Option<String>) -> Result<String> {
-    if let Some(explicit) = project_arg {
-        return Ok(explicit);
+fn print_project_info(json_output: bool, info: &ProjectInfo) -> Result<()> {
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(info)?);
+        return Ok(());
+    }
+    println!("Project: {}", info.id);
+    if let Some(name) = &info.display_name {
+        println!("Name: {name}");
+    }
+    if let Some(description) = &info.description {
+        println!("Description: {description}");
     }
+    println!("Created: {}", info.created_at.to_rfc3339());
+    for (key, value) in &info.settings {
+        println!("Setting: {key}={value}");
+    }
+    Ok(())
+}
 
-    if let Ok(env_project) = env::var("CONTEXT_PROJECT") {
-        if !env_project.trim().is_empty() {
-            return Ok(env_project);
+fn handle_project_alias(json_output: bool, action: AliasCommands) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let lock = lock_project_config()?;
+    let mut config = load_project_config()?;
+
+    let payload = match action {
+        AliasCommands::Add { alias, project } => {
+            let alias = alias.trim().to_string();
+            let project = config.resolve_alias(project.trim());
+            if alias.is_empty() || project.is_empty() {
+                bail!("Alias and project cannot be empty.");
+            }
+            if alias == project {
+                bail!("Alias {alias} cannot point at itself.");
+            }
+            if runtime.block_on(storage.project(&alias))?.is_some() {
+                bail!("{alias} is already a project name; pick a different alias.");
+            }
+            runtime.block_on(storage.register_project(&project))?;
+            config.aliases.insert(alias.clone(), project.clone());
+            save_project_config(&lock, &config)?;
+            if !json_output {
+                println!("Alias {alias} -> {project}");
+            }
+            serde_json::json!({ "status": "ok", "alias": alias, "project": project })
+        }
+        AliasCommands::Rm { alias } => {
+            let Some(project) = config.aliases.remove(&alias) else {
+                bail!("No alias named {alias}.");
+            };
+            save_project_config(&lock, &config)?;
+            if !json_output {
+                println!("Removed alias {alias} (was {project})");
+            }
+            serde_json::json!({ "status": "removed", "alias": alias, "project": project })
         }
-    }
+        AliasCommands::List => {
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&config.aliases)?);
+                return Ok(());
+            }
+            println!("Aliases:");
+            for (alias, project) in &config.aliases {
+                println!("- {alias} -> {project}");
+            }
+            return Ok(());
+        }
+    };
 
-    let config = load_project_config()?;
-    Ok(config.current.unwrap_or_else(|| "default".to_string()))
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    }
+    Ok(())
 }
 
-fn handle_project_current(json_output: bool, project_arg: Option<String>) -> Result<()> {
-    let project = resolve_project(project_arg)?;
+fn handle_project_export(json_output: bool, out: Option<PathBuf>) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let mut config = load_project_config()?;
+    config.known = runtime
+        .block_on(storage.projects())?
+        .into_iter()
+        .map(|info| info.id)
+        .collect();
+    let serialized = serde_json::to_string_pretty(&config)?;
+
+    let Some(path) = out else {
+        println!("{serialized}");
+        return Ok(());
+    };
+    fs::write(&path, format!("{serialized}\n"))
+        .with_context(|| format!("Failed to write config export to {}", path.display()))?;
     if json_output {
-        let payload = serde_json::json!({ "project": project });
+        let payload = serde_json::json!({ "status": "ok", "path": path });
         println!("{}", serde_json::to_string_pretty(&payload)?);
     } else {
-        println!("Current project: {project}");
+        println!("Exported config to {}", path.display());
     }
     Ok(())
 }
 
-fn handle_project_set(json_output: bool, project: String) -> Result<()> {
-    let project = project.trim().to_string();
-    if project.is_empty() {
-        bail!("Project name cannot be empty.");
-    }
+fn handle_project_import(json_output: bool, file: Option<PathBuf>, replace: bool) -> Result<()> {
+    let raw = read_body(file)?;
+    let incoming: ProjectConfig =
+        serde_json::from_str(&raw).context("Import is not a valid context config export")?;
+    ExpiryPolicy::new(incoming.store.expiry.clone())
+        .map_err(|err| anyhow::anyhow!("Invalid expiry rule in import: {err:#}"))?;
 
-    let mut config = load_project_config()?;
-    config.current = Some(project.clone());
-    config.ensure_known("default");
-    config.ensure_known(&project);
-    save_project_config(&config)?;
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let lock = lock_project_config()?;
+    let mut config = if replace {
+        incoming
+    } else {
+        let mut merged = load_project_config()?;
+        merged.known = incoming.known;
+        merged.aliases.extend(incoming.aliases);
+        merged.current = incoming.current.or(merged.current);
+        merged.store.keys = incoming.store.keys;
+        merged.summarize = incoming.summarize;
+        merged.store.expiry = incoming.store.expiry;
+        merged
+    };
+    // The project list goes to the registry; only the rest stays in config.json.
+    let known = std::mem::take(&mut config.known);
+    for project in known.iter().chain(config.aliases.values()) {
+        runtime.block_on(storage.register_project(project))?;
+    }
+    save_project_config(&lock, &config)?;
 
     if json_output {
         let payload = serde_json::json!({
             "status": "ok",
-            "project": project,
+            "mode": if replace { "replace" } else { "merge" },
+            "current": config.current,
+            "known": known,
+            "aliases": config.aliases,
         });
         println!("{}", serde_json::to_string_pretty(&payload)?);
     } else {
-        println!("Set current project to {project}");
+        println!(
+            "Imported config ({}): {} known project(s), {} alias(es)",
+            if replace { "replaced" } else { "merged" },
+            known.len(),
+            config.aliases.len()
+        );
     }
-
     Ok(())
 }
 
-fn handle_project_list(json_output: bool) -> Result<()> {
-    let mut config = load_project_config()?;
-    let current = config.current.clone();
-    if let Some(curr) = current.as_deref() {
-        config.ensure_known(curr);
-    }
-    config.ensure_known("default");
-    config.known.sort();
-    config.known.dedup();
-
-    if json_output {
-        println!("{}", serde_json::to_string_pretty(&config.known)?);
-        return Ok(());
+fn load_project_config() -> Result<ProjectConfig> {
+    let path = project_config_path()?;
+    if !path.exists() {
+        return Ok(ProjectConfig::default());
     }
 
-    println!("Projects:");
-    for project in &config.known {
-        println!("- {project}");
-    }
-    Ok(())
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read project config at {}", path.display()))?;
+    let config = match serde_json::from_str::<ProjectConfig>(&contents) {
+        Ok(config) => config,
+        Err(err) => recover_project_config(&path, &err)?,
+    };
+    Ok(config)
 }
 
-fn load_project_config() -> Result<ProjectConfig> {
+/// Read config.json for a running server. Unlike `load_project_config` a broken file is
+/// an error, not something to recover from: it may be half-saved by an editor.
+fn reload_project_config() -> Result<ProjectConfig> {
     let path = project_config_path()?;
     if !path.exists() {
         return Ok(ProjectConfig::default());
     }
-
     let contents = fs::read_to_string(&path)
         .with_context(|| format!("Failed to read project config at {}", path.display()))?;
-    let mut config: ProjectConfig = serde_json::from_str(&contents)
-        .with_context(|| format!("Failed to parse project config at {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
 
-    config.ensure_known("default");
+/// Replace an unparseable config.json with its last good backup (or defaults), keeping
+/// the broken file next to it so nothing is lost and later commands stop failing.
+fn recover_project_config(path: &Path, err: &serde_json::Error) -> Result<ProjectConfig> {
+    let corrupt = path.with_extension("json.corrupt");
+    fs::copy(path, &corrupt)
+        .with_context(|| format!("Failed to back up corrupt config to {}", corrupt.display()))?;
+
+    let backup = path.with_extension("json.bak");
+    let restored = fs::read_to_string(&backup)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<ProjectConfig>(&contents).ok());
+    let source = if restored.is_some() {
+        backup.display().to_string()
+    } else {
+        "defaults".to_string()
+    };
+    let config = restored.unwrap_or_default();
+    write_atomically(path, &serde_json::to_string_pretty(&config)?)?;
+
+    tracing::warn!(path = %path.display(), error = %err, "recovered corrupt project config");
+    eprintln!(
+        "warning: {} was corrupt ({err}); restored from {source}, original saved to {}",
+        path.display(),
+        corrupt.display()
+    );
     Ok(config)
 }
 
-fn save_project_config(config: &ProjectConfig) -> Result<()> {
+/// Exclusive hold on config.json for a read-modify-write; released on drop.
+struct ConfigLock {
+    _file: fs::File,
+}
+
+fn lock_project_config() -> Result<ConfigLock> {
+    let path = project_config_path()?.with_extension("json.lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open config lock {}", path.display()))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {}", path.display()))?;
+    Ok(ConfigLock { _file: file })
+}
+
+/// Save config.json, keeping the previous version as config.json.bak. Taking the lock
+/// makes callers load the config under it too, so concurrent updates are not lost.
+fn save_project_config(_lock: &ConfigLock, config: &ProjectConfig) -> Result<()> {
     let path = project_config_path()?;
     let serialized = serde_json::to_string_pretty(config)?;
-    fs::write(&path, serialized)
-        .with_context(|| format!("Failed to write project config to {}", path.display()))?;
+    if path.exists() {
+        let backup = path.with_extension("json.bak");
+        fs::copy(&path, &backup)
+            .with_context(|| format!("Failed to back up project config to {}", backup.display()))?;
+    }
+    write_atomically(&path, &serialized)
+        .with_context(|| format!("Failed to write project config to {}", path.display()))
+}
+
+/// Write via a sibling temp file and rename, so readers never see a partial file.
+fn write_atomically(path: &Path, contents: &str) -> Result<()> {
+    let tmp = path.with_extension(format!("json.tmp.{}", std::process::id()));
+    let mut file =
+        fs::File::create(&tmp).with_context(|| format!("Failed to create {}", tmp.display()))?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to move {} into place", tmp.display()))?;
     Ok(())
 }
 
@@ -871,6 +5239,97 @@ fn project_config_path() -> Result<PathBuf> {
     Ok(dir.join("config.json"))
 }
 
+fn db_path() -> Result<PathBuf> {
+    let dir = context_home()?;
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join("db.sqlite"))
+}
+
+/// This machine's id, generated into `$CONTEXT_HOME/machine-id` the first time it is
+/// asked for. Unlike the host name it survives renames and tells apart two laptops that
+/// share one.
+fn machine_id() -> Result<String> {
+    let dir = context_home()?;
+    let path = dir.join("machine-id");
+    if let Some(id) = fs::read_to_string(&path)
+        .ok()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+    {
+        return Ok(id);
+    }
+    fs::create_dir_all(&dir)?;
+    let id = Uuid::new_v4().to_string();
+    fs::write(&path, format!("{id}\n"))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(id)
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start async runtime")
+}
+
+async fn open_storage() -> Result<TracedStorage<SqliteStorage>> {
+    let path = db_path()?;
+    let config = load_project_config()?;
+    let storage = SqliteStorage::open(&path)
+        .await
+        .with_context(|| format!("Failed to open database at {}", path.display()))?;
+    if !config.known.is_empty() {
+        migrate_known_projects(&storage, &config.known).await?;
+    }
+    let storage = configure_storage(storage, config.store)?;
+    completion::watch(&storage);
+    Ok(TracedStorage::new(storage))
+}
+
+/// `open_storage` as it stands now (see `SqliteStorage::snapshot`), for commands whose
+/// reads must agree with each other even while agents write.
+async fn open_snapshot() -> Result<TracedStorage<SqliteStorage>> {
+    Ok(open_storage().await?.snapshot().await?)
+}
+
+/// `open_storage` for a batch write; with `fast_import` the search index is filled in
+/// after the batch instead of per document.
+async fn open_import_storage(fast_import: bool) -> Result<TracedStorage<SqliteStorage>> {
+    Ok(open_storage().await?.with_deferred_indexing(fast_import))
+}
+
+/// Move a pre-registry `known` list from config.json into the `projects` table.
+async fn migrate_known_projects(storage: &SqliteStorage, known: &[String]) -> Result<()> {
+    for project in known.iter().filter(|project| !project.trim().is_empty()) {
+        storage
+            .register_project(&project.trim().to_string())
+            .await?;
+    }
+    let lock = lock_project_config()?;
+    let mut config = load_project_config()?;
+    config.known.clear();
+    save_project_config(&lock, &config)
+}
+
+/// Parse a user-supplied point in time: RFC3339, or a bare `YYYY-MM-DD` meaning midnight UTC.
+fn parse_timestamp(raw: &str) -> Result<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    bail!("Invalid timestamp {raw:?}. Use RFC3339 (2024-07-01T12:00:00Z) or YYYY-MM-DD.")
+}
+
+/// Resolve `--since`: a look-back window such as `30m`, `24h`, `7d`, or `2w`, or a timestamp.
+fn parse_since(raw: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(at) = parse_timestamp(raw.trim()) {
+        return Ok(at);
+    }
+    Ok(now - parse_duration(raw)?)
+}
+
 fn read_body(file: Option<PathBuf>) -> Result<String> {
     if let Some(path) = file {
         let contents = fs::read_to_string(&path)
@@ -897,19 +5356,55 @@ fn read_body(file: Option<PathBuf>) -> Result<String> {
 fn command_name(command: &Commands) -> &'static str {
     match command {
         Commands::AgentDoc { .. } => "agent-doc",
-        Commands::Init => "init",
+        Commands::Init { .. } => "init",
+        Commands::Whoami => "whoami",
         Commands::Put { .. } => "put",
+        Commands::Touch { .. } => "touch",
         Commands::Get { .. } => "get",
+        Commands::History { .. } => "history",
+        Commands::Diff { .. } => "diff",
+        Commands::Blame { .. } => "blame",
+        Commands::Related { .. } => "related",
         Commands::Cat { .. } => "cat",
         Commands::Find { .. } => "find",
-        Commands::Ls {} => "ls",
+        Commands::Ls { .. } => "ls",
         Commands::Rm { .. } => "rm",
+        Commands::Mv { .. } => "mv",
+        Commands::Restore { .. } => "restore",
         Commands::Gc { .. } => "gc",
+        Commands::Db { .. } => "db",
+        Commands::Scenario { .. } => "scenario",
         Commands::Web { .. } => "web",
+        Commands::Daemon { .. } => "daemon",
+        Commands::ServeReadonly { .. } => "serve-readonly",
         Commands::WebDev { .. } => "web-dev",
         Commands::DebugBundle { .. } => "debug-bundle",
+        Commands::Logs { .. } => "logs",
+        Commands::Completions { .. } => "completions",
         Commands::AgentConfig { .. } => "agent-config",
+        Commands::Pack { .. } => "pack",
+        Commands::Summarize { .. } => "summarize",
+        Commands::Digest { .. } => "digest",
+        Commands::Todos { .. } => "todos",
+        Commands::Remind { .. } => "remind",
+        Commands::Reminders { .. } => "reminders",
+        Commands::Calendar { .. } => "calendar",
+        Commands::Notify { .. } => "notify",
         Commands::Project { .. } => "project",
+        Commands::Keys { .. } => "keys",
+        Commands::Ns { .. } => "ns",
+        Commands::Template { .. } => "template",
+        Commands::Tag { .. } => "tag",
+        Commands::Tags => "tags",
+        Commands::Attach { .. } => "attach",
+        Commands::Attachments { .. } => "attachments",
+        Commands::Links { .. } => "links",
+        Commands::Backlinks { .. } => "backlinks",
+        Commands::Policy { .. } => "policy",
+        Commands::Dedup { .. } => "dedup",
+        Commands::Fixtures { .. } => "fixtures",
+        Commands::Import { .. } => "import",
+        Commands::Export { .. } => "export",
     }
 }
 
@@ -921,24 +5416,60 @@ fn command_span(log_context: LogContext<'_>, command: &Commands) -> Span {
             project = log_context.project,
             command = log_context.command
         ),
-        Commands::Init => tracing::info_span!(
+        Commands::Init { .. } => tracing::info_span!(
             "cli.init",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Whoami => tracing::info_span!(
+            "cli.whoami",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::Put { .. } => tracing::info_span!(
             "cli.put",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Touch { .. } => tracing::info_span!(
+            "cli.touch",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::Get { .. } => tracing::info_span!(
             "cli.get",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::History { .. } => tracing::info_span!(
+            "cli.history",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Diff { .. } => tracing::info_span!(
+            "cli.diff",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Blame { .. } => tracing::info_span!(
+            "cli.blame",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Related { .. } => tracing::info_span!(
+            "cli.related",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::Cat { .. } => tracing::info_span!(
             "cli.cat",
             scenario_id = log_context.scenario_id,
@@ -951,7 +5482,7 @@ fn command_span(log_context: LogContext<'_>, command: &Commands) -> Span {
             project = log_context.project,
             command = log_context.command
         ),
-        Commands::Ls {} => tracing::info_span!(
+        Commands::Ls { .. } => tracing::info_span!(
             "cli.ls",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
@@ -963,18 +5494,54 @@ fn command_span(log_context: LogContext<'_>, command: &Commands) -> Span {
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Mv { .. } => tracing::info_span!(
+            "cli.mv",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Restore { .. } => tracing::info_span!(
+            "cli.restore",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::Gc { .. } => tracing::info_span!(
             "cli.gc",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Db { .. } => tracing::info_span!(
+            "cli.db",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Scenario { .. } => tracing::info_span!(
+            "cli.scenario",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::Web { .. } => tracing::info_span!(
             "cli.web",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Daemon { .. } => tracing::info_span!(
+            "cli.daemon",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::ServeReadonly { .. } => tracing::info_span!(
+            "cli.serve-readonly",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::WebDev { .. } => tracing::info_span!(
             "cli.web-dev",
             scenario_id = log_context.scenario_id,
@@ -987,34 +5554,191 @@ fn command_span(log_context: LogContext<'_>, command: &Commands) -> Span {
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Logs { .. } => tracing::info_span!(
+            "cli.logs",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Completions { .. } => tracing::info_span!(
+            "cli.completions",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::AgentConfig { .. } => tracing::info_span!(
             "cli.agent-config",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Pack { .. } => tracing::info_span!(
+            "cli.pack",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Summarize { .. } => tracing::info_span!(
+            "cli.summarize",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Digest { .. } => tracing::info_span!(
+            "cli.digest",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Todos { .. } => tracing::info_span!(
+            "cli.todos",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Remind { .. } => tracing::info_span!(
+            "cli.remind",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Reminders { .. } => tracing::info_span!(
+            "cli.reminders",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Calendar { .. } => tracing::info_span!(
+            "cli.calendar",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Notify { .. } => tracing::info_span!(
+            "cli.notify",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
         Commands::Project { .. } => tracing::info_span!(
             "cli.project",
             scenario_id = log_context.scenario_id,
             project = log_context.project,
             command = log_context.command
         ),
+        Commands::Keys { .. } => tracing::info_span!(
+            "cli.keys",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Ns { .. } => tracing::info_span!(
+            "cli.ns",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Template { .. } => tracing::info_span!(
+            "cli.template",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Tag { .. } => tracing::info_span!(
+            "cli.tag",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Tags => tracing::info_span!(
+            "cli.tags",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Attach { .. } => tracing::info_span!(
+            "cli.attach",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Attachments { .. } => tracing::info_span!(
+            "cli.attachments",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Links { .. } => tracing::info_span!(
+            "cli.links",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Backlinks { .. } => tracing::info_span!(
+            "cli.backlinks",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Policy { .. } => tracing::info_span!(
+            "cli.policy",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Dedup { .. } => tracing::info_span!(
+            "cli.dedup",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Fixtures { .. } => tracing::info_span!(
+            "cli.fixtures",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Import { .. } => tracing::info_span!(
+            "cli.import",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
+        Commands::Export { .. } => tracing::info_span!(
+            "cli.export",
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command
+        ),
     }
 }
 
-fn resolve_log_dir() -> Result<PathBuf> {
-    let log_dir = match env::var("CONTEXT_LOG_DIR") {
-        Ok(dir) if Path::new(&dir).is_absolute() => PathBuf::from(dir),
-        Ok(dir) => env::current_dir()?.join(dir),
-        Err(_) => context_home()?.join("logs"),
-    };
-
-    fs::create_dir_all(&log_dir)?;
-    Ok(log_dir)
+fn handle_logs(json: bool, app: &str, pretty: bool, filter: logs::LogFilter) -> Result<()> {
+    let path = resolve_log_dir(Some(&context_home()?))?.join(logs::log_file_name(app));
+    let entries = logs::read_entries(&path, &filter)?;
+    if json {
+        let entries: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|line| {
+                serde_json::from_str(line)
+                    .unwrap_or_else(|_| serde_json::Value::from(line.as_str()))
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for line in &entries {
+            if pretty {
+                println!("{}", logs::render_pretty(line));
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+    Ok(())
 }
 
 fn create_debug_bundle(scenario: Option<String>, out: Option<String>) -> Result<PathBuf> {
-    let log_dir = resolve_log_dir()?;
+    let log_dir = resolve_log_dir(Some(&context_home()?))?;
     let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
     let bundle_path = out
         .map(PathBuf::from)
@@ -1024,10 +5748,26 @@ fn create_debug_bundle(scenario: Option<String>, out: Option<String>) -> Result<
     let mut writer = ZipWriter::new(file);
     let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
 
+    let mut crash_reports: Vec<String> = fs::read_dir(log_dir.join(CRASH_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    crash_reports.sort();
+    // Attachments are listed, not copied: they can hold anything a user attached.
+    let attachments = match bundled_attachments() {
+        Ok(attachments) => serde_json::json!(attachments),
+        Err(err) => serde_json::json!({ "error": format!("{err:#}") }),
+    };
     let meta = serde_json::json!({
         "scenario_id": scenario,
         "created_at": timestamp,
         "log_dir": log_dir,
+        "crash_reports": crash_reports,
+        "attachments": attachments,
     });
     writer.start_file("meta.json", options)?;
     writer.write_all(meta.to_string().as_bytes())?;
@@ -1050,6 +5790,25 @@ fn create_debug_bundle(scenario: Option<String>, out: Option<String>) -> Result<
     Ok(bundle_path)
 }
 
+/// Every attachment in the store, without its bytes; none when there is no store yet.
+fn bundled_attachments() -> Result<Vec<context_core::attachments::Attachment>> {
+    if !db_path()?.exists() {
+        return Ok(Vec::new());
+    }
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    Ok(runtime.block_on(storage.all_attachments())?)
+}
+
+/// The error for a subsystem left out of this build by its cargo feature.
+#[cfg(not(all(feature = "http", feature = "serve", feature = "smtp")))]
+fn not_built_with(what: &str, feature: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{what} is not part of this build of context; reinstall with \
+         `cargo install --path context-cli --features {feature}`."
+    )
+}
+
 fn context_home() -> Result<PathBuf> {
     if let Ok(home) = env::var("CONTEXT_HOME") {
         let path = PathBuf::from(home);
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+#[cfg(any(feature = "http", feature = "smtp"))]
+use std::{env, time::Duration};
+
+use anyhow::Result;
+#[cfg(any(feature = "http", feature = "smtp"))]
+use anyhow::{bail, Context};
+#[cfg(feature = "smtp")]
+use lettre::{
+    message::Mailbox,
+    transport::smtp::{authentication::Credentials, SmtpTransport},
+    Message, Transport,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(all(feature = "http", feature = "smtp")))]
+use crate::not_built_with;
+
+/// Where alerts go, read from `notify` in `config.json`.
+///
+/// `channels` apply to every project; an entry under `projects` replaces them for that
+/// project (an empty list silences it). Secrets are named by environment variable so
+/// config.json can be shared and exported.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<ChannelConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, Vec<ChannelConfig>>,
+}
+
+impl NotifyConfig {
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.projects.is_empty()
+    }
+
+    pub fn channels_for(&self, project: &str) -> &[ChannelConfig] {
+        self.projects.get(project).unwrap_or(&self.channels)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChannelConfig {
+    /// A Slack incoming webhook.
+    Slack {
+        /// Environment variable holding the webhook URL.
+        #[serde(default = "default_slack_env")]
+        webhook_url_env: String,
+    },
+    /// Mail through an SMTP relay.
+    Smtp {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        tls: SmtpTls,
+        from: String,
+        to: Vec<String>,
+        #[serde(default)]
+        username: Option<String>,
+        /// Environment variable holding the password for `username`.
+        #[serde(default = "default_smtp_password_env")]
+        password_env: String,
+    },
+}
+
+fn default_slack_env() -> String {
+    "CONTEXT_SLACK_WEBHOOK_URL".to_string()
+}
+
+fn default_smtp_password_env() -> String {
+    "CONTEXT_SMTP_PASSWORD".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    /// Upgrade a plain connection (port 587 by default).
+    #[default]
+    Starttls,
+    /// TLS from the first byte (port 465 by default).
+    Tls,
+    /// No encryption, for a relay on localhost (port 25 by default).
+    None,
+}
+
+/// One alert: what happened, in which project.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(any(feature = "http", feature = "smtp")), allow(dead_code))]
+pub struct Notification {
+    pub project: String,
+    pub title: String,
+    pub body: String,
+}
+
+/// Something that delivers a notification to people.
+pub trait Notifier {
+    /// Label for logs, such as `slack` or `smtp`.
+    fn name(&self) -> &'static str;
+    fn send(&self, notification: &Notification) -> Result<()>;
+}
+
+#[cfg(any(feature = "http", feature = "smtp"))]
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn notifier(channel: &ChannelConfig) -> Result<Box<dyn Notifier>> {
+    match channel {
+        #[cfg(not(feature = "http"))]
+        ChannelConfig::Slack { .. } => Err(not_built_with("Slack notifications", "http")),
+        #[cfg(not(feature = "smtp"))]
+        ChannelConfig::Smtp { .. } => Err(not_built_with("SMTP notifications", "smtp")),
+        #[cfg(feature = "http")]
+        ChannelConfig::Slack { webhook_url_env } => {
+            let url = env::var(webhook_url_env)
+                .ok()
+                .filter(|url| !url.is_empty())
+                .with_context(|| format!("Set {webhook_url_env} to the Slack webhook URL."))?;
+            Ok(Box::new(SlackWebhook { url }))
+        }
+        #[cfg(feature = "smtp")]
+        ChannelConfig::Smtp {
+            host,
+            port,
+            tls,
+            from,
+            to,
+            username,
+            password_env,
+        } => {
+            if to.is_empty() {
+                bail!("notify smtp channel for {host} has no `to` addresses in config.json.");
+            }
+            let mut builder = match tls {
+                SmtpTls::Starttls => SmtpTransport::starttls_relay(host)?,
+                SmtpTls::Tls => SmtpTransport::relay(host)?,
+                SmtpTls::None => SmtpTransport::builder_dangerous(host),
+            };
+            if let Some(port) = port {
+                builder = builder.port(*port);
+            }
+            if let Some(username) = username {
+                let password = env::var(password_env)
+                    .with_context(|| format!("Set {password_env} to the SMTP password."))?;
+                builder = builder.credentials(Credentials::new(username.clone(), password));
+            }
+            Ok(Box::new(Smtp {
+                transport: builder.timeout(Some(TIMEOUT)).build(),
+                from: from
+                    .parse()
+                    .with_context(|| format!("Invalid notify from address {from:?}"))?,
+                to: to
+                    .iter()
+                    .map(|to| {
+                        to.parse()
+                            .with_context(|| format!("Invalid notify to address {to:?}"))
+                    })
+                    .collect::<Result<_>>()?,
+            }))
+        }
+    }
+}
+
+/// Send `notification` through every channel configured for its project, returning the
+/// names of the ones that delivered it. A failing channel does not stop the others.
+pub fn broadcast(config: &NotifyConfig, notification: &Notification) -> Vec<&'static str> {
+    let mut delivered = Vec::new();
+    for channel in config.channels_for(&notification.project) {
+        let result = notifier(channel).and_then(|notifier| {
+            notifier.send(notification)?;
+            Ok(notifier.name())
+        });
+        match result {
+            Ok(name) => delivered.push(name),
+            Err(err) => tracing::warn!(
+                project = %notification.project,
+                error = %format!("{err:#}"),
+                "Notification channel failed"
+            ),
+        }
+    }
+    delivered
+}
+
+#[cfg(feature = "http")]
+struct SlackWebhook {
+    url: String,
+}
+
+#[cfg(feature = "http")]
+impl Notifier for SlackWebhook {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let text = format!("*{}*\n{}", notification.title, notification.body);
+        let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+        match agent
+            .post(&self.url)
+            .send_json(serde_json::json!({ "text": text }))
+        {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, response)) => {
+                let detail = response.into_string().unwrap_or_default();
+                bail!("Slack webhook returned HTTP {code}: {}", detail.trim())
+            }
+            Err(err) => Err(err).context("Slack webhook request failed"),
+        }
+    }
+}
+
+#[cfg(feature = "smtp")]
+struct Smtp {
+    transport: SmtpTransport,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+#[cfg(feature = "smtp")]
+impl Notifier for Smtp {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<()> {
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(&notification.title);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message.body(notification.body.clone())?;
+        self.transport
+            .send(&message)
+            .context("SMTP delivery failed")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(feature = "http")]
+use std::{env, time::Duration};
+
+use anyhow::Result;
+#[cfg(feature = "http")]
+use anyhow::{bail, Context};
+use context_core::Document;
+use serde::{Deserialize, Serialize};
+
+/// A team `context-web` server that `get` reads through to when a key is missing locally,
+/// read from `remote` in `config.json`, e.g. `{"url": "https://context.example.com"}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Base URL of the server (a tenant's `/t/<name>` prefix included); unset turns the
+    /// fallback off.
+    pub url: Option<String>,
+    /// Environment variable holding the bearer token, if the server wants one.
+    pub token_env: String,
+    /// Store what the remote returns so the next read is local.
+    pub cache: bool,
+    pub timeout_seconds: u64,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token_env: "CONTEXT_REMOTE_TOKEN".to_string(),
+            cache: true,
+            timeout_seconds: 10,
+        }
+    }
+}
+
+impl RemoteConfig {
+    pub fn is_unset(&self) -> bool {
+        self.url.is_none()
+    }
+
+    /// The remote's live document at `key` in `project`, or `None` when it has none.
+    #[cfg(feature = "http")]
+    pub fn fetch(&self, url: &str, project: &str, key: &str) -> Result<Option<Document>> {
+        let url = format!(
+            "{}/api/projects/{}/docs/{}",
+            url.trim_end_matches('/'),
+            encode_path(project),
+            encode_path(key)
+        );
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(self.timeout_seconds))
+            .build();
+        let mut request = agent.get(&url);
+        if let Some(token) = env::var(&self.token_env)
+            .ok()
+            .filter(|token| !token.is_empty())
+        {
+            request = request.set("Authorization", &format!("Bearer {token}"));
+        }
+        match request.call() {
+            Ok(response) => response
+                .into_json()
+                .map(Some)
+                .with_context(|| format!("Remote {url} returned something other than a document")),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(ureq::Error::Status(code, response)) => {
+                let detail = response.into_string().unwrap_or_default();
+                bail!("Remote {url} returned HTTP {code}: {}", detail.trim());
+            }
+            Err(err) => Err(err).with_context(|| format!("Failed to reach {url}")),
+        }
+    }
+
+    #[cfg(not(feature = "http"))]
+    pub fn fetch(&self, _url: &str, _project: &str, _key: &str) -> Result<Option<Document>> {
+        Err(crate::not_built_with(
+            "The remote fallback for `get`",
+            "http",
+        ))
+    }
+}
+
+/// Percent-encode what a key or project name could hold that a URL path can't; `/` stays,
+/// since the server's route matches the rest of the path as the key.
+#[cfg(feature = "http")]
+fn encode_path(raw: &str) -> String {
+    let mut encoded = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'_'
+            | b'.'
+            | b'~'
+            | b'/'
+            | b':'
+            | b'@' => encoded.push(byte as char),
+            other => encoded.push_str(&format!("%{other:02X}")),
+        }
+    }
+    encoded
+}
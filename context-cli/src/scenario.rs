@@ -0,0 +1,118 @@
+//! `context scenario start|end`: the scenario id that ties one session's logs, plan
+//! claims, and debug bundles together.
+//!
+//! An id is the start time (RFC 3339, seconds, UTC) followed by a label, e.g.
+//! `2026-10-14T19:22:45Z-import-run`, which is the shape `context-plan` reads the age of
+//! an `@scenario(...)` claim from. The running scenario is kept in
+//! `$CONTEXT_HOME/scenario.json` until it ends, so every later `context` invocation
+//! logs under it without `--scenario` or `CONTEXT_SCENARIO`; either of those still wins.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{context_home, NotFound};
+
+const SCENARIO_FILE: &str = "scenario.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl Scenario {
+    /// A scenario starting at `now`, labelled `label` or, without one, a random suffix.
+    fn new(label: Option<&str>, now: DateTime<Utc>) -> Self {
+        let suffix = match label.map(str::trim).filter(|label| !label.is_empty()) {
+            Some(label) => label
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect(),
+            None => Uuid::new_v4().simple().to_string()[..8].to_string(),
+        };
+        Self {
+            id: format!(
+                "{}-{suffix}",
+                now.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ),
+            started_at: now,
+        }
+    }
+}
+
+/// The scenario started by `context scenario start` and not yet ended, if any.
+pub fn current() -> Option<Scenario> {
+    let path = context_home().ok()?.join(SCENARIO_FILE);
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Start a scenario, ending the running one first. Returns the new scenario and the one
+/// it replaced.
+pub fn start(label: Option<&str>) -> Result<(Scenario, Option<Scenario>)> {
+    let previous = current();
+    if let Some(previous) = &previous {
+        log_end(previous, Utc::now());
+    }
+    let scenario = Scenario::new(label, Utc::now());
+    let home = context_home()?;
+    fs::create_dir_all(&home)?;
+    write(&home.join(SCENARIO_FILE), &scenario)?;
+    in_span(&scenario, || {
+        tracing::info!(
+            event = "scenario.started",
+            scenario_id = %scenario.id,
+            started_at = %scenario.started_at,
+            "Scenario started"
+        )
+    });
+    Ok((scenario, previous))
+}
+
+/// End the running scenario, returning it and when it ended.
+pub fn end() -> Result<(Scenario, DateTime<Utc>)> {
+    let Some(scenario) = current() else {
+        return Err(NotFound(
+            "No scenario is running; start one with `context scenario start`.".to_string(),
+        )
+        .into());
+    };
+    let path = context_home()?.join(SCENARIO_FILE);
+    fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    let ended_at = Utc::now();
+    log_end(&scenario, ended_at);
+    Ok((scenario, ended_at))
+}
+
+fn log_end(scenario: &Scenario, ended_at: DateTime<Utc>) {
+    in_span(scenario, || {
+        tracing::info!(
+            event = "scenario.ended",
+            scenario_id = %scenario.id,
+            started_at = %scenario.started_at,
+            duration_ms = (ended_at - scenario.started_at).num_milliseconds(),
+            "Scenario ended"
+        )
+    });
+}
+
+/// Run `log` in a span naming `scenario`, so `context logs --scenario` finds the entry
+/// even when the command itself ran under another scenario or none.
+fn in_span(scenario: &Scenario, log: impl FnOnce()) {
+    tracing::info_span!("scenario", scenario_id = %scenario.id).in_scope(log);
+}
+
+fn write(path: &Path, scenario: &Scenario) -> Result<()> {
+    fs::write(path, serde_json::to_string(scenario)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
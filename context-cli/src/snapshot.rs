@@ -0,0 +1,449 @@
+//! `context serve-readonly`: a public, read-only view of selected projects.
+//!
+//! Every live document is rendered to HTML once at startup and served from memory, so
+//! the server never touches the database again and has no way to change it: the router
+//! only has `GET` routes, and other methods get `405 Method Not Allowed`. Search runs
+//! over the same snapshot. Restart the server to publish newer revisions.
+//!
+//! Private documents are never published; `--public-only` narrows the snapshot further
+//! to documents marked public.
+
+use std::{collections::BTreeMap, io::Write, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Html,
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use futures::{future, TryStreamExt};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use serde::Deserialize;
+
+use context_core::{sqlite::SqliteStorage, traced::TracedStorage, Storage, Visibility};
+
+use crate::{open_storage, runtime};
+
+/// Results shown per search.
+const SEARCH_LIMIT: usize = 50;
+
+/// The rendered pages of the published projects.
+pub(crate) struct Snapshot {
+    projects: BTreeMap<String, Vec<Page>>,
+    taken_at: DateTime<Utc>,
+}
+
+struct Page {
+    /// Key for keyed documents, otherwise the document id.
+    label: String,
+    keyed: bool,
+    title: String,
+    tags: Vec<String>,
+    updated_at: DateTime<Utc>,
+    html: String,
+    /// Lowercased title, label, tags, and body, for search.
+    haystack: String,
+    body: String,
+}
+
+impl Page {
+    fn href(&self, project: &str) -> String {
+        let kind = if self.keyed { "d" } else { "id" };
+        format!(
+            "/p/{}/{kind}/{}",
+            encode_path(project),
+            encode_path(&self.label)
+        )
+    }
+}
+
+impl Snapshot {
+    /// Render every live, shared document of `projects`, each of which must exist; only
+    /// the public ones with `public_only`.
+    pub(crate) async fn take(
+        storage: &TracedStorage<SqliteStorage>,
+        projects: &[String],
+        public_only: bool,
+    ) -> Result<Self> {
+        let published: &[Visibility] = if public_only {
+            &[Visibility::Public]
+        } else {
+            &Visibility::SHARED
+        };
+        let known: Vec<String> = storage
+            .projects()
+            .await?
+            .into_iter()
+            .map(|info| info.id)
+            .collect();
+        let mut rendered = BTreeMap::new();
+        for project in projects {
+            if !known.contains(project) {
+                bail!("Unknown project {project}; nothing to publish.");
+            }
+            let mut pages: Vec<Page> = storage
+                .stream_project(project)
+                .try_filter(|doc| future::ready(published.contains(&doc.visibility())))
+                .map_ok(|doc| {
+                    let label = doc.key.clone().unwrap_or_else(|| doc.id.0.clone());
+                    let title = doc.title.clone().unwrap_or_else(|| label.clone());
+                    let haystack = format!(
+                        "{title}\n{label}\n{}\n{}",
+                        doc.tags.join(" "),
+                        doc.body_markdown
+                    )
+                    .to_lowercase();
+                    Page {
+                        keyed: doc.key.is_some(),
+                        html: render_markdown(&doc.body_markdown),
+                        label,
+                        title,
+                        tags: doc.tags,
+                        updated_at: doc.updated_at,
+                        haystack,
+                        body: doc.body_markdown,
+                    }
+                })
+                .try_collect()
+                .await?;
+            pages.sort_by(|a, b| a.label.cmp(&b.label));
+            rendered.insert(project.clone(), pages);
+        }
+        Ok(Self {
+            projects: rendered,
+            taken_at: Utc::now(),
+        })
+    }
+
+    fn documents(&self) -> usize {
+        self.projects.values().map(Vec::len).sum()
+    }
+
+    fn find(&self, project: &str, keyed: bool, label: &str) -> Option<&Page> {
+        self.projects
+            .get(project)?
+            .iter()
+            .find(|page| page.keyed == keyed && page.label == label)
+    }
+}
+
+/// The routes of the read-only server; there are deliberately no write handlers.
+pub(crate) fn router(snapshot: Arc<Snapshot>) -> Router {
+    Router::new()
+        .route("/", get(index))
+        .route("/healthz", get(|| async { "OK" }))
+        .route("/search", get(search))
+        .route("/p/:project", get(project_index))
+        .route("/p/:project/d/*key", get(document_by_key))
+        .route("/p/:project/id/:id", get(document_by_id))
+        .with_state(snapshot)
+}
+
+/// Publish `projects` on `host:port` until interrupted.
+pub(crate) fn serve(
+    projects: Vec<String>,
+    host: String,
+    port: u16,
+    public_only: bool,
+    json_output: bool,
+) -> Result<()> {
+    let runtime = runtime()?;
+    runtime.block_on(async {
+        let storage = open_storage().await?;
+        let snapshot = Arc::new(Snapshot::take(&storage, &projects, public_only).await?);
+        drop(storage);
+
+        let listener = tokio::net::TcpListener::bind((host.as_str(), port))
+            .await
+            .with_context(|| format!("Failed to listen on {host}:{port}"))?;
+        let url = format!("http://{}", listener.local_addr()?);
+        tracing::info!(
+            %url,
+            projects = ?projects,
+            documents = snapshot.documents(),
+            "Read-only snapshot server listening"
+        );
+        if json_output {
+            let payload = serde_json::json!({
+                "status": "listening",
+                "url": url,
+                "projects": projects,
+                "documents": snapshot.documents(),
+            });
+            println!("{payload}");
+        } else {
+            println!(
+                "Serving a read-only snapshot of {} document(s) from {} on {url} (Ctrl-C to stop)",
+                snapshot.documents(),
+                projects.join(", ")
+            );
+        }
+        std::io::stdout().flush()?;
+
+        axum::serve(listener, router(snapshot))
+            .with_graceful_shutdown(async {
+                tokio::signal::ctrl_c().await.ok();
+            })
+            .await?;
+        Ok(())
+    })
+}
+
+async fn index(State(snapshot): State<Arc<Snapshot>>) -> Html<String> {
+    let mut body = String::from("<h1>Projects</h1>\n<ul>\n");
+    for (project, pages) in &snapshot.projects {
+        body.push_str(&format!(
+            "<li><a href=\"/p/{}\">{}</a> ({} document(s))</li>\n",
+            encode_path(project),
+            escape(project),
+            pages.len()
+        ));
+    }
+    body.push_str("</ul>\n");
+    layout("context", None, &body, &snapshot)
+}
+
+async fn project_index(
+    State(snapshot): State<Arc<Snapshot>>,
+    Path(project): Path<String>,
+) -> Result<Html<String>, (StatusCode, Html<String>)> {
+    let Some(pages) = snapshot.projects.get(&project) else {
+        return Err(not_found(&snapshot));
+    };
+    let mut body = format!("<h1>{}</h1>\n<ul>\n", escape(&project));
+    for page in pages {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{}</small></li>\n",
+            page.href(&project),
+            escape(&page.title),
+            page.updated_at.format("%Y-%m-%d")
+        ));
+    }
+    body.push_str("</ul>\n");
+    Ok(layout(&project, Some(&project), &body, &snapshot))
+}
+
+async fn document_by_key(
+    State(snapshot): State<Arc<Snapshot>>,
+    Path((project, key)): Path<(String, String)>,
+) -> Result<Html<String>, (StatusCode, Html<String>)> {
+    document(&snapshot, &project, true, &key)
+}
+
+async fn document_by_id(
+    State(snapshot): State<Arc<Snapshot>>,
+    Path((project, id)): Path<(String, String)>,
+) -> Result<Html<String>, (StatusCode, Html<String>)> {
+    document(&snapshot, &project, false, &id)
+}
+
+fn document(
+    snapshot: &Snapshot,
+    project: &str,
+    keyed: bool,
+    label: &str,
+) -> Result<Html<String>, (StatusCode, Html<String>)> {
+    let Some(page) = snapshot.find(project, keyed, label) else {
+        return Err(not_found(snapshot));
+    };
+    let tags = if page.tags.is_empty() {
+        String::new()
+    } else {
+        format!(" · {}", escape(&page.tags.join(", ")))
+    };
+    let body = format!(
+        "<p><a href=\"/p/{}\">{}</a></p>\n<h1>{}</h1>\n<p><small>{} · updated {}{tags}</small></p>\n<article>\n{}</article>\n",
+        encode_path(project),
+        escape(project),
+        escape(&page.title),
+        escape(&page.label),
+        page.updated_at.format("%Y-%m-%d %H:%M UTC"),
+        page.html
+    );
+    Ok(layout(&page.title, Some(project), &body, snapshot))
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    #[serde(default)]
+    q: String,
+    project: Option<String>,
+}
+
+async fn search(
+    State(snapshot): State<Arc<Snapshot>>,
+    Query(params): Query<SearchParams>,
+) -> Html<String> {
+    let terms: Vec<String> = params.q.split_whitespace().map(str::to_lowercase).collect();
+    let mut hits: Vec<(usize, &str, &Page)> = Vec::new();
+    if !terms.is_empty() {
+        for (project, pages) in &snapshot.projects {
+            if params.project.as_ref().is_some_and(|only| only != project) {
+                continue;
+            }
+            for page in pages {
+                let counts: Vec<usize> = terms
+                    .iter()
+                    .map(|term| page.haystack.matches(term.as_str()).count())
+                    .collect();
+                if counts.iter().all(|count| *count > 0) {
+                    hits.push((counts.iter().sum(), project, page));
+                }
+            }
+        }
+    }
+    hits.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.label.cmp(&b.2.label)));
+    hits.truncate(SEARCH_LIMIT);
+
+    let mut body = format!("<h1>Search: {}</h1>\n", escape(&params.q));
+    if hits.is_empty() {
+        body.push_str("<p>No matches.</p>\n");
+    } else {
+        body.push_str("<ol>\n");
+        for (_, project, page) in &hits {
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a> <small>{}</small><br>{}</li>\n",
+                page.href(project),
+                escape(&page.title),
+                escape(project),
+                escape(&snippet(&page.body, &terms[0]))
+            ));
+        }
+        body.push_str("</ol>\n");
+    }
+    layout("Search", params.project.as_deref(), &body, &snapshot)
+}
+
+fn not_found(snapshot: &Snapshot) -> (StatusCode, Html<String>) {
+    (
+        StatusCode::NOT_FOUND,
+        layout(
+            "Not found",
+            None,
+            "<h1>Not found</h1>\n<p>Nothing is published at this address.</p>\n",
+            snapshot,
+        ),
+    )
+}
+
+/// Wrap `body` in the page chrome with a search box (scoped to `project` when given).
+fn layout(title: &str, project: Option<&str>, body: &str, snapshot: &Snapshot) -> Html<String> {
+    let scope = project
+        .map(|project| {
+            format!(
+                "<input type=\"hidden\" name=\"project\" value=\"{}\">",
+                escape(project)
+            )
+        })
+        .unwrap_or_default();
+    Html(format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n\
+         <style>body{{font-family:system-ui,sans-serif;max-width:48rem;margin:2rem auto;padding:0 1rem;line-height:1.5}}\
+         pre{{overflow-x:auto;background:#f4f4f4;padding:.75rem}}small{{color:#666}}</style>\n\
+         </head>\n<body>\n<header><a href=\"/\">context</a> \
+         <form action=\"/search\" method=\"get\" style=\"display:inline\">{scope}\
+         <input type=\"search\" name=\"q\" placeholder=\"Search\"></form></header>\n\
+         <main>\n{body}</main>\n<footer><small>Read-only snapshot taken {}</small></footer>\n\
+         </body>\n</html>\n",
+        escape(title),
+        snapshot.taken_at.format("%Y-%m-%d %H:%M UTC")
+    ))
+}
+
+/// Markdown to HTML for the public: raw HTML in bodies is shown as text and script
+/// links are neutralized, so a document cannot inject markup into the page.
+fn render_markdown(markdown: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        other => other,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    let scheme = url
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if url.contains(':') && matches!(scheme.as_str(), "javascript" | "vbscript" | "data") {
+        CowStr::Borrowed("#")
+    } else {
+        url
+    }
+}
+
+/// The first body line mentioning `term`, shortened for a result list.
+fn snippet(body: &str, term: &str) -> String {
+    let line = body
+        .lines()
+        .find(|line| line.to_lowercase().contains(term))
+        .or_else(|| body.lines().find(|line| !line.trim().is_empty()))
+        .unwrap_or_default()
+        .trim();
+    let mut snippet: String = line.chars().take(160).collect();
+    if line.chars().count() > 160 {
+        snippet.push('…');
+    }
+    snippet
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Percent-encode a project or key for a URL path, keeping `/` so keys read naturally.
+fn encode_path(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~/".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
@@ -0,0 +1,176 @@
+#[cfg(feature = "http")]
+use std::{env, time::Duration};
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Instructions sent ahead of the material to condense.
+const SYSTEM_PROMPT: &str = "You condense project knowledge for AI coding agents with small \
+context windows. Summarize the material below as concise markdown: keep decisions, commands, \
+paths, names, and open questions; drop repetition and prose. Reply with the summary only.";
+
+/// Which LLM backend `context summarize` talks to, read from `summarize` in `config.json`.
+///
+/// `openai` works with any OpenAI-compatible chat completions endpoint, including local
+/// servers such as Ollama or llama.cpp; `command` pipes the prompt through a local program
+/// and uses its stdout as the summary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummarizeConfig {
+    pub backend: BackendKind,
+    /// Base URL of the API; `/chat/completions` is appended.
+    pub endpoint: String,
+    pub model: String,
+    /// Environment variable holding the bearer token (unset means no auth header).
+    pub api_key_env: String,
+    /// Program and arguments for the `command` backend.
+    pub command: Vec<String>,
+    pub timeout_seconds: u64,
+}
+
+impl Default for SummarizeConfig {
+    fn default() -> Self {
+        Self {
+            backend: BackendKind::OpenAi,
+            endpoint: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: "OPENAI_API_KEY".to_string(),
+            command: Vec::new(),
+            timeout_seconds: 120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[serde(rename = "openai")]
+    OpenAi,
+    Command,
+}
+
+/// Something that turns a block of markdown into a shorter one.
+pub trait Summarizer {
+    fn summarize(&self, material: &str) -> Result<String>;
+}
+
+pub fn summarizer(config: &SummarizeConfig) -> Result<Box<dyn Summarizer>> {
+    match config.backend {
+        #[cfg(not(feature = "http"))]
+        BackendKind::OpenAi => Err(crate::not_built_with(
+            "The openai summarize backend",
+            "http",
+        )),
+        #[cfg(feature = "http")]
+        BackendKind::OpenAi => Ok(Box::new(OpenAiCompatible {
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            model: config.model.clone(),
+            api_key: env::var(&config.api_key_env)
+                .ok()
+                .filter(|key| !key.is_empty()),
+            timeout: Duration::from_secs(config.timeout_seconds),
+        })),
+        BackendKind::Command => {
+            if config.command.is_empty() {
+                bail!("summarize.backend is \"command\" but summarize.command is empty in config.json.");
+            }
+            Ok(Box::new(LocalCommand {
+                argv: config.command.clone(),
+            }))
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+struct OpenAiCompatible {
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+#[cfg(feature = "http")]
+impl Summarizer for OpenAiCompatible {
+    fn summarize(&self, material: &str) -> Result<String> {
+        let url = format!("{}/chat/completions", self.endpoint);
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        let mut request = agent.post(&url);
+        if let Some(key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {key}"));
+        }
+
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                { "role": "user", "content": material },
+            ],
+        });
+        let response: serde_json::Value = match request.send_json(body) {
+            Ok(response) => response
+                .into_json()
+                .context("LLM endpoint returned a non-JSON response")?,
+            Err(ureq::Error::Status(code, response)) => {
+                let detail = response.into_string().unwrap_or_default();
+                bail!("LLM endpoint {url} returned HTTP {code}: {}", detail.trim());
+            }
+            Err(err) => return Err(err).with_context(|| format!("Failed to reach {url}")),
+        };
+
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+            .with_context(|| format!("LLM endpoint {url} returned no completion"))
+    }
+}
+
+struct LocalCommand {
+    argv: Vec<String>,
+}
+
+impl Summarizer for LocalCommand {
+    fn summarize(&self, material: &str) -> Result<String> {
+        let program = &self.argv[0];
+        let mut child = Command::new(program)
+            .args(&self.argv[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start summarize command {program}"))?;
+
+        // Feed stdin from a separate thread so a program that streams output while
+        // reading cannot deadlock against a full pipe.
+        let prompt = format!("{SYSTEM_PROMPT}\n\n{material}");
+        let mut stdin = child
+            .stdin
+            .take()
+            .context("summarize command stdin unavailable")?;
+        let writer = thread::spawn(move || match stdin.write_all(prompt.as_bytes()) {
+            // The program is free to stop reading early.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            other => other,
+        });
+
+        let output = child.wait_with_output()?;
+        writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("summarize command stdin writer panicked"))?
+            .context("Failed to send the prompt to the summarize command")?;
+        if !output.status.success() {
+            bail!("Summarize command {program} exited with {}", output.status);
+        }
+        let summary = String::from_utf8(output.stdout)
+            .context("Summarize command produced non-UTF-8 output")?;
+        if summary.trim().is_empty() {
+            bail!("Summarize command {program} produced no output");
+        }
+        Ok(summary.trim().to_string())
+    }
+}
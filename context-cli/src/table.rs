@@ -0,0 +1,133 @@
+//! Spreadsheet-friendly tables of document metadata: `ls --format csv|tsv` and
+//! `export --format csv|tsv`.
+//!
+//! CSV quotes a cell only when it has to (RFC 4180). TSV has no quoting, so tabs and
+//! line breaks inside a cell become spaces. Tags share one cell, joined with `, `; `size`
+//! is the body's length in bytes.
+
+use std::io::Write;
+
+use anyhow::Result;
+
+use context_core::Document;
+
+/// Columns a table can carry, accepted by `--fields` with `--format csv|tsv`.
+pub const COLUMNS: &[&str] = &[
+    "id",
+    "project",
+    "key",
+    "namespace",
+    "title",
+    "tags",
+    "created_at",
+    "updated_at",
+    "size",
+    "version",
+    "source",
+    "visibility",
+    "ttl_seconds",
+    "deleted_at",
+];
+
+/// Columns written when `--fields` doesn't pick any.
+pub const DEFAULT_COLUMNS: &[&str] = &[
+    "key",
+    "title",
+    "tags",
+    "created_at",
+    "updated_at",
+    "size",
+    "version",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delimiter {
+    Comma,
+    Tab,
+}
+
+/// Writes a header row, then one row per document.
+pub struct TableWriter<W> {
+    writer: W,
+    delimiter: Delimiter,
+    columns: Vec<String>,
+}
+
+impl<W: Write> TableWriter<W> {
+    /// `columns` must come from [`COLUMNS`]; `None` means [`DEFAULT_COLUMNS`].
+    pub fn new(writer: W, delimiter: Delimiter, columns: Option<Vec<String>>) -> Result<Self> {
+        let columns = columns.unwrap_or_else(|| {
+            DEFAULT_COLUMNS
+                .iter()
+                .map(|column| column.to_string())
+                .collect()
+        });
+        let mut table = Self {
+            writer,
+            delimiter,
+            columns,
+        };
+        let header = table.columns.clone();
+        table.write_row(header.iter().map(String::as_str))?;
+        Ok(table)
+    }
+
+    pub fn write(&mut self, doc: &Document) -> Result<()> {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| cell(doc, column))
+            .collect();
+        self.write_row(cells.iter().map(String::as_str))
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_row<'a>(&mut self, cells: impl Iterator<Item = &'a str>) -> Result<()> {
+        let separator = match self.delimiter {
+            Delimiter::Comma => ",",
+            Delimiter::Tab => "\t",
+        };
+        let row: Vec<String> = cells.map(|cell| self.escape(cell)).collect();
+        // CRLF is what RFC 4180 and spreadsheet importers expect.
+        write!(self.writer, "{}\r\n", row.join(separator))?;
+        Ok(())
+    }
+
+    fn escape(&self, cell: &str) -> String {
+        match self.delimiter {
+            Delimiter::Comma if cell.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            }
+            Delimiter::Comma => cell.to_string(),
+            Delimiter::Tab => cell.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+}
+
+fn cell(doc: &Document, column: &str) -> String {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    match column {
+        "id" => doc.id.0.clone(),
+        "project" => doc.project.clone(),
+        "key" => optional(&doc.key),
+        "namespace" => optional(&doc.namespace),
+        "title" => optional(&doc.title),
+        "tags" => doc.tags.join(", "),
+        "created_at" => doc.created_at.to_rfc3339(),
+        "updated_at" => doc.updated_at.to_rfc3339(),
+        "size" => doc.body_markdown.len().to_string(),
+        "version" => doc.version.to_string(),
+        "source" => doc.source.as_str().to_string(),
+        "visibility" => doc.visibility().as_str().to_string(),
+        "ttl_seconds" => doc
+            .ttl_seconds
+            .map(|ttl| ttl.to_string())
+            .unwrap_or_default(),
+        "deleted_at" => doc.deleted_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
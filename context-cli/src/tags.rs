@@ -0,0 +1,95 @@
+//! `context tag add|remove|list` edits one document's tags without re-putting its body;
+//! `context tags` lists every tag in a project with how many documents carry it.
+
+use anyhow::{bail, Result};
+
+use context_core::Document;
+
+use crate::{fetch_document_exact, open_storage, runtime, TagCommands};
+
+pub fn handle_tag(project: Option<String>, json_output: bool, action: TagCommands) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let (key, id, add, remove) = match action {
+        TagCommands::Add { key, id, tags } => (key, id, clean(tags)?, Vec::new()),
+        TagCommands::Remove { key, id, tags } => (key, id, Vec::new(), clean(tags)?),
+        TagCommands::List { key, id } => {
+            let runtime = runtime()?;
+            let storage = runtime.block_on(open_storage())?;
+            let doc = runtime.block_on(fetch_document_exact(&storage, &project, &key, &id))?;
+            if json_output {
+                println!("{}", serde_json::to_string_pretty(&doc.tags)?);
+            } else {
+                for tag in &doc.tags {
+                    println!("{tag}");
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let doc = runtime.block_on(fetch_document_exact(&storage, &project, &key, &id))?;
+    let version = doc.version;
+    let updated = runtime.block_on(storage.update_tags(doc, &add, &remove))?;
+    let changed = updated.version != version;
+    if json_output {
+        let payload = serde_json::json!({
+            "status": if changed { "updated" } else { "unchanged" },
+            "project": project,
+            "id": updated.id.0,
+            "key": updated.key,
+            "tags": updated.tags,
+            "version": updated.version,
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        println!(
+            "Tags on {}: {}{}",
+            label(&updated),
+            if updated.tags.is_empty() {
+                "(none)".to_string()
+            } else {
+                updated.tags.join(", ")
+            },
+            if changed {
+                format!(" (version {})", updated.version)
+            } else {
+                " (unchanged)".to_string()
+            }
+        );
+    }
+    Ok(())
+}
+
+pub fn handle_tags(project: Option<String>, json_output: bool) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let tags = runtime.block_on(storage.tag_counts(&project))?;
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&tags)?);
+        return Ok(());
+    }
+    println!("Tags in project {project}");
+    for tag in &tags {
+        println!("- {} ({})", tag.name, tag.documents);
+    }
+    Ok(())
+}
+
+fn clean(tags: Vec<String>) -> Result<Vec<String>> {
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    if tags.is_empty() {
+        bail!("Name at least one tag.");
+    }
+    Ok(tags)
+}
+
+fn label(doc: &Document) -> &str {
+    doc.key.as_deref().unwrap_or(&doc.id.0)
+}
@@ -0,0 +1,171 @@
+//! `context template`: reusable Markdown skeletons stored per project, filled in with
+//! `--var name=value` by `template render` and `put --from-template`.
+//!
+//! `{{date}}` is today's date (UTC, `YYYY-MM-DD`) unless a `--var date=...` says
+//! otherwise.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use serde::Serialize;
+
+use context_core::templates::{self, Template};
+
+use crate::{open_storage, read_body, runtime, NotFound, TemplateCommands};
+
+#[derive(Debug, Serialize)]
+struct TemplateSummary {
+    #[serde(flatten)]
+    template: Template,
+    variables: Vec<String>,
+}
+
+impl From<Template> for TemplateSummary {
+    fn from(template: Template) -> Self {
+        Self {
+            variables: template.variables(),
+            template,
+        }
+    }
+}
+
+pub fn handle_template(
+    project: Option<String>,
+    json_output: bool,
+    action: TemplateCommands,
+) -> Result<()> {
+    let project = project.unwrap_or_else(|| "default".to_string());
+    match action {
+        TemplateCommands::Add {
+            name,
+            file,
+            description,
+        } => add(&project, json_output, &name, file, description),
+        TemplateCommands::List => list(&project, json_output),
+        TemplateCommands::Render { name, vars } => {
+            let body = render(&project, &name, &vars)?;
+            if json_output {
+                let payload = serde_json::json!({
+                    "project": project,
+                    "template": name,
+                    "body_markdown": body,
+                });
+                println!("{}", serde_json::to_string_pretty(&payload)?);
+            } else {
+                print!("{body}");
+            }
+            Ok(())
+        }
+        TemplateCommands::Rm { name } => {
+            let runtime = runtime()?;
+            let storage = runtime.block_on(open_storage())?;
+            if !runtime.block_on(storage.delete_template(&project, &name))? {
+                return Err(NotFound(format!("No template {name} in project {project}.")).into());
+            }
+            if json_output {
+                let payload = serde_json::json!({
+                    "status": "removed",
+                    "project": project,
+                    "template": name,
+                });
+                println!("{}", serde_json::to_string_pretty(&payload)?);
+            } else {
+                println!("Removed template {name} from project {project}");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn add(
+    project: &str,
+    json_output: bool,
+    name: &str,
+    file: Option<PathBuf>,
+    description: Option<String>,
+) -> Result<()> {
+    templates::validate_name(name)?;
+    let body = read_body(file)?;
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let project = project.to_string();
+    let template = runtime.block_on(storage.put_template(&project, name, description, &body))?;
+    let summary = TemplateSummary::from(template);
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        println!(
+            "Stored template {} in project {}{}",
+            summary.template.name,
+            summary.template.project,
+            variables_note(&summary.variables)
+        );
+    }
+    Ok(())
+}
+
+fn list(project: &str, json_output: bool) -> Result<()> {
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let templates: Vec<TemplateSummary> = runtime
+        .block_on(storage.templates(&project.to_string()))?
+        .into_iter()
+        .map(TemplateSummary::from)
+        .collect();
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&templates)?);
+        return Ok(());
+    }
+    println!("Templates in project {project}");
+    for summary in &templates {
+        let description = summary
+            .template
+            .description
+            .as_ref()
+            .map(|description| format!(": {description}"))
+            .unwrap_or_default();
+        println!(
+            "- {}{description}{}",
+            summary.template.name,
+            variables_note(&summary.variables)
+        );
+    }
+    Ok(())
+}
+
+/// The body of template `name` in `project`, filled in from `raw_vars` (`name=value`).
+pub fn render(project: &str, name: &str, raw_vars: &[String]) -> Result<String> {
+    let vars = parse_vars(raw_vars)?;
+    let runtime = runtime()?;
+    let storage = runtime.block_on(open_storage())?;
+    let Some(template) = runtime.block_on(storage.template(&project.to_string(), name))? else {
+        return Err(NotFound(format!(
+            "No template {name} in project {project}; see `context template list`."
+        ))
+        .into());
+    };
+    Ok(templates::render(&template.body_markdown, &vars)?)
+}
+
+fn parse_vars(raw_vars: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut vars = BTreeMap::from([(
+        "date".to_string(),
+        Utc::now().format("%Y-%m-%d").to_string(),
+    )]);
+    for raw in raw_vars {
+        let Some((name, value)) = raw.split_once('=') else {
+            bail!("--var takes name=value, got {raw:?}");
+        };
+        vars.insert(name.trim().to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+fn variables_note(variables: &[String]) -> String {
+    if variables.is_empty() {
+        String::new()
+    } else {
+        format!(" (vars: {})", variables.join(", "))
+    }
+}
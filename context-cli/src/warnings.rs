@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// When commands end with a hint to run `context gc`, read from `warnings` in `config.json`,
+/// e.g. `{"max_db_mb": 4096}` or `{"enabled": false}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarningConfig {
+    pub enabled: bool,
+    /// Size of the database file plus its WAL, in megabytes.
+    pub max_db_mb: u64,
+    /// Soft-deleted documents in the current project.
+    pub max_deleted: u64,
+}
+
+impl Default for WarningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_db_mb: 1024,
+            max_deleted: 10_000,
+        }
+    }
+}
+
+impl WarningConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// One line for each limit a database of `db_bytes` with `deleted` tombstones in
+    /// `project` is over.
+    pub fn check(&self, project: &str, db_bytes: u64, deleted: u64) -> Vec<String> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let silence = "or set warnings.enabled to false in config.json";
+        let mut warnings = Vec::new();
+        if db_bytes > self.max_db_mb * BYTES_PER_MB {
+            warnings.push(format!(
+                "warning: the database is {:.1} MB (limit {} MB); `context gc` purges deleted documents and compacts it, {silence}",
+                db_bytes as f64 / BYTES_PER_MB as f64,
+                self.max_db_mb
+            ));
+        }
+        if deleted > self.max_deleted {
+            warnings.push(format!(
+                "warning: project {project} has {deleted} deleted documents (limit {}); `context gc --project {project}` purges them, {silence}",
+                self.max_deleted
+            ));
+        }
+        warnings
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+use zip::read::ZipArchive;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn json(cmd: &mut Command) -> Result<Value> {
+    let assert = cmd.assert().success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+#[test]
+fn attach_stores_a_file_that_attachments_get_writes_back() -> Result<()> {
+    let home = tempdir()?;
+    context(home.path())
+        .args(["put", "--key", "design"])
+        .write_stdin("The design.")
+        .assert()
+        .success();
+    let png: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0, 255];
+    let file = home.path().join("diagram.png");
+    fs::write(&file, &png)?;
+
+    let attached = json(context(home.path()).args([
+        "--json",
+        "attach",
+        "--key",
+        "design",
+        "--file",
+        file.to_str().unwrap(),
+    ]))?;
+    assert_eq!(attached["name"], "diagram.png");
+    assert_eq!(attached["media_type"], "image/png");
+    assert_eq!(attached["size"], png.len());
+
+    // A new revision keeps the document's attachments.
+    context(home.path())
+        .args(["put", "--key", "design"])
+        .write_stdin("The design, revised.")
+        .assert()
+        .success();
+    let listed =
+        json(context(home.path()).args(["--json", "attachments", "list", "--key", "design"]))?;
+    assert_eq!(listed.as_array().map(Vec::len), Some(1));
+    assert_eq!(listed[0]["sha256"], attached["sha256"]);
+
+    let assert = context(home.path())
+        .args(["attachments", "get", "--key", "design", "diagram.png"])
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, png);
+    let out = home.path().join("copy.png");
+    context(home.path())
+        .args([
+            "attachments",
+            "get",
+            "--key",
+            "design",
+            "diagram.png",
+            "--out",
+            out.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    assert_eq!(fs::read(&out)?, png);
+
+    context(home.path())
+        .args(["attachments", "get", "--key", "design", "missing.png"])
+        .assert()
+        .code(3);
+    context(home.path())
+        .args(["attach", "--key", "nope", "--file", file.to_str().unwrap()])
+        .assert()
+        .code(3);
+
+    // The debug bundle lists attachments without copying their bytes.
+    let bundle = home.path().join("bundle.zip");
+    context(home.path())
+        .args(["debug-bundle", "--out", bundle.to_str().unwrap()])
+        .assert()
+        .success();
+    let mut archive = ZipArchive::new(fs::File::open(&bundle)?)?;
+    let meta: Value = serde_json::from_reader(archive.by_name("meta.json")?)?;
+    assert_eq!(meta["attachments"][0]["name"], "diagram.png");
+    assert!(archive.file_names().all(|name| !name.contains("diagram")));
+    Ok(())
+}
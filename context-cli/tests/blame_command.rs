@@ -0,0 +1,57 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put;
+
+#[test]
+fn blame_attributes_lines_to_versions() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "runbook", "step one\nstep two");
+    put(
+        temp.path(),
+        "demo",
+        "runbook",
+        "step one\nstep two revised\nstep three",
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "--json", "blame", "--key", "runbook"])
+        .assert()
+        .success();
+    let lines: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let versions: Vec<u64> = lines
+        .iter()
+        .map(|l| l["version"].as_u64().unwrap())
+        .collect();
+    assert_eq!(versions, vec![1, 2, 2]);
+    assert_eq!(lines[0]["source"], "User");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "blame", "--key", "runbook"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.lines().next().unwrap().starts_with("v1"), "{stdout}");
+    assert!(stdout.contains("| step three"), "{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn blame_fails_for_missing_document() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["blame", "--key", "nope"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("not found"), "stderr: {stderr}");
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::fs;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+#[test]
+fn calendar_exports_reminders_and_expirations() -> Result<()> {
+    let temp = tempdir()?;
+    fs::write(
+        temp.path().join("config.json"),
+        r#"{"expiry": [{"tag": "scratch", "after": "14d"}]}"#,
+    )?;
+    context(temp.path())
+        .args([
+            "--project",
+            "demo",
+            "put",
+            "--key",
+            "scratch/idea",
+            "--tag",
+            "scratch",
+        ])
+        .write_stdin("short-lived")
+        .assert()
+        .success();
+    context(temp.path())
+        .args(["--project", "demo", "put", "--key", "rollout"])
+        .write_stdin("body")
+        .assert()
+        .success();
+    context(temp.path())
+        .args(["--project", "demo", "remind", "--key", "rollout"])
+        .args(["--at", "2999-07-01T09:30:00Z", "--note", "check rates"])
+        .assert()
+        .success();
+
+    let assert = context(temp.path())
+        .args(["--project", "demo", "calendar"])
+        .assert()
+        .success();
+    let ics = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+    assert!(ics.contains("SUMMARY:Expires: demo/scratch/idea\r\n"));
+    assert!(ics.contains("SUMMARY:Follow up: demo/rollout\r\n"));
+    assert!(ics.contains("DTSTART:29990701T093000Z\r\n"));
+
+    let out = temp.path().join("context.ics");
+    let assert = context(temp.path())
+        .args(["--project", "demo", "--json", "calendar", "--out"])
+        .arg(&out)
+        .assert()
+        .success();
+    let payload: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(payload["events"], 2);
+    assert!(fs::read_to_string(&out)?.ends_with("END:VCALENDAR\r\n"));
+
+    let assert = context(temp.path())
+        .args(["--project", "other", "--json", "calendar"])
+        .assert()
+        .success();
+    let events: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(events.is_empty());
+    Ok(())
+}
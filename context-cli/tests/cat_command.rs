@@ -1,20 +1,27 @@
+mod common;
+
 use anyhow::Result;
 use assert_cmd::Command;
 use context_core::Document;
+use serde_json::Value;
 use tempfile::tempdir;
 
+use common::put;
+
 #[test]
 fn cat_outputs_body_only_by_id() -> Result<()> {
     let temp = tempdir()?;
+    let stored = put(temp.path(), "default", "doc-42", "Content for doc-42");
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
-        .args(["cat", "--id", "doc-42"])
+        .args(["cat", "--id", &stored.id.0])
         .assert()
         .success();
 
     let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
-    assert!(stdout.contains("doc-42"));
+    assert_eq!(stdout.trim_end(), "Content for doc-42");
     assert!(!stdout.contains("Project:"));
 
     Ok(())
@@ -23,6 +30,8 @@ fn cat_outputs_body_only_by_id() -> Result<()> {
 #[test]
 fn cat_can_output_json_with_key() -> Result<()> {
     let temp = tempdir()?;
+    put(temp.path(), "demo-project", "note-9", "Notes for note-9");
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
@@ -65,3 +74,69 @@ fn cat_requires_key_or_id() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn cat_missing_key_exits_with_not_found_code() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "cat", "--key", "missing"])
+        .assert()
+        .failure();
+
+    let output = assert.get_output();
+    assert_eq!(output.status.code(), Some(3));
+    let value: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(value["error"]["code"], "not_found");
+
+    Ok(())
+}
+
+#[test]
+fn cat_keys_joins_bodies_in_order_and_reports_missing_keys() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "default", "a", "alpha");
+    put(temp.path(), "default", "b", "bravo");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["cat", "--keys", "b,a", "--delimiter", "\\n==\\n"])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "bravo\n==\nalpha\n"
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "cat", "--key", "a", "--key", "nope", "--key", "b"])
+        .assert()
+        .success();
+    let entries: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    let summary: Vec<(&str, bool)> = entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| {
+            (
+                entry["key"].as_str().unwrap(),
+                entry["found"].as_bool().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(summary, [("a", true), ("nope", false), ("b", true)]);
+    assert_eq!(entries[2]["document"]["body_markdown"], "bravo");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["cat", "--keys", "a,nope"])
+        .assert()
+        .code(3);
+    let output = assert.get_output();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "alpha\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("nope"));
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! Helpers shared by the command tests; each test binary uses only some of them.
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use assert_cmd::Command;
+use context_core::Document;
+
+/// `context put --key <key>` in `project` with `body` on stdin, returning the stored
+/// document.
+pub fn put(home: &Path, project: &str, key: &str, body: &str) -> Document {
+    put_with(home, key, body, &["--project", project])
+}
+
+/// `put` with `args` added instead of a project, e.g. `["--tag", "ops"]`; without
+/// `--project` the document goes to the current project.
+pub fn put_with(home: &Path, key: &str, body: &str, args: &[&str]) -> Document {
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", home)
+        .args(["--json", "put", "--key", key])
+        .args(args)
+        .write_stdin(body)
+        .assert()
+        .success();
+    serde_json::from_slice(&assert.get_output().stdout).expect("put prints the stored document")
+}
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+#[test]
+fn completions_cover_the_subcommands() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["completions", "zsh"])
+        .assert()
+        .success();
+    let script = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(script.starts_with("#compdef context"), "{script}");
+    for command in ["put", "find", "history", "completions"] {
+        assert!(
+            script.contains(&format!("'{command}:")),
+            "no {command} in {script}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,322 @@
+#![cfg(unix)]
+
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    process::{Child, Command as StdCommand, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use tempfile::tempdir;
+
+fn context(home: &Path, log_dir: &Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home)
+        .env("CONTEXT_LOG_DIR", log_dir);
+    cmd
+}
+
+fn start_daemon(home: &Path, log_dir: &Path) -> Result<Child> {
+    start_daemon_with(home, log_dir, &[], &[])
+}
+
+fn start_daemon_with(
+    home: &Path,
+    log_dir: &Path,
+    args: &[&str],
+    envs: &[(&str, &Path)],
+) -> Result<Child> {
+    let child = StdCommand::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", home)
+        .env("CONTEXT_LOG_DIR", log_dir)
+        .envs(envs.iter().copied())
+        .arg("daemon")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let socket = home.join("daemon.sock");
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !socket.exists() {
+        assert!(Instant::now() < deadline, "daemon never created its socket");
+        thread::sleep(Duration::from_millis(50));
+    }
+    Ok(child)
+}
+
+fn signal(child: &Child, signal: &str) -> Result<()> {
+    StdCommand::new("kill")
+        .args([signal, &child.id().to_string()])
+        .status()?;
+    Ok(())
+}
+
+/// Stop the daemon the way Ctrl-C would, so it flushes its log and removes the socket.
+fn stop_daemon(mut child: Child) -> Result<()> {
+    signal(&child, "-INT")?;
+    assert!(child.wait()?.success());
+    Ok(())
+}
+
+#[test]
+fn put_get_and_find_proxy_through_the_daemon() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    let daemon_logs = home.join("daemon-logs");
+    let client_logs = home.join("client-logs");
+    let daemon = start_daemon(home, &daemon_logs)?;
+    let mode = fs::metadata(home.join("daemon.sock"))?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o600, "only the owner may connect");
+
+    let assert = context(home, &client_logs)
+        .args(["--project", "demo", "--json", "put", "--key", "notes"])
+        .write_stdin("daemon served body")
+        .assert()
+        .success();
+    let stored: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(stored.key.as_deref(), Some("notes"));
+
+    let assert = context(home, &client_logs)
+        .args(["--project", "demo", "--json", "get", "--key", "notes"])
+        .assert()
+        .success();
+    let fetched: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(fetched["id"], stored.id.0);
+    assert_eq!(fetched["body_markdown"], "daemon served body");
+
+    let assert = context(home, &client_logs)
+        .args(["--project", "demo", "--json", "history", "--key", "notes"])
+        .assert()
+        .success();
+    let history: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["version"], 1);
+
+    let assert = context(home, &client_logs)
+        .args(["--project", "demo", "--json", "find", "daemon"])
+        .assert()
+        .success();
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0]["key"], "notes");
+
+    // A missing document keeps its exit code and JSON error code through the proxy.
+    let assert = context(home, &client_logs)
+        .args(["--project", "demo", "--json", "get", "--key", "missing"])
+        .assert()
+        .code(3);
+    let error: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(error["error"]["code"], "not_found");
+
+    stop_daemon(daemon)?;
+    assert!(!home.join("daemon.sock").exists());
+
+    let client_log = fs::read_to_string(client_logs.join("context-cli.jsonl"))?;
+    assert_eq!(client_log.matches("Proxying to daemon").count(), 5);
+    let daemon_log = fs::read_to_string(daemon_logs.join("context-cli.jsonl"))?;
+    for op in ["store", "fetch", "history", "search"] {
+        assert!(
+            daemon_log.contains(&format!("\"op\":\"{op}\"")),
+            "daemon log has no {op} request"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn commands_open_the_store_directly_without_a_daemon() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    // A socket file left behind by a daemon that died is ignored.
+    fs::write(home.join("daemon.sock"), "")?;
+
+    context(home, home)
+        .args(["--project", "demo", "put", "--key", "notes"])
+        .write_stdin("stored without a daemon")
+        .assert()
+        .success();
+    context(home, home)
+        .args(["--project", "demo", "get", "--key", "notes"])
+        .assert()
+        .success();
+
+    let log = fs::read_to_string(home.join("context-cli.jsonl"))?;
+    assert!(!log.contains("Proxying to daemon"));
+    Ok(())
+}
+
+#[test]
+fn conflicts_exit_4_with_and_without_a_daemon() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    let put = |body: &str| {
+        context(home, home)
+            .args([
+                "--project",
+                "demo",
+                "--json",
+                "put",
+                "--key",
+                "notes",
+                "--idempotency-key",
+                "batch-1",
+            ])
+            .write_stdin(body.to_string())
+            .assert()
+    };
+    put("first write").success();
+
+    let assert = put("reused key, local").code(4);
+    let error: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(error["error"]["code"], "conflict");
+
+    let daemon = start_daemon(home, &home.join("daemon-logs"))?;
+    let assert = put("reused key, proxied").code(4);
+    let error: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(error["error"]["code"], "conflict");
+    assert!(error["error"]["message"]
+        .as_str()
+        .is_some_and(|message| message.contains("batch-1")));
+    stop_daemon(daemon)?;
+    Ok(())
+}
+
+#[test]
+fn searches_past_their_timeout_exit_5_through_the_daemon() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    let batch: String = (0..5000)
+        .map(|n| {
+            format!(
+                "{{\"key\":\"deploys/{n}\",\"body\":\"Deploy {n} went out behind a canary.\"}}\n"
+            )
+        })
+        .collect();
+    context(home, home)
+        .args(["--project", "demo", "put", "--batch", "--fast-import"])
+        .write_stdin(batch)
+        .assert()
+        .success();
+
+    let daemon = start_daemon(home, &home.join("daemon-logs"))?;
+    let assert = context(home, home)
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "find",
+            "canary",
+            "--timeout",
+            "1",
+        ])
+        .assert()
+        .code(5);
+    let error: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(error["error"]["code"], "timeout");
+    // The limit held for that search only.
+    context(home, home)
+        .args(["--project", "demo", "find", "canary"])
+        .assert()
+        .success();
+    stop_daemon(daemon)?;
+
+    let log = fs::read_to_string(home.join("context-cli.jsonl"))?;
+    assert_eq!(log.matches("Proxying to daemon").count(), 2);
+    Ok(())
+}
+
+#[test]
+fn daemon_reloads_config_on_sighup() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    let daemon = start_daemon(home, &home.join("daemon-logs"))?;
+
+    let put = |key: &str| -> Result<Document> {
+        let assert = context(home, home)
+            .args(["--project", "demo", "--json", "put", "--key", key])
+            .write_stdin("body")
+            .assert()
+            .success();
+        Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+    };
+    assert_eq!(put("Before")?.key.as_deref(), Some("Before"));
+
+    fs::write(home.join("config.json"), r#"{"keys": {"normalize": true}}"#)?;
+    signal(&daemon, "-HUP")?;
+    // The daemon keeps running and the next writes see the new key rules.
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let stored = put(&format!("After-{attempt}"))?;
+        if stored.key.as_deref() == Some(format!("after-{attempt}").as_str()) {
+            break;
+        }
+        assert!(Instant::now() < deadline, "config was never reloaded");
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    stop_daemon(daemon)?;
+    let log = fs::read_to_string(home.join("daemon-logs").join("context-cli.jsonl"))?;
+    assert!(log.contains("Config reloaded"));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn daemon_notifies_due_reminders_once() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    context(home, home)
+        .args(["--project", "demo", "put", "--key", "plan"])
+        .write_stdin("body")
+        .assert()
+        .success();
+    context(home, home)
+        .args(["--project", "demo", "remind", "--key", "plan"])
+        .args(["--at", "2020-01-01", "--note", "check rollout"])
+        .assert()
+        .success();
+
+    // A stand-in notify-send that records what it was asked to show.
+    let bin = home.join("bin");
+    fs::create_dir(&bin)?;
+    let shown = home.join("shown.txt");
+    let script = bin.join("notify-send");
+    fs::write(
+        &script,
+        format!("#!/bin/sh\necho \"$@\" >> {}\n", shown.display()),
+    )?;
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+
+    let daemon = start_daemon_with(
+        home,
+        &home.join("daemon-logs"),
+        &["--notify"],
+        &[("PATH", &bin)],
+    )?;
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !shown.exists() {
+        assert!(Instant::now() < deadline, "reminder was never announced");
+        thread::sleep(Duration::from_millis(50));
+    }
+    stop_daemon(daemon)?;
+
+    assert_eq!(
+        fs::read_to_string(&shown)?,
+        "context: demo/plan check rollout\n"
+    );
+    let assert = context(home, home)
+        .args(["--project", "demo", "--json", "reminders", "due"])
+        .assert()
+        .success();
+    let due: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(due.len(), 1, "announced reminders stay due until done");
+    assert!(due[0]["notified_at"].is_string());
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+#[test]
+fn db_analyze_refreshes_query_statistics() -> Result<()> {
+    let home = tempdir()?;
+    for key in ["a", "b", "c"] {
+        context(home.path())
+            .args(["put", "--key", key])
+            .write_stdin(format!("body of {key}"))
+            .assert()
+            .success();
+    }
+
+    let assert = context(home.path())
+        .args(["--json", "db", "analyze"])
+        .assert()
+        .success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(report["status"], "ok");
+    assert!(report["analyzed"].as_u64().unwrap() > 0);
+
+    let assert = context(home.path())
+        .args(["db", "analyze"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert!(stdout.starts_with("Query statistics refreshed"), "{stdout}");
+    Ok(())
+}
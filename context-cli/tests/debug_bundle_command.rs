@@ -60,3 +60,39 @@ fn debug_bundle_outputs_json_when_requested() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn debug_bundle_includes_crash_reports() -> Result<()> {
+    let temp = tempdir()?;
+    let crash_dir = temp.path().join("logs/crashes");
+    fs::create_dir_all(&crash_dir)?;
+    fs::write(
+        crash_dir.join("context-cli-1718000000-42.txt"),
+        "name: context-cli\nmessage: boom\n",
+    )?;
+
+    let out_path = temp.path().join("bundle.zip");
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .env_remove("CONTEXT_LOG_DIR")
+        .args(["debug-bundle", "--out", out_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let mut archive = ZipArchive::new(fs::File::open(&out_path)?)?;
+    let mut meta = String::new();
+    std::io::Read::read_to_string(&mut archive.by_name("meta.json")?, &mut meta)?;
+    let meta: Value = serde_json::from_str(&meta)?;
+    assert_eq!(
+        meta["crash_reports"],
+        serde_json::json!(["context-cli-1718000000-42.txt"])
+    );
+
+    let mut report = String::new();
+    std::io::Read::read_to_string(
+        &mut archive.by_name("logs/crashes/context-cli-1718000000-42.txt")?,
+        &mut report,
+    )?;
+    assert!(report.contains("message: boom"));
+    Ok(())
+}
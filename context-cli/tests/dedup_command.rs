@@ -0,0 +1,77 @@
+mod common;
+
+use std::path::Path;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+use common::put_with;
+
+fn dedup(home: &Path, extra: &[&str]) -> Result<serde_json::Value> {
+    let mut args = vec!["--json", "dedup", "report"];
+    args.extend(extra);
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", home)
+        .args(args)
+        .assert()
+        .success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+#[test]
+fn report_lists_groups_and_merge_keeps_newest() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(
+        temp.path(),
+        "notes/deploy-1",
+        "deploy with make release and tag it",
+        &[],
+    );
+    put_with(
+        temp.path(),
+        "notes/deploy-2",
+        "deploy with make release and tag it",
+        &[],
+    );
+    put_with(temp.path(), "notes/lunch", "tacos on tuesday", &[]);
+
+    let report = dedup(temp.path(), &[])?;
+    let groups = report["groups"].as_array().expect("groups");
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0]["exact"], true);
+    assert_eq!(groups[0]["members"][0]["key"], "notes/deploy-2");
+    assert!(report["removed"].as_array().unwrap().is_empty());
+
+    let merged = dedup(temp.path(), &["--merge", "keep-newest"])?;
+    assert_eq!(merged["removed"].as_array().unwrap().len(), 1);
+    assert_eq!(merged["redirects"][0]["from"], "notes/deploy-1");
+    assert_eq!(merged["redirects"][0]["to"], "notes/deploy-2");
+
+    let ls = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "ls", "--key-only"])
+        .assert()
+        .success();
+    let listed: Vec<serde_json::Value> = serde_json::from_slice(&ls.get_output().stdout)?;
+    assert_eq!(listed.len(), 2);
+    assert!(dedup(temp.path(), &[])?["groups"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn report_rejects_unknown_merge_strategy() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["dedup", "report", "--merge", "keep-oldest"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Use --merge keep-newest"));
+    Ok(())
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+#[test]
+fn diff_prints_a_unified_diff_between_versions() -> Result<()> {
+    let temp = tempdir()?;
+    put(
+        temp.path(),
+        "demo",
+        "runbook",
+        "# Runbook\nrestart api\ncheck logs\n",
+    );
+    put(
+        temp.path(),
+        "demo",
+        "runbook",
+        "# Runbook\nrestart api and worker\ncheck logs\n",
+    );
+    put(
+        temp.path(),
+        "demo",
+        "runbook",
+        "# Runbook\nrestart api and worker\ncheck logs\npage on-call\n",
+    );
+
+    let assert = context(temp.path())
+        .args(["diff", "--key", "runbook", "--from", "1", "--to", "3"])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8_lossy(&assert.get_output().stdout),
+        "--- runbook v1\n+++ runbook v3\n@@ -1,3 +1,4 @@\n # Runbook\n-restart api\n\
+         +restart api and worker\n check logs\n+page on-call\n"
+    );
+
+    // Without versions it compares the latest with the one before.
+    let assert = context(temp.path())
+        .args(["--json", "diff", "--key", "runbook"])
+        .assert()
+        .success();
+    let diff: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(diff["from"], 2);
+    assert_eq!(diff["to"], 3);
+    let lines = diff["hunks"][0]["lines"].as_array().unwrap();
+    let added: Vec<&str> = lines
+        .iter()
+        .filter(|line| line["change"] == "added")
+        .map(|line| line["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(added, ["page on-call"]);
+    assert_eq!(diff["hunks"][0]["old_start"], 1);
+    Ok(())
+}
+
+#[test]
+fn diff_reports_missing_versions() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "runbook", "only version");
+
+    let assert = context(temp.path())
+        .args(["diff", "--key", "runbook", "--from", "1", "--to", "4"])
+        .assert()
+        .code(3);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("has no version 4; the latest is 1"),
+        "{stderr}"
+    );
+
+    let assert = context(temp.path())
+        .args(["diff", "--key", "runbook"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("has no earlier version"), "{stderr}");
+
+    let assert = context(temp.path())
+        .args(["diff", "--key", "runbook", "--from", "1", "--to", "1"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("v1 and v1 have the same body"), "{stdout}");
+    Ok(())
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use tempfile::tempdir;
+
+use common::put_with;
+
+fn digest(home: &std::path::Path, since: &str) -> Result<Document> {
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", home)
+        .args(["--json", "digest", "--since", since])
+        .assert()
+        .success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+#[test]
+fn digest_compiles_recent_documents_into_dated_document() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(
+        temp.path(),
+        "notes/auth",
+        "# Auth\n\nTokens rotate every 24 hours.",
+        &[],
+    );
+    put_with(temp.path(), "notes/db", "We use SQLite with WAL.", &[]);
+
+    let stored = digest(temp.path(), "7d")?;
+    let key = stored.key.clone().unwrap_or_default();
+    assert!(key.starts_with("digest/"), "key: {key}");
+    assert!(stored.tags.contains(&"digest".to_string()));
+    assert!(stored.body_markdown.contains("**notes/auth** (new, v1"));
+    assert!(stored
+        .body_markdown
+        .contains("Tokens rotate every 24 hours."));
+    assert!(stored.body_markdown.contains("**notes/db**"));
+    assert!(stored.body_markdown.contains("`context get --id "));
+
+    // A second run the same day revises the digest and does not include itself.
+    let again = digest(temp.path(), "7d")?;
+    assert_eq!(again.id.0, stored.id.0);
+    assert_eq!(again.version, 2);
+    assert!(!again.body_markdown.contains("**Digest"));
+
+    Ok(())
+}
+
+#[test]
+fn digest_rejects_unknown_window_unit() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["digest", "--since", "7y"])
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Invalid duration"), "stderr: {stderr}");
+
+    Ok(())
+}
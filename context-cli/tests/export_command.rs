@@ -0,0 +1,251 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::{put, put_with};
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+#[test]
+fn export_writes_namespaced_markdown_that_import_reads_back() -> Result<()> {
+    let home = tempdir()?;
+    put_with(
+        home.path(),
+        "deploy",
+        "Canary first.\n",
+        &[
+            "--project",
+            "demo",
+            "--namespace",
+            "ops",
+            "--tag",
+            "runbook",
+        ],
+    );
+    put(home.path(), "demo", "notes/today", "Standup at ten.\n");
+    put(home.path(), "demo", "../escape", "Stays inside.\n");
+    put(home.path(), "demo", "gone", "Deleted.\n");
+    context(home.path())
+        .args(["rm", "--key", "gone"])
+        .assert()
+        .success();
+
+    let out = tempdir()?;
+    let dir = out.path().join("export");
+    let assert = context(home.path())
+        .args(["--json", "export", "--out", dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let summary: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(summary["documents"], 3);
+
+    let deploy = fs::read_to_string(dir.join("ops/deploy.md"))?;
+    assert!(
+        deploy.starts_with("---\nkey: \"deploy\"\nnamespace: \"ops\"\n"),
+        "{deploy}"
+    );
+    assert!(deploy.contains("\nversion: 1\ntags:\n  - \"runbook\"\n---\nCanary first.\n"));
+    assert!(dir.join("notes/today.md").is_file());
+    let names: Vec<String> = fs::read_dir(&dir)?
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names.len(), 3, "{names:?}");
+    assert!(!dir.join("gone.md").exists());
+
+    let summary: Value = serde_json::from_slice(
+        &context(home.path())
+            .args(["--json", "import", dir.to_str().unwrap()])
+            .assert()
+            .success()
+            .get_output()
+            .stdout,
+    )?;
+    assert_eq!(summary["skipped"], 3, "{summary}");
+
+    context(home.path())
+        .args(["export", "--out", dir.to_str().unwrap()])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn export_jsonl_streams_documents_to_stdout() -> Result<()> {
+    let home = tempdir()?;
+    put(home.path(), "demo", "a", "first");
+    put(home.path(), "demo", "b", "second");
+
+    let assert = context(home.path())
+        .args(["export", "--format", "jsonl"])
+        .assert()
+        .success();
+    let documents: Vec<Document> = String::from_utf8(assert.get_output().stdout.clone())?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let keys: Vec<_> = documents
+        .iter()
+        .map(|doc| doc.key.clone().unwrap())
+        .collect();
+    assert_eq!(keys, ["a", "b"]);
+    Ok(())
+}
+
+#[test]
+fn export_excludes_tags_redacts_patterns_and_writes_a_manifest() -> Result<()> {
+    let home = tempdir()?;
+    put(
+        home.path(),
+        "demo",
+        "contacts",
+        "Call sam@example.com or ana@example.org.\n",
+    );
+    put_with(
+        home.path(),
+        "diary",
+        "Dear diary.\n",
+        &["--project", "demo", "--tag", "personal,private"],
+    );
+    put(home.path(), "demo", "plain", "Nothing to hide.\n");
+
+    let out = tempdir()?;
+    let file = out.path().join("dump.jsonl");
+    let assert = context(home.path())
+        .args([
+            "--json",
+            "export",
+            "--format",
+            "jsonl",
+            "--out",
+            file.to_str().unwrap(),
+            "--exclude-tag",
+            "personal",
+            "--redact-pattern",
+            r"\w+@\w+\.\w+",
+        ])
+        .assert()
+        .success();
+    let summary: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(
+        (
+            &summary["documents"],
+            &summary["excluded"],
+            &summary["redacted"]
+        ),
+        (&2.into(), &1.into(), &1.into())
+    );
+
+    let dump = fs::read_to_string(&file)?;
+    assert!(
+        !dump.contains("example.com") && !dump.contains("diary"),
+        "{dump}"
+    );
+    assert!(dump.contains("Call [REDACTED] or [REDACTED]."), "{dump}");
+
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(
+        out.path().join("dump.jsonl.manifest.json"),
+    )?)?;
+    assert_eq!(manifest["excluded"][0]["key"], "diary");
+    assert_eq!(
+        manifest["excluded"][0]["tags"],
+        serde_json::json!(["personal"])
+    );
+    assert_eq!(manifest["redacted"][0]["key"], "contacts");
+    assert_eq!(manifest["redacted"][0]["matches"], 2);
+    assert!(!manifest.to_string().contains("sam@"));
+
+    context(home.path())
+        .args(["export", "--format", "jsonl", "--redact-pattern", "("])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn export_leaves_private_documents_behind_unless_asked() -> Result<()> {
+    let home = tempdir()?;
+    put_with(
+        home.path(),
+        "scratch",
+        "Mine.\n",
+        &["--project", "demo", "--visibility", "private"],
+    );
+    put(home.path(), "demo", "plan", "Ours.\n");
+
+    let out = tempdir()?;
+    let dir = out.path().join("notes");
+    let assert = context(home.path())
+        .args(["--json", "export", "--out", dir.to_str().unwrap()])
+        .assert()
+        .success();
+    let summary: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(
+        (&summary["documents"], &summary["private"]),
+        (&1.into(), &1.into())
+    );
+    assert!(dir.join("plan.md").exists());
+    assert!(!dir.join("scratch.md").exists());
+
+    let assert = context(home.path())
+        .args(["export", "--format", "jsonl", "--include-private"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(stdout.contains("\"visibility\":\"private\""), "{stdout}");
+    Ok(())
+}
+
+#[test]
+fn export_csv_writes_a_metadata_table() -> Result<()> {
+    let home = tempdir()?;
+    put_with(
+        home.path(),
+        "a",
+        "first",
+        &["--project", "demo", "--tag", "ops"],
+    );
+    put(home.path(), "demo", "b", "second\n");
+    put(home.path(), "demo", "b", "second, revised\n");
+
+    let out = tempdir()?;
+    let file = out.path().join("review.csv");
+    context(home.path())
+        .args(["export", "--format", "csv", "--out", file.to_str().unwrap()])
+        .assert()
+        .success();
+    let table = fs::read_to_string(&file)?;
+    let rows: Vec<Vec<&str>> = table
+        .lines()
+        .map(|line| line.trim_end_matches('\r').split(',').collect())
+        .collect();
+    assert_eq!(
+        rows[0],
+        [
+            "key",
+            "title",
+            "tags",
+            "created_at",
+            "updated_at",
+            "size",
+            "version"
+        ]
+    );
+    assert_eq!(rows.len(), 3, "{table}");
+    assert_eq!(
+        (rows[1][0], rows[1][2], rows[1][5], rows[1][6]),
+        ("a", "ops", "5", "1")
+    );
+    assert_eq!((rows[2][0], rows[2][5], rows[2][6]), ("b", "16", "2"));
+    Ok(())
+}
@@ -1,11 +1,24 @@
+mod common;
+
 use anyhow::Result;
 use assert_cmd::Command;
 use context_core::Document;
 use tempfile::tempdir;
 
+use common::put;
+
 #[test]
 fn find_returns_json_hits() -> Result<()> {
     let temp = tempdir()?;
+    for i in 1..=3 {
+        put(
+            temp.path(),
+            "demo-project",
+            &format!("hit-{i}"),
+            &format!("notes on rust search, part {i}"),
+        );
+    }
+    put(temp.path(), "demo-project", "miss", "python tips");
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
@@ -30,6 +43,59 @@ fn find_returns_json_hits() -> Result<()> {
         .iter()
         .all(|d| d.body_markdown.contains("rust search")));
 
+    // Each hit carries its combined bm25 + recency + tag score, best first.
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&stdout)?;
+    let scores: Vec<f64> = hits
+        .iter()
+        .map(|hit| hit["score"].as_f64().expect("score"))
+        .collect();
+    assert!(
+        scores.windows(2).all(|pair| pair[0] >= pair[1]),
+        "{scores:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn find_streams_ndjson_and_tolerates_punctuation() -> Result<()> {
+    let temp = tempdir()?;
+    put(
+        temp.path(),
+        "demo",
+        "deploy",
+        "blue-green deploy: step by step",
+    );
+    put(
+        temp.path(),
+        "other",
+        "deploy",
+        "blue-green deploy elsewhere",
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "find",
+            "blue-green deploy:",
+            "--all-projects",
+            "--format",
+            "ndjson",
+        ])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let projects: Vec<String> = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<Document>(line).map(|d| d.project))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(projects.len(), 2);
+    assert!(projects.contains(&"other".to_string()));
+
     Ok(())
 }
 
@@ -68,3 +134,315 @@ fn find_rejects_zero_limit() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn find_max_tokens_caps_combined_result_size() -> Result<()> {
+    let temp = tempdir()?;
+    for i in 1..=3 {
+        put(
+            temp.path(),
+            "demo",
+            &format!("note-{i}"),
+            &format!("budget note {i} {}", "filler ".repeat(20)),
+        );
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "find",
+            "budget",
+            "--max-tokens",
+            "50",
+        ])
+        .assert()
+        .success();
+
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(hits.len(), 2);
+    let total: u64 = hits.iter().map(|hit| hit["tokens"].as_u64().unwrap()).sum();
+    assert!(total <= 50, "total: {total}");
+
+    Ok(())
+}
+
+#[test]
+fn find_fields_trim_json_to_the_requested_keys() -> Result<()> {
+    let temp = tempdir()?;
+    put(
+        temp.path(),
+        "demo-project",
+        "notes/rust",
+        "notes on rust search",
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo-project",
+            "--json",
+            "find",
+            "rust",
+            "--fields",
+            "key,tokens",
+        ])
+        .assert()
+        .success();
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(hits.len(), 1);
+    let hit = hits[0].as_object().expect("object");
+    assert_eq!(hit.len(), 2);
+    assert_eq!(hit["key"], "notes/rust");
+    assert!(hit["tokens"].as_u64().unwrap() > 0);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo-project", "find", "rust", "--key-only"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("add --json"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "find", "rust", "--fields", "key,bogus"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Unknown field \"bogus\""));
+
+    Ok(())
+}
+
+#[test]
+fn find_excludes_namespaces_tags_and_negated_terms() -> Result<()> {
+    let temp = tempdir()?;
+    for (key, body, extra) in [
+        ("keep", "deploy runbook", vec![]),
+        (
+            "scratch",
+            "deploy scratch idea",
+            vec!["--namespace", "scratch"],
+        ),
+        ("old", "deploy legacy flow", vec!["--tag", "legacy"]),
+        ("phrase", "deploy with blue green", vec![]),
+    ] {
+        let mut args = vec!["--project", "demo", "put", "--key", key];
+        args.extend(extra);
+        Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args(args)
+            .write_stdin(body)
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "find",
+            r#"deploy -"blue green""#,
+            "--exclude-namespace",
+            "scratch",
+            "--not-tag",
+            "legacy",
+            "--key-only",
+        ])
+        .assert()
+        .success();
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(hits, vec![serde_json::json!({ "key": "keep" })]);
+
+    Ok(())
+}
+
+#[test]
+fn find_within_restricts_to_a_key_subtree() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "runbooks/deploy", "deploy steps");
+    put(
+        temp.path(),
+        "demo",
+        "runbooks/rollback",
+        "rollback after deploy",
+    );
+    put(temp.path(), "demo", "notes/deploy", "deploy musings");
+
+    let find = |extra: &[&str]| -> Result<Vec<String>> {
+        let mut args = vec![
+            "--project",
+            "demo",
+            "--json",
+            "find",
+            "deploy",
+            "--key-only",
+        ];
+        args.extend(extra);
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args(args)
+            .assert()
+            .success();
+        let hits: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+        let mut keys: Vec<String> = hits
+            .iter()
+            .filter_map(|hit| hit["key"].as_str().map(str::to_string))
+            .collect();
+        keys.sort();
+        Ok(keys)
+    };
+
+    assert_eq!(
+        find(&["--within", "runbooks/"])?,
+        vec!["runbooks/deploy", "runbooks/rollback"]
+    );
+    assert_eq!(
+        find(&["--keys", "notes/deploy,runbooks/deploy"])?,
+        vec!["notes/deploy", "runbooks/deploy"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn find_explain_reports_score_components() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "notes/rust", "notes on rust search");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "find",
+            "rust",
+            "--explain",
+            "--key-only",
+        ])
+        .assert()
+        .success();
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let explain = &hits[0]["explain"];
+    let sum = explain["bm25"].as_f64().unwrap()
+        + explain["recency"].as_f64().unwrap()
+        + explain["tag_bonus"].as_f64().unwrap()
+        + explain["pin_boost"].as_f64().unwrap();
+    assert!((sum - explain["score"].as_f64().unwrap()).abs() < 1e-4);
+    assert_eq!(hits[0]["key"], "notes/rust");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "find", "rust", "--explain"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("Score: "));
+    assert!(stdout.contains("+ recency "));
+    assert!(stdout.contains("+ pin 0.000"));
+
+    Ok(())
+}
+
+#[test]
+fn find_code_matches_identifiers_in_fenced_blocks() -> Result<()> {
+    let temp = tempdir()?;
+    put(
+        temp.path(),
+        "demo-project",
+        "pasted",
+        "The fix:\n```rust\nfn parseHttpRequest(raw: &str) {}\n```\n",
+    );
+    put(
+        temp.path(),
+        "demo-project",
+        "prose",
+        "We should parse the http request before routing.",
+    );
+
+    let find_code = |query: &str| -> Result<Vec<String>> {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args([
+                "--project",
+                "demo-project",
+                "--json",
+                "find",
+                "--code",
+                query,
+            ])
+            .assert()
+            .success();
+        let hits: Vec<Document> = serde_json::from_slice(&assert.get_output().stdout)?;
+        Ok(hits.into_iter().filter_map(|doc| doc.key).collect())
+    };
+
+    for query in [
+        "parseHttpRequest",
+        "HttpRequest",
+        "http_request",
+        "parsehttprequest",
+        "HttpRequest raw",
+    ] {
+        assert_eq!(find_code(query)?, vec!["pasted".to_string()], "{query}");
+    }
+    // Prose that mentions the words is not code.
+    assert!(find_code("routing")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn find_past_its_timeout_exits_5() -> Result<()> {
+    let temp = tempdir()?;
+    let batch: String = (0..5000)
+        .map(|n| {
+            format!(
+                "{{\"key\":\"deploys/{n}\",\"body\":\"Deploy {n} went out behind a canary.\"}}\n"
+            )
+        })
+        .collect();
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "put", "--batch", "--fast-import"])
+        .write_stdin(batch)
+        .assert()
+        .success();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "find",
+            "canary",
+            "--timeout",
+            "1",
+        ])
+        .assert()
+        .code(5);
+    let error: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(error["error"]["code"], "timeout");
+
+    // A generous limit lets the same search finish.
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "find", "canary", "--timeout", "60000"])
+        .assert()
+        .success();
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+fn keys(home: &std::path::Path, project: &str) -> Result<Vec<String>> {
+    let assert = context(home)
+        .args(["--project", project, "--json", "ls", "--fields", "key"])
+        .assert()
+        .success();
+    let docs: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let mut keys: Vec<String> = docs
+        .iter()
+        .map(|doc| doc["key"].as_str().unwrap().to_string())
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+#[test]
+fn fixtures_load_the_same_corpus_for_the_same_seed() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = context(temp.path())
+        .args(["--project", "one", "--json", "fixtures", "load", "small"])
+        .assert()
+        .success();
+    let report: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(report["fixture"], "small");
+    assert_eq!(report["seed"], 42);
+    assert_eq!(report["documents"], 12);
+
+    let assert = context(temp.path())
+        .args([
+            "--project",
+            "two",
+            "fixtures",
+            "load",
+            "small",
+            "--seed",
+            "42",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("Loaded fixture small (seed 42) into project two: 12 document(s)"),
+        "{stdout}"
+    );
+    let one = keys(temp.path(), "one")?;
+    assert_eq!(one.len(), 12);
+    assert_eq!(one, keys(temp.path(), "two")?);
+
+    context(temp.path())
+        .args([
+            "--project",
+            "three",
+            "fixtures",
+            "load",
+            "small",
+            "--seed",
+            "7",
+        ])
+        .assert()
+        .success();
+    assert_ne!(one, keys(temp.path(), "three")?);
+
+    // A second load into the same project would mix two corpora.
+    let assert = context(temp.path())
+        .args(["--project", "one", "fixtures", "load", "small"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("already has 12 document(s)"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn fixtures_list_names_each_fixture() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = context(temp.path())
+        .args(["--json", "fixtures", "list"])
+        .assert()
+        .success();
+    let fixtures: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let names: Vec<&str> = fixtures
+        .iter()
+        .map(|fixture| fixture["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["small", "demo", "large"]);
+
+    let assert = context(temp.path())
+        .args(["fixtures", "load", "huge"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Unknown fixture \"huge\""), "{stderr}");
+    Ok(())
+}
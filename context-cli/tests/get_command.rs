@@ -1,11 +1,25 @@
+mod common;
+
+use std::{thread, time::Duration};
+
 use anyhow::Result;
 use assert_cmd::Command;
+use chrono::{SecondsFormat, Utc};
 use context_core::Document;
 use tempfile::tempdir;
 
+use common::put;
+
 #[test]
 fn get_returns_json_by_key() -> Result<()> {
     let temp = tempdir()?;
+    put(
+        temp.path(),
+        "demo-project",
+        "test-key",
+        "stored body for test-key",
+    );
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
@@ -34,17 +48,20 @@ fn get_returns_json_by_key() -> Result<()> {
 #[test]
 fn get_prints_markdown_when_not_json() -> Result<()> {
     let temp = tempdir()?;
+    let stored = put(temp.path(), "default", "doc-123", "markdown body");
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
-        .args(["get", "--id", "doc-123"])
+        .args(["get", "--id", &stored.id.0])
         .assert()
         .success();
 
     let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
     assert!(stdout.contains("Project: default"));
-    assert!(stdout.contains("Document ID: doc-123"));
-    assert!(stdout.contains("doc-123"));
+    assert!(stdout.contains(&format!("Document ID: {}", stored.id.0)));
+    assert!(stdout.contains("Key: doc-123"));
+    assert!(stdout.contains("markdown body"));
 
     Ok(())
 }
@@ -66,3 +83,143 @@ fn get_requires_key_or_id() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn get_missing_key_exits_with_not_found_code() -> Result<()> {
+    let temp = tempdir()?;
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["get", "--key", "missing"])
+        .assert()
+        .failure();
+    let output = assert.get_output();
+    assert_eq!(output.status.code(), Some(3));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "get", "--key", "missing"])
+        .assert()
+        .failure();
+    let output = assert.get_output();
+    assert_eq!(output.status.code(), Some(3));
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(value["error"]["code"], "not_found");
+
+    Ok(())
+}
+
+#[test]
+fn get_by_id_skips_deleted_documents() -> Result<()> {
+    let temp = tempdir()?;
+    let stored = put(temp.path(), "default", "short-lived", "body");
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["rm", "--id", &stored.id.0])
+        .assert()
+        .success();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "get", "--id", &stored.id.0])
+        .assert()
+        .failure();
+    let output = assert.get_output();
+    assert_eq!(output.status.code(), Some(3));
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(value["error"]["code"], "not_found");
+    assert!(value["error"]["message"]
+        .as_str()
+        .unwrap_or_default()
+        .contains(&stored.id.0));
+
+    Ok(())
+}
+
+#[test]
+fn get_as_of_returns_revision_current_at_that_time() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "ops", "runbook", "restart the api");
+    thread::sleep(Duration::from_millis(20));
+    let incident = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+    thread::sleep(Duration::from_millis(20));
+    put(
+        temp.path(),
+        "ops",
+        "runbook",
+        "restart the api, then the worker",
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "ops",
+            "--json",
+            "get",
+            "--key",
+            "runbook",
+            "--as-of",
+            &incident,
+        ])
+        .assert()
+        .success();
+    let document: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(document.version, 1);
+    assert_eq!(document.body_markdown, "restart the api");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "ops",
+            "get",
+            "--key",
+            "runbook",
+            "--as-of",
+            "2000-01-01",
+        ])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("no revision at or before"),
+        "stderr: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn get_reports_tokens_and_enforces_max_tokens() -> Result<()> {
+    let temp = tempdir()?;
+    put(
+        temp.path(),
+        "default",
+        "long",
+        &"many words here ".repeat(50),
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "get", "--key", "long"])
+        .assert()
+        .success();
+    let value: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    let tokens = value["tokens"].as_u64().expect("tokens field");
+    assert!(tokens >= 150, "tokens: {tokens}");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["get", "--key", "long", "--max-tokens", "10"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("over --max-tokens 10"), "stderr: {stderr}");
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+#[test]
+fn history_lists_versions_newest_first() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "runbook", "step one");
+    put(temp.path(), "demo", "runbook", "step one\nstep two");
+    put(
+        temp.path(),
+        "demo",
+        "runbook",
+        "step one\nstep two\nstep three",
+    );
+
+    let assert = context(temp.path())
+        .args(["--json", "history", "--key", "runbook"])
+        .assert()
+        .success();
+    let entries: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let versions: Vec<u64> = entries
+        .iter()
+        .map(|entry| entry["version"].as_u64().unwrap())
+        .collect();
+    assert_eq!(versions, vec![3, 2, 1]);
+    assert_eq!(entries[0]["source"], "User");
+    assert_eq!(entries[0]["deleted"], false);
+    assert!(entries[0]["updated_at"].is_string());
+
+    let assert = context(temp.path())
+        .args(["history", "--key", "runbook"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "History of runbook");
+    assert!(lines[1].starts_with("v3 "), "{stdout}");
+    assert!(lines[3].starts_with("v1 "), "{stdout}");
+    Ok(())
+}
+
+#[test]
+fn get_version_returns_an_older_revision() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "runbook", "first draft");
+    put(temp.path(), "demo", "runbook", "second draft");
+
+    let assert = context(temp.path())
+        .args(["--json", "get", "--key", "runbook", "--version", "1"])
+        .assert()
+        .success();
+    let doc: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(doc["version"], 1);
+    assert_eq!(doc["body_markdown"], "first draft");
+
+    let assert = context(temp.path())
+        .args(["get", "--key", "runbook", "--version", "9"])
+        .assert()
+        .code(3);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("has no version 9; the latest is 2"),
+        "{stderr}"
+    );
+
+    context(temp.path())
+        .args(["get", "--key", "runbook", "--version", "1"])
+        .args(["--as-of", "2024-01-01"])
+        .assert()
+        .failure();
+    Ok(())
+}
@@ -0,0 +1,250 @@
+use std::fs;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn json(cmd: &mut Command) -> Result<Value> {
+    let assert = cmd.assert().success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+fn statuses(summary: &Value) -> Vec<(String, String)> {
+    summary["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| {
+            (
+                file["key"].as_str().unwrap().to_string(),
+                file["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn import_keys_files_by_path_and_reports_what_changed() -> Result<()> {
+    let home = tempdir()?;
+    let notes = tempdir()?;
+    fs::create_dir_all(notes.path().join("ops"))?;
+    fs::create_dir_all(notes.path().join(".git"))?;
+    fs::write(
+        notes.path().join("ops/deploy.md"),
+        "---\ntags: [ops, runbook]\n---\n# Deploy\n\nCanary first.\n",
+    )?;
+    fs::write(
+        notes.path().join("readme.markdown"),
+        "---\nkey: intro\ntitle: Start here\n---\nWelcome.\n",
+    )?;
+    fs::write(notes.path().join("todo.txt"), "not markdown")?;
+    fs::write(notes.path().join(".git/HEAD.md"), "hidden")?;
+    let dir = notes.path().to_str().unwrap();
+
+    let preview = json(context(home.path()).args(["--json", "import", dir, "--dry-run"]))?;
+    assert_eq!(preview["dry_run"], true);
+    assert_eq!(preview["created"], 2);
+    context(home.path())
+        .args(["get", "--key", "intro"])
+        .assert()
+        .code(3);
+
+    let summary = json(context(home.path()).args(["--json", "import", dir]))?;
+    assert_eq!(
+        statuses(&summary),
+        [
+            ("ops/deploy".to_string(), "created".to_string()),
+            ("intro".to_string(), "created".to_string())
+        ]
+    );
+    let doc = json(context(home.path()).args(["--json", "get", "--key", "ops/deploy"]))?;
+    assert_eq!(doc["source"], "Import");
+    assert_eq!(doc["title"], "Deploy");
+    assert_eq!(doc["tags"], serde_json::json!(["ops", "runbook"]));
+    assert_eq!(doc["body_markdown"], "# Deploy\n\nCanary first.\n");
+    let doc = json(context(home.path()).args(["--json", "get", "--key", "intro"]))?;
+    assert_eq!(doc["title"], "Start here");
+
+    fs::write(
+        notes.path().join("ops/deploy.md"),
+        "# Deploy\n\nCanary, then all.\n",
+    )?;
+    let summary = json(context(home.path()).args(["--json", "import", dir]))?;
+    assert_eq!(
+        (summary["updated"].clone(), summary["skipped"].clone()),
+        (1.into(), 1.into())
+    );
+
+    let summary = json(context(home.path()).args(["--json", "import", dir, "--glob", "*.txt"]))?;
+    assert_eq!(
+        statuses(&summary),
+        [("todo.txt".to_string(), "created".to_string())]
+    );
+    Ok(())
+}
+
+#[test]
+fn import_refuses_two_files_with_the_same_key_and_writes_nothing() -> Result<()> {
+    let home = tempdir()?;
+    let notes = tempdir()?;
+    fs::write(notes.path().join("a.md"), "a")?;
+    fs::write(notes.path().join("b.md"), "---\nkey: a\n---\nb")?;
+
+    let assert = context(home.path())
+        .args(["import", notes.path().to_str().unwrap()])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("a.md and b.md both map to key a"),
+        "{stderr}"
+    );
+    context(home.path())
+        .args(["get", "--key", "a"])
+        .assert()
+        .code(3);
+    Ok(())
+}
+
+#[test]
+fn import_merges_exported_json_lines_by_id_and_version() -> Result<()> {
+    let (source, target, out) = (tempdir()?, tempdir()?, tempdir()?);
+    let put = |home: &std::path::Path, key: &str, body: &str| -> Result<Value> {
+        json(
+            context(home)
+                .args(["--json", "put", "--key", key])
+                .write_stdin(body.to_string()),
+        )
+    };
+    let export = |name: &str| -> Result<String> {
+        let path = out.path().join(name);
+        context(source.path())
+            .args([
+                "export",
+                "--format",
+                "jsonl",
+                "--out",
+                path.to_str().unwrap(),
+            ])
+            .assert()
+            .success();
+        Ok(path.to_str().unwrap().to_string())
+    };
+    put(source.path(), "a", "one")?;
+    let a = put(source.path(), "a", "two")?;
+    put(source.path(), "b", "from the source")?;
+    let old = export("old.jsonl")?;
+    put(target.path(), "b", "written here")?;
+
+    let summary = json(context(target.path()).args(["--json", "import", &old]))?;
+    assert_eq!(
+        statuses(&summary),
+        [
+            ("a".to_string(), "created".to_string()),
+            ("b".to_string(), "conflict".to_string())
+        ]
+    );
+    assert_eq!(summary["conflicts"], 1);
+    assert!(summary["files"][1]["conflict"]
+        .as_str()
+        .unwrap()
+        .contains("key belongs to document"));
+    let copy = json(context(target.path()).args(["--json", "get", "--key", "a"]))?;
+    assert_eq!((&copy["id"], &copy["version"]), (&a["id"], &a["version"]));
+    let b = json(context(target.path()).args(["--json", "get", "--key", "b"]))?;
+    assert_eq!(b["body_markdown"], "written here");
+
+    put(source.path(), "a", "three")?;
+    let new = export("new.jsonl")?;
+    let summary = json(context(target.path()).args(["--json", "import", &new]))?;
+    assert_eq!(summary["files"][0]["status"], "updated");
+    let summary = json(context(target.path()).args(["--json", "import", &old]))?;
+    assert_eq!(summary["files"][0]["status"], "kept");
+    let summary = json(context(target.path()).args(["--json", "import", &new]))?;
+    assert_eq!(summary["files"][0]["status"], "skipped");
+
+    // Both sides moved on to version 4 with different text: neither is overwritten.
+    put(source.path(), "a", "four from the source")?;
+    put(target.path(), "a", "four from here")?;
+    let newest = export("newest.jsonl")?;
+    let summary = json(context(target.path()).args(["--json", "import", &newest]))?;
+    assert_eq!(summary["files"][0]["status"], "conflict");
+    assert_eq!(
+        summary["files"][0]["conflict"],
+        "version 4 differs from the local copy"
+    );
+    let a = json(context(target.path()).args(["--json", "get", "--key", "a"]))?;
+    assert_eq!(a["body_markdown"], "four from here");
+    Ok(())
+}
+
+#[test]
+fn import_refuses_json_lines_with_malformed_ids() -> Result<()> {
+    let (source, target) = (tempdir()?, tempdir()?);
+    context(source.path())
+        .args(["put", "--key", "a"])
+        .write_stdin("one")
+        .assert()
+        .success();
+    let path = source.path().join("out.jsonl");
+    context(source.path())
+        .args([
+            "export",
+            "--format",
+            "jsonl",
+            "--out",
+            path.to_str().unwrap(),
+        ])
+        .assert()
+        .success();
+    let mut doc: Value = serde_json::from_str(fs::read_to_string(&path)?.trim())?;
+    doc["id"] = "bad \"id\"".into();
+    fs::write(&path, format!("{doc}\n"))?;
+
+    let assert = context(target.path())
+        .args(["import", path.to_str().unwrap()])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("must be printable ASCII"), "{stderr}");
+    context(target.path())
+        .args(["get", "--key", "a"])
+        .assert()
+        .code(3);
+    Ok(())
+}
+
+#[test]
+fn fast_import_leaves_every_document_searchable() -> Result<()> {
+    let home = tempdir()?;
+    let notes = tempdir()?;
+    for n in 0..20 {
+        fs::write(
+            notes.path().join(format!("note-{n}.md")),
+            "# Note\n\nbulkterm\n",
+        )?;
+    }
+    let dir = notes.path().to_str().unwrap();
+    let summary = json(context(home.path()).args(["--json", "import", dir, "--fast-import"]))?;
+    assert_eq!(summary["created"], 20);
+
+    let batch = "{\"key\": \"note-0\", \"body\": \"rewritten\"}\n{\"key\": \"extra\", \"body\": \"bulkterm\"}\n";
+    context(home.path())
+        .args(["put", "--batch", "--fast-import"])
+        .write_stdin(batch)
+        .assert()
+        .success();
+
+    let hits = json(context(home.path()).args(["--json", "find", "bulkterm", "--limit", "50"]))?;
+    assert_eq!(hits.as_array().unwrap().len(), 20);
+    let hits = json(context(home.path()).args(["--json", "find", "rewritten"]))?;
+    assert_eq!(hits[0]["key"], "note-0");
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::{fs, path::Path, process::Command as StdCommand};
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &Path, cwd: &Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home)
+        .env_remove("CONTEXT_PROJECT")
+        .current_dir(cwd);
+    cmd
+}
+
+#[test]
+fn init_sets_up_home_with_the_repo_name_as_project() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let repo = temp.path().join("billing-service");
+    fs::create_dir_all(repo.join("src"))?;
+    let status = StdCommand::new("git")
+        .args(["init", "-q"])
+        .current_dir(&repo)
+        .status()?;
+    assert!(status.success());
+
+    let output = context(&home, &repo.join("src"))
+        .args(["--json", "init", "--agent-config", "codex"])
+        .output()?;
+    assert!(output.status.success(), "{:?}", output);
+    let payload: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(payload["project"], "billing-service");
+    assert_eq!(payload["replaced_config"], false);
+
+    assert!(home.join("db.sqlite").exists());
+    let config: Value = serde_json::from_str(&fs::read_to_string(home.join("config.json"))?)?;
+    assert_eq!(config["current"], "billing-service");
+    let agents = fs::read_to_string(repo.join("AGENTS.md"))?;
+    assert!(agents.starts_with("# context - Agent Usage"));
+    assert!(!repo.join("CLAUDE.md").exists());
+
+    let output = context(&home, &repo)
+        .args(["--json", "project", "list"])
+        .output()?;
+    let projects: Value = serde_json::from_slice(&output.stdout)?;
+    assert!(projects
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|stats| stats["id"] == "billing-service"));
+    Ok(())
+}
+
+#[test]
+fn init_refuses_to_rerun_without_force() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    context(&home, temp.path())
+        .args(["init", "--project", "notes"])
+        .assert()
+        .success();
+
+    let output = context(&home, temp.path())
+        .args(["init", "--project", "other"])
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("already initialized"), "{stderr}");
+
+    let output = context(&home, temp.path())
+        .args(["--json", "init", "--force", "--project", "other"])
+        .output()?;
+    assert!(output.status.success());
+    let payload: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(payload["project"], "other");
+    assert_eq!(payload["replaced_config"], true);
+    let backup: Value = serde_json::from_str(&fs::read_to_string(home.join("config.json.bak"))?)?;
+    assert_eq!(backup["current"], "notes");
+    Ok(())
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put;
+
+#[test]
+fn put_normalizes_keys_when_enabled_in_config() -> Result<()> {
+    let temp = tempdir()?;
+    fs::write(
+        temp.path().join("config.json"),
+        r#"{"current":null,"known":["default"],"keys":{"normalize":true}}"#,
+    )?;
+
+    let first = put(temp.path(), "demo", "Runbooks/Deploy", "v1");
+    assert_eq!(first.key.as_deref(), Some("runbooks/deploy"));
+    assert_eq!(first.version, 1);
+
+    let second = put(temp.path(), "demo", "RUNBOOKS/DEPLOY", "v2");
+    assert_eq!(second.id.0, first.id.0, "same document is updated");
+    assert_eq!(second.version, 2);
+
+    Ok(())
+}
+
+#[test]
+fn keys_normalize_reports_collisions() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "Notes", "one");
+    put(temp.path(), "demo", "notes", "two");
+    put(temp.path(), "demo", "Ideas", "three");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "keys",
+            "normalize",
+            "--dry-run",
+        ])
+        .assert()
+        .success();
+
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["dry_run"], true);
+    assert_eq!(value["renamed"][0]["from"], "Ideas");
+    assert_eq!(value["renamed"][0]["to"], "ideas");
+    assert_eq!(value["collisions"][0]["normalized"], "notes");
+
+    Ok(())
+}
+
+#[test]
+fn renamed_keys_redirect_reads_to_the_new_key() -> Result<()> {
+    let temp = tempdir()?;
+    let original = put(temp.path(), "demo", "notes/old", "hello");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "keys",
+            "rename",
+            "notes/old",
+            "notes/new",
+        ])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["to"], "notes/new");
+    assert_eq!(value["version"], 2);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "--json", "get", "--key", "notes/old"])
+        .assert()
+        .success();
+    let doc: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(doc.id.0, original.id.0);
+    assert_eq!(doc.key.as_deref(), Some("notes/new"));
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("notes/old now redirects to notes/new"));
+
+    // Writing to the old key again takes it back from the redirect.
+    let reclaimed = put(temp.path(), "demo", "notes/old", "second life");
+    assert_ne!(reclaimed.id.0, original.id.0);
+
+    Ok(())
+}
+
+#[test]
+fn rename_refuses_to_overwrite_a_live_key() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "a", "one");
+    put(temp.path(), "demo", "b", "two");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "keys", "rename", "a", "b"])
+        .assert()
+        .code(4);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("already used"));
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "--json",
+            "mv",
+            "--key",
+            "a",
+            "--to",
+            "b",
+        ])
+        .assert()
+        .code(4);
+    let error: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(error["error"]["code"], "conflict");
+
+    Ok(())
+}
+
+#[test]
+fn mv_renames_with_or_without_an_alias() -> Result<()> {
+    let temp = tempdir()?;
+    let context = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", temp.path())
+            .args(["--project", "demo"]);
+        cmd
+    };
+    let original = put(temp.path(), "demo", "draft", "plan");
+
+    let assert = context()
+        .args(["--json", "mv", "--key", "draft", "--to", "plan"])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["alias"], true);
+    assert_eq!(value["version"], 2);
+    let assert = context()
+        .args(["--json", "get", "--key", "draft"])
+        .assert()
+        .success();
+    let doc: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(doc.id.0, original.id.0);
+    assert_eq!(doc.key.as_deref(), Some("plan"));
+
+    let assert = context()
+        .args(["mv", "--key", "plan", "--to", "final", "--no-alias"])
+        .assert()
+        .success();
+    assert!(String::from_utf8_lossy(&assert.get_output().stdout).contains("old key freed"));
+    context().args(["get", "--key", "plan"]).assert().code(3);
+    // The earlier alias followed the document to its new key.
+    let assert = context()
+        .args(["--json", "get", "--key", "draft"])
+        .assert()
+        .success();
+    let doc: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(doc.key.as_deref(), Some("final"));
+    assert_eq!(doc.version, 3);
+    assert_ne!(put(temp.path(), "demo", "plan", "new").id.0, original.id.0);
+
+    // Each rename is a revision of its own; the first still carries the old key.
+    let assert = context()
+        .args(["--json", "get", "--key", "final", "--version", "1"])
+        .assert()
+        .success();
+    let first: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(first.key.as_deref(), Some("draft"));
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn stdout(cmd: &mut Command) -> Result<String> {
+    let assert = cmd.assert().success();
+    Ok(String::from_utf8(assert.get_output().stdout.clone())?)
+}
+
+#[test]
+fn links_and_backlinks_follow_wiki_links_and_uris() -> Result<()> {
+    let home = tempdir()?;
+    context(home.path())
+        .args(["put", "--key", "plan"])
+        .write_stdin("Ship after [[checklist]]; DNS lives in context://infra/dns.")
+        .assert()
+        .success();
+    context(home.path())
+        .args(["put", "--key", "checklist", "--title", "Launch checklist"])
+        .write_stdin("- [ ] canary")
+        .assert()
+        .success();
+
+    let links = stdout(context(home.path()).args(["links", "--key", "plan"]))?;
+    assert!(links.contains("- checklist: Launch checklist"), "{links}");
+    assert!(links.contains("- infra/dns (missing)"), "{links}");
+
+    let backlinks: Value = serde_json::from_str(&stdout(context(home.path()).args([
+        "--json",
+        "backlinks",
+        "--key",
+        "checklist",
+    ]))?)?;
+    assert_eq!(backlinks.as_array().map(Vec::len), Some(1));
+    assert_eq!(backlinks[0]["key"], "plan");
+
+    let nothing = stdout(context(home.path()).args(["backlinks", "--key", "plan"]))?;
+    assert_eq!(nothing.trim(), "Links to plan");
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::fs;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+const LOG: &str = r#"{"timestamp":"2024-06-01T09:30:00.123456Z","level":"INFO","fields":{"message":"Command start","project":"demo","command":"put"},"target":"context_cli","span":{"command":"put","project":"demo","name":"cli.put"},"spans":[{"scenario_id":"cli-042","command":"put","project":"demo","name":"context"},{"command":"put","project":"demo","name":"cli.put"}]}
+{"timestamp":"2024-06-01T09:30:00.200000Z","level":"WARN","fields":{"message":"Key rules rejected put","key":"bad key"},"target":"context_cli","span":{"command":"put","project":"demo","name":"cli.put"},"spans":[{"scenario_id":"cli-042","command":"put","project":"demo","name":"context"},{"command":"put","project":"demo","name":"cli.put"}]}
+{"timestamp":"2024-06-01T09:31:00Z","level":"INFO","fields":{"message":"Ls command invoked"},"target":"context_cli","span":{"command":"ls","project":"demo","name":"cli.ls"},"spans":[{"command":"ls","project":"demo","name":"cli.ls"}]}
+"#;
+
+fn logs_cmd(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).env_remove("CONTEXT_LOG_DIR");
+    cmd
+}
+
+fn stdout_lines(output: &std::process::Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn logs_pretty_renders_one_readable_line_per_entry() -> Result<()> {
+    let temp = tempdir()?;
+    fs::create_dir_all(temp.path().join("logs"))?;
+    fs::write(temp.path().join("logs/context-cli.jsonl"), LOG)?;
+
+    let output = logs_cmd(temp.path()).args(["logs", "--pretty"]).output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        stdout_lines(&output),
+        vec![
+            "2024-06-01 09:30:00.123 INFO  cli.put{command=put project=demo}: Command start",
+            r#"2024-06-01 09:30:00.200 WARN  cli.put{command=put project=demo}: Key rules rejected put key="bad key""#,
+            "2024-06-01 09:31:00.000 INFO  cli.ls{command=ls project=demo}: Ls command invoked",
+        ],
+        "the logs command's own entries are left out"
+    );
+    Ok(())
+}
+
+#[test]
+fn logs_filters_by_scenario_level_and_tail() -> Result<()> {
+    let temp = tempdir()?;
+    fs::create_dir_all(temp.path().join("logs"))?;
+    fs::write(temp.path().join("logs/context-cli.jsonl"), LOG)?;
+
+    let output = logs_cmd(temp.path())
+        .args(["logs", "--scenario", "cli-042"])
+        .output()?;
+    let lines = stdout_lines(&output);
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.contains("cli-042")));
+
+    let output = logs_cmd(temp.path())
+        .args(["logs", "--level", "warn", "--json"])
+        .output()?;
+    let entries: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(entries.as_array().map(Vec::len), Some(1));
+    assert_eq!(entries[0]["fields"]["key"], "bad key");
+
+    let output = logs_cmd(temp.path())
+        .args(["logs", "--tail", "1"])
+        .output()?;
+    let lines = stdout_lines(&output);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].contains("Ls command invoked"));
+    Ok(())
+}
+
+#[test]
+fn logs_reports_a_missing_file() -> Result<()> {
+    let temp = tempdir()?;
+    let output = logs_cmd(temp.path())
+        .args(["logs", "--app", "web"])
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No log file at"), "{stderr}");
+    assert!(stderr.contains("context-web.jsonl"), "{stderr}");
+    Ok(())
+}
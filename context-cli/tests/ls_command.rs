@@ -1,11 +1,25 @@
+mod common;
+
 use anyhow::Result;
 use assert_cmd::Command;
 use context_core::Document;
 use tempfile::tempdir;
 
+use common::put;
+
 #[test]
 fn ls_outputs_json_list_for_project() -> Result<()> {
     let temp = tempdir()?;
+    for i in 1..=3 {
+        put(
+            temp.path(),
+            "demo-project",
+            &format!("doc-{i}"),
+            &format!("This is listed document {i}"),
+        );
+    }
+    put(temp.path(), "other-project", "doc-x", "not listed here");
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
@@ -28,6 +42,8 @@ fn ls_outputs_json_list_for_project() -> Result<()> {
 #[test]
 fn ls_prints_human_readable_output() -> Result<()> {
     let temp = tempdir()?;
+    put(temp.path(), "default", "doc-1", "first");
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
@@ -42,3 +58,352 @@ fn ls_prints_human_readable_output() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn ls_streams_ndjson_one_document_per_line() -> Result<()> {
+    let temp = tempdir()?;
+    for i in 1..=3 {
+        put(temp.path(), "demo", &format!("doc-{i}"), "body");
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "ls", "--format", "ndjson"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for line in lines {
+        let doc: Document = serde_json::from_str(line)?;
+        assert_eq!(doc.project, "demo");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ls_key_only_streams_keys_as_ndjson() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo-project", "a", "first");
+    put(temp.path(), "demo-project", "b", "second");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo-project",
+            "ls",
+            "--format",
+            "ndjson",
+            "--key-only",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(lines, vec![r#"{"key":"a"}"#, r#"{"key":"b"}"#]);
+
+    Ok(())
+}
+
+#[test]
+fn ls_skips_excluded_namespaces_and_tags() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo-project", "keep", "kept");
+    for (key, flag, value) in [
+        ("tmp", "--namespace", "scratch"),
+        ("old", "--tag", "legacy"),
+    ] {
+        Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args([
+                "--project",
+                "demo-project",
+                "put",
+                "--key",
+                key,
+                flag,
+                value,
+            ])
+            .write_stdin("excluded")
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo-project",
+            "--json",
+            "ls",
+            "--exclude-namespace",
+            "scratch",
+            "--not-tag",
+            "legacy",
+        ])
+        .assert()
+        .success();
+    let docs: Vec<Document> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let keys: Vec<_> = docs.iter().filter_map(|doc| doc.key.as_deref()).collect();
+    assert_eq!(keys, vec!["keep"]);
+
+    Ok(())
+}
+
+#[test]
+fn ls_filters_by_prefix_and_pages_with_a_cursor() -> Result<()> {
+    let temp = tempdir()?;
+    for key in ["auth/tokens", "auth/sessions", "auth/roles", "deploy"] {
+        put(temp.path(), "default", key, "body");
+    }
+
+    let ls = |args: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args(["--json", "ls"])
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .clone()
+    };
+
+    let first = ls(&["--prefix", "auth/", "--limit", "2"]);
+    let page: Vec<Document> = serde_json::from_slice(&first.stdout)?;
+    assert_eq!(page.len(), 2);
+    let stderr = String::from_utf8_lossy(&first.stderr);
+    let cursor = stderr
+        .split("--cursor ")
+        .nth(1)
+        .expect("a next-page cursor")
+        .trim()
+        .to_string();
+
+    let second = ls(&["--prefix", "auth/", "--limit", "2", "--cursor", &cursor]);
+    let rest: Vec<Document> = serde_json::from_slice(&second.stdout)?;
+    assert_eq!(rest.len(), 1);
+    assert!(!String::from_utf8_lossy(&second.stderr).contains("--cursor"));
+
+    let mut keys: Vec<_> = page
+        .iter()
+        .chain(&rest)
+        .map(|doc| doc.key.clone().unwrap())
+        .collect();
+    keys.sort();
+    assert_eq!(keys, vec!["auth/roles", "auth/sessions", "auth/tokens"]);
+
+    Ok(())
+}
+
+#[test]
+fn ls_sorts_by_key_or_creation() -> Result<()> {
+    let temp = tempdir()?;
+    for key in ["beta", "gamma", "alpha"] {
+        put(temp.path(), "default", key, "body");
+    }
+    // Rewriting beta makes it the most recently updated, not the most recently created.
+    put(temp.path(), "default", "beta", "body, rewritten");
+
+    let ls = |args: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .arg("ls")
+            .args(args)
+            .assert()
+    };
+    let listed = |args: &[&str]| -> Result<Vec<String>> {
+        let output = ls(&[&["--json"], args].concat())
+            .success()
+            .get_output()
+            .clone();
+        let documents: Vec<Document> = serde_json::from_slice(&output.stdout)?;
+        Ok(documents.into_iter().map(|doc| doc.key.unwrap()).collect())
+    };
+
+    assert_eq!(listed(&[])?, vec!["beta", "alpha", "gamma"]);
+    assert_eq!(
+        listed(&["--sort", "created"])?,
+        vec!["alpha", "gamma", "beta"]
+    );
+    assert_eq!(listed(&["--sort", "key"])?, vec!["alpha", "beta", "gamma"]);
+
+    let text = ls(&["--sort", "key", "--limit", "2"]).success();
+    let stdout = String::from_utf8(text.get_output().stdout.clone())?;
+    let alpha = stdout.find("alpha").expect("alpha listed");
+    let beta = stdout.find("beta").expect("beta listed");
+    assert!(alpha < beta, "{stdout}");
+    assert!(!stdout.contains("gamma"), "{stdout}");
+    assert!(stdout.contains("More: context ls --cursor "), "{stdout}");
+
+    let refused = ls(&["--sort", "size"]).failure();
+    let stderr = String::from_utf8(refused.get_output().stderr.clone())?;
+    assert!(stderr.contains("Use updated, created, or key"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn ls_count_reports_matches_without_listing() -> Result<()> {
+    let temp = tempdir()?;
+    for key in ["auth/tokens", "auth/sessions", "deploy"] {
+        put(temp.path(), "default", key, "body");
+    }
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["ls", "--count", "--prefix", "auth/"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "2");
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "ls", "--count"])
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let payload: serde_json::Value = serde_json::from_slice(&out.stdout)?;
+    assert_eq!(payload["count"], 3);
+    Ok(())
+}
+
+#[test]
+fn ls_csv_lists_metadata_and_quotes_when_needed() -> Result<()> {
+    let temp = tempdir()?;
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "put",
+            "--key",
+            "plan",
+            "--title",
+            "Q3 plan, \"draft\"",
+            "--tag",
+            "ops,roadmap",
+        ])
+        .write_stdin("12345")
+        .assert()
+        .success();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "ls", "--format", "csv"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let lines: Vec<&str> = stdout.split("\r\n").collect();
+    assert_eq!(
+        lines[0],
+        "key,title,tags,created_at,updated_at,size,version"
+    );
+    assert!(
+        lines[1].starts_with(r#"plan,"Q3 plan, ""draft""","ops, roadmap","#),
+        "{stdout}"
+    );
+    assert!(lines[1].ends_with(",5,1"), "{stdout}");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "ls",
+            "--format",
+            "tsv",
+            "--fields",
+            "key,title,version",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert_eq!(
+        stdout,
+        "key\ttitle\tversion\r\nplan\tQ3 plan, \"draft\"\t1\r\n"
+    );
+
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--project",
+            "demo",
+            "ls",
+            "--format",
+            "csv",
+            "--fields",
+            "body_markdown",
+        ])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn ls_filters_on_metadata_set_by_put() -> Result<()> {
+    let temp = tempdir()?;
+    let context = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", temp.path())
+            .args(["--project", "demo"]);
+        cmd
+    };
+    context()
+        .args([
+            "put",
+            "--key",
+            "incident",
+            "--meta",
+            "ticket=ABC-123",
+            "--meta",
+            "severity=high",
+        ])
+        .write_stdin("Replica lag")
+        .assert()
+        .success();
+    put(temp.path(), "demo", "other", "Cache misses");
+    let assert = context()
+        .args(["put", "--key", "incident", "--meta", "severity="])
+        .write_stdin("Replica lag, fixed")
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert!(stdout.contains("Meta: ticket=ABC-123\n"), "{stdout}");
+    assert!(!stdout.contains("severity"), "{stdout}");
+
+    let assert = context()
+        .args(["--json", "ls", "--meta", "ticket=ABC-123"])
+        .assert()
+        .success();
+    let docs: Vec<Document> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].key.as_deref(), Some("incident"));
+    assert_eq!(
+        serde_json::Value::Object(docs[0].metadata.clone()),
+        serde_json::json!({"ticket": "ABC-123"})
+    );
+
+    let assert = context()
+        .args(["ls", "--count", "--meta", "severity=high"])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8(assert.get_output().stdout.clone())?,
+        "0\n"
+    );
+
+    context()
+        .args(["ls", "--meta", "ticket"])
+        .assert()
+        .failure();
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Builds without the default features keep every command and explain what is missing.
+#![cfg(not(all(feature = "http", feature = "serve", feature = "smtp")))]
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+fn assert_needs_feature(assert: assert_cmd::assert::Assert, feature: &str) {
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(
+        stderr.contains("is not part of this build of context"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains(&format!("--features {feature}")),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+#[cfg(not(feature = "serve"))]
+fn serve_readonly_needs_the_serve_feature() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = context(temp.path())
+        .args(["serve-readonly", "--port", "0"])
+        .assert()
+        .failure();
+    assert_needs_feature(assert, "serve");
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "http"))]
+fn openai_summaries_need_the_http_feature() -> Result<()> {
+    let temp = tempdir()?;
+    context(temp.path())
+        .args(["put", "--key", "notes"])
+        .write_stdin("body")
+        .assert()
+        .success();
+    let assert = context(temp.path())
+        .args(["summarize", "--key", "notes"])
+        .assert()
+        .failure();
+    assert_needs_feature(assert, "http");
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "smtp"))]
+fn smtp_channels_need_the_smtp_feature() -> Result<()> {
+    let temp = tempdir()?;
+    let config = serde_json::json!({
+        "notify": {"channels": [{
+            "type": "smtp",
+            "host": "127.0.0.1",
+            "from": "context@example.com",
+            "to": ["team@example.com"]
+        }]}
+    });
+    std::fs::write(temp.path().join("config.json"), config.to_string())?;
+    let assert = context(temp.path())
+        .args(["notify", "test"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).into_owned();
+    assert!(
+        stderr.contains("1 of 1 notify channel(s) failed"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("--features smtp"), "stderr: {stderr}");
+    Ok(())
+}
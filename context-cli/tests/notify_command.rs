@@ -0,0 +1,171 @@
+use std::path::Path;
+#[cfg(any(feature = "http", feature = "smtp"))]
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+fn context(home: &Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+fn write_config(home: &Path, notify: serde_json::Value) -> Result<()> {
+    let config = serde_json::json!({ "notify": notify });
+    std::fs::write(home.join("config.json"), config.to_string())?;
+    Ok(())
+}
+
+/// Accept one webhook POST and return its body.
+#[cfg(feature = "http")]
+fn fake_slack() -> Result<(u16, thread::JoinHandle<Result<String>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let server = thread::spawn(move || -> Result<String> {
+        let (stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse()?;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+        )?;
+        Ok(String::from_utf8(body)?)
+    });
+    Ok((port, server))
+}
+
+/// Speak just enough SMTP to accept one message, and return its data.
+#[cfg(feature = "smtp")]
+fn fake_smtp() -> Result<(u16, thread::JoinHandle<Result<String>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let server = thread::spawn(move || -> Result<String> {
+        let (stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut stream = stream;
+        write!(stream, "220 localhost ESMTP\r\n")?;
+        let mut data = String::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let command = line.trim_end().to_ascii_uppercase();
+            if command.starts_with("EHLO") {
+                write!(stream, "250 localhost\r\n")?;
+            } else if command == "DATA" {
+                write!(stream, "354 go ahead\r\n")?;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line)?;
+                    if line == ".\r\n" {
+                        break;
+                    }
+                    data.push_str(&line);
+                }
+                write!(stream, "250 queued\r\n")?;
+            } else if command == "QUIT" {
+                write!(stream, "221 bye\r\n")?;
+                break;
+            } else {
+                write!(stream, "250 ok\r\n")?;
+            }
+        }
+        Ok(data)
+    });
+    Ok((port, server))
+}
+
+#[test]
+#[cfg(all(feature = "http", feature = "smtp"))]
+fn notify_test_delivers_through_slack_and_smtp() -> Result<()> {
+    let temp = tempdir()?;
+    let (slack_port, slack) = fake_slack()?;
+    let (smtp_port, smtp) = fake_smtp()?;
+    write_config(
+        temp.path(),
+        serde_json::json!({
+            "channels": [
+                {"type": "slack"},
+                {
+                    "type": "smtp",
+                    "host": "127.0.0.1",
+                    "port": smtp_port,
+                    "tls": "none",
+                    "from": "context@example.com",
+                    "to": ["team@example.com"]
+                }
+            ]
+        }),
+    )?;
+
+    let assert = context(temp.path())
+        .env(
+            "CONTEXT_SLACK_WEBHOOK_URL",
+            format!("http://127.0.0.1:{slack_port}/hook"),
+        )
+        .args(["--project", "demo", "--json", "notify", "test"])
+        .args(["--message", "hello team"])
+        .assert()
+        .success();
+    let payload: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(payload["delivered"], serde_json::json!(["slack", "smtp"]));
+
+    let posted: serde_json::Value = serde_json::from_str(&slack.join().unwrap()?)?;
+    assert_eq!(posted["text"], "*context: demo*\nhello team");
+    let mail = smtp.join().unwrap()?;
+    assert!(mail.contains("Subject: context: demo\r\n"), "mail: {mail}");
+    assert!(mail.contains("To: team@example.com\r\n"), "mail: {mail}");
+    assert!(mail.contains("hello team"), "mail: {mail}");
+    Ok(())
+}
+
+#[test]
+fn project_channels_replace_the_defaults() -> Result<()> {
+    let temp = tempdir()?;
+    write_config(
+        temp.path(),
+        serde_json::json!({
+            "channels": [{"type": "slack", "webhook_url_env": "UNSET_WEBHOOK_FOR_TEST"}],
+            "projects": {"quiet": []}
+        }),
+    )?;
+
+    let assert = context(temp.path())
+        .args(["--project", "quiet", "notify", "test"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("No notify channels"), "stderr: {stderr}");
+
+    // The default channel is misconfigured, so delivery fails rather than passing silently.
+    let assert = context(temp.path())
+        .args(["--project", "demo", "notify", "test"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("1 of 1 notify channel(s) failed"),
+        "stderr: {stderr}"
+    );
+    Ok(())
+}
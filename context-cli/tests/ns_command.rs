@@ -0,0 +1,75 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put_with;
+
+fn context(home: &std::path::Path, args: &[&str]) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(args);
+    cmd
+}
+
+fn list(home: &std::path::Path) -> Result<Value> {
+    let assert = context(home, &["--json", "ns", "list"]).assert().success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+#[test]
+fn ns_lists_counts_and_renames_typos() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(temp.path(), "a", "body", &["--namespace", "runbooks"]);
+    put_with(temp.path(), "b", "body", &["--namespace", "runbok"]);
+    context(temp.path(), &["ns", "create", "decisions"])
+        .assert()
+        .success();
+
+    let namespaces = list(temp.path())?;
+    assert_eq!(
+        namespaces,
+        serde_json::json!([
+            {"name": "decisions", "documents": 0},
+            {"name": "runbok", "documents": 1},
+            {"name": "runbooks", "documents": 1},
+        ])
+    );
+
+    let assert = context(
+        temp.path(),
+        &["--json", "ns", "rename", "runbok", "runbooks"],
+    )
+    .assert()
+    .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["documents"], 1);
+
+    let namespaces = list(temp.path())?;
+    assert_eq!(
+        namespaces[1],
+        serde_json::json!({"name": "runbooks", "documents": 2})
+    );
+
+    Ok(())
+}
+
+#[test]
+fn ns_rm_refuses_non_empty_namespace_without_force() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(temp.path(), "a", "body", &["--namespace", "scratch"]);
+
+    let assert = context(temp.path(), &["ns", "rm", "scratch"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("still has 1 document"), "stderr: {stderr}");
+
+    context(temp.path(), &["ns", "rm", "scratch", "--force"])
+        .assert()
+        .success();
+    assert_eq!(list(temp.path())?, serde_json::json!([]));
+
+    Ok(())
+}
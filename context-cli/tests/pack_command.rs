@@ -0,0 +1,65 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put_with;
+
+#[test]
+fn pack_puts_explicit_keys_first_and_dedups_search_hits() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(
+        temp.path(),
+        "deploy/api",
+        "deploy the api with make deploy",
+        &[],
+    );
+    put_with(
+        temp.path(),
+        "deploy/worker",
+        "deploy the worker after the api",
+        &[],
+    );
+    put_with(temp.path(), "style", "use rustfmt", &[]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "pack", "--key", "style", "--query", "deploy", "--budget", "500",
+        ])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.starts_with("## style (v1)\n\nuse rustfmt\n\n"));
+    assert!(stdout.contains("## deploy/api (v1)"));
+    assert!(stdout.contains("## deploy/worker (v1)"));
+    assert_eq!(stdout.matches("## ").count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn pack_json_reports_skipped_documents() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(temp.path(), "small", "tiny note", &[]);
+    put_with(temp.path(), "large", &"lots of words ".repeat(100), &[]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "pack", "--key", "large,small", "--budget", "50"])
+        .assert()
+        .success();
+
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["budget"], 50);
+    assert_eq!(value["included"][0]["key"], "small");
+    assert_eq!(value["skipped"][0]["key"], "large");
+    assert!(value["tokens"].as_u64().unwrap() <= 50);
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put_with;
+
+#[test]
+fn expiry_rules_apply_on_put_and_via_policy_apply() -> Result<()> {
+    let temp = tempdir()?;
+    let before = put_with(temp.path(), "early", "agent chatter", &["--tag", "scratch"]);
+    assert_eq!(before.ttl_seconds, None);
+
+    std::fs::write(
+        temp.path().join("config.json"),
+        r#"{"current": null, "known": ["default"], "expiry": [{"tag": "scratch", "after": "14d"}]}"#,
+    )?;
+
+    let after = put_with(temp.path(), "late", "agent chatter", &["--tag", "scratch"]);
+    assert_eq!(after.ttl_seconds, Some(14 * 86_400));
+    let untagged = put_with(temp.path(), "design", "agent chatter", &["--tag", "keep"]);
+    assert_eq!(untagged.ttl_seconds, None);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "policy", "apply"])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    let changed = value["changed"].as_array().expect("changed list");
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0]["key"], "early");
+    assert_eq!(changed[0]["ttl_seconds"], 14 * 86_400);
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "get", "--key", "early"])
+        .assert()
+        .success();
+    let early: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(early.ttl_seconds, Some(14 * 86_400));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_expiry_rule_is_reported() -> Result<()> {
+    let temp = tempdir()?;
+    std::fs::write(
+        temp.path().join("config.json"),
+        r#"{"current": null, "known": ["default"], "expiry": [{"tag": "scratch", "after": "fortnight"}]}"#,
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["policy", "apply", "--dry-run"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Invalid expiry rule"), "stderr: {stderr}");
+
+    Ok(())
+}
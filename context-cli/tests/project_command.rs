@@ -3,6 +3,15 @@ use assert_cmd::Command;
 use context_core::Document;
 use tempfile::tempdir;
 
+/// Project names from `--json project list`.
+fn project_ids(stdout: &[u8]) -> Result<Vec<String>> {
+    let projects: Vec<serde_json::Value> = serde_json::from_slice(stdout)?;
+    Ok(projects
+        .iter()
+        .filter_map(|project| project["id"].as_str().map(str::to_string))
+        .collect())
+}
+
 #[test]
 fn current_defaults_to_default_project() -> Result<()> {
     let temp = tempdir()?;
@@ -87,10 +96,340 @@ fn list_returns_known_projects() -> Result<()> {
         .assert()
         .success();
 
-    let projects: Vec<String> = serde_json::from_slice(&list.get_output().stdout)?;
+    let projects = project_ids(&list.get_output().stdout)?;
     assert!(projects.contains(&"alpha".to_string()));
     assert!(projects.contains(&"bravo".to_string()));
     assert!(projects.contains(&"default".to_string()));
 
     Ok(())
 }
+
+#[test]
+fn aliases_resolve_wherever_a_project_is_accepted() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let context = |args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", &home)
+            .env_remove("CONTEXT_PROJECT")
+            .current_dir(temp.path())
+            .args(args);
+        cmd
+    };
+
+    context(&["project", "alias", "add", "ctx", "acme-main-repo"])
+        .assert()
+        .success();
+    context(&["--project", "ctx", "put", "--key", "note"])
+        .write_stdin("aliased write")
+        .assert()
+        .success();
+
+    let ls = context(&["--project", "acme-main-repo", "--json", "ls"])
+        .assert()
+        .success();
+    let docs: Vec<Document> = serde_json::from_slice(&ls.get_output().stdout)?;
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].project, "acme-main-repo");
+
+    let current = context(&["--json", "project", "current"])
+        .env("CONTEXT_PROJECT", "ctx")
+        .assert()
+        .success();
+    let payload: serde_json::Value = serde_json::from_slice(&current.get_output().stdout)?;
+    assert_eq!(payload["project"], "acme-main-repo");
+
+    context(&["project", "alias", "rm", "ctx"])
+        .assert()
+        .success();
+    let aliases = context(&["--json", "project", "alias", "list"])
+        .assert()
+        .success();
+    let payload: serde_json::Value = serde_json::from_slice(&aliases.get_output().stdout)?;
+    assert_eq!(payload, serde_json::json!({}));
+
+    Ok(())
+}
+
+#[test]
+fn export_and_import_round_trip_config_between_homes() -> Result<()> {
+    let temp = tempdir()?;
+    let old_home = temp.path().join("old");
+    let new_home = temp.path().join("new");
+    let context = |home: &std::path::Path, args: &[&str]| {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", home)
+            .env_remove("CONTEXT_PROJECT")
+            .current_dir(temp.path())
+            .args(args);
+        cmd
+    };
+
+    context(&old_home, &["project", "set", "acme-main-repo"])
+        .assert()
+        .success();
+    context(
+        &old_home,
+        &["project", "alias", "add", "ctx", "acme-main-repo"],
+    )
+    .assert()
+    .success();
+    let export = temp.path().join("config-export.json");
+    context(
+        &old_home,
+        &["project", "export", "--out", export.to_str().unwrap()],
+    )
+    .assert()
+    .success();
+
+    context(&new_home, &["project", "set", "side-project"])
+        .assert()
+        .success();
+    context(
+        &new_home,
+        &["project", "import", "--file", export.to_str().unwrap()],
+    )
+    .assert()
+    .success();
+
+    let current = context(&new_home, &["--json", "project", "current"])
+        .assert()
+        .success();
+    let payload: serde_json::Value = serde_json::from_slice(&current.get_output().stdout)?;
+    assert_eq!(payload["project"], "acme-main-repo");
+
+    let list = context(&new_home, &["--json", "project", "list"])
+        .assert()
+        .success();
+    let known = project_ids(&list.get_output().stdout)?;
+    assert!(
+        known.contains(&"side-project".to_string()),
+        "merge keeps local projects"
+    );
+    assert!(known.contains(&"acme-main-repo".to_string()));
+
+    let aliases = context(&new_home, &["--json", "project", "alias", "list"])
+        .assert()
+        .success();
+    let aliases: serde_json::Value = serde_json::from_slice(&aliases.get_output().stdout)?;
+    assert_eq!(aliases["ctx"], "acme-main-repo");
+
+    Ok(())
+}
+
+#[test]
+fn concurrent_updates_do_not_lose_writes() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    let bin = assert_cmd::cargo::cargo_bin!("context-cli");
+    // Create the store up front; this test is about config.json, not first-open migrations.
+    Command::new(bin)
+        .env("CONTEXT_HOME", &home)
+        .args(["project", "list"])
+        .assert()
+        .success();
+
+    let children = (0..8)
+        .map(|n| {
+            std::process::Command::new(bin)
+                .env("CONTEXT_HOME", &home)
+                .args([
+                    "project",
+                    "alias",
+                    "add",
+                    &format!("a{n}"),
+                    &format!("p{n}"),
+                ])
+                .stdout(std::process::Stdio::null())
+                .spawn()
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+    for mut child in children {
+        assert!(child.wait()?.success());
+    }
+
+    let config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(home.join("config.json"))?)?;
+    for n in 0..8 {
+        assert_eq!(config["aliases"][format!("a{n}")], format!("p{n}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn corrupt_config_is_restored_from_backup() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+
+    for project in ["alpha", "beta"] {
+        Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", &home)
+            .args(["project", "set", project])
+            .assert()
+            .success();
+    }
+    std::fs::write(home.join("config.json"), "{\"current\": \"be")?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .env_remove("CONTEXT_PROJECT")
+        .args(["--json", "project", "current"])
+        .assert()
+        .success();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("was corrupt"));
+    let payload: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    // The backup predates the last `project set`.
+    assert_eq!(payload["project"], "alpha");
+    assert_eq!(
+        std::fs::read_to_string(home.join("config.json.corrupt"))?,
+        "{\"current\": \"be"
+    );
+
+    // The restored file parses again, so the next command is quiet.
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", &home)
+        .args(["project", "list"])
+        .assert()
+        .success();
+    assert!(!String::from_utf8_lossy(&assert.get_output().stderr).contains("corrupt"));
+
+    Ok(())
+}
+
+#[test]
+fn edit_stores_project_metadata_in_the_registry() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .args([
+            "--json",
+            "project",
+            "edit",
+            "payments",
+            "--name",
+            "Payments API",
+            "--description",
+            "Billing backend",
+            "--setting",
+            "owner=team-pay",
+        ])
+        .assert()
+        .success();
+    let edited: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(edited["id"], "payments");
+    assert_eq!(edited["display_name"], "Payments API");
+    assert_eq!(edited["settings"]["owner"], "team-pay");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .args(["project", "show", "payments"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("Name: Payments API"));
+    assert!(stdout.contains("Description: Billing backend"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .args(["project", "list"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout
+        .lines()
+        .any(|line| line.starts_with("payments") && line.ends_with("Payments API")));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", &home)
+        .args(["project", "show", "nope"])
+        .assert()
+        .code(3);
+
+    Ok(())
+}
+
+#[test]
+fn legacy_known_projects_move_into_the_registry() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    std::fs::create_dir_all(&home)?;
+    std::fs::write(
+        home.join("config.json"),
+        r#"{"current": null, "known": ["default", "legacy-app"]}"#,
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .args(["--json", "project", "list"])
+        .assert()
+        .success();
+    let known = project_ids(&assert.get_output().stdout)?;
+    assert_eq!(known, vec!["default", "legacy-app"]);
+
+    let config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(home.join("config.json"))?)?;
+    assert!(config.get("known").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn list_reports_document_counts_size_and_last_activity() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path().join("home");
+    for (key, body) in [("a", "12345"), ("b", "678")] {
+        Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", &home)
+            .args(["--project", "busy", "put", "--key", key])
+            .write_stdin(body)
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .args(["--json", "project", "list"])
+        .assert()
+        .success();
+    let projects: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    let busy = projects
+        .iter()
+        .find(|project| project["id"] == "busy")
+        .expect("busy project listed");
+    assert_eq!(busy["documents"], 2);
+    assert_eq!(busy["bytes"], 8);
+    assert!(busy["last_updated"].is_string());
+    let default = projects
+        .iter()
+        .find(|project| project["id"] == "default")
+        .expect("default project listed");
+    assert_eq!(default["documents"], 0);
+    assert!(default["last_updated"].is_null());
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", &home)
+        .args(["project", "list"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    let busy_line = stdout
+        .lines()
+        .find(|line| line.starts_with("busy"))
+        .expect("busy row");
+    assert!(busy_line.contains(" 2 "));
+    assert!(busy_line.contains("8 B"));
+
+    Ok(())
+}
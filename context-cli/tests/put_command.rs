@@ -2,7 +2,7 @@ use std::io::Write;
 
 use anyhow::Result;
 use assert_cmd::Command;
-use context_core::{Document, SourceType};
+use context_core::{Document, SourceType, Visibility};
 use tempfile::{tempdir, NamedTempFile};
 
 #[test]
@@ -89,3 +89,250 @@ fn put_fails_without_input() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn put_rejects_keys_that_violate_key_rules() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["put", "--key", "what we learned today"])
+        .write_stdin("body")
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("a space at position 4"), "stderr: {stderr}");
+
+    std::fs::write(
+        temp.path().join("config.json"),
+        r#"{"current":null,"known":["default"],"keys":{"reserved_prefixes":["sys/"]}}"#,
+    )?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["put", "--key", "sys/config"])
+        .write_stdin("body")
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("reserved prefix"), "stderr: {stderr}");
+
+    Ok(())
+}
+
+#[test]
+fn put_with_idempotency_key_does_not_duplicate_on_retry() -> Result<()> {
+    let temp = tempdir()?;
+    let put = || -> Result<Document> {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args([
+                "--json",
+                "put",
+                "--key",
+                "facts",
+                "--idempotency-key",
+                "batch-7",
+            ])
+            .write_stdin("the sky is blue")
+            .assert()
+            .success();
+        Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+    };
+
+    let first = put()?;
+    let retry = put()?;
+    assert_eq!(retry.id.0, first.id.0);
+    assert_eq!(retry.version, 1, "the retry did not add a revision");
+
+    Ok(())
+}
+
+#[test]
+fn put_file_reads_front_matter_and_titles_show_in_ls_and_find() -> Result<()> {
+    let temp = tempdir()?;
+    let mut notes = NamedTempFile::new()?;
+    write!(
+        notes,
+        "---\nkey: ops/deploy\ntags: [ops, runbook]\nnamespace: ops\nttl: 30d\n---\n# Deploy runbook\n\nRoll the canary first.\n"
+    )?;
+    let path = notes.path().to_str().expect("temp file path");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args([
+            "--json", "put", "--file", path, "--tag", "ops", "--tag", "prod",
+        ])
+        .assert()
+        .success();
+    let document: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(document.key.as_deref(), Some("ops/deploy"));
+    assert_eq!(document.title.as_deref(), Some("Deploy runbook"));
+    assert_eq!(document.namespace.as_deref(), Some("ops"));
+    assert_eq!(document.tags, ["ops", "runbook", "prod"]);
+    assert_eq!(document.ttl_seconds, Some(30 * 24 * 3600));
+    assert_eq!(
+        document.body_markdown,
+        "# Deploy runbook\n\nRoll the canary first.\n"
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["put", "--file", path, "--title", "Canary deploys"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("Title: Canary deploys"), "{stdout}");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .arg("ls")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("(Key: ops/deploy) \"Canary deploys\""),
+        "{stdout}"
+    );
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["find", "canary"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("Title: Canary deploys"), "{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn put_from_stdin_leaves_front_matter_in_the_body() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "put", "--key", "raw"])
+        .write_stdin("---\ntitle: kept\n---\n# Heading\n")
+        .assert()
+        .success();
+    let document: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(document.title, None);
+    assert_eq!(document.body_markdown, "---\ntitle: kept\n---\n# Heading\n");
+
+    Ok(())
+}
+
+#[test]
+fn put_batch_stores_json_lines_and_reports_each_one() -> Result<()> {
+    let temp = tempdir()?;
+    let input = concat!(
+        r#"{"key": "facts/a", "body": "first", "tags": ["fact"]}"#,
+        "\n\n",
+        r#"{"key": "facts/b", "body_markdown": "second", "ttl": "7d"}"#,
+        "\n",
+        "not json\n",
+        r#"{"key": "facts/a", "body": "first, corrected", "namespace": "ops"}"#,
+        "\n",
+    );
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "put", "--batch", "--tag", "agent"])
+        .write_stdin(input)
+        .assert()
+        .failure();
+    let output = assert.get_output();
+    let results: Vec<serde_json::Value> = String::from_utf8(output.stdout.clone())?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let summary: Vec<(u64, &str)> = results
+        .iter()
+        .map(|result| {
+            (
+                result["line"].as_u64().unwrap(),
+                result["status"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [(1, "created"), (3, "created"), (4, "error"), (5, "updated")]
+    );
+    assert_eq!(results[0]["id"], results[3]["id"]);
+    assert_eq!(results[3]["version"], 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 batch line(s)"));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo", "--json", "get", "--key", "facts/b"])
+        .assert()
+        .success();
+    let document: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(document.body_markdown, "second");
+    assert_eq!(document.tags, ["agent"]);
+    assert_eq!(document.ttl_seconds, Some(7 * 24 * 3600));
+
+    Ok(())
+}
+
+#[test]
+fn put_visibility_sticks_across_revisions_and_shows_in_ls() -> Result<()> {
+    let temp = tempdir()?;
+    let context = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", temp.path());
+        cmd
+    };
+    let assert = context()
+        .args(["put", "--key", "scratch", "--visibility", "private"])
+        .write_stdin("half-baked")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("Visibility: private"), "{stdout}");
+    context()
+        .args(["put", "--key", "plan"])
+        .write_stdin("shareable")
+        .assert()
+        .success();
+
+    let assert = context()
+        .args(["--json", "put", "--key", "scratch"])
+        .write_stdin("half-baked, v2")
+        .assert()
+        .success();
+    let document: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(document.version, 2);
+    assert_eq!(document.visibility, Some(Visibility::Private));
+
+    let assert = context().arg("ls").assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("(Key: scratch) [private]"), "{stdout}");
+    assert!(!stdout.contains("(Key: plan) [private]"), "{stdout}");
+
+    let assert = context()
+        .args(["ls", "--visibility", "project,public"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("(Key: plan)"), "{stdout}");
+    assert!(!stdout.contains("scratch"), "{stdout}");
+
+    let assert = context()
+        .args(["put", "--key", "x", "--visibility", "secret"])
+        .write_stdin("body")
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Unknown visibility \"secret\""), "{stderr}");
+    Ok(())
+}
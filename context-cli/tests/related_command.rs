@@ -0,0 +1,52 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::put_with;
+
+#[test]
+fn related_lists_best_matches_first() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(
+        temp.path(),
+        "auth/tokens",
+        "Access tokens rotate daily",
+        &["--tag", "auth,security"],
+    );
+    put_with(
+        temp.path(),
+        "auth/sessions",
+        "Session cookies",
+        &["--tag", "auth,security"],
+    );
+    put_with(temp.path(), "lunch", "Pizza on fridays", &["--tag", "food"]);
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "related", "--key", "auth/tokens"])
+        .assert()
+        .success();
+
+    let related: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0]["document"]["key"], "auth/sessions");
+    assert_eq!(related[0]["tag_overlap"], 1.0);
+
+    Ok(())
+}
+
+#[test]
+fn related_missing_document_is_not_found() -> Result<()> {
+    let temp = tempdir()?;
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["related", "--key", "nope"])
+        .assert()
+        .code(3);
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+use common::put;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+#[test]
+fn reminders_come_due_and_can_be_completed() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "rollout", "body");
+    put(temp.path(), "demo", "roadmap", "body");
+
+    let assert = context(temp.path())
+        .args(["--project", "demo", "--json", "remind", "--key", "rollout"])
+        .args(["--at", "2024-07-01", "--note", "check error rates"])
+        .assert()
+        .success();
+    let reminder: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(reminder["key"], "rollout");
+    assert_eq!(reminder["remind_at"], "2024-07-01T00:00:00Z");
+    let id = reminder["id"].as_i64().expect("reminder id");
+
+    context(temp.path())
+        .args(["--project", "demo", "remind", "--key", "roadmap"])
+        .args(["--at", "2999-01-01T09:00:00Z"])
+        .assert()
+        .success();
+
+    let assert = context(temp.path())
+        .args(["--project", "demo", "reminders", "due"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert_eq!(
+        stdout,
+        format!("[ ] #{id} 2024-07-01 00:00  rollout  check error rates\n")
+    );
+
+    let assert = context(temp.path())
+        .args(["--project", "demo", "--json", "reminders", "list"])
+        .assert()
+        .success();
+    let pending: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(pending.len(), 2, "list includes reminders not yet due");
+
+    context(temp.path())
+        .args(["--project", "demo", "reminders", "done", &id.to_string()])
+        .assert()
+        .success();
+    let assert = context(temp.path())
+        .args(["--project", "demo", "reminders", "due"])
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"No reminders found.\n");
+
+    context(temp.path())
+        .args(["--project", "demo", "reminders", "done", &id.to_string()])
+        .assert()
+        .code(3);
+    Ok(())
+}
+
+#[test]
+fn remind_rejects_unknown_documents_and_bad_times() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "rollout", "body");
+
+    context(temp.path())
+        .args(["--project", "demo", "remind", "--key", "missing"])
+        .args(["--at", "2024-07-01"])
+        .assert()
+        .code(3);
+    let assert = context(temp.path())
+        .args(["--project", "demo", "remind", "--key", "rollout"])
+        .args(["--at", "next tuesday"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Invalid timestamp"), "stderr: {stderr}");
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home)
+        .env("CONTEXT_NO_DAEMON", "1")
+        .env("CONTEXT_REMOTE_TOKEN", "team-secret")
+        .args(["--project", "demo"]);
+    cmd
+}
+
+/// A stand-in for `context-web` that serves one document at `shared/runbook`, counting
+/// requests and checking the bearer token.
+fn serve_one_document() -> Result<(String, Arc<AtomicUsize>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut reader = BufReader::new(&stream);
+            let mut head = Vec::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                head.push(line);
+            }
+            let authorized = head
+                .iter()
+                .any(|line| line.eq_ignore_ascii_case("authorization: Bearer team-secret\r\n"));
+            let (status, body) = match head.first().map(String::as_str) {
+                Some("GET /api/projects/demo/docs/shared/runbook HTTP/1.1\r\n") if authorized => (
+                    "200 OK",
+                    serde_json::json!({
+                        "id": "remote-doc-1",
+                        "project": "demo",
+                        "key": "shared/runbook",
+                        "namespace": null,
+                        "title": "Runbook",
+                        "tags": ["ops"],
+                        "body_markdown": "restart the gateway",
+                        "created_at": "2024-01-01T00:00:00Z",
+                        "updated_at": "2024-01-02T00:00:00Z",
+                        "source": "User",
+                        "version": 4,
+                        "ttl_seconds": null,
+                        "deleted_at": null
+                    })
+                    .to_string(),
+                ),
+                _ => ("404 Not Found", "{}".to_string()),
+            };
+            let mut stream = &stream;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    Ok((url, requests))
+}
+
+#[test]
+fn get_reads_missing_keys_through_the_remote_and_caches_them() -> Result<()> {
+    let temp = tempdir()?;
+    let (url, requests) = serve_one_document()?;
+    std::fs::write(
+        temp.path().join("config.json"),
+        serde_json::json!({ "remote": { "url": url } }).to_string(),
+    )?;
+
+    let assert = context(temp.path())
+        .args(["get", "--key", "shared/runbook"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("restart the gateway"), "{stdout}");
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("read it from http://127.0.0.1"), "{stderr}");
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    // The second read is local, and the copy says where it came from.
+    let assert = context(temp.path())
+        .args(["--json", "get", "--key", "shared/runbook"])
+        .assert()
+        .success();
+    let doc: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(doc["version"], 4);
+    assert_eq!(doc["source_meta"]["fetched_from"], url.as_str());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    let assert = context(temp.path())
+        .args(["get", "--key", "shared/missing"])
+        .assert()
+        .code(3);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(!stderr.contains("read it from"), "{stderr}");
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[test]
+fn an_unreachable_remote_leaves_the_key_not_found() -> Result<()> {
+    let temp = tempdir()?;
+    // Bind and drop to find a port nothing listens on.
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    std::fs::write(
+        temp.path().join("config.json"),
+        serde_json::json!({ "remote": { "url": format!("http://127.0.0.1:{port}") } }).to_string(),
+    )?;
+
+    let assert = context(temp.path())
+        .args(["get", "--key", "shared/runbook"])
+        .assert()
+        .code(3);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("remote lookup of shared/runbook failed"),
+        "{stderr}"
+    );
+    Ok(())
+}
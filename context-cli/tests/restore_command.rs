@@ -0,0 +1,84 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn listed_keys(home: &std::path::Path, args: &[&str]) -> Result<Vec<String>> {
+    let assert = context(home)
+        .args(["--json", "ls", "--fields", "key"])
+        .args(args)
+        .assert()
+        .success();
+    let docs: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    Ok(docs
+        .iter()
+        .map(|doc| doc["key"].as_str().unwrap_or_default().to_string())
+        .collect())
+}
+
+#[test]
+fn restore_undoes_rm() -> Result<()> {
+    let temp = tempdir()?;
+    for key in ["runbook", "notes"] {
+        context(temp.path())
+            .args(["put", "--key", key])
+            .write_stdin(format!("{key} about the gateway"))
+            .assert()
+            .success();
+    }
+    context(temp.path())
+        .args(["rm", "--key", "runbook"])
+        .assert()
+        .success();
+
+    assert_eq!(listed_keys(temp.path(), &["--deleted"])?, ["runbook"]);
+    assert_eq!(listed_keys(temp.path(), &[])?, ["notes"]);
+
+    let assert = context(temp.path())
+        .args(["restore", "--key", "runbook"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("Restored runbook in project demo (v3)"),
+        "{stdout}"
+    );
+    assert!(listed_keys(temp.path(), &["--deleted"])?.is_empty());
+
+    let assert = context(temp.path())
+        .args(["--json", "find", "gateway"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("runbook"), "{stdout}");
+    Ok(())
+}
+
+#[test]
+fn restore_reports_missing_tombstones() -> Result<()> {
+    let temp = tempdir()?;
+    context(temp.path())
+        .args(["put", "--key", "runbook"])
+        .write_stdin("live")
+        .assert()
+        .success();
+
+    let assert = context(temp.path())
+        .args(["restore", "--key", "runbook"])
+        .assert()
+        .code(3);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("No deleted document runbook in project demo"),
+        "{stderr}"
+    );
+
+    context(temp.path()).args(["restore"]).assert().failure();
+    Ok(())
+}
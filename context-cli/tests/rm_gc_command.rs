@@ -1,8 +1,12 @@
+mod common;
+
 use anyhow::Result;
 use assert_cmd::Command;
 use serde_json::Value;
 use tempfile::tempdir;
 
+use common::put;
+
 #[test]
 fn rm_requires_key_or_id() -> Result<()> {
     let temp = tempdir()?;
@@ -24,6 +28,8 @@ fn rm_requires_key_or_id() -> Result<()> {
 #[test]
 fn rm_accepts_key_and_outputs_json() -> Result<()> {
     let temp = tempdir()?;
+    put(temp.path(), "demo-project", "rm-key", "to be removed");
+
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
     let assert = cmd
         .env("CONTEXT_HOME", temp.path())
@@ -46,6 +52,157 @@ fn rm_accepts_key_and_outputs_json() -> Result<()> {
     assert_eq!(value["key"], "rm-key");
     assert!(value["id"].as_str().is_some());
 
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "demo-project", "get", "--key", "rm-key"])
+        .assert()
+        .code(3);
+
+    Ok(())
+}
+
+#[test]
+fn rm_missing_key_exits_with_not_found_code() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "rm", "--key", "missing"])
+        .assert()
+        .failure();
+
+    let output = assert.get_output();
+    assert_eq!(output.status.code(), Some(3));
+    let value: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(value["error"]["code"], "not_found");
+
+    Ok(())
+}
+
+#[test]
+fn rm_force_tolerates_missing_key() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "rm", "--key", "missing", "--force"])
+        .assert()
+        .success();
+
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["status"], "missing");
+
+    Ok(())
+}
+
+#[test]
+fn rm_purge_erases_history_and_force_deletes_for_good() -> Result<()> {
+    let temp = tempdir()?;
+    let context = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", temp.path());
+        cmd
+    };
+    for key in ["purged", "gone"] {
+        put(temp.path(), "default", key, "first draft");
+        put(temp.path(), "default", key, "second draft");
+    }
+
+    let assert = context()
+        .args(["--json", "rm", "--key", "purged", "--purge"])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["mode"], "purge");
+    // Restored as it was deleted, but with only the tombstone behind it.
+    context()
+        .args(["restore", "--key", "purged"])
+        .assert()
+        .success();
+    let assert = context()
+        .args(["--json", "history", "--key", "purged"])
+        .assert()
+        .success();
+    let history: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(history.len(), 2, "{history:?}");
+
+    let assert = context()
+        .args(["--json", "rm", "--key", "gone", "--force"])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["status"], "deleted");
+    assert_eq!(value["mode"], "hard");
+    context()
+        .args(["restore", "--key", "gone"])
+        .assert()
+        .failure();
+    let assert = context()
+        .args(["--json", "ls", "--deleted"])
+        .assert()
+        .success();
+    let deleted: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(deleted.is_empty(), "{deleted:?}");
+
+    Ok(())
+}
+
+#[test]
+fn rm_force_erases_an_earlier_tombstone() -> Result<()> {
+    let temp = tempdir()?;
+    let context = || {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+        cmd.env("CONTEXT_HOME", temp.path());
+        cmd
+    };
+    put(temp.path(), "default", "twice", "deleted twice");
+    context().args(["rm", "--key", "twice"]).assert().success();
+
+    let assert = context()
+        .args(["--json", "rm", "--key", "twice", "--force"])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["status"], "deleted");
+    let assert = context()
+        .args(["restore", "--key", "twice"])
+        .assert()
+        .failure();
+    assert_eq!(assert.get_output().status.code(), Some(3));
+    let assert = context()
+        .args(["--json", "ls", "--deleted"])
+        .assert()
+        .success();
+    let deleted: Vec<Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(deleted.is_empty(), "{deleted:?}");
+
+    context()
+        .args(["rm", "--key", "twice", "--force", "--purge"])
+        .assert()
+        .code(2);
+
+    Ok(())
+}
+
+#[test]
+fn put_after_rm_reuses_the_key() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "default", "cycle", "first life");
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["rm", "--key", "cycle"])
+        .assert()
+        .success();
+    put(temp.path(), "default", "cycle", "second life");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["cat", "--key", "cycle"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("second life"));
+
     Ok(())
 }
 
@@ -66,6 +223,27 @@ fn gc_respects_dry_run_and_outputs_json() -> Result<()> {
     assert_eq!(value["dry_run"], true);
     assert!(value["deleted"].as_u64().is_some());
     assert_eq!(value["vacuumed"], false);
+    assert_eq!(value["consistency"]["repaired"], false);
+
+    Ok(())
+}
+
+#[test]
+fn gc_check_only_reports_consistency_without_changes() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "demo", "notes", "body");
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "gc", "--check-only"])
+        .assert()
+        .success();
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["check_only"], true);
+    assert_eq!(value["vacuumed"], false);
+    assert_eq!(value["consistency"]["orphaned_fts_rows"], 0);
+    assert_eq!(value["consistency"]["orphaned_versions"], 0);
+    assert_eq!(value["consistency"]["repaired"], false);
 
     Ok(())
 }
@@ -86,3 +264,87 @@ fn gc_human_output_when_not_json() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn gc_purges_the_projects_deleted_documents() -> Result<()> {
+    let temp = tempdir()?;
+    for (project, key) in [("demo", "gone"), ("demo", "kept"), ("other", "gone")] {
+        put(temp.path(), project, key, "body");
+        if key == "gone" {
+            Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+                .env("CONTEXT_HOME", temp.path())
+                .args(["--project", project, "rm", "--key", key])
+                .assert()
+                .success();
+        }
+    }
+    let gc = |args: &[&str]| -> Result<Value> {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args(["--project", "demo", "--json", "gc"])
+            .args(args)
+            .assert()
+            .success();
+        Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+    };
+
+    assert_eq!(gc(&["--dry-run"])?["deleted"], 1);
+    let value = gc(&[])?;
+    assert_eq!(value["deleted"], 1);
+    assert_eq!(value["vacuumed"], true);
+    assert!(value["analyzed"].as_u64().unwrap() > 0);
+    assert_eq!(gc(&["--dry-run"])?["deleted"], 0);
+
+    // Other projects keep their tombstones for `restore`.
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--project", "other", "restore", "--key", "gone"])
+        .assert()
+        .success();
+    Ok(())
+}
+
+#[test]
+fn gc_keeps_tombstones_within_the_retention_window() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "default", "recent", "body");
+    put(temp.path(), "default", "kept", "body");
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["rm", "--key", "recent"])
+        .assert()
+        .success();
+    let gc = |args: &[&str]| -> Result<Value> {
+        let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+            .env("CONTEXT_HOME", temp.path())
+            .args(["--json", "gc"])
+            .args(args)
+            .assert()
+            .success();
+        Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+    };
+
+    let within = gc(&["--dry-run", "--retention", "1d"])?;
+    assert_eq!(within["deleted"], 0);
+    assert_eq!(within["expired"], 0);
+    assert_eq!(within["reindexed"], 2);
+    assert_eq!(within["retention_seconds"], 86_400);
+
+    let value = gc(&["--retention", "1d"])?;
+    assert_eq!(value["deleted"], 0);
+    assert_eq!(value["reindexed"], 2);
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["restore", "--key", "recent"])
+        .assert()
+        .success();
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["gc", "--retention", "soon"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Invalid duration"), "{stderr}");
+    Ok(())
+}
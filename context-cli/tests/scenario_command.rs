@@ -0,0 +1,112 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use chrono::DateTime;
+use serde_json::Value;
+use std::{fs, path::Path};
+use tempfile::tempdir;
+use zip::read::ZipArchive;
+
+fn context(home: &Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home)
+        .env_remove("CONTEXT_LOG_DIR")
+        .env_remove("CONTEXT_SCENARIO")
+        .args(["--project", "demo"]);
+    cmd
+}
+
+fn log_entries(home: &Path) -> Vec<Value> {
+    fs::read_to_string(home.join("logs").join("context-cli.jsonl"))
+        .unwrap()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[test]
+fn scenario_start_tags_later_commands_until_it_ends() -> Result<()> {
+    let home = tempdir()?;
+
+    let assert = context(home.path())
+        .args(["scenario", "start", "--label", "import run"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    let id = stdout
+        .trim()
+        .strip_prefix("export CONTEXT_SCENARIO=")
+        .expect("an export line")
+        .to_string();
+    assert!(id.ends_with("Z-import-run"), "{id}");
+    // The leading timestamp is what `context-plan` ages `@scenario(...)` claims by.
+    DateTime::parse_from_rfc3339(&id[..=id.find('Z').unwrap()])?;
+
+    // Without --scenario or CONTEXT_SCENARIO, commands log under the running scenario.
+    context(home.path()).arg("ls").assert().success();
+    let logs = context(home.path())
+        .args(["logs", "--scenario", &id])
+        .assert()
+        .success();
+    let logged = String::from_utf8(logs.get_output().stdout.clone())?;
+    assert!(logged.contains("scenario.started"), "{logged}");
+    assert!(logged.contains("\"command\":\"ls\""), "{logged}");
+
+    let bundle = home.path().join("bundle.zip");
+    context(home.path())
+        .args(["debug-bundle", "--out"])
+        .arg(&bundle)
+        .assert()
+        .success();
+    let mut archive = ZipArchive::new(fs::File::open(&bundle)?)?;
+    let meta: Value = serde_json::from_reader(archive.by_name("meta.json")?)?;
+    assert_eq!(meta["scenario_id"], id.as_str());
+
+    let assert = context(home.path())
+        .args(["--json", "scenario", "end"])
+        .assert()
+        .success();
+    let ended: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(ended["scenario_id"], id.as_str());
+    assert!(ended["duration_ms"].as_i64().unwrap() >= 0);
+    assert!(log_entries(home.path()).iter().any(|entry| {
+        entry["fields"]["event"] == "scenario.ended"
+            && entry["fields"]["scenario_id"] == id.as_str()
+    }));
+
+    // Ended: nothing is tagged any more, and there is nothing left to end.
+    context(home.path()).arg("ls").assert().success();
+    let last = log_entries(home.path()).pop().unwrap();
+    assert!(!last.to_string().contains(&id), "{last}");
+    context(home.path())
+        .args(["scenario", "end"])
+        .assert()
+        .code(3);
+    Ok(())
+}
+
+#[test]
+fn scenario_start_ends_the_running_scenario_and_yields_to_the_environment() -> Result<()> {
+    let home = tempdir()?;
+    let start = |home: &Path| -> Result<Value> {
+        let assert = context(home)
+            .args(["--json", "scenario", "start"])
+            .assert()
+            .success();
+        Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+    };
+
+    let first = start(home.path())?;
+    assert!(first["replaced"].is_null());
+    let second = start(home.path())?;
+    assert_eq!(second["replaced"], first["scenario_id"]);
+    assert_ne!(second["scenario_id"], first["scenario_id"]);
+
+    context(home.path())
+        .env("CONTEXT_SCENARIO", "scn-explicit")
+        .arg("ls")
+        .assert()
+        .success();
+    let last = log_entries(home.path()).pop().unwrap();
+    assert_eq!(last["span"]["scenario_id"], "scn-explicit", "{last}");
+    Ok(())
+}
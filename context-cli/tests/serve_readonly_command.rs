@@ -0,0 +1,160 @@
+#![cfg(feature = "serve")]
+
+mod common;
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    path::Path,
+    process::{Child, Command as StdCommand, Stdio},
+};
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+use common::{put, put_with};
+
+/// Start the server on a free port and return it with its address.
+fn serve(home: &Path, projects: &str, extra: &[&str]) -> Result<(Child, String)> {
+    let mut child = StdCommand::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", home)
+        .env("CONTEXT_LOG_DIR", home)
+        .args([
+            "--json",
+            "serve-readonly",
+            "--projects",
+            projects,
+            "--port",
+            "0",
+        ])
+        .args(extra)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().expect("stdout")).read_line(&mut line)?;
+    let status: serde_json::Value = serde_json::from_str(&line)?;
+    let url = status["url"].as_str().expect("url").to_string();
+    Ok((child, url.trim_start_matches("http://").to_string()))
+}
+
+/// Send one request and return the status code and body.
+fn request(addr: &str, method: &str, path: &str) -> Result<(u16, String)> {
+    let mut stream = TcpStream::connect(addr)?;
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+    )?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    let status = response
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .parse()?;
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body.to_string())
+        .unwrap_or_default();
+    Ok((status, body))
+}
+
+#[test]
+fn serve_readonly_publishes_rendered_pages_and_search() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    put(
+        home,
+        "docs",
+        "guides/setup",
+        "# Setup\n\nRun `make bootstrap` first.\n\n<script>alert(1)</script>\n\n[x](javascript:alert(1))",
+    );
+    put(home, "docs", "faq", "Answers about bootstrap and releases.");
+    put(home, "private", "secrets", "bootstrap token lives here");
+
+    let (mut server, addr) = serve(home, "docs", &[])?;
+
+    let (status, index) = request(&addr, "GET", "/")?;
+    assert_eq!(status, 200);
+    assert!(index.contains("<a href=\"/p/docs\">docs</a> (2 document(s))"));
+    assert!(!index.contains("private"));
+
+    let (status, page) = request(&addr, "GET", "/p/docs/d/guides/setup")?;
+    assert_eq!(status, 200);
+    assert!(page.contains("<h1>Setup</h1>"));
+    assert!(page.contains("<code>make bootstrap</code>"));
+    assert!(page.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    assert!(!page.contains("<script>"));
+    assert!(!page.contains("javascript:"));
+
+    let (status, results) = request(&addr, "GET", "/search?q=bootstrap")?;
+    assert_eq!(status, 200);
+    assert!(results.contains("/p/docs/d/guides/setup"));
+    assert!(results.contains("/p/docs/d/faq"));
+    assert!(
+        !results.contains("secrets"),
+        "unpublished projects stay out"
+    );
+
+    assert_eq!(request(&addr, "GET", "/p/private")?.0, 404);
+    assert_eq!(request(&addr, "GET", "/p/docs/d/missing")?.0, 404);
+    assert_eq!(request(&addr, "PUT", "/p/docs/d/faq")?.0, 405);
+    assert_eq!(request(&addr, "DELETE", "/p/docs/d/faq")?.0, 405);
+    assert_eq!(request(&addr, "POST", "/search")?.0, 405);
+
+    server.kill()?;
+    server.wait()?;
+    Ok(())
+}
+
+#[test]
+fn serve_readonly_rejects_unknown_projects() -> Result<()> {
+    let temp = tempdir()?;
+    put(temp.path(), "docs", "faq", "body");
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["serve-readonly", "--projects", "docs,nope", "--port", "0"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Unknown project nope"), "stderr: {stderr}");
+    Ok(())
+}
+
+#[test]
+fn serve_readonly_leaves_out_private_documents() -> Result<()> {
+    let temp = tempdir()?;
+    let home = temp.path();
+    put_with(
+        home,
+        "scratch",
+        "half-baked bootstrap notes",
+        &["--project", "docs", "--visibility", "private"],
+    );
+    put_with(
+        home,
+        "launch",
+        "Launch bootstrap post",
+        &["--project", "docs", "--visibility", "public"],
+    );
+    put(home, "docs", "faq", "Answers about bootstrap.");
+
+    let (mut server, addr) = serve(home, "docs", &[])?;
+    let (_, index) = request(&addr, "GET", "/")?;
+    assert!(index.contains("(2 document(s))"), "{index}");
+    assert_eq!(request(&addr, "GET", "/p/docs/d/scratch")?.0, 404);
+    let (_, results) = request(&addr, "GET", "/search?q=bootstrap")?;
+    assert!(!results.contains("scratch"), "{results}");
+    assert!(results.contains("/p/docs/d/faq"));
+    server.kill()?;
+    server.wait()?;
+
+    let (mut server, addr) = serve(home, "docs", &["--public-only"])?;
+    let (_, results) = request(&addr, "GET", "/search?q=bootstrap")?;
+    assert!(results.contains("/p/docs/d/launch"), "{results}");
+    assert!(!results.contains("/p/docs/d/faq"), "{results}");
+    server.kill()?;
+    server.wait()?;
+    Ok(())
+}
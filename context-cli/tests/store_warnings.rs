@@ -0,0 +1,75 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn stderr_of(cmd: &mut Command) -> String {
+    let assert = cmd.assert().success();
+    String::from_utf8_lossy(&assert.get_output().stderr).into_owned()
+}
+
+fn configure(home: &std::path::Path, warnings: &str) -> Result<()> {
+    std::fs::write(
+        home.join("config.json"),
+        format!(r#"{{"warnings": {warnings}}}"#),
+    )?;
+    Ok(())
+}
+
+#[test]
+fn commands_warn_when_tombstones_pile_up() -> Result<()> {
+    let temp = tempdir()?;
+    configure(temp.path(), r#"{"max_deleted": 1}"#)?;
+    for key in ["a", "b"] {
+        context(temp.path())
+            .args(["put", "--key", key])
+            .write_stdin("body")
+            .assert()
+            .success();
+    }
+    let stderr = stderr_of(context(temp.path()).args(["rm", "--key", "a"]));
+    assert!(!stderr.contains("warning:"), "{stderr}");
+
+    let stderr = stderr_of(context(temp.path()).args(["rm", "--key", "b"]));
+    assert!(
+        stderr.contains("warning: project demo has 2 deleted documents (limit 1)"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("`context gc --project demo`"), "{stderr}");
+
+    // JSON on stdout stays parseable; the hint only goes to stderr.
+    let assert = context(temp.path())
+        .args(["--json", "ls", "--deleted"])
+        .assert()
+        .success();
+    let docs: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(docs.len(), 2);
+
+    context(temp.path()).arg("gc").assert().success();
+    let stderr = stderr_of(context(temp.path()).arg("ls"));
+    assert!(!stderr.contains("warning:"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn database_size_warning_can_be_silenced() -> Result<()> {
+    let temp = tempdir()?;
+    configure(temp.path(), r#"{"max_db_mb": 0}"#)?;
+    let stderr = stderr_of(
+        context(temp.path())
+            .args(["put", "--key", "notes"])
+            .write_stdin("body"),
+    );
+    assert!(stderr.contains("warning: the database is"), "{stderr}");
+    assert!(stderr.contains("(limit 0 MB)"), "{stderr}");
+
+    configure(temp.path(), r#"{"max_db_mb": 0, "enabled": false}"#)?;
+    let stderr = stderr_of(context(temp.path()).arg("ls"));
+    assert!(!stderr.contains("warning:"), "{stderr}");
+    Ok(())
+}
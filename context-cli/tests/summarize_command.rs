@@ -0,0 +1,144 @@
+mod common;
+
+use std::path::Path;
+#[cfg(feature = "http")]
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use tempfile::tempdir;
+
+use common::put_with;
+
+fn write_config(home: &Path, summarize: serde_json::Value) -> Result<()> {
+    let config = serde_json::json!({
+        "current": null,
+        "known": ["default"],
+        "summarize": summarize,
+    });
+    std::fs::write(home.join("config.json"), config.to_string())?;
+    Ok(())
+}
+
+#[test]
+fn summarize_stores_linked_summary_from_command_backend() -> Result<()> {
+    let temp = tempdir()?;
+    let source = put_with(temp.path(), "notes/deploy", "deploy with make release", &[]);
+    write_config(
+        temp.path(),
+        serde_json::json!({
+            "backend": "command",
+            "command": ["sh", "-c", "cat > /dev/null; echo condensed deploy notes"],
+        }),
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["--json", "summarize", "--key", "notes/deploy"])
+        .assert()
+        .success();
+
+    let summary: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(summary.key.as_deref(), Some("summary/notes/deploy"));
+    assert!(summary.tags.contains(&"summary".to_string()));
+    assert!(summary
+        .tags
+        .contains(&format!("summary-of:{}", source.id.0)));
+    assert!(summary.body_markdown.contains("condensed deploy notes"));
+    assert!(summary.body_markdown.contains("`notes/deploy` (v1)"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn summarize_calls_openai_compatible_endpoint() -> Result<()> {
+    let temp = tempdir()?;
+    put_with(
+        temp.path(),
+        "design",
+        "we picked sqlite for local-first storage",
+        &[],
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    let server = thread::spawn(move || -> Result<String> {
+        let (stream, _) = listener.accept()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some(value) = header.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse()?;
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+
+        let reply =
+            r#"{"choices":[{"message":{"role":"assistant","content":"SQLite, local-first."}}]}"#;
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+            reply.len()
+        )?;
+        Ok(format!("{request_line}{}", String::from_utf8(body)?))
+    });
+
+    write_config(
+        temp.path(),
+        serde_json::json!({
+            "backend": "openai",
+            "endpoint": format!("http://127.0.0.1:{port}/v1"),
+            "model": "local-test",
+        }),
+    )?;
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .args(["summarize", "--key", "design"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("Key: summary/design"));
+    assert!(stdout.contains("SQLite, local-first."));
+
+    let request = server.join().expect("server thread")?;
+    assert!(request.starts_with("POST /v1/chat/completions"));
+    assert!(request.contains("\"model\":\"local-test\""));
+    assert!(request.contains("local-first storage"));
+
+    Ok(())
+}
+
+#[test]
+fn summarize_requires_a_target() -> Result<()> {
+    let temp = tempdir()?;
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .arg("summarize")
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Provide --key, --id, or --query"));
+
+    Ok(())
+}
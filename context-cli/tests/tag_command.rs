@@ -0,0 +1,102 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn json(cmd: &mut Command) -> Result<Value> {
+    let assert = cmd.assert().success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+#[test]
+fn tag_add_and_remove_write_a_revision_and_reindex() -> Result<()> {
+    let home = tempdir()?;
+    context(home.path())
+        .args(["put", "--key", "deploy", "--tag", "ops"])
+        .write_stdin("Canary first.")
+        .assert()
+        .success();
+
+    let updated = json(context(home.path()).args([
+        "--json",
+        "tag",
+        "add",
+        "--key",
+        "deploy",
+        "runbook,zebra",
+        "ops",
+    ]))?;
+    assert_eq!(updated["status"], "updated");
+    assert_eq!(
+        updated["tags"],
+        serde_json::json!(["ops", "runbook", "zebra"])
+    );
+    assert_eq!(updated["version"], 2);
+
+    let hits = json(context(home.path()).args(["--json", "find", "zebra"]))?;
+    assert_eq!(hits[0]["key"], "deploy");
+    assert_eq!(hits[0]["body_markdown"], "Canary first.");
+
+    let unchanged =
+        json(context(home.path()).args(["--json", "tag", "remove", "--key", "deploy", "missing"]))?;
+    assert_eq!(unchanged["status"], "unchanged");
+    assert_eq!(unchanged["version"], 2);
+
+    let removed =
+        json(context(home.path()).args(["--json", "tag", "remove", "--key", "deploy", "zebra"]))?;
+    assert_eq!(removed["version"], 3);
+    let hits = json(context(home.path()).args(["--json", "find", "zebra"]))?;
+    assert_eq!(hits, serde_json::json!([]));
+
+    let assert = context(home.path())
+        .args(["tag", "list", "--key", "deploy"])
+        .assert()
+        .success();
+    assert_eq!(
+        String::from_utf8(assert.get_output().stdout.clone())?,
+        "ops\nrunbook\n"
+    );
+
+    context(home.path())
+        .args(["tag", "add", "--key", "nope", "x"])
+        .assert()
+        .code(3);
+    Ok(())
+}
+
+#[test]
+fn tags_counts_live_documents_per_tag() -> Result<()> {
+    let home = tempdir()?;
+    for (key, tags) in [("a", "ops,db"), ("b", "ops"), ("gone", "ops,old")] {
+        context(home.path())
+            .args(["put", "--key", key, "--tag", tags])
+            .write_stdin(format!("body of {key}"))
+            .assert()
+            .success();
+    }
+    context(home.path())
+        .args(["rm", "--key", "gone"])
+        .assert()
+        .success();
+
+    let tags = json(context(home.path()).args(["--json", "tags"]))?;
+    assert_eq!(
+        tags,
+        serde_json::json!([
+            {"name": "db", "documents": 1},
+            {"name": "ops", "documents": 2},
+        ])
+    );
+    let assert = context(home.path()).arg("tags").assert().success();
+    assert_eq!(
+        String::from_utf8(assert.get_output().stdout.clone())?,
+        "Tags in project demo\n- db (1)\n- ops (2)\n"
+    );
+    Ok(())
+}
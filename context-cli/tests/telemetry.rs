@@ -87,3 +87,136 @@ fn debug_bundle_collects_logs() {
         "expected scenario id in log contents"
     );
 }
+
+#[test]
+fn logs_record_the_git_state_of_the_working_directory() {
+    let temp = tempdir().unwrap();
+    let repo = temp.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(&repo)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    };
+    git(&["init", "-q", "-b", "feature/logs"]);
+    fs::write(repo.join("README.md"), "hello").unwrap();
+    git(&["add", "README.md"]);
+    git(&["commit", "-q", "-m", "init"]);
+    fs::write(repo.join("README.md"), "changed").unwrap();
+
+    let context_span = |log_file: &str| -> Value {
+        let contents = fs::read_to_string(temp.path().join(log_file)).unwrap();
+        let json: Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+        json["spans"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|span| span["name"] == "context")
+            .cloned()
+            .unwrap()
+    };
+
+    let logs = temp.path().join("with-git");
+    cargo_bin_cmd!("context-cli")
+        .current_dir(&repo)
+        .env("CONTEXT_HOME", temp.path())
+        .env("CONTEXT_LOG_DIR", &logs)
+        .arg("ls")
+        .assert()
+        .success();
+    let span = context_span("with-git/context-cli.jsonl");
+    assert_eq!(span["git_branch"], "feature/logs");
+    assert_eq!(span["git_commit"].as_str().unwrap().len(), 40);
+    assert_eq!(span["git_dirty"], true);
+
+    fs::write(temp.path().join("config.json"), r#"{"log_git": false}"#).unwrap();
+    let logs = temp.path().join("without-git");
+    cargo_bin_cmd!("context-cli")
+        .current_dir(&repo)
+        .env("CONTEXT_HOME", temp.path())
+        .env("CONTEXT_LOG_DIR", &logs)
+        .arg("ls")
+        .assert()
+        .success();
+    let span = context_span("without-git/context-cli.jsonl");
+    assert!(span.get("git_commit").is_none(), "span: {span}");
+}
+
+#[test]
+fn logs_default_to_the_context_home() {
+    let temp = tempdir().unwrap();
+    let home = temp.path().join("home");
+    let cwd = temp.path().join("work");
+    fs::create_dir(&cwd).unwrap();
+
+    cargo_bin_cmd!("context-cli")
+        .current_dir(&cwd)
+        .env("CONTEXT_HOME", &home)
+        .env_remove("CONTEXT_LOG_DIR")
+        .arg("ls")
+        .assert()
+        .success();
+    assert!(home.join("logs").join("context-cli.jsonl").exists());
+    assert!(!cwd.join(".context").exists());
+
+    // debug-bundle reads from the same place the logs were written.
+    let bundle = temp.path().join("bundle.zip");
+    cargo_bin_cmd!("context-cli")
+        .current_dir(&cwd)
+        .env("CONTEXT_HOME", &home)
+        .env_remove("CONTEXT_LOG_DIR")
+        .arg("debug-bundle")
+        .arg("--out")
+        .arg(&bundle)
+        .assert()
+        .success();
+    let mut archive = ZipArchive::new(fs::File::open(&bundle).unwrap()).unwrap();
+    assert!(archive.by_name("logs/context-cli.jsonl").is_ok());
+}
+
+/// The `command.completed` entry of the log in `dir`, after the last command ran.
+fn last_completion(dir: &std::path::Path) -> Value {
+    let contents = fs::read_to_string(dir.join("context-cli.jsonl")).unwrap();
+    contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|entry| entry["fields"]["event"] == "command.completed")
+        .expect("a command.completed entry")
+}
+
+#[test]
+fn every_command_logs_its_completion() {
+    let temp = tempdir().unwrap();
+    let body = "# Notes\nshipped";
+
+    cargo_bin_cmd!("context-cli")
+        .env("CONTEXT_HOME", temp.path())
+        .env("CONTEXT_LOG_DIR", temp.path())
+        .args(["put", "--key", "notes", "--meta", "ticket=SEC-1"])
+        .write_stdin(body)
+        .assert()
+        .success();
+    let fields = last_completion(temp.path())["fields"].clone();
+    assert_eq!(fields["exit_code"], 0);
+    assert_eq!(fields["documents_written"], 1);
+    assert_eq!(fields["bytes_written"], body.len());
+    assert!(fields["duration_ms"].as_f64().unwrap() >= 0.0);
+    let argv = fields["argv"].as_str().unwrap();
+    assert!(argv.contains("ticket=[redacted]"), "{argv}");
+    assert!(!argv.contains("SEC-1"), "{argv}");
+
+    cargo_bin_cmd!("context-cli")
+        .env("CONTEXT_HOME", temp.path())
+        .env("CONTEXT_LOG_DIR", temp.path())
+        .args(["get", "--key", "missing"])
+        .assert()
+        .code(3);
+    let fields = last_completion(temp.path())["fields"].clone();
+    assert_eq!(fields["exit_code"], 3);
+    assert_eq!(fields["documents_written"], 0);
+}
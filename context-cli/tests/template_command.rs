@@ -0,0 +1,110 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use context_core::Document;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+const ADR: &str = "---\ntags: [adr]\nnamespace: decisions\n---\n# ADR: {{ title }}\n\nDate: {{date}}\nStatus: {{status}}\n";
+
+#[test]
+fn put_from_template_fills_blanks_and_reads_its_front_matter() -> Result<()> {
+    let home = tempdir()?;
+    context(home.path())
+        .args(["template", "add", "adr", "--description", "Decision record"])
+        .write_stdin(ADR)
+        .assert()
+        .success();
+
+    let assert = context(home.path())
+        .args(["--json", "template", "list"])
+        .assert()
+        .success();
+    let templates: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(templates[0]["name"], "adr");
+    assert_eq!(templates[0]["description"], "Decision record");
+    assert_eq!(
+        templates[0]["variables"],
+        serde_json::json!(["title", "date", "status"])
+    );
+
+    let assert = context(home.path())
+        .args([
+            "template",
+            "render",
+            "adr",
+            "--var",
+            "title=Use SQLite",
+            "--var",
+            "status=draft",
+            "--var",
+            "date=2024-05-01",
+        ])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert!(
+        stdout.ends_with("# ADR: Use SQLite\n\nDate: 2024-05-01\nStatus: draft\n"),
+        "{stdout}"
+    );
+
+    let assert = context(home.path())
+        .args([
+            "--json",
+            "put",
+            "--key",
+            "decisions/sqlite",
+            "--from-template",
+            "adr",
+            "--var",
+            "title=Use SQLite",
+            "--var",
+            "status=accepted",
+        ])
+        .assert()
+        .success();
+    let document: Document = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(document.title.as_deref(), Some("ADR: Use SQLite"));
+    assert_eq!(document.namespace.as_deref(), Some("decisions"));
+    assert_eq!(document.tags, ["adr"]);
+    assert!(document.body_markdown.starts_with("# ADR: Use SQLite\n"));
+    assert!(document.body_markdown.contains("Status: accepted"));
+    assert!(!document.body_markdown.contains("{{"));
+    Ok(())
+}
+
+#[test]
+fn missing_values_and_templates_are_reported() -> Result<()> {
+    let home = tempdir()?;
+    context(home.path())
+        .args(["template", "add", "adr"])
+        .write_stdin(ADR)
+        .assert()
+        .success();
+
+    let assert = context(home.path())
+        .args(["put", "--from-template", "adr", "--var", "title=x"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("No value for status"), "{stderr}");
+
+    context(home.path())
+        .args(["template", "render", "nope"])
+        .assert()
+        .code(3);
+    context(home.path())
+        .args(["template", "rm", "adr"])
+        .assert()
+        .success();
+    context(home.path())
+        .args(["template", "rm", "adr"])
+        .assert()
+        .code(3);
+    Ok(())
+}
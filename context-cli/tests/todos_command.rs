@@ -0,0 +1,52 @@
+mod common;
+
+use anyhow::Result;
+use assert_cmd::Command;
+use tempfile::tempdir;
+
+use common::put;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home);
+    cmd
+}
+
+#[test]
+fn todos_lists_open_items_and_decisions() -> Result<()> {
+    let temp = tempdir()?;
+    put(
+        temp.path(),
+        "demo",
+        "plan",
+        "TODO: write docs\n- [ ] cut release\n- [x] merge branch\nDECISION: ship fridays",
+    );
+    put(temp.path(), "other", "misc", "- [ ] elsewhere");
+
+    let assert = context(temp.path())
+        .args(["--project", "demo", "todos", "--open"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert_eq!(stdout, "[ ] plan:1  write docs\n[ ] plan:2  cut release\n");
+
+    let assert = context(temp.path())
+        .args(["--project", "demo", "--json", "todos", "--decisions"])
+        .assert()
+        .success();
+    let items: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["kind"], "decision");
+    assert_eq!(items[0]["key"], "plan");
+    assert_eq!(items[0]["line"], 4);
+    assert_eq!(items[0]["text"], "ship fridays");
+
+    let assert = context(temp.path())
+        .args(["--json", "todos", "--all-projects"])
+        .assert()
+        .success();
+    let items: Vec<serde_json::Value> = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(items.len(), 4, "done items are listed without --open");
+    assert!(items.iter().any(|item| item["project"] == "other"));
+    Ok(())
+}
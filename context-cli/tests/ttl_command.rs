@@ -0,0 +1,102 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+fn context(home: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    cmd.env("CONTEXT_HOME", home).args(["--project", "demo"]);
+    cmd
+}
+
+fn json(cmd: &mut Command) -> Result<Value> {
+    let assert = cmd.assert().success();
+    Ok(serde_json::from_slice(&assert.get_output().stdout)?)
+}
+
+fn expiring_within(home: &std::path::Path, span: &str) -> Result<Vec<String>> {
+    let docs =
+        json(context(home).args(["--json", "ls", "--fields", "key", "--expiring-within", span]))?;
+    Ok(docs
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|doc| doc["key"].as_str().unwrap().to_string())
+        .collect())
+}
+
+#[test]
+fn put_ttl_touch_and_expiring_within() -> Result<()> {
+    let temp = tempdir()?;
+    let doc = json(
+        context(temp.path())
+            .args(["--json", "put", "--key", "scratch", "--ttl", "7d"])
+            .write_stdin("temporary notes"),
+    )?;
+    assert_eq!(doc["ttl_seconds"], 7 * 24 * 3600);
+    context(temp.path())
+        .args(["put", "--key", "keeper"])
+        .write_stdin("kept")
+        .assert()
+        .success();
+
+    assert_eq!(expiring_within(temp.path(), "8d")?, ["scratch"]);
+    assert!(expiring_within(temp.path(), "1d")?.is_empty());
+
+    let assert = context(temp.path())
+        .args(["touch", "--key", "scratch", "--ttl", "30d"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.starts_with("scratch expires "), "{stdout}");
+    assert!(expiring_within(temp.path(), "8d")?.is_empty());
+    assert_eq!(expiring_within(temp.path(), "31d")?, ["scratch"]);
+
+    let doc = json(context(temp.path()).args(["--json", "touch", "--key", "scratch", "--no-ttl"]))?;
+    assert_eq!(doc["ttl_seconds"], Value::Null);
+    assert!(expiring_within(temp.path(), "31d")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn put_ttl_on_a_new_revision_counts_from_now() -> Result<()> {
+    let temp = tempdir()?;
+    context(temp.path())
+        .args(["put", "--key", "notes"])
+        .write_stdin("first")
+        .assert()
+        .success();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let doc = json(
+        context(temp.path())
+            .args(["--json", "put", "--key", "notes", "--ttl", "1h"])
+            .write_stdin("second"),
+    )?;
+    assert_eq!(doc["version"], 2);
+    // TTLs count from the first revision's creation, so this one includes the gap.
+    assert!(doc["ttl_seconds"].as_i64().unwrap() > 3600, "{doc}");
+    assert_eq!(expiring_within(temp.path(), "2h")?, ["notes"]);
+    Ok(())
+}
+
+#[test]
+fn ttl_flags_reject_bad_durations() -> Result<()> {
+    let temp = tempdir()?;
+    let assert = context(temp.path())
+        .args(["put", "--key", "notes", "--ttl", "soon"])
+        .write_stdin("body")
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Invalid duration \"soon\""), "{stderr}");
+
+    context(temp.path())
+        .args(["touch", "--key", "notes"])
+        .assert()
+        .failure();
+    context(temp.path())
+        .args(["touch", "--key", "missing", "--ttl", "1d"])
+        .assert()
+        .code(3);
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use anyhow::Result;
+use assert_cmd::Command;
+use serde_json::Value;
+use tempfile::tempdir;
+
+#[test]
+fn whoami_reports_resolved_store_and_project() -> Result<()> {
+    let temp = tempdir()?;
+    let logs = temp.path().join("logs");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .args(["put", "--key", "seed"])
+        .write_stdin("seed")
+        .assert()
+        .success();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"));
+    let assert = cmd
+        .env("CONTEXT_HOME", temp.path())
+        .env("CONTEXT_LOG_DIR", &logs)
+        .env("CONTEXT_AGENT", "codex")
+        .env_remove("CONTEXT_PROJECT")
+        .args(["--project", "demo", "--json", "whoami"])
+        .assert()
+        .success();
+
+    let value: Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(value["project"], "demo");
+    assert_eq!(value["project_source"], "--project");
+    assert_eq!(
+        value["db"]["path"],
+        temp.path().join("db.sqlite").to_string_lossy().as_ref()
+    );
+    assert_eq!(value["db"]["exists"], true);
+    assert!(value["db"]["bytes"].as_u64().unwrap() > 0);
+    assert_eq!(
+        value["log_file"],
+        logs.join("context-cli.jsonl").to_string_lossy().as_ref()
+    );
+    assert_eq!(value["agent"], "codex");
+    let machine_id = value["machine_id"].as_str().unwrap().to_string();
+    assert_eq!(
+        std::fs::read_to_string(temp.path().join("machine-id"))?.trim(),
+        machine_id
+    );
+
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("context-cli"))
+        .env("CONTEXT_HOME", temp.path())
+        .arg("whoami")
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains(&format!("Machine id:   {machine_id}")),
+        "the id is kept: {stdout}"
+    );
+
+    Ok(())
+}
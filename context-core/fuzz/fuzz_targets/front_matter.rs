@@ -0,0 +1,17 @@
+//! Markdown file text → front matter and body. Files handed to `put --file` come from
+//! anywhere, so splitting them must not panic, and the body must be the tail of the input.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok((front_matter, body)) = context_core::frontmatter::split(text) {
+        assert!(text.ends_with(body), "{text:?} split into a body it doesn't end with");
+        assert!(
+            front_matter.tags.iter().all(|tag| !tag.is_empty()),
+            "{text:?} produced an empty tag"
+        );
+        let _ = context_core::frontmatter::first_heading(body);
+    }
+});
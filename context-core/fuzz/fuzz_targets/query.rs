@@ -0,0 +1,51 @@
+//! Search query text → FTS5 `MATCH` expression. Whatever an agent types, the parser
+//! must not panic, and any expression it produces must be one FTS5 accepts.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use libfuzzer_sys::fuzz_target;
+use sqlx::SqlitePool;
+use tokio::runtime::Runtime;
+
+fn fts() -> &'static (Runtime, SqlitePool) {
+    static FTS: OnceLock<(Runtime, SqlitePool)> = OnceLock::new();
+    FTS.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let pool = runtime.block_on(async {
+            let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+            sqlx::query("CREATE VIRTUAL TABLE docs USING fts5(body, code)")
+                .execute(&pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO docs VALUES ('alpha bravo', 'parse http request')")
+                .execute(&pool)
+                .await
+                .unwrap();
+            pool
+        });
+        (runtime, pool)
+    })
+}
+
+fuzz_target!(|text: &str| {
+    let (runtime, pool) = fts();
+    for code in [false, true] {
+        let Ok(expression) = context_core::sqlite::match_expression(text, code) else {
+            continue;
+        };
+        runtime.block_on(async {
+            if let Err(err) = sqlx::query("SELECT rowid FROM docs WHERE docs MATCH ?")
+                .bind(&expression)
+                .fetch_all(pool)
+                .await
+            {
+                panic!("{text:?} (code: {code}) became {expression:?}, which FTS5 rejects: {err}");
+            }
+        });
+    }
+});
@@ -0,0 +1,12 @@
+-- Registered namespaces per project, so namespaces can exist before any
+-- document uses them and typos show up as unfamiliar entries.
+CREATE TABLE namespaces (
+    project_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (project_id, name),
+    CONSTRAINT fk_namespaces_project FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+INSERT INTO namespaces (project_id, name)
+SELECT DISTINCT project_id, namespace FROM documents WHERE namespace IS NOT NULL;
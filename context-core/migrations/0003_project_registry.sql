@@ -0,0 +1,5 @@
+-- Project registry metadata, so the CLI and web UI read the same list of
+-- projects from the database instead of a separate config file.
+ALTER TABLE projects ADD COLUMN display_name TEXT;
+ALTER TABLE projects ADD COLUMN description TEXT;
+ALTER TABLE projects ADD COLUMN settings TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,10 @@
+-- Keys retired by merges or renames, pointing at the key that replaced them,
+-- so stale references in prompts and other documents keep resolving.
+CREATE TABLE key_redirects (
+    project_id TEXT NOT NULL,
+    from_key TEXT NOT NULL,
+    to_key TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (project_id, from_key),
+    CONSTRAINT fk_key_redirects_project FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
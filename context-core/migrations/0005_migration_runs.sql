@@ -0,0 +1,9 @@
+-- Audit trail of schema upgrades: which versions each run moved between and
+-- where the pre-migration snapshot of the database was written.
+CREATE TABLE migration_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    from_version INTEGER NOT NULL,
+    to_version INTEGER NOT NULL,
+    backup_path TEXT,
+    applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -0,0 +1,9 @@
+-- The oldest schema version a binary must understand to open this database.
+-- Migrations that only add nullable columns or new tables leave it alone, so older
+-- releases keep working; a migration older readers would corrupt data under raises it.
+CREATE TABLE schema_compat (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    min_reader_version INTEGER NOT NULL
+);
+
+INSERT INTO schema_compat (id, min_reader_version) VALUES (1, 6);
@@ -0,0 +1,102 @@
+-- Store sources as stable lowercase names instead of Rust Debug strings, allow the
+-- tool/web/mcp sources, and keep per-write source details (tool name, agent session)
+-- as JSON. SQLite cannot alter a CHECK constraint, so both tables are rebuilt.
+--
+-- The history table is rebuilt first, pointing at the new documents table, so
+-- dropping the old documents table has no dependent rows to cascade into.
+
+CREATE TABLE documents_new (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    key TEXT,
+    namespace TEXT,
+    title TEXT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    body_markdown TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    source TEXT NOT NULL,
+    source_meta TEXT,
+    version INTEGER NOT NULL DEFAULT 1,
+    ttl_seconds INTEGER,
+    deleted_at TEXT,
+    CONSTRAINT fk_documents_project FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE,
+    CONSTRAINT documents_source_valid CHECK (source IN ('agent', 'user', 'import', 'system', 'tool', 'web', 'mcp'))
+);
+
+-- Keep rowids: documents_fts rows are keyed by them.
+INSERT INTO documents_new (rowid, id, project_id, key, namespace, title, tags, body_markdown, created_at, updated_at, source, version, ttl_seconds, deleted_at)
+SELECT rowid, id, project_id, key, namespace, title, tags, body_markdown, created_at, updated_at, lower(source), version, ttl_seconds, deleted_at
+FROM documents;
+
+CREATE TABLE document_versions_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    title TEXT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    body_markdown TEXT NOT NULL,
+    namespace TEXT,
+    key TEXT,
+    source TEXT NOT NULL,
+    source_meta TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    ttl_seconds INTEGER,
+    deleted_at TEXT,
+    CONSTRAINT fk_document_versions_document FOREIGN KEY (document_id) REFERENCES documents_new(id) ON DELETE CASCADE,
+    CONSTRAINT document_versions_source_valid CHECK (source IN ('agent', 'user', 'import', 'system', 'tool', 'web', 'mcp')),
+    CONSTRAINT version_unique UNIQUE (document_id, version)
+);
+
+INSERT INTO document_versions_new (id, document_id, version, title, tags, body_markdown, namespace, key, source, created_at, ttl_seconds, deleted_at)
+SELECT id, document_id, version, title, tags, body_markdown, namespace, key, lower(source), created_at, ttl_seconds, deleted_at
+FROM document_versions;
+
+DROP TRIGGER documents_ai;
+DROP TRIGGER documents_ad;
+DROP TRIGGER documents_au;
+DROP TABLE document_versions;
+DROP TABLE documents;
+
+-- Renaming rewrites the history table's foreign key to follow.
+ALTER TABLE documents_new RENAME TO documents;
+ALTER TABLE document_versions_new RENAME TO document_versions;
+
+CREATE UNIQUE INDEX idx_documents_project_key ON documents(project_id, key) WHERE key IS NOT NULL;
+CREATE INDEX idx_documents_project ON documents(project_id);
+CREATE INDEX idx_documents_project_updated ON documents(project_id, updated_at);
+CREATE INDEX idx_document_versions_document ON document_versions(document_id);
+
+CREATE TRIGGER documents_ai AFTER INSERT ON documents BEGIN
+    INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace)
+    VALUES (
+        new.rowid,
+        new.id,
+        new.project_id,
+        coalesce(new.title, ''),
+        new.body_markdown,
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.tags)), ''),
+        coalesce(new.namespace, '')
+    );
+END;
+
+CREATE TRIGGER documents_ad AFTER DELETE ON documents BEGIN
+    DELETE FROM documents_fts WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER documents_au AFTER UPDATE ON documents BEGIN
+    DELETE FROM documents_fts WHERE rowid = old.rowid;
+    INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace)
+    VALUES (
+        new.rowid,
+        new.id,
+        new.project_id,
+        coalesce(new.title, ''),
+        new.body_markdown,
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.tags)), ''),
+        coalesce(new.namespace, '')
+    );
+END;
+
+-- Binaries that write the old Debug names would now violate the constraint.
+UPDATE schema_compat SET min_reader_version = 7 WHERE id = 1;
@@ -0,0 +1,10 @@
+-- Recently used client idempotency keys, so a retried write replays the first
+-- result instead of storing another revision. Rows older than a day are purged.
+CREATE TABLE idempotency_keys (
+    project_id TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    response TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (project_id, idempotency_key)
+);
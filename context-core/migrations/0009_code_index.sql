@@ -0,0 +1,64 @@
+-- Identifiers from fenced code blocks, split into words, in their own search column.
+-- `code_terms` is filled in by the application when it writes a document; rows written
+-- before this migration (or by older releases) are NULL until backfilled on open.
+ALTER TABLE documents ADD COLUMN code_terms TEXT;
+
+DROP TRIGGER documents_ai;
+DROP TRIGGER documents_ad;
+DROP TRIGGER documents_au;
+DROP TABLE documents_fts;
+
+CREATE VIRTUAL TABLE documents_fts USING fts5(
+    document_id UNINDEXED,
+    project_id UNINDEXED,
+    title,
+    body,
+    tags,
+    namespace,
+    code
+);
+
+INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code)
+SELECT
+    rowid,
+    id,
+    project_id,
+    coalesce(title, ''),
+    body_markdown,
+    coalesce((SELECT group_concat(value, ' ') FROM json_each(tags)), ''),
+    coalesce(namespace, ''),
+    ''
+FROM documents;
+
+CREATE TRIGGER documents_ai AFTER INSERT ON documents BEGIN
+    INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code)
+    VALUES (
+        new.rowid,
+        new.id,
+        new.project_id,
+        coalesce(new.title, ''),
+        new.body_markdown,
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.tags)), ''),
+        coalesce(new.namespace, ''),
+        coalesce(new.code_terms, '')
+    );
+END;
+
+CREATE TRIGGER documents_ad AFTER DELETE ON documents BEGIN
+    DELETE FROM documents_fts WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER documents_au AFTER UPDATE ON documents BEGIN
+    DELETE FROM documents_fts WHERE rowid = old.rowid;
+    INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code)
+    VALUES (
+        new.rowid,
+        new.id,
+        new.project_id,
+        coalesce(new.title, ''),
+        new.body_markdown,
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.tags)), ''),
+        coalesce(new.namespace, ''),
+        coalesce(new.code_terms, '')
+    );
+END;
@@ -0,0 +1,16 @@
+-- TODO/DECISION markers and checkboxes found in each live document's body, rewritten
+-- on every write. See `context_core::items`.
+CREATE TABLE items (
+    document_id TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    text TEXT NOT NULL,
+    done INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (document_id, line),
+    CONSTRAINT fk_items_document FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE,
+    CONSTRAINT items_kind_valid CHECK (kind IN ('todo', 'task', 'decision'))
+);
+
+-- Existing bodies have not been scanned yet: clearing the derived code terms makes
+-- the next open re-derive both for every document.
+UPDATE documents SET code_terms = NULL;
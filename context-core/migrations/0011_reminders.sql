@@ -0,0 +1,14 @@
+-- Follow-ups attached to documents (`context remind`). Timestamps are RFC 3339 in UTC.
+CREATE TABLE reminders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id TEXT NOT NULL,
+    remind_at TEXT NOT NULL,
+    note TEXT,
+    created_at TEXT NOT NULL,
+    -- Set once a desktop notification has been shown, so the daemon shows it only once.
+    notified_at TEXT,
+    done_at TEXT,
+    CONSTRAINT fk_reminders_document FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_reminders_pending ON reminders(remind_at) WHERE done_at IS NULL;
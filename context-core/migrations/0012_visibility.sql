@@ -0,0 +1,6 @@
+-- Who a document is for: 'private', 'project', or 'public'. NULL reads as 'project'.
+ALTER TABLE documents ADD COLUMN visibility TEXT;
+ALTER TABLE document_versions ADD COLUMN visibility TEXT;
+
+-- Binaries that don't know the column would export and serve private documents.
+UPDATE schema_compat SET min_reader_version = 12 WHERE id = 1;
@@ -0,0 +1,11 @@
+-- Reusable Markdown skeletons (`context template`), filled in with `{{var}}` values by
+-- `context put --from-template`. Names are unique per project.
+CREATE TABLE templates (
+    project_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    description TEXT,
+    body_markdown TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (project_id, name)
+);
@@ -0,0 +1,68 @@
+-- Structured fields beyond tags (`{"ticket": "ABC-123"}`), as a JSON object. NULL reads as
+-- no metadata. The values are searchable in their own column of the full-text index.
+ALTER TABLE documents ADD COLUMN metadata TEXT;
+ALTER TABLE document_versions ADD COLUMN metadata TEXT;
+
+DROP TRIGGER documents_ai;
+DROP TRIGGER documents_ad;
+DROP TRIGGER documents_au;
+DROP TABLE documents_fts;
+
+CREATE VIRTUAL TABLE documents_fts USING fts5(
+    document_id UNINDEXED,
+    project_id UNINDEXED,
+    title,
+    body,
+    tags,
+    namespace,
+    code,
+    metadata
+);
+
+INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code, metadata)
+SELECT
+    rowid,
+    id,
+    project_id,
+    coalesce(title, ''),
+    body_markdown,
+    coalesce((SELECT group_concat(value, ' ') FROM json_each(tags)), ''),
+    coalesce(namespace, ''),
+    coalesce(code_terms, ''),
+    ''
+FROM documents;
+
+CREATE TRIGGER documents_ai AFTER INSERT ON documents BEGIN
+    INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code, metadata)
+    VALUES (
+        new.rowid,
+        new.id,
+        new.project_id,
+        coalesce(new.title, ''),
+        new.body_markdown,
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.tags)), ''),
+        coalesce(new.namespace, ''),
+        coalesce(new.code_terms, ''),
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.metadata)), '')
+    );
+END;
+
+CREATE TRIGGER documents_ad AFTER DELETE ON documents BEGIN
+    DELETE FROM documents_fts WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER documents_au AFTER UPDATE ON documents BEGIN
+    DELETE FROM documents_fts WHERE rowid = old.rowid;
+    INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code, metadata)
+    VALUES (
+        new.rowid,
+        new.id,
+        new.project_id,
+        coalesce(new.title, ''),
+        new.body_markdown,
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.tags)), ''),
+        coalesce(new.namespace, ''),
+        coalesce(new.code_terms, ''),
+        coalesce((SELECT group_concat(value, ' ') FROM json_each(new.metadata)), '')
+    );
+END;
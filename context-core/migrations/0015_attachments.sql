@@ -0,0 +1,13 @@
+-- Files attached to documents (`context attach`). One row per document and file name;
+-- attaching the same name again replaces the bytes. Timestamps are RFC 3339 in UTC.
+CREATE TABLE attachments (
+    document_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    media_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (document_id, name),
+    CONSTRAINT fk_attachments_document FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+);
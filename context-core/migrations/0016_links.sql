@@ -0,0 +1,15 @@
+-- The documents each document's body links to, by project and key, rewritten on every
+-- write. See `context_core::links`.
+CREATE TABLE links (
+    source_id TEXT NOT NULL,
+    target_project TEXT NOT NULL,
+    target_key TEXT NOT NULL,
+    PRIMARY KEY (source_id, target_project, target_key),
+    CONSTRAINT fk_links_document FOREIGN KEY (source_id) REFERENCES documents(id) ON DELETE CASCADE
+);
+
+CREATE INDEX links_target ON links (target_project, target_key);
+
+-- Existing bodies have not been scanned yet: clearing the derived code terms makes
+-- the next open re-derive them, links included, for every document.
+UPDATE documents SET code_terms = NULL;
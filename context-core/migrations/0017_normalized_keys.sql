@@ -0,0 +1,7 @@
+-- Each key's normalized form (NFC, lowercase), so a lookup with key normalization on can
+-- find a key written before it was turned on without scanning the project. Written by
+-- `write_document`; existing rows are filled in on the next open.
+ALTER TABLE documents ADD COLUMN normalized_key TEXT;
+
+CREATE INDEX idx_documents_project_normalized_key ON documents(project_id, normalized_key)
+    WHERE normalized_key IS NOT NULL;
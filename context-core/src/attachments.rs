@@ -0,0 +1,69 @@
+//! Files attached to documents (`context attach`, `context attachments`).
+//!
+//! Attachments belong to a document, not to one of its revisions: a new revision keeps
+//! them, and purging the document removes them. Their bytes live in the `attachments`
+//! table next to the documents, so backups and snapshots of the database carry them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{error::fail, Result};
+
+/// Files larger than this are refused; the store is not meant for build artifacts.
+pub const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// What is known about an attachment without reading its bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    pub document_id: String,
+    pub name: String,
+    pub media_type: String,
+    pub size: u64,
+    /// Hex SHA-256 of the bytes.
+    pub sha256: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Attachment names are file names: not empty, no `/` or `\`, and not `.` or `..`.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        fail!(
+            Validation,
+            "Attachment name {name:?} must be a file name without '/' or '\\'."
+        );
+    }
+    Ok(())
+}
+
+/// The media type for a file name, from its extension; `application/octet-stream` when
+/// the extension is unknown.
+pub fn media_type_for(name: &str) -> &'static str {
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        "txt" | "log" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Hex SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
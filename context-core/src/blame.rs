@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use crate::{Document, SourceType};
+
+/// Attribution for one line of the latest revision.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlameLine {
+    /// 1-based line number in the latest revision.
+    pub line: usize,
+    pub version: u64,
+    pub source: SourceType,
+    pub changed_at: DateTime<Utc>,
+    pub text: String,
+}
+
+/// Attribute each line of the newest revision to the revision that last changed it.
+///
+/// `revisions` must be ordered oldest first, as returned by `Storage::history`.
+/// Consecutive revisions are diffed line by line; unchanged lines keep their
+/// earlier attribution and inserted or replaced lines are credited to the
+/// revision that introduced them.
+pub fn blame(revisions: &[Document]) -> Vec<BlameLine> {
+    let mut lines: Vec<BlameLine> = Vec::new();
+    let mut previous: Vec<&str> = Vec::new();
+
+    for revision in revisions {
+        let current: Vec<&str> = revision.body_markdown.lines().collect();
+        let mut next = Vec::with_capacity(current.len());
+
+        for op in capture_diff_slices(Algorithm::Myers, &previous, &current) {
+            match op {
+                DiffOp::Equal { old_index, len, .. } => {
+                    next.extend(lines[old_index..old_index + len].iter().cloned());
+                }
+                DiffOp::Delete { .. } => {}
+                DiffOp::Insert {
+                    new_index, new_len, ..
+                }
+                | DiffOp::Replace {
+                    new_index, new_len, ..
+                } => {
+                    next.extend(current[new_index..new_index + new_len].iter().map(|text| {
+                        BlameLine {
+                            line: 0,
+                            version: revision.version,
+                            source: revision.source.clone(),
+                            changed_at: revision.updated_at,
+                            text: text.to_string(),
+                        }
+                    }));
+                }
+            }
+        }
+
+        lines = next;
+        previous = current;
+    }
+
+    for (idx, line) in lines.iter_mut().enumerate() {
+        line.line = idx + 1;
+    }
+    lines
+}
@@ -0,0 +1,162 @@
+//! Pending reminders and TTL expirations as an iCalendar (RFC 5545) feed, so they show
+//! up next to everything else in a normal calendar (`context calendar`,
+//! `GET /api/calendar.ics`).
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::{
+    reminders::{Reminder, ReminderQuery},
+    sqlite::SqliteStorage,
+    Document, ProjectId,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Reminder,
+    Expiry,
+}
+
+/// One point-in-time entry of the feed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CalendarEvent {
+    /// Stable across exports, so calendars update events instead of duplicating them.
+    pub uid: String,
+    pub kind: EventKind,
+    pub at: DateTime<Utc>,
+    pub project: ProjectId,
+    pub document_id: String,
+    pub key: Option<String>,
+    pub summary: String,
+    pub description: Option<String>,
+}
+
+impl CalendarEvent {
+    pub fn reminder(reminder: &Reminder) -> Self {
+        let label = reminder.key.as_deref().unwrap_or(&reminder.document_id);
+        Self {
+            uid: format!("reminder-{}@context", reminder.id),
+            kind: EventKind::Reminder,
+            at: reminder.remind_at,
+            project: reminder.project.clone(),
+            document_id: reminder.document_id.clone(),
+            key: reminder.key.clone(),
+            summary: format!("Follow up: {}/{label}", reminder.project),
+            description: reminder.note.clone(),
+        }
+    }
+
+    /// The moment a document's TTL runs out; `None` without a TTL.
+    pub fn expiry(document: &Document) -> Option<Self> {
+        let ttl = document.ttl_seconds?;
+        let label = document.key.as_deref().unwrap_or(&document.id.0);
+        Some(Self {
+            uid: format!("expiry-{}@context", document.id.0),
+            kind: EventKind::Expiry,
+            at: document.created_at + Duration::seconds(ttl),
+            project: document.project.clone(),
+            document_id: document.id.0.clone(),
+            key: document.key.clone(),
+            summary: format!("Expires: {}/{label}", document.project),
+            description: document.title.clone(),
+        })
+    }
+}
+
+/// Events for `reminders` and `documents`, in time order.
+pub fn calendar_events(reminders: &[Reminder], documents: &[Document]) -> Vec<CalendarEvent> {
+    let mut events: Vec<CalendarEvent> = reminders
+        .iter()
+        .filter(|reminder| reminder.done_at.is_none())
+        .map(CalendarEvent::reminder)
+        .chain(documents.iter().filter_map(CalendarEvent::expiry))
+        .collect();
+    events.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.uid.cmp(&b.uid)));
+    events
+}
+
+/// Every pending reminder and upcoming expiration in `project` (all projects for `None`).
+pub async fn upcoming_events(
+    storage: &SqliteStorage,
+    project: Option<&ProjectId>,
+) -> crate::Result<Vec<CalendarEvent>> {
+    let reminders = storage
+        .reminders(&ReminderQuery {
+            project: project.cloned(),
+            ..ReminderQuery::default()
+        })
+        .await?;
+    let documents = storage.expiring_documents(project).await?;
+    Ok(calendar_events(&reminders, &documents))
+}
+
+/// Render `events` as a VCALENDAR; `stamp` is the export time written to each event.
+pub fn to_ics(events: &[CalendarEvent], stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//context//reminders//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:context".to_string(),
+    ];
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", escape(&event.uid)));
+        lines.push(format!("DTSTAMP:{}", format_time(stamp)));
+        lines.push(format!("DTSTART:{}", format_time(event.at)));
+        lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        let category = match event.kind {
+            EventKind::Reminder => "REMINDER",
+            EventKind::Expiry => "EXPIRY",
+        };
+        lines.push(format!("CATEGORIES:{category}"));
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        out.push_str(&fold(&line));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value: backslashes, separators, and line breaks.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Fold a content line to 75 octets per physical line, never splitting a character.
+fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out
+}
@@ -0,0 +1,99 @@
+//! Notifications of document writes, for `context-web`'s `GET /api/events`.
+//!
+//! `SqliteStorage` announces each revision once its transaction commits, to every
+//! subscriber of that store (clones share one feed). Only writes made through this
+//! process's store are seen; another process writing the same database file, such as
+//! the CLI, goes unannounced. A hard delete announces the document as deleted; purges
+//! of tombstones and in-place key or TTL rewrites add no revision and so announce nothing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::{Document, Key, ProjectId, Visibility};
+
+/// How many changes a subscriber may fall behind before it starts missing them.
+pub const CHANGE_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Updated => "updated",
+            ChangeKind::Deleted => "deleted",
+        }
+    }
+}
+
+/// One revision written to the store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentChange {
+    pub kind: ChangeKind,
+    pub project: ProjectId,
+    pub id: String,
+    pub key: Option<Key>,
+    pub version: u64,
+    pub visibility: Visibility,
+    pub at: DateTime<Utc>,
+    /// Size of the revision's body in bytes.
+    pub bytes: u64,
+}
+
+impl DocumentChange {
+    /// The change `doc`, as just written, makes.
+    pub fn of(doc: &Document) -> Self {
+        let kind = match (doc.deleted_at, doc.version) {
+            (Some(_), _) => ChangeKind::Deleted,
+            (None, 1) => ChangeKind::Created,
+            (None, _) => ChangeKind::Updated,
+        };
+        Self {
+            kind,
+            project: doc.project.clone(),
+            id: doc.id.0.clone(),
+            key: doc.key.clone(),
+            version: doc.version,
+            visibility: doc.visibility(),
+            at: doc.updated_at,
+            bytes: doc.body_markdown.len() as u64,
+        }
+    }
+}
+
+/// The sending side of a store's feed.
+#[derive(Debug, Clone)]
+pub(crate) struct ChangeFeed {
+    sender: broadcast::Sender<DocumentChange>,
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CHANGE_BUFFER).0,
+        }
+    }
+}
+
+impl ChangeFeed {
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<DocumentChange> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn announce<'a>(&self, documents: impl IntoIterator<Item = &'a Document>) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        for doc in documents {
+            // Fails only when every subscriber has gone, which is nobody's problem.
+            let _ = self.sender.send(DocumentChange::of(doc));
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Terms from fenced code blocks, indexed in their own search column so identifiers
+//! pasted into a note can be found by name (`context find --code`).
+//!
+//! SQLite's tokenizer already breaks `snake_case` apart at the underscores but keeps
+//! `parseHttpRequest` as one opaque token. Each identifier is therefore indexed whole
+//! and followed by its words (`parseHttpRequest parse http request`), and a code
+//! query matches either form.
+
+/// The contents of the ```` ``` ```` and `~~~` fenced blocks in `markdown`, in order.
+/// An unclosed fence runs to the end of the document, as CommonMark renders it.
+pub fn fenced_blocks(markdown: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(char, usize, Vec<&str>)> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let fence = trimmed
+            .chars()
+            .next()
+            .filter(|c| *c == '`' || *c == '~')
+            .map(|c| (c, trimmed.chars().take_while(|d| *d == c).count()))
+            .filter(|(_, len)| *len >= 3);
+        let closes = matches!(
+            (&open, fence),
+            (Some((c, len, _)), Some((d, closing)))
+                if *c == d && closing >= *len && trimmed.trim_start_matches(d).trim().is_empty()
+        );
+        if closes {
+            let (_, _, lines) = open.take().expect("a block is open");
+            blocks.push(lines.join("\n"));
+        } else if let Some((_, _, lines)) = &mut open {
+            lines.push(line);
+        } else if let Some((c, len)) = fence {
+            open = Some((c, len, Vec::new()));
+        }
+    }
+    if let Some((_, _, lines)) = open {
+        blocks.push(lines.join("\n"));
+    }
+    blocks
+}
+
+/// The words of an identifier: split at `_`, `-`, and case changes, lowercased.
+/// `HTTPServer_v2` becomes `["http", "server", "v2"]`.
+pub fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for chunk in identifier.split(['_', '-']) {
+        let chars: Vec<char> = chunk.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1], chars[i]);
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            let boundary = (cur.is_uppercase() && (prev.is_lowercase() || prev.is_ascii_digit()))
+                || (cur.is_uppercase() && prev.is_uppercase() && next_is_lower);
+            if boundary {
+                words.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        if start < chars.len() {
+            words.push(chars[start..].iter().collect::<String>().to_lowercase());
+        }
+    }
+    words
+}
+
+/// What the `code` search column holds for `markdown`: every identifier in its fenced
+/// blocks, each followed by its words when it has more than one. Empty without code.
+pub fn code_terms(markdown: &str) -> String {
+    let mut terms = Vec::new();
+    for block in fenced_blocks(markdown) {
+        for identifier in block
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| word.chars().any(char::is_alphabetic))
+        {
+            terms.push(identifier.to_string());
+            let words = split_identifier(identifier);
+            if words.len() > 1 {
+                terms.extend(words);
+            }
+        }
+    }
+    terms.join(" ")
+}
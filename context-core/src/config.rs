@@ -0,0 +1,74 @@
+//! The store settings in a context home's `config.json`: key rules, expiry rules, and the
+//! query timeout. Every binary that opens a home applies them through
+//! [`configure_storage`], so a document written through the CLI, the daemon,
+//! `context-web`, or `context-grpc` gets the same key and TTL treatment.
+
+use std::{path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Context,
+    keys::KeyRules,
+    policy::{ExpiryPolicy, ExpiryRule},
+    sqlite::SqliteStorage,
+    Result,
+};
+
+/// How long a search or count may run when config.json doesn't say.
+pub const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 30;
+
+/// The parts of `config.json` that change how the store behaves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreConfig {
+    #[serde(default)]
+    pub keys: KeyConfig,
+    /// Tag-based expiry rules, e.g. `[{"tag": "scratch", "after": "14d"}]`.
+    #[serde(default)]
+    pub expiry: Vec<ExpiryRule>,
+    /// Seconds a search or count may run before it is stopped (default 30); 0 never
+    /// stops one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyConfig {
+    /// Store keys as NFC + lowercase and match them case-insensitively.
+    #[serde(default)]
+    pub normalize: bool,
+    /// Format rules enforced when keys are written.
+    #[serde(flatten)]
+    pub rules: KeyRules,
+}
+
+impl StoreConfig {
+    /// The store settings in `<home>/config.json`; defaults when there is no such file.
+    pub fn load(home: &Path) -> Result<Self> {
+        let path = home.join("config.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+}
+
+/// Apply `config`'s key, expiry, and query timeout settings to `storage`.
+pub fn configure_storage(storage: SqliteStorage, config: StoreConfig) -> Result<SqliteStorage> {
+    let expiry = ExpiryPolicy::new(config.expiry).context("Invalid expiry rule in config.json")?;
+    let storage = storage
+        .with_key_normalization(config.keys.normalize)
+        .with_key_rules(config.keys.rules)
+        .with_expiry_policy(expiry);
+    Ok(
+        match config
+            .query_timeout_secs
+            .unwrap_or(DEFAULT_QUERY_TIMEOUT_SECS)
+        {
+            0 => storage,
+            seconds => storage.with_query_timeout(Duration::from_secs(seconds)),
+        },
+    )
+}
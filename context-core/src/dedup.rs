@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{Document, Key};
+
+/// Words per shingle when comparing bodies for near-duplicates.
+const SHINGLE_WORDS: usize = 3;
+
+/// One document in a duplicate group.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateMember {
+    pub id: String,
+    pub key: Option<Key>,
+    pub version: u64,
+    pub updated_at: DateTime<Utc>,
+    /// Shingle similarity to the group's newest document (1.0 for the newest itself).
+    pub similarity: f32,
+}
+
+/// Documents whose bodies are identical or nearly so.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Every body is the same once whitespace is collapsed.
+    pub exact: bool,
+    /// Newest first; `members[0]` is the one `keep-newest` keeps.
+    pub members: Vec<DuplicateMember>,
+}
+
+/// Group `documents` whose bodies match exactly or whose word-shingle Jaccard
+/// similarity reaches `threshold` (0.0–1.0).
+///
+/// Similarity is transitive for grouping: if A matches B and B matches C, all three
+/// land in one group even when A and C fall below the threshold.
+pub fn find_duplicates(documents: &[Document], threshold: f32) -> Vec<DuplicateGroup> {
+    let bodies: Vec<String> = documents
+        .iter()
+        .map(|doc| normalize_body(&doc.body_markdown))
+        .collect();
+    let shingles: Vec<HashSet<String>> = bodies.iter().map(|body| shingle_set(body)).collect();
+
+    let mut groups = DisjointSet::new(documents.len());
+    let mut by_body: HashMap<&str, usize> = HashMap::new();
+    for (idx, body) in bodies.iter().enumerate() {
+        if let Some(&first) = by_body.get(body.as_str()) {
+            groups.union(first, idx);
+        } else {
+            by_body.insert(body, idx);
+        }
+    }
+    for a in 0..documents.len() {
+        for b in a + 1..documents.len() {
+            if groups.find(a) != groups.find(b) && jaccard(&shingles[a], &shingles[b]) >= threshold
+            {
+                groups.union(a, b);
+            }
+        }
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for idx in 0..documents.len() {
+        members.entry(groups.find(idx)).or_default().push(idx);
+    }
+
+    let mut result: Vec<DuplicateGroup> = members
+        .into_values()
+        .filter(|indices| indices.len() > 1)
+        .map(|mut indices| {
+            indices.sort_by(|&a, &b| {
+                documents[b]
+                    .updated_at
+                    .cmp(&documents[a].updated_at)
+                    .then_with(|| documents[a].id.0.cmp(&documents[b].id.0))
+            });
+            let newest = indices[0];
+            DuplicateGroup {
+                exact: indices.iter().all(|&idx| bodies[idx] == bodies[newest]),
+                members: indices
+                    .iter()
+                    .map(|&idx| {
+                        let doc = &documents[idx];
+                        DuplicateMember {
+                            id: doc.id.0.clone(),
+                            key: doc.key.clone(),
+                            version: doc.version,
+                            updated_at: doc.updated_at,
+                            similarity: if bodies[idx] == bodies[newest] {
+                                1.0
+                            } else {
+                                jaccard(&shingles[idx], &shingles[newest])
+                            },
+                        }
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    result.sort_by(|a, b| {
+        b.members
+            .len()
+            .cmp(&a.members.len())
+            .then_with(|| a.members[0].id.cmp(&b.members[0].id))
+    });
+    result
+}
+
+fn normalize_body(body: &str) -> String {
+    body.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn shingle_set(body: &str) -> HashSet<String> {
+    let words: Vec<String> = body.split_whitespace().map(str::to_lowercase).collect();
+    if words.len() <= SHINGLE_WORDS {
+        return HashSet::from([words.join(" ")]);
+    }
+    words
+        .windows(SHINGLE_WORDS)
+        .map(|window| window.join(" "))
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let total = a.union(b).count();
+    if total == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f32 / total as f32
+}
+
+/// Union-find over document indices.
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, idx: usize) -> usize {
+        let parent = self.parent[idx];
+        if parent == idx {
+            return idx;
+        }
+        let root = self.find(parent);
+        self.parent[idx] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[b] = a;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+
+use crate::{Document, DocumentId, Key};
+
+/// Whether a diff line is shared by both revisions or only in one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineChange {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub change: LineChange,
+    /// The line without its newline.
+    pub text: String,
+}
+
+/// One `@@ -old_start,old_lines +new_start,new_lines @@` block of a unified diff.
+///
+/// Starts are 1-based; an empty side starts at the line before the change, as in
+/// `diff -u`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+impl DiffHunk {
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{} +{} @@",
+            range(self.old_start, self.old_lines),
+            range(self.new_start, self.new_lines)
+        )
+    }
+}
+
+/// The body changes between two revisions of a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionDiff {
+    pub id: DocumentId,
+    pub key: Option<Key>,
+    pub from: u64,
+    pub to: u64,
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl VersionDiff {
+    /// The diff as `diff -u` text, labelled `<key or id> vN`; empty when nothing changed.
+    pub fn unified(&self) -> String {
+        if self.hunks.is_empty() {
+            return String::new();
+        }
+        let label = self.key.as_deref().unwrap_or(&self.id.0);
+        let mut out = format!("--- {label} v{}\n+++ {label} v{}\n", self.from, self.to);
+        for hunk in &self.hunks {
+            out.push_str(&hunk.header());
+            out.push('\n');
+            for line in &hunk.lines {
+                out.push(match line.change {
+                    LineChange::Context => ' ',
+                    LineChange::Added => '+',
+                    LineChange::Removed => '-',
+                });
+                out.push_str(&line.text);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Diff the bodies of two revisions with `context` unchanged lines around each change.
+pub fn diff_revisions(from: &Document, to: &Document, context: usize) -> VersionDiff {
+    VersionDiff {
+        id: to.id.clone(),
+        key: to.key.clone().or_else(|| from.key.clone()),
+        from: from.version,
+        to: to.version,
+        hunks: diff_lines(&from.body_markdown, &to.body_markdown, context),
+    }
+}
+
+/// Line-by-line hunks turning `old` into `new`.
+pub fn diff_lines(old: &str, new: &str, context: usize) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    diff.grouped_ops(context)
+        .iter()
+        .map(|group| {
+            let (first, last) = (&group[0], &group[group.len() - 1]);
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    change: match change.tag() {
+                        ChangeTag::Equal => LineChange::Context,
+                        ChangeTag::Insert => LineChange::Added,
+                        ChangeTag::Delete => LineChange::Removed,
+                    },
+                    text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                })
+                .collect();
+            DiffHunk {
+                old_start: start(old_range.start, old_range.len()),
+                old_lines: old_range.len(),
+                new_start: start(new_range.start, new_range.len()),
+                new_lines: new_range.len(),
+                lines,
+            }
+        })
+        .collect()
+}
+
+fn start(index: usize, len: usize) -> usize {
+    if len == 0 {
+        index
+    } else {
+        index + 1
+    }
+}
+
+fn range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{start},{len}")
+    }
+}
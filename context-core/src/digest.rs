@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{sqlite::SqliteStorage, Document, DocumentId, SourceType, Storage};
+
+/// A stored digest and how many documents it covers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Digest {
+    pub document: Document,
+    pub covered: usize,
+}
+
+/// Summarize the documents of `project` updated since `from` into a `digest/<date>` document.
+///
+/// Earlier digests are left out, and a second digest on the same day replaces the first.
+pub async fn write_digest(
+    storage: &SqliteStorage,
+    project: &str,
+    from: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> crate::Result<Digest> {
+    let project = project.to_string();
+    let recent: Vec<Document> = storage
+        .stream_project(&project)
+        .try_filter(|doc| {
+            let is_digest = doc.tags.iter().any(|tag| tag == "digest");
+            futures::future::ready(doc.updated_at >= from && !is_digest)
+        })
+        .try_collect()
+        .await?;
+
+    let mut body = format!(
+        "# Digest for {project}: {} to {}\n\n",
+        from.format("%Y-%m-%d"),
+        now.format("%Y-%m-%d")
+    );
+    if recent.is_empty() {
+        body.push_str("No documents were created or updated in this period.\n");
+    }
+    for doc in &recent {
+        let heading = doc
+            .title
+            .clone()
+            .or_else(|| doc.key.clone())
+            .unwrap_or_else(|| doc.id.0.clone());
+        let change = if doc.version == 1 { "new" } else { "updated" };
+        body.push_str(&format!(
+            "- **{heading}** ({change}, v{}, {:?}, {})\n",
+            doc.version,
+            doc.source,
+            doc.updated_at.format("%Y-%m-%d %H:%M")
+        ));
+        if let Some(snippet) = snippet(&doc.body_markdown) {
+            body.push_str(&format!("  {snippet}\n"));
+        }
+        body.push_str(&format!("  `context get --id {}`\n", doc.id.0));
+    }
+
+    let draft = Document {
+        id: DocumentId(Uuid::new_v4().to_string()),
+        project,
+        key: Some(format!("digest/{}", now.format("%Y-%m-%d"))),
+        namespace: None,
+        title: Some(format!("Digest {}", now.format("%Y-%m-%d"))),
+        tags: vec!["digest".to_string()],
+        body_markdown: body,
+        created_at: now,
+        updated_at: now,
+        source: SourceType::System,
+        source_meta: None,
+        version: 1,
+        ttl_seconds: None,
+        deleted_at: None,
+        visibility: None,
+        metadata: Default::default(),
+    };
+    Ok(Digest {
+        document: storage.store(draft).await?,
+        covered: recent.len(),
+    })
+}
+
+/// First line of prose in a markdown body, shortened for listings.
+fn snippet(body: &str) -> Option<String> {
+    const MAX_CHARS: usize = 160;
+    let line = body
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("```"))?;
+    if line.chars().count() <= MAX_CHARS {
+        return Some(line.to_string());
+    }
+    let cut: String = line.chars().take(MAX_CHARS).collect();
+    Some(format!("{}…", cut.trim_end()))
+}
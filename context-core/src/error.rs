@@ -0,0 +1,203 @@
+//! The error every `context-core` operation returns.
+//!
+//! [`ContextError`] says what kind of failure it was, so the CLI, `context-web`, and
+//! `context-grpc` can each pick an exit code or status without reading the message. The
+//! message itself is still written for people.
+
+use std::fmt;
+
+use crate::{idempotency::IdempotencyConflict, VersionConflict};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContextError {
+    /// The document, revision, namespace, or other named thing does not exist.
+    #[error("{0}")]
+    NotFound(String),
+    /// The write collided with the current state of the store.
+    #[error(transparent)]
+    Conflict(#[from] Conflict),
+    /// The input was refused: a malformed name, key, rule, query, or duration.
+    #[error("{0}")]
+    Validation(String),
+    /// The database could not be opened, read, or written, or holds data this build
+    /// can't read.
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    /// Exchanging data with another store (a remote or peer) failed.
+    #[error("{0}")]
+    Sync(String),
+    /// The write is larger than the store accepts.
+    #[error("{0}")]
+    Quota(String),
+    /// The operation ran past its time limit and was stopped.
+    #[error("{0}")]
+    Timeout(String),
+}
+
+/// The ways a write can collide with what is already stored.
+#[derive(Debug, thiserror::Error)]
+pub enum Conflict {
+    #[error(transparent)]
+    Version(#[from] VersionConflict),
+    #[error(transparent)]
+    Idempotency(#[from] IdempotencyConflict),
+    /// Another live document holds the key.
+    #[error("{0}")]
+    Key(String),
+}
+
+/// A database or encoding failure, with what was being done when it happened.
+#[derive(Debug)]
+pub struct StorageError {
+    context: Option<String>,
+    source: BoxError,
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.context {
+            Some(context) => f.write_str(context),
+            None => self.source.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.context {
+            Some(_) => Some(&*self.source),
+            None => self.source.source(),
+        }
+    }
+}
+
+impl StorageError {
+    /// The underlying `sqlx` error, when there is one.
+    pub fn sqlx(&self) -> Option<&sqlx::Error> {
+        match self.source.downcast_ref::<StorageError>() {
+            Some(inner) => inner.sqlx(),
+            None => self.source.downcast_ref(),
+        }
+    }
+}
+
+impl ContextError {
+    /// A storage failure described only by `message`, for states that should not happen.
+    pub(crate) fn storage(message: impl Into<String>) -> Self {
+        let message: String = message.into();
+        StorageError {
+            context: None,
+            source: message.into(),
+        }
+        .into()
+    }
+
+    /// The version conflict behind this error, if that is what it is.
+    pub fn version_conflict(&self) -> Option<&VersionConflict> {
+        match self {
+            ContextError::Conflict(Conflict::Version(conflict)) => Some(conflict),
+            _ => None,
+        }
+    }
+
+    fn with_prefix(self, context: impl fmt::Display) -> Self {
+        match self {
+            ContextError::NotFound(message) => {
+                ContextError::NotFound(format!("{context}: {message}"))
+            }
+            ContextError::Validation(message) => {
+                ContextError::Validation(format!("{context}: {message}"))
+            }
+            ContextError::Sync(message) => ContextError::Sync(format!("{context}: {message}")),
+            ContextError::Quota(message) => ContextError::Quota(format!("{context}: {message}")),
+            ContextError::Timeout(message) => {
+                ContextError::Timeout(format!("{context}: {message}"))
+            }
+            ContextError::Storage(inner) => StorageError {
+                context: Some(context.to_string()),
+                source: Box::new(inner),
+            }
+            .into(),
+            // Conflicts describe themselves; a caller's context would only repeat it.
+            conflict @ ContextError::Conflict(_) => conflict,
+        }
+    }
+}
+
+macro_rules! storage_sources {
+    ($($source:ty),* $(,)?) => {
+        $(
+            impl From<$source> for ContextError {
+                fn from(source: $source) -> Self {
+                    StorageError {
+                        context: None,
+                        source: Box::new(source),
+                    }
+                    .into()
+                }
+            }
+        )*
+    };
+}
+
+storage_sources!(
+    sqlx::Error,
+    sqlx::migrate::MigrateError,
+    serde_json::Error,
+    chrono::ParseError,
+    std::io::Error,
+);
+
+impl From<VersionConflict> for ContextError {
+    fn from(conflict: VersionConflict) -> Self {
+        Conflict::Version(conflict).into()
+    }
+}
+
+impl From<IdempotencyConflict> for ContextError {
+    fn from(conflict: IdempotencyConflict) -> Self {
+        Conflict::Idempotency(conflict).into()
+    }
+}
+
+pub type Result<T, E = ContextError> = std::result::Result<T, E>;
+
+/// `anyhow::Context` for [`ContextError`]: say what was being done, keeping the kind.
+/// A missing `Option` value is a storage error, since it means the store changed or
+/// holds something unexpected.
+pub trait Context<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T>;
+
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, context: F) -> Result<T>;
+}
+
+impl<T, E: Into<ContextError>> Context<T> for std::result::Result<T, E> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T> {
+        self.map_err(|err| err.into().with_prefix(context))
+    }
+
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, context: F) -> Result<T> {
+        self.map_err(|err| err.into().with_prefix(context()))
+    }
+}
+
+impl<T> Context<T> for Option<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T> {
+        self.ok_or_else(|| ContextError::storage(context.to_string()))
+    }
+
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, context: F) -> Result<T> {
+        self.ok_or_else(|| ContextError::storage(context().to_string()))
+    }
+}
+
+/// `return Err(ContextError::$kind(format!(...)))`, like `anyhow::bail!` for one kind.
+macro_rules! fail {
+    ($kind:ident, $($arg:tt)*) => {
+        return Err($crate::error::ContextError::$kind(format!($($arg)*)))
+    };
+}
+
+pub(crate) use fail;
@@ -0,0 +1,251 @@
+//! Reproducible document corpora for tests, benchmarks, and demos.
+//!
+//! A fixture and a seed fully determine the documents (ids, keys, tags, bodies, revisions,
+//! and timestamps), so a failing test or a slow benchmark can be replayed exactly.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::fail, Document, DocumentId, ProjectId, SourceType, Storage};
+
+/// Seed used when none is given.
+pub const DEFAULT_SEED: u64 = 42;
+
+/// A named corpus shape.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Documents generated, before revisions.
+    pub documents: usize,
+    /// Upper bound on a document's paragraphs; most stay well below it.
+    pub max_paragraphs: usize,
+    /// Upper bound on a document's revisions (1 means no history).
+    pub max_revisions: u64,
+}
+
+pub const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "small",
+        description: "A dozen short notes with a little history, for unit-sized tests",
+        documents: 12,
+        max_paragraphs: 3,
+        max_revisions: 3,
+    },
+    Fixture {
+        name: "demo",
+        description: "Runbooks, decisions, and meeting notes across namespaces, for trying the UI",
+        documents: 80,
+        max_paragraphs: 8,
+        max_revisions: 5,
+    },
+    Fixture {
+        name: "large",
+        description: "Thousands of documents of mixed size, for benchmarks",
+        documents: 5_000,
+        max_paragraphs: 40,
+        max_revisions: 4,
+    },
+];
+
+/// The fixture called `name`.
+pub fn fixture(name: &str) -> crate::Result<&'static Fixture> {
+    match FIXTURES.iter().find(|fixture| fixture.name == name) {
+        Some(fixture) => Ok(fixture),
+        None => {
+            let names: Vec<&str> = FIXTURES.iter().map(|fixture| fixture.name).collect();
+            fail!(
+                Validation,
+                "Unknown fixture {name:?}; available: {}.",
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// What `load` wrote.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureReport {
+    pub fixture: String,
+    pub project: ProjectId,
+    pub seed: u64,
+    pub documents: usize,
+    /// Every stored revision, including each document's first.
+    pub revisions: usize,
+}
+
+const NAMESPACES: &[&str] = &["notes", "runbooks", "decisions", "meetings"];
+const TAGS: &[&str] = &[
+    "rust", "web", "infra", "incident", "design", "todo", "ops", "api",
+];
+const TOPICS: &[&str] = &[
+    "deploy",
+    "rollback",
+    "search",
+    "storage",
+    "auth",
+    "billing",
+    "cache",
+    "release",
+    "onboarding",
+    "metrics",
+    "backup",
+    "migration",
+];
+const WORDS: &[&str] = &[
+    "the", "service", "should", "index", "request", "latency", "agent", "config", "retry", "queue",
+    "token", "budget", "schema", "owner", "review", "follow", "up", "before", "after", "error",
+    "timeout", "cluster", "primary", "replica", "alert", "page", "runbook", "step", "check",
+    "verify", "document", "version", "key", "tag", "project", "rollout", "window",
+];
+const SOURCES: &[SourceType] = &[SourceType::Agent, SourceType::User, SourceType::Import];
+
+impl Fixture {
+    /// Every revision of every document, oldest revision of each first, as `load` stores them.
+    pub fn revisions(&self, project: &ProjectId, seed: u64) -> Vec<Document> {
+        let mut rng = Rng::new(seed);
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        // Ids are global, so the same fixture can go into two projects side by side.
+        let project_salt = fnv1a(project.as_bytes());
+        let mut out = Vec::new();
+        for index in 0..self.documents {
+            let namespace = *rng.pick(NAMESPACES);
+            let topic = *rng.pick(TOPICS);
+            let created_at = start + Duration::minutes(rng.below(365 * 24 * 60) as i64);
+            let mut doc = Document {
+                id: DocumentId(format!("fixture-{:016x}", rng.next() ^ project_salt)),
+                project: project.clone(),
+                key: Some(format!("{namespace}/{topic}-{index}")),
+                namespace: Some(namespace.to_string()),
+                title: Some(format!("{} {topic} {index}", capitalized(namespace))),
+                tags: self.tags(&mut rng),
+                body_markdown: String::new(),
+                created_at,
+                updated_at: created_at,
+                source: rng.pick(SOURCES).clone(),
+                source_meta: None,
+                version: 1,
+                ttl_seconds: None,
+                deleted_at: None,
+                visibility: None,
+                metadata: Default::default(),
+            };
+            let revisions = 1 + rng.below(self.max_revisions);
+            for version in 1..=revisions {
+                doc.version = version;
+                doc.body_markdown = self.body(&mut rng, topic);
+                if version > 1 {
+                    doc.updated_at = bump(doc.updated_at, &mut rng);
+                }
+                out.push(doc.clone());
+            }
+        }
+        out
+    }
+
+    fn tags(&self, rng: &mut Rng) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for _ in 0..rng.below(4) {
+            let tag = rng.pick(TAGS).to_string();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Mostly short bodies with an occasional long one, like real notes.
+    fn body(&self, rng: &mut Rng, topic: &str) -> String {
+        let long = rng.below(10) == 0;
+        let paragraphs = if long {
+            self.max_paragraphs
+        } else {
+            1 + rng.below(self.max_paragraphs.div_ceil(3) as u64) as usize
+        };
+        let mut body = format!("# {}\n\n", capitalized(topic));
+        for _ in 0..paragraphs {
+            let words: Vec<&str> = (0..8 + rng.below(40)).map(|_| *rng.pick(WORDS)).collect();
+            body.push_str(&capitalized(&words.join(" ")));
+            body.push_str(".\n\n");
+        }
+        match rng.below(4) {
+            0 => body.push_str(&format!("- [ ] follow up on {topic}\n")),
+            1 => body.push_str(&format!("DECISION: keep {topic} as is\n")),
+            _ => {}
+        }
+        body
+    }
+}
+
+/// Store `fixture` (generated from `seed`) in `project`, which must be empty so a
+/// load never mixes with real documents or an earlier load.
+pub async fn load(
+    storage: &dyn Storage,
+    fixture: &Fixture,
+    project: &ProjectId,
+    seed: u64,
+) -> crate::Result<FixtureReport> {
+    let existing = storage.count(project, &Default::default()).await?;
+    if existing > 0 {
+        fail!(
+            Validation,
+            "Project {project} already has {existing} document(s); load fixtures into an empty project."
+        );
+    }
+    let revisions = fixture.revisions(project, seed);
+    for revision in &revisions {
+        storage.put(revision.clone()).await?;
+    }
+    Ok(FixtureReport {
+        fixture: fixture.name.to_string(),
+        project: project.clone(),
+        seed,
+        documents: fixture.documents,
+        revisions: revisions.len(),
+    })
+}
+
+fn bump(at: DateTime<Utc>, rng: &mut Rng) -> DateTime<Utc> {
+    at + Duration::minutes(1 + rng.below(14 * 24 * 60) as i64)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// SplitMix64: tiny, fast, and stable across platforms and releases, which is all a
+/// fixture needs (and what a general-purpose RNG crate doesn't promise).
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform-enough in `0..bound` (`bound` > 0).
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound.max(1)
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{error::fail, Document, Result};
+
+/// The fields read from a `---` block at the top of a markdown file. Other keys are
+/// ignored, so front matter written for a static site generator still loads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub key: Option<String>,
+    pub namespace: Option<String>,
+    pub tags: Vec<String>,
+    /// A span such as `14d`, for `policy::parse_duration`.
+    pub ttl: Option<String>,
+    /// `private`, `project`, or `public`, for `Visibility::from_str`.
+    pub visibility: Option<String>,
+}
+
+/// Split `text` into its front matter and the body after it. Text that doesn't open with
+/// a `---` line closed by a later `---` (or `...`) line is all body.
+///
+/// The block is read as flat YAML: `key: value` lines with optionally quoted values, and
+/// `tags` as `[a, b]`, `a, b`, or `- a` lines under `tags:`. Indented lines under other
+/// keys are skipped; a top-level line without a colon is an error.
+pub fn split(text: &str) -> Result<(FrontMatter, &str)> {
+    let unmarked = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(inside) = after_line(unmarked, "---") else {
+        return Ok((FrontMatter::default(), text));
+    };
+    let mut offset = 0;
+    let mut close = None;
+    for line in inside.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        if content == "---" || content == "..." {
+            close = Some((offset, offset + line.len()));
+            break;
+        }
+        offset += line.len();
+    }
+    let Some((block_end, body_start)) = close else {
+        return Ok((FrontMatter::default(), text));
+    };
+
+    let mut front_matter = FrontMatter::default();
+    // The key of a `key:` line with nothing after it, whose `- item` lines may follow.
+    let mut list_key: Option<&str> = None;
+    for (index, line) in inside[..block_end].lines().enumerate() {
+        let content = line.trim_end();
+        if content.trim().is_empty() || content.trim_start().starts_with('#') {
+            continue;
+        }
+        if content.starts_with([' ', '\t', '-']) {
+            let item = content.trim_start().strip_prefix('-');
+            if let (Some("tags"), Some(item)) = (list_key, item) {
+                front_matter.tags.extend(scalar(item));
+            }
+            continue;
+        }
+        let Some((key, value)) = content.split_once(':') else {
+            // Line 1 is the opening `---`.
+            fail!(
+                Validation,
+                "Front matter line {} is not `key: value`: {content:?}",
+                index + 2
+            );
+        };
+        let key = key.trim();
+        let value = value.trim();
+        list_key = value.is_empty().then_some(key);
+        match key {
+            "title" => front_matter.title = scalar(value),
+            "key" => front_matter.key = scalar(value),
+            "namespace" => front_matter.namespace = scalar(value),
+            "ttl" => front_matter.ttl = scalar(value),
+            "visibility" => front_matter.visibility = scalar(value),
+            "tags" => front_matter.tags = list(value),
+            _ => {}
+        }
+    }
+    Ok((front_matter, &inside[body_start..]))
+}
+
+/// The text of the first `# heading` outside code fences.
+pub fn first_heading(body: &str) -> Option<String> {
+    let mut fence: Option<&str> = None;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker))
+        {
+            fence = match fence {
+                Some(open) if open == marker => None,
+                Some(open) => Some(open),
+                None => Some(marker),
+            };
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+        let Some(heading) = trimmed
+            .strip_prefix("# ")
+            .or_else(|| trimmed.strip_prefix("#\t"))
+        else {
+            continue;
+        };
+        let heading = heading.trim().trim_end_matches('#').trim_end();
+        if !heading.is_empty() {
+            return Some(heading.to_string());
+        }
+    }
+    None
+}
+
+/// `doc` as a markdown file: a front matter block that `split` reads back into the same
+/// key, namespace, title, tags, and visibility, then the body. The timestamps and version are there
+/// for whoever reads the file; `split` skips them.
+pub fn render(doc: &Document) -> String {
+    let mut text = String::from("---\n");
+    let mut field = |name: &str, value: String| {
+        text.push_str(name);
+        text.push_str(": ");
+        text.push_str(&value);
+        text.push('\n');
+    };
+    if let Some(key) = &doc.key {
+        field("key", quote(key));
+    }
+    if let Some(namespace) = &doc.namespace {
+        field("namespace", quote(namespace));
+    }
+    if let Some(title) = &doc.title {
+        field("title", quote(title));
+    }
+    if let Some(visibility) = doc.visibility {
+        field("visibility", visibility.as_str().to_string());
+    }
+    field("created_at", doc.created_at.to_rfc3339());
+    field("updated_at", doc.updated_at.to_rfc3339());
+    field("version", doc.version.to_string());
+    if doc.tags.is_empty() {
+        text.push_str("tags: []\n");
+    } else {
+        text.push_str("tags:\n");
+        for tag in &doc.tags {
+            text.push_str("  - ");
+            text.push_str(&quote(tag));
+            text.push('\n');
+        }
+    }
+    text.push_str("---\n");
+    text.push_str(&doc.body_markdown);
+    text
+}
+
+/// What follows `marker` when it is the whole first line of `text`.
+fn after_line<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = text.strip_prefix(marker)?;
+    rest.strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+}
+
+/// A value with its quotes or trailing ` # comment` removed; `None` when empty.
+fn scalar(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let value = if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+        unescape(inner)
+    } else if let Some(inner) = raw.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')) {
+        inner.replace("''", "'")
+    } else {
+        match raw.find(" #") {
+            Some(comment) => raw[..comment].trim_end().to_string(),
+            None => raw.to_string(),
+        }
+    };
+    (!value.is_empty()).then_some(value)
+}
+
+/// The text of a double-quoted value: `\\`, `\"`, `\n`, `\r`, and `\t` are escapes, and any
+/// other backslash is kept as written.
+fn unescape(inner: &str) -> String {
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some(escaped @ ('\\' | '"')) => value.push(escaped),
+            Some(other) => {
+                value.push('\\');
+                value.push(other);
+            }
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+/// `value` as a double-quoted scalar `unescape` reads back unchanged.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            other => quoted.push(other),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn list(raw: &str) -> Vec<String> {
+    let raw = raw.trim();
+    let inner = raw
+        .strip_prefix('[')
+        .and_then(|r| r.strip_suffix(']'))
+        .unwrap_or(raw);
+    inner.split(',').filter_map(scalar).collect()
+}
@@ -0,0 +1,44 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::Document;
+
+/// How long a client idempotency key is remembered after its first use.
+pub const IDEMPOTENCY_WINDOW: Duration = Duration::hours(24);
+
+/// The outcome of a write made under an idempotency key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotentWrite {
+    pub document: Document,
+    /// True when the key had been seen before and the first result was returned.
+    pub replayed: bool,
+}
+
+/// An idempotency key was reused for a write with different content.
+#[derive(Debug)]
+pub struct IdempotencyConflict(pub String);
+
+impl std::fmt::Display for IdempotencyConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Idempotency key {:?} was already used for a different write.",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for IdempotencyConflict {}
+
+/// What a retry must repeat exactly for its first result to be replayed.
+pub(crate) fn fingerprint(draft: &Document) -> String {
+    serde_json::json!({
+        "key": draft.key,
+        "namespace": draft.namespace,
+        "title": draft.title,
+        "tags": draft.tags,
+        "body_markdown": draft.body_markdown,
+        "ttl_seconds": draft.ttl_seconds,
+    })
+    .to_string()
+}
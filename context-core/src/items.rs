@@ -0,0 +1,149 @@
+//! Action items and decisions marked up in document bodies (`context todos`).
+//!
+//! Bodies are scanned on every write: `TODO:` and `DECISION:` markers and Markdown
+//! checkboxes (`- [ ] ...`, `- [x] ...`) become rows of the `items` table, so open work
+//! in free-form notes can be listed without a separate tracker. Fenced code is skipped.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ProjectId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// A `TODO:` marker.
+    Todo,
+    /// A Markdown checkbox.
+    Task,
+    /// A `DECISION:` marker.
+    Decision,
+}
+
+impl ItemKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ItemKind::Todo => "todo",
+            ItemKind::Task => "task",
+            ItemKind::Decision => "decision",
+        }
+    }
+
+    pub fn from_stored(raw: &str) -> Option<Self> {
+        match raw {
+            "todo" => Some(ItemKind::Todo),
+            "task" => Some(ItemKind::Task),
+            "decision" => Some(ItemKind::Decision),
+            _ => None,
+        }
+    }
+}
+
+/// One marked line of a body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Item {
+    pub kind: ItemKind,
+    /// 1-based line number in the body.
+    pub line: u32,
+    pub text: String,
+    /// A ticked checkbox. `TODO:` items stay open until the marker is removed.
+    pub done: bool,
+}
+
+impl Item {
+    /// Still to be done: an unticked checkbox or a `TODO:`.
+    pub fn is_open(&self) -> bool {
+        self.kind != ItemKind::Decision && !self.done
+    }
+}
+
+/// An item with the live document it came from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentItem {
+    pub project: ProjectId,
+    pub document_id: String,
+    pub key: Option<String>,
+    #[serde(flatten)]
+    pub item: Item,
+}
+
+/// Which items `SqliteStorage::items` returns.
+#[derive(Debug, Clone, Default)]
+pub struct ItemQuery {
+    /// Limit to one project; `None` searches all of them.
+    pub project: Option<ProjectId>,
+    /// Only these kinds; empty means all.
+    pub kinds: Vec<ItemKind>,
+    /// Leave out ticked checkboxes and decisions.
+    pub open_only: bool,
+}
+
+/// The items marked in `markdown`, in body order.
+pub fn extract_items(markdown: &str) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut fence: Option<String> = None;
+    for (index, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(open) = &fence {
+            if trimmed.starts_with(open.as_str()) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(trimmed.chars().take(3).collect());
+            continue;
+        }
+        let line_number = index as u32 + 1;
+        if let Some((done, text)) = checkbox(trimmed) {
+            items.push(Item {
+                kind: ItemKind::Task,
+                line: line_number,
+                text: text.to_string(),
+                done,
+            });
+            continue;
+        }
+        for (marker, kind) in [("TODO:", ItemKind::Todo), ("DECISION:", ItemKind::Decision)] {
+            if let Some(at) = trimmed.find(marker) {
+                let text = trimmed[at + marker.len()..].trim();
+                if !text.is_empty() {
+                    items.push(Item {
+                        kind,
+                        line: line_number,
+                        text: text.to_string(),
+                        done: false,
+                    });
+                }
+                break;
+            }
+        }
+    }
+    items
+}
+
+/// `- [ ] text`, `* [x] text`, or `1. [X] text`: whether it is ticked, and its text.
+fn checkbox(line: &str) -> Option<(bool, &str)> {
+    let rest = match line.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            if digits == 0 {
+                return None;
+            }
+            line[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+    let rest = rest.strip_prefix(' ')?.trim_start();
+    let (done, text) = if let Some(text) = rest.strip_prefix("[ ]") {
+        (false, text)
+    } else if let Some(text) = rest
+        .strip_prefix("[x]")
+        .or_else(|| rest.strip_prefix("[X]"))
+    {
+        (true, text)
+    } else {
+        return None;
+    };
+    let text = text.trim();
+    (!text.is_empty()).then_some((done, text))
+}
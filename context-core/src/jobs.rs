@@ -0,0 +1,141 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    digest::write_digest,
+    error::{fail, Context},
+    policy::parse_duration,
+    sqlite::{backup_dir, SqliteStorage},
+    Result,
+};
+
+const DEFAULT_BACKUPS_KEPT: usize = 7;
+const SCHEDULED_BACKUP_PREFIX: &str = "scheduled-";
+
+/// A maintenance task the server can run on a schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Fold the write-ahead log into the database file.
+    WalCheckpoint,
+    /// Delete documents whose TTL has run out.
+    Gc,
+    /// Snapshot the database into `backups/`, keeping the newest `keep`.
+    Backup,
+    /// Write a digest of the last period's changes for `project`.
+    Digest,
+}
+
+/// One scheduled job, e.g. `{"kind": "backup", "every": "1d", "keep": 7}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobSpec {
+    pub kind: JobKind,
+    /// How often to run, as a span such as `30m`, `1h`, or `1d`.
+    pub every: String,
+    /// Digest only: the project to summarize (default `default`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Backup only: how many scheduled snapshots to keep.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+impl JobSpec {
+    /// The time between runs; at least a minute.
+    pub fn interval(&self) -> Result<Duration> {
+        let interval =
+            parse_duration(&self.every).with_context(|| format!("Job {:?}", self.kind.as_str()))?;
+        if interval < Duration::minutes(1) {
+            fail!(
+                Validation,
+                "Job {:?} runs every {}; the shortest interval is 1m.",
+                self.kind.as_str(),
+                self.every
+            );
+        }
+        Ok(interval)
+    }
+
+    /// Run the job once, returning a one-line summary of what it did.
+    pub async fn run(&self, storage: &SqliteStorage, now: DateTime<Utc>) -> Result<String> {
+        match self.kind {
+            JobKind::WalCheckpoint => {
+                storage.checkpoint_wal().await?;
+                Ok("checkpointed the write-ahead log".to_string())
+            }
+            JobKind::Gc => {
+                let purged = storage.purge_expired().await?;
+                Ok(format!("deleted {purged} expired document(s)"))
+            }
+            JobKind::Backup => {
+                let keep = self.keep.unwrap_or(DEFAULT_BACKUPS_KEPT);
+                backup(storage, now, keep).await
+            }
+            JobKind::Digest => {
+                let project = self.project.as_deref().unwrap_or("default");
+                let digest = write_digest(storage, project, now - self.interval()?, now).await?;
+                Ok(format!(
+                    "digested {} document(s) into {}",
+                    digest.covered,
+                    digest.document.key.as_deref().unwrap_or_default()
+                ))
+            }
+        }
+    }
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::WalCheckpoint => "wal_checkpoint",
+            JobKind::Gc => "gc",
+            JobKind::Backup => "backup",
+            JobKind::Digest => "digest",
+        }
+    }
+}
+
+/// Check every job's interval, so a bad config fails at startup rather than on schedule.
+pub fn validate_jobs(jobs: &[JobSpec]) -> Result<()> {
+    for job in jobs {
+        job.interval()?;
+        if job.keep == Some(0) {
+            fail!(Validation, "Backup job must keep at least one snapshot.");
+        }
+    }
+    Ok(())
+}
+
+async fn backup(storage: &SqliteStorage, now: DateTime<Utc>, keep: usize) -> Result<String> {
+    let Some(file) = storage.database_file().await? else {
+        return Ok("skipped: the database is in memory".to_string());
+    };
+    let dir = backup_dir(&file);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create backup directory {}", dir.display()))?;
+    let path = dir.join(format!(
+        "{SCHEDULED_BACKUP_PREFIX}{}.sqlite",
+        now.format("%Y%m%dT%H%M%SZ")
+    ));
+    storage.backup_to(&path).await?;
+
+    // Timestamped names sort oldest first; migration snapshots are never pruned.
+    let mut scheduled: Vec<_> = std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(SCHEDULED_BACKUP_PREFIX))
+        })
+        .collect();
+    scheduled.sort();
+    let excess = scheduled.len().saturating_sub(keep);
+    for old in &scheduled[..excess] {
+        std::fs::remove_file(old)
+            .with_context(|| format!("Failed to remove old backup {}", old.display()))?;
+    }
+    Ok(format!(
+        "wrote {} (removed {excess} older snapshot(s))",
+        path.display()
+    ))
+}
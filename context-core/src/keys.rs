@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{error::fail, Key, Result};
+
+/// Canonical form used when key normalization is enabled: NFC, then lowercase.
+pub fn normalize_key(key: &str) -> Key {
+    key.nfc().collect::<String>().to_lowercase()
+}
+
+/// Keys in one project that collapse to the same normalized form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyCollision {
+    pub normalized: Key,
+    pub keys: Vec<Key>,
+}
+
+/// A key that was (or would be) rewritten to its normalized form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRename {
+    pub from: Key,
+    pub to: Key,
+}
+
+/// A retired key that resolves to the key that replaced it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRedirect {
+    pub from: Key,
+    pub to: Key,
+}
+
+/// Outcome of normalizing the existing keys of a project.
+///
+/// Colliding keys are never rewritten; they are reported so a human can pick
+/// which document should keep the canonical key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyNormalizationReport {
+    pub renamed: Vec<KeyRename>,
+    pub collisions: Vec<KeyCollision>,
+}
+
+/// Shape constraints applied to keys on write.
+///
+/// Letters and digits are always allowed; anything else must be listed in
+/// `allowed_punctuation`. Whitespace, control characters, and symbols such as
+/// emoji are rejected unless explicitly allowed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyRules {
+    pub max_length: usize,
+    pub allowed_punctuation: String,
+    pub reserved_prefixes: Vec<String>,
+}
+
+impl Default for KeyRules {
+    fn default() -> Self {
+        Self {
+            max_length: 128,
+            allowed_punctuation: "-_./:@".to_string(),
+            reserved_prefixes: Vec::new(),
+        }
+    }
+}
+
+impl KeyRules {
+    pub fn validate(&self, key: &str) -> Result<()> {
+        if key.is_empty() {
+            fail!(Validation, "Key cannot be empty.");
+        }
+
+        let length = key.chars().count();
+        if length > self.max_length {
+            fail!(
+                Validation,
+                "Key is {length} characters long; the maximum is {}.",
+                self.max_length
+            );
+        }
+
+        for (position, ch) in key.chars().enumerate() {
+            if ch.is_alphanumeric() || self.allowed_punctuation.contains(ch) {
+                continue;
+            }
+            fail!(
+                Validation,
+                "Key contains {} at position {position}; keys may only contain letters, digits, and \"{}\".",
+                describe_char(ch),
+                self.allowed_punctuation
+            );
+        }
+
+        if let Some(prefix) = self
+            .reserved_prefixes
+            .iter()
+            .find(|prefix| key.starts_with(prefix.as_str()))
+        {
+            fail!(
+                Validation,
+                "Key {key:?} uses the reserved prefix {prefix:?}."
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn describe_char(ch: char) -> String {
+    match ch {
+        '\n' => "a newline".to_string(),
+        '\r' => "a carriage return".to_string(),
+        '\t' => "a tab".to_string(),
+        ' ' => "a space".to_string(),
+        c if c.is_control() => format!("control character U+{:04X}", c as u32),
+        c => format!("{c:?} (U+{:04X})", c as u32),
+    }
+}
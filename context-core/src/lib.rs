@@ -1,5 +1,10 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use futures::stream::BoxStream;
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub use error::{Conflict, ContextError, Result, StorageError};
 
 pub type ProjectId = String;
 pub type Key = String;
@@ -13,6 +18,111 @@ pub enum SourceType {
     User,
     Import,
     System,
+    /// Written by a tool integration (name it in `SourceMeta::tool`).
+    Tool,
+    /// Written through the web UI or HTTP API.
+    Web,
+    /// Written by an MCP client.
+    Mcp,
+    /// A source recorded by a newer release that this build does not recognize.
+    #[serde(other)]
+    Unknown,
+}
+
+impl SourceType {
+    /// The name stored in the `source` column; stable across releases.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceType::Agent => "agent",
+            SourceType::User => "user",
+            SourceType::Import => "import",
+            SourceType::System => "system",
+            SourceType::Tool => "tool",
+            SourceType::Web => "web",
+            SourceType::Mcp => "mcp",
+            SourceType::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a stored `source`; names written by a newer release read back as `Unknown`.
+    pub fn from_stored(raw: &str) -> Self {
+        match raw {
+            "agent" => SourceType::Agent,
+            "user" => SourceType::User,
+            "import" => SourceType::Import,
+            "system" => SourceType::System,
+            "tool" => SourceType::Tool,
+            "web" => SourceType::Web,
+            "mcp" => SourceType::Mcp,
+            _ => SourceType::Unknown,
+        }
+    }
+}
+
+/// Details about where a write came from, beyond its `SourceType`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// The agent session that made the write, if the caller knows it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// The server this local copy was read through from by `get`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_from: Option<String>,
+}
+
+/// Who a document is for. A document that never set one is `Project`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    /// Personal notes: only the local CLI shows them. `context-web`, `serve-readonly`,
+    /// and exports leave them out.
+    Private,
+    /// Anyone the project is shared with.
+    #[default]
+    Project,
+    /// Fit to publish, e.g. with `serve-readonly --public-only`.
+    Public,
+}
+
+impl Visibility {
+    /// What may leave the machine: everything but `Private`.
+    pub const SHARED: [Visibility; 2] = [Visibility::Project, Visibility::Public];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Private => "private",
+            Visibility::Project => "project",
+            Visibility::Public => "public",
+        }
+    }
+
+    /// Parse a stored `visibility`. A name this build doesn't know reads as `Private`, so
+    /// documents a newer release restricted stay restricted.
+    pub fn from_stored(raw: &str) -> Self {
+        raw.parse().unwrap_or(Visibility::Private)
+    }
+}
+
+impl std::str::FromStr for Visibility {
+    type Err = ContextError;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        match raw.trim() {
+            "private" => Ok(Visibility::Private),
+            "project" => Ok(Visibility::Project),
+            "public" => Ok(Visibility::Public),
+            other => {
+                error::fail!(
+                    Validation,
+                    "Unknown visibility {other:?}. Use private, project, or public."
+                )
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,31 +137,402 @@ pub struct Document {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub source: SourceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_meta: Option<SourceMeta>,
     pub version: u64,
     pub ttl_seconds: Option<i64>,
     pub deleted_at: Option<DateTime<Utc>>,
+    /// Unset reads as `Visibility::Project`; a later revision that leaves it unset keeps
+    /// the previous one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Visibility>,
+    /// Structured fields beyond tags (`ticket`, `severity`, `url`). A later revision's
+    /// entries are merged over the previous ones; a `null` value removes that entry.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub metadata: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug)]
+impl Document {
+    pub fn visibility(&self) -> Visibility {
+        self.visibility.unwrap_or_default()
+    }
+
+    /// Whether the document may leave this machine; see `Visibility::SHARED`.
+    pub fn is_shared(&self) -> bool {
+        self.visibility() != Visibility::Private
+    }
+
+    /// When the TTL runs out; it counts from `created_at`, which later revisions keep.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.ttl_seconds
+            .and_then(chrono::Duration::try_seconds)
+            .and_then(|ttl| self.created_at.checked_add_signed(ttl))
+    }
+
+    /// The `ttl_seconds` that makes this document expire at `at` (zero if `at` has passed).
+    pub fn ttl_until(&self, at: DateTime<Utc>) -> i64 {
+        (at - self.created_at).num_seconds().max(0)
+    }
+}
+
+/// Full-text search request.
+///
+/// `text` is matched term by term; `"a phrase"` matches literally and a leading `-`
+/// (`-word`, `-"a phrase"`) excludes documents containing it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SearchQuery {
     pub project: Option<ProjectId>,
     pub text: String,
     pub limit: Option<usize>,
+    /// Drop documents carrying any of these tags.
+    pub exclude_tags: Vec<String>,
+    /// Drop documents in any of these namespaces.
+    pub exclude_namespaces: Vec<String>,
+    /// Only search documents whose key starts with this prefix.
+    pub key_prefix: Option<Key>,
+    /// Only search these keys (empty means no restriction).
+    pub keys: Vec<Key>,
+    /// Match terms against identifiers in fenced code blocks only (see `code`).
+    pub code: bool,
+    /// Only documents with one of these visibilities; empty means all.
+    #[serde(skip)]
+    pub visibility: Vec<Visibility>,
+}
+
+/// Filters and paging for listing a project's documents, newest update first.
+///
+/// `context ls` and `GET /api/projects/:project/docs` both parse into this, so the two
+/// surfaces offer the same filters. In a query string, list fields are comma-separated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListQuery {
+    /// Only documents carrying all of these tags.
+    #[serde(deserialize_with = "comma_separated")]
+    pub tags: Vec<String>,
+    /// Drop documents carrying any of these tags.
+    #[serde(deserialize_with = "comma_separated")]
+    pub not_tags: Vec<String>,
+    pub namespace: Option<String>,
+    #[serde(deserialize_with = "comma_separated")]
+    pub exclude_namespaces: Vec<String>,
+    pub key_prefix: Option<Key>,
+    /// Updated at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Updated before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// Also list tombstoned and expired documents.
+    pub include_deleted: bool,
+    /// List only tombstoned documents: the ones `restore` can bring back.
+    pub only_deleted: bool,
+    /// Only documents whose TTL runs out before this time.
+    pub expiring_before: Option<DateTime<Utc>>,
+    /// Only documents with one of these visibilities; empty means all. Servers set it, so
+    /// a query string can't widen it.
+    #[serde(skip)]
+    pub visibility: Vec<Visibility>,
+    /// Only documents whose metadata has all of these entries, values compared as text
+    /// (`meta=ticket=ABC-123,severity=high` in a query string).
+    #[serde(rename = "meta", deserialize_with = "metadata_pairs")]
+    pub metadata: BTreeMap<String, String>,
+    pub sort: ListSort,
+    pub limit: Option<usize>,
+    /// `next_cursor` of the previous page, listed in the same `sort`.
+    pub cursor: Option<String>,
+}
+
+/// The order of a listing. Ties break by document id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListSort {
+    /// Most recently updated first.
+    #[default]
+    Updated,
+    /// Most recently created first.
+    Created,
+    /// By key, A to Z; documents without a key come first.
+    Key,
 }
 
-#[derive(Debug)]
+impl std::str::FromStr for ListSort {
+    type Err = ContextError;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        match raw.trim() {
+            "updated" => Ok(ListSort::Updated),
+            "created" => Ok(ListSort::Created),
+            "key" => Ok(ListSort::Key),
+            other => {
+                error::fail!(
+                    Validation,
+                    "Unknown sort {other:?}. Use updated, created, or key."
+                )
+            }
+        }
+    }
+}
+
+impl ListQuery {
+    /// The `(sort value, id)` position a cursor resumes after: the `updated_at`,
+    /// `created_at`, or key of the last document of the previous page.
+    pub fn cursor_position(&self) -> Result<Option<(String, String)>> {
+        let Some(cursor) = &self.cursor else {
+            return Ok(None);
+        };
+        let invalid = || ContextError::Validation(format!("Invalid cursor {cursor:?}."));
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| {
+                cursor
+                    .get(i..i + 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+        // Keys may hold `|`; ids never do.
+        let (position, id) = text.rsplit_once('|').ok_or_else(invalid)?;
+        Ok(Some((position.to_string(), id.to_string())))
+    }
+
+    /// An opaque, URL-safe cursor for the page after `last`.
+    pub fn cursor_after(&self, last: &Document) -> String {
+        let position = match self.sort {
+            ListSort::Updated => last.updated_at.to_rfc3339(),
+            ListSort::Created => last.created_at.to_rfc3339(),
+            ListSort::Key => last.key.clone().unwrap_or_default(),
+        };
+        format!("{position}|{}", last.id.0)
+            .bytes()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+/// Accept `a,b` (query strings) as well as `["a", "b"]`.
+fn comma_separated<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    let values = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(joined) => joined.split(',').map(str::to_string).collect(),
+        OneOrMany::Many(values) => values,
+    };
+    Ok(values
+        .into_iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect())
+}
+
+fn metadata_pairs<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<BTreeMap<String, String>, D::Error> {
+    comma_separated(deserializer)?
+        .iter()
+        .map(|pair| parse_metadata_pair(pair).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Split `name=value` as given to `--meta`.
+pub fn parse_metadata_pair(raw: &str) -> Result<(String, String)> {
+    match raw.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => error::fail!(Validation, "Metadata takes name=value, got {raw:?}"),
+    }
+}
+
+/// One page of a listing; `next_cursor` is set when more documents follow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPage {
+    pub documents: Vec<Document>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchHit {
     pub document: Document,
     pub score: f32,
+    /// The parts `score` is the sum of.
+    pub breakdown: ScoreBreakdown,
+}
+
+/// Components of a search score; `bm25 + recency + tag_bonus + pin_boost` is the hit's
+/// score.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    /// Negated FTS5 bm25 rank: higher means a closer text match.
+    pub bm25: f32,
+    /// `1 / (1 + hours since the last update)`.
+    pub recency: f32,
+    /// 0.5 for each tag that equals a query term.
+    pub tag_bonus: f32,
+    /// What pinning a document adds. Documents can't be pinned yet, so it is always 0;
+    /// it is reported so the parts keep adding up to the score once they can.
+    #[serde(default)]
+    pub pin_boost: f32,
+}
+
+/// A namespace and how many live documents it holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceInfo {
+    pub name: String,
+    pub documents: u64,
 }
 
-pub type Result<T> = anyhow::Result<T>;
+/// A tag and how many live documents carry it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagInfo {
+    pub name: String,
+    pub documents: u64,
+}
+
+/// A registered project with its optional display metadata and free-form settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub id: ProjectId,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A project with totals over its live documents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectStats {
+    #[serde(flatten)]
+    pub project: ProjectInfo,
+    pub documents: u64,
+    /// Combined size of the live document bodies, in bytes.
+    pub bytes: u64,
+    /// Most recent `updated_at` among live documents.
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+/// Rows left behind by crashes or manual edits that no longer belong to any document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    /// Full-text index rows whose document is gone (or whose rowid now belongs to another).
+    pub orphaned_fts_rows: u64,
+    /// History rows for document ids that no longer exist.
+    pub orphaned_versions: u64,
+    /// Whether the orphans were deleted, rather than only counted.
+    pub repaired: bool,
+}
+
+/// A conditional write found the document at a different version than the caller read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConflict {
+    pub expected: u64,
+    /// The live version now, or `None` when there is no live document.
+    pub actual: Option<u64>,
+}
+
+impl std::fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.actual {
+            Some(actual) => write!(
+                f,
+                "Document changed: expected version {}, found version {actual}.",
+                self.expected
+            ),
+            None => write!(
+                f,
+                "Document changed: expected version {}, but it no longer exists.",
+                self.expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VersionConflict {}
+
+/// How much of a document `Storage::delete_by_id` and `delete_by_key` remove.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeleteMode {
+    /// Tombstone the document; `restore` brings it back and its history stays.
+    #[default]
+    Soft,
+    /// Tombstone the document and erase its earlier versions. It can still be restored,
+    /// but only as it was when deleted.
+    Purge,
+    /// Remove the document and its history outright, as `gc` does to old tombstones.
+    /// A document already soft-deleted has its tombstone removed the same way.
+    Hard,
+}
 
+/// A document store.
+///
+/// Every method is cancel-safe: dropping its future abandons the operation, and a write
+/// dropped before it commits is rolled back. SQLite still finishes a statement it has
+/// started, so a caller that gives up on a slow query frees itself, not the connection;
+/// `SqliteStorage::with_query_timeout` makes SQLite stop the statement too.
 #[async_trait::async_trait]
 pub trait Storage: Send + Sync {
     async fn put(&self, doc: Document) -> Result<Document>;
     async fn get_by_key(&self, project: &ProjectId, key: &str) -> Result<Option<Document>>;
+    async fn get_by_id(&self, id: &DocumentId) -> Result<Option<Document>>;
+    /// Every recorded revision of a document, oldest first (including tombstoned documents).
+    async fn history(&self, id: &DocumentId) -> Result<Vec<Document>>;
+    /// The document as it existed at `at`: the latest revision written at or before that time.
+    async fn version_as_of(&self, id: &DocumentId, at: DateTime<Utc>) -> Result<Option<Document>>;
     async fn search(&self, query: SearchQuery) -> Result<Vec<SearchHit>>;
+    /// One page of a project's documents matching `query`, with a cursor for the next
+    /// when `query.limit` cut it short.
+    async fn list_page(&self, project: &ProjectId, query: &ListQuery) -> Result<ListPage>;
+    /// How many documents match `query`, ignoring its `limit` and `cursor`.
+    async fn count(&self, project: &ProjectId, query: &ListQuery) -> Result<u64>;
+    /// Delete the live document with this id as far as `mode` says, returning it as
+    /// deleted; `None` when there is none to delete. A hard delete also takes a
+    /// tombstone.
+    async fn delete_by_id(&self, id: &DocumentId, mode: DeleteMode) -> Result<Option<Document>>;
+    /// `delete_by_id` for the live document at `key`. Keys left behind by a rename
+    /// are not followed.
+    async fn delete_by_key(
+        &self,
+        project: &ProjectId,
+        key: &str,
+        mode: DeleteMode,
+    ) -> Result<Option<Document>>;
+    /// Live documents of a project, most recently updated first, yielded as rows are read.
+    fn stream_project<'a>(&'a self, project: &'a ProjectId) -> BoxStream<'a, Result<Document>>;
 }
 
+pub mod attachments;
+pub mod blame;
+pub mod calendar;
+pub mod changes;
+pub mod code;
+pub mod config;
+pub mod dedup;
+pub mod diff;
+pub mod digest;
+pub mod error;
+pub mod fixtures;
+pub mod frontmatter;
+pub mod gc;
+pub mod idempotency;
+pub mod items;
+pub mod jobs;
+pub mod keys;
+pub mod links;
+pub mod merge;
+pub mod pack;
+pub mod policy;
+pub mod related;
+pub mod reload;
+pub mod reminders;
+pub mod socket;
 pub mod sqlite;
+pub mod templates;
+pub mod tokens;
+pub mod traced;
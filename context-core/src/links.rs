@@ -0,0 +1,86 @@
+//! Links between documents (`context links`, `context backlinks`).
+//!
+//! Bodies are scanned on every write: `[[key]]` (or `[[key|label]]`) names another
+//! document in the same project, and `context://<project>/<key>` names one in any
+//! project. The targets become rows of the `links` table. They are stored by key, so a
+//! link to a document that doesn't exist yet starts resolving once one is written under
+//! that key. Fenced code is skipped.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Key, ProjectId};
+
+const URI_SCHEME: &str = "context://";
+
+/// A document a body links to, by project and key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LinkTarget {
+    pub project: ProjectId,
+    pub key: Key,
+}
+
+/// One end of a link, as `SqliteStorage::links` and `SqliteStorage::backlinks` return it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkedDocument {
+    pub project: ProjectId,
+    pub key: Option<Key>,
+    /// The live document at the other end; `None` when nothing lives at the linked key.
+    pub document_id: Option<String>,
+    pub title: Option<String>,
+}
+
+/// The documents `markdown` links to, each once, in order of first mention. Wiki-links
+/// resolve in `project`.
+pub fn extract_links(markdown: &str, project: &str) -> Vec<LinkTarget> {
+    let mut targets: Vec<LinkTarget> = Vec::new();
+    let mut fence: Option<String> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if let Some(open) = &fence {
+            if trimmed.starts_with(open.as_str()) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(trimmed.chars().take(3).collect());
+            continue;
+        }
+        let found = wiki_links(line, project).chain(uri_links(line));
+        for target in found {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    }
+    targets
+}
+
+/// `[[key]]` and `[[key|label]]` on one line.
+fn wiki_links<'a>(line: &'a str, project: &'a str) -> impl Iterator<Item = LinkTarget> + 'a {
+    line.split("[[").skip(1).filter_map(move |rest| {
+        let (inner, _) = rest.split_once("]]")?;
+        let key = inner.split('|').next().unwrap_or_default().trim();
+        let valid = !key.is_empty() && !key.contains(['[', ']']);
+        valid.then(|| LinkTarget {
+            project: project.to_string(),
+            key: key.to_string(),
+        })
+    })
+}
+
+/// `context://<project>/<key>` on one line. The URI ends at whitespace or at a character
+/// that closes Markdown syntax around it; trailing sentence punctuation is dropped.
+fn uri_links(line: &str) -> impl Iterator<Item = LinkTarget> + '_ {
+    line.split(URI_SCHEME).skip(1).filter_map(|rest| {
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "()[]<>\"'`?#,;".contains(c))
+            .unwrap_or(rest.len());
+        let uri = rest[..end].trim_end_matches(['.', ':', '!']);
+        let (project, key) = uri.split_once('/')?;
+        (!project.is_empty() && !key.is_empty()).then(|| LinkTarget {
+            project: project.to_string(),
+            key: key.to_string(),
+        })
+    })
+}
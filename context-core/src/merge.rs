@@ -0,0 +1,48 @@
+//! Merging documents exported from another store (`context export --format jsonl`).
+//!
+//! Documents keep their ids, so the same document exported twice, or from two machines,
+//! lines up with itself. The higher version wins. A tie with different content, or a key
+//! already held by another document, is a conflict: it is reported and nothing is written
+//! for it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Document, Key};
+
+/// What a merge did with one incoming document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeOutcome {
+    /// No document had its id.
+    Created,
+    /// The incoming version was higher and replaced the local one.
+    Updated,
+    /// The local document is already at this version with this content.
+    Unchanged,
+    /// The local document is at a higher version and was kept.
+    Older,
+    Conflict,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergedDocument {
+    pub id: String,
+    pub key: Option<Key>,
+    /// The incoming document's version.
+    pub version: u64,
+    pub outcome: MergeOutcome,
+    /// Why a conflict was left alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<String>,
+}
+
+/// Whether two copies of the same version of a document say the same thing.
+pub fn same_revision(a: &Document, b: &Document) -> bool {
+    a.key == b.key
+        && a.namespace == b.namespace
+        && a.title == b.title
+        && a.tags == b.tags
+        && a.metadata == b.metadata
+        && a.body_markdown == b.body_markdown
+        && a.deleted_at.is_some() == b.deleted_at.is_some()
+}
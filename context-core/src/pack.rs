@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{tokens::count_tokens, Document};
+
+/// One document placed into (or left out of) a pack.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackEntry {
+    pub id: String,
+    pub key: Option<String>,
+    pub version: u64,
+    pub tokens: usize,
+}
+
+/// Markdown assembled from the most relevant documents that fit a token budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pack {
+    pub markdown: String,
+    pub tokens: usize,
+    pub budget: usize,
+    pub included: Vec<PackEntry>,
+    /// Documents that would have exceeded the budget.
+    pub skipped: Vec<PackEntry>,
+}
+
+/// Concatenate `documents` (most relevant first) under per-document headers until `budget` is spent.
+///
+/// Repeated documents and byte-identical bodies are included once. A document that
+/// does not fit is skipped rather than truncated, and smaller ones after it may still
+/// be packed.
+pub fn pack(documents: Vec<Document>, budget: usize) -> Pack {
+    let mut seen_ids = HashSet::new();
+    let mut seen_bodies = HashSet::new();
+    let mut markdown = String::new();
+    let mut tokens = 0;
+    let mut included = Vec::new();
+    let mut skipped = Vec::new();
+
+    for doc in documents {
+        if !seen_ids.insert(doc.id.0.clone()) || !seen_bodies.insert(doc.body_markdown.clone()) {
+            continue;
+        }
+
+        let section = section(&doc);
+        let cost = count_tokens(&section);
+        let entry = PackEntry {
+            id: doc.id.0,
+            key: doc.key,
+            version: doc.version,
+            tokens: cost,
+        };
+        if tokens + cost > budget {
+            skipped.push(entry);
+            continue;
+        }
+        markdown.push_str(&section);
+        tokens += cost;
+        included.push(entry);
+    }
+
+    Pack {
+        markdown,
+        tokens,
+        budget,
+        included,
+        skipped,
+    }
+}
+
+fn section(doc: &Document) -> String {
+    let name = doc.key.as_deref().unwrap_or(&doc.id.0);
+    let mut section = format!("## {name} (v{})\n\n", doc.version);
+    section.push_str(doc.body_markdown.trim_end());
+    section.push_str("\n\n");
+    section
+}
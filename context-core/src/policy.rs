@@ -0,0 +1,74 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{fail, Context},
+    ContextError, Result,
+};
+
+/// Parse a span such as `30m`, `24h`, `14d`, or `2w`.
+pub fn parse_duration(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let split = raw.len().saturating_sub(1);
+    let amount = raw
+        .get(..split)
+        .and_then(|digits| digits.parse::<i64>().ok())
+        .filter(|amount| *amount >= 0);
+    let duration = match (amount, raw.get(split..)) {
+        (Some(amount), Some("m")) => Duration::try_minutes(amount),
+        (Some(amount), Some("h")) => Duration::try_hours(amount),
+        (Some(amount), Some("d")) => Duration::try_days(amount),
+        (Some(amount), Some("w")) => Duration::try_weeks(amount),
+        _ => None,
+    };
+    duration.ok_or_else(|| {
+        ContextError::Validation(format!(
+            "Invalid duration {raw:?}. Use a number followed by m, h, d, or w (e.g. 14d)."
+        ))
+    })
+}
+
+/// "Documents tagged `tag` expire `after` their creation", e.g. `{"tag": "scratch", "after": "14d"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiryRule {
+    pub tag: String,
+    pub after: String,
+}
+
+/// Tag-based expiry rules, applied to documents written without an explicit TTL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExpiryPolicy {
+    pub rules: Vec<ExpiryRule>,
+}
+
+impl ExpiryPolicy {
+    pub fn new(rules: Vec<ExpiryRule>) -> Result<Self> {
+        for rule in &rules {
+            if rule.tag.trim().is_empty() {
+                fail!(Validation, "Expiry rule has an empty tag.");
+            }
+            parse_duration(&rule.after)
+                .with_context(|| format!("Expiry rule for tag {:?}", rule.tag))?;
+        }
+        Ok(Self { rules })
+    }
+
+    /// TTL in seconds for a document with `tags`: the shortest of the matching rules.
+    pub fn ttl_for(&self, tags: &[String]) -> Option<i64> {
+        self.rules
+            .iter()
+            .filter(|rule| tags.iter().any(|tag| tag == &rule.tag))
+            .filter_map(|rule| parse_duration(&rule.after).ok())
+            .map(|duration| duration.num_seconds())
+            .min()
+    }
+}
+
+/// A document whose TTL was (or would be) set by `apply_expiry_policy`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiryChange {
+    pub id: String,
+    pub key: Option<String>,
+    pub ttl_seconds: i64,
+}
@@ -0,0 +1,99 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Document;
+
+const TAG_WEIGHT: f32 = 0.4;
+const LINK_WEIGHT: f32 = 0.35;
+const TEXT_WEIGHT: f32 = 0.25;
+
+/// A document suggested as adjacent to another, with the signals behind its score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedDocument {
+    pub document: Document,
+    pub score: f32,
+    /// Jaccard overlap of the two tag sets.
+    pub tag_overlap: f32,
+    /// 1.0 when either document references the other (by key, or a `summary-of:` tag).
+    pub link: f32,
+    /// Jaccard overlap of the two bodies' word sets.
+    pub text_similarity: f32,
+}
+
+/// Rank `candidates` by how closely they relate to `target`, best first.
+///
+/// The target itself and candidates with no signal at all are dropped.
+pub fn rank_related(
+    target: &Document,
+    candidates: Vec<Document>,
+    limit: usize,
+) -> Vec<RelatedDocument> {
+    let target_tags = tag_set(target);
+    let target_words = word_set(&target.body_markdown);
+
+    let mut ranked: Vec<RelatedDocument> = candidates
+        .into_iter()
+        .filter(|doc| doc.id.0 != target.id.0)
+        .filter_map(|document| {
+            let tag_overlap = jaccard(&target_tags, &tag_set(&document));
+            let link = if links(target, &document) || links(&document, target) {
+                1.0
+            } else {
+                0.0
+            };
+            let text_similarity = jaccard(&target_words, &word_set(&document.body_markdown));
+            let score =
+                TAG_WEIGHT * tag_overlap + LINK_WEIGHT * link + TEXT_WEIGHT * text_similarity;
+            (score > 0.0).then_some(RelatedDocument {
+                document,
+                score,
+                tag_overlap,
+                link,
+                text_similarity,
+            })
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked.truncate(limit);
+    ranked
+}
+
+/// Whether `from` points at `to`: its body mentions `to`'s key, or it is tagged as derived from `to`.
+fn links(from: &Document, to: &Document) -> bool {
+    let by_key = to
+        .key
+        .as_deref()
+        .is_some_and(|key| from.body_markdown.contains(key));
+    let by_tag = from
+        .tags
+        .iter()
+        .any(|tag| tag.strip_prefix("summary-of:") == Some(to.id.0.as_str()));
+    by_key || by_tag
+}
+
+fn tag_set(doc: &Document) -> HashSet<String> {
+    doc.tags
+        .iter()
+        // Derivation tags are link signals, not topical overlap.
+        .filter(|tag| !tag.starts_with("summary-of:"))
+        .map(|tag| tag.to_lowercase())
+        .collect()
+}
+
+fn word_set(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(b).count();
+    let total = a.union(b).count();
+    shared as f32 / total as f32
+}
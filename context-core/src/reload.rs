@@ -0,0 +1,82 @@
+//! Telling the long-running servers (`context daemon`, `context-web`) to re-read
+//! `config.json` without restarting and dropping their connections.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::Result;
+
+/// How often `ConfigWatcher` checks the file for changes by default.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Why the config should be re-read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The process received SIGHUP.
+    Hangup,
+    /// The file's modification time moved (or it appeared or vanished).
+    Modified,
+}
+
+impl Trigger {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Trigger::Hangup => "sighup",
+            Trigger::Modified => "modified",
+        }
+    }
+}
+
+/// Resolves each time a config file should be re-read: on SIGHUP, or when polling sees
+/// its modification time change.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    poll: Duration,
+    seen: Option<SystemTime>,
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl ConfigWatcher {
+    /// Start watching `path`; must be called inside a tokio runtime.
+    pub fn new(path: impl Into<PathBuf>, poll: Duration) -> Result<Self> {
+        let path = path.into();
+        let seen = modified(&path);
+        Ok(Self {
+            path,
+            poll,
+            seen,
+            #[cfg(unix)]
+            hangup: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    pub async fn changed(&mut self) -> Trigger {
+        loop {
+            #[cfg(unix)]
+            tokio::select! {
+                _ = self.hangup.recv() => {
+                    self.seen = modified(&self.path);
+                    return Trigger::Hangup;
+                }
+                _ = tokio::time::sleep(self.poll) => {}
+            }
+            #[cfg(not(unix))]
+            tokio::time::sleep(self.poll).await;
+
+            let now = modified(&self.path);
+            if now != self.seen {
+                self.seen = now;
+                return Trigger::Modified;
+            }
+        }
+    }
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
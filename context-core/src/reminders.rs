@@ -0,0 +1,41 @@
+//! Follow-up reminders attached to documents (`context remind`, `context reminders`).
+//!
+//! A reminder is due once its time has passed and stays due until it is marked done.
+//! `context daemon --notify` shows each due reminder as a desktop notification once.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::ProjectId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reminder {
+    pub id: i64,
+    pub project: ProjectId,
+    pub document_id: String,
+    pub key: Option<String>,
+    pub remind_at: DateTime<Utc>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub notified_at: Option<DateTime<Utc>>,
+    pub done_at: Option<DateTime<Utc>>,
+}
+
+impl Reminder {
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.done_at.is_none() && self.remind_at <= now
+    }
+}
+
+/// Which reminders `SqliteStorage::reminders` returns, soonest first.
+#[derive(Debug, Clone, Default)]
+pub struct ReminderQuery {
+    /// Limit to one project; `None` covers all of them.
+    pub project: Option<ProjectId>,
+    /// Only reminders due at or before this time.
+    pub due_by: Option<DateTime<Utc>>,
+    /// Keep reminders already marked done.
+    pub include_done: bool,
+    /// Only reminders no desktop notification has been shown for.
+    pub unnotified: bool,
+}
@@ -0,0 +1,92 @@
+//! Unix domain sockets for the local servers (`context daemon`, `context-web`).
+//!
+//! A socket is reachable by whoever may open the file, so access is governed by its
+//! permission bits rather than by a token on an open TCP port.
+
+#[cfg(unix)]
+use std::path::Path;
+
+use crate::{
+    error::{fail, Context},
+    Result,
+};
+
+/// Permissions given to a socket unless configured otherwise: owner only.
+pub const DEFAULT_MODE: u32 = 0o600;
+
+/// How long a server waits after a failed accept, so a persistent failure doesn't spin.
+pub const ACCEPT_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Parse permission bits written in octal, as for `chmod` (`600`, `0660`).
+pub fn parse_mode(raw: &str) -> Result<u32> {
+    let Ok(mode) = u32::from_str_radix(raw.trim(), 8) else {
+        fail!(
+            Validation,
+            "Socket mode {raw:?} is not an octal number like 600."
+        );
+    };
+    if mode > 0o777 {
+        fail!(Validation, "Socket mode {raw:?} has bits beyond 777.");
+    }
+    if mode & 0o600 != 0o600 {
+        fail!(
+            Validation,
+            "Socket mode {raw:?} must let the owner read and write."
+        );
+    }
+    Ok(mode)
+}
+
+/// Make `path` free to bind: fail if a server answers there, remove a stale socket left
+/// behind by one that died.
+#[cfg(unix)]
+pub fn prepare(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AddrInUse,
+            format!("A server is already listening on {}.", path.display()),
+        )
+        .into());
+    }
+    std::fs::remove_file(path)
+        .with_context(|| format!("Failed to remove stale socket {}", path.display()))
+}
+
+/// Listen on `path` with permissions `mode`, freeing it first as `prepare` does.
+///
+/// The socket is bound and restricted inside a private (0700) directory next to `path`,
+/// then renamed into place, so it is never reachable with the looser bits of the umask.
+/// The listener is non-blocking, ready for `tokio::net::UnixListener::from_std`.
+#[cfg(unix)]
+pub fn bind(path: &Path, mode: u32) -> Result<std::os::unix::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    prepare(path)?;
+    let name = path.file_name().map(|name| name.to_string_lossy());
+    let private = path.with_file_name(format!(
+        ".{}.{}",
+        name.as_deref().unwrap_or("socket"),
+        std::process::id()
+    ));
+    std::fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private)
+        .with_context(|| format!("Failed to create {}", private.display()))?;
+    let staged = private.join("socket");
+    let bound = (|| -> Result<_> {
+        let listener = std::os::unix::net::UnixListener::bind(&staged)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+        listener.set_nonblocking(true)?;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+        std::fs::rename(&staged, path)
+            .with_context(|| format!("Failed to move the socket to {}", path.display()))?;
+        Ok(listener)
+    })();
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&private);
+    bound
+}
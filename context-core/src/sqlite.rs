@@ -1,34 +1,2037 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use sqlx::{
+    migrate::Migrator,
+    pool::PoolConnection,
+    sqlite::{
+        SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePoolOptions, SqliteRow,
+    },
+    Row, Sqlite, SqlitePool,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    attachments::{self, Attachment, MAX_ATTACHMENT_BYTES},
+    changes::{ChangeFeed, DocumentChange},
+    code::{code_terms, split_identifier},
+    error::{fail, Conflict, Context, ContextError},
+    idempotency::{fingerprint, IdempotencyConflict, IdempotentWrite, IDEMPOTENCY_WINDOW},
+    items::{extract_items, DocumentItem, Item, ItemKind, ItemQuery},
+    keys::{normalize_key, KeyCollision, KeyNormalizationReport, KeyRedirect, KeyRename, KeyRules},
+    links::{extract_links, LinkedDocument},
+    merge::{same_revision, MergeOutcome, MergedDocument},
+    policy::{ExpiryChange, ExpiryPolicy},
+    reminders::{Reminder, ReminderQuery},
+    templates::{self, Template},
+    ConsistencyReport, DeleteMode, Document, DocumentId, Key, ListPage, ListQuery, ListSort,
+    NamespaceInfo, ProjectId, ProjectInfo, ProjectStats, Result, ScoreBreakdown, SearchHit,
+    SearchQuery, SourceType, Storage, TagInfo, VersionConflict, Visibility,
+};
+
+/// The `WHERE` clause of `list` and `count`: every `ListQuery` filter except paging.
+/// Bind its parameters with `bind_list_filter`.
+macro_rules! list_filter_sql {
+    () => {
+        "WHERE project_id = ? \
+           AND (? OR (deleted_at IS NULL \
+                AND (ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds))) \
+           AND (NOT ? OR deleted_at IS NOT NULL) \
+           AND NOT EXISTS (SELECT 1 FROM json_each(?) wanted \
+                WHERE wanted.value NOT IN (SELECT value FROM json_each(documents.tags))) \
+           AND NOT EXISTS (SELECT 1 FROM json_each(documents.tags) t WHERE t.value IN (SELECT value FROM json_each(?))) \
+           AND (? IS NULL OR namespace = ?) \
+           AND (namespace IS NULL OR namespace NOT IN (SELECT value FROM json_each(?))) \
+           AND (? IS NULL OR substr(key, 1, length(?)) = ?) \
+           AND (? IS NULL OR julianday(updated_at) >= julianday(?)) \
+           AND (? IS NULL OR julianday(updated_at) < julianday(?)) \
+           AND (? IS NULL OR strftime('%s', created_at) + ttl_seconds < CAST(strftime('%s', ?) AS INTEGER)) \
+           AND (? = '[]' OR COALESCE(visibility, 'project') IN (SELECT value FROM json_each(?))) \
+           AND NOT EXISTS (SELECT 1 FROM json_each(?) wanted \
+                WHERE CAST(json_extract(COALESCE(documents.metadata, '{}'), '$.\"' || wanted.key || '\"') AS TEXT) IS NOT wanted.value)"
+    };
+}
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Run database migrations for the SQLite backend.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
+    MIGRATOR.run(pool).await?;
+    Ok(())
+}
+
+/// Run pending migrations, first snapshotting an already-initialized file database to
+/// `backups/pre-migrate-<version>.sqlite` next to it (`<version>` being the schema the
+/// snapshot holds), and record the run in `migration_runs`.
+async fn migrate_with_backup(pool: &SqlitePool) -> Result<()> {
+    let applied = applied_migrations(pool).await?;
+    let from_version = applied.iter().copied().max().unwrap_or(0);
+    let to_version = MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0);
+    if from_version > to_version {
+        return open_newer_schema(pool, from_version, to_version).await;
+    }
+    let pending = MIGRATOR
+        .iter()
+        .any(|migration| !applied.contains(&migration.version));
+    if !pending {
+        return run_migrations(pool).await;
+    }
+
+    let backup = if applied.is_empty() {
+        None
+    } else {
+        snapshot_before_migrating(pool, from_version).await?
+    };
+    run_migrations(pool).await?;
+    sqlx::query(
+        "INSERT INTO migration_runs (from_version, to_version, backup_path) VALUES (?, ?, ?)",
+    )
+    .bind(from_version)
+    .bind(to_version)
+    .bind(backup.map(|path| path.display().to_string()))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Derive the code terms, items, links, and normalized keys of documents written before
+/// those indexes existed, or by a release that does not maintain them (`code_terms` or
+/// `normalized_key` is NULL for all).
+async fn backfill_derived(pool: &SqlitePool) -> Result<()> {
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT id, project_id, body_markdown FROM documents WHERE code_terms IS NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+    let keys: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, key FROM documents WHERE key IS NOT NULL AND normalized_key IS NULL",
+    )
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() && keys.is_empty() {
+        return Ok(());
+    }
+    let mut tx = pool.begin().await?;
+    for (id, project, body) in rows {
+        sqlx::query("UPDATE documents SET code_terms = ? WHERE id = ?")
+            .bind(code_terms(&body))
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        write_items(&mut tx, &id, &body).await?;
+        write_links(&mut tx, &id, &project, &body).await?;
+    }
+    for (id, key) in keys {
+        sqlx::query("UPDATE documents SET normalized_key = ? WHERE id = ?")
+            .bind(normalize_key(&key))
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace the `links` rows of document `id` in `project` with the targets `body` names.
+async fn write_links(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    id: &str,
+    project: &str,
+    body: &str,
+) -> Result<()> {
+    sqlx::query("DELETE FROM links WHERE source_id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    for target in extract_links(body, project) {
+        sqlx::query("INSERT INTO links (source_id, target_project, target_key) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(&target.project)
+            .bind(&target.key)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Replace the `items` rows of document `id` with those marked in `body`.
+async fn write_items(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    id: &str,
+    body: &str,
+) -> Result<()> {
+    sqlx::query("DELETE FROM items WHERE document_id = ?")
+        .bind(id)
+        .execute(&mut **tx)
+        .await?;
+    for item in extract_items(body) {
+        sqlx::query(
+            "INSERT INTO items (document_id, line, kind, text, done) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(item.line as i64)
+        .bind(item.kind.as_str())
+        .bind(&item.text)
+        .bind(item.done)
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Open a database a newer release has migrated, as long as it says this build can still
+/// read it (`schema_compat.min_reader_version`); migrations this build has never heard of
+/// are then left alone instead of failing the open.
+async fn open_newer_schema(pool: &SqlitePool, version: i64, supported: i64) -> Result<()> {
+    let min_reader: Option<i64> =
+        sqlx::query_scalar("SELECT min_reader_version FROM schema_compat WHERE id = 1")
+            .fetch_optional(pool)
+            .await
+            .context("Failed to read schema compatibility")?;
+    let min_reader = min_reader.unwrap_or(version);
+    if min_reader > supported {
+        return Err(ContextError::storage(format!(
+            "This database uses schema version {version}, which needs a newer context \
+             (this build supports up to {supported}). Upgrade context to open it."
+        )));
+    }
+    let mut migrator = sqlx::migrate!("./migrations");
+    migrator.set_ignore_missing(true);
+    migrator.run(pool).await?;
+    Ok(())
+}
+
+async fn applied_migrations(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let tracked: Option<i64> = sqlx::query_scalar(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if tracked.is_none() {
+        return Ok(Vec::new());
+    }
+    Ok(
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// Copy the database aside with `VACUUM INTO`, which is consistent even with a live WAL.
+///
+/// In-memory databases have nothing to protect and are skipped. A snapshot left by an
+/// earlier attempt at the same upgrade is kept: it is the copy from before any of them.
+async fn snapshot_before_migrating(pool: &SqlitePool, version: i64) -> Result<Option<PathBuf>> {
+    let Some(file) = database_file(pool).await? else {
+        return Ok(None);
+    };
+    let dir = backup_dir(&file);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create backup directory {}", dir.display()))?;
+    let backup = dir.join(format!("pre-migrate-{version}.sqlite"));
+    if !backup.exists() {
+        sqlx::query("VACUUM INTO ?")
+            .bind(backup.display().to_string())
+            .execute(pool)
+            .await
+            .with_context(|| format!("Failed to back up the database to {}", backup.display()))?;
+    }
+    Ok(Some(backup))
+}
+
+/// The file behind the main database, or `None` for an in-memory one.
+async fn database_file(pool: &SqlitePool) -> Result<Option<PathBuf>> {
+    let file: Option<String> =
+        sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+            .fetch_optional(pool)
+            .await?;
+    Ok(file.filter(|file| !file.is_empty()).map(PathBuf::from))
+}
+
+/// Where snapshots of the database at `file` are kept: a `backups` directory beside it.
+pub(crate) fn backup_dir(file: &Path) -> PathBuf {
+    file.parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("backups")
+}
+
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    pool: SqlitePool,
+    normalize_keys: bool,
+    key_rules: Option<KeyRules>,
+    expiry: ExpiryPolicy,
+    query_timeout: Option<Duration>,
+    defer_indexing: bool,
+    changes: ChangeFeed,
+}
+
+impl SqliteStorage {
+    pub async fn new(pool: SqlitePool) -> Result<Self> {
+        migrate_with_backup(&pool).await?;
+        backfill_derived(&pool).await?;
+        Ok(Self {
+            pool,
+            normalize_keys: false,
+            key_rules: None,
+            expiry: ExpiryPolicy::default(),
+            query_timeout: None,
+            defer_indexing: false,
+            changes: ChangeFeed::default(),
+        })
+    }
+
+    /// Open (creating if needed) the database file at `path` and run migrations.
+    pub async fn open(path: &Path) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+        Self::new(pool).await
+    }
+
+    /// Normalize keys (NFC + lowercase) on write and match them case-insensitively on read.
+    pub fn with_key_normalization(mut self, enabled: bool) -> Self {
+        self.normalize_keys = enabled;
+        self
+    }
+
+    /// Reject writes whose key violates `rules`.
+    pub fn with_key_rules(mut self, rules: KeyRules) -> Self {
+        self.key_rules = Some(rules);
+        self
+    }
+
+    /// Give documents written without a TTL the TTL their tags call for.
+    pub fn with_expiry_policy(mut self, policy: ExpiryPolicy) -> Self {
+        self.expiry = policy;
+        self
+    }
+
+    /// Stop a search or count that is still running after `limit`, failing it with
+    /// `ContextError::Timeout`. SQLite checks the deadline as it works, so the statement
+    /// stops even when nobody is waiting for it any more.
+    pub fn with_query_timeout(mut self, limit: Duration) -> Self {
+        self.query_timeout = Some(limit);
+        self
+    }
+
+    /// Have `store_all` and `merge_documents` fill in the full-text index in one pass
+    /// after their writes instead of row by row through the triggers, which makes large
+    /// imports much faster. The batch still commits as one transaction, and holds the
+    /// database's write lock from its first write to its commit either way.
+    pub fn with_deferred_indexing(mut self, enabled: bool) -> Self {
+        self.defer_indexing = enabled;
+        self
+    }
+
+    /// Hear about every revision written through this store (or a clone of it) from now
+    /// on; see `changes`.
+    pub fn subscribe(&self) -> broadcast::Receiver<DocumentChange> {
+        self.changes.subscribe()
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// A pooled connection whose statements SQLite interrupts once `query_timeout` passes.
+    async fn bounded_connection(&self) -> Result<BoundedConnection> {
+        let mut conn = self.pool.acquire().await?;
+        if let Some(limit) = self.query_timeout {
+            let deadline = Instant::now() + limit;
+            conn.lock_handle()
+                .await?
+                .set_progress_handler(PROGRESS_INTERVAL_OPS, move || Instant::now() < deadline);
+        }
+        Ok(BoundedConnection {
+            conn: Some(conn),
+            limit: self.query_timeout,
+        })
+    }
+
+    /// Group the live keys of a project by normalized form, returning groups with more than one key.
+    pub async fn key_collisions(&self, project: &ProjectId) -> Result<Vec<KeyCollision>> {
+        let groups = self.keys_by_normalized_form(project).await?;
+        Ok(groups
+            .into_iter()
+            .filter(|(_, keys)| keys.len() > 1)
+            .map(|(normalized, keys)| KeyCollision { normalized, keys })
+            .collect())
+    }
+
+    /// Rewrite the live keys of a project to their normalized form.
+    ///
+    /// Keys that collide with another live key after normalization are left untouched
+    /// and reported instead; a deleted or expired document holding the normalized form
+    /// gives it up. With `dry_run` nothing is written.
+    pub async fn normalize_existing_keys(
+        &self,
+        project: &ProjectId,
+        dry_run: bool,
+    ) -> Result<KeyNormalizationReport> {
+        let groups = self.keys_by_normalized_form(project).await?;
+        let mut report = KeyNormalizationReport::default();
+        let mut tx = self.pool.begin().await?;
+
+        for (normalized, keys) in groups {
+            if keys.len() > 1 {
+                report.collisions.push(KeyCollision { normalized, keys });
+                continue;
+            }
+            let key = keys.into_iter().next().expect("group has one key");
+            if key == normalized {
+                continue;
+            }
+            if !dry_run {
+                sqlx::query(
+                    "UPDATE documents SET key = NULL, normalized_key = NULL \
+                     WHERE project_id = ? AND key = ? \
+                       AND (deleted_at IS NOT NULL \
+                            OR (ttl_seconds IS NOT NULL AND CAST(strftime('%s','now') AS INTEGER) >= strftime('%s', created_at) + ttl_seconds))",
+                )
+                .bind(project)
+                .bind(&normalized)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query("UPDATE documents SET key = ? WHERE project_id = ? AND key = ?")
+                    .bind(&normalized)
+                    .bind(project)
+                    .bind(&key)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query(
+                    "INSERT INTO key_redirects (project_id, from_key, to_key) VALUES (?, ?, ?) \
+                     ON CONFLICT(project_id, from_key) DO UPDATE SET to_key = excluded.to_key",
+                )
+                .bind(project)
+                .bind(&key)
+                .bind(&normalized)
+                .execute(&mut *tx)
+                .await?;
+            }
+            report.renamed.push(KeyRename {
+                from: key,
+                to: normalized,
+            });
+        }
+
+        tx.commit().await?;
+        Ok(report)
+    }
+
+    /// Set the policy TTL on live documents of a project that have no TTL yet.
+    ///
+    /// The TTL counts from each document's creation, so old matching documents
+    /// may expire immediately. With `dry_run` nothing is written.
+    pub async fn apply_expiry_policy(
+        &self,
+        project: &ProjectId,
+        dry_run: bool,
+    ) -> Result<Vec<ExpiryChange>> {
+        let documents: Vec<Document> = self.stream_project(project).try_collect().await?;
+        let changes: Vec<ExpiryChange> = documents
+            .into_iter()
+            .filter(|doc| doc.ttl_seconds.is_none())
+            .filter_map(|doc| {
+                let ttl_seconds = self.expiry.ttl_for(&doc.tags)?;
+                Some(ExpiryChange {
+                    id: doc.id.0,
+                    key: doc.key,
+                    ttl_seconds,
+                })
+            })
+            .collect();
+
+        if !dry_run {
+            let mut tx = self.pool.begin().await?;
+            for change in &changes {
+                sqlx::query("UPDATE documents SET ttl_seconds = ? WHERE id = ?")
+                    .bind(change.ttl_seconds)
+                    .bind(&change.id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+        }
+        Ok(changes)
+    }
+
+    /// Make the live document `id` expire at `expires_at`, or never with `None`.
+    ///
+    /// Like `apply_expiry_policy` this changes the TTL in place: how long to keep a document
+    /// is not part of its content, so no revision is written.
+    pub async fn set_expiry(
+        &self,
+        id: &DocumentId,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<Document>> {
+        let Some(doc) = self.get_by_id(id).await? else {
+            return Ok(None);
+        };
+        let ttl_seconds = expires_at.map(|at| doc.ttl_until(at));
+        sqlx::query("UPDATE documents SET ttl_seconds = ? WHERE id = ?")
+            .bind(ttl_seconds)
+            .bind(&id.0)
+            .execute(&self.pool)
+            .await?;
+        Ok(Some(Document { ttl_seconds, ..doc }))
+    }
+
+    /// Registered and in-use namespaces of a project with their live document counts.
+    pub async fn namespaces(&self, project: &ProjectId) -> Result<Vec<NamespaceInfo>> {
+        let rows = sqlx::query(
+            "SELECT n.name AS name, \
+                    (SELECT COUNT(*) FROM documents d \
+                      WHERE d.project_id = n.project_id AND d.namespace = n.name \
+                        AND d.deleted_at IS NULL \
+                        AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds)) AS documents \
+             FROM (SELECT project_id, name FROM namespaces WHERE project_id = ? \
+                   UNION SELECT project_id, namespace FROM documents \
+                         WHERE project_id = ? AND namespace IS NOT NULL) n \
+             ORDER BY n.name",
+        )
+        .bind(project)
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(NamespaceInfo {
+                    name: row.try_get("name")?,
+                    documents: row.try_get::<i64, _>("documents")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Tags on live documents of a project with how many carry each, by name.
+    pub async fn tag_counts(&self, project: &ProjectId) -> Result<Vec<TagInfo>> {
+        let rows = sqlx::query(
+            "SELECT t.value AS name, COUNT(*) AS documents \
+             FROM documents d, json_each(d.tags) t \
+             WHERE d.project_id = ? \
+               AND d.deleted_at IS NULL \
+               AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds) \
+             GROUP BY t.value \
+             ORDER BY t.value",
+        )
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(TagInfo {
+                    name: row.try_get("name")?,
+                    documents: row.try_get::<i64, _>("documents")? as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Add and remove tags on `document` without touching its body.
+    ///
+    /// A change is written as the next revision, which also refreshes the search index;
+    /// when the tags come out the same, `document` is returned as it was.
+    pub async fn update_tags(
+        &self,
+        document: Document,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<Document> {
+        let mut tags: Vec<String> = document
+            .tags
+            .iter()
+            .filter(|tag| !remove.contains(tag))
+            .cloned()
+            .collect();
+        for tag in add {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if tags == document.tags {
+            return Ok(document);
+        }
+        self.put_revision(Document {
+            tags,
+            updated_at: Utc::now(),
+            version: document.version + 1,
+            ..document
+        })
+        .await
+    }
+
+    /// Items marked in live documents, by project, key, and line.
+    pub async fn items(&self, query: &ItemQuery) -> Result<Vec<DocumentItem>> {
+        let kinds: Vec<&str> = query.kinds.iter().map(|kind| kind.as_str()).collect();
+        let rows = sqlx::query(
+            "SELECT d.project_id, d.id, d.key, i.line, i.kind, i.text, i.done \
+             FROM items i JOIN documents d ON d.id = i.document_id \
+             WHERE (? IS NULL OR d.project_id = ?) \
+               AND d.deleted_at IS NULL \
+               AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds) \
+               AND (json_array_length(?) = 0 OR i.kind IN (SELECT value FROM json_each(?))) \
+               AND (? = 0 OR (i.kind != 'decision' AND i.done = 0)) \
+             ORDER BY d.project_id, d.key, d.id, i.line",
+        )
+        .bind(&query.project)
+        .bind(&query.project)
+        .bind(serde_json::to_string(&kinds)?)
+        .bind(serde_json::to_string(&kinds)?)
+        .bind(query.open_only)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let kind: String = row.try_get("kind")?;
+                Ok(DocumentItem {
+                    project: row.try_get("project_id")?,
+                    document_id: row.try_get("id")?,
+                    key: row.try_get("key")?,
+                    item: Item {
+                        kind: ItemKind::from_stored(&kind)
+                            .with_context(|| format!("Unknown item kind {kind:?}"))?,
+                        line: row.try_get::<i64, _>("line")? as u32,
+                        text: row.try_get("text")?,
+                        done: row.try_get("done")?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// The documents `id`'s body links to, in key order; a target with no live document
+    /// has no `document_id`.
+    pub async fn links(&self, id: &DocumentId) -> Result<Vec<LinkedDocument>> {
+        let rows = sqlx::query(
+            "SELECT l.target_project, l.target_key, d.id, d.title FROM links l \
+             LEFT JOIN documents d ON d.project_id = l.target_project AND d.key = l.target_key \
+               AND d.deleted_at IS NULL \
+               AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds) \
+             WHERE l.source_id = ? \
+             ORDER BY l.target_project, l.target_key",
+        )
+        .bind(&id.0)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(LinkedDocument {
+                    project: row.try_get("target_project")?,
+                    key: Some(row.try_get("target_key")?),
+                    document_id: row.try_get("id")?,
+                    title: row.try_get("title")?,
+                })
+            })
+            .collect()
+    }
+
+    /// The live documents whose bodies link to `key` in `project`, or to a key that now
+    /// redirects to it, by project and key.
+    pub async fn backlinks(&self, project: &ProjectId, key: &str) -> Result<Vec<LinkedDocument>> {
+        let rows = sqlx::query(
+            "SELECT DISTINCT d.project_id, d.key, d.id, d.title FROM links l \
+             JOIN documents d ON d.id = l.source_id \
+             WHERE l.target_project = ? \
+               AND (l.target_key = ? OR l.target_key IN \
+                    (SELECT from_key FROM key_redirects WHERE project_id = ? AND to_key = ?)) \
+               AND d.deleted_at IS NULL \
+               AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds) \
+             ORDER BY d.project_id, d.key, d.id",
+        )
+        .bind(project)
+        .bind(key)
+        .bind(project)
+        .bind(key)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(LinkedDocument {
+                    project: row.try_get("project_id")?,
+                    key: row.try_get("key")?,
+                    document_id: Some(row.try_get("id")?),
+                    title: row.try_get("title")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Attach a reminder to a document.
+    pub async fn add_reminder(
+        &self,
+        document_id: &DocumentId,
+        remind_at: DateTime<Utc>,
+        note: Option<String>,
+    ) -> Result<Reminder> {
+        let id = sqlx::query(
+            "INSERT INTO reminders (document_id, remind_at, note, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&document_id.0)
+        .bind(remind_at.to_rfc3339())
+        .bind(&note)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        self.reminder(id)
+            .await?
+            .context("Reminder vanished right after it was added")
+    }
+
+    pub async fn reminder(&self, id: i64) -> Result<Option<Reminder>> {
+        let row = sqlx::query(
+            "SELECT r.*, d.project_id, d.key FROM reminders r \
+             JOIN documents d ON d.id = r.document_id WHERE r.id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| reminder_from_row(&row)).transpose()
+    }
+
+    /// Reminders on live documents matching `query`, soonest first.
+    pub async fn reminders(&self, query: &ReminderQuery) -> Result<Vec<Reminder>> {
+        let due_by = query.due_by.map(|at| at.to_rfc3339());
+        let rows = sqlx::query(
+            "SELECT r.*, d.project_id, d.key FROM reminders r \
+             JOIN documents d ON d.id = r.document_id \
+             WHERE (? IS NULL OR d.project_id = ?) \
+               AND d.deleted_at IS NULL \
+               AND (? IS NULL OR strftime('%s', r.remind_at) <= strftime('%s', ?)) \
+               AND (? = 1 OR r.done_at IS NULL) \
+               AND (? = 0 OR r.notified_at IS NULL) \
+             ORDER BY r.remind_at, r.id",
+        )
+        .bind(&query.project)
+        .bind(&query.project)
+        .bind(&due_by)
+        .bind(&due_by)
+        .bind(query.include_done)
+        .bind(query.unnotified)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(reminder_from_row).collect()
+    }
+
+    /// Mark a reminder done; returns false when no pending reminder has that id.
+    pub async fn complete_reminder(&self, id: i64) -> Result<bool> {
+        let updated =
+            sqlx::query("UPDATE reminders SET done_at = ? WHERE id = ? AND done_at IS NULL")
+                .bind(Utc::now().to_rfc3339())
+                .bind(id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        Ok(updated > 0)
+    }
+
+    /// Record that a desktop notification was shown for a reminder.
+    pub async fn mark_reminder_notified(&self, id: i64, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query("UPDATE reminders SET notified_at = ? WHERE id = ?")
+            .bind(at.to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Create the template `name` in `project`, or replace its body and description.
+    pub async fn put_template(
+        &self,
+        project: &ProjectId,
+        name: &str,
+        description: Option<String>,
+        body_markdown: &str,
+    ) -> Result<Template> {
+        templates::validate_name(name)?;
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO templates (project_id, name, description, body_markdown, created_at, updated_at) \
+             VALUES (?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(project_id, name) DO UPDATE SET \
+                 description = excluded.description, \
+                 body_markdown = excluded.body_markdown, \
+                 updated_at = excluded.updated_at",
+        )
+        .bind(project)
+        .bind(name)
+        .bind(&description)
+        .bind(body_markdown)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+        self.template(project, name)
+            .await?
+            .context("Template vanished right after it was stored")
+    }
+
+    pub async fn template(&self, project: &ProjectId, name: &str) -> Result<Option<Template>> {
+        let row = sqlx::query("SELECT * FROM templates WHERE project_id = ? AND name = ?")
+            .bind(project)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.map(|row| template_from_row(&row)).transpose()
+    }
+
+    /// The templates of `project`, by name.
+    pub async fn templates(&self, project: &ProjectId) -> Result<Vec<Template>> {
+        let rows = sqlx::query("SELECT * FROM templates WHERE project_id = ? ORDER BY name")
+            .bind(project)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(template_from_row).collect()
+    }
+
+    /// Remove a template; returns false when `project` has none by that name.
+    pub async fn delete_template(&self, project: &ProjectId, name: &str) -> Result<bool> {
+        let deleted = sqlx::query("DELETE FROM templates WHERE project_id = ? AND name = ?")
+            .bind(project)
+            .bind(name)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    /// Attach `data` to `document` as `name`, replacing an attachment of that name.
+    /// `media_type` defaults to a guess from the name's extension.
+    pub async fn put_attachment(
+        &self,
+        document: &Document,
+        name: &str,
+        media_type: Option<&str>,
+        data: &[u8],
+    ) -> Result<Attachment> {
+        attachments::validate_name(name)?;
+        if data.len() > MAX_ATTACHMENT_BYTES {
+            fail!(
+                Quota,
+                "Attachment {name:?} is {} bytes; the limit is {MAX_ATTACHMENT_BYTES}.",
+                data.len()
+            );
+        }
+        let media_type = media_type.unwrap_or_else(|| attachments::media_type_for(name));
+        sqlx::query(
+            "INSERT INTO attachments (document_id, name, media_type, size, sha256, data, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?) \
+             ON CONFLICT(document_id, name) DO UPDATE SET \
+                 media_type = excluded.media_type, \
+                 size = excluded.size, \
+                 sha256 = excluded.sha256, \
+                 data = excluded.data, \
+                 created_at = excluded.created_at",
+        )
+        .bind(&document.id.0)
+        .bind(name)
+        .bind(media_type)
+        .bind(data.len() as i64)
+        .bind(attachments::sha256_hex(data))
+        .bind(data)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        self.attachment(&document.id, name)
+            .await?
+            .map(|(attachment, _)| attachment)
+            .context("Attachment vanished right after it was stored")
+    }
+
+    /// The attachments of a document, by name.
+    pub async fn attachments(&self, document_id: &DocumentId) -> Result<Vec<Attachment>> {
+        let rows = sqlx::query(
+            "SELECT document_id, name, media_type, size, sha256, created_at FROM attachments \
+             WHERE document_id = ? ORDER BY name",
+        )
+        .bind(&document_id.0)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(attachment_from_row).collect()
+    }
+
+    /// Every attachment in the store, by document and name, without their bytes.
+    pub async fn all_attachments(&self) -> Result<Vec<Attachment>> {
+        let rows = sqlx::query(
+            "SELECT document_id, name, media_type, size, sha256, created_at FROM attachments \
+             ORDER BY document_id, name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(attachment_from_row).collect()
+    }
+
+    /// One attachment with its bytes.
+    pub async fn attachment(
+        &self,
+        document_id: &DocumentId,
+        name: &str,
+    ) -> Result<Option<(Attachment, Vec<u8>)>> {
+        let row = sqlx::query("SELECT * FROM attachments WHERE document_id = ? AND name = ?")
+            .bind(&document_id.0)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.map(|row| Ok((attachment_from_row(&row)?, row.try_get("data")?)))
+            .transpose()
+    }
+
+    /// Live documents with a TTL that has not run out yet, soonest expiry first.
+    pub async fn expiring_documents(&self, project: Option<&ProjectId>) -> Result<Vec<Document>> {
+        let rows = sqlx::query(
+            "SELECT * FROM documents \
+             WHERE (? IS NULL OR project_id = ?) AND deleted_at IS NULL AND ttl_seconds IS NOT NULL \
+               AND CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds \
+             ORDER BY strftime('%s', created_at) + ttl_seconds, id",
+        )
+        .bind(project)
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(Self::deserialize_row).collect()
+    }
+
+    /// Register a namespace; returns false when it already existed.
+    pub async fn create_namespace(&self, project: &ProjectId, name: &str) -> Result<bool> {
+        validate_namespace(name)?;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT OR IGNORE INTO projects (id) VALUES (?)")
+            .bind(project)
+            .execute(&mut *tx)
+            .await?;
+        let inserted =
+            sqlx::query("INSERT OR IGNORE INTO namespaces (project_id, name) VALUES (?, ?)")
+                .bind(project)
+                .bind(name)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        tx.commit().await?;
+        Ok(inserted > 0)
+    }
+
+    /// Move every document (including tombstones) from namespace `from` to `to` in one transaction.
+    ///
+    /// Renaming onto an existing namespace merges the two. Returns the number of documents moved.
+    pub async fn rename_namespace(&self, project: &ProjectId, from: &str, to: &str) -> Result<u64> {
+        validate_namespace(to)?;
+        if from == to {
+            fail!(Validation, "Namespace {from:?} is already named {to:?}.");
+        }
+        if !self.namespace_exists(project, from).await? {
+            fail!(
+                NotFound,
+                "Namespace {from:?} does not exist in project {project}."
+            );
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let moved = sqlx::query(
+            "UPDATE documents SET namespace = ? WHERE project_id = ? AND namespace = ?",
+        )
+        .bind(to)
+        .bind(project)
+        .bind(from)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query("DELETE FROM namespaces WHERE project_id = ? AND name = ?")
+            .bind(project)
+            .bind(from)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT OR IGNORE INTO namespaces (project_id, name) VALUES (?, ?)")
+            .bind(project)
+            .bind(to)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(moved)
+    }
+
+    /// Unregister a namespace.
+    ///
+    /// Fails while live documents still use it unless `force`, which clears the namespace
+    /// from those documents instead. Returns the number of documents cleared.
+    pub async fn remove_namespace(
+        &self,
+        project: &ProjectId,
+        name: &str,
+        force: bool,
+    ) -> Result<u64> {
+        let Some(info) = self
+            .namespaces(project)
+            .await?
+            .into_iter()
+            .find(|info| info.name == name)
+        else {
+            fail!(
+                NotFound,
+                "Namespace {name:?} does not exist in project {project}."
+            );
+        };
+        if info.documents > 0 && !force {
+            fail!(
+                Validation,
+                "Namespace {name:?} still has {} document(s); rename it or pass --force to clear it from them.",
+                info.documents
+            );
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let cleared = sqlx::query(
+            "UPDATE documents SET namespace = NULL WHERE project_id = ? AND namespace = ?",
+        )
+        .bind(project)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        sqlx::query("DELETE FROM namespaces WHERE project_id = ? AND name = ?")
+            .bind(project)
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(cleared)
+    }
+
+    /// Every registered project, including those created implicitly by writing a document.
+    pub async fn projects(&self) -> Result<Vec<ProjectInfo>> {
+        let rows = sqlx::query(
+            "SELECT id, display_name, description, settings, created_at FROM projects ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(deserialize_project).collect()
+    }
+
+    /// Every registered project with its live document count, body size, and last update.
+    /// Only documents with one of `visibility` count; empty counts all.
+    pub async fn project_stats(&self, visibility: &[Visibility]) -> Result<Vec<ProjectStats>> {
+        let rows = sqlx::query(
+            "SELECT p.id, p.display_name, p.description, p.settings, p.created_at, \
+                    COUNT(d.id) AS documents, \
+                    COALESCE(SUM(length(CAST(d.body_markdown AS BLOB))), 0) AS bytes, \
+                    MAX(d.updated_at) AS last_updated \
+             FROM projects p \
+             LEFT JOIN documents d ON d.project_id = p.id AND d.deleted_at IS NULL \
+                  AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds) \
+                  AND (json_array_length(?) = 0 OR COALESCE(d.visibility, 'project') IN (SELECT value FROM json_each(?))) \
+             GROUP BY p.id \
+             ORDER BY p.id",
+        )
+        .bind(serde_json::to_string(visibility)?)
+        .bind(serde_json::to_string(visibility)?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let documents = row.try_get::<i64, _>("documents")? as u64;
+                let bytes = row.try_get::<i64, _>("bytes")? as u64;
+                let last_updated: Option<String> = row.try_get("last_updated")?;
+                Ok(ProjectStats {
+                    documents,
+                    bytes,
+                    last_updated: last_updated.as_deref().map(parse_datetime).transpose()?,
+                    project: deserialize_project(row)?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn project(&self, id: &ProjectId) -> Result<Option<ProjectInfo>> {
+        sqlx::query(
+            "SELECT id, display_name, description, settings, created_at FROM projects WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(deserialize_project)
+        .transpose()
+    }
+
+    /// Register a project; returns false when it already existed.
+    pub async fn register_project(&self, id: &ProjectId) -> Result<bool> {
+        validate_project(id)?;
+        let inserted = sqlx::query("INSERT OR IGNORE INTO projects (id) VALUES (?)")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(inserted > 0)
+    }
+
+    /// Store a project's display name, description, and settings, registering it if needed.
+    ///
+    /// `created_at` is kept from the existing row.
+    pub async fn update_project(&self, info: &ProjectInfo) -> Result<ProjectInfo> {
+        validate_project(&info.id)?;
+        sqlx::query(
+            "INSERT INTO projects (id, display_name, description, settings) VALUES (?, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET display_name = excluded.display_name, \
+                description = excluded.description, settings = excluded.settings",
+        )
+        .bind(&info.id)
+        .bind(&info.display_name)
+        .bind(&info.description)
+        .bind(serde_json::to_string(&info.settings)?)
+        .execute(&self.pool)
+        .await?;
+        self.project(&info.id)
+            .await?
+            .context("Project vanished while it was being updated")
+    }
+
+    /// Point `from` at `to` so lookups of the retired key can follow it.
+    ///
+    /// Existing redirects that ended at `from` are re-pointed at `to`, keeping every
+    /// chain one hop long; a redirect away from `to` is dropped since `to` is live again.
+    pub async fn record_redirect(&self, project: &ProjectId, from: &str, to: &str) -> Result<()> {
+        if from == to {
+            fail!(Validation, "Cannot redirect key {from:?} to itself.");
+        }
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT OR IGNORE INTO projects (id) VALUES (?)")
+            .bind(project)
+            .execute(&mut *tx)
+            .await?;
+        write_redirect(&mut tx, project, from, to, true).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Give `document` the key `to` as its next revision, all in one transaction.
+    ///
+    /// With `alias` the old key keeps resolving to it, as after `record_redirect`.
+    /// Without, the old key is free for another document; redirects that ended at it
+    /// still follow the document to `to`.
+    pub async fn rename_key(&self, document: Document, to: &str, alias: bool) -> Result<Document> {
+        let Some(from) = document.key.clone() else {
+            fail!(
+                Validation,
+                "Document {} has no key to rename.",
+                document.id.0
+            );
+        };
+        let renamed = self.prepare(Document {
+            key: Some(to.to_string()),
+            updated_at: Utc::now(),
+            version: document.version + 1,
+            ..document
+        })?;
+        let to = renamed.key.clone().unwrap_or_default();
+        if to == from {
+            fail!(Validation, "Document already has key {to}.");
+        }
+        if self.get_by_key(&renamed.project, &to).await?.is_some() {
+            return Err(Conflict::Key(format!(
+                "Key {to} is already used by another document in project {}.",
+                renamed.project
+            ))
+            .into());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        write_document(&mut tx, &renamed).await?;
+        write_redirect(&mut tx, &renamed.project, &from, &to, alias).await?;
+        tx.commit().await?;
+        self.changes.announce([&renamed]);
+        Ok(renamed)
+    }
+
+    /// `get_by_key` for reading: when `key` has no live document but was retired by a merge
+    /// or rename, the live document its redirect leads to, with the key it now has.
+    /// Writes go through `get_by_key`, so they never land on a redirect's target.
+    pub async fn resolve_key(
+        &self,
+        project: &ProjectId,
+        key: &str,
+    ) -> Result<Option<(Document, Option<Key>)>> {
+        if let Some(document) = self.get_by_key(project, key).await? {
+            return Ok(Some((document, None)));
+        }
+        let Some(target) = self.redirect_for(project, key).await? else {
+            return Ok(None);
+        };
+        Ok(self
+            .get_by_key(project, &target)
+            .await?
+            .map(|document| (document, Some(target))))
+    }
+
+    /// The key a retired key now redirects to, if any.
+    pub async fn redirect_for(&self, project: &ProjectId, key: &str) -> Result<Option<Key>> {
+        Ok(sqlx::query_scalar(
+            "SELECT to_key FROM key_redirects WHERE project_id = ? AND from_key = ?",
+        )
+        .bind(project)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    pub async fn redirects(&self, project: &ProjectId) -> Result<Vec<KeyRedirect>> {
+        let rows = sqlx::query(
+            "SELECT from_key, to_key FROM key_redirects WHERE project_id = ? ORDER BY from_key",
+        )
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(KeyRedirect {
+                    from: row.try_get("from_key")?,
+                    to: row.try_get("to_key")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Save `draft`, turning it into the next revision of the live document with the same key if one exists.
+    ///
+    /// Identity, creation time, and any metadata the draft leaves unset carry over from the previous revision.
+    pub async fn store(&self, draft: Document) -> Result<Document> {
+        let existing = match &draft.key {
+            Some(key) => self.get_by_key(&draft.project, key).await?,
+            None => None,
+        };
+
+        let document = match existing {
+            Some(previous) => next_revision(previous, draft),
+            None => draft,
+        };
+        self.put(document).await
+    }
+
+    /// `store` every draft in one transaction, so either all of them are written or none
+    /// are. A draft at the same key as an earlier one in `drafts` becomes its next revision.
+    pub async fn store_all(&self, drafts: Vec<Document>) -> Result<Vec<Document>> {
+        let mut documents: Vec<Document> = Vec::with_capacity(drafts.len());
+        // Where each key written so far sits in `documents`.
+        let mut written: HashMap<(ProjectId, Key), usize> = HashMap::new();
+        for draft in drafts {
+            let earlier = match &draft.key {
+                Some(key) => {
+                    let key = if self.normalize_keys {
+                        normalize_key(key)
+                    } else {
+                        key.clone()
+                    };
+                    written.get(&(draft.project.clone(), key)).copied()
+                }
+                None => None,
+            };
+            let existing = match (earlier, &draft.key) {
+                (Some(index), _) => Some(documents[index].clone()),
+                (None, Some(key)) => self.get_by_key(&draft.project, key).await?,
+                (None, None) => None,
+            };
+            let document = self.prepare(match existing {
+                Some(previous) => next_revision(previous, draft),
+                None => draft,
+            })?;
+            if let Some(key) = &document.key {
+                written.insert((document.project.clone(), key.clone()), documents.len());
+            }
+            documents.push(document);
+        }
+
+        self.write_all(&documents).await?;
+        self.optimize_after(documents.len()).await?;
+        Ok(documents)
+    }
+
+    /// Merge documents exported from another store into `project`, by id (see `merge`).
+    ///
+    /// Each document keeps its id, version, and timestamps. Everything is written in one
+    /// transaction; with `dry_run` nothing is.
+    pub async fn merge_documents(
+        &self,
+        project: &ProjectId,
+        incoming: Vec<Document>,
+        dry_run: bool,
+    ) -> Result<Vec<MergedDocument>> {
+        let mut results = Vec::with_capacity(incoming.len());
+        let mut accepted: Vec<Document> = Vec::new();
+        // Ids and keys claimed earlier in this batch, by index into `accepted`.
+        let mut by_id: HashMap<String, usize> = HashMap::new();
+        let mut by_key: HashMap<Key, String> = HashMap::new();
+        for doc in incoming {
+            validate_id(&doc.id.0)?;
+            let doc = self.prepare(Document {
+                project: project.clone(),
+                ..doc
+            })?;
+            let existing = match by_id.get(&doc.id.0) {
+                Some(&index) => Some(accepted[index].clone()),
+                None => sqlx::query("SELECT * FROM documents WHERE id = ?")
+                    .bind(&doc.id.0)
+                    .fetch_optional(&self.pool)
+                    .await?
+                    .map(Self::deserialize_row)
+                    .transpose()?,
+            };
+            let (outcome, conflict) = match &existing {
+                Some(local) if &local.project != project => (
+                    MergeOutcome::Conflict,
+                    Some(format!(
+                        "the id belongs to a document in project {}",
+                        local.project
+                    )),
+                ),
+                Some(local) if local.version > doc.version => (MergeOutcome::Older, None),
+                Some(local) if local.version == doc.version && same_revision(local, &doc) => {
+                    (MergeOutcome::Unchanged, None)
+                }
+                Some(local) if local.version == doc.version => (
+                    MergeOutcome::Conflict,
+                    Some(format!(
+                        "version {} differs from the local copy",
+                        local.version
+                    )),
+                ),
+                _ => match self.key_holder(project, &doc, &by_key).await? {
+                    Some(holder) => (
+                        MergeOutcome::Conflict,
+                        Some(format!("the key belongs to document {holder}")),
+                    ),
+                    None if existing.is_some() => (MergeOutcome::Updated, None),
+                    None => (MergeOutcome::Created, None),
+                },
+            };
+            results.push(MergedDocument {
+                id: doc.id.0.clone(),
+                key: doc.key.clone(),
+                version: doc.version,
+                outcome,
+                conflict,
+            });
+            if matches!(outcome, MergeOutcome::Created | MergeOutcome::Updated) {
+                if let (Some(key), None) = (&doc.key, doc.deleted_at) {
+                    by_key.insert(key.clone(), doc.id.0.clone());
+                }
+                match by_id.get(&doc.id.0) {
+                    Some(&index) => accepted[index] = doc,
+                    None => {
+                        by_id.insert(doc.id.0.clone(), accepted.len());
+                        accepted.push(doc);
+                    }
+                }
+            }
+        }
+
+        if !dry_run && !accepted.is_empty() {
+            self.write_all(&accepted).await?;
+            self.optimize_after(accepted.len()).await?;
+        }
+        Ok(results)
+    }
+
+    /// After a batch of `written` documents, let SQLite refresh the planner statistics
+    /// (`PRAGMA optimize`) if the batch was big enough to have moved them. It only
+    /// analyzes tables whose statistics are out of date, so it is cheap when they aren't.
+    async fn optimize_after(&self, written: usize) -> Result<()> {
+        if written >= OPTIMIZE_AFTER_BATCH {
+            sqlx::query("PRAGMA optimize").execute(&self.pool).await?;
+        }
+        Ok(())
+    }
+
+    /// Write `documents` in one transaction, indexing them afterwards when indexing is
+    /// deferred (see `with_deferred_indexing`).
+    async fn write_all(&self, documents: &[Document]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if !self.defer_indexing {
+            for document in documents {
+                write_document(&mut tx, document).await?;
+            }
+            tx.commit().await?;
+            self.changes.announce(documents);
+            return Ok(());
+        }
+
+        // The triggers are dropped inside the transaction and recreated from their own
+        // definitions before it commits, so no other connection ever sees them missing.
+        let triggers: Vec<(String, String)> = sqlx::query_as(
+            "SELECT name, sql FROM sqlite_master \
+             WHERE type = 'trigger' AND name IN ('documents_ai', 'documents_au') ORDER BY name",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if triggers.len() != 2 {
+            return Err(ContextError::storage(
+                "The full-text index triggers are missing from the database.",
+            ));
+        }
+        for (name, _) in &triggers {
+            sqlx::query(&format!("DROP TRIGGER {name}"))
+                .execute(&mut *tx)
+                .await?;
+        }
+        for document in documents {
+            write_document(&mut tx, document).await?;
+        }
+        let ids: Vec<&str> = documents.iter().map(|doc| doc.id.0.as_str()).collect();
+        let ids = serde_json::to_string(&ids)?;
+        sqlx::query(
+            "DELETE FROM documents_fts WHERE rowid IN \
+             (SELECT rowid FROM documents WHERE id IN (SELECT value FROM json_each(?)))",
+        )
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "{INDEX_DOCUMENTS} WHERE id IN (SELECT value FROM json_each(?))"
+        ))
+        .bind(&ids)
+        .execute(&mut *tx)
+        .await?;
+        for (_, sql) in &triggers {
+            sqlx::query(sql).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        self.changes.announce(documents);
+        Ok(())
+    }
+
+    /// The id of another live document holding `doc`'s key, in the store or earlier in
+    /// the batch being merged.
+    async fn key_holder(
+        &self,
+        project: &ProjectId,
+        doc: &Document,
+        batch: &HashMap<Key, String>,
+    ) -> Result<Option<String>> {
+        let Some(key) = doc.key.as_deref().filter(|_| doc.deleted_at.is_none()) else {
+            return Ok(None);
+        };
+        if let Some(id) = batch.get(key) {
+            return Ok((id != &doc.id.0).then(|| id.clone()));
+        }
+        Ok(self
+            .get_by_key(project, key)
+            .await?
+            .map(|holder| holder.id.0)
+            .filter(|id| id != &doc.id.0))
+    }
+
+    /// Check `doc` can be written and fill in what the store decides: its normalized key
+    /// and the TTL the expiry policy gives its tags.
+    fn prepare(&self, mut doc: Document) -> Result<Document> {
+        if matches!(doc.source, SourceType::Unknown) {
+            // Writing it back would replace the newer release's value with a placeholder.
+            fail!(Validation, "Cannot write a document whose source is not recognized by this build; upgrade context to edit it.");
+        }
+        if self.normalize_keys {
+            doc.key = doc.key.as_deref().map(normalize_key);
+        }
+        if let (Some(rules), Some(key)) = (&self.key_rules, &doc.key) {
+            rules.validate(key)?;
+        }
+        if let Some(namespace) = &doc.namespace {
+            validate_namespace(namespace)?;
+        }
+        if doc.ttl_seconds.is_none() && doc.deleted_at.is_none() {
+            doc.ttl_seconds = self.expiry.ttl_for(&doc.tags);
+        }
+        // A `null` only means something against a previous revision.
+        doc.metadata.retain(|_, value| !value.is_null());
+        Ok(doc)
+    }
+
+    /// `store` only if the live document at the draft's key is still at `expected_version`,
+    /// failing with `VersionConflict` otherwise (including when it is gone).
+    pub async fn store_expecting(
+        &self,
+        draft: Document,
+        expected_version: u64,
+    ) -> Result<Document> {
+        let current = match &draft.key {
+            Some(key) => self.get_by_key(&draft.project, key).await?,
+            None => None,
+        };
+        let previous = check_version(current, expected_version)?;
+        self.put_revision(next_revision(previous, draft)).await
+    }
+
+    /// Tombstone the live document at `key`, first checking it is at `expected_version`
+    /// when one is given. Returns `None` when there is no live document to delete.
+    pub async fn delete_expecting(
+        &self,
+        project: &ProjectId,
+        key: &str,
+        expected_version: Option<u64>,
+    ) -> Result<Option<Document>> {
+        let current = self.get_by_key(project, key).await?;
+        let current = match expected_version {
+            Some(expected) => check_version(current, expected)?,
+            None => match current {
+                Some(current) => current,
+                None => return Ok(None),
+            },
+        };
+        let now = Utc::now();
+        let tombstone = Document {
+            updated_at: now,
+            deleted_at: Some(now),
+            version: current.version + 1,
+            ..current
+        };
+        self.put_revision(tombstone).await.map(Some)
+    }
+
+    /// Delete the live document `current` as far as `mode` says, returning its tombstone.
+    ///
+    /// A hard delete writes no tombstone row, but announces the tombstone it would have
+    /// been so change subscribers still see the document go.
+    async fn delete_document(&self, current: Document, mode: DeleteMode) -> Result<Document> {
+        let now = Utc::now();
+        let tombstone = Document {
+            updated_at: now,
+            deleted_at: Some(now),
+            version: current.version + 1,
+            ..current
+        };
+        match mode {
+            DeleteMode::Soft => self.put_revision(tombstone).await,
+            DeleteMode::Purge => {
+                // The tombstone and the erased versions commit together, so a failed purge
+                // leaves the document as it was.
+                let tombstone = self.prepare(tombstone)?;
+                let mut tx = self.pool.begin().await?;
+                if let Err(err) = write_document(&mut tx, &tombstone).await {
+                    drop(tx);
+                    return Err(self.revision_conflict(err, &tombstone).await);
+                }
+                sqlx::query("DELETE FROM document_versions WHERE document_id = ? AND version < ?")
+                    .bind(&tombstone.id.0)
+                    .bind(tombstone.version as i64)
+                    .execute(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                self.changes.announce([&tombstone]);
+                Ok(tombstone)
+            }
+            DeleteMode::Hard => {
+                // History rows, FTS entries, and links go with the row.
+                let removed = sqlx::query(
+                    "DELETE FROM documents WHERE id = ? AND version = ? AND deleted_at IS NULL",
+                )
+                .bind(&tombstone.id.0)
+                .bind(tombstone.version as i64 - 1)
+                .execute(&self.pool)
+                .await?;
+                if removed.rows_affected() == 0 {
+                    return Err(VersionConflict {
+                        expected: tombstone.version - 1,
+                        actual: self.get_by_id(&tombstone.id).await?.map(|doc| doc.version),
+                    }
+                    .into());
+                }
+                self.changes.announce([&tombstone]);
+                Ok(tombstone)
+            }
+        }
+    }
+
+    /// The tombstone that last held `key`, unless a later document has reused the key.
+    pub async fn deleted_by_key(&self, project: &ProjectId, key: &str) -> Result<Option<Document>> {
+        let key = if self.normalize_keys {
+            normalize_key(key)
+        } else {
+            key.to_string()
+        };
+        let row = sqlx::query(
+            "SELECT * FROM documents WHERE project_id = ? AND key = ? AND deleted_at IS NOT NULL",
+        )
+        .bind(project)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(Self::deserialize_row).transpose()
+    }
+
+    /// The tombstone of the document with this id, if it is deleted.
+    pub async fn deleted_by_id(&self, id: &DocumentId) -> Result<Option<Document>> {
+        let row = sqlx::query("SELECT * FROM documents WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(&id.0)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.map(Self::deserialize_row).transpose()
+    }
+
+    /// Undo `delete`: write the tombstone's content back as a new live revision, which puts
+    /// it back in search. Returns `None` when `id` is not a tombstone.
+    ///
+    /// A tombstone whose key was reused has lost it; the restored document takes back the
+    /// last key its history records, and fails if a live document holds that key now.
+    pub async fn restore(&self, id: &DocumentId) -> Result<Option<Document>> {
+        let Some(tombstone) = self.deleted_by_id(id).await? else {
+            return Ok(None);
+        };
+        let key = match tombstone.key.clone() {
+            Some(key) => Some(key),
+            None => self
+                .history(id)
+                .await?
+                .into_iter()
+                .rev()
+                .find_map(|revision| revision.key),
+        };
+        if let Some(key) = &key {
+            if let Some(live) = self.live_document_by_key(&tombstone.project, key).await? {
+                return Err(Conflict::Key(format!(
+                    "Key {key} now belongs to document {}; rename or remove it before restoring {}.",
+                    live.id.0,
+                    id.0
+                ))
+                .into());
+            }
+        }
+        let restored = Document {
+            key,
+            updated_at: Utc::now(),
+            deleted_at: None,
+            version: tombstone.version + 1,
+            ..tombstone
+        };
+        self.put_revision(restored).await.map(Some)
+    }
+
+    /// Write a revision computed from a document read earlier. A concurrent writer that got
+    /// there first already holds this version number, which `document_versions` rejects.
+    async fn put_revision(&self, revision: Document) -> Result<Document> {
+        match self.put(revision.clone()).await {
+            Err(err) => Err(self.revision_conflict(err, &revision).await),
+            ok => ok,
+        }
+    }
+
+    /// `err` from writing `revision`, as a `VersionConflict` when it failed because a
+    /// concurrent writer already holds that version number.
+    async fn revision_conflict(&self, err: ContextError, revision: &Document) -> ContextError {
+        if !is_unique_violation(&err) {
+            return err;
+        }
+        match self.get_by_id(&revision.id).await {
+            Ok(current) => VersionConflict {
+                expected: revision.version - 1,
+                actual: current
+                    .filter(|doc| doc.deleted_at.is_none())
+                    .map(|doc| doc.version),
+            }
+            .into(),
+            Err(err) => err,
+        }
+    }
+
+    /// Remove `tombstone` and its history, as `gc` does once the retention window has
+    /// passed. `None` when a restore or `gc` got to it first.
+    async fn erase_tombstone(&self, tombstone: Document) -> Result<Option<Document>> {
+        let removed = sqlx::query(
+            "DELETE FROM documents WHERE id = ? AND version = ? AND deleted_at IS NOT NULL",
+        )
+        .bind(&tombstone.id.0)
+        .bind(tombstone.version as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok((removed.rows_affected() > 0).then_some(tombstone))
+    }
+
+    /// `store` under a client idempotency key: a retry of the same write within
+    /// `IDEMPOTENCY_WINDOW` returns the first result instead of writing again.
+    ///
+    /// Reusing the key for different content fails with `IdempotencyConflict`.
+    pub async fn store_once(
+        &self,
+        draft: Document,
+        idempotency_key: &str,
+    ) -> Result<IdempotentWrite> {
+        sqlx::query(
+            "DELETE FROM idempotency_keys \
+             WHERE CAST(strftime('%s', created_at) AS INTEGER) < CAST(strftime('%s','now') AS INTEGER) - ?",
+        )
+        .bind(IDEMPOTENCY_WINDOW.num_seconds())
+        .execute(&self.pool)
+        .await?;
+
+        let fingerprint = fingerprint(&draft);
+        let seen = sqlx::query(
+            "SELECT fingerprint, response FROM idempotency_keys \
+             WHERE project_id = ? AND idempotency_key = ?",
+        )
+        .bind(&draft.project)
+        .bind(idempotency_key)
+        .fetch_optional(&self.pool)
+        .await?;
+        if let Some(row) = seen {
+            if row.try_get::<String, _>("fingerprint")? != fingerprint {
+                return Err(IdempotencyConflict(idempotency_key.to_string()).into());
+            }
+            let response: String = row.try_get("response")?;
+            return Ok(IdempotentWrite {
+                document: serde_json::from_str(&response)?,
+                replayed: true,
+            });
+        }
+
+        let project = draft.project.clone();
+        let document = self.store(draft).await?;
+        sqlx::query(
+            "INSERT OR IGNORE INTO idempotency_keys \
+             (project_id, idempotency_key, fingerprint, response) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&project)
+        .bind(idempotency_key)
+        .bind(&fingerprint)
+        .bind(serde_json::to_string(&document)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(IdempotentWrite {
+            document,
+            replayed: false,
+        })
+    }
+
+    /// Documents of `project` matching `query` in `query.sort` order (most recently
+    /// updated first by default), yielded as rows are read. `query.limit` caps the stream.
+    pub fn list<'a>(
+        &'a self,
+        project: &'a ProjectId,
+        query: &ListQuery,
+    ) -> BoxStream<'a, Result<Document>> {
+        let after = match query.cursor_position() {
+            Ok(after) => after,
+            Err(err) => return futures::stream::once(async { Err(err) }).boxed(),
+        };
+        let (after_position, after_id) = after.unzip();
+        let sql = match query.sort {
+            ListSort::Updated => concat!(
+                "SELECT * FROM documents ",
+                list_filter_sql!(),
+                " AND (? IS NULL OR updated_at < ? OR (updated_at = ? AND id < ?)) \
+                 ORDER BY updated_at DESC, id DESC \
+                 LIMIT ?"
+            ),
+            ListSort::Created => concat!(
+                "SELECT * FROM documents ",
+                list_filter_sql!(),
+                " AND (? IS NULL OR created_at < ? OR (created_at = ? AND id < ?)) \
+                 ORDER BY created_at DESC, id DESC \
+                 LIMIT ?"
+            ),
+            ListSort::Key => concat!(
+                "SELECT * FROM documents ",
+                list_filter_sql!(),
+                " AND (? IS NULL OR COALESCE(key, '') > ? \
+                       OR (COALESCE(key, '') = ? AND id > ?)) \
+                 ORDER BY COALESCE(key, '') ASC, id ASC \
+                 LIMIT ?"
+            ),
+        };
+
+        self.bind_list_filter(sqlx::query(sql), project, query)
+            .bind(after_position.clone())
+            .bind(after_position.clone())
+            .bind(after_position)
+            .bind(after_id)
+            .bind(query.limit.map(|limit| limit as i64).unwrap_or(-1))
+            .fetch(&self.pool)
+            .map(|row| Self::deserialize_row(row?))
+            .boxed()
+    }
+
+    /// Bind the parameters of `list_filter_sql!` for `project` and `query`.
+    fn bind_list_filter<'q>(
+        &self,
+        sql: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+        project: &ProjectId,
+        query: &ListQuery,
+    ) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+        let key_prefix = query.key_prefix.as_ref().map(|prefix| {
+            if self.normalize_keys {
+                normalize_key(prefix)
+            } else {
+                prefix.clone()
+            }
+        });
+        let json = |values: &[String]| serde_json::to_string(values).unwrap_or_default();
+        let visibilities = json(
+            &query
+                .visibility
+                .iter()
+                .map(|visibility| visibility.as_str().to_string())
+                .collect::<Vec<_>>(),
+        );
+        sql.bind(project.clone())
+            .bind(query.include_deleted || query.only_deleted)
+            .bind(query.only_deleted)
+            .bind(json(&query.tags))
+            .bind(json(&query.not_tags))
+            .bind(query.namespace.clone())
+            .bind(query.namespace.clone())
+            .bind(json(&query.exclude_namespaces))
+            .bind(key_prefix.clone())
+            .bind(key_prefix.clone())
+            .bind(key_prefix)
+            .bind(query.since.map(|since| since.to_rfc3339()))
+            .bind(query.since.map(|since| since.to_rfc3339()))
+            .bind(query.until.map(|until| until.to_rfc3339()))
+            .bind(query.until.map(|until| until.to_rfc3339()))
+            .bind(query.expiring_before.map(|before| before.to_rfc3339()))
+            .bind(query.expiring_before.map(|before| before.to_rfc3339()))
+            .bind(visibilities.clone())
+            .bind(visibilities)
+            .bind(serde_json::to_string(&query.metadata).unwrap_or_default())
+    }
+
+    /// The file backing this database, or `None` when it lives in memory.
+    pub async fn database_file(&self) -> Result<Option<PathBuf>> {
+        database_file(&self.pool).await
+    }
+
+    /// A read-only view of the store as it is now, for a series of reads that must agree
+    /// with each other (`context pack`, `context export`): writes committed after this
+    /// returns, including the rest of a multi-document batch, stay invisible to it.
+    ///
+    /// The view is one connection holding a read transaction open until it is dropped,
+    /// which keeps the write-ahead log from being checkpointed past that point, so keep it
+    /// short-lived. A store in memory has no second connection to give and is returned
+    /// as it is.
+    pub async fn snapshot(&self) -> Result<SqliteStorage> {
+        if self.database_file().await?.is_none() {
+            return Ok(self.clone());
+        }
+        let options = self.pool.connect_options().as_ref().clone().read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await
+            .context("Failed to open a snapshot of the database")?;
+        sqlx::query("BEGIN").execute(&pool).await?;
+        // A deferred transaction settles on what it sees at its first read.
+        sqlx::query("SELECT 1 FROM documents LIMIT 1")
+            .fetch_optional(&pool)
+            .await?;
+        Ok(SqliteStorage {
+            pool,
+            changes: ChangeFeed::default(),
+            ..self.clone()
+        })
+    }
+
+    /// Fold the write-ahead log back into the database file and truncate it.
+    pub async fn checkpoint_wal(&self) -> Result<()> {
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Write a consistent copy of the database to `path`, which must not exist yet.
+    pub async fn backup_to(&self, path: &Path) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.display().to_string())
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to back up the database to {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Permanently delete documents whose TTL has run out, along with their history.
+    ///
+    /// Expired documents are already hidden from every read; this reclaims their rows.
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM documents \
+             WHERE ttl_seconds IS NOT NULL \
+               AND CAST(strftime('%s','now') AS INTEGER) >= strftime('%s', created_at) + ttl_seconds",
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Permanently delete `project`'s tombstones along with their history, after which
+    /// `restore` can no longer bring them back.
+    pub async fn purge_deleted(&self, project: &ProjectId) -> Result<u64> {
+        let result =
+            sqlx::query("DELETE FROM documents WHERE project_id = ? AND deleted_at IS NOT NULL")
+                .bind(project)
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Rebuild the database file so the pages freed by purges go back to the filesystem.
+    pub async fn vacuum(&self) -> Result<()> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Recompute the statistics SQLite's query planner works from, returning how many
+    /// tables and indexes now have them. Plans go stale as the documents table grows
+    /// by orders of magnitude; this scans every index, so it takes a while on a big store.
+    pub async fn analyze(&self) -> Result<u64> {
+        sqlx::query("ANALYZE").execute(&self.pool).await?;
+        let analyzed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_stat1")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(analyzed as u64)
+    }
+
+    /// Rewrite the whole full-text index from `documents`, returning how many rows it now
+    /// holds. Repairs an index the triggers let drift, e.g. after manual edits.
+    pub async fn rebuild_fts(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM documents_fts")
+            .execute(&mut *tx)
+            .await?;
+        let indexed = sqlx::query(INDEX_DOCUMENTS).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(indexed.rows_affected())
+    }
 
-use anyhow::bail;
-use chrono::{DateTime, Utc};
-use sqlx::{migrate::Migrator, sqlite::SqliteRow, Row, SqlitePool};
+    /// Find (and unless `check_only`, delete) FTS and history rows that point at no document.
+    ///
+    /// The triggers and foreign keys normally prevent these, so the sweep spans the whole
+    /// database rather than one project: an orphan no longer says which project it was in.
+    pub async fn sweep_orphans(&self, check_only: bool) -> Result<ConsistencyReport> {
+        const ORPHANED_FTS: &str = "FROM documents_fts WHERE NOT EXISTS (\
+             SELECT 1 FROM documents d \
+             WHERE d.rowid = documents_fts.rowid AND d.id = documents_fts.document_id)";
+        const ORPHANED_VERSIONS: &str = "FROM document_versions WHERE NOT EXISTS (\
+             SELECT 1 FROM documents d WHERE d.id = document_versions.document_id)";
 
-use crate::{
-    Document, DocumentId, Key, ProjectId, Result, SearchHit, SearchQuery, SourceType, Storage,
-};
+        let mut tx = self.pool.begin().await?;
+        let orphaned_fts_rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {ORPHANED_FTS}"))
+            .fetch_one(&mut *tx)
+            .await?;
+        let orphaned_versions: i64 =
+            sqlx::query_scalar(&format!("SELECT COUNT(*) {ORPHANED_VERSIONS}"))
+                .fetch_one(&mut *tx)
+                .await?;
+        if !check_only {
+            sqlx::query(&format!("DELETE {ORPHANED_FTS}"))
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(&format!("DELETE {ORPHANED_VERSIONS}"))
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
 
-static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+        Ok(ConsistencyReport {
+            orphaned_fts_rows: orphaned_fts_rows as u64,
+            orphaned_versions: orphaned_versions as u64,
+            repaired: !check_only,
+        })
+    }
 
-/// Run database migrations for the SQLite backend.
-pub async fn run_migrations(pool: &SqlitePool) -> Result<()> {
-    MIGRATOR.run(pool).await?;
-    Ok(())
-}
+    async fn namespace_exists(&self, project: &ProjectId, name: &str) -> Result<bool> {
+        let found: Option<i64> = sqlx::query_scalar(
+            "SELECT 1 FROM namespaces WHERE project_id = ? AND name = ? \
+             UNION SELECT 1 FROM documents WHERE project_id = ? AND namespace = ? \
+             LIMIT 1",
+        )
+        .bind(project)
+        .bind(name)
+        .bind(project)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(found.is_some())
+    }
 
-#[derive(Debug, Clone)]
-pub struct SqliteStorage {
-    pool: SqlitePool,
-}
+    /// The live keys of a project, grouped by normalized form.
+    async fn keys_by_normalized_form(
+        &self,
+        project: &ProjectId,
+    ) -> Result<BTreeMap<Key, Vec<Key>>> {
+        let keys: Vec<Key> = sqlx::query_scalar(
+            "SELECT key FROM documents \
+             WHERE project_id = ? \
+               AND key IS NOT NULL \
+               AND deleted_at IS NULL \
+               AND (ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds) \
+             ORDER BY key",
+        )
+        .bind(project)
+        .fetch_all(&self.pool)
+        .await?;
 
-impl SqliteStorage {
-    pub async fn new(pool: SqlitePool) -> Result<Self> {
-        run_migrations(&pool).await?;
-        Ok(Self { pool })
+        let mut groups: BTreeMap<Key, Vec<Key>> = BTreeMap::new();
+        for key in keys {
+            groups.entry(normalize_key(&key)).or_default().push(key);
+        }
+        Ok(groups)
     }
 
-    pub fn pool(&self) -> &SqlitePool {
-        &self.pool
+    /// Find a live key whose normalized form matches `normalized` (legacy keys written before
+    /// normalization was enabled).
+    async fn find_equivalent_key(
+        &self,
+        project: &ProjectId,
+        normalized: &str,
+    ) -> Result<Option<Key>> {
+        Ok(sqlx::query_scalar(
+            "SELECT key FROM documents \
+             WHERE project_id = ? \
+               AND normalized_key = ? \
+               AND deleted_at IS NULL \
+               AND (ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds) \
+             ORDER BY key \
+             LIMIT 1",
+        )
+        .bind(project)
+        .bind(normalized)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    async fn live_document_by_key(
+        &self,
+        project: &ProjectId,
+        key: &str,
+    ) -> Result<Option<Document>> {
+        let row = sqlx::query(
+            "SELECT * FROM documents \
+             WHERE project_id = ? \
+               AND key = ? \
+               AND deleted_at IS NULL \
+               AND (ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds) \
+             LIMIT 1",
+        )
+        .bind(project)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::deserialize_row(row)?)),
+            None => Ok(None),
+        }
     }
 
     fn deserialize_row(row: SqliteRow) -> Result<Document> {
@@ -40,13 +2043,8 @@ impl SqliteStorage {
         let deleted_at: Option<String> = row.try_get("deleted_at")?;
 
         let source_raw: String = row.try_get("source")?;
-        let source = match source_raw.as_str() {
-            "Agent" => SourceType::Agent,
-            "User" => SourceType::User,
-            "Import" => SourceType::Import,
-            "System" => SourceType::System,
-            other => bail!("unknown source type: {other}"),
-        };
+        let source = SourceType::from_stored(&source_raw);
+        let source_meta: Option<String> = row.try_get("source_meta")?;
 
         Ok(Document {
             id: DocumentId(row.try_get("id")?),
@@ -59,12 +2057,53 @@ impl SqliteStorage {
             created_at: parse_datetime(&created_at)?,
             updated_at: parse_datetime(&updated_at)?,
             source,
+            source_meta: source_meta
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?,
+            version: row.try_get::<i64, _>("version")? as u64,
+            ttl_seconds: row.try_get("ttl_seconds")?,
+            deleted_at: match deleted_at {
+                Some(ts) => Some(parse_datetime(&ts)?),
+                None => None,
+            },
+            visibility: row
+                .try_get::<Option<String>, _>("visibility")?
+                .map(|raw| Visibility::from_stored(&raw)),
+            metadata: metadata_from_row(&row)?,
+        })
+    }
+
+    /// Overlay a `document_versions` row onto the current document, yielding the document as it
+    /// was at that revision. `updated_at` becomes the time the revision was written.
+    fn apply_version_row(current: Document, row: SqliteRow) -> Result<Document> {
+        let tags_json: String = row.try_get("tags")?;
+        let created_at: String = row.try_get("created_at")?;
+        let deleted_at: Option<String> = row.try_get("deleted_at")?;
+        let source_raw: String = row.try_get("source")?;
+        let source_meta: Option<String> = row.try_get("source_meta")?;
+
+        Ok(Document {
+            key: row.try_get("key")?,
+            namespace: row.try_get("namespace")?,
+            title: row.try_get("title")?,
+            tags: serde_json::from_str(&tags_json)?,
+            body_markdown: row.try_get("body_markdown")?,
+            updated_at: parse_datetime(&created_at)?,
+            source: SourceType::from_stored(&source_raw),
+            source_meta: source_meta
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?,
             version: row.try_get::<i64, _>("version")? as u64,
             ttl_seconds: row.try_get("ttl_seconds")?,
             deleted_at: match deleted_at {
                 Some(ts) => Some(parse_datetime(&ts)?),
                 None => None,
             },
+            visibility: row
+                .try_get::<Option<String>, _>("visibility")?
+                .map(|raw| Visibility::from_stored(&raw)),
+            metadata: metadata_from_row(&row)?,
+            ..current
         })
     }
 }
@@ -72,81 +2111,38 @@ impl SqliteStorage {
 #[async_trait::async_trait]
 impl Storage for SqliteStorage {
     async fn put(&self, doc: Document) -> Result<Document> {
+        let doc = self.prepare(doc)?;
         let mut tx = self.pool.begin().await?;
-
-        sqlx::query("INSERT OR IGNORE INTO projects (id) VALUES (?)")
-            .bind(&doc.project)
-            .execute(&mut *tx)
-            .await?;
-
-        let tags = serde_json::to_string(&doc.tags)?;
-
-        sqlx::query(
-            "INSERT INTO documents (id, project_id, key, namespace, title, tags, body_markdown, created_at, updated_at, source, version, ttl_seconds, deleted_at) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
-             ON CONFLICT(id) DO UPDATE SET \
-                 project_id=excluded.project_id, \
-                 key=excluded.key, \
-                 namespace=excluded.namespace, \
-                 title=excluded.title, \
-                 tags=excluded.tags, \
-                 body_markdown=excluded.body_markdown, \
-                 created_at=excluded.created_at, \
-                 updated_at=excluded.updated_at, \
-                 source=excluded.source, \
-                 version=excluded.version, \
-                 ttl_seconds=excluded.ttl_seconds, \
-                 deleted_at=excluded.deleted_at",
-        )
-        .bind(&doc.id.0)
-        .bind(&doc.project)
-        .bind(&doc.key)
-        .bind(&doc.namespace)
-        .bind(&doc.title)
-        .bind(&tags)
-        .bind(&doc.body_markdown)
-        .bind(doc.created_at.to_rfc3339())
-        .bind(doc.updated_at.to_rfc3339())
-        .bind(format!("{:?}", doc.source))
-        .bind(doc.version as i64)
-        .bind(doc.ttl_seconds)
-        .bind(doc.deleted_at.map(|t| t.to_rfc3339()))
-        .execute(&mut *tx)
-        .await?;
-
-        sqlx::query(
-            "INSERT INTO document_versions (document_id, version, title, tags, body_markdown, namespace, key, source, ttl_seconds, deleted_at) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-        )
-        .bind(&doc.id.0)
-        .bind(doc.version as i64)
-        .bind(&doc.title)
-        .bind(&tags)
-        .bind(&doc.body_markdown)
-        .bind(&doc.namespace)
-        .bind(&doc.key)
-        .bind(format!("{:?}", doc.source))
-        .bind(doc.ttl_seconds)
-        .bind(doc.deleted_at.map(|t| t.to_rfc3339()))
-        .execute(&mut *tx)
-        .await?;
-
+        write_document(&mut tx, &doc).await?;
         tx.commit().await?;
-
+        self.changes.announce([&doc]);
         Ok(doc)
     }
 
     async fn get_by_key(&self, project: &ProjectId, key: &str) -> Result<Option<Document>> {
+        if !self.normalize_keys {
+            return self.live_document_by_key(project, key).await;
+        }
+
+        let normalized = normalize_key(key);
+        if let Some(doc) = self.live_document_by_key(project, &normalized).await? {
+            return Ok(Some(doc));
+        }
+        match self.find_equivalent_key(project, &normalized).await? {
+            Some(stored) => self.live_document_by_key(project, &stored).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn get_by_id(&self, id: &DocumentId) -> Result<Option<Document>> {
         let row = sqlx::query(
             "SELECT * FROM documents \
-             WHERE project_id = ? \
-               AND key = ? \
+             WHERE id = ? \
                AND deleted_at IS NULL \
-               AND (ttl_seconds IS NULL OR strftime('%s','now') < strftime('%s', created_at) + ttl_seconds) \
+               AND (ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds) \
              LIMIT 1",
         )
-        .bind(project)
-        .bind(key)
+        .bind(&id.0)
         .fetch_optional(&self.pool)
         .await?;
 
@@ -156,28 +2152,87 @@ impl Storage for SqliteStorage {
         }
     }
 
+    async fn history(&self, id: &DocumentId) -> Result<Vec<Document>> {
+        let Some(current) = sqlx::query("SELECT * FROM documents WHERE id = ?")
+            .bind(&id.0)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let current = Self::deserialize_row(current)?;
+
+        let rows = sqlx::query(
+            "SELECT * FROM document_versions WHERE document_id = ? ORDER BY version ASC",
+        )
+        .bind(&id.0)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| Self::apply_version_row(current.clone(), row))
+            .collect()
+    }
+
+    async fn version_as_of(&self, id: &DocumentId, at: DateTime<Utc>) -> Result<Option<Document>> {
+        let history = self.history(id).await?;
+        Ok(history
+            .into_iter()
+            .rev()
+            .find(|version| version.updated_at <= at)
+            .filter(|version| version.deleted_at.is_none()))
+    }
+
     async fn search(&self, query: SearchQuery) -> Result<Vec<SearchHit>> {
         let project = query.project.clone();
         let limit: i64 = query.limit.map(|l| l as i64).unwrap_or(-1);
+        let terms = SearchTerms::parse(&query.text);
+        let expression = terms.match_expression(query.code)?;
+        let normalize = |key: &Key| {
+            if self.normalize_keys {
+                normalize_key(key)
+            } else {
+                key.clone()
+            }
+        };
+        let key_prefix = query.key_prefix.as_ref().map(normalize);
+        let keys: Vec<Key> = query.keys.iter().map(normalize).collect();
 
+        let mut conn = self.bounded_connection().await?;
         let rows = sqlx::query(
             "SELECT d.*, bm25(documents_fts) AS bm25_score FROM documents_fts \
              JOIN documents d ON d.id = documents_fts.document_id \
              WHERE documents_fts MATCH ? AND (? IS NULL OR documents_fts.project_id = ?) AND d.deleted_at IS NULL \
-               AND (d.ttl_seconds IS NULL OR strftime('%s','now') < strftime('%s', d.created_at) + d.ttl_seconds) \
+               AND (d.ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', d.created_at) + d.ttl_seconds) \
+               AND NOT EXISTS (SELECT 1 FROM json_each(d.tags) t WHERE t.value IN (SELECT value FROM json_each(?))) \
+               AND (d.namespace IS NULL OR d.namespace NOT IN (SELECT value FROM json_each(?))) \
+               AND (? IS NULL OR substr(d.key, 1, length(?)) = ?) \
+               AND (json_array_length(?) = 0 OR d.key IN (SELECT value FROM json_each(?))) \
+               AND (json_array_length(?) = 0 OR COALESCE(d.visibility, 'project') IN (SELECT value FROM json_each(?))) \
              ORDER BY bm25_score ASC \
              LIMIT ?",
         )
-        .bind(&query.text)
+        .bind(expression)
         .bind(&project)
         .bind(&project)
+        .bind(serde_json::to_string(&query.exclude_tags)?)
+        .bind(serde_json::to_string(&query.exclude_namespaces)?)
+        .bind(&key_prefix)
+        .bind(&key_prefix)
+        .bind(&key_prefix)
+        .bind(serde_json::to_string(&keys)?)
+        .bind(serde_json::to_string(&keys)?)
+        .bind(serde_json::to_string(&query.visibility)?)
+        .bind(serde_json::to_string(&query.visibility)?)
         .bind(limit)
-        .fetch_all(&self.pool)
-        .await?;
+        .fetch_all(conn.get())
+        .await;
+        let rows = conn.finish(rows, "Search").await?;
 
-        let terms: Vec<String> = query
-            .text
-            .split_whitespace()
+        let terms: Vec<String> = terms
+            .include
+            .iter()
+            .flat_map(|term| term.split_whitespace())
             .map(|t| t.to_lowercase())
             .collect();
         let now = Utc::now();
@@ -186,13 +2241,20 @@ impl Storage for SqliteStorage {
         for row in rows {
             let bm25_score: f32 = row.try_get("bm25_score")?;
             let doc = Self::deserialize_row(row)?;
-            let text_score = -bm25_score;
-            let recency_score = recency_score(&doc, now);
-            let tag_score = tag_match_bonus(&doc.tags, &terms);
-            let total_score = text_score + recency_score + tag_score;
+            let breakdown = ScoreBreakdown {
+                bm25: -bm25_score,
+                recency: recency_score(&doc, now),
+                tag_bonus: tag_match_bonus(&doc.tags, &terms),
+                // Nothing can be pinned yet.
+                pin_boost: 0.0,
+            };
             hits.push(SearchHit {
                 document: doc,
-                score: total_score,
+                score: breakdown.bm25
+                    + breakdown.recency
+                    + breakdown.tag_bonus
+                    + breakdown.pin_boost,
+                breakdown,
             });
         }
 
@@ -214,12 +2276,578 @@ impl Storage for SqliteStorage {
 
         Ok(hits)
     }
+
+    async fn list_page(&self, project: &ProjectId, query: &ListQuery) -> Result<ListPage> {
+        // Read one extra row to learn whether another page follows.
+        let probe = ListQuery {
+            limit: query.limit.map(|limit| limit + 1),
+            ..query.clone()
+        };
+        let mut documents: Vec<Document> = self.list(project, &probe).try_collect().await?;
+        let next_cursor = match query.limit {
+            Some(limit) if documents.len() > limit => {
+                documents.truncate(limit);
+                documents.last().map(|last| query.cursor_after(last))
+            }
+            _ => None,
+        };
+        Ok(ListPage {
+            documents,
+            next_cursor,
+        })
+    }
+
+    async fn count(&self, project: &ProjectId, query: &ListQuery) -> Result<u64> {
+        let mut conn = self.bounded_connection().await?;
+        let row = self
+            .bind_list_filter(
+                sqlx::query(concat!(
+                    "SELECT COUNT(*) FROM documents ",
+                    list_filter_sql!()
+                )),
+                project,
+                query,
+            )
+            .fetch_one(conn.get())
+            .await;
+        let row = conn.finish(row, "Count").await?;
+        Ok(row.get::<i64, _>(0) as u64)
+    }
+
+    async fn delete_by_id(&self, id: &DocumentId, mode: DeleteMode) -> Result<Option<Document>> {
+        match self.get_by_id(id).await? {
+            Some(current) => self.delete_document(current, mode).await.map(Some),
+            None if mode == DeleteMode::Hard => match self.deleted_by_id(id).await? {
+                Some(tombstone) => self.erase_tombstone(tombstone).await,
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    async fn delete_by_key(
+        &self,
+        project: &ProjectId,
+        key: &str,
+        mode: DeleteMode,
+    ) -> Result<Option<Document>> {
+        match self.get_by_key(project, key).await? {
+            Some(current) => self.delete_document(current, mode).await.map(Some),
+            None if mode == DeleteMode::Hard => match self.deleted_by_key(project, key).await? {
+                Some(tombstone) => self.erase_tombstone(tombstone).await,
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn stream_project<'a>(&'a self, project: &'a ProjectId) -> BoxStream<'a, Result<Document>> {
+        sqlx::query(
+            "SELECT * FROM documents \
+             WHERE project_id = ? \
+               AND deleted_at IS NULL \
+               AND (ttl_seconds IS NULL OR CAST(strftime('%s','now') AS INTEGER) < strftime('%s', created_at) + ttl_seconds) \
+             ORDER BY updated_at DESC",
+        )
+        .bind(project)
+        .fetch(&self.pool)
+        .map(|row| Self::deserialize_row(row?))
+        .boxed()
+    }
+}
+
+/// Re-point redirects that ended at `from` to `to`, drop any redirect away from `to`, and
+/// with `alias` add `from -> to`.
+async fn write_redirect(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    project: &ProjectId,
+    from: &str,
+    to: &str,
+    alias: bool,
+) -> Result<()> {
+    sqlx::query("DELETE FROM key_redirects WHERE project_id = ? AND from_key = ?")
+        .bind(project)
+        .bind(to)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query("UPDATE key_redirects SET to_key = ? WHERE project_id = ? AND to_key = ?")
+        .bind(to)
+        .bind(project)
+        .bind(from)
+        .execute(&mut **tx)
+        .await?;
+    if alias {
+        sqlx::query(
+            "INSERT INTO key_redirects (project_id, from_key, to_key) VALUES (?, ?, ?) \
+             ON CONFLICT(project_id, from_key) DO UPDATE SET to_key = excluded.to_key",
+        )
+        .bind(project)
+        .bind(from)
+        .bind(to)
+        .execute(&mut **tx)
+        .await?;
+    } else {
+        sqlx::query("DELETE FROM key_redirects WHERE project_id = ? AND from_key = ?")
+            .bind(project)
+            .bind(from)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Write `doc` as the latest revision of its document inside `tx`. `doc` has been through
+/// `SqliteStorage::prepare`.
+async fn write_document(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    doc: &Document,
+) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO projects (id) VALUES (?)")
+        .bind(&doc.project)
+        .execute(&mut **tx)
+        .await?;
+
+    // A deleted or expired document gives up its key so the key can be reused;
+    // its revisions in document_versions still record the key it had.
+    sqlx::query(
+        "UPDATE documents SET key = NULL, normalized_key = NULL \
+         WHERE project_id = ? AND key = ? AND id != ? \
+           AND (deleted_at IS NOT NULL \
+                OR (ttl_seconds IS NOT NULL AND CAST(strftime('%s','now') AS INTEGER) >= strftime('%s', created_at) + ttl_seconds))",
+    )
+    .bind(&doc.project)
+    .bind(&doc.key)
+    .bind(&doc.id.0)
+    .execute(&mut **tx)
+    .await?;
+
+    // A live document owns its key outright; any redirect away from it is stale.
+    if doc.deleted_at.is_none() {
+        sqlx::query("DELETE FROM key_redirects WHERE project_id = ? AND from_key = ?")
+            .bind(&doc.project)
+            .bind(&doc.key)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    if let Some(namespace) = &doc.namespace {
+        sqlx::query("INSERT OR IGNORE INTO namespaces (project_id, name) VALUES (?, ?)")
+            .bind(&doc.project)
+            .bind(namespace)
+            .execute(&mut **tx)
+            .await?;
+    }
+
+    let tags = serde_json::to_string(&doc.tags)?;
+    let source_meta = doc
+        .source_meta
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+    let metadata = (!doc.metadata.is_empty())
+        .then(|| serde_json::to_string(&doc.metadata))
+        .transpose()?;
+
+    sqlx::query(
+        "INSERT INTO documents (id, project_id, key, namespace, title, tags, body_markdown, created_at, updated_at, source, source_meta, version, ttl_seconds, deleted_at, code_terms, visibility, metadata, normalized_key) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET \
+             project_id=excluded.project_id, \
+             key=excluded.key, \
+             namespace=excluded.namespace, \
+             title=excluded.title, \
+             tags=excluded.tags, \
+             body_markdown=excluded.body_markdown, \
+             created_at=excluded.created_at, \
+             updated_at=excluded.updated_at, \
+             source=excluded.source, \
+             source_meta=excluded.source_meta, \
+             version=excluded.version, \
+             ttl_seconds=excluded.ttl_seconds, \
+             deleted_at=excluded.deleted_at, \
+             code_terms=excluded.code_terms, \
+             visibility=excluded.visibility, \
+             metadata=excluded.metadata, \
+             normalized_key=excluded.normalized_key",
+    )
+    .bind(&doc.id.0)
+    .bind(&doc.project)
+    .bind(&doc.key)
+    .bind(&doc.namespace)
+    .bind(&doc.title)
+    .bind(&tags)
+    .bind(&doc.body_markdown)
+    .bind(doc.created_at.to_rfc3339())
+    .bind(doc.updated_at.to_rfc3339())
+    .bind(doc.source.as_str())
+    .bind(&source_meta)
+    .bind(doc.version as i64)
+    .bind(doc.ttl_seconds)
+    .bind(doc.deleted_at.map(|t| t.to_rfc3339()))
+    .bind(code_terms(&doc.body_markdown))
+    .bind(doc.visibility.map(|visibility| visibility.as_str()))
+    .bind(&metadata)
+    .bind(doc.key.as_deref().map(normalize_key))
+    .execute(&mut **tx)
+    .await?;
+    write_items(tx, &doc.id.0, &doc.body_markdown).await?;
+    write_links(tx, &doc.id.0, &doc.project, &doc.body_markdown).await?;
+
+    sqlx::query(
+        "INSERT INTO document_versions (document_id, version, title, tags, body_markdown, namespace, key, source, source_meta, created_at, ttl_seconds, deleted_at, visibility, metadata) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&doc.id.0)
+    .bind(doc.version as i64)
+    .bind(&doc.title)
+    .bind(&tags)
+    .bind(&doc.body_markdown)
+    .bind(&doc.namespace)
+    .bind(&doc.key)
+    .bind(doc.source.as_str())
+    .bind(&source_meta)
+    .bind(doc.updated_at.to_rfc3339())
+    .bind(doc.ttl_seconds)
+    .bind(doc.deleted_at.map(|t| t.to_rfc3339()))
+    .bind(doc.visibility.map(|visibility| visibility.as_str()))
+    .bind(&metadata)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// The `metadata` column of a `documents` or `document_versions` row; NULL is empty.
+fn metadata_from_row(row: &SqliteRow) -> Result<serde_json::Map<String, serde_json::Value>> {
+    Ok(row
+        .try_get::<Option<String>, _>("metadata")?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()?
+        .unwrap_or_default())
+}
+
+fn validate_namespace(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        fail!(Validation, "Namespace cannot be empty.");
+    }
+    if name.trim() != name || name.chars().any(char::is_control) {
+        fail!(
+            Validation,
+            "Namespace {name:?} has leading/trailing whitespace or control characters."
+        );
+    }
+    Ok(())
+}
+
+/// Ids are generated here, so only an imported one can be malformed. They end up in
+/// URLs and `ETag` headers, so they must be printable ASCII without spaces or quotes.
+fn validate_id(id: &str) -> Result<()> {
+    if id.is_empty() || !id.chars().all(|ch| ch.is_ascii_graphic() && ch != '"') {
+        fail!(
+            Validation,
+            "Document id {id:?} must be printable ASCII without spaces or quotes."
+        );
+    }
+    Ok(())
+}
+
+fn validate_project(id: &str) -> Result<()> {
+    if id.trim().is_empty() {
+        fail!(Validation, "Project name cannot be empty.");
+    }
+    if id.trim() != id || id.chars().any(char::is_control) {
+        fail!(
+            Validation,
+            "Project name {id:?} has leading/trailing whitespace or control characters."
+        );
+    }
+    Ok(())
+}
+
+fn deserialize_project(row: SqliteRow) -> Result<ProjectInfo> {
+    let settings: String = row.try_get("settings")?;
+    let created_at: String = row.try_get("created_at")?;
+    Ok(ProjectInfo {
+        id: row.try_get("id")?,
+        display_name: row.try_get("display_name")?,
+        description: row.try_get("description")?,
+        settings: serde_json::from_str(&settings)?,
+        created_at: parse_datetime(&created_at)?,
+    })
+}
+
+fn reminder_from_row(row: &SqliteRow) -> Result<Reminder> {
+    let optional = |column: &str| -> Result<Option<DateTime<Utc>>> {
+        row.try_get::<Option<String>, _>(column)?
+            .map(|raw| parse_datetime(&raw))
+            .transpose()
+    };
+    Ok(Reminder {
+        id: row.try_get("id")?,
+        project: row.try_get("project_id")?,
+        document_id: row.try_get("document_id")?,
+        key: row.try_get("key")?,
+        remind_at: parse_datetime(&row.try_get::<String, _>("remind_at")?)?,
+        note: row.try_get("note")?,
+        created_at: parse_datetime(&row.try_get::<String, _>("created_at")?)?,
+        notified_at: optional("notified_at")?,
+        done_at: optional("done_at")?,
+    })
+}
+
+fn attachment_from_row(row: &SqliteRow) -> Result<Attachment> {
+    Ok(Attachment {
+        document_id: row.try_get("document_id")?,
+        name: row.try_get("name")?,
+        media_type: row.try_get("media_type")?,
+        size: row.try_get::<i64, _>("size")? as u64,
+        sha256: row.try_get("sha256")?,
+        created_at: parse_datetime(&row.try_get::<String, _>("created_at")?)?,
+    })
+}
+
+fn template_from_row(row: &SqliteRow) -> Result<Template> {
+    Ok(Template {
+        project: row.try_get("project_id")?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        body_markdown: row.try_get("body_markdown")?,
+        created_at: parse_datetime(&row.try_get::<String, _>("created_at")?)?,
+        updated_at: parse_datetime(&row.try_get::<String, _>("updated_at")?)?,
+    })
 }
 
 fn parse_datetime(raw: &str) -> Result<DateTime<Utc>> {
     Ok(DateTime::parse_from_rfc3339(raw)?.with_timezone(&Utc))
 }
 
+/// The FTS5 `MATCH` expression `search` runs for `text` (see `SearchQuery`), searching
+/// fenced code identifiers when `code` is set. Fails if every term is excluded.
+pub fn match_expression(text: &str, code: bool) -> Result<String> {
+    SearchTerms::parse(text).match_expression(code)
+}
+
+/// Query text split into terms to match and terms to exclude.
+struct SearchTerms {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl SearchTerms {
+    /// Split on whitespace, keeping `"quoted phrases"` together; a leading `-` excludes.
+    /// NUL counts as whitespace: FTS5 would read it as the end of the expression.
+    fn parse(text: &str) -> Self {
+        let mut terms = Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+        };
+        let text = text.replace('\0', " ");
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let (negated, body) = match rest.strip_prefix('-') {
+                Some(body) if !body.is_empty() && !body.starts_with(char::is_whitespace) => {
+                    (true, body)
+                }
+                _ => (false, rest),
+            };
+            let (term, remainder) = match body.strip_prefix('"') {
+                Some(quoted) => match quoted.find('"') {
+                    Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                    None => (quoted, ""),
+                },
+                None => {
+                    let end = body.find(char::is_whitespace).unwrap_or(body.len());
+                    (&body[..end], &body[end..])
+                }
+            };
+            if !term.trim().is_empty() {
+                let target = if negated {
+                    &mut terms.exclude
+                } else {
+                    &mut terms.include
+                };
+                target.push(term.trim().to_string());
+            }
+            rest = remainder.trim_start();
+        }
+        terms
+    }
+
+    fn match_expression(&self, code: bool) -> Result<String> {
+        if self.include.is_empty() {
+            fail!(
+                Validation,
+                "Search needs at least one term that is not excluded with '-'."
+            );
+        }
+        Ok(if code {
+            self.code_expression()
+        } else {
+            self.fts_expression()
+        })
+    }
+
+    /// An FTS5 expression that matches every term literally, so punctuation such as `-` or
+    /// `:` in agent queries never trips FTS syntax errors.
+    fn fts_expression(&self) -> String {
+        format!(
+            "({}){}",
+            self.include
+                .iter()
+                .map(|term| fts_phrase(term))
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.exclusion()
+        )
+    }
+
+    /// Like `fts_expression`, but the terms must match in the `code` column, each either
+    /// as the whole identifier or as its words in a row (`HttpRequest`: `http request`).
+    fn code_expression(&self) -> String {
+        let include = self
+            .include
+            .iter()
+            .map(|term| {
+                let words = split_identifier(term);
+                if words.len() > 1 {
+                    format!("({} OR {})", fts_phrase(term), fts_phrase(&words.join(" ")))
+                } else {
+                    fts_phrase(term)
+                }
+            })
+            .collect::<Vec<_>>()
+            // FTS5 has no implicit AND after a parenthesized group, so spell it out.
+            .join(" AND ");
+        format!("code : ({include}){}", self.exclusion())
+    }
+
+    /// ` NOT ("a" OR "b")` for the excluded terms, or nothing. One `NOT` of an `OR` group
+    /// rather than a `NOT` per term, which FTS5 would nest past its depth limit of 256.
+    fn exclusion(&self) -> String {
+        if self.exclude.is_empty() {
+            return String::new();
+        }
+        let quoted: Vec<String> = self.exclude.iter().map(|term| fts_phrase(term)).collect();
+        format!(" NOT ({})", quoted.join(" OR "))
+    }
+}
+
+/// `term` as an FTS5 string, matched literally.
+fn fts_phrase(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// The revision `draft` makes of `previous`: identity, creation time, and any metadata
+/// the draft leaves unset carry over.
+fn next_revision(previous: Document, draft: Document) -> Document {
+    Document {
+        id: previous.id,
+        created_at: previous.created_at,
+        version: previous.version + 1,
+        namespace: draft.namespace.or(previous.namespace),
+        title: draft.title.or(previous.title),
+        visibility: draft.visibility.or(previous.visibility),
+        ttl_seconds: draft.ttl_seconds.or(previous.ttl_seconds),
+        metadata: merge_metadata(previous.metadata, draft.metadata.clone()),
+        ..draft
+    }
+}
+
+/// `draft`'s entries over `previous`'s; a `null` in `draft` removes the entry.
+fn merge_metadata(
+    mut previous: serde_json::Map<String, serde_json::Value>,
+    draft: serde_json::Map<String, serde_json::Value>,
+) -> serde_json::Map<String, serde_json::Value> {
+    for (name, value) in draft {
+        if value.is_null() {
+            previous.remove(&name);
+        } else {
+            previous.insert(name, value);
+        }
+    }
+    previous
+}
+
+fn check_version(current: Option<Document>, expected: u64) -> Result<Document> {
+    match current {
+        Some(current) if current.version == expected => Ok(current),
+        other => Err(VersionConflict {
+            expected,
+            actual: other.map(|doc| doc.version),
+        }
+        .into()),
+    }
+}
+
+/// Batches of at least this many documents are followed by `PRAGMA optimize`.
+const OPTIMIZE_AFTER_BATCH: usize = 1000;
+
+/// Index `documents` rows into `documents_fts`, with the same columns the `documents_ai`
+/// trigger writes. Append a `WHERE` clause to index only some of them.
+const INDEX_DOCUMENTS: &str = "INSERT INTO documents_fts(rowid, document_id, project_id, title, body, tags, namespace, code, metadata) \
+     SELECT rowid, id, project_id, coalesce(title, ''), body_markdown, \
+            coalesce((SELECT group_concat(value, ' ') FROM json_each(documents.tags)), ''), \
+            coalesce(namespace, ''), coalesce(code_terms, ''), \
+            coalesce((SELECT group_concat(value, ' ') FROM json_each(documents.metadata)), '') \
+     FROM documents";
+
+/// How many SQLite VM instructions run between deadline checks.
+const PROGRESS_INTERVAL_OPS: i32 = 1000;
+
+/// SQLite's result code for a statement stopped by its progress handler.
+const SQLITE_INTERRUPT: &str = "9";
+
+/// A connection from `SqliteStorage::bounded_connection`. Hand it back with `finish`; one
+/// dropped instead (its caller gave up mid-query) is closed rather than pooled, so its
+/// deadline can't interrupt whoever borrows the connection next.
+struct BoundedConnection {
+    conn: Option<PoolConnection<Sqlite>>,
+    limit: Option<Duration>,
+}
+
+impl BoundedConnection {
+    fn get(&mut self) -> &mut SqliteConnection {
+        self.conn.as_mut().expect("connection is held until finish")
+    }
+
+    /// Return the connection to the pool and turn an interrupted `what` into a timeout.
+    async fn finish<T>(mut self, result: sqlx::Result<T>, what: &str) -> Result<T> {
+        if self.limit.is_some() {
+            self.get().lock_handle().await?.remove_progress_handler();
+        }
+        self.conn.take();
+        match (result, self.limit) {
+            (Err(sqlx::Error::Database(db)), Some(limit))
+                if db.code().as_deref() == Some(SQLITE_INTERRUPT) =>
+            {
+                fail!(
+                    Timeout,
+                    "{what} ran past its {limit:?} time limit and was stopped."
+                )
+            }
+            (result, _) => Ok(result?),
+        }
+    }
+}
+
+impl Drop for BoundedConnection {
+    fn drop(&mut self) {
+        if let (Some(conn), Some(_)) = (self.conn.take(), self.limit) {
+            drop(conn.detach());
+        }
+    }
+}
+
+fn is_unique_violation(err: &ContextError) -> bool {
+    let ContextError::Storage(err) = err else {
+        return false;
+    };
+    matches!(
+        err.sqlx(),
+        Some(sqlx::Error::Database(db)) if db.is_unique_violation()
+    )
+}
+
 fn recency_score(doc: &Document, now: DateTime<Utc>) -> f32 {
     let age_secs = (now - doc.updated_at).num_seconds().max(0) as f32;
     1.0 / (1.0 + age_secs / 3600.0)
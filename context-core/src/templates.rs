@@ -0,0 +1,122 @@
+//! Reusable Markdown skeletons (`context template`, `context put --from-template`).
+//!
+//! A template body marks its blanks as `{{name}}` (spaces inside the braces are fine).
+//! Rendering replaces every blank with its value and fails when one has none. Braces
+//! around anything other than such a name are left as written.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::fail, ProjectId, Result};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Template {
+    pub project: ProjectId,
+    pub name: String,
+    pub description: Option<String>,
+    pub body_markdown: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Template {
+    /// The blanks the body asks for, in order of first use.
+    pub fn variables(&self) -> Vec<String> {
+        variables(&self.body_markdown)
+    }
+}
+
+/// Template names are what `--from-template` takes: letters, digits, `-`, and `_`.
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        fail!(
+            Validation,
+            "Template name {name:?} must be letters, digits, '-' or '_'."
+        );
+    }
+    Ok(())
+}
+
+/// The names of the `{{name}}` blanks in `body`, in order of first use.
+pub fn variables(body: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for piece in pieces(body) {
+        if let Piece::Blank(name) = piece {
+            if !names.iter().any(|seen| seen == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// `body` with every `{{name}}` replaced by `vars[name]`. Values are inserted as is and
+/// not scanned again. Fails naming every blank without a value.
+pub fn render(body: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut rendered = String::with_capacity(body.len());
+    let mut missing: Vec<&str> = Vec::new();
+    for piece in pieces(body) {
+        match piece {
+            Piece::Text(text) => rendered.push_str(text),
+            Piece::Blank(name) => match vars.get(name) {
+                Some(value) => rendered.push_str(value),
+                None if !missing.contains(&name) => missing.push(name),
+                None => {}
+            },
+        }
+    }
+    if !missing.is_empty() {
+        fail!(
+            Validation,
+            "No value for {}; pass --var {}=...",
+            missing.join(", "),
+            missing[0]
+        );
+    }
+    Ok(rendered)
+}
+
+enum Piece<'a> {
+    Text(&'a str),
+    Blank(&'a str),
+}
+
+/// Split `body` into literal text and `{{name}}` blanks.
+fn pieces(body: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    let mut rest = body;
+    while let Some(open) = rest.find("{{") {
+        let after = &rest[open + 2..];
+        let blank = after.find("}}").and_then(|close| {
+            let name = after[..close].trim();
+            is_variable(name).then_some((name, close))
+        });
+        match blank {
+            Some((name, close)) => {
+                pieces.push(Piece::Text(&rest[..open]));
+                pieces.push(Piece::Blank(name));
+                rest = &after[close + 2..];
+            }
+            None => {
+                // Not a blank: keep the first brace and look again from the second.
+                pieces.push(Piece::Text(&rest[..open + 1]));
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    pieces.push(Piece::Text(rest));
+    pieces
+}
+
+fn is_variable(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
@@ -0,0 +1,15 @@
+use std::sync::OnceLock;
+
+use tiktoken_rs::CoreBPE;
+
+/// Number of tokens `text` occupies in a prompt, using the `cl100k_base` encoding.
+///
+/// Counts from other model families differ by a few percent, which is close enough for
+/// budgeting.
+pub fn count_tokens(text: &str) -> usize {
+    static ENCODING: OnceLock<CoreBPE> = OnceLock::new();
+    ENCODING
+        .get_or_init(|| tiktoken_rs::cl100k_base().expect("bundled cl100k_base encoding loads"))
+        .encode_with_special_tokens(text)
+        .len()
+}
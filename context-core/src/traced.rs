@@ -0,0 +1,712 @@
+//! `TracedStorage` logs every `Storage` call of the store it wraps.
+//!
+//! Each call runs in a `storage` span carrying the operation, its project, how many rows
+//! it returned, and how long it took, and ends with a `debug` event holding the same
+//! fields. Around a `SqliteStorage` its own methods are traced too, so every call a front
+//! end makes through the wrapper is logged. `untraced` and `into_inner` are the ways past it.
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use tokio::sync::broadcast;
+use tracing::{field, Instrument};
+
+use crate::{
+    attachments::Attachment,
+    changes::DocumentChange,
+    idempotency::IdempotentWrite,
+    items::{DocumentItem, ItemQuery},
+    keys::{KeyCollision, KeyNormalizationReport, KeyRedirect},
+    links::LinkedDocument,
+    merge::MergedDocument,
+    policy::ExpiryChange,
+    reminders::{Reminder, ReminderQuery},
+    sqlite::SqliteStorage,
+    templates::Template,
+    DeleteMode, Document, DocumentId, Key, ListPage, ListQuery, NamespaceInfo, ProjectId,
+    ProjectInfo, ProjectStats, Result, SearchHit, SearchQuery, Storage, TagInfo, Visibility,
+};
+
+#[derive(Debug, Clone)]
+pub struct TracedStorage<S> {
+    inner: S,
+}
+
+impl<S> TracedStorage<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// The wrapped store, for helpers that take it directly such as `gc::collect`. Calls
+    /// through it are not traced.
+    pub fn untraced(&self) -> &S {
+        &self.inner
+    }
+}
+
+/// Run `call` in a span for `operation`, recording its duration and `rows(result)`.
+async fn traced<T>(
+    operation: &'static str,
+    project: Option<&str>,
+    rows: impl FnOnce(&T) -> usize,
+    call: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let span = tracing::info_span!(
+        "storage",
+        operation,
+        project,
+        rows = field::Empty,
+        duration_ms = field::Empty
+    );
+    let started = Instant::now();
+    let result = call.instrument(span.clone()).await;
+    let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+    span.record("duration_ms", duration_ms);
+    match &result {
+        Ok(value) => {
+            let rows = rows(value);
+            span.record("rows", rows);
+            tracing::debug!(
+                parent: &span,
+                operation,
+                project,
+                rows,
+                duration_ms,
+                "Storage call finished"
+            );
+        }
+        Err(err) => {
+            tracing::debug!(
+                parent: &span,
+                operation,
+                project,
+                duration_ms,
+                error = %err,
+                "Storage call failed"
+            );
+        }
+    }
+    result
+}
+
+fn one<T>(_: &T) -> usize {
+    1
+}
+
+fn found<T>(value: &Option<T>) -> usize {
+    usize::from(value.is_some())
+}
+
+#[async_trait::async_trait]
+impl<S: Storage> Storage for TracedStorage<S> {
+    async fn put(&self, doc: Document) -> Result<Document> {
+        let project = doc.project.clone();
+        traced("put", Some(&project), one, self.inner.put(doc)).await
+    }
+
+    async fn get_by_key(&self, project: &ProjectId, key: &str) -> Result<Option<Document>> {
+        traced(
+            "get_by_key",
+            Some(project),
+            found,
+            self.inner.get_by_key(project, key),
+        )
+        .await
+    }
+
+    async fn get_by_id(&self, id: &DocumentId) -> Result<Option<Document>> {
+        traced("get_by_id", None, found, self.inner.get_by_id(id)).await
+    }
+
+    async fn history(&self, id: &DocumentId) -> Result<Vec<Document>> {
+        traced("history", None, Vec::len, self.inner.history(id)).await
+    }
+
+    async fn version_as_of(&self, id: &DocumentId, at: DateTime<Utc>) -> Result<Option<Document>> {
+        traced(
+            "version_as_of",
+            None,
+            found,
+            self.inner.version_as_of(id, at),
+        )
+        .await
+    }
+
+    async fn search(&self, query: SearchQuery) -> Result<Vec<SearchHit>> {
+        let project = query.project.clone();
+        traced(
+            "search",
+            project.as_deref(),
+            Vec::len,
+            self.inner.search(query),
+        )
+        .await
+    }
+
+    async fn list_page(&self, project: &ProjectId, query: &ListQuery) -> Result<ListPage> {
+        traced(
+            "list_page",
+            Some(project),
+            |page: &ListPage| page.documents.len(),
+            self.inner.list_page(project, query),
+        )
+        .await
+    }
+
+    async fn count(&self, project: &ProjectId, query: &ListQuery) -> Result<u64> {
+        traced(
+            "count",
+            Some(project),
+            |count: &u64| *count as usize,
+            self.inner.count(project, query),
+        )
+        .await
+    }
+
+    async fn delete_by_id(&self, id: &DocumentId, mode: DeleteMode) -> Result<Option<Document>> {
+        traced(
+            "delete_by_id",
+            None,
+            found,
+            self.inner.delete_by_id(id, mode),
+        )
+        .await
+    }
+
+    async fn delete_by_key(
+        &self,
+        project: &ProjectId,
+        key: &str,
+        mode: DeleteMode,
+    ) -> Result<Option<Document>> {
+        traced(
+            "delete_by_key",
+            Some(project),
+            found,
+            self.inner.delete_by_key(project, key, mode),
+        )
+        .await
+    }
+
+    /// Streams are logged when opened; their rows arrive after the call returns.
+    fn stream_project<'a>(&'a self, project: &'a ProjectId) -> BoxStream<'a, Result<Document>> {
+        tracing::debug!(
+            operation = "stream_project",
+            project = %project,
+            "Storage stream opened"
+        );
+        self.inner.stream_project(project)
+    }
+}
+
+fn changed(changed: &bool) -> usize {
+    usize::from(*changed)
+}
+
+/// The `SqliteStorage` methods beyond `Storage`, traced the same way. Builders and the
+/// change feed pass straight through.
+impl TracedStorage<SqliteStorage> {
+    pub fn with_query_timeout(self, limit: Duration) -> Self {
+        Self::new(self.inner.with_query_timeout(limit))
+    }
+
+    pub fn with_deferred_indexing(self, enabled: bool) -> Self {
+        Self::new(self.inner.with_deferred_indexing(enabled))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DocumentChange> {
+        self.inner.subscribe()
+    }
+
+    pub async fn snapshot(&self) -> Result<Self> {
+        traced("snapshot", None, one, self.inner.snapshot())
+            .await
+            .map(Self::new)
+    }
+
+    pub async fn analyze(&self) -> Result<u64> {
+        traced("analyze", None, |_| 0, self.inner.analyze()).await
+    }
+
+    pub async fn store(&self, draft: Document) -> Result<Document> {
+        let project = draft.project.clone();
+        traced("store", Some(&project), one, self.inner.store(draft)).await
+    }
+
+    pub async fn store_expecting(
+        &self,
+        draft: Document,
+        expected_version: u64,
+    ) -> Result<Document> {
+        let project = draft.project.clone();
+        traced(
+            "store_expecting",
+            Some(&project),
+            one,
+            self.inner.store_expecting(draft, expected_version),
+        )
+        .await
+    }
+
+    pub async fn store_once(
+        &self,
+        draft: Document,
+        idempotency_key: &str,
+    ) -> Result<IdempotentWrite> {
+        let project = draft.project.clone();
+        traced(
+            "store_once",
+            Some(&project),
+            one,
+            self.inner.store_once(draft, idempotency_key),
+        )
+        .await
+    }
+
+    pub async fn store_all(&self, drafts: Vec<Document>) -> Result<Vec<Document>> {
+        let project = drafts.first().map(|draft| draft.project.clone());
+        traced(
+            "store_all",
+            project.as_deref(),
+            Vec::len,
+            self.inner.store_all(drafts),
+        )
+        .await
+    }
+
+    pub async fn merge_documents(
+        &self,
+        project: &ProjectId,
+        incoming: Vec<Document>,
+        dry_run: bool,
+    ) -> Result<Vec<MergedDocument>> {
+        traced(
+            "merge_documents",
+            Some(project),
+            Vec::len,
+            self.inner.merge_documents(project, incoming, dry_run),
+        )
+        .await
+    }
+
+    pub async fn update_tags(
+        &self,
+        document: Document,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<Document> {
+        let project = document.project.clone();
+        traced(
+            "update_tags",
+            Some(&project),
+            one,
+            self.inner.update_tags(document, add, remove),
+        )
+        .await
+    }
+
+    pub async fn rename_key(&self, document: Document, to: &str, alias: bool) -> Result<Document> {
+        let project = document.project.clone();
+        traced(
+            "rename_key",
+            Some(&project),
+            one,
+            self.inner.rename_key(document, to, alias),
+        )
+        .await
+    }
+
+    pub async fn resolve_key(
+        &self,
+        project: &ProjectId,
+        key: &str,
+    ) -> Result<Option<(Document, Option<Key>)>> {
+        traced(
+            "resolve_key",
+            Some(project),
+            found,
+            self.inner.resolve_key(project, key),
+        )
+        .await
+    }
+
+    pub async fn redirect_for(&self, project: &ProjectId, key: &str) -> Result<Option<Key>> {
+        traced(
+            "redirect_for",
+            Some(project),
+            found,
+            self.inner.redirect_for(project, key),
+        )
+        .await
+    }
+
+    pub async fn redirects(&self, project: &ProjectId) -> Result<Vec<KeyRedirect>> {
+        traced(
+            "redirects",
+            Some(project),
+            Vec::len,
+            self.inner.redirects(project),
+        )
+        .await
+    }
+
+    pub async fn record_redirect(&self, project: &ProjectId, from: &str, to: &str) -> Result<()> {
+        traced(
+            "record_redirect",
+            Some(project),
+            one,
+            self.inner.record_redirect(project, from, to),
+        )
+        .await
+    }
+
+    pub async fn key_collisions(&self, project: &ProjectId) -> Result<Vec<KeyCollision>> {
+        traced(
+            "key_collisions",
+            Some(project),
+            Vec::len,
+            self.inner.key_collisions(project),
+        )
+        .await
+    }
+
+    pub async fn normalize_existing_keys(
+        &self,
+        project: &ProjectId,
+        dry_run: bool,
+    ) -> Result<KeyNormalizationReport> {
+        traced(
+            "normalize_existing_keys",
+            Some(project),
+            |report: &KeyNormalizationReport| report.renamed.len(),
+            self.inner.normalize_existing_keys(project, dry_run),
+        )
+        .await
+    }
+
+    pub async fn delete_expecting(
+        &self,
+        project: &ProjectId,
+        key: &str,
+        expected_version: Option<u64>,
+    ) -> Result<Option<Document>> {
+        traced(
+            "delete_expecting",
+            Some(project),
+            found,
+            self.inner.delete_expecting(project, key, expected_version),
+        )
+        .await
+    }
+
+    pub async fn deleted_by_key(&self, project: &ProjectId, key: &str) -> Result<Option<Document>> {
+        traced(
+            "deleted_by_key",
+            Some(project),
+            found,
+            self.inner.deleted_by_key(project, key),
+        )
+        .await
+    }
+
+    pub async fn restore(&self, id: &DocumentId) -> Result<Option<Document>> {
+        traced("restore", None, found, self.inner.restore(id)).await
+    }
+
+    pub async fn set_expiry(
+        &self,
+        id: &DocumentId,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<Document>> {
+        traced(
+            "set_expiry",
+            None,
+            found,
+            self.inner.set_expiry(id, expires_at),
+        )
+        .await
+    }
+
+    pub async fn apply_expiry_policy(
+        &self,
+        project: &ProjectId,
+        dry_run: bool,
+    ) -> Result<Vec<ExpiryChange>> {
+        traced(
+            "apply_expiry_policy",
+            Some(project),
+            Vec::len,
+            self.inner.apply_expiry_policy(project, dry_run),
+        )
+        .await
+    }
+
+    /// Like `stream_project`, logged when opened.
+    pub fn list<'a>(
+        &'a self,
+        project: &'a ProjectId,
+        query: &ListQuery,
+    ) -> BoxStream<'a, Result<Document>> {
+        tracing::debug!(
+            operation = "list",
+            project = %project,
+            "Storage stream opened"
+        );
+        self.inner.list(project, query)
+    }
+
+    pub async fn tag_counts(&self, project: &ProjectId) -> Result<Vec<TagInfo>> {
+        traced(
+            "tag_counts",
+            Some(project),
+            Vec::len,
+            self.inner.tag_counts(project),
+        )
+        .await
+    }
+
+    pub async fn namespaces(&self, project: &ProjectId) -> Result<Vec<NamespaceInfo>> {
+        traced(
+            "namespaces",
+            Some(project),
+            Vec::len,
+            self.inner.namespaces(project),
+        )
+        .await
+    }
+
+    pub async fn create_namespace(&self, project: &ProjectId, name: &str) -> Result<bool> {
+        traced(
+            "create_namespace",
+            Some(project),
+            changed,
+            self.inner.create_namespace(project, name),
+        )
+        .await
+    }
+
+    pub async fn rename_namespace(&self, project: &ProjectId, from: &str, to: &str) -> Result<u64> {
+        traced(
+            "rename_namespace",
+            Some(project),
+            |moved: &u64| *moved as usize,
+            self.inner.rename_namespace(project, from, to),
+        )
+        .await
+    }
+
+    pub async fn remove_namespace(
+        &self,
+        project: &ProjectId,
+        name: &str,
+        force: bool,
+    ) -> Result<u64> {
+        traced(
+            "remove_namespace",
+            Some(project),
+            |moved: &u64| *moved as usize,
+            self.inner.remove_namespace(project, name, force),
+        )
+        .await
+    }
+
+    pub async fn projects(&self) -> Result<Vec<ProjectInfo>> {
+        traced("projects", None, Vec::len, self.inner.projects()).await
+    }
+
+    pub async fn project(&self, id: &ProjectId) -> Result<Option<ProjectInfo>> {
+        traced("project", Some(id), found, self.inner.project(id)).await
+    }
+
+    pub async fn project_stats(&self, visibility: &[Visibility]) -> Result<Vec<ProjectStats>> {
+        traced(
+            "project_stats",
+            None,
+            Vec::len,
+            self.inner.project_stats(visibility),
+        )
+        .await
+    }
+
+    pub async fn register_project(&self, id: &ProjectId) -> Result<bool> {
+        traced(
+            "register_project",
+            Some(id),
+            changed,
+            self.inner.register_project(id),
+        )
+        .await
+    }
+
+    pub async fn update_project(&self, info: &ProjectInfo) -> Result<ProjectInfo> {
+        traced(
+            "update_project",
+            Some(&info.id),
+            one,
+            self.inner.update_project(info),
+        )
+        .await
+    }
+
+    pub async fn templates(&self, project: &ProjectId) -> Result<Vec<Template>> {
+        traced(
+            "templates",
+            Some(project),
+            Vec::len,
+            self.inner.templates(project),
+        )
+        .await
+    }
+
+    pub async fn template(&self, project: &ProjectId, name: &str) -> Result<Option<Template>> {
+        traced(
+            "template",
+            Some(project),
+            found,
+            self.inner.template(project, name),
+        )
+        .await
+    }
+
+    pub async fn put_template(
+        &self,
+        project: &ProjectId,
+        name: &str,
+        description: Option<String>,
+        body_markdown: &str,
+    ) -> Result<Template> {
+        traced(
+            "put_template",
+            Some(project),
+            one,
+            self.inner
+                .put_template(project, name, description, body_markdown),
+        )
+        .await
+    }
+
+    pub async fn delete_template(&self, project: &ProjectId, name: &str) -> Result<bool> {
+        traced(
+            "delete_template",
+            Some(project),
+            changed,
+            self.inner.delete_template(project, name),
+        )
+        .await
+    }
+
+    pub async fn attachments(&self, document_id: &DocumentId) -> Result<Vec<Attachment>> {
+        traced(
+            "attachments",
+            None,
+            Vec::len,
+            self.inner.attachments(document_id),
+        )
+        .await
+    }
+
+    pub async fn all_attachments(&self) -> Result<Vec<Attachment>> {
+        traced(
+            "all_attachments",
+            None,
+            Vec::len,
+            self.inner.all_attachments(),
+        )
+        .await
+    }
+
+    pub async fn attachment(
+        &self,
+        document_id: &DocumentId,
+        name: &str,
+    ) -> Result<Option<(Attachment, Vec<u8>)>> {
+        traced(
+            "attachment",
+            None,
+            found,
+            self.inner.attachment(document_id, name),
+        )
+        .await
+    }
+
+    pub async fn put_attachment(
+        &self,
+        document: &Document,
+        name: &str,
+        media_type: Option<&str>,
+        data: &[u8],
+    ) -> Result<Attachment> {
+        traced(
+            "put_attachment",
+            Some(&document.project),
+            one,
+            self.inner.put_attachment(document, name, media_type, data),
+        )
+        .await
+    }
+
+    pub async fn links(&self, id: &DocumentId) -> Result<Vec<LinkedDocument>> {
+        traced("links", None, Vec::len, self.inner.links(id)).await
+    }
+
+    pub async fn backlinks(&self, project: &ProjectId, key: &str) -> Result<Vec<LinkedDocument>> {
+        traced(
+            "backlinks",
+            Some(project),
+            Vec::len,
+            self.inner.backlinks(project, key),
+        )
+        .await
+    }
+
+    pub async fn items(&self, query: &ItemQuery) -> Result<Vec<DocumentItem>> {
+        traced("items", None, Vec::len, self.inner.items(query)).await
+    }
+
+    pub async fn reminders(&self, query: &ReminderQuery) -> Result<Vec<Reminder>> {
+        traced("reminders", None, Vec::len, self.inner.reminders(query)).await
+    }
+
+    pub async fn add_reminder(
+        &self,
+        document_id: &DocumentId,
+        remind_at: DateTime<Utc>,
+        note: Option<String>,
+    ) -> Result<Reminder> {
+        traced(
+            "add_reminder",
+            None,
+            one,
+            self.inner.add_reminder(document_id, remind_at, note),
+        )
+        .await
+    }
+
+    pub async fn complete_reminder(&self, id: i64) -> Result<bool> {
+        traced(
+            "complete_reminder",
+            None,
+            changed,
+            self.inner.complete_reminder(id),
+        )
+        .await
+    }
+
+    pub async fn mark_reminder_notified(&self, id: i64, at: DateTime<Utc>) -> Result<()> {
+        traced(
+            "mark_reminder_notified",
+            None,
+            one,
+            self.inner.mark_reminder_notified(id, at),
+        )
+        .await
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use context_core::{
+    attachments::{media_type_for, sha256_hex},
+    sqlite::SqliteStorage,
+    Document,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn draft(id: &str, body: &str) -> Document {
+    document(id, "design", body)
+}
+
+#[test]
+fn media_types_are_guessed_from_the_extension() {
+    assert_eq!(media_type_for("diagram.PNG"), "image/png");
+    assert_eq!(media_type_for("notes.md"), "text/markdown");
+    assert_eq!(media_type_for("core"), "application/octet-stream");
+}
+
+#[tokio::test]
+async fn attachments_outlive_revisions_and_go_with_a_purged_document() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let doc = storage.store(draft("d1", "The design.")).await?;
+
+    let png = [0x89, b'P', b'N', b'G', 0, 1, 2];
+    let first = storage
+        .put_attachment(&doc, "diagram.png", None, &png)
+        .await?;
+    assert_eq!(
+        (first.media_type.as_str(), first.size),
+        ("image/png", png.len() as u64)
+    );
+    assert_eq!(first.sha256, sha256_hex(&png));
+    storage
+        .put_attachment(&doc, "trace.bin", Some("application/x-trace"), b"v1")
+        .await?;
+    let replaced = storage
+        .put_attachment(&doc, "trace.bin", Some("application/x-trace"), b"v2!")
+        .await?;
+    assert_eq!(replaced.size, 3);
+    assert!(storage
+        .put_attachment(&doc, "../escape", None, b"x")
+        .await
+        .is_err());
+
+    let revised = storage.store(draft("d2", "The design, revised.")).await?;
+    assert_eq!((revised.id.0.as_str(), revised.version), ("d1", 2));
+    let names: Vec<String> = storage
+        .attachments(&revised.id)
+        .await?
+        .into_iter()
+        .map(|attachment| attachment.name)
+        .collect();
+    assert_eq!(names, ["diagram.png", "trace.bin"]);
+    let (_, data) = storage
+        .attachment(&revised.id, "diagram.png")
+        .await?
+        .expect("attachment exists");
+    assert_eq!(data, png);
+
+    storage
+        .delete_expecting(&"demo".to_string(), "design", None)
+        .await?;
+    assert_eq!(storage.purge_deleted(&"demo".to_string()).await?, 1);
+    assert!(storage.all_attachments().await?.is_empty());
+    Ok(())
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use context_core::{blame::blame, Document, SourceType};
+
+use common::document;
+
+fn revision(version: u64, source: SourceType, body: &str) -> Document {
+    let created = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    Document {
+        created_at: created,
+        updated_at: created + Duration::hours(version as i64),
+        source,
+        version,
+        ..document("doc-1", "runbook", body)
+    }
+}
+
+#[test]
+fn blame_credits_each_line_to_the_revision_that_last_changed_it() {
+    let revisions = vec![
+        revision(1, SourceType::User, "# Runbook\nrestart api\ncheck logs"),
+        revision(
+            2,
+            SourceType::Agent,
+            "# Runbook\nrestart api and worker\ncheck logs",
+        ),
+        revision(
+            3,
+            SourceType::User,
+            "# Runbook\nrestart api and worker\ncheck logs\npage on-call",
+        ),
+    ];
+
+    let lines = blame(&revisions);
+    let attribution: Vec<(usize, u64, &str)> = lines
+        .iter()
+        .map(|l| (l.line, l.version, l.text.as_str()))
+        .collect();
+
+    assert_eq!(
+        attribution,
+        vec![
+            (1, 1, "# Runbook"),
+            (2, 2, "restart api and worker"),
+            (3, 1, "check logs"),
+            (4, 3, "page on-call"),
+        ]
+    );
+    assert!(matches!(lines[1].source, SourceType::Agent));
+    assert_eq!(lines[3].changed_at, revisions[2].updated_at);
+}
+
+#[test]
+fn blame_of_empty_history_is_empty() {
+    assert!(blame(&[]).is_empty());
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use context_core::{
+    calendar::{calendar_events, to_ics, EventKind},
+    reminders::Reminder,
+    sqlite::SqliteStorage,
+    Document, Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn doc(key: &str, ttl_seconds: Option<i64>) -> Document {
+    let created = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+    Document {
+        created_at: created,
+        updated_at: created,
+        ttl_seconds,
+        ..document(&format!("demo-{key}"), key, "body")
+    }
+}
+
+fn reminder(id: i64, note: Option<&str>, done: bool) -> Reminder {
+    let at = Utc.with_ymd_and_hms(2024, 7, 1, 9, 30, 0).unwrap();
+    Reminder {
+        id,
+        project: "demo".to_string(),
+        document_id: "demo-rollout".to_string(),
+        key: Some("rollout".to_string()),
+        remind_at: at,
+        note: note.map(str::to_string),
+        created_at: at,
+        notified_at: None,
+        done_at: done.then_some(at),
+    }
+}
+
+#[test]
+fn reminders_and_expirations_render_as_events() {
+    let events = calendar_events(
+        &[
+            reminder(1, Some("check rates, then; ship"), false),
+            reminder(2, None, true),
+        ],
+        &[doc("scratch", Some(86_400)), doc("keep", None)],
+    );
+    let kinds: Vec<EventKind> = events.iter().map(|event| event.kind).collect();
+    assert_eq!(kinds, vec![EventKind::Expiry, EventKind::Reminder]);
+
+    let stamp = Utc.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+    let ics = to_ics(&events, stamp);
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+    assert!(ics.contains(
+        "BEGIN:VEVENT\r\nUID:expiry-demo-scratch@context\r\nDTSTAMP:20240615T120000Z\r\n\
+         DTSTART:20240602T000000Z\r\nSUMMARY:Expires: demo/scratch\r\nCATEGORIES:EXPIRY\r\n"
+    ));
+    assert!(ics.contains("UID:reminder-1@context\r\n"));
+    assert!(ics.contains("DTSTART:20240701T093000Z\r\n"));
+    assert!(ics.contains("DESCRIPTION:check rates\\, then\\; ship\r\n"));
+    assert!(!ics.contains("reminder-2@"), "done reminders are left out");
+}
+
+#[test]
+fn long_lines_are_folded() {
+    let mut long = reminder(1, None, false);
+    long.note = Some("é".repeat(60));
+    let ics = to_ics(&calendar_events(&[long], &[]), Utc::now());
+    for line in ics.split("\r\n") {
+        assert!(line.len() <= 75, "line too long: {line:?}");
+    }
+    let unfolded = ics.replace("\r\n ", "");
+    assert!(unfolded.contains(&format!("DESCRIPTION:{}\r\n", "é".repeat(60))));
+}
+
+#[tokio::test]
+async fn expiring_documents_skip_expired_and_permanent_ones() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let now = Utc::now();
+    let fresh = |key: &str, ttl: Option<i64>| Document {
+        created_at: now,
+        updated_at: now,
+        ..doc(key, ttl)
+    };
+    storage.put(fresh("later", Some(7 * 86_400))).await?;
+    storage.put(fresh("sooner", Some(3_600))).await?;
+    storage.put(fresh("forever", None)).await?;
+    storage
+        .put(Document {
+            created_at: now - Duration::days(2),
+            ..fresh("gone", Some(60))
+        })
+        .await?;
+
+    let keys: Vec<Option<String>> = storage
+        .expiring_documents(None)
+        .await?
+        .into_iter()
+        .map(|doc| doc.key)
+        .collect();
+    assert_eq!(
+        keys,
+        vec![Some("sooner".to_string()), Some("later".to_string())]
+    );
+    assert!(storage
+        .expiring_documents(Some(&"other".to_string()))
+        .await?
+        .is_empty());
+    Ok(())
+}
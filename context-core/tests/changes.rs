@@ -0,0 +1,62 @@
+mod common;
+
+use context_core::{
+    changes::{ChangeKind, DocumentChange},
+    sqlite::SqliteStorage,
+    DeleteMode, DocumentId, Storage, Visibility,
+};
+use tempfile::tempdir;
+use tokio::sync::broadcast::{error::TryRecvError, Receiver};
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn kinds(changes: &mut Receiver<DocumentChange>) -> Vec<(ChangeKind, String, u64)> {
+    let mut seen = Vec::new();
+    loop {
+        match changes.try_recv() {
+            Ok(change) => seen.push((change.kind, change.key.unwrap_or_default(), change.version)),
+            Err(TryRecvError::Empty) => return seen,
+            Err(err) => panic!("feed failed: {err}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn committed_writes_are_announced_to_every_clone() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let mut changes = storage.subscribe();
+    let writer = storage.clone();
+
+    writer.store(document("d1", "plan", "v1")).await?;
+    writer.store(document("d2", "plan", "v2")).await?;
+    writer
+        .delete_by_id(&DocumentId("d1".to_string()), DeleteMode::Soft)
+        .await?;
+    writer
+        .store_all(vec![document("d3", "a", "a"), document("d4", "b", "b")])
+        .await?;
+    assert_eq!(
+        kinds(&mut changes),
+        vec![
+            (ChangeKind::Created, "plan".to_string(), 1),
+            (ChangeKind::Updated, "plan".to_string(), 2),
+            (ChangeKind::Deleted, "plan".to_string(), 3),
+            (ChangeKind::Created, "a".to_string(), 1),
+            (ChangeKind::Created, "b".to_string(), 1),
+        ]
+    );
+
+    // A write that rolls back announces nothing: d3 already has a version 1.
+    assert!(writer.put(document("d3", "a", "again")).await.is_err());
+    assert!(kinds(&mut changes).is_empty());
+
+    let mut private = document("d6", "mine", "secret");
+    private.visibility = Some(Visibility::Private);
+    writer.store(private).await?;
+    let change = changes.try_recv()?;
+    assert_eq!(change.visibility, Visibility::Private);
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use context_core::code::{code_terms, fenced_blocks, split_identifier};
+
+#[test]
+fn fenced_blocks_are_extracted_in_order() {
+    let markdown = "intro `inline` text\n\
+                    ```rust\nfn first() {}\n```\n\
+                    between\n\
+                    ~~~\nsecond_block\n```not a close\n~~~\n\
+                    ````\nunclosed";
+    assert_eq!(
+        fenced_blocks(markdown),
+        vec![
+            "fn first() {}".to_string(),
+            "second_block\n```not a close".to_string(),
+            "unclosed".to_string(),
+        ]
+    );
+    assert!(fenced_blocks("no code here").is_empty());
+}
+
+#[test]
+fn identifiers_split_at_case_changes_and_underscores() {
+    assert_eq!(
+        split_identifier("parseHttpRequest"),
+        ["parse", "http", "request"]
+    );
+    assert_eq!(split_identifier("snake_case_fn"), ["snake", "case", "fn"]);
+    assert_eq!(split_identifier("HTTPServer_v2"), ["http", "server", "v2"]);
+    assert_eq!(split_identifier("utf8Decode"), ["utf8", "decode"]);
+    assert_eq!(split_identifier("plain"), ["plain"]);
+}
+
+#[test]
+fn code_terms_index_identifiers_whole_and_split() {
+    let markdown =
+        "Call it like this:\n```\nlet req = parseHttpRequest(raw, 42);\n```\nprose_words stay out";
+    assert_eq!(
+        code_terms(markdown),
+        "let req parseHttpRequest parse http request raw"
+    );
+    assert_eq!(code_terms("no fences"), "");
+}
@@ -0,0 +1,28 @@
+//! Documents shared by the integration tests.
+
+use chrono::Utc;
+use context_core::{Document, DocumentId, SourceType};
+
+/// A first-version user document in project `demo`, created now. Tests override the
+/// fields they care about with struct update syntax.
+pub fn document(id: &str, key: &str, body: &str) -> Document {
+    let now = Utc::now();
+    Document {
+        id: DocumentId(id.to_string()),
+        project: "demo".to_string(),
+        key: Some(key.to_string()),
+        namespace: None,
+        title: None,
+        tags: Vec::new(),
+        body_markdown: body.to_string(),
+        created_at: now,
+        updated_at: now,
+        source: SourceType::User,
+        source_meta: None,
+        version: 1,
+        ttl_seconds: None,
+        deleted_at: None,
+        visibility: None,
+        metadata: Default::default(),
+    }
+}
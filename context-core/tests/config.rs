@@ -0,0 +1,57 @@
+mod common;
+
+use context_core::{
+    config::{configure_storage, StoreConfig},
+    sqlite::SqliteStorage,
+    ContextError, Document,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn draft(key: &str, tags: &[&str]) -> Document {
+    Document {
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..document(
+            &key.to_lowercase(),
+            key,
+            &format!("Notes filed under {key}."),
+        )
+    }
+}
+
+#[tokio::test]
+async fn store_settings_from_config_json_apply_to_the_storage() -> TestResult<()> {
+    let temp = tempdir()?;
+    assert_eq!(StoreConfig::load(temp.path())?, StoreConfig::default());
+
+    std::fs::write(
+        temp.path().join("config.json"),
+        r#"{"current": "demo", "keys": {"normalize": true}, "expiry": [{"tag": "scratch", "after": "1d"}]}"#,
+    )?;
+    let config = StoreConfig::load(temp.path())?;
+    assert!(config.keys.normalize);
+
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let storage = configure_storage(storage, config)?;
+    let stored = storage.store(draft("Notes/Deploy", &["scratch"])).await?;
+    assert_eq!(stored.key.as_deref(), Some("notes/deploy"));
+    assert_eq!(stored.ttl_seconds, Some(86_400));
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_expiry_rules_are_refused() -> TestResult<()> {
+    let temp = tempdir()?;
+    std::fs::write(
+        temp.path().join("config.json"),
+        r#"{"expiry": [{"tag": "scratch", "after": "soon"}]}"#,
+    )?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let err = configure_storage(storage, StoreConfig::load(temp.path())?).unwrap_err();
+    assert!(matches!(err, ContextError::Validation(_)), "{err}");
+    assert!(err.to_string().contains("config.json"), "{err}");
+    Ok(())
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use context_core::{dedup::find_duplicates, sqlite::SqliteStorage, Document, SourceType, Storage};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn doc(id: &str, body: &str, age_hours: i64) -> Document {
+    let at = Utc::now() - Duration::hours(age_hours);
+    Document {
+        created_at: at,
+        updated_at: at,
+        source: SourceType::Agent,
+        ..document(id, &format!("notes/{id}"), body)
+    }
+}
+
+#[test]
+fn exact_and_near_duplicates_are_grouped_newest_first() {
+    let documents = vec![
+        doc("a", "run make release then tag the build", 3),
+        doc("b", "run  make release\nthen tag the build", 1),
+        doc("c", "run make release then tag the build and push", 2),
+        doc("d", "completely unrelated note about lunch", 0),
+    ];
+
+    let groups = find_duplicates(&documents, 0.6);
+    assert_eq!(groups.len(), 1);
+    let group = &groups[0];
+    assert!(!group.exact);
+    let ids: Vec<&str> = group.members.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["b", "c", "a"]);
+    assert_eq!(
+        group.members[2].similarity, 1.0,
+        "whitespace-only difference"
+    );
+    assert!(group.members[1].similarity < 1.0);
+
+    let exact_only = find_duplicates(&documents, 1.0);
+    assert_eq!(exact_only.len(), 1);
+    assert!(exact_only[0].exact);
+    assert_eq!(exact_only[0].members.len(), 2);
+}
+
+#[tokio::test]
+async fn redirects_stay_one_hop_long() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let project = "demo".to_string();
+
+    storage.record_redirect(&project, "old", "mid").await?;
+    storage.record_redirect(&project, "mid", "new").await?;
+    assert_eq!(
+        storage.redirect_for(&project, "old").await?.as_deref(),
+        Some("new")
+    );
+    assert_eq!(storage.redirects(&project).await?.len(), 2);
+
+    // Redirecting back onto a retired key revives it.
+    storage.record_redirect(&project, "new", "old").await?;
+    assert_eq!(storage.redirect_for(&project, "old").await?, None);
+    assert_eq!(
+        storage.redirect_for(&project, "mid").await?.as_deref(),
+        Some("old")
+    );
+
+    assert!(storage.record_redirect(&project, "x", "x").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn writing_a_live_document_at_a_redirected_key_drops_the_redirect() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let project = "demo".to_string();
+
+    storage
+        .record_redirect(&project, "notes/a", "notes/b")
+        .await?;
+    storage.put(doc("a", "fresh content", 0)).await?;
+
+    assert_eq!(storage.redirect_for(&project, "notes/a").await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn reads_follow_a_redirect_that_writes_ignore() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let project = "demo".to_string();
+
+    storage.put(doc("b", "merged content", 0)).await?;
+    storage
+        .record_redirect(&project, "notes/a", "notes/b")
+        .await?;
+
+    let (document, target) = storage
+        .resolve_key(&project, "notes/a")
+        .await?
+        .expect("redirect followed");
+    assert_eq!(
+        (document.id.0.as_str(), target.as_deref()),
+        ("b", Some("notes/b"))
+    );
+    assert_eq!(
+        storage
+            .resolve_key(&project, "notes/b")
+            .await?
+            .map(|(_, target)| target),
+        Some(None)
+    );
+    assert!(storage.get_by_key(&project, "notes/a").await?.is_none());
+    assert!(storage.resolve_key(&project, "notes/c").await?.is_none());
+    Ok(())
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use context_core::{
+    diff::{diff_lines, diff_revisions, LineChange},
+    Document,
+};
+
+use common::document;
+
+fn revision(version: u64, body: &str) -> Document {
+    let at = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    Document {
+        created_at: at,
+        updated_at: at,
+        version,
+        ..document("doc-1", "runbook", body)
+    }
+}
+
+#[test]
+fn revisions_diff_as_unified_hunks() {
+    let old = revision(2, "# Runbook\nrestart api\ncheck logs\n");
+    let new = revision(
+        4,
+        "# Runbook\nrestart api and worker\ncheck logs\npage on-call\n",
+    );
+
+    let diff = diff_revisions(&old, &new, 3);
+    assert_eq!((diff.from, diff.to), (2, 4));
+    assert_eq!(diff.hunks.len(), 1);
+    let hunk = &diff.hunks[0];
+    assert_eq!(hunk.header(), "@@ -1,3 +1,4 @@");
+    let changes: Vec<LineChange> = hunk.lines.iter().map(|line| line.change).collect();
+    assert_eq!(
+        changes,
+        [
+            LineChange::Context,
+            LineChange::Removed,
+            LineChange::Added,
+            LineChange::Context,
+            LineChange::Added,
+        ]
+    );
+    assert_eq!(hunk.lines[2].text, "restart api and worker");
+
+    assert_eq!(
+        diff.unified(),
+        "--- runbook v2\n+++ runbook v4\n@@ -1,3 +1,4 @@\n # Runbook\n-restart api\n\
+         +restart api and worker\n check logs\n+page on-call\n"
+    );
+}
+
+#[test]
+fn distant_changes_get_their_own_hunks() {
+    let old: String = (1..=20).map(|n| format!("line {n}\n")).collect();
+    let new = old
+        .replace("line 2\n", "line two\n")
+        .replace("line 19\n", "");
+
+    let hunks = diff_lines(&old, &new, 1);
+    let headers: Vec<String> = hunks.iter().map(|hunk| hunk.header()).collect();
+    assert_eq!(headers, ["@@ -1,3 +1,3 @@", "@@ -18,3 +18,2 @@"]);
+
+    // Lines added to an empty body start at line 0 on the old side, like `diff -u`.
+    let hunks = diff_lines("", "first\n", 3);
+    assert_eq!(hunks[0].header(), "@@ -0,0 +1 @@");
+    assert!(diff_lines("same\n", "same\n", 3).is_empty());
+}
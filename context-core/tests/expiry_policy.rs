@@ -0,0 +1,106 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use context_core::{
+    policy::{parse_duration, ExpiryPolicy, ExpiryRule},
+    sqlite::SqliteStorage,
+    ContextError, Document, SourceType, Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn rule(tag: &str, after: &str) -> ExpiryRule {
+    ExpiryRule {
+        tag: tag.to_string(),
+        after: after.to_string(),
+    }
+}
+
+fn doc(id: &str, tags: &[&str], age: Duration) -> Document {
+    let created = Utc::now() - age;
+    Document {
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        created_at: created,
+        updated_at: created,
+        source: SourceType::Agent,
+        ..document(id, id, "body")
+    }
+}
+
+#[test]
+fn durations_and_shortest_matching_rule() {
+    assert_eq!(parse_duration("14d").unwrap(), Duration::days(14));
+    assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+    assert!(parse_duration("14").is_err());
+    assert!(parse_duration("-1d").is_err());
+
+    let policy = ExpiryPolicy::new(vec![rule("scratch", "14d"), rule("chatter", "1d")]).unwrap();
+    let tags = vec!["scratch".to_string(), "chatter".to_string()];
+    assert_eq!(policy.ttl_for(&tags), Some(86_400));
+    assert_eq!(policy.ttl_for(&["keep".to_string()]), None);
+
+    match ExpiryPolicy::new(vec![rule("scratch", "soon")]) {
+        Err(ContextError::Validation(message)) => assert!(
+            message.starts_with("Expiry rule for tag \"scratch\": Invalid duration \"soon\""),
+            "{message}"
+        ),
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn policy_applies_on_put_and_retroactively() -> TestResult<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("db.sqlite");
+
+    // Written before the policy existed.
+    let plain = SqliteStorage::open(&path).await?;
+    plain
+        .put(doc("old-scratch", &["scratch"], Duration::days(30)))
+        .await?;
+    plain
+        .put(doc("keeper", &["design"], Duration::days(30)))
+        .await?;
+
+    let storage = SqliteStorage::open(&path)
+        .await?
+        .with_expiry_policy(ExpiryPolicy::new(vec![rule("scratch", "14d")])?);
+    let fresh = storage
+        .put(doc("new-scratch", &["scratch"], Duration::zero()))
+        .await?;
+    assert_eq!(fresh.ttl_seconds, Some(14 * 86_400));
+
+    let preview = storage
+        .apply_expiry_policy(&"demo".to_string(), true)
+        .await?;
+    assert_eq!(preview.len(), 1);
+    assert_eq!(preview[0].id, "old-scratch");
+    assert!(storage
+        .get_by_key(&"demo".to_string(), "old-scratch")
+        .await?
+        .is_some());
+
+    storage
+        .apply_expiry_policy(&"demo".to_string(), false)
+        .await?;
+    assert!(
+        storage
+            .get_by_key(&"demo".to_string(), "old-scratch")
+            .await?
+            .is_none(),
+        "a 30-day-old scratch doc is past its 14-day TTL"
+    );
+    assert!(storage
+        .get_by_key(&"demo".to_string(), "keeper")
+        .await?
+        .is_some());
+    assert!(storage
+        .get_by_key(&"demo".to_string(), "new-scratch")
+        .await?
+        .is_some());
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use context_core::{
+    fixtures::{fixture, load, DEFAULT_SEED},
+    sqlite::SqliteStorage,
+    ListQuery, Storage,
+};
+use tempfile::tempdir;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[test]
+fn a_seed_fixes_the_whole_corpus() -> Result<()> {
+    let demo = fixture("demo")?;
+    let project = "demo".to_string();
+    let first = serde_json::to_string(&demo.revisions(&project, 7))?;
+    let again = serde_json::to_string(&demo.revisions(&project, 7))?;
+    assert_eq!(first, again);
+
+    let other_seed = serde_json::to_string(&demo.revisions(&project, 8))?;
+    assert_ne!(first, other_seed);
+
+    // Another project gets the same documents under different ids.
+    let elsewhere = demo.revisions(&"other".to_string(), 7);
+    let here = demo.revisions(&project, 7);
+    assert_eq!(elsewhere.len(), here.len());
+    assert_eq!(elsewhere[0].body_markdown, here[0].body_markdown);
+    assert_ne!(elsewhere[0].id.0, here[0].id.0);
+
+    let revisions = demo.revisions(&project, DEFAULT_SEED);
+    assert!(revisions.iter().any(|doc| doc.version > 1));
+    assert!(revisions.iter().any(|doc| !doc.tags.is_empty()));
+    Ok(())
+}
+
+#[test]
+fn unknown_fixtures_list_the_known_ones() {
+    let err = fixture("huge").unwrap_err().to_string();
+    assert!(err.contains("small, demo, large"), "{err}");
+}
+
+#[tokio::test]
+async fn loading_fills_an_empty_project_once() -> Result<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let small = fixture("small")?;
+    let project = "fx".to_string();
+
+    let report = load(&storage, small, &project, DEFAULT_SEED).await?;
+    assert_eq!(report.documents, small.documents);
+    assert_eq!(
+        report.revisions,
+        small.revisions(&project, DEFAULT_SEED).len()
+    );
+    assert_eq!(
+        storage.count(&project, &ListQuery::default()).await?,
+        small.documents as u64
+    );
+
+    let latest = small
+        .revisions(&project, DEFAULT_SEED)
+        .into_iter()
+        .max_by_key(|doc| doc.version)
+        .unwrap();
+    let history = storage.history(&latest.id).await?;
+    assert_eq!(history.len() as u64, latest.version);
+    let stored = storage.get_by_id(&latest.id).await?.unwrap();
+    assert_eq!(stored.body_markdown, latest.body_markdown);
+
+    let err = load(&storage, small, &project, DEFAULT_SEED)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already has"), "{err}");
+
+    load(&storage, small, &"fx-copy".to_string(), DEFAULT_SEED).await?;
+    assert_eq!(
+        storage.count(&project, &ListQuery::default()).await?,
+        small.documents as u64
+    );
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use context_core::{
+    frontmatter::{first_heading, render, split, FrontMatter},
+    Document, Visibility,
+};
+
+use common::document;
+
+#[test]
+fn split_reads_the_block_and_returns_the_rest_as_body() {
+    let text = "---\ntitle: \"Deploy: the runbook\"\nkey: ops/deploy\nnamespace: ops\ntags: [ops, 'run book']\nttl: 30d # long enough\nlayout: post\n---\n# Ignored heading\n\nSteps.\n";
+    let (front_matter, body) = split(text).unwrap();
+
+    assert_eq!(
+        front_matter,
+        FrontMatter {
+            title: Some("Deploy: the runbook".to_string()),
+            key: Some("ops/deploy".to_string()),
+            namespace: Some("ops".to_string()),
+            tags: vec!["ops".to_string(), "run book".to_string()],
+            ttl: Some("30d".to_string()),
+            visibility: None,
+        }
+    );
+    assert_eq!(body, "# Ignored heading\n\nSteps.\n");
+}
+
+#[test]
+fn tags_can_be_a_block_list_and_other_nested_keys_are_skipped() {
+    let text = "\u{feff}---\r\nauthor:\r\n  name: Sam\r\ntags:\r\n  - ops\r\n  - \"release\"\r\n...\r\nbody";
+    let (front_matter, body) = split(text).unwrap();
+
+    assert_eq!(front_matter.tags, ["ops", "release"]);
+    assert_eq!(front_matter.title, None);
+    assert_eq!(body, "body");
+}
+
+#[test]
+fn text_without_a_closed_block_is_all_body() {
+    for text in [
+        "plain notes",
+        "---\ntitle: never closed\n",
+        "--- \ntitle: x\n---\n",
+        "",
+    ] {
+        let (front_matter, body) = split(text).unwrap();
+        assert_eq!(front_matter, FrontMatter::default(), "{text:?}");
+        assert_eq!(body, text);
+    }
+}
+
+#[test]
+fn a_line_that_isnt_key_value_is_reported_with_its_number() {
+    let err = split("---\ntitle: ok\njust words\n---\nbody").unwrap_err();
+    assert!(err.to_string().contains("line 3"), "{err}");
+}
+
+#[test]
+fn first_heading_skips_code_fences_and_deeper_headings() {
+    let body = "intro\n```sh\n# not a heading\n```\n## Section\n# Release notes ##\n# Later\n";
+    assert_eq!(first_heading(body).as_deref(), Some("Release notes"));
+    assert_eq!(first_heading("#hashtag\n    # indented code\n"), None);
+}
+
+#[test]
+fn render_writes_a_block_that_split_reads_back() {
+    let at = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let doc = Document {
+        namespace: Some("ops".to_string()),
+        title: Some("Line one\nline two: with \\ and # hash".to_string()),
+        tags: vec!["a, b".to_string(), "- dash".to_string()],
+        created_at: at,
+        updated_at: at,
+        version: 3,
+        visibility: Some(Visibility::Public),
+        ..document(
+            "doc-1",
+            "notes/a \"quoted\" key",
+            "---\nnot front matter\n---\n",
+        )
+    };
+    let text = render(&doc);
+    assert!(text.contains("\nversion: 3\n"), "{text}");
+    assert!(
+        text.contains("\nupdated_at: 2024-03-01T12:00:00+00:00\n"),
+        "{text}"
+    );
+
+    let (front_matter, body) = split(&text).unwrap();
+    assert_eq!(
+        front_matter,
+        FrontMatter {
+            title: doc.title.clone(),
+            key: doc.key.clone(),
+            namespace: doc.namespace.clone(),
+            tags: doc.tags.clone(),
+            ttl: None,
+            visibility: Some("public".to_string()),
+        }
+    );
+    assert_eq!(body, doc.body_markdown);
+}
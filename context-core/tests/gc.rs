@@ -0,0 +1,121 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use context_core::{
+    gc::{collect, GcOptions},
+    sqlite::SqliteStorage,
+    Document, DocumentId, SearchQuery, Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn doc(id: &str, project: &str, created_at: DateTime<Utc>) -> Document {
+    Document {
+        project: project.to_string(),
+        created_at,
+        updated_at: created_at,
+        ..document(id, id, &format!("Notes about {id}."))
+    }
+}
+
+#[tokio::test]
+async fn gc_counts_each_category_exactly_and_dry_runs_change_nothing() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let now = Utc::now();
+    let long_ago = now - Duration::days(10);
+    let project = "demo".to_string();
+
+    storage.put(doc("kept", "demo", long_ago)).await?;
+    storage
+        .put(Document {
+            ttl_seconds: Some(60),
+            ..doc("expired", "demo", long_ago)
+        })
+        .await?;
+    storage
+        .put(Document {
+            deleted_at: Some(now - Duration::days(5)),
+            ..doc("old-tombstone", "demo", long_ago)
+        })
+        .await?;
+    storage
+        .put(Document {
+            deleted_at: Some(now - Duration::hours(1)),
+            ..doc("new-tombstone", "demo", long_ago)
+        })
+        .await?;
+    storage
+        .put(Document {
+            ttl_seconds: Some(60),
+            deleted_at: Some(now - Duration::hours(1)),
+            ..doc("expired-tombstone", "demo", long_ago)
+        })
+        .await?;
+    storage
+        .put(Document {
+            ttl_seconds: Some(60),
+            ..doc("elsewhere", "other", long_ago)
+        })
+        .await?;
+
+    let options = GcOptions {
+        retention: Duration::days(1),
+        dry_run: true,
+    };
+    let planned = collect(&storage, &project, options, now).await?;
+    assert_eq!((planned.expired, planned.tombstones), (1, 1));
+    assert_eq!(planned.reindexed, 4);
+    assert!(!planned.vacuumed);
+    assert_eq!(planned.analyzed, None);
+    assert_eq!(collect(&storage, &project, options, now).await?, planned);
+
+    // Lose the index so the rebuild has something to put back.
+    sqlx::query("DELETE FROM documents_fts")
+        .execute(storage.pool())
+        .await?;
+    let done = collect(
+        &storage,
+        &project,
+        GcOptions {
+            dry_run: false,
+            ..options
+        },
+        now,
+    )
+    .await?;
+    assert_eq!((done.expired, done.tombstones), (1, 1));
+    assert_eq!(done.reindexed, 4);
+    assert!(done.vacuumed);
+    assert!(done.analyzed.is_some());
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some(project.clone()),
+            text: "notes".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].document.id.0, "kept");
+    assert!(storage
+        .restore(&DocumentId("new-tombstone".to_string()))
+        .await?
+        .is_some());
+    // Expired, but deleted inside the retention window: still restorable.
+    assert!(storage
+        .deleted_by_id(&DocumentId("expired-tombstone".to_string()))
+        .await?
+        .is_some());
+    assert!(storage
+        .restore(&DocumentId("old-tombstone".to_string()))
+        .await?
+        .is_none());
+
+    let again = collect(&storage, &project, options, now).await?;
+    assert_eq!((again.expired, again.tombstones), (0, 0));
+    Ok(())
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use context_core::{sqlite::SqliteStorage, Conflict, ContextError, Document, SourceType, Storage};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn draft(id: &str, body: &str) -> Document {
+    Document {
+        source: SourceType::Web,
+        ..document(id, "notes/deploy", body)
+    }
+}
+
+#[tokio::test]
+async fn retried_write_replays_the_first_result() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+
+    let first = storage.store_once(draft("a", "v1"), "req-1").await?;
+    assert!(!first.replayed);
+    // A retry arrives with a freshly generated id, as a client that lost the response would send.
+    let retry = storage.store_once(draft("b", "v1"), "req-1").await?;
+    assert!(retry.replayed);
+    assert_eq!(retry.document.id.0, first.document.id.0);
+    assert_eq!(retry.document.version, 1);
+    assert_eq!(storage.history(&first.document.id).await?.len(), 1);
+
+    let next = storage.store_once(draft("c", "v2"), "req-2").await?;
+    assert_eq!(next.document.version, 2, "a new key is a new write");
+    Ok(())
+}
+
+#[tokio::test]
+async fn reusing_a_key_for_different_content_conflicts() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+
+    storage.store_once(draft("a", "v1"), "req-1").await?;
+    let err = storage
+        .store_once(draft("b", "something else"), "req-1")
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ContextError::Conflict(Conflict::Idempotency(_))),
+        "{err}"
+    );
+    Ok(())
+}
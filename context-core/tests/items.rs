@@ -0,0 +1,117 @@
+mod common;
+
+use chrono::Utc;
+use context_core::{
+    items::{extract_items, Item, ItemKind, ItemQuery},
+    sqlite::SqliteStorage,
+    Document, Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn doc(project: &str, key: &str, body: &str) -> Document {
+    Document {
+        project: project.to_string(),
+        ..document(&format!("{project}-{key}"), key, body)
+    }
+}
+
+fn item(kind: ItemKind, line: u32, text: &str, done: bool) -> Item {
+    Item {
+        kind,
+        line,
+        text: text.to_string(),
+        done,
+    }
+}
+
+#[test]
+fn markers_and_checkboxes_are_extracted() {
+    let body = "# Plan\n\
+                TODO: write the migration\n\
+                - [ ] add tests\n\
+                * [x] design the table\n\
+                1. [X] ask for review\n\
+                DECISION: items live in SQLite\n\
+                ```\n\
+                // TODO: not a real item\n\
+                - [ ] nor this\n\
+                ```\n\
+                - [ ]\n\
+                plain line with TODO in it";
+    assert_eq!(
+        extract_items(body),
+        vec![
+            item(ItemKind::Todo, 2, "write the migration", false),
+            item(ItemKind::Task, 3, "add tests", false),
+            item(ItemKind::Task, 4, "design the table", true),
+            item(ItemKind::Task, 5, "ask for review", true),
+            item(ItemKind::Decision, 6, "items live in SQLite", false),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn items_follow_the_live_documents() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let plan = storage
+        .put(doc(
+            "demo",
+            "plan",
+            "- [ ] ship it\n- [x] plan it\nDECISION: weekly releases",
+        ))
+        .await?;
+    storage
+        .put(doc("other", "notes", "TODO: elsewhere"))
+        .await?;
+
+    let open = ItemQuery {
+        project: Some("demo".to_string()),
+        kinds: vec![ItemKind::Todo, ItemKind::Task],
+        open_only: true,
+    };
+    let texts = |items: Vec<context_core::items::DocumentItem>| {
+        items
+            .into_iter()
+            .map(|entry| entry.item.text)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(texts(storage.items(&open).await?), vec!["ship it"]);
+    let decisions = ItemQuery {
+        kinds: vec![ItemKind::Decision],
+        ..open.clone()
+    };
+    assert_eq!(
+        texts(storage.items(&decisions).await?),
+        Vec::<String>::new(),
+        "decisions are never open"
+    );
+    let everything = storage.items(&ItemQuery::default()).await?;
+    assert_eq!(everything.len(), 4);
+
+    // A new revision replaces the document's items.
+    let revised = storage
+        .put(Document {
+            body_markdown: "- [x] ship it".to_string(),
+            version: 2,
+            ..plan
+        })
+        .await?;
+    assert!(storage.items(&open).await?.is_empty());
+
+    // Deleted documents drop out.
+    storage
+        .put(Document {
+            deleted_at: Some(Utc::now()),
+            version: 3,
+            ..revised
+        })
+        .await?;
+    let remaining = storage.items(&ItemQuery::default()).await?;
+    assert_eq!(texts(remaining), vec!["elsewhere"]);
+    Ok(())
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use context_core::{
+    jobs::{validate_jobs, JobKind, JobSpec},
+    sqlite::SqliteStorage,
+    Document, Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn job(kind: JobKind, every: &str) -> JobSpec {
+    JobSpec {
+        kind,
+        every: every.to_string(),
+        project: None,
+        keep: None,
+    }
+}
+
+fn draft(id: &str, key: &str, ttl_seconds: Option<i64>) -> Document {
+    let created = Utc::now() - Duration::hours(2);
+    Document {
+        project: "default".to_string(),
+        created_at: created,
+        updated_at: created,
+        ttl_seconds,
+        ..document(id, key, &format!("body of {key}"))
+    }
+}
+
+#[test]
+fn job_intervals_are_validated() {
+    assert!(validate_jobs(&[job(JobKind::Gc, "1h")]).is_ok());
+    assert!(validate_jobs(&[job(JobKind::Gc, "0m")]).is_err());
+    assert!(validate_jobs(&[job(JobKind::Gc, "soon")]).is_err());
+    let mut backup = job(JobKind::Backup, "1d");
+    backup.keep = Some(0);
+    assert!(validate_jobs(&[backup]).is_err());
+
+    let parsed: JobSpec =
+        serde_json::from_str(r#"{"kind": "wal_checkpoint", "every": "30m"}"#).unwrap();
+    assert_eq!(parsed, job(JobKind::WalCheckpoint, "30m"));
+}
+
+#[tokio::test]
+async fn gc_job_deletes_expired_documents() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    storage.put(draft("gone", "scratch", Some(60))).await?;
+    storage.put(draft("kept", "notes", None)).await?;
+
+    let summary = job(JobKind::Gc, "1d").run(&storage, Utc::now()).await?;
+    assert_eq!(summary, "deleted 1 expired document(s)");
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM documents")
+        .fetch_one(storage.pool())
+        .await?;
+    assert_eq!(remaining, 1);
+    let history: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM document_versions WHERE document_id = 'gone'")
+            .fetch_one(storage.pool())
+            .await?;
+    assert_eq!(history, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn backup_job_keeps_the_newest_snapshots() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let mut backup = job(JobKind::Backup, "1d");
+    backup.keep = Some(2);
+    let backups = temp.path().join("backups");
+    std::fs::create_dir_all(&backups)?;
+    std::fs::write(backups.join("pre-migrate-4.sqlite"), "")?;
+
+    for day in 1..=3 {
+        let now = Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+        backup.run(&storage, now).await?;
+    }
+    let mut names: Vec<String> = std::fs::read_dir(&backups)?
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("scheduled-"))
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "scheduled-20240102T000000Z.sqlite",
+            "scheduled-20240103T000000Z.sqlite"
+        ]
+    );
+    // Snapshots taken before migrations are not rotated.
+    assert!(backups.join("pre-migrate-4.sqlite").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn digest_job_covers_its_interval() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    storage.put(draft("recent", "notes/deploy", None)).await?;
+
+    let now = Utc::now();
+    let summary = job(JobKind::Digest, "1d").run(&storage, now).await?;
+    let key = format!("digest/{}", now.format("%Y-%m-%d"));
+    assert_eq!(summary, format!("digested 1 document(s) into {key}"));
+
+    let digest = storage
+        .get_by_key(&"default".to_string(), &key)
+        .await?
+        .expect("digest stored");
+    assert!(digest.body_markdown.contains("notes/deploy"));
+    Ok(())
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use std::str::FromStr;
+
+use chrono::{TimeZone, Utc};
+use context_core::{keys::normalize_key, sqlite::SqliteStorage, Document, Storage};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+async fn test_storage() -> TestResult<SqliteStorage> {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")?
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(SqliteJournalMode::Wal);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+
+    Ok(SqliteStorage::new(pool).await?)
+}
+
+fn sample_document(id: &str, key: &str) -> Document {
+    let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    Document {
+        created_at: now,
+        updated_at: now,
+        ..document(id, key, &format!("body of {key}"))
+    }
+}
+
+#[test]
+fn normalize_key_applies_nfc_and_lowercase() {
+    // "Café" spelled with a combining acute accent.
+    let decomposed = "Cafe\u{0301}/Notes";
+    assert_eq!(normalize_key(decomposed), "caf\u{e9}/notes");
+    assert_eq!(normalize_key("caf\u{e9}/notes"), normalize_key(decomposed));
+}
+
+#[tokio::test]
+async fn normalized_storage_writes_canonical_keys_and_reads_case_insensitively() -> TestResult<()> {
+    let storage = test_storage().await?.with_key_normalization(true);
+
+    let stored = storage
+        .put(sample_document("doc-1", "Runbooks/Deploy"))
+        .await?;
+    assert_eq!(stored.key.as_deref(), Some("runbooks/deploy"));
+
+    let fetched = storage
+        .get_by_key(&"demo".to_string(), "RUNBOOKS/deploy")
+        .await?
+        .expect("case-insensitive match");
+    assert_eq!(fetched.id.0, "doc-1");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn legacy_keys_resolve_and_collisions_are_reported() -> TestResult<()> {
+    let storage = test_storage().await?;
+    storage.put(sample_document("doc-a", "Notes")).await?;
+    storage.put(sample_document("doc-b", "notes")).await?;
+    storage.put(sample_document("doc-c", "Ideas")).await?;
+
+    let storage = storage.with_key_normalization(true);
+    let fetched = storage
+        .get_by_key(&"demo".to_string(), "IDEAS")
+        .await?
+        .expect("legacy key found through normalized comparison");
+    assert_eq!(fetched.id.0, "doc-c");
+
+    let collisions = storage.key_collisions(&"demo".to_string()).await?;
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].normalized, "notes");
+    assert_eq!(
+        collisions[0].keys,
+        vec!["Notes".to_string(), "notes".to_string()]
+    );
+
+    let dry_run = storage
+        .normalize_existing_keys(&"demo".to_string(), true)
+        .await?;
+    assert_eq!(dry_run.renamed.len(), 1);
+    assert_eq!(dry_run.renamed[0].from, "Ideas");
+    let untouched: Option<String> =
+        sqlx::query_scalar("SELECT key FROM documents WHERE id = 'doc-c'")
+            .fetch_one(storage.pool())
+            .await?;
+    assert_eq!(untouched.as_deref(), Some("Ideas"));
+
+    let report = storage
+        .normalize_existing_keys(&"demo".to_string(), false)
+        .await?;
+    assert_eq!(report.renamed.len(), 1);
+    assert_eq!(report.collisions.len(), 1);
+    let renamed: Option<String> =
+        sqlx::query_scalar("SELECT key FROM documents WHERE id = 'doc-c'")
+            .fetch_one(storage.pool())
+            .await?;
+    assert_eq!(renamed.as_deref(), Some("ideas"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn deleted_keys_neither_resolve_nor_block_normalization() -> TestResult<()> {
+    let storage = test_storage().await?;
+    storage
+        .put(Document {
+            deleted_at: Some(Utc::now()),
+            ..sample_document("doc-old", "NOTES")
+        })
+        .await?;
+    storage
+        .put(Document {
+            ttl_seconds: Some(60),
+            ..sample_document("doc-expired", "notes")
+        })
+        .await?;
+    storage.put(sample_document("doc-live", "Notes")).await?;
+
+    // Keys written before the normalized form was stored are filled in on open.
+    sqlx::query("UPDATE documents SET normalized_key = NULL")
+        .execute(storage.pool())
+        .await?;
+    let storage = SqliteStorage::new(storage.pool().clone())
+        .await?
+        .with_key_normalization(true);
+
+    let fetched = storage
+        .get_by_key(&"demo".to_string(), "NOTES")
+        .await?
+        .expect("the live legacy key, not the deleted one");
+    assert_eq!(fetched.id.0, "doc-live");
+    assert!(storage
+        .key_collisions(&"demo".to_string())
+        .await?
+        .is_empty());
+
+    let report = storage
+        .normalize_existing_keys(&"demo".to_string(), false)
+        .await?;
+    assert_eq!(report.renamed.len(), 1);
+    assert!(report.collisions.is_empty());
+    let renamed: Option<String> =
+        sqlx::query_scalar("SELECT key FROM documents WHERE id = 'doc-live'")
+            .fetch_one(storage.pool())
+            .await?;
+    assert_eq!(renamed.as_deref(), Some("notes"));
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use context_core::keys::KeyRules;
+
+#[test]
+fn default_rules_reject_whitespace_emoji_and_long_keys() {
+    let rules = KeyRules::default();
+
+    assert!(rules.validate("notes/deploy-2024_v1.md").is_ok());
+    assert!(rules.validate("caf\u{e9}/notes").is_ok());
+
+    let err = rules.validate("line one\nline two").unwrap_err();
+    assert!(err.to_string().contains("a space at position 4"), "{err}");
+
+    let err = rules.validate("notes\nmore").unwrap_err();
+    assert!(err.to_string().contains("a newline at position 5"), "{err}");
+
+    let err = rules.validate("rocket-\u{1F680}").unwrap_err();
+    assert!(err.to_string().contains("U+1F680"), "{err}");
+
+    let sentence = "a".repeat(500);
+    let err = rules.validate(&sentence).unwrap_err();
+    assert!(err.to_string().contains("500 characters"), "{err}");
+
+    assert!(rules.validate("").is_err());
+}
+
+#[test]
+fn custom_rules_allow_punctuation_and_reserve_prefixes() {
+    let rules: KeyRules = serde_json::from_str(
+        r#"{"max_length": 16, "allowed_punctuation": "-+", "reserved_prefixes": ["sys-"]}"#,
+    )
+    .unwrap();
+
+    assert!(rules.validate("c++-notes").is_ok());
+    assert!(rules.validate("notes/deploy").is_err());
+
+    let err = rules.validate("sys-internal").unwrap_err();
+    assert!(err.to_string().contains("reserved prefix"), "{err}");
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use context_core::{
+    links::{extract_links, LinkTarget},
+    sqlite::SqliteStorage,
+    Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn target(project: &str, key: &str) -> LinkTarget {
+    LinkTarget {
+        project: project.to_string(),
+        key: key.to_string(),
+    }
+}
+
+#[test]
+fn wiki_links_and_uris_are_found_outside_code_fences() {
+    let body = "See [[deploy/canary]] and [[runbook|the runbook]].\n\
+                ```\n[[not-a-link]]\n```\n\
+                Upstream: <context://infra/dns/zones>, and context://infra/dns/zones.\n\
+                Again [[deploy/canary]]; empty [[ ]].";
+    assert_eq!(
+        extract_links(body, "demo"),
+        vec![
+            target("demo", "deploy/canary"),
+            target("demo", "runbook"),
+            target("infra", "dns/zones"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn links_resolve_by_key_and_backlinks_follow_renames() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let plan = storage
+        .put(document(
+            "d1",
+            "plan",
+            "Ship after [[checklist]] and [[retro]].",
+        ))
+        .await?;
+    let checklist = storage
+        .put(document("d2", "checklist", "- [ ] canary"))
+        .await?;
+
+    let links = storage.links(&plan.id).await?;
+    let resolved: Vec<(Option<&str>, Option<&str>)> = links
+        .iter()
+        .map(|link| (link.key.as_deref(), link.document_id.as_deref()))
+        .collect();
+    assert_eq!(
+        resolved,
+        vec![(Some("checklist"), Some("d2")), (Some("retro"), None)]
+    );
+
+    let backlinks = storage.backlinks(&"demo".to_string(), "checklist").await?;
+    assert_eq!(backlinks.len(), 1);
+    assert_eq!(backlinks[0].document_id.as_deref(), Some("d1"));
+
+    // Renaming the target keeps the link found from its new key.
+    storage
+        .rename_key(checklist, "launch-checklist", true)
+        .await?;
+    let backlinks = storage
+        .backlinks(&"demo".to_string(), "launch-checklist")
+        .await?;
+    assert_eq!(backlinks.len(), 1);
+
+    // A revision that drops the link drops the backlink.
+    storage
+        .store(document("d3", "plan", "Ship after [[retro]]."))
+        .await?;
+    assert!(storage
+        .backlinks(&"demo".to_string(), "launch-checklist")
+        .await?
+        .is_empty());
+    Ok(())
+}
@@ -1,11 +1,16 @@
-use std::str::FromStr;
+use std::{path::Path, str::FromStr};
 
 use chrono::Utc;
-use context_core::{sqlite::run_migrations, Result};
+use context_core::{
+    sqlite::{run_migrations, SqliteStorage},
+    Document, DocumentId, Result, SearchQuery, SourceMeta, SourceType, Storage,
+};
 use sqlx::{
+    migrate::Migrator,
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
     SqlitePool,
 };
+use tempfile::tempdir;
 
 async fn test_pool() -> Result<SqlitePool> {
     let options = SqliteConnectOptions::from_str("sqlite::memory:")?
@@ -73,7 +78,7 @@ async fn documents_enforce_unique_key_per_project() -> Result<()> {
     .bind("Rust setup instructions")
     .bind(&now)
     .bind(&now)
-    .bind("User")
+    .bind("user")
     .bind(1_i64)
     .execute(&pool)
     .await?;
@@ -91,7 +96,7 @@ async fn documents_enforce_unique_key_per_project() -> Result<()> {
     .bind("Rust setup instructions")
     .bind(&now)
     .bind(&now)
-    .bind("User")
+    .bind("user")
     .bind(1_i64)
     .execute(&pool)
     .await;
@@ -130,7 +135,7 @@ async fn full_text_index_tracks_document_changes() -> Result<()> {
     .bind("rust search works")
     .bind(&now)
     .bind(&now)
-    .bind("System")
+    .bind("system")
     .bind(1_i64)
     .execute(&pool)
     .await?;
@@ -186,3 +191,247 @@ async fn search_ids(pool: &SqlitePool, term: &str, project: &str) -> Result<Vec<
 
     Ok(rows)
 }
+
+/// A file database migrated only up to `version`, as an older release would have left it.
+async fn database_at_version(path: &Path, version: i64) -> Result<SqlitePool> {
+    let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+    let older = tempdir()?;
+    for entry in std::fs::read_dir(&source)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let number: i64 = name
+            .split('_')
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .expect("migration file names start with their number");
+        if number <= version {
+            std::fs::copy(entry.path(), older.path().join(&name))?;
+        }
+    }
+
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new().connect_with(options).await?;
+    Migrator::new(older.path()).await?.run(&pool).await?;
+    Ok(pool)
+}
+
+#[tokio::test]
+async fn pending_migrations_snapshot_existing_database_first() -> Result<()> {
+    let temp = tempdir()?;
+
+    let fresh = SqliteStorage::open(&temp.path().join("fresh.sqlite")).await?;
+    let runs: Vec<(i64, Option<String>)> =
+        sqlx::query_as("SELECT from_version, backup_path FROM migration_runs")
+            .fetch_all(fresh.pool())
+            .await?;
+    assert_eq!(
+        runs,
+        vec![(0, None)],
+        "a new database has nothing to back up"
+    );
+
+    let path = temp.path().join("db.sqlite");
+    let older = database_at_version(&path, 4).await?;
+    sqlx::query("INSERT INTO projects (id) VALUES ('demo')")
+        .execute(&older)
+        .await?;
+    older.close().await;
+
+    let storage = SqliteStorage::open(&path).await?;
+    let (from, to, backup): (i64, i64, Option<String>) =
+        sqlx::query_as("SELECT from_version, to_version, backup_path FROM migration_runs")
+            .fetch_one(storage.pool())
+            .await?;
+    assert_eq!(from, 4);
+    assert!(to > 4);
+    let expected = temp.path().join("backups").join("pre-migrate-4.sqlite");
+    assert_eq!(
+        backup.as_deref(),
+        Some(expected.display().to_string().as_str())
+    );
+
+    let snapshot = SqlitePoolOptions::new()
+        .connect_with(SqliteConnectOptions::new().filename(&expected))
+        .await?;
+    let projects: Vec<String> = sqlx::query_scalar("SELECT id FROM projects")
+        .fetch_all(&snapshot)
+        .await?;
+    assert_eq!(projects, vec!["demo".to_string()]);
+    let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+        .fetch_one(&snapshot)
+        .await?;
+    assert_eq!(version, 4, "the snapshot predates the upgrade");
+    Ok(())
+}
+
+#[tokio::test]
+async fn debug_named_sources_are_migrated_to_stable_names() -> Result<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("db.sqlite");
+    let older = database_at_version(&path, 6).await?;
+    sqlx::query("INSERT INTO projects (id) VALUES ('demo')")
+        .execute(&older)
+        .await?;
+    sqlx::query(
+        "INSERT INTO documents (id, project_id, key, body_markdown, source) \
+         VALUES ('doc-1', 'demo', 'notes', 'written by an agent', 'Agent')",
+    )
+    .execute(&older)
+    .await?;
+    sqlx::query(
+        "INSERT INTO document_versions (document_id, version, body_markdown, source) \
+         VALUES ('doc-1', 1, 'written by an agent', 'Agent')",
+    )
+    .execute(&older)
+    .await?;
+    older.close().await;
+
+    let storage = SqliteStorage::open(&path).await?;
+    let stored: Vec<String> = sqlx::query_scalar(
+        "SELECT source FROM documents UNION ALL SELECT source FROM document_versions",
+    )
+    .fetch_all(storage.pool())
+    .await?;
+    assert_eq!(stored, vec!["agent".to_string(), "agent".to_string()]);
+
+    let id = DocumentId("doc-1".to_string());
+    let doc = storage.get_by_id(&id).await?.expect("migrated document");
+    assert!(matches!(doc.source, SourceType::Agent));
+    assert_eq!(storage.history(&id).await?.len(), 1);
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "agent".to_string(),
+            limit: Some(5),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(
+        hits.len(),
+        1,
+        "the search index still lines up with the rebuilt table"
+    );
+
+    let revised = storage
+        .put(Document {
+            source: SourceType::Tool,
+            source_meta: Some(SourceMeta {
+                tool: Some("formatter".to_string()),
+                session: Some("run-42".to_string()),
+                fetched_from: None,
+            }),
+            version: 2,
+            ..doc
+        })
+        .await?;
+    let reread = storage.get_by_id(&id).await?.expect("revised document");
+    assert!(matches!(reread.source, SourceType::Tool));
+    assert_eq!(reread.source_meta, revised.source_meta);
+    Ok(())
+}
+
+#[tokio::test]
+async fn newer_additive_schema_still_opens() -> Result<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("db.sqlite");
+    let storage = SqliteStorage::open(&path).await?;
+    storage.register_project(&"demo".to_string()).await?;
+
+    let mut conn = storage.pool().acquire().await?;
+    // What a newer release might leave behind: an unknown migration, an extra column,
+    // and a source value this build has no variant for.
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+         VALUES (99, 'from the future', 1, x'00', 0)",
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("ALTER TABLE documents ADD COLUMN mood TEXT")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("PRAGMA ignore_check_constraints = ON")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "INSERT INTO documents (id, project_id, key, body_markdown, source, mood) \
+         VALUES ('doc-1', 'demo', 'notes', 'hello', 'Telepathy', 'calm')",
+    )
+    .execute(&mut *conn)
+    .await?;
+    drop(conn);
+    storage.pool().close().await;
+
+    let storage = SqliteStorage::open(&path).await?;
+    let doc = storage
+        .get_by_id(&DocumentId("doc-1".to_string()))
+        .await?
+        .expect("document from the newer schema");
+    assert!(matches!(doc.source, SourceType::Unknown));
+    assert!(
+        storage.put(doc).await.is_err(),
+        "unknown sources are not overwritten"
+    );
+
+    // A newer release that declares itself unreadable by this build is refused.
+    sqlx::query("UPDATE schema_compat SET min_reader_version = 99")
+        .execute(storage.pool())
+        .await?;
+    storage.pool().close().await;
+    let err = SqliteStorage::open(&path).await.unwrap_err();
+    assert!(err.to_string().contains("needs a newer context"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn readers_from_before_visibility_are_refused() -> Result<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("db.sqlite");
+    let min_reader = "SELECT min_reader_version FROM schema_compat WHERE id = 1";
+    let older = database_at_version(&path, 11).await?;
+    let before: i64 = sqlx::query_scalar(min_reader).fetch_one(&older).await?;
+    assert!(before <= 11);
+    older.close().await;
+
+    // A build that stops at migration 11 would hand private documents to anyone, so once
+    // the column exists such a build must refuse the file.
+    let storage = SqliteStorage::open(&path).await?;
+    let after: i64 = sqlx::query_scalar(min_reader)
+        .fetch_one(storage.pool())
+        .await?;
+    assert_eq!(after, 12);
+    Ok(())
+}
+
+#[tokio::test]
+async fn code_written_before_the_code_index_is_backfilled() -> Result<()> {
+    let temp = tempdir()?;
+    let path = temp.path().join("db.sqlite");
+    let older = database_at_version(&path, 8).await?;
+    sqlx::query("INSERT INTO projects (id) VALUES ('demo')")
+        .execute(&older)
+        .await?;
+    sqlx::query(
+        "INSERT INTO documents (id, project_id, key, body_markdown, source) \
+         VALUES ('doc-1', 'demo', 'snippet', 'Paste:\n```\nloadUserProfile(id)\n```', 'agent')",
+    )
+    .execute(&older)
+    .await?;
+    older.close().await;
+
+    let storage = SqliteStorage::open(&path).await?;
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "UserProfile".to_string(),
+            code: true,
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].document.key.as_deref(), Some("snippet"));
+    Ok(())
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use context_core::{sqlite::SqliteStorage, ContextError, Document, NamespaceInfo, Storage};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn doc(id: &str, namespace: Option<&str>) -> Document {
+    Document {
+        namespace: namespace.map(str::to_string),
+        ..document(id, id, &format!("body of {id}"))
+    }
+}
+
+fn info(name: &str, documents: u64) -> NamespaceInfo {
+    NamespaceInfo {
+        name: name.to_string(),
+        documents,
+    }
+}
+
+#[tokio::test]
+async fn namespaces_are_counted_renamed_and_removed() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let project = "demo".to_string();
+
+    storage.put(doc("a", Some("runbooks"))).await?;
+    storage.put(doc("b", Some("runbok"))).await?;
+    storage.put(doc("c", None)).await?;
+    assert!(storage.create_namespace(&project, "decisions").await?);
+    assert!(!storage.create_namespace(&project, "decisions").await?);
+
+    assert_eq!(
+        storage.namespaces(&project).await?,
+        vec![info("decisions", 0), info("runbok", 1), info("runbooks", 1)]
+    );
+
+    let moved = storage
+        .rename_namespace(&project, "runbok", "runbooks")
+        .await?;
+    assert_eq!(moved, 1);
+    assert_eq!(
+        storage.namespaces(&project).await?,
+        vec![info("decisions", 0), info("runbooks", 2)]
+    );
+    let fixed = storage.get_by_key(&project, "b").await?.unwrap();
+    assert_eq!(fixed.namespace.as_deref(), Some("runbooks"));
+
+    assert!(matches!(
+        storage.rename_namespace(&project, "missing", "x").await,
+        Err(ContextError::NotFound(_))
+    ));
+    assert!(matches!(
+        storage.remove_namespace(&project, "runbooks", false).await,
+        Err(ContextError::Validation(_))
+    ));
+    assert_eq!(
+        storage
+            .remove_namespace(&project, "decisions", false)
+            .await?,
+        0
+    );
+    assert_eq!(
+        storage.remove_namespace(&project, "runbooks", true).await?,
+        2
+    );
+    assert!(storage.namespaces(&project).await?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use context_core::{pack::pack, Document};
+
+use common::document;
+
+fn doc(id: &str, key: &str, body: &str) -> Document {
+    Document {
+        version: 2,
+        ..document(id, key, body)
+    }
+}
+
+#[test]
+fn pack_keeps_order_dedups_and_respects_budget() {
+    let big = "x".repeat(400);
+    let documents = vec![
+        doc("a", "first", "alpha"),
+        doc("a", "first", "alpha"),
+        doc("b", "copy", "alpha"),
+        doc("c", "huge", &big),
+        doc("d", "last", "omega"),
+    ];
+
+    let packed = pack(documents, 40);
+
+    let keys: Vec<&str> = packed
+        .included
+        .iter()
+        .filter_map(|entry| entry.key.as_deref())
+        .collect();
+    assert_eq!(keys, vec!["first", "last"]);
+    assert_eq!(packed.skipped.len(), 1);
+    assert_eq!(packed.skipped[0].key.as_deref(), Some("huge"));
+    assert!(packed
+        .markdown
+        .starts_with("## first (v2)\n\nalpha\n\n## last (v2)"));
+    assert!(packed.tokens <= 40);
+}
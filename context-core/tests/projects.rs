@@ -0,0 +1,88 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use context_core::{sqlite::SqliteStorage, Document, Storage};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[tokio::test]
+async fn projects_are_registered_described_and_listed() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+
+    assert!(storage.register_project(&"beta".to_string()).await?);
+    assert!(!storage.register_project(&"beta".to_string()).await?);
+
+    // Writing a document registers its project implicitly.
+    storage
+        .put(Document {
+            project: "alpha".to_string(),
+            ..document("doc", "notes", "hello")
+        })
+        .await?;
+
+    let ids: Vec<String> = storage
+        .projects()
+        .await?
+        .into_iter()
+        .map(|p| p.id)
+        .collect();
+    assert_eq!(ids, vec!["alpha", "beta"]);
+
+    let mut info = storage.project(&"beta".to_string()).await?.unwrap();
+    assert_eq!(info.display_name, None);
+    info.display_name = Some("Beta Service".to_string());
+    info.description = Some("Payments backend".to_string());
+    info.settings = BTreeMap::from([("owner".to_string(), "team-pay".to_string())]);
+    let created_at = info.created_at;
+
+    let updated = storage.update_project(&info).await?;
+    assert_eq!(updated.display_name.as_deref(), Some("Beta Service"));
+    assert_eq!(updated.settings["owner"], "team-pay");
+    assert_eq!(updated.created_at, created_at);
+    assert_eq!(storage.project(&"beta".to_string()).await?, Some(updated));
+
+    assert!(storage
+        .register_project(&" padded ".to_string())
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn project_stats_cover_live_documents_only() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    storage.register_project(&"empty".to_string()).await?;
+
+    let doc = |id: &str, body: &str| Document {
+        project: "alpha".to_string(),
+        ..document(id, id, body)
+    };
+    storage.put(doc("a", "héllo")).await?;
+    storage.put(doc("b", "world")).await?;
+    storage
+        .put(Document {
+            deleted_at: Some(Utc::now()),
+            ..doc("c", "tombstoned body")
+        })
+        .await?;
+
+    let stats = storage.project_stats(&[]).await?;
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].project.id, "alpha");
+    assert_eq!(stats[0].documents, 2);
+    assert_eq!(stats[0].bytes, "héllo".len() as u64 + 5);
+    assert!(stats[0].last_updated.is_some());
+    assert_eq!(stats[1].project.id, "empty");
+    assert_eq!(stats[1].documents, 0);
+    assert_eq!(stats[1].last_updated, None);
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use std::time::Duration;
+
+use context_core::{
+    sqlite::SqliteStorage, ContextError, Document, ListQuery, SearchQuery, Storage,
+};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn draft(n: usize) -> Document {
+    document(
+        &format!("d{n}"),
+        &format!("notes/{n}"),
+        &format!("Deploy {n} went out behind a canary."),
+    )
+}
+
+fn canary() -> SearchQuery {
+    SearchQuery {
+        project: Some("demo".to_string()),
+        text: "canary".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn queries_past_the_timeout_are_stopped_without_spoiling_the_pool() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    for n in 0..200 {
+        storage.put(draft(n)).await?;
+    }
+
+    let hurried = storage.clone().with_query_timeout(Duration::ZERO);
+    let err = hurried.search(canary()).await.unwrap_err();
+    assert!(matches!(err, ContextError::Timeout(_)), "{err}");
+    let err = hurried
+        .count(&"demo".to_string(), &ListQuery::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ContextError::Timeout(_)), "{err}");
+
+    // The connections that ran them went back to the pool without their deadline.
+    for _ in 0..20 {
+        assert_eq!(storage.search(canary()).await?.len(), 200);
+    }
+    let patient = storage.with_query_timeout(Duration::from_secs(30));
+    assert_eq!(
+        patient
+            .count(&"demo".to_string(), &ListQuery::default())
+            .await?,
+        200
+    );
+    Ok(())
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use context_core::{related::rank_related, Document};
+
+use common::document;
+
+fn doc(id: &str, key: &str, tags: &[&str], body: &str) -> Document {
+    Document {
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..document(id, key, body)
+    }
+}
+
+#[test]
+fn related_ranks_by_tags_links_and_text() {
+    let target = doc(
+        "t",
+        "auth/tokens",
+        &["auth", "security"],
+        "Access tokens rotate daily",
+    );
+    let candidates = vec![
+        target.clone(),
+        doc(
+            "tags",
+            "auth/sessions",
+            &["auth", "security"],
+            "Cookie lifetime",
+        ),
+        doc("link", "runbook", &[], "See auth/tokens before rotating"),
+        doc("text", "notes", &[], "tokens rotate on a schedule"),
+        doc("none", "lunch", &["food"], "Pizza on fridays"),
+    ];
+
+    let related = rank_related(&target, candidates, 10);
+    let ids: Vec<&str> = related.iter().map(|r| r.document.id.0.as_str()).collect();
+
+    assert_eq!(ids, vec!["tags", "link", "text"]);
+    assert_eq!(related[0].tag_overlap, 1.0);
+    assert_eq!(related[1].link, 1.0);
+    assert!(related[2].text_similarity > 0.0);
+}
+
+#[test]
+fn related_treats_summary_tags_as_links() {
+    let target = doc("src", "design", &[], "Long design doc");
+    let summary = doc(
+        "sum",
+        "summary/design",
+        &["summary", "summary-of:src"],
+        "Tl;dr",
+    );
+
+    let related = rank_related(&target, vec![summary], 1);
+
+    assert_eq!(related.len(), 1);
+    assert_eq!(related[0].link, 1.0);
+    assert_eq!(related[0].tag_overlap, 0.0);
+}
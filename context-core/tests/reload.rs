@@ -0,0 +1,24 @@
+use std::time::{Duration, SystemTime};
+
+use context_core::reload::{ConfigWatcher, Trigger};
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn watcher_fires_when_the_file_changes() {
+    let temp = tempdir().unwrap();
+    let path = temp.path().join("config.json");
+    std::fs::write(&path, "{}").unwrap();
+    let mut watcher = ConfigWatcher::new(&path, Duration::from_millis(20)).unwrap();
+
+    // Nothing changed yet, so polling alone never fires.
+    let quiet = tokio::time::timeout(Duration::from_millis(100), watcher.changed()).await;
+    assert!(quiet.is_err());
+
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+    let trigger = tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+        .await
+        .expect("watcher noticed the new modification time");
+    assert_eq!(trigger, Trigger::Modified);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use context_core::{reminders::ReminderQuery, sqlite::SqliteStorage, Document, Storage};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn doc(project: &str, key: &str) -> Document {
+    Document {
+        project: project.to_string(),
+        ..document(&format!("{project}-{key}"), key, "follow up")
+    }
+}
+
+#[tokio::test]
+async fn due_reminders_stay_due_until_done() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let plan = storage.put(doc("demo", "plan")).await?;
+    let other = storage.put(doc("other", "notes")).await?;
+    let now = Utc::now();
+
+    let overdue = storage
+        .add_reminder(
+            &plan.id,
+            now - Duration::days(1),
+            Some("check rollout".into()),
+        )
+        .await?;
+    storage
+        .add_reminder(&plan.id, now + Duration::days(7), None)
+        .await?;
+    storage
+        .add_reminder(&other.id, now - Duration::hours(1), None)
+        .await?;
+    assert_eq!(overdue.key.as_deref(), Some("plan"));
+    assert_eq!(overdue.project, "demo");
+    assert!(overdue.is_due(now));
+
+    let due = ReminderQuery {
+        project: Some("demo".to_string()),
+        due_by: Some(now),
+        ..ReminderQuery::default()
+    };
+    let ids = |reminders: Vec<context_core::reminders::Reminder>| {
+        reminders.into_iter().map(|r| r.id).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(storage.reminders(&due).await?), vec![overdue.id]);
+    let pending = ReminderQuery {
+        due_by: None,
+        ..due.clone()
+    };
+    assert_eq!(storage.reminders(&pending).await?.len(), 2);
+    assert_eq!(storage.reminders(&ReminderQuery::default()).await?.len(), 3);
+
+    // Notifying once takes it out of the unnotified set but leaves it due.
+    storage.mark_reminder_notified(overdue.id, now).await?;
+    let unnotified = ReminderQuery {
+        unnotified: true,
+        ..due.clone()
+    };
+    assert!(storage.reminders(&unnotified).await?.is_empty());
+    assert_eq!(ids(storage.reminders(&due).await?), vec![overdue.id]);
+
+    assert!(storage.complete_reminder(overdue.id).await?);
+    assert!(!storage.complete_reminder(overdue.id).await?);
+    assert!(storage.reminders(&due).await?.is_empty());
+    let with_done = ReminderQuery {
+        include_done: true,
+        ..due
+    };
+    let done = storage.reminders(&with_done).await?;
+    assert!(done[0].done_at.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn reminders_on_deleted_documents_are_hidden() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let plan = storage.put(doc("demo", "plan")).await?;
+    storage
+        .add_reminder(&plan.id, Utc::now() - Duration::minutes(5), None)
+        .await?;
+    storage
+        .put(Document {
+            deleted_at: Some(Utc::now()),
+            version: 2,
+            ..plan
+        })
+        .await?;
+    assert!(storage
+        .reminders(&ReminderQuery::default())
+        .await?
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use context_core::{sqlite::SqliteStorage, ListQuery, SearchQuery, Storage};
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[tokio::test]
+async fn a_snapshot_does_not_see_later_writes() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    storage.store(document("d1", "plan", "first draft")).await?;
+
+    let snapshot = storage.snapshot().await?;
+    storage
+        .store_all(vec![
+            document("d2", "plan", "second draft"),
+            document("d3", "notes", "second draft notes"),
+        ])
+        .await?;
+
+    let project = "demo".to_string();
+    let plan = snapshot.get_by_key(&project, "plan").await?.unwrap();
+    assert_eq!(plan.body_markdown, "first draft");
+    assert!(snapshot.get_by_key(&project, "notes").await?.is_none());
+    let hits = snapshot
+        .search(SearchQuery {
+            project: Some(project.clone()),
+            text: "second".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert!(hits.is_empty());
+    assert_eq!(snapshot.count(&project, &ListQuery::default()).await?, 1);
+    assert!(snapshot.put(document("d4", "late", "body")).await.is_err());
+
+    assert_eq!(storage.count(&project, &ListQuery::default()).await?, 2);
+    drop(snapshot);
+    assert_eq!(
+        storage
+            .snapshot()
+            .await?
+            .count(&project, &ListQuery::default())
+            .await?,
+        2
+    );
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use context_core::{
+    socket::{parse_mode, DEFAULT_MODE},
+    ContextError,
+};
+
+#[test]
+fn socket_modes_parse_as_octal() {
+    assert_eq!(parse_mode("600").unwrap(), DEFAULT_MODE);
+    assert_eq!(parse_mode("0660").unwrap(), 0o660);
+    assert!(matches!(parse_mode("rw"), Err(ContextError::Validation(_))));
+    assert!(parse_mode("1777").is_err());
+    // The server itself must be able to use the socket.
+    assert!(parse_mode("060").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn sockets_are_bound_with_their_mode_and_refuse_a_second_server() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("context.sock");
+    let _listener = context_core::socket::bind(&path, 0o660).unwrap();
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o660);
+    // The private directory it was staged in is gone.
+    assert_eq!(std::fs::read_dir(temp.path()).unwrap().count(), 1);
+    assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+    assert!(context_core::socket::bind(&path, 0o600).is_err());
+}
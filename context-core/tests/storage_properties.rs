@@ -0,0 +1,293 @@
+//! Property tests: random sequences of put/delete/search/gc run against an in-memory
+//! store and a model of what should be live, checking the store's invariants after
+//! every step. Raise `PROPTEST_CASES` for a longer run.
+
+mod common;
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use chrono::{TimeZone, Utc};
+use context_core::{
+    sqlite::{match_expression, SqliteStorage},
+    DeleteMode, Document, DocumentId, Key, ListQuery, ProjectId, SearchQuery, SourceType, Storage,
+};
+use proptest::prelude::*;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use common::document;
+
+const PROJECT: &str = "props";
+const KEYS: &[&str] = &["notes/a", "notes/b", "plans/c", "plans/d"];
+const WORDS: &[&str] = &["alpha", "bravo", "charlie", "delta", "echo"];
+
+#[derive(Debug, Clone)]
+enum Op {
+    /// Store `words` at `KEYS[key]`; a new document is written already expired when
+    /// `expired` is set.
+    Put {
+        key: usize,
+        words: Vec<usize>,
+        expired: bool,
+    },
+    Delete {
+        key: usize,
+    },
+    Search {
+        word: usize,
+    },
+    Gc,
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        4 => (
+            0..KEYS.len(),
+            prop::collection::vec(0..WORDS.len(), 1..4),
+            prop::bool::weighted(0.2),
+        )
+            .prop_map(|(key, words, expired)| Op::Put { key, words, expired }),
+        2 => (0..KEYS.len()).prop_map(|key| Op::Delete { key }),
+        2 => (0..WORDS.len()).prop_map(|word| Op::Search { word }),
+        1 => Just(Op::Gc),
+    ]
+}
+
+/// What the model expects to be live at a key.
+#[derive(Debug, Clone)]
+struct Live {
+    id: DocumentId,
+    version: u64,
+    words: Vec<usize>,
+}
+
+async fn memory_storage() -> SqliteStorage {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")
+        .unwrap()
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .unwrap();
+    SqliteStorage::new(pool).await.unwrap()
+}
+
+fn draft(key: &str, words: &[usize], expired: bool) -> Document {
+    let now = Utc::now();
+    // Created long ago with a one-minute TTL: hidden from every read as soon as it lands.
+    let created_at = if expired {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+    } else {
+        now
+    };
+    let body = words
+        .iter()
+        .map(|&word| WORDS[word])
+        .collect::<Vec<_>>()
+        .join(" ");
+    Document {
+        project: ProjectId::from(PROJECT),
+        created_at,
+        updated_at: now,
+        source: SourceType::Agent,
+        ttl_seconds: expired.then_some(60),
+        ..document(&uuid::Uuid::new_v4().to_string(), key, &body)
+    }
+}
+
+async fn apply(storage: &SqliteStorage, model: &mut BTreeMap<usize, Live>, op: &Op) {
+    let project = ProjectId::from(PROJECT);
+    match op {
+        Op::Put {
+            key,
+            words,
+            expired,
+        } => {
+            // Only a new document can be written expired; a revision keeps its creation time.
+            let expired = *expired && !model.contains_key(key);
+            let stored = storage
+                .store(draft(KEYS[*key], words, expired))
+                .await
+                .unwrap();
+            if expired {
+                assert!(storage
+                    .get_by_key(&project, &Key::from(KEYS[*key]))
+                    .await
+                    .unwrap()
+                    .is_none());
+                return;
+            }
+            let expected_version = match model.get(key) {
+                Some(previous) => {
+                    assert_eq!(stored.id.0, previous.id.0, "a revision keeps its id");
+                    previous.version + 1
+                }
+                None => 1,
+            };
+            assert_eq!(stored.version, expected_version);
+            model.insert(
+                *key,
+                Live {
+                    id: stored.id,
+                    version: stored.version,
+                    words: words.clone(),
+                },
+            );
+        }
+        Op::Delete { key } => match model.remove(key) {
+            Some(live) => {
+                let tombstone = storage
+                    .delete_by_id(&live.id, DeleteMode::Soft)
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(tombstone.version, live.version + 1);
+                assert!(storage
+                    .delete_by_id(&live.id, DeleteMode::Soft)
+                    .await
+                    .unwrap()
+                    .is_none());
+            }
+            None => assert!(storage
+                .get_by_key(&project, &Key::from(KEYS[*key]))
+                .await
+                .unwrap()
+                .is_none()),
+        },
+        Op::Search { word } => {
+            let hits = storage
+                .search(SearchQuery {
+                    project: Some(project),
+                    text: WORDS[*word].to_string(),
+                    ..SearchQuery::default()
+                })
+                .await
+                .unwrap();
+            let mut found: Vec<&str> = hits
+                .iter()
+                .map(|hit| hit.document.key.as_deref().unwrap())
+                .collect();
+            found.sort_unstable();
+            let expected: Vec<&str> = model
+                .iter()
+                .filter(|(_, live)| live.words.contains(word))
+                .map(|(&key, _)| KEYS[key])
+                .collect();
+            assert_eq!(found, expected, "search for {:?}", WORDS[*word]);
+        }
+        Op::Gc => {
+            storage.purge_expired().await.unwrap();
+            storage.sweep_orphans(false).await.unwrap();
+        }
+    }
+}
+
+async fn check_invariants(storage: &SqliteStorage, model: &BTreeMap<usize, Live>) {
+    let project = ProjectId::from(PROJECT);
+    let report = storage.sweep_orphans(true).await.unwrap();
+    assert_eq!(report.orphaned_fts_rows, 0, "search index out of step");
+
+    let count = storage
+        .count(&project, &ListQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(count, model.len() as u64);
+
+    for (&key, live) in model {
+        let doc = storage
+            .get_by_key(&project, &Key::from(KEYS[key]))
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("{} should be live", KEYS[key]));
+        assert_eq!(doc.id.0, live.id.0);
+        assert_eq!(doc.version, live.version);
+
+        let versions: Vec<u64> = storage
+            .history(&live.id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|revision| revision.version)
+            .collect();
+        assert!(
+            versions.windows(2).all(|pair| pair[0] < pair[1]),
+            "versions of {} not increasing: {versions:?}",
+            KEYS[key]
+        );
+        assert_eq!(versions.last(), Some(&live.version));
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// Query fragments that stress quoting, negation, and FTS5 operator syntax.
+fn query_text() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        Just("\"".to_string()),
+        Just("-".to_string()),
+        Just(" ".to_string()),
+        Just("*".to_string()),
+        Just("(".to_string()),
+        Just(")".to_string()),
+        Just(":".to_string()),
+        Just("NOT".to_string()),
+        Just("OR".to_string()),
+        Just("NEAR".to_string()),
+        Just("^".to_string()),
+        Just("\0".to_string()),
+        "[a-z]{1,6}",
+        "\\PC{1,4}",
+    ];
+    prop::collection::vec(piece, 0..12).prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    #[test]
+    fn random_operations_keep_the_store_consistent(
+        ops in prop::collection::vec(op(), 1..40)
+    ) {
+        runtime().block_on(async {
+            let storage = memory_storage().await;
+            let mut model = BTreeMap::new();
+            for op in &ops {
+                apply(&storage, &mut model, op).await;
+                check_invariants(&storage, &model).await;
+            }
+        });
+    }
+
+    #[test]
+    fn any_query_text_is_valid_fts(text in query_text()) {
+        let expression = match_expression(&text, false);
+        let code_expression = match_expression(&text, true);
+        prop_assert_eq!(expression.is_ok(), code_expression.is_ok());
+
+        runtime().block_on(async {
+            let storage = memory_storage().await;
+            storage.store(draft("notes/a", &[0, 1], false)).await.unwrap();
+            for code in [false, true] {
+                let result = storage
+                    .search(SearchQuery {
+                        project: Some(ProjectId::from(PROJECT)),
+                        text: text.clone(),
+                        code,
+                        ..SearchQuery::default()
+                    })
+                    .await;
+                match (&expression, result) {
+                    (Ok(_), Ok(_)) => {}
+                    (Err(_), Err(err)) => assert!(
+                        err.to_string().contains("at least one term"),
+                        "{text:?}: {err}"
+                    ),
+                    (_, result) => panic!("{text:?}: parser and search disagree: {result:?}"),
+                }
+            }
+        });
+    }
+}
@@ -1,14 +1,19 @@
+mod common;
+
 use std::str::FromStr;
 
 use chrono::{TimeZone, Utc};
 use context_core::{
-    sqlite::SqliteStorage, Document, DocumentId, Key, ProjectId, SearchQuery, SourceType, Storage,
+    sqlite::SqliteStorage, ContextError, DeleteMode, Document, DocumentId, ListQuery, ListSort,
+    ProjectId, SearchQuery, Storage, Visibility,
 };
 use sqlx::{
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
     SqlitePool,
 };
 
+use common::document;
+
 type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 async fn test_pool() -> TestResult<SqlitePool> {
@@ -34,19 +39,13 @@ async fn test_storage() -> TestResult<SqliteStorage> {
 fn sample_document(id: &str, project: &str, key: &str, body: &str) -> Document {
     let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
     Document {
-        id: DocumentId(id.to_string()),
         project: ProjectId::from(project),
-        key: Some(Key::from(key)),
         namespace: Some("notes".to_string()),
         title: Some("Sample".to_string()),
         tags: vec!["rust".to_string()],
-        body_markdown: body.to_string(),
         created_at: now,
         updated_at: now,
-        source: SourceType::User,
-        version: 1,
-        ttl_seconds: None,
-        deleted_at: None,
+        ..document(id, key, body)
     }
 }
 
@@ -104,6 +103,48 @@ async fn put_overwrites_existing_document_by_id() -> TestResult<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn version_as_of_returns_latest_revision_before_timestamp() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let mut doc = sample_document("doc-1", "demo", "runbook", "v1 body");
+    storage.put(doc.clone()).await?;
+
+    doc.body_markdown = "v2 body".to_string();
+    doc.version = 2;
+    doc.updated_at += chrono::Duration::hours(2);
+    storage.put(doc.clone()).await?;
+
+    let one_hour_in = doc.created_at + chrono::Duration::hours(1);
+    let at_v1 = storage
+        .version_as_of(&doc.id, one_hour_in)
+        .await?
+        .expect("v1 existed");
+    assert_eq!(at_v1.version, 1);
+    assert_eq!(at_v1.body_markdown, "v1 body");
+    assert_eq!(at_v1.updated_at, doc.created_at);
+
+    let latest = storage
+        .version_as_of(&doc.id, doc.updated_at)
+        .await?
+        .expect("v2 existed");
+    assert_eq!(latest.version, 2);
+
+    let before = storage
+        .version_as_of(&doc.id, doc.created_at - chrono::Duration::seconds(1))
+        .await?;
+    assert!(
+        before.is_none(),
+        "nothing existed before the first revision"
+    );
+
+    assert_eq!(
+        storage.get_by_id(&doc.id).await?.map(|d| d.version),
+        Some(2)
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn search_returns_matches_in_project() -> TestResult<()> {
     let storage = test_storage().await?;
@@ -124,6 +165,7 @@ async fn search_returns_matches_in_project() -> TestResult<()> {
             project: Some(rust_doc.project.clone()),
             text: "rust".to_string(),
             limit: None,
+            ..Default::default()
         })
         .await?;
 
@@ -154,6 +196,7 @@ async fn search_prefers_recent_documents() -> TestResult<()> {
             project: Some("demo".to_string()),
             text: "rust".to_string(),
             limit: None,
+            ..Default::default()
         })
         .await?;
 
@@ -184,6 +227,7 @@ async fn tag_matches_are_ranked_higher() -> TestResult<()> {
             project: Some("demo".to_string()),
             text: "rust install".to_string(),
             limit: None,
+            ..Default::default()
         })
         .await?;
 
@@ -216,6 +260,7 @@ async fn ttl_expired_documents_are_filtered_out() -> TestResult<()> {
             project: Some(doc.project.clone()),
             text: "expired".to_string(),
             limit: None,
+            ..Default::default()
         })
         .await?;
     assert!(
@@ -248,6 +293,7 @@ async fn soft_deleted_documents_are_ignored() -> TestResult<()> {
             project: Some(doc.project.clone()),
             text: "body".to_string(),
             limit: None,
+            ..Default::default()
         })
         .await?;
     assert!(
@@ -257,3 +303,856 @@ async fn soft_deleted_documents_are_ignored() -> TestResult<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn key_of_soft_deleted_document_can_be_reused() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let mut old = sample_document("doc-old", "demo", "reused", "old body");
+    old.deleted_at = Some(Utc::now());
+    storage.put(old).await?;
+
+    let new = sample_document("doc-new", "demo", "reused", "new body");
+    storage.put(new).await?;
+
+    let fetched = storage
+        .get_by_key(&"demo".to_string(), "reused")
+        .await?
+        .expect("new document should own the key");
+    assert_eq!(fetched.id.0, "doc-new");
+
+    let old_history = storage.history(&DocumentId("doc-old".to_string())).await?;
+    assert_eq!(old_history.len(), 1);
+    assert_eq!(old_history[0].key.as_deref(), Some("reused"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_brings_a_tombstone_back_into_search() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    storage
+        .put(sample_document(
+            "doc-1",
+            "demo",
+            "runbook",
+            "restart the gateway",
+        ))
+        .await?;
+    let id = DocumentId("doc-1".to_string());
+    storage.delete_by_id(&id, DeleteMode::Soft).await?;
+
+    let only_deleted = ListQuery {
+        only_deleted: true,
+        ..Default::default()
+    };
+    assert_eq!(storage.count(&project, &only_deleted).await?, 1);
+    let tombstone = storage
+        .deleted_by_key(&project, "runbook")
+        .await?
+        .expect("tombstone holds its key");
+    assert_eq!(tombstone.id.0, "doc-1");
+
+    let restored = storage.restore(&id).await?.expect("restored");
+    assert_eq!(restored.version, 3);
+    assert!(restored.deleted_at.is_none());
+    assert_eq!(restored.body_markdown, "restart the gateway");
+    assert_eq!(storage.count(&project, &only_deleted).await?, 0);
+    let hits = storage
+        .search(SearchQuery {
+            project: Some(project.clone()),
+            text: "gateway".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+    assert!(storage.restore(&id).await?.is_none(), "already live");
+
+    // Once the key is reused, restoring the old document would take it away.
+    storage.delete_by_id(&id, DeleteMode::Soft).await?;
+    storage
+        .put(sample_document("doc-2", "demo", "runbook", "new runbook"))
+        .await?;
+    assert!(storage.deleted_by_key(&project, "runbook").await?.is_none());
+    let err = storage.restore(&id).await.unwrap_err().to_string();
+    assert!(err.contains("now belongs to document doc-2"), "{err}");
+
+    storage
+        .delete_by_id(&DocumentId("doc-2".to_string()), DeleteMode::Soft)
+        .await?;
+    let restored = storage.restore(&id).await?.expect("restored");
+    assert_eq!(restored.key.as_deref(), Some("runbook"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_excludes_terms_phrases_tags_and_namespaces() -> TestResult<()> {
+    let storage = test_storage().await?;
+
+    let plain = sample_document("doc-plain", "demo", "plain", "deploy checklist");
+    let mut legacy = sample_document("doc-legacy", "demo", "legacy", "deploy via old pipeline");
+    legacy.tags = vec!["legacy".to_string()];
+    let mut scratch = sample_document("doc-scratch", "demo", "scratch", "deploy scratch notes");
+    scratch.namespace = Some("scratch".to_string());
+    let phrase = sample_document("doc-phrase", "demo", "phrase", "deploy to staging cluster");
+    for doc in [plain, legacy, scratch, phrase] {
+        storage.put(doc).await?;
+    }
+
+    let ids = |hits: Vec<context_core::SearchHit>| {
+        let mut ids: Vec<String> = hits.into_iter().map(|h| h.document.id.0).collect();
+        ids.sort();
+        ids
+    };
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: r#"deploy -"staging cluster" -pipeline"#.to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(ids(hits), vec!["doc-plain", "doc-scratch"]);
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "deploy".to_string(),
+            exclude_tags: vec!["legacy".to_string()],
+            exclude_namespaces: vec!["scratch".to_string()],
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(ids(hits), vec!["doc-phrase", "doc-plain"]);
+
+    let only_negative = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "-deploy".to_string(),
+            ..Default::default()
+        })
+        .await;
+    assert!(only_negative.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_survives_many_exclusions_and_nul_bytes() -> TestResult<()> {
+    let storage = test_storage().await?;
+    storage
+        .put(sample_document(
+            "doc-1",
+            "demo",
+            "plain",
+            "deploy checklist",
+        ))
+        .await?;
+
+    // One NOT per term would nest past FTS5's expression depth limit.
+    let exclusions: Vec<String> = (0..300).map(|i| format!("-word{i}")).collect();
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: format!("deploy {}", exclusions.join(" ")),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+
+    // FTS5 would read NUL as the end of the expression.
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "deploy\0checklist".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_can_be_restricted_to_a_key_prefix_or_key_set() -> TestResult<()> {
+    let storage = test_storage().await?;
+    for (id, key) in [
+        ("doc-a", "runbooks/deploy"),
+        ("doc-b", "runbooks/rollback"),
+        ("doc-c", "notes/deploy"),
+    ] {
+        storage
+            .put(sample_document(id, "demo", key, "deploy steps"))
+            .await?;
+    }
+
+    let keys = |hits: Vec<context_core::SearchHit>| {
+        let mut keys: Vec<String> = hits.into_iter().filter_map(|h| h.document.key).collect();
+        keys.sort();
+        keys
+    };
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "deploy".to_string(),
+            key_prefix: Some("runbooks/".to_string()),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(keys(hits), vec!["runbooks/deploy", "runbooks/rollback"]);
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "deploy".to_string(),
+            keys: vec!["notes/deploy".to_string(), "runbooks/rollback".to_string()],
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(keys(hits), vec!["notes/deploy", "runbooks/rollback"]);
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "deploy".to_string(),
+            key_prefix: Some("runbooks/".to_string()),
+            keys: vec!["notes/deploy".to_string()],
+            ..Default::default()
+        })
+        .await?;
+    assert!(hits.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_hits_break_their_score_down() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let mut tagged = sample_document("doc-tagged", "demo", "tagged", "rust search notes");
+    tagged.tags = vec!["search".to_string()];
+    storage.put(tagged).await?;
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "search".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    let parts = hits[0].breakdown;
+    assert!(parts.bm25 > 0.0);
+    assert_eq!(parts.tag_bonus, 0.5);
+    assert_eq!(parts.pin_boost, 0.0);
+    assert!(
+        (parts.bm25 + parts.recency + parts.tag_bonus + parts.pin_boost - hits[0].score).abs()
+            < f32::EPSILON
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn sweep_orphans_counts_then_removes_stray_rows() -> TestResult<()> {
+    let storage = test_storage().await?;
+    storage
+        .put(sample_document("doc-1", "demo", "kept", "searchable body"))
+        .await?;
+
+    // Simulate the debris a crash or hand edit can leave behind.
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(storage.pool())
+        .await?;
+    sqlx::query(
+        "INSERT INTO documents_fts (rowid, document_id, project_id, title, body, tags, namespace) \
+         VALUES (9999, 'ghost', 'demo', '', 'searchable ghost', '', '')",
+    )
+    .execute(storage.pool())
+    .await?;
+    sqlx::query(
+        "INSERT INTO document_versions (document_id, version, body_markdown, source) \
+         VALUES ('ghost', 1, 'gone', 'user')",
+    )
+    .execute(storage.pool())
+    .await?;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(storage.pool())
+        .await?;
+
+    let checked = storage.sweep_orphans(true).await?;
+    assert_eq!(checked.orphaned_fts_rows, 1);
+    assert_eq!(checked.orphaned_versions, 1);
+    assert!(!checked.repaired);
+
+    let repaired = storage.sweep_orphans(false).await?;
+    assert_eq!(repaired.orphaned_fts_rows, 1);
+    assert!(repaired.repaired);
+
+    let again = storage.sweep_orphans(true).await?;
+    assert_eq!((again.orphaned_fts_rows, again.orphaned_versions), (0, 0));
+    let history = storage.history(&DocumentId("doc-1".to_string())).await?;
+    assert_eq!(history.len(), 1, "live history is untouched");
+    Ok(())
+}
+
+#[tokio::test]
+async fn deferred_indexing_indexes_the_batch_and_keeps_the_triggers() -> TestResult<()> {
+    let storage = test_storage().await?.with_deferred_indexing(true);
+    storage
+        .put(sample_document("doc-1", "demo", "plan", "original wording"))
+        .await?;
+
+    let mut drafts = vec![sample_document("draft", "demo", "plan", "revised wording")];
+    for n in 0..50 {
+        let key = format!("bulk/{n}");
+        drafts.push(sample_document(
+            &format!("bulk-{n}"),
+            "demo",
+            &key,
+            "bulkterm",
+        ));
+    }
+    storage.store_all(drafts).await?;
+
+    let search = |text: &str| SearchQuery {
+        project: Some(ProjectId::from("demo")),
+        text: text.to_string(),
+        limit: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(storage.search(search("bulkterm")).await?.len(), 50);
+    assert_eq!(storage.search(search("revised")).await?.len(), 1);
+    assert!(storage.search(search("original")).await?.is_empty());
+
+    // Writes outside a batch still go through the triggers.
+    storage
+        .put(sample_document("doc-2", "demo", "later", "afterwards"))
+        .await?;
+    assert_eq!(storage.search(search("afterwards")).await?.len(), 1);
+    let report = storage.sweep_orphans(true).await?;
+    assert_eq!(report.orphaned_fts_rows, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn conditional_writes_report_version_conflicts() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    storage
+        .put(sample_document("doc-1", "demo", "plan", "v1"))
+        .await?;
+
+    let revised = storage
+        .store_expecting(sample_document("draft", "demo", "plan", "v2"), 1)
+        .await?;
+    assert_eq!((revised.id.0.as_str(), revised.version), ("doc-1", 2));
+
+    let stale = storage
+        .store_expecting(sample_document("draft", "demo", "plan", "v3"), 1)
+        .await
+        .unwrap_err();
+    let conflict = stale.version_conflict().expect("version conflict");
+    assert_eq!((conflict.expected, conflict.actual), (1, Some(2)));
+
+    assert!(storage
+        .delete_expecting(&project, "plan", Some(1))
+        .await
+        .is_err());
+    let deleted = storage
+        .delete_expecting(&project, "plan", Some(2))
+        .await?
+        .expect("deleted document");
+    assert!(deleted.deleted_at.is_some());
+    assert!(storage
+        .delete_expecting(&project, "plan", None)
+        .await?
+        .is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_filters_and_pages_with_a_cursor() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    for (i, key) in ["auth/tokens", "auth/sessions", "deploy", "auth/old"]
+        .iter()
+        .enumerate()
+    {
+        let mut doc = sample_document(&format!("doc-{i}"), "demo", key, "body");
+        doc.updated_at = Utc
+            .with_ymd_and_hms(2024, 1, 1 + i as u32, 0, 0, 0)
+            .unwrap();
+        if *key == "deploy" {
+            doc.tags = vec!["ops".to_string()];
+        }
+        if *key == "auth/old" {
+            doc.deleted_at = Some(Utc::now());
+        }
+        storage.put(doc).await?;
+    }
+
+    let keys = |page: &context_core::ListPage| -> Vec<String> {
+        page.documents
+            .iter()
+            .map(|doc| doc.key.clone().unwrap())
+            .collect()
+    };
+
+    let prefixed = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                key_prefix: Some("auth/".to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(keys(&prefixed), vec!["auth/sessions", "auth/tokens"]);
+
+    let tagged = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                tags: vec!["ops".to_string()],
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(keys(&tagged), vec!["deploy"]);
+
+    let with_deleted = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                key_prefix: Some("auth/".to_string()),
+                include_deleted: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(
+        keys(&with_deleted),
+        vec!["auth/old", "auth/sessions", "auth/tokens"]
+    );
+
+    let first = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                limit: Some(2),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(keys(&first), vec!["deploy", "auth/sessions"]);
+    let second = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                limit: Some(2),
+                cursor: first.next_cursor.clone(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(keys(&second), vec!["auth/tokens"]);
+    assert!(second.next_cursor.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_sorts_by_created_or_key_and_pages_in_that_order() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    // Created in one order, updated in the reverse one, keyed in neither.
+    for (i, key) in ["m|middle", "z-last", "a-first"].iter().enumerate() {
+        let mut doc = sample_document(&format!("doc-{i}"), "demo", key, "body");
+        doc.created_at = Utc
+            .with_ymd_and_hms(2024, 1, 1 + i as u32, 0, 0, 0)
+            .unwrap();
+        doc.updated_at = Utc
+            .with_ymd_and_hms(2024, 2, 3 - i as u32, 0, 0, 0)
+            .unwrap();
+        storage.put(doc).await?;
+    }
+
+    let keys = |page: &context_core::ListPage| -> Vec<String> {
+        page.documents
+            .iter()
+            .map(|doc| doc.key.clone().unwrap())
+            .collect()
+    };
+    let list = |sort: ListSort, cursor: Option<String>| {
+        let storage = &storage;
+        let project = &project;
+        async move {
+            storage
+                .list_page(
+                    project,
+                    &ListQuery {
+                        sort,
+                        limit: Some(2),
+                        cursor,
+                        ..Default::default()
+                    },
+                )
+                .await
+        }
+    };
+
+    let updated = list(ListSort::Updated, None).await?;
+    assert_eq!(keys(&updated), vec!["m|middle", "z-last"]);
+
+    let created = list(ListSort::Created, None).await?;
+    assert_eq!(keys(&created), vec!["a-first", "z-last"]);
+    let rest = list(ListSort::Created, created.next_cursor).await?;
+    assert_eq!(keys(&rest), vec!["m|middle"]);
+
+    let by_key = list(ListSort::Key, None).await?;
+    assert_eq!(keys(&by_key), vec!["a-first", "m|middle"]);
+    let rest = list(ListSort::Key, by_key.next_cursor).await?;
+    assert_eq!(keys(&rest), vec!["z-last"]);
+    assert!(rest.next_cursor.is_none());
+
+    assert!(matches!(
+        "size".parse::<ListSort>(),
+        Err(ContextError::Validation(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn delete_modes_decide_what_history_and_restore_keep() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    for key in ["soft", "purged", "hard"] {
+        storage
+            .store(sample_document(key, "demo", key, "first"))
+            .await?;
+        storage
+            .store(sample_document(key, "demo", key, "second"))
+            .await?;
+    }
+
+    let soft = storage
+        .delete_by_key(&project, "soft", DeleteMode::Soft)
+        .await?
+        .expect("soft deleted");
+    assert_eq!(soft.version, 3);
+    assert_eq!(storage.history(&soft.id).await?.len(), 3);
+
+    let purged = storage
+        .delete_by_key(&project, "purged", DeleteMode::Purge)
+        .await?
+        .expect("purged");
+    let history = storage.history(&purged.id).await?;
+    assert_eq!(history.len(), 1);
+    assert!(history[0].deleted_at.is_some());
+    let restored = storage.restore(&purged.id).await?.expect("restorable");
+    assert_eq!(restored.body_markdown, "second");
+
+    let hard = storage
+        .delete_by_id(&DocumentId("hard".to_string()), DeleteMode::Hard)
+        .await?
+        .expect("hard deleted");
+    assert!(hard.deleted_at.is_some());
+    assert!(storage.history(&hard.id).await?.is_empty());
+    assert!(storage.restore(&hard.id).await?.is_none());
+    assert!(storage.deleted_by_key(&project, "hard").await?.is_none());
+    assert!(storage
+        .search(SearchQuery {
+            project: Some(project.clone()),
+            text: "second".to_string(),
+            ..Default::default()
+        })
+        .await?
+        .iter()
+        .all(|hit| hit.document.id.0 != hard.id.0));
+
+    assert!(storage
+        .delete_by_key(&project, "hard", DeleteMode::Hard)
+        .await?
+        .is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn count_and_delete_go_through_the_trait() -> TestResult<()> {
+    async fn tidy(storage: &dyn Storage, project: &ProjectId) -> TestResult<(u64, u64)> {
+        let before = storage.count(project, &ListQuery::default()).await?;
+        let page = storage
+            .list_page(
+                project,
+                &ListQuery {
+                    limit: Some(1),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let deleted = storage
+            .delete_by_id(&page.documents[0].id, DeleteMode::Soft)
+            .await?
+            .expect("a live document to delete");
+        assert!(deleted.deleted_at.is_some());
+        assert_eq!(deleted.version, 2);
+        assert!(storage
+            .delete_by_id(&deleted.id, DeleteMode::Soft)
+            .await?
+            .is_none());
+        let after = storage.count(project, &ListQuery::default()).await?;
+        Ok((before, after))
+    }
+
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    for (i, key) in ["a", "b", "c"].iter().enumerate() {
+        storage
+            .put(sample_document(&format!("doc-{i}"), "demo", key, "body"))
+            .await?;
+    }
+
+    assert_eq!(tidy(&storage, &project).await?, (3, 2));
+    let with_deleted = ListQuery {
+        include_deleted: true,
+        limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(storage.count(&project, &with_deleted).await?, 3);
+    assert!(storage
+        .delete_by_id(&DocumentId("missing".to_string()), DeleteMode::Soft)
+        .await?
+        .is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn expiry_can_be_moved_and_filtered_on() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    let now = Utc::now();
+    let mut doc = sample_document("doc-1", "demo", "scratch", "temporary");
+    doc.created_at = now;
+    doc.updated_at = now;
+    storage.put(doc).await?;
+    let id = DocumentId("doc-1".to_string());
+    let expiring_within = |hours: i64| ListQuery {
+        expiring_before: Some(now + chrono::Duration::hours(hours)),
+        ..Default::default()
+    };
+    assert_eq!(storage.count(&project, &expiring_within(48)).await?, 0);
+
+    let doc = storage
+        .set_expiry(&id, Some(now + chrono::Duration::hours(24)))
+        .await?
+        .expect("live document");
+    assert_eq!(doc.ttl_seconds, Some(24 * 3600));
+    assert_eq!(doc.expires_at(), Some(now + chrono::Duration::hours(24)));
+    assert_eq!(storage.count(&project, &expiring_within(48)).await?, 1);
+    assert_eq!(storage.count(&project, &expiring_within(12)).await?, 0);
+    assert_eq!(storage.history(&id).await?.len(), 1, "no new revision");
+
+    storage.set_expiry(&id, None).await?;
+    assert_eq!(storage.count(&project, &expiring_within(48)).await?, 0);
+
+    storage
+        .set_expiry(&id, Some(now - chrono::Duration::hours(1)))
+        .await?;
+    assert!(storage.get_by_id(&id).await?.is_none(), "expired at once");
+    assert!(storage.set_expiry(&id, None).await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn store_all_writes_every_draft_or_none() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    storage
+        .store(sample_document("old", "demo", "notes/a", "first"))
+        .await?;
+
+    let stored = storage
+        .store_all(vec![
+            sample_document("new-a", "demo", "notes/a", "second"),
+            sample_document("new-b", "demo", "notes/b", "b"),
+            sample_document("new-b2", "demo", "notes/b", "b again"),
+        ])
+        .await?;
+    let versions: Vec<(String, u64)> = stored
+        .iter()
+        .map(|doc| (doc.id.0.clone(), doc.version))
+        .collect();
+    assert_eq!(
+        versions,
+        [
+            ("old".to_string(), 2),
+            ("new-b".to_string(), 1),
+            ("new-b".to_string(), 2)
+        ]
+    );
+    let b = storage.get_by_key(&project, "notes/b").await?.unwrap();
+    assert_eq!(b.body_markdown, "b again");
+
+    let mut bad = sample_document("bad", "demo", "notes/c", "c");
+    bad.namespace = Some(" padded ".to_string());
+    let err = storage
+        .store_all(vec![sample_document("c", "demo", "notes/d", "d"), bad])
+        .await;
+    assert!(err.is_err());
+    assert!(storage.get_by_key(&project, "notes/d").await?.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn visibility_is_kept_across_revisions_and_filters_lists_and_search() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    let mut scratch = sample_document("scratch", "demo", "notes/scratch", "rollout ideas");
+    scratch.visibility = Some(Visibility::Private);
+    storage.store(scratch).await?;
+    storage
+        .store(sample_document(
+            "plan",
+            "demo",
+            "notes/plan",
+            "rollout plan",
+        ))
+        .await?;
+
+    let revised = storage
+        .store(sample_document(
+            "later",
+            "demo",
+            "notes/scratch",
+            "rollout ideas, v2",
+        ))
+        .await?;
+    assert_eq!(revised.version, 2);
+    assert_eq!(revised.visibility, Some(Visibility::Private));
+    let history = storage.history(&revised.id).await?;
+    assert!(history.iter().all(|doc| !doc.is_shared()));
+    let plan = storage.get_by_key(&project, "notes/plan").await?.unwrap();
+    assert_eq!(plan.visibility, None);
+    assert_eq!(plan.visibility(), Visibility::Project);
+
+    let listed = |page: context_core::ListPage| -> Vec<String> {
+        page.documents
+            .into_iter()
+            .filter_map(|doc| doc.key)
+            .collect()
+    };
+    let all = storage.list_page(&project, &ListQuery::default()).await?;
+    assert_eq!(all.documents.len(), 2);
+    let shared = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                visibility: Visibility::SHARED.to_vec(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(listed(shared), ["notes/plan"]);
+    let private = storage
+        .list_page(
+            &project,
+            &ListQuery {
+                visibility: vec![Visibility::Private],
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(listed(private), ["notes/scratch"]);
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some(project),
+            text: "rollout".to_string(),
+            visibility: Visibility::SHARED.to_vec(),
+            ..Default::default()
+        })
+        .await?;
+    let keys: Vec<_> = hits
+        .iter()
+        .filter_map(|hit| hit.document.key.clone())
+        .collect();
+    assert_eq!(keys, ["notes/plan"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn metadata_merges_across_revisions_and_is_filtered_and_searched() -> TestResult<()> {
+    let storage = test_storage().await?;
+    let project = ProjectId::from("demo");
+    let mut incident = sample_document("incident", "demo", "incidents/db", "replica lag");
+    incident.metadata = serde_json::json!({"ticket": "ABC-123", "severity": "high", "pages": 3})
+        .as_object()
+        .cloned()
+        .unwrap();
+    storage.store(incident).await?;
+    storage
+        .store(sample_document(
+            "other",
+            "demo",
+            "incidents/cdn",
+            "cache misses",
+        ))
+        .await?;
+
+    let mut revision = sample_document("later", "demo", "incidents/db", "replica lag, fixed");
+    revision.metadata = serde_json::json!({"severity": null, "url": "https://example.com/pr/7"})
+        .as_object()
+        .cloned()
+        .unwrap();
+    let revised = storage.store(revision).await?;
+    assert_eq!(revised.version, 2);
+    assert_eq!(
+        serde_json::Value::Object(revised.metadata.clone()),
+        serde_json::json!({"ticket": "ABC-123", "pages": 3, "url": "https://example.com/pr/7"})
+    );
+    let history = storage.history(&revised.id).await?;
+    assert_eq!(history[0].metadata["severity"], "high");
+    assert!(!history[1].metadata.contains_key("severity"));
+
+    let matching = |pairs: &[(&str, &str)]| ListQuery {
+        metadata: pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let keys = |page: context_core::ListPage| -> Vec<String> {
+        page.documents
+            .into_iter()
+            .filter_map(|doc| doc.key)
+            .collect()
+    };
+    let found = storage
+        .list_page(
+            &project,
+            &matching(&[("ticket", "ABC-123"), ("pages", "3")]),
+        )
+        .await?;
+    assert_eq!(keys(found), ["incidents/db"]);
+    let found = storage
+        .list_page(&project, &matching(&[("ticket", "ABC-999")]))
+        .await?;
+    assert!(found.documents.is_empty());
+    assert_eq!(
+        storage
+            .count(&project, &matching(&[("severity", "high")]))
+            .await?,
+        0
+    );
+
+    let hits = storage
+        .search(SearchQuery {
+            project: Some(project),
+            text: "ABC".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].document.key.as_deref(), Some("incidents/db"));
+    Ok(())
+}
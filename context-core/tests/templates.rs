@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use context_core::{
+    sqlite::SqliteStorage,
+    templates::{render, variables},
+};
+use tempfile::tempdir;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn render_fills_blanks_and_leaves_other_braces_alone() {
+    let body = "# {{ title }}\n\n{{title}} by {{owner}}; `{{not a var}}`, {{}}, {{{status}}}\n";
+    assert_eq!(variables(body), ["title", "owner", "status"]);
+    let rendered = render(
+        body,
+        &vars(&[
+            ("title", "Use {{owner}}"),
+            ("owner", "ops"),
+            ("status", "ok"),
+        ]),
+    )
+    .unwrap();
+    assert_eq!(
+        rendered,
+        "# Use {{owner}}\n\nUse {{owner}} by ops; `{{not a var}}`, {{}}, {ok}\n"
+    );
+}
+
+#[test]
+fn render_names_every_missing_value() {
+    let err = render("{{a}} {{b}} {{a}} {{c}}", &vars(&[("b", "2")])).unwrap_err();
+    assert_eq!(err.to_string(), "No value for a, c; pass --var a=...");
+}
+
+#[tokio::test]
+async fn templates_are_stored_per_project_and_replaced_by_name() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let demo = "demo".to_string();
+
+    let first = storage
+        .put_template(&demo, "adr", None, "# ADR: {{title}}\n")
+        .await?;
+    assert_eq!(first.variables(), ["title"]);
+    let replaced = storage
+        .put_template(&demo, "adr", Some("Decisions".to_string()), "# {{title}}\n")
+        .await?;
+    assert_eq!(replaced.created_at, first.created_at);
+    assert_eq!(replaced.body_markdown, "# {{title}}\n");
+    storage
+        .put_template(&demo, "postmortem", None, "# Incident\n")
+        .await?;
+    storage
+        .put_template(&"other".to_string(), "adr", None, "elsewhere")
+        .await?;
+
+    let names: Vec<_> = storage
+        .templates(&demo)
+        .await?
+        .into_iter()
+        .map(|template| template.name)
+        .collect();
+    assert_eq!(names, ["adr", "postmortem"]);
+    assert!(storage
+        .put_template(&demo, "bad name", None, "body")
+        .await
+        .is_err());
+
+    assert!(storage.delete_template(&demo, "adr").await?);
+    assert!(!storage.delete_template(&demo, "adr").await?);
+    assert!(storage.template(&demo, "adr").await?.is_none());
+    assert!(storage
+        .template(&"other".to_string(), "adr")
+        .await?
+        .is_some());
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use context_core::tokens::count_tokens;
+
+#[test]
+fn token_counts_follow_the_tokenizer() {
+    assert_eq!(count_tokens(""), 0);
+    assert_eq!(count_tokens("hello world"), 2);
+    assert!(count_tokens(&"word ".repeat(100)) >= 100);
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use context_core::{sqlite::SqliteStorage, traced::TracedStorage, SearchQuery, Storage};
+use serde_json::Value;
+use tempfile::tempdir;
+
+use common::document;
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn storage_calls_are_logged_with_operation_project_and_rows() -> TestResult<()> {
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let temp = tempdir()?;
+    let storage = TracedStorage::new(SqliteStorage::open(&temp.path().join("db.sqlite")).await?);
+    storage
+        .put(document("d1", "deploy", "Canary first."))
+        .await?;
+    storage
+        .store(document("d2", "rollback", "Roll back fast."))
+        .await?;
+    let hits = storage
+        .search(SearchQuery {
+            project: Some("demo".to_string()),
+            text: "canary".to_string(),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(hits.len(), 1);
+    // So are the backend's own methods.
+    assert_eq!(storage.tag_counts(&"demo".to_string()).await?.len(), 0);
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone())?;
+    let finished: Vec<Value> = output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|event| event["fields"]["message"] == "Storage call finished")
+        .collect();
+    let calls: Vec<(&str, u64)> = finished
+        .iter()
+        .map(|event| {
+            (
+                event["fields"]["operation"].as_str().unwrap_or_default(),
+                event["fields"]["rows"].as_u64().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        calls,
+        vec![("put", 1), ("store", 1), ("search", 1), ("tag_counts", 0)]
+    );
+    assert!(finished
+        .iter()
+        .all(|event| event["fields"]["project"] == "demo"
+            && event["fields"]["duration_ms"].is_number()));
+    Ok(())
+}
@@ -0,0 +1,8 @@
+// Compiled with protox so building does not need a system `protoc`.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let proto = "proto/context/v1/context.proto";
+    println!("cargo:rerun-if-changed={proto}");
+    let descriptors = protox::compile([proto], ["proto"])?;
+    tonic_build::configure().compile_fds(descriptors)?;
+    Ok(())
+}
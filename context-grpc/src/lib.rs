@@ -0,0 +1,367 @@
+//! gRPC access to a context home's `Storage` operations (see `proto/context/v1/context.proto`).
+
+// The generated service trait fixes `tonic::Status` as the error type.
+#![allow(clippy::result_large_err)]
+
+use std::{net::SocketAddr, pin::Pin};
+
+use chrono::{DateTime, TimeZone, Utc};
+use context_core::{
+    sqlite::SqliteStorage, traced::TracedStorage, Conflict, ContextError, Document, DocumentId,
+    ListQuery, SearchHit, SearchQuery, SourceMeta, SourceType, Storage, Visibility,
+};
+use futures::{Stream, StreamExt};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+pub mod proto {
+    tonic::include_proto!("context.v1");
+}
+
+use proto::storage_server::StorageServer;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Documents buffered per `ListDocuments` call ahead of a slow reader.
+const LIST_BUFFER: usize = 32;
+
+/// The gRPC `Storage` service over one SQLite store. Like `context-web` it serves only
+/// shared documents: a private one answers `NOT_FOUND`, as if it were missing.
+#[derive(Clone)]
+pub struct ContextService {
+    storage: TracedStorage<SqliteStorage>,
+}
+
+impl ContextService {
+    pub fn new(storage: SqliteStorage) -> Self {
+        Self {
+            storage: TracedStorage::new(storage),
+        }
+    }
+
+    pub fn into_server(self) -> StorageServer<Self> {
+        StorageServer::new(self)
+    }
+}
+
+/// Serve `storage` on `addr` until the process is stopped.
+pub async fn serve(storage: SqliteStorage, addr: SocketAddr) -> anyhow::Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(ContextService::new(storage).into_server())
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+/// The status for a storage failure: its kind picks the code.
+fn internal(err: ContextError) -> Status {
+    match &err {
+        ContextError::NotFound(_) => Status::not_found(err.to_string()),
+        ContextError::Conflict(Conflict::Version(_)) => {
+            Status::failed_precondition(err.to_string())
+        }
+        ContextError::Conflict(_) => Status::already_exists(err.to_string()),
+        ContextError::Validation(_) => Status::invalid_argument(err.to_string()),
+        ContextError::Quota(_) => Status::resource_exhausted(err.to_string()),
+        ContextError::Timeout(_) => Status::deadline_exceeded(err.to_string()),
+        ContextError::Storage(_) | ContextError::Sync(_) => {
+            tracing::error!(error = %err, "Request failed");
+            Status::internal(err.to_string())
+        }
+    }
+}
+
+fn timestamp(at: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: at.timestamp(),
+        nanos: at.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn from_timestamp(at: &prost_types::Timestamp) -> Result<DateTime<Utc>, Status> {
+    u32::try_from(at.nanos)
+        .ok()
+        .and_then(|nanos| Utc.timestamp_opt(at.seconds, nanos).single())
+        .ok_or_else(|| Status::invalid_argument("Timestamp is out of range."))
+}
+
+impl From<Document> for proto::Document {
+    fn from(doc: Document) -> Self {
+        Self {
+            id: doc.id.0,
+            project: doc.project,
+            key: doc.key,
+            namespace: doc.namespace,
+            title: doc.title,
+            tags: doc.tags,
+            body_markdown: doc.body_markdown,
+            created_at: Some(timestamp(doc.created_at)),
+            updated_at: Some(timestamp(doc.updated_at)),
+            source: doc.source.as_str().to_string(),
+            source_meta: doc.source_meta.map(|meta| proto::SourceMeta {
+                tool: meta.tool,
+                session: meta.session,
+            }),
+            version: doc.version,
+            ttl_seconds: doc.ttl_seconds,
+            deleted_at: doc.deleted_at.map(timestamp),
+        }
+    }
+}
+
+impl From<SearchHit> for proto::SearchHit {
+    fn from(hit: SearchHit) -> Self {
+        Self {
+            document: Some(hit.document.into()),
+            score: hit.score,
+            breakdown: Some(proto::ScoreBreakdown {
+                bm25: hit.breakdown.bm25,
+                recency: hit.breakdown.recency,
+                tag_bonus: hit.breakdown.tag_bonus,
+                pin_boost: hit.breakdown.pin_boost,
+            }),
+        }
+    }
+}
+
+/// The document a `Put` asks to write; server-owned fields are filled in here.
+fn draft(doc: proto::Document) -> Result<Document, Status> {
+    if doc.project.trim().is_empty() {
+        return Err(Status::invalid_argument("Document needs a project."));
+    }
+    if doc.body_markdown.trim().is_empty() {
+        return Err(Status::invalid_argument("Document body is empty."));
+    }
+    let source = if doc.source.is_empty() {
+        SourceType::Agent
+    } else {
+        SourceType::from_stored(&doc.source)
+    };
+    if matches!(source, SourceType::Unknown) {
+        return Err(Status::invalid_argument(format!(
+            "Unknown source {:?}; use agent, user, import, system, tool, web, or mcp.",
+            doc.source
+        )));
+    }
+    let id = if doc.id.is_empty() {
+        Uuid::new_v4().to_string()
+    } else {
+        doc.id
+    };
+    let now = Utc::now();
+    Ok(Document {
+        id: DocumentId(id),
+        project: doc.project,
+        key: doc.key,
+        namespace: doc.namespace,
+        title: doc.title,
+        tags: doc.tags,
+        body_markdown: doc.body_markdown,
+        created_at: now,
+        updated_at: now,
+        source,
+        source_meta: doc.source_meta.map(|meta| SourceMeta {
+            tool: meta.tool,
+            session: meta.session,
+            fetched_from: None,
+        }),
+        version: 1,
+        ttl_seconds: doc.ttl_seconds,
+        deleted_at: None,
+        visibility: None,
+        metadata: Default::default(),
+    })
+}
+
+fn found(
+    doc: Option<Document>,
+    what: impl FnOnce() -> String,
+) -> Result<Response<proto::Document>, Status> {
+    match doc {
+        Some(doc) => Ok(Response::new(doc.into())),
+        None => Err(Status::not_found(format!("{} not found.", what()))),
+    }
+}
+
+#[tonic::async_trait]
+impl proto::storage_server::Storage for ContextService {
+    async fn put(
+        &self,
+        request: Request<proto::PutRequest>,
+    ) -> Result<Response<proto::Document>, Status> {
+        let span = tracing::info_span!("grpc.put");
+        let _guard = span.enter();
+        let request = request.into_inner();
+        let doc = request
+            .document
+            .ok_or_else(|| Status::invalid_argument("PutRequest needs a document."))?;
+        let draft = draft(doc)?;
+        let stored = match request.expected_version {
+            Some(expected) => self.storage.store_expecting(draft, expected).await,
+            None => self.storage.store(draft).await,
+        }
+        .map_err(internal)?;
+        tracing::info!(document = %stored.id.0, version = stored.version, "Document stored");
+        Ok(Response::new(stored.into()))
+    }
+
+    async fn get_by_key(
+        &self,
+        request: Request<proto::GetByKeyRequest>,
+    ) -> Result<Response<proto::Document>, Status> {
+        let span = tracing::info_span!("grpc.get");
+        let _guard = span.enter();
+        let request = request.into_inner();
+        let doc = self
+            .storage
+            .resolve_key(&request.project, &request.key)
+            .await
+            .map_err(internal)?
+            .map(|(document, _)| document)
+            .filter(Document::is_shared);
+        found(doc, || {
+            format!("Document {} in project {}", request.key, request.project)
+        })
+    }
+
+    async fn get_by_id(
+        &self,
+        request: Request<proto::GetByIdRequest>,
+    ) -> Result<Response<proto::Document>, Status> {
+        let span = tracing::info_span!("grpc.get");
+        let _guard = span.enter();
+        let id = DocumentId(request.into_inner().id);
+        let doc = self
+            .storage
+            .get_by_id(&id)
+            .await
+            .map_err(internal)?
+            .filter(Document::is_shared);
+        found(doc, || format!("Document {}", id.0))
+    }
+
+    type HistoryStream = ResponseStream<proto::Document>;
+
+    async fn history(
+        &self,
+        request: Request<proto::GetByIdRequest>,
+    ) -> Result<Response<Self::HistoryStream>, Status> {
+        let span = tracing::info_span!("grpc.history");
+        let _guard = span.enter();
+        let id = DocumentId(request.into_inner().id);
+        // Revisions made while the document was private are left out.
+        let versions: Vec<Document> = self
+            .storage
+            .history(&id)
+            .await
+            .map_err(internal)?
+            .into_iter()
+            .filter(Document::is_shared)
+            .collect();
+        if versions.is_empty() {
+            return Err(Status::not_found(format!("Document {} not found.", id.0)));
+        }
+        let stream = futures::stream::iter(versions.into_iter().map(|doc| Ok(doc.into())));
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn version_as_of(
+        &self,
+        request: Request<proto::VersionAsOfRequest>,
+    ) -> Result<Response<proto::Document>, Status> {
+        let span = tracing::info_span!("grpc.version_as_of");
+        let _guard = span.enter();
+        let request = request.into_inner();
+        let at = request
+            .at
+            .as_ref()
+            .ok_or_else(|| Status::invalid_argument("VersionAsOfRequest needs a time."))
+            .and_then(from_timestamp)?;
+        let id = DocumentId(request.id);
+        let doc = self
+            .storage
+            .version_as_of(&id, at)
+            .await
+            .map_err(internal)?
+            .filter(Document::is_shared);
+        found(doc, || {
+            format!("Document {} as of {}", id.0, at.to_rfc3339())
+        })
+    }
+
+    type SearchStream = ResponseStream<proto::SearchHit>;
+
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<Self::SearchStream>, Status> {
+        let span = tracing::info_span!("grpc.search");
+        let _guard = span.enter();
+        let request = request.into_inner();
+        if request.text.trim().is_empty() {
+            return Err(Status::invalid_argument("Search text is empty."));
+        }
+        let hits = self
+            .storage
+            .search(SearchQuery {
+                project: request.project,
+                text: request.text,
+                limit: request.limit.map(|limit| limit as usize),
+                exclude_tags: request.exclude_tags,
+                exclude_namespaces: request.exclude_namespaces,
+                key_prefix: request.key_prefix,
+                keys: request.keys,
+                code: request.code,
+                visibility: Visibility::SHARED.to_vec(),
+            })
+            .await
+            .map_err(internal)?;
+        tracing::info!(count = hits.len(), "Search served");
+        let stream = futures::stream::iter(hits.into_iter().map(|hit| Ok(hit.into())));
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    type ListDocumentsStream = ResponseStream<proto::Document>;
+
+    async fn list_documents(
+        &self,
+        request: Request<proto::ListRequest>,
+    ) -> Result<Response<Self::ListDocumentsStream>, Status> {
+        let span = tracing::info_span!("grpc.list");
+        let _guard = span.enter();
+        let request = request.into_inner();
+        let query = ListQuery {
+            tags: request.tags,
+            not_tags: request.not_tags,
+            namespace: request.namespace,
+            exclude_namespaces: request.exclude_namespaces,
+            key_prefix: request.key_prefix,
+            since: request.since.as_ref().map(from_timestamp).transpose()?,
+            until: request.until.as_ref().map(from_timestamp).transpose()?,
+            include_deleted: request.include_deleted,
+            only_deleted: false,
+            expiring_before: None,
+            limit: request.limit.map(|limit| limit as usize),
+            cursor: None,
+            visibility: Visibility::SHARED.to_vec(),
+            metadata: Default::default(),
+            sort: Default::default(),
+        };
+
+        // Rows are read on a task of their own so the response starts before the listing ends.
+        let (sender, receiver) = tokio::sync::mpsc::channel(LIST_BUFFER);
+        let storage = self.storage.clone();
+        let project = request.project;
+        tokio::spawn(async move {
+            let mut documents = storage.list(&project, &query);
+            while let Some(doc) = documents.next().await {
+                let item = doc.map(proto::Document::from).map_err(internal);
+                if sender.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
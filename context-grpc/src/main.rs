@@ -0,0 +1,46 @@
+use std::{env, net::SocketAddr, path::PathBuf};
+
+use anyhow::{Context, Result};
+use context_core::{
+    config::{configure_storage, StoreConfig},
+    sqlite::SqliteStorage,
+};
+use context_telemetry::{context_span, init_tracing_in_home, LogContext};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8078";
+
+fn context_home() -> Result<PathBuf> {
+    let home = match env::var("CONTEXT_HOME") {
+        Ok(home) => PathBuf::from(home),
+        Err(_) => dirs::home_dir()
+            .map(|home| home.join(".context"))
+            .unwrap_or_else(|| PathBuf::from(".context")),
+    };
+    std::fs::create_dir_all(&home)?;
+    Ok(home)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let home = context_home()?;
+    let _telemetry =
+        init_tracing_in_home("context-grpc", &["context_grpc", "context_core"], &home)?;
+    let scenario = env::var("CONTEXT_SCENARIO").ok();
+    let project = env::var("CONTEXT_PROJECT").ok();
+    let log_context = LogContext {
+        scenario_id: scenario.as_deref(),
+        project: project.as_deref(),
+        command: Some("grpc"),
+    };
+    let span = context_span(log_context);
+    let _span_guard = span.enter();
+
+    let raw_addr = env::var("CONTEXT_GRPC_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let addr: SocketAddr = raw_addr
+        .parse()
+        .with_context(|| format!("Invalid CONTEXT_GRPC_ADDR {raw_addr:?}"))?;
+    let storage = SqliteStorage::open(&home.join("db.sqlite")).await?;
+    let storage = configure_storage(storage, StoreConfig::load(&home)?)?;
+    tracing::info!("Starting context-grpc on {addr}");
+    context_grpc::serve(storage, addr).await
+}
@@ -0,0 +1,219 @@
+use context_core::{sqlite::SqliteStorage, Visibility};
+use context_grpc::{
+    proto::{
+        storage_client::StorageClient, Document, GetByIdRequest, GetByKeyRequest, ListRequest,
+        PutRequest, SearchRequest,
+    },
+    ContextService,
+};
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+use tonic::{transport::Channel, Code};
+
+type TestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+async fn client(storage: SqliteStorage) -> TestResult<StorageClient<Channel>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(ContextService::new(storage).into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    Ok(StorageClient::connect(format!("http://{addr}")).await?)
+}
+
+fn put(key: &str, body: &str, tags: &[&str]) -> PutRequest {
+    PutRequest {
+        document: Some(Document {
+            project: "demo".to_string(),
+            key: Some(key.to_string()),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            body_markdown: body.to_string(),
+            ..Default::default()
+        }),
+        expected_version: None,
+    }
+}
+
+#[tokio::test]
+async fn documents_round_trip_and_stream() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let mut client = client(storage).await?;
+
+    let first = client
+        .put(put("notes/deploy", "deploy with make release", &["ops"]))
+        .await?
+        .into_inner();
+    assert_eq!(first.version, 1);
+    assert_eq!(first.source, "agent");
+    let second = client
+        .put(put("notes/deploy", "deploy with cargo dist", &["ops"]))
+        .await?
+        .into_inner();
+    assert_eq!(second.id, first.id);
+    assert_eq!(second.version, 2);
+    client
+        .put(put("notes/setup", "install the toolchain", &[]))
+        .await?;
+
+    let fetched = client
+        .get_by_key(GetByKeyRequest {
+            project: "demo".to_string(),
+            key: "notes/deploy".to_string(),
+        })
+        .await?
+        .into_inner();
+    assert_eq!(fetched.body_markdown, "deploy with cargo dist");
+
+    let history: Vec<Document> = client
+        .history(GetByIdRequest {
+            id: first.id.clone(),
+        })
+        .await?
+        .into_inner()
+        .collect::<Result<_, _>>()
+        .await?;
+    let versions: Vec<u64> = history.iter().map(|doc| doc.version).collect();
+    assert_eq!(versions, vec![1, 2]);
+
+    let hits: Vec<_> = client
+        .search(SearchRequest {
+            project: Some("demo".to_string()),
+            text: "deploy".to_string(),
+            ..Default::default()
+        })
+        .await?
+        .into_inner()
+        .collect::<Result<_, _>>()
+        .await?;
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].document.as_ref().unwrap().id, first.id);
+
+    let listed: Vec<_> = client
+        .list_documents(ListRequest {
+            project: "demo".to_string(),
+            tags: vec!["ops".to_string()],
+            ..Default::default()
+        })
+        .await?
+        .into_inner()
+        .collect::<Result<_, _>>()
+        .await?;
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].key.as_deref(), Some("notes/deploy"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn errors_map_to_grpc_status_codes() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let mut client = client(storage).await?;
+
+    let missing = client
+        .get_by_key(GetByKeyRequest {
+            project: "demo".to_string(),
+            key: "nope".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), Code::NotFound);
+
+    let mut bad_source = put("notes/a", "body", &[]);
+    bad_source.document.as_mut().unwrap().source = "robot".to_string();
+    let invalid = client.put(bad_source).await.unwrap_err();
+    assert_eq!(invalid.code(), Code::InvalidArgument);
+
+    client.put(put("notes/a", "body", &[])).await?;
+    let mut stale = put("notes/a", "edited", &[]);
+    stale.expected_version = Some(3);
+    let conflict = client.put(stale).await.unwrap_err();
+    assert_eq!(conflict.code(), Code::FailedPrecondition);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn private_documents_are_never_served() -> TestResult<()> {
+    let temp = tempdir()?;
+    let storage = SqliteStorage::open(&temp.path().join("db.sqlite")).await?;
+    let now = chrono::Utc::now();
+    let secret = storage
+        .store(context_core::Document {
+            id: context_core::DocumentId("secret".to_string()),
+            project: "demo".to_string(),
+            key: Some("notes/secret".to_string()),
+            namespace: None,
+            title: None,
+            tags: Vec::new(),
+            body_markdown: "deploy keys live in the vault".to_string(),
+            created_at: now,
+            updated_at: now,
+            source: context_core::SourceType::User,
+            source_meta: None,
+            version: 1,
+            ttl_seconds: None,
+            deleted_at: None,
+            visibility: Some(Visibility::Private),
+            metadata: Default::default(),
+        })
+        .await?;
+    let mut client = client(storage).await?;
+    client
+        .put(put("notes/deploy", "deploy with cargo dist", &[]))
+        .await?;
+
+    let by_key = client
+        .get_by_key(GetByKeyRequest {
+            project: "demo".to_string(),
+            key: "notes/secret".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(by_key.code(), Code::NotFound);
+    let by_id = client
+        .get_by_id(GetByIdRequest {
+            id: secret.id.0.clone(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(by_id.code(), Code::NotFound);
+    let history = client
+        .history(GetByIdRequest { id: secret.id.0 })
+        .await
+        .unwrap_err();
+    assert_eq!(history.code(), Code::NotFound);
+
+    let hits: Vec<_> = client
+        .search(SearchRequest {
+            project: Some("demo".to_string()),
+            text: "deploy".to_string(),
+            ..Default::default()
+        })
+        .await?
+        .into_inner()
+        .collect::<Result<_, _>>()
+        .await?;
+    let keys: Vec<_> = hits
+        .iter()
+        .filter_map(|hit| hit.document.as_ref()?.key.clone())
+        .collect();
+    assert_eq!(keys, ["notes/deploy"]);
+
+    let listed: Vec<_> = client
+        .list_documents(ListRequest {
+            project: "demo".to_string(),
+            ..Default::default()
+        })
+        .await?
+        .into_inner()
+        .collect::<Result<_, _>>()
+        .await?;
+    let keys: Vec<_> = listed.iter().filter_map(|doc| doc.key.clone()).collect();
+    assert_eq!(keys, ["notes/deploy"]);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::{
+    backtrace::Backtrace,
+    env, fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Directory under the log directory that holds crash reports, so `debug-bundle`
+/// collects them with the logs.
+pub const CRASH_DIR: &str = "crashes";
+
+/// Replace the default panic output with a short note on stderr, an `ERROR` entry (with
+/// backtrace) in the JSONL log, and a report file in `<log_dir>/crashes`. If the report
+/// can't be written, the default hook runs as well.
+pub fn install_panic_hook(app_name: &str, log_dir: &Path) {
+    let app_name = app_name.to_string();
+    let crash_dir = log_dir.join(CRASH_DIR);
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match record_panic(&app_name, &crash_dir, info) {
+            Some(path) => eprintln!(
+                "{app_name} hit an internal error: {}\nA crash report was written to {}. \
+                 Please attach it (or run `context debug-bundle`) when reporting this.",
+                panic_message(info),
+                path.display()
+            ),
+            None => default_hook(info),
+        }
+    }));
+}
+
+fn record_panic(app_name: &str, crash_dir: &Path, info: &PanicHookInfo<'_>) -> Option<PathBuf> {
+    let message = panic_message(info);
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let backtrace = Backtrace::force_capture().to_string();
+    let thread = thread::current().name().unwrap_or("unnamed").to_string();
+
+    let report = crash_report(app_name, &thread, &message, &location, &backtrace);
+    let path = write_report(crash_dir, app_name, &report).ok();
+    tracing::error!(
+        panic.message = %message,
+        panic.location = %location,
+        panic.thread = %thread,
+        crash_report = ?path,
+        %backtrace,
+        "Panic"
+    );
+    path
+}
+
+fn panic_message(info: &PanicHookInfo<'_>) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|text| text.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+fn crash_report(
+    app_name: &str,
+    thread: &str,
+    message: &str,
+    location: &str,
+    backtrace: &str,
+) -> String {
+    let args: Vec<String> = env::args().collect();
+    format!(
+        "name: {app_name}\nversion: {}\nos: {} {}\nargs: {args:?}\nthread: {thread}\n\
+         message: {message}\nlocation: {location}\n\nbacktrace:\n{backtrace}\n",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::OS,
+        env::consts::ARCH,
+    )
+}
+
+fn write_report(crash_dir: &Path, app_name: &str, report: &str) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(crash_dir)?;
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let path = crash_dir.join(format!("{app_name}-{seconds}-{}.txt", std::process::id()));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_leave_a_crash_report() {
+        let temp = tempfile::tempdir().unwrap();
+        let crash_dir = temp.path().join(CRASH_DIR);
+        let hook_dir = crash_dir.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            record_panic("context-test", &hook_dir, info);
+        }));
+        let result = panic::catch_unwind(|| panic!("index {} out of range", 7));
+        panic::set_hook(previous);
+        assert!(result.is_err());
+
+        let reports: Vec<PathBuf> = fs::read_dir(&crash_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(reports.len(), 1);
+        let name = reports[0].file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("context-test-"), "{name}");
+        let report = fs::read_to_string(&reports[0]).unwrap();
+        assert!(report.contains("name: context-test\n"));
+        assert!(report.contains("message: index 7 out of range\n"));
+        assert!(report.contains("location: context-telemetry/src/crash.rs:"));
+        assert!(report.contains("backtrace:\n"));
+    }
+}
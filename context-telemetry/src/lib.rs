@@ -4,12 +4,25 @@ use std::{
     path::{Path, PathBuf},
 };
 use tracing::{Dispatch, Span};
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use tracing_subscriber::{filter::filter_fn, fmt, prelude::*, reload, EnvFilter, Registry};
+
+mod crash;
+mod workspace;
+
+pub use crash::{install_panic_hook, CRASH_DIR};
+pub use workspace::WorkspaceState;
 
 const LOG_DIR_ENV: &str = "CONTEXT_LOG_DIR";
 
+/// Events whose target ends with this go to the JSONL log only, never the console, e.g.
+/// `tracing::info!(target: "context_cli::file_only", ...)`. Keep the crate name first so
+/// the app's default filter still lets them through.
+pub const FILE_ONLY_TARGET_SUFFIX: &str = "::file_only";
+
 pub struct TelemetryGuard {
     log_path: PathBuf,
+    filter: reload::Handle<EnvFilter, Registry>,
+    default_directives: Vec<String>,
     _file_guard: tracing_appender::non_blocking::WorkerGuard,
 }
 
@@ -17,6 +30,22 @@ impl TelemetryGuard {
     pub fn log_path(&self) -> &Path {
         &self.log_path
     }
+
+    /// Swap the level filter of a running process, e.g. `context_web=debug`; `None`
+    /// goes back to the filter it started with (`RUST_LOG` or the app's defaults).
+    pub fn set_filter(&self, directives: Option<&str>) -> Result<()> {
+        let filter = match directives {
+            Some(directives) => EnvFilter::try_new(directives)
+                .map_err(|err| anyhow::anyhow!("Invalid log filter {directives:?}: {err}"))?,
+            None => {
+                let defaults: Vec<&str> =
+                    self.default_directives.iter().map(String::as_str).collect();
+                default_env_filter(&defaults)
+            }
+        };
+        self.filter.reload(filter)?;
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -26,26 +55,38 @@ pub struct LogContext<'a> {
     pub command: Option<&'a str>,
 }
 
+/// The root span of a command. Its `git_*` fields start empty; fill them with
+/// `WorkspaceState::record`.
 pub fn context_span(ctx: LogContext<'_>) -> Span {
     tracing::info_span!(
         "context",
         scenario_id = ctx.scenario_id,
         project = ctx.project,
-        command = ctx.command
+        command = ctx.command,
+        git_branch = tracing::field::Empty,
+        git_commit = tracing::field::Empty,
+        git_dirty = tracing::field::Empty
     )
 }
 
-fn resolve_log_dir() -> Result<PathBuf> {
-    let log_dir = match env::var(LOG_DIR_ENV) {
-        Ok(dir) if Path::new(&dir).is_absolute() => PathBuf::from(dir),
-        Ok(dir) => env::current_dir()?.join(dir),
-        Err(_) => env::current_dir()?.join(".context").join("logs"),
-    };
-
+/// The directory every context binary logs to, created if missing: `CONTEXT_LOG_DIR`
+/// (relative paths are taken from the working directory), else `<home>/logs` when the
+/// caller knows its context home, else `./.context/logs`.
+pub fn resolve_log_dir(home: Option<&Path>) -> Result<PathBuf> {
+    let log_dir = log_dir_for(env::var(LOG_DIR_ENV).ok(), &env::current_dir()?, home);
     fs::create_dir_all(&log_dir)?;
     Ok(log_dir)
 }
 
+fn log_dir_for(configured: Option<String>, cwd: &Path, home: Option<&Path>) -> PathBuf {
+    match (configured.filter(|dir| !dir.is_empty()), home) {
+        (Some(dir), _) if Path::new(&dir).is_absolute() => PathBuf::from(dir),
+        (Some(dir), _) => cwd.join(dir),
+        (None, Some(home)) => home.join("logs"),
+        (None, None) => cwd.join(".context").join("logs"),
+    }
+}
+
 fn default_env_filter(default_directives: &[&str]) -> EnvFilter {
     EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         if default_directives.is_empty() {
@@ -82,8 +123,14 @@ fn build_dispatch(
         .with_current_span(true)
         .with_span_list(true);
 
-    let console_layer = fmt::layer().with_writer(console_writer).with_target(true);
+    let console_layer = fmt::layer()
+        .with_writer(console_writer)
+        .with_target(true)
+        .with_filter(filter_fn(|metadata| {
+            !metadata.target().ends_with(FILE_ONLY_TARGET_SUFFIX)
+        }));
 
+    let (env_filter, filter) = reload::Layer::new(env_filter);
     let subscriber = tracing_subscriber::registry()
         .with(env_filter)
         .with(json_layer)
@@ -92,6 +139,8 @@ fn build_dispatch(
     let dispatch = Dispatch::new(subscriber);
     let guard = TelemetryGuard {
         log_path,
+        filter,
+        default_directives: Vec::new(),
         _file_guard: file_guard,
     };
 
@@ -99,11 +148,32 @@ fn build_dispatch(
 }
 
 pub fn init_tracing(app_name: &str, default_directives: &[&str]) -> Result<TelemetryGuard> {
-    let log_dir = resolve_log_dir()?;
+    start(app_name, default_directives, None)
+}
+
+/// `init_tracing` for an app that has resolved its context home: unless
+/// `CONTEXT_LOG_DIR` says otherwise, logs go to `<home>/logs`, next to the database.
+/// Both install the panic hook, so crash reports land in `<log dir>/crashes`.
+pub fn init_tracing_in_home(
+    app_name: &str,
+    default_directives: &[&str],
+    home: &Path,
+) -> Result<TelemetryGuard> {
+    start(app_name, default_directives, Some(home))
+}
+
+fn start(
+    app_name: &str,
+    default_directives: &[&str],
+    home: Option<&Path>,
+) -> Result<TelemetryGuard> {
+    let log_dir = resolve_log_dir(home)?;
+    install_panic_hook(app_name, &log_dir);
     let env_filter = default_env_filter(default_directives);
     let console_writer = fmt::writer::BoxMakeWriter::new(std::io::stderr);
 
-    let (dispatch, guard) = build_dispatch(app_name, log_dir, env_filter, console_writer)?;
+    let (dispatch, mut guard) = build_dispatch(app_name, log_dir, env_filter, console_writer)?;
+    guard.default_directives = default_directives.iter().map(|d| d.to_string()).collect();
     tracing::dispatcher::set_global_default(dispatch)?;
 
     Ok(guard)
@@ -172,6 +242,28 @@ mod tests {
         output
     }
 
+    #[test]
+    fn log_dir_prefers_the_env_then_the_home_hint() {
+        let cwd = Path::new("/work");
+        let home = Path::new("/home/me/.context");
+        assert_eq!(
+            log_dir_for(Some("/var/log/context".into()), cwd, Some(home)),
+            PathBuf::from("/var/log/context")
+        );
+        assert_eq!(
+            log_dir_for(Some("logs".into()), cwd, Some(home)),
+            PathBuf::from("/work/logs")
+        );
+        assert_eq!(
+            log_dir_for(None, cwd, Some(home)),
+            PathBuf::from("/home/me/.context/logs")
+        );
+        assert_eq!(
+            log_dir_for(Some(String::new()), cwd, None),
+            PathBuf::from("/work/.context/logs")
+        );
+    }
+
     #[test]
     fn writes_json_logs_to_file_with_context_fields() {
         let temp = tempfile::tempdir().unwrap();
@@ -290,4 +382,59 @@ mod tests {
         assert!(output.contains("command=\"web\""));
         assert!(!output.trim_start().starts_with('{'));
     }
+
+    #[test]
+    fn file_only_events_stay_off_the_console() {
+        let temp = tempfile::tempdir().unwrap();
+        let writer = TestWriter::default();
+
+        let (dispatch, guard) = build_dispatch(
+            "context-cli",
+            temp.path().to_path_buf(),
+            EnvFilter::new("context_cli=info"),
+            writer.make_writer(),
+        )
+        .unwrap();
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            tracing::info!(target: "context_cli::file_only", "file-only");
+            tracing::info!(target: "context_cli", "everywhere");
+        });
+
+        drop(guard);
+
+        let output = strip_ansi(&writer.contents());
+        assert!(!output.contains("file-only"));
+        assert!(output.contains("everywhere"));
+        let contents = std::fs::read_to_string(temp.path().join("context-cli.jsonl")).unwrap();
+        assert!(contents.contains("file-only"));
+        assert!(contents.contains("everywhere"));
+    }
+
+    #[test]
+    fn filters_can_be_swapped_while_running() {
+        let temp = tempfile::tempdir().unwrap();
+        let writer = TestWriter::default();
+
+        let (dispatch, guard) = build_dispatch(
+            "context-web",
+            temp.path().to_path_buf(),
+            EnvFilter::new("info"),
+            writer.make_writer(),
+        )
+        .unwrap();
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            tracing::info!("before-reload");
+            guard.set_filter(Some("warn")).unwrap();
+            tracing::info!("hidden-after-reload");
+            tracing::warn!("shown-after-reload");
+        });
+        assert!(guard.set_filter(Some("=[")).is_err());
+
+        let output = strip_ansi(&writer.contents());
+        assert!(output.contains("before-reload"));
+        assert!(!output.contains("hidden-after-reload"));
+        assert!(output.contains("shown-after-reload"));
+    }
 }
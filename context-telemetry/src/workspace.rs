@@ -0,0 +1,94 @@
+//! The git state of the directory a command runs in, recorded on the `context` span so
+//! logs can be matched to the code an agent was working on.
+
+use std::{path::Path, process::Command};
+
+use tracing::Span;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceState {
+    /// `None` on a detached HEAD.
+    pub branch: Option<String>,
+    /// `None` before the first commit.
+    pub commit: Option<String>,
+    /// Tracked files have uncommitted changes (untracked files do not count).
+    pub dirty: bool,
+}
+
+impl WorkspaceState {
+    /// The state of the repository containing `dir`; `None` outside a repository or
+    /// when git is not installed.
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args([
+                "status",
+                "--porcelain=v2",
+                "--branch",
+                "--untracked-files=no",
+            ])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(Self::from_porcelain(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// Parse `git status --porcelain=v2 --branch` output.
+    pub fn from_porcelain(status: &str) -> Self {
+        let mut state = Self::default();
+        for line in status.lines() {
+            if let Some(oid) = line.strip_prefix("# branch.oid ") {
+                state.commit = (oid != "(initial)").then(|| oid.to_string());
+            } else if let Some(head) = line.strip_prefix("# branch.head ") {
+                state.branch = (head != "(detached)").then(|| head.to_string());
+            } else if !line.starts_with('#') && !line.is_empty() {
+                state.dirty = true;
+            }
+        }
+        state
+    }
+
+    /// Fill the `git_*` fields `context_span` declares.
+    pub fn record(&self, span: &Span) {
+        if let Some(branch) = &self.branch {
+            span.record("git_branch", branch.as_str());
+        }
+        if let Some(commit) = &self.commit {
+            span.record("git_commit", commit.as_str());
+        }
+        span.record("git_dirty", self.dirty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn porcelain_status_is_parsed() {
+        let clean =
+            "# branch.oid 4f3a8350c0ffee\n# branch.head main\n# branch.upstream origin/main\n";
+        assert_eq!(
+            WorkspaceState::from_porcelain(clean),
+            WorkspaceState {
+                branch: Some("main".to_string()),
+                commit: Some("4f3a8350c0ffee".to_string()),
+                dirty: false,
+            }
+        );
+
+        let detached_and_dirty =
+            "# branch.oid 4f3a8350c0ffee\n# branch.head (detached)\n1 .M N... 100644 100644 100644 a b src/lib.rs\n";
+        let state = WorkspaceState::from_porcelain(detached_and_dirty);
+        assert_eq!(state.branch, None);
+        assert!(state.dirty);
+
+        let fresh = "# branch.oid (initial)\n# branch.head main\n";
+        assert_eq!(WorkspaceState::from_porcelain(fresh).commit, None);
+    }
+}
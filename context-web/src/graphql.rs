@@ -0,0 +1,297 @@
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, InputObject, Object, Result, Schema,
+};
+use chrono::{DateTime, Utc};
+use context_core::{
+    related::{rank_related, RelatedDocument},
+    sqlite::SqliteStorage,
+    traced::TracedStorage,
+    Document, DocumentId, ListPage, ListQuery, ProjectStats, SearchHit, SearchQuery, Storage,
+    Visibility,
+};
+use futures::TryStreamExt;
+
+/// Read-only GraphQL view of one context home, served at `POST /graphql`. Private
+/// documents are left out everywhere, as in the REST API.
+pub type ContextSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn schema(storage: TracedStorage<SqliteStorage>) -> ContextSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(storage)
+        .finish()
+}
+
+fn storage<'a>(ctx: &Context<'a>) -> &'a TracedStorage<SqliteStorage> {
+    ctx.data_unchecked::<TracedStorage<SqliteStorage>>()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// The live document at `key` in `project`, or at the key it redirects to.
+    async fn document(
+        &self,
+        ctx: &Context<'_>,
+        project: String,
+        key: String,
+    ) -> Result<Option<DocumentNode>> {
+        Ok(storage(ctx)
+            .resolve_key(&project, &key)
+            .await?
+            .map(|(document, _)| document)
+            .filter(Document::is_shared)
+            .map(DocumentNode))
+    }
+
+    /// A document by id, in whichever project it lives.
+    async fn document_by_id(&self, ctx: &Context<'_>, id: String) -> Result<Option<DocumentNode>> {
+        Ok(storage(ctx)
+            .get_by_id(&DocumentId(id))
+            .await?
+            .filter(Document::is_shared)
+            .map(DocumentNode))
+    }
+
+    /// A page of a project's documents, newest update first, filtered like `context ls`.
+    async fn documents(
+        &self,
+        ctx: &Context<'_>,
+        project: String,
+        #[graphql(default)] filter: DocumentFilter,
+        limit: Option<usize>,
+        cursor: Option<String>,
+    ) -> Result<DocumentPage> {
+        let query = ListQuery {
+            tags: filter.tags,
+            not_tags: filter.not_tags,
+            namespace: filter.namespace,
+            key_prefix: filter.key_prefix,
+            include_deleted: filter.include_deleted,
+            visibility: Visibility::SHARED.to_vec(),
+            limit,
+            cursor,
+            ..Default::default()
+        };
+        query.cursor_position()?;
+        Ok(DocumentPage(
+            storage(ctx).list_page(&project, &query).await?,
+        ))
+    }
+
+    /// Full-text search, best match first.
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        text: String,
+        project: Option<String>,
+        #[graphql(default = 10)] limit: usize,
+    ) -> Result<Vec<SearchHitNode>> {
+        let hits = storage(ctx)
+            .search(SearchQuery {
+                project,
+                text,
+                limit: Some(limit),
+                visibility: Visibility::SHARED.to_vec(),
+                ..Default::default()
+            })
+            .await?;
+        Ok(hits.into_iter().map(SearchHitNode).collect())
+    }
+
+    /// Registered projects with their document counts.
+    async fn projects(&self, ctx: &Context<'_>) -> Result<Vec<ProjectNode>> {
+        let stats = storage(ctx).project_stats(&Visibility::SHARED).await?;
+        Ok(stats.into_iter().map(ProjectNode).collect())
+    }
+}
+
+/// Which documents `documents` lists; every field narrows the listing.
+#[derive(Debug, Default, InputObject)]
+pub struct DocumentFilter {
+    /// Documents carrying all of these tags.
+    #[graphql(default)]
+    tags: Vec<String>,
+    /// Documents carrying none of these tags.
+    #[graphql(default)]
+    not_tags: Vec<String>,
+    namespace: Option<String>,
+    key_prefix: Option<String>,
+    /// Also list deleted and expired documents.
+    #[graphql(default)]
+    include_deleted: bool,
+}
+
+pub struct DocumentNode(Document);
+
+#[Object(name = "Document")]
+impl DocumentNode {
+    async fn id(&self) -> &str {
+        &self.0.id.0
+    }
+
+    async fn project(&self) -> &str {
+        &self.0.project
+    }
+
+    async fn key(&self) -> Option<&str> {
+        self.0.key.as_deref()
+    }
+
+    async fn namespace(&self) -> Option<&str> {
+        self.0.namespace.as_deref()
+    }
+
+    async fn title(&self) -> Option<&str> {
+        self.0.title.as_deref()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    async fn body_markdown(&self) -> &str {
+        &self.0.body_markdown
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// `agent`, `user`, `import`, `system`, `tool`, `web`, or `mcp`.
+    async fn source(&self) -> &str {
+        self.0.source.as_str()
+    }
+
+    async fn version(&self) -> u64 {
+        self.0.version
+    }
+
+    async fn ttl_seconds(&self) -> Option<i64> {
+        self.0.ttl_seconds
+    }
+
+    async fn deleted_at(&self) -> Option<DateTime<Utc>> {
+        self.0.deleted_at
+    }
+
+    /// Every recorded revision, oldest first, except those made while it was private.
+    async fn versions(&self, ctx: &Context<'_>) -> Result<Vec<DocumentNode>> {
+        let history = storage(ctx).history(&self.0.id).await?;
+        Ok(history
+            .into_iter()
+            .filter(Document::is_shared)
+            .map(DocumentNode)
+            .collect())
+    }
+
+    /// Documents that share tags, reference this one, or overlap in wording, best first.
+    async fn related(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 5)] limit: usize,
+    ) -> Result<Vec<RelatedNode>> {
+        let candidates: Vec<Document> = storage(ctx)
+            .stream_project(&self.0.project)
+            .try_filter(|doc| futures::future::ready(doc.is_shared()))
+            .try_collect()
+            .await?;
+        let ranked = rank_related(&self.0, candidates, limit);
+        Ok(ranked.into_iter().map(RelatedNode).collect())
+    }
+}
+
+pub struct DocumentPage(ListPage);
+
+#[Object]
+impl DocumentPage {
+    async fn documents(&self) -> Vec<DocumentNode> {
+        self.0.documents.iter().cloned().map(DocumentNode).collect()
+    }
+
+    /// Pass as `cursor` for the next page; null on the last one.
+    async fn next_cursor(&self) -> Option<&str> {
+        self.0.next_cursor.as_deref()
+    }
+}
+
+pub struct SearchHitNode(SearchHit);
+
+#[Object(name = "SearchHit")]
+impl SearchHitNode {
+    async fn document(&self) -> DocumentNode {
+        DocumentNode(self.0.document.clone())
+    }
+
+    async fn score(&self) -> f32 {
+        self.0.score
+    }
+
+    async fn bm25(&self) -> f32 {
+        self.0.breakdown.bm25
+    }
+
+    async fn recency(&self) -> f32 {
+        self.0.breakdown.recency
+    }
+
+    async fn tag_bonus(&self) -> f32 {
+        self.0.breakdown.tag_bonus
+    }
+
+    /// Always 0 until documents can be pinned.
+    async fn pin_boost(&self) -> f32 {
+        self.0.breakdown.pin_boost
+    }
+}
+
+pub struct RelatedNode(RelatedDocument);
+
+#[Object(name = "RelatedDocument")]
+impl RelatedNode {
+    async fn document(&self) -> DocumentNode {
+        DocumentNode(self.0.document.clone())
+    }
+
+    async fn score(&self) -> f32 {
+        self.0.score
+    }
+
+    async fn tag_overlap(&self) -> f32 {
+        self.0.tag_overlap
+    }
+
+    /// 1.0 when either document references the other.
+    async fn link(&self) -> f32 {
+        self.0.link
+    }
+
+    async fn text_similarity(&self) -> f32 {
+        self.0.text_similarity
+    }
+}
+
+pub struct ProjectNode(ProjectStats);
+
+#[Object(name = "Project")]
+impl ProjectNode {
+    async fn id(&self) -> &str {
+        &self.0.project.id
+    }
+
+    async fn documents(&self) -> u64 {
+        self.0.documents
+    }
+
+    async fn bytes(&self) -> u64 {
+        self.0.bytes
+    }
+
+    async fn last_updated(&self) -> Option<DateTime<Utc>> {
+        self.0.last_updated
+    }
+}
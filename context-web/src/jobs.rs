@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use context_core::{
+    jobs::{validate_jobs, JobSpec},
+    sqlite::SqliteStorage,
+};
+use serde::Serialize;
+
+/// What `/api/v1/jobs` reports for one scheduled job.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    #[serde(flatten)]
+    pub spec: JobSpec,
+    pub runs: u64,
+    pub failures: u64,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    /// `ok` or `error`.
+    pub last_outcome: Option<&'static str>,
+    /// The job's summary, or the error it failed with.
+    pub last_message: Option<String>,
+}
+
+/// Scheduled maintenance jobs and how their runs went.
+#[derive(Clone, Default)]
+pub struct JobBoard {
+    statuses: Arc<Mutex<Vec<JobStatus>>>,
+    tasks: Arc<Mutex<Vec<tokio::task::AbortHandle>>>,
+}
+
+impl JobBoard {
+    pub fn snapshot(&self) -> Vec<JobStatus> {
+        self.lock().clone()
+    }
+
+    /// Spawn one task per job; each first runs a full interval after startup.
+    pub fn start(&self, storage: SqliteStorage, jobs: Vec<JobSpec>) -> Result<()> {
+        validate_jobs(&jobs)?;
+        for spec in jobs {
+            let interval = spec.interval()?;
+            let index = {
+                let mut statuses = self.lock();
+                statuses.push(JobStatus {
+                    spec,
+                    runs: 0,
+                    failures: 0,
+                    next_run_at: Utc::now() + interval,
+                    last_run_at: None,
+                    last_duration_ms: None,
+                    last_outcome: None,
+                    last_message: None,
+                });
+                statuses.len() - 1
+            };
+            let board = self.clone();
+            let storage = storage.clone();
+            let period = interval.to_std()?;
+            let task = tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(period).await;
+                    board.run(index, &storage).await;
+                }
+            });
+            self.tasks
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .push(task.abort_handle());
+        }
+        Ok(())
+    }
+
+    /// Replace the schedule after a config reload. Returns false, leaving the running
+    /// jobs and their history alone, when `jobs` is what is already scheduled.
+    pub fn restart(&self, storage: SqliteStorage, jobs: Vec<JobSpec>) -> Result<bool> {
+        validate_jobs(&jobs)?;
+        let current: Vec<JobSpec> = self
+            .lock()
+            .iter()
+            .map(|status| status.spec.clone())
+            .collect();
+        if current == jobs {
+            return Ok(false);
+        }
+        for task in self
+            .tasks
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .drain(..)
+        {
+            task.abort();
+        }
+        self.lock().clear();
+        self.start(storage, jobs)?;
+        Ok(true)
+    }
+
+    /// Run the job at `index` now and record the outcome.
+    pub async fn run(&self, index: usize, storage: &SqliteStorage) {
+        let Some(spec) = self.lock().get(index).map(|status| status.spec.clone()) else {
+            return;
+        };
+        let span = tracing::info_span!("web.job", kind = spec.kind.as_str());
+        let _guard = span.enter();
+        let started = Utc::now();
+        let result = spec.run(storage, started).await;
+        let finished = Utc::now();
+
+        let mut statuses = self.lock();
+        // The schedule was replaced while this run was in flight.
+        let Some(status) = statuses.get_mut(index) else {
+            return;
+        };
+        status.runs += 1;
+        status.last_run_at = Some(started);
+        status.last_duration_ms = (finished - started).num_milliseconds().try_into().ok();
+        if let Ok(interval) = spec.interval() {
+            status.next_run_at = finished + interval;
+        }
+        match result {
+            Ok(summary) => {
+                tracing::info!(summary = %summary, "Job finished");
+                status.last_outcome = Some("ok");
+                status.last_message = Some(summary);
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "Job failed");
+                status.failures += 1;
+                status.last_outcome = Some("error");
+                status.last_message = Some(err.to_string());
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<JobStatus>> {
+        self.statuses.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
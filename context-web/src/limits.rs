@@ -0,0 +1,298 @@
+use std::{
+    collections::HashMap,
+    env,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::{api_error, tenants::Caller};
+
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 600;
+const DEFAULT_MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long an empty bucket takes to fill; one left alone this long is full and dropped.
+const REFILL_WINDOW: Duration = Duration::from_secs(60);
+
+/// Request limits, read from `CONTEXT_WEB_RATE_LIMIT` (requests per minute per
+/// caller, `0` disables), `CONTEXT_WEB_MAX_BODY_BYTES`, and `CONTEXT_WEB_REQUEST_TIMEOUT`
+/// (seconds, `0` disables).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitConfig {
+    pub requests_per_minute: u32,
+    pub max_body_bytes: usize,
+    /// How long a request may run before it is answered with 503; the storage's
+    /// searches and counts are stopped at the same deadline.
+    pub request_timeout: Option<Duration>,
+}
+
+impl Default for LimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: DEFAULT_REQUESTS_PER_MINUTE,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            request_timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+        }
+    }
+}
+
+impl LimitConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let mut config = Self::default();
+        if let Ok(raw) = env::var("CONTEXT_WEB_RATE_LIMIT") {
+            config.requests_per_minute = raw.trim().parse().map_err(|_| {
+                anyhow::anyhow!("CONTEXT_WEB_RATE_LIMIT must be a whole number, got {raw:?}.")
+            })?;
+        }
+        if let Ok(raw) = env::var("CONTEXT_WEB_MAX_BODY_BYTES") {
+            config.max_body_bytes = raw.trim().parse().map_err(|_| {
+                anyhow::anyhow!("CONTEXT_WEB_MAX_BODY_BYTES must be a byte count, got {raw:?}.")
+            })?;
+        }
+        if let Ok(raw) = env::var("CONTEXT_WEB_REQUEST_TIMEOUT") {
+            let seconds: u64 = raw.trim().parse().map_err(|_| {
+                anyhow::anyhow!(
+                    "CONTEXT_WEB_REQUEST_TIMEOUT must be a whole number of seconds, got {raw:?}."
+                )
+            })?;
+            config.request_timeout = (seconds > 0).then(|| Duration::from_secs(seconds));
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    by_caller: HashMap<String, Bucket>,
+    /// When idle buckets were last dropped.
+    swept_at: Option<Instant>,
+}
+
+/// Token buckets keyed by caller: a burst of up to a minute's allowance, refilled evenly.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: LimitConfig,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: LimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Take one request from `caller`'s bucket, or say how long until one is available.
+    fn check(&self, caller: &str, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(self.config.requests_per_minute);
+        if capacity == 0.0 {
+            return Ok(());
+        }
+        let per_second = capacity / REFILL_WINDOW.as_secs_f64();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let sweep_due = buckets
+            .swept_at
+            .is_none_or(|swept| now.saturating_duration_since(swept) >= REFILL_WINDOW);
+        if sweep_due {
+            // A dropped bucket comes back full, just as it would have refilled.
+            buckets.by_caller.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.refilled_at) < REFILL_WINDOW
+            });
+            buckets.swept_at = Some(now);
+        }
+        let bucket = buckets
+            .by_caller
+            .entry(caller.to_string())
+            .or_insert(Bucket {
+                tokens: capacity,
+                refilled_at: now,
+            });
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * per_second).min(capacity);
+        bucket.refilled_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Whose allowance a request counts against: the home or tenant whose token `select`
+/// accepted, else the client's address, so sending made-up tokens buys nothing. Requests
+/// with neither (over a unix socket) share one bucket.
+fn caller(request: &Request) -> String {
+    match request.extensions().get::<Caller>() {
+        Some(Caller::Home) => "home".to_string(),
+        Some(Caller::Tenant(name)) => format!("tenant:{name}"),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| format!("peer:{}", addr.ip()))
+            .unwrap_or_else(|| "anonymous".to_string()),
+    }
+}
+
+/// Reject over-limit callers with 429, oversized bodies with 413, and requests still
+/// running at the timeout with 503, all as JSON errors.
+///
+/// Bodies without a `Content-Length` are capped by `DefaultBodyLimit` while they are
+/// read; its plain-text 413 is rewritten here so clients always see the JSON shape.
+pub async fn enforce(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    if let Err(retry_after) = limiter.check(&caller(&request), Instant::now()) {
+        let seconds = retry_after.as_secs().max(1);
+        tracing::warn!(retry_after = seconds, "Rate limit exceeded");
+        let mut response = api_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limited",
+            format!(
+                "Too many requests; limit is {} per minute. Retry in {seconds}s.",
+                limiter.config.requests_per_minute
+            ),
+        )
+        .into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        return response;
+    }
+
+    let max = limiter.config.max_body_bytes;
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > max as u64) {
+        return too_large(max);
+    }
+
+    let response = match limiter.config.request_timeout {
+        Some(limit) => match tokio::time::timeout(limit, next.run(request)).await {
+            Ok(response) => response,
+            Err(_) => return timed_out(limit),
+        },
+        None => next.run(request).await,
+    };
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return too_large(max);
+    }
+    response
+}
+
+fn too_large(max: usize) -> Response {
+    tracing::warn!(max_body_bytes = max, "Request body too large");
+    api_error(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "payload_too_large",
+        format!("Request body exceeds the {max}-byte limit."),
+    )
+    .into_response()
+}
+
+fn timed_out(limit: Duration) -> Response {
+    tracing::warn!(timeout_secs = limit.as_secs_f64(), "Request timed out");
+    api_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "timeout",
+        format!("Request ran past the {limit:?} time limit and was stopped."),
+    )
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_refill_per_caller() {
+        let limiter = RateLimiter::new(LimitConfig {
+            requests_per_minute: 2,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            request_timeout: None,
+        });
+        let start = Instant::now();
+        assert!(limiter.check("tenant:a", start).is_ok());
+        assert!(limiter.check("tenant:a", start).is_ok());
+        let wait = limiter.check("tenant:a", start).unwrap_err();
+        assert_eq!(wait.as_secs(), 30);
+        assert!(limiter.check("tenant:b", start).is_ok());
+        assert!(limiter
+            .check("tenant:a", start + Duration::from_secs(30))
+            .is_ok());
+    }
+
+    #[test]
+    fn idle_buckets_are_dropped() {
+        let limiter = RateLimiter::new(LimitConfig {
+            requests_per_minute: 2,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            request_timeout: None,
+        });
+        let start = Instant::now();
+        for n in 0..100 {
+            assert!(limiter.check(&format!("peer:{n}"), start).is_ok());
+        }
+        let later = start + REFILL_WINDOW;
+        assert!(limiter.check("peer:0", later).is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.by_caller.len(), 1);
+    }
+
+    #[test]
+    fn zero_disables_rate_limiting() {
+        let limiter = RateLimiter::new(LimitConfig {
+            requests_per_minute: 0,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            request_timeout: None,
+        });
+        let now = Instant::now();
+        assert!((0..1000).all(|_| limiter.check("anonymous", now).is_ok()));
+    }
+
+    #[tokio::test]
+    async fn requests_past_the_timeout_answer_503() {
+        use axum::{body::Body, middleware, routing::get, Router};
+        use tower::ServiceExt;
+
+        let limiter = RateLimiter::new(LimitConfig {
+            request_timeout: Some(Duration::from_millis(50)),
+            ..LimitConfig::default()
+        });
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .route("/fast", get(|| async { "done" }))
+            .layer(middleware::from_fn_with_state(limiter, enforce));
+        let get = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/slow")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let response = app.oneshot(get("/fast")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -1,8 +1,93 @@
-use anyhow::Result;
-use axum::{routing::get, Router};
-use context_telemetry::{context_span, init_tracing, LogContext};
-use std::{env, net::SocketAddr};
-use tokio::net::TcpListener;
+use anyhow::{Context, Result};
+use axum::{
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post},
+    Json, Router,
+};
+use context_core::reload::{self, ConfigWatcher};
+use context_core::{
+    calendar::{to_ics, upcoming_events},
+    changes::DocumentChange,
+    config::{configure_storage, StoreConfig},
+    fixtures::{self, DEFAULT_SEED},
+    jobs::JobSpec,
+    related::{rank_related, RelatedDocument},
+    sqlite::SqliteStorage,
+    traced::TracedStorage,
+    Conflict, ContextError, Document, DocumentId, ListPage, ListQuery, ProjectStats, SearchHit,
+    SearchQuery, SourceType, Storage, Visibility,
+};
+use context_telemetry::{context_span, init_tracing_in_home, LogContext, TelemetryGuard};
+use futures::{Stream, TryStreamExt};
+use serde::Deserialize;
+use std::{collections::HashSet, env, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tokio::{net::TcpListener, sync::broadcast::error::RecvError};
+
+use graphql::ContextSchema;
+use jobs::{JobBoard, JobStatus};
+use limits::{LimitConfig, RateLimiter};
+use tenants::{Tenant, TenantDirectory};
+
+mod graphql;
+mod jobs;
+mod limits;
+mod tenants;
+#[cfg(unix)]
+mod unix;
+
+#[derive(Clone)]
+struct AppState {
+    storage: TracedStorage<SqliteStorage>,
+    jobs: JobBoard,
+    graphql: ContextSchema,
+}
+
+impl AppState {
+    fn new(storage: SqliteStorage) -> Self {
+        let storage = TracedStorage::new(storage);
+        Self {
+            graphql: graphql::schema(storage.clone()),
+            storage,
+            jobs: JobBoard::default(),
+        }
+    }
+}
+
+/// The parts of `config.json` the server reads.
+#[derive(Debug, Default, Deserialize)]
+struct ServerConfig {
+    #[serde(default)]
+    jobs: Vec<JobSpec>,
+    /// Tracing filter such as `context_web=debug`; unset keeps `RUST_LOG` or the default.
+    #[serde(default)]
+    log_filter: Option<String>,
+    /// Key rules, expiry rules, and the query timeout, as the CLI applies them.
+    #[serde(flatten)]
+    store: StoreConfig,
+}
+
+/// Error response: `{"error":{"code":..., "message":...}}`, matching the CLI's JSON errors.
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn api_error(status: StatusCode, code: &str, message: impl Into<String>) -> ApiError {
+    let message = message.into();
+    (
+        status,
+        Json(serde_json::json!({ "error": { "code": code, "message": message } })),
+    )
+}
+
+fn internal_error(err: impl std::fmt::Display) -> ApiError {
+    tracing::error!(error = %err, "Request failed");
+    api_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "internal",
+        err.to_string(),
+    )
+}
 
 async fn health() -> &'static str {
     let span = tracing::info_span!("web.healthz");
@@ -18,9 +103,684 @@ async fn agent_doc() -> String {
     context_agent::agent_doc_markdown().to_string()
 }
 
+#[derive(Debug, Deserialize)]
+struct RelatedParams {
+    project: Option<String>,
+    key: Option<String>,
+    id: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn related(
+    State(state): State<AppState>,
+    Query(params): Query<RelatedParams>,
+) -> Result<Json<Vec<RelatedDocument>>, ApiError> {
+    let span = tracing::info_span!("web.related");
+    let _guard = span.enter();
+    let project = params.project.unwrap_or_else(|| "default".to_string());
+
+    let target: Option<Document> = match (&params.key, &params.id) {
+        (Some(key), None) => state
+            .storage
+            .resolve_key(&project, key)
+            .await
+            .map(|found| found.map(|(document, _)| document)),
+        (None, Some(id)) => state.storage.get_by_id(&DocumentId(id.clone())).await,
+        _ => {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                "bad_request",
+                "Provide exactly one of key or id.",
+            ))
+        }
+    }
+    .map_err(storage_error)?
+    .filter(Document::is_shared);
+    let label = params.key.or(params.id).unwrap_or_default();
+    let Some(target) = target else {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Document {label} not found in project {project}."),
+        ));
+    };
+
+    let candidates: Vec<Document> = state
+        .storage
+        .stream_project(&target.project)
+        .try_filter(|doc| futures::future::ready(doc.is_shared()))
+        .try_collect()
+        .await
+        .map_err(storage_error)?;
+    tracing::info!(document = %label, "Related documents served");
+    Ok(Json(rank_related(
+        &target,
+        candidates,
+        params.limit.unwrap_or(5),
+    )))
+}
+
+/// The project registry with per-project totals, as shown by `context project list`.
+async fn projects(State(state): State<AppState>) -> Result<Json<Vec<ProjectStats>>, ApiError> {
+    let span = tracing::info_span!("web.projects");
+    let _guard = span.enter();
+    let projects = state
+        .storage
+        .project_stats(&Visibility::SHARED)
+        .await
+        .map_err(storage_error)?;
+    tracing::info!(count = projects.len(), "Projects served");
+    Ok(Json(projects))
+}
+
+/// `"<id>.<version>"`: changes with every revision and differs between documents that
+/// held the same key at different times.
+fn etag(doc: &Document) -> String {
+    format!("\"{}.{}\"", doc.id.0, doc.version)
+}
+
+/// Set the `ETag` header for `doc`. A document whose id isn't valid header text (one
+/// imported before ids were checked) goes without; `If-Match: *` still works for it.
+fn insert_etag(headers: &mut HeaderMap, doc: &Document) {
+    match HeaderValue::from_str(&etag(doc)) {
+        Ok(tag) => {
+            headers.insert(header::ETAG, tag);
+        }
+        Err(_) => tracing::warn!(document = ?doc.id.0, "Document id can't be sent as an ETag"),
+    }
+}
+
+/// Check `If-Match` against the live document, returning the version a conditional write
+/// must still find (`None` when the request has no precondition).
+fn if_match(headers: &HeaderMap, current: Option<&Document>) -> Result<Option<u64>, ApiError> {
+    let Some(value) = headers.get("if-match") else {
+        return Ok(None);
+    };
+    let failed = || {
+        api_error(
+            StatusCode::PRECONDITION_FAILED,
+            "precondition_failed",
+            "If-Match does not match the current version of the document.",
+        )
+    };
+    let value = value.to_str().map_err(|_| failed())?;
+    let Some(current) = current else {
+        return Err(failed());
+    };
+    let current_tag = etag(current);
+    // If-Match uses strong comparison, so weak (`W/`) tags never match.
+    let matches = value
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag == current_tag);
+    if matches {
+        Ok(Some(current.version))
+    } else {
+        Err(failed())
+    }
+}
+
+/// The response for a failed storage call: its kind picks the status.
+fn storage_error(err: ContextError) -> ApiError {
+    let (status, code) = match &err {
+        ContextError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+        ContextError::Conflict(Conflict::Version(_)) => {
+            (StatusCode::PRECONDITION_FAILED, "precondition_failed")
+        }
+        ContextError::Conflict(Conflict::Idempotency(_)) => {
+            (StatusCode::UNPROCESSABLE_ENTITY, "idempotency_conflict")
+        }
+        ContextError::Conflict(_) => (StatusCode::CONFLICT, "conflict"),
+        ContextError::Validation(_) => (StatusCode::BAD_REQUEST, "bad_request"),
+        ContextError::Quota(_) => (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large"),
+        ContextError::Timeout(_) => (StatusCode::SERVICE_UNAVAILABLE, "timeout"),
+        ContextError::Storage(_) | ContextError::Sync(_) => return internal_error(err),
+    };
+    api_error(status, code, err.to_string())
+}
+
+/// A page of documents, filtered like `context ls` (same `ListQuery` fields, lists
+/// comma-separated); follow `next_cursor` with `?cursor=` for the next page. Private
+/// documents are never listed.
+async fn list_documents(
+    State(state): State<AppState>,
+    Path(project): Path<String>,
+    Query(mut query): Query<ListQuery>,
+) -> Result<Json<ListPage>, ApiError> {
+    let span = tracing::info_span!("web.list");
+    let _guard = span.enter();
+    if let Err(err) = query.cursor_position() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "bad_request",
+            err.to_string(),
+        ));
+    }
+    query.visibility = Visibility::SHARED.to_vec();
+    let page = state
+        .storage
+        .list_page(&project, &query)
+        .await
+        .map_err(storage_error)?;
+    tracing::info!(count = page.documents.len(), "Documents listed");
+    Ok(Json(page))
+}
+
+/// Full-text search in one project, ranked like `context search`. The body is a
+/// `SearchQuery` (`text`, plus optional `limit`, `exclude_tags`, `key_prefix`, ...) whose
+/// project the path sets. Private documents are never returned.
+async fn search_documents(
+    State(state): State<AppState>,
+    Path(project): Path<String>,
+    Json(query): Json<SearchQuery>,
+) -> Result<Json<Vec<SearchHit>>, ApiError> {
+    let span = tracing::info_span!("web.search");
+    let _guard = span.enter();
+    let hits = state
+        .storage
+        .search(SearchQuery {
+            project: Some(project),
+            visibility: vec![Visibility::Project, Visibility::Public],
+            ..query
+        })
+        .await
+        .map_err(storage_error)?;
+    tracing::info!(count = hits.len(), "Search served");
+    Ok(Json(hits))
+}
+
+/// The live document at `key`, or at the key it redirects to, with its version as the
+/// `ETag`. A private document answers 404 like a missing one.
+async fn get_document(
+    State(state): State<AppState>,
+    Path((project, key)): Path<(String, String)>,
+) -> Result<(HeaderMap, Json<Document>), ApiError> {
+    let span = tracing::info_span!("web.get");
+    let _guard = span.enter();
+    let Some(document) = state
+        .storage
+        .resolve_key(&project, &key)
+        .await
+        .map_err(storage_error)?
+        .map(|(document, _)| document)
+        .filter(Document::is_shared)
+    else {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Document {key} not found in project {project}."),
+        ));
+    };
+    let mut headers = HeaderMap::new();
+    insert_etag(&mut headers, &document);
+    tracing::info!(document = %document.id.0, "Document served");
+    Ok((headers, Json(document)))
+}
+
+#[derive(Debug, Deserialize)]
+struct PutDocument {
+    body_markdown: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    namespace: Option<String>,
+    title: Option<String>,
+}
+
+/// Create or revise the document at `key`, like `context put`.
+///
+/// With an `Idempotency-Key` header, a retry of the same request within a day returns
+/// the first result (marked `Idempotent-Replayed: true`) instead of adding a revision.
+/// With `If-Match`, the write only happens if the document is still at that `ETag`
+/// (412 otherwise); the two headers cannot be combined. A private document can't be
+/// revised here (403), since the response would show what the CLI keeps to itself.
+async fn put_document(
+    State(state): State<AppState>,
+    Path((project, key)): Path<(String, String)>,
+    headers: HeaderMap,
+    Json(request): Json<PutDocument>,
+) -> Result<(HeaderMap, Json<Document>), ApiError> {
+    let span = tracing::info_span!("web.put");
+    let _guard = span.enter();
+    let idempotency_key = match headers.get("idempotency-key").map(HeaderValue::to_str) {
+        Some(Ok(value)) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        Some(_) => {
+            return Err(api_error(
+                StatusCode::BAD_REQUEST,
+                "bad_request",
+                "Idempotency-Key must be a non-empty ASCII string.",
+            ))
+        }
+        None => None,
+    };
+    if headers.contains_key("if-match") && idempotency_key.is_some() {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "bad_request",
+            "Use either Idempotency-Key or If-Match, not both.",
+        ));
+    }
+    let current = state
+        .storage
+        .get_by_key(&project, &key)
+        .await
+        .map_err(storage_error)?;
+    if current.as_ref().is_some_and(|doc| !doc.is_shared()) {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            format!(
+                "Document {key} is private; change it with the CLI on the machine that holds it."
+            ),
+        ));
+    }
+    let expected_version = if_match(&headers, current.as_ref())?;
+
+    let now = chrono::Utc::now();
+    let draft = Document {
+        id: DocumentId(uuid::Uuid::new_v4().to_string()),
+        project,
+        key: Some(key),
+        namespace: request.namespace,
+        title: request.title,
+        tags: request.tags,
+        body_markdown: request.body_markdown,
+        created_at: now,
+        updated_at: now,
+        source: SourceType::Web,
+        source_meta: None,
+        version: 1,
+        ttl_seconds: None,
+        deleted_at: None,
+        visibility: None,
+        metadata: Default::default(),
+    };
+
+    let mut response_headers = HeaderMap::new();
+    let document = match (idempotency_key, expected_version) {
+        (Some(idempotency_key), _) => {
+            let write = state
+                .storage
+                .store_once(draft, &idempotency_key)
+                .await
+                .map_err(storage_error)?;
+            if write.replayed {
+                response_headers.insert("idempotent-replayed", HeaderValue::from_static("true"));
+            }
+            write.document
+        }
+        (None, Some(expected)) => state
+            .storage
+            .store_expecting(draft, expected)
+            .await
+            .map_err(storage_error)?,
+        (None, None) => state.storage.store(draft).await.map_err(storage_error)?,
+    };
+    insert_etag(&mut response_headers, &document);
+    tracing::info!(
+        document = %document.id.0,
+        version = document.version,
+        "Document stored"
+    );
+    Ok((response_headers, Json(document)))
+}
+
+/// Soft-delete the document at `key`, like `context rm`, honoring `If-Match`.
+async fn delete_document(
+    State(state): State<AppState>,
+    Path((project, key)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let span = tracing::info_span!("web.delete");
+    let _guard = span.enter();
+    let current = state
+        .storage
+        .get_by_key(&project, &key)
+        .await
+        .map_err(storage_error)?;
+    if current.as_ref().is_some_and(|doc| !doc.is_shared()) {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Document {key} not found in project {project}."),
+        ));
+    }
+    let expected_version = if_match(&headers, current.as_ref())?;
+    let Some(deleted) = state
+        .storage
+        .delete_expecting(&project, &key, expected_version)
+        .await
+        .map_err(storage_error)?
+    else {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            "not_found",
+            format!("Document {key} not found in project {project}."),
+        ));
+    };
+    tracing::info!(document = %deleted.id.0, "Document deleted");
+    Ok(Json(serde_json::json!({
+        "status": "deleted",
+        "project": project,
+        "id": deleted.id.0,
+        "key": key,
+        "version": deleted.version,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct CalendarParams {
+    /// Limit the feed to one project; every project when absent.
+    project: Option<String>,
+}
+
+/// Pending reminders and upcoming TTL expirations as an iCalendar feed that calendar
+/// apps can subscribe to, like `context calendar` without the private documents.
+async fn calendar(
+    State(state): State<AppState>,
+    Query(params): Query<CalendarParams>,
+) -> Result<(HeaderMap, String), ApiError> {
+    let span = tracing::info_span!("web.calendar");
+    let _guard = span.enter();
+    let mut events = upcoming_events(state.storage.untraced(), params.project.as_ref())
+        .await
+        .map_err(storage_error)?;
+    let ids: HashSet<String> = events
+        .iter()
+        .map(|event| event.document_id.clone())
+        .collect();
+    let mut private = HashSet::new();
+    for id in ids {
+        let document = state
+            .storage
+            .get_by_id(&DocumentId(id.clone()))
+            .await
+            .map_err(storage_error)?;
+        if document.is_some_and(|doc| !doc.is_shared()) {
+            private.insert(id);
+        }
+    }
+    events.retain(|event| !private.contains(&event.document_id));
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/calendar; charset=utf-8"),
+    );
+    tracing::info!(events = events.len(), "Calendar served");
+    Ok((headers, to_ics(&events, chrono::Utc::now())))
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsParams {
+    /// Only changes in this project; every project when absent.
+    project: Option<String>,
+}
+
+/// Server-sent events for documents written through this server from now on. Each event
+/// is named for its kind (`created`, `updated`, `deleted`) and carries the
+/// `DocumentChange` as JSON; private documents are left out. A client that falls more
+/// than `CHANGE_BUFFER` changes behind gets a `lagged` event with how many it missed, and
+/// should re-read what it shows.
+async fn events(
+    State(state): State<AppState>,
+    Query(params): Query<EventsParams>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let span = tracing::info_span!("web.events");
+    let _guard = span.enter();
+    let changes = state.storage.subscribe();
+    tracing::info!(project = ?params.project, "Event stream opened");
+    let project = params.project;
+    let stream = futures::stream::unfold(changes, move |mut changes| {
+        let project = project.clone();
+        async move {
+            loop {
+                let event = match changes.recv().await {
+                    Ok(change) if !wanted(&change, project.as_deref()) => continue,
+                    Ok(change) => Event::default()
+                        .event(change.kind.as_str())
+                        .json_data(&change),
+                    Err(RecvError::Lagged(missed)) => {
+                        Ok(Event::default().event("lagged").data(missed.to_string()))
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((event, changes));
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn wanted(change: &DocumentChange, project: Option<&str>) -> bool {
+    Visibility::SHARED.contains(&change.visibility)
+        && project.is_none_or(|project| change.project == project)
+}
+
+/// A GraphQL query against the documents, their versions, related documents, and search.
+async fn graphql(
+    State(state): State<AppState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let span = tracing::info_span!("web.graphql");
+    let _guard = span.enter();
+    let response = state.graphql.execute(request).await;
+    tracing::info!(errors = response.errors.len(), "GraphQL query served");
+    Json(response)
+}
+
+/// Scheduled maintenance jobs from `config.json` and the outcome of their last runs.
+async fn jobs(State(state): State<AppState>) -> Json<Vec<JobStatus>> {
+    let span = tracing::info_span!("web.jobs");
+    let _guard = span.enter();
+    let statuses = state.jobs.snapshot();
+    tracing::info!(count = statuses.len(), "Jobs served");
+    Json(statuses)
+}
+
+fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(health))
+        .route("/agent-doc", get(agent_doc))
+        .route("/api/projects", get(projects))
+        .route("/api/related", get(related))
+        .route("/api/calendar.ics", get(calendar))
+        .route("/api/events", get(events))
+        .route("/api/projects/:project/docs", get(list_documents))
+        .route("/api/projects/:project/search", post(search_documents))
+        .route("/api/v1/jobs", get(jobs))
+        .route("/graphql", post(graphql))
+        .route(
+            "/api/projects/:project/docs/*key",
+            get(get_document).put(put_document).delete(delete_document),
+        )
+}
+
+/// The server's own context home at `/` (its API behind `home_token`, if any), each
+/// tenant's under `/t/<name>/`, all behind the request limits. Tenant selection rewrites
+/// the path and checks tokens, so it runs before routing and before the limits, which
+/// count requests per accepted token.
+fn router(
+    state: AppState,
+    tenant_states: Vec<(Tenant, AppState)>,
+    config: LimitConfig,
+    home_token: Option<String>,
+) -> Router {
+    let directory = TenantDirectory::new(
+        &tenant_states
+            .iter()
+            .map(|(tenant, _)| tenant.clone())
+            .collect::<Vec<_>>(),
+    )
+    .with_home_token(home_token);
+    let mut app = routes().with_state(state);
+    for (tenant, tenant_state) in tenant_states {
+        app = app.nest(
+            &format!("{}{}", tenants::PREFIX, tenant.name),
+            routes().with_state(tenant_state),
+        );
+    }
+    Router::new()
+        .fallback_service(app)
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+        .layer(middleware::from_fn_with_state(
+            RateLimiter::new(config),
+            limits::enforce,
+        ))
+        .layer(middleware::from_fn_with_state(directory, tenants::select))
+}
+
+/// Open a context home with its `config.json` store settings and start the jobs it
+/// schedules. Its searches and counts stop after `query_timeout` when one is given.
+async fn open_home(home: &std::path::Path, query_timeout: Option<Duration>) -> Result<AppState> {
+    std::fs::create_dir_all(home)?;
+    open_store(home, &home.join("db.sqlite"), query_timeout).await
+}
+
+/// `open_home` with the database at `database` instead of `<home>/db.sqlite`. A database
+/// named this way must already exist: a mistyped path should not start an empty store.
+async fn open_store(
+    home: &std::path::Path,
+    database: &std::path::Path,
+    query_timeout: Option<Duration>,
+) -> Result<AppState> {
+    if database != home.join("db.sqlite") && !database.is_file() {
+        anyhow::bail!(
+            "No database at {}; check --db/CONTEXT_WEB_DB, or run `context init` to create one.",
+            database.display()
+        );
+    }
+    let config = load_server_config(home)?;
+    let storage = SqliteStorage::open(database)
+        .await
+        .with_context(|| format!("Failed to open database at {}", database.display()))?;
+    let mut storage = configure_storage(storage, config.store)?;
+    if let Some(limit) = query_timeout {
+        storage = storage.with_query_timeout(limit);
+    }
+    let state = AppState::new(storage.clone());
+    state.jobs.start(storage, config.jobs)?;
+    Ok(state)
+}
+
+/// Project the demo corpus is loaded into.
+const DEMO_PROJECT: &str = "demo";
+
+/// With `CONTEXT_WEB_DEMO=<fixture>`, the server runs on a throwaway home under the temp
+/// directory holding that fixture in project `demo`, so the UI can be tried (or shown)
+/// without touching `$CONTEXT_HOME`'s database.
+async fn demo_home(fixture_name: &str) -> Result<PathBuf> {
+    let fixture = fixtures::fixture(fixture_name)?;
+    let home = env::temp_dir().join(format!("context-web-demo-{}", std::process::id()));
+    if home.exists() {
+        std::fs::remove_dir_all(&home)?;
+    }
+    std::fs::create_dir_all(&home)?;
+    let storage = SqliteStorage::open(&home.join("db.sqlite")).await?;
+    let report = fixtures::load(&storage, fixture, &DEMO_PROJECT.to_string(), DEFAULT_SEED).await?;
+    storage.pool().close().await;
+    tracing::info!(
+        fixture = %report.fixture,
+        documents = report.documents,
+        home = %home.display(),
+        "Demo store loaded"
+    );
+    Ok(home)
+}
+
+fn context_home() -> Result<PathBuf> {
+    let home = match env::var("CONTEXT_HOME") {
+        Ok(home) => PathBuf::from(home),
+        Err(_) => dirs::home_dir()
+            .map(|home| home.join(".context"))
+            .unwrap_or_else(|| PathBuf::from(".context")),
+    };
+    std::fs::create_dir_all(&home)?;
+    Ok(home)
+}
+
+/// The database `--db <path>` (or `--db=<path>`) in `args`, falling back to
+/// `CONTEXT_WEB_DB`; `None` means `<home>/db.sqlite`.
+fn database_override(mut args: impl Iterator<Item = String>) -> Result<Option<PathBuf>> {
+    let mut database = None;
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--db") {
+            Some("") => {
+                let path = args.next().context("--db needs a path")?;
+                database = Some(PathBuf::from(path));
+            }
+            Some(path) if path.starts_with('=') => database = Some(PathBuf::from(&path[1..])),
+            _ => anyhow::bail!(
+                "Unknown argument {arg:?}; context-web takes only --db <path> and is \
+                 otherwise configured through CONTEXT_WEB_* variables."
+            ),
+        }
+    }
+    Ok(database.or_else(|| env::var_os("CONTEXT_WEB_DB").map(PathBuf::from)))
+}
+
+fn load_server_config(home: &std::path::Path) -> Result<ServerConfig> {
+    let path = home.join("config.json");
+    if !path.exists() {
+        return Ok(ServerConfig::default());
+    }
+    let raw = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&raw)
+        .map_err(|err| anyhow::anyhow!("Failed to parse {}: {err}", path.display()))
+}
+
+/// Bring a running home in line with its `config.json`: reschedule its jobs and, for the
+/// server's own home (the one given `telemetry`), swap the log filter.
+fn apply_config(
+    home: &std::path::Path,
+    state: &AppState,
+    telemetry: Option<&TelemetryGuard>,
+) -> Result<()> {
+    let config = load_server_config(home)?;
+    if let Some(telemetry) = telemetry {
+        telemetry.set_filter(config.log_filter.as_deref())?;
+    }
+    if state
+        .jobs
+        .restart(state.storage.untraced().clone(), config.jobs)?
+    {
+        tracing::info!(home = %home.display(), "Job schedule replaced");
+    }
+    Ok(())
+}
+
+/// Re-apply `<home>/config.json` whenever it changes or the process gets SIGHUP. A bad
+/// edit is logged and the previous settings stay in force.
+async fn watch_config(home: PathBuf, state: AppState, telemetry: Option<Arc<TelemetryGuard>>) {
+    let mut watcher = match ConfigWatcher::new(home.join("config.json"), reload::POLL_INTERVAL) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            tracing::warn!(error = %err, "Config reload disabled");
+            return;
+        }
+    };
+    loop {
+        let trigger = watcher.changed().await;
+        match apply_config(&home, &state, telemetry.as_deref()) {
+            Ok(()) => tracing::info!(
+                home = %home.display(),
+                trigger = trigger.as_str(),
+                "Config reloaded"
+            ),
+            Err(err) => tracing::error!(
+                home = %home.display(),
+                error = %err,
+                "Config reload failed; keeping the previous settings"
+            ),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let _telemetry = init_tracing("context-web", &["context_web"])?;
+    let telemetry = Arc::new(init_tracing_in_home(
+        "context-web",
+        &["context_web", "context_core"],
+        &context_home()?,
+    )?);
     let scenario = env::var("CONTEXT_SCENARIO").ok();
     let project = env::var("CONTEXT_PROJECT").ok();
     let log_context = LogContext {
@@ -38,9 +798,49 @@ async fn main() -> Result<()> {
     );
     let _server_guard = server_span.enter();
 
-    let app = Router::new()
-        .route("/healthz", get(health))
-        .route("/agent-doc", get(agent_doc));
+    let database = database_override(env::args().skip(1))?;
+    let home = match env::var("CONTEXT_WEB_DEMO") {
+        Ok(_) if database.is_some() => anyhow::bail!(
+            "CONTEXT_WEB_DEMO serves a throwaway database; drop it or --db/CONTEXT_WEB_DB."
+        ),
+        Ok(fixture) => demo_home(&fixture).await?,
+        Err(_) => context_home()?,
+    };
+    let database = database.unwrap_or_else(|| home.join("db.sqlite"));
+    let limits = LimitConfig::from_env()?;
+    let state = open_store(&home, &database, limits.request_timeout).await?;
+    tracing::info!(database = %database.display(), "Database opened");
+    // Jobs were just scheduled from this config; this picks up the log filter.
+    apply_config(&home, &state, Some(&telemetry))?;
+    tokio::spawn(watch_config(home, state.clone(), Some(telemetry.clone())));
+    let mut tenant_states = Vec::new();
+    for tenant in tenants::load()? {
+        let tenant_state = open_home(&tenant.home, limits.request_timeout)
+            .await
+            .with_context(|| format!("Failed to open tenant {}", tenant.name))?;
+        tracing::info!(tenant = %tenant.name, home = %tenant.home.display(), "Tenant opened");
+        tokio::spawn(watch_config(
+            tenant.home.clone(),
+            tenant_state.clone(),
+            None,
+        ));
+        tenant_states.push((tenant, tenant_state));
+    }
+    let app = router(state, tenant_states, limits, tenants::home_token()?);
+
+    #[cfg(unix)]
+    if let Some(socket) = unix::SocketConfig::from_env()? {
+        let listener = unix::bind(&socket)?;
+        tracing::info!(
+            scenario_id = log_context.scenario_id,
+            project = log_context.project,
+            command = log_context.command,
+            mode = format!("{:o}", socket.mode),
+            "Starting context-web on unix:{}",
+            socket.path.display()
+        );
+        return unix::serve(listener, app).await;
+    }
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 8077));
     tracing::info!(
@@ -50,7 +850,11 @@ async fn main() -> Result<()> {
         "Starting context-web on http://{addr}"
     );
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app.into_make_service()).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -58,6 +862,7 @@ async fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use context_core::DeleteMode;
     use serde_json::Value;
     use tempfile::tempdir;
 
@@ -95,4 +900,959 @@ mod tests {
         assert!(saw_healthz, "expected web.healthz span");
         assert!(saw_agent_doc, "expected web.agent-doc span");
     }
+
+    #[tokio::test]
+    async fn db_flag_names_the_database_and_must_exist() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            database_override(args(&["--db", "a.sqlite"]).into_iter()).unwrap(),
+            Some(PathBuf::from("a.sqlite"))
+        );
+        assert_eq!(
+            database_override(args(&["--db=b.sqlite"]).into_iter()).unwrap(),
+            Some(PathBuf::from("b.sqlite"))
+        );
+        assert!(database_override(args(&["--db"]).into_iter()).is_err());
+        assert!(database_override(args(&["--port", "1"]).into_iter()).is_err());
+
+        let temp = tempdir().unwrap();
+        let missing = temp.path().join("nope.sqlite");
+        let Err(err) = open_store(temp.path(), &missing, None).await else {
+            panic!("opened a database that does not exist");
+        };
+        assert!(err.to_string().contains("No database at"), "{err}");
+        assert!(!missing.exists());
+
+        let elsewhere = temp.path().join("elsewhere.sqlite");
+        SqliteStorage::open(&elsewhere).await.unwrap();
+        open_store(temp.path(), &elsewhere, None).await.unwrap();
+        assert!(!temp.path().join("db.sqlite").exists());
+    }
+
+    #[tokio::test]
+    async fn open_home_applies_the_store_settings_in_config_json() {
+        let temp = tempdir().unwrap();
+        std::fs::write(
+            temp.path().join("config.json"),
+            r#"{"keys": {"normalize": true}, "expiry": [{"tag": "scratch", "after": "1h"}]}"#,
+        )
+        .unwrap();
+        let state = open_home(temp.path(), None).await.unwrap();
+        let stored = state
+            .storage
+            .store(doc("a", "Notes/Deploy", &["scratch"]))
+            .await
+            .unwrap();
+        assert_eq!(stored.key.as_deref(), Some("notes/deploy"));
+        assert_eq!(stored.ttl_seconds, Some(3600));
+    }
+
+    fn doc(id: &str, key: &str, tags: &[&str]) -> Document {
+        let now = chrono::Utc::now();
+        Document {
+            id: DocumentId(id.to_string()),
+            project: "demo".to_string(),
+            key: Some(key.to_string()),
+            namespace: None,
+            title: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            body_markdown: format!("body of {key}"),
+            created_at: now,
+            updated_at: now,
+            source: context_core::SourceType::Web,
+            source_meta: None,
+            version: 1,
+            ttl_seconds: None,
+            deleted_at: None,
+            visibility: None,
+            metadata: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn related_endpoint_ranks_documents_and_reports_missing() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage
+            .put(doc("a", "auth/tokens", &["auth"]))
+            .await
+            .unwrap();
+        storage
+            .put(doc("b", "auth/sessions", &["auth"]))
+            .await
+            .unwrap();
+        let state = AppState::new(storage);
+
+        let params = |key: &str| RelatedParams {
+            project: Some("demo".to_string()),
+            key: Some(key.to_string()),
+            id: None,
+            limit: None,
+        };
+
+        let Json(hits) = related(State(state.clone()), Query(params("auth/tokens")))
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.key.as_deref(), Some("auth/sessions"));
+
+        let (status, Json(body)) = related(State(state), Query(params("missing")))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn projects_endpoint_lists_the_registry() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage.put(doc("a", "notes", &[])).await.unwrap();
+        storage
+            .register_project(&"empty".to_string())
+            .await
+            .unwrap();
+
+        let Json(listed) = projects(State(AppState::new(storage))).await.unwrap();
+        let ids: Vec<&str> = listed
+            .iter()
+            .map(|stats| stats.project.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["demo", "empty"]);
+        assert_eq!(listed[0].documents, 1);
+    }
+
+    #[tokio::test]
+    async fn demo_home_serves_the_fixture() {
+        let home = demo_home("small").await.unwrap();
+        let state = open_home(&home, None).await.unwrap();
+
+        let Json(listed) = projects(State(state)).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].project.id, DEMO_PROJECT);
+        assert_eq!(
+            listed[0].documents as usize,
+            fixtures::fixture("small").unwrap().documents
+        );
+        std::fs::remove_dir_all(home).unwrap();
+
+        assert!(demo_home("nope").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn put_endpoint_honors_idempotency_keys() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let state = AppState::new(storage);
+        let request = || PutDocument {
+            body_markdown: "deploy with make release".to_string(),
+            tags: vec!["ops".to_string()],
+            namespace: None,
+            title: None,
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", HeaderValue::from_static("retry-1"));
+        let path = || Path(("demo".to_string(), "runbooks/deploy".to_string()));
+
+        let (first_headers, Json(first)) = put_document(
+            State(state.clone()),
+            path(),
+            headers.clone(),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+        assert!(first_headers.get("idempotent-replayed").is_none());
+        assert_eq!(first.source.as_str(), "web");
+
+        let (retry_headers, Json(retry)) = put_document(
+            State(state.clone()),
+            path(),
+            headers.clone(),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(retry_headers["idempotent-replayed"], "true");
+        assert_eq!(
+            (retry.id.0.as_str(), retry.version),
+            (first.id.0.as_str(), 1)
+        );
+
+        let changed = PutDocument {
+            body_markdown: "something else".to_string(),
+            ..request()
+        };
+        let (status, Json(body)) = put_document(State(state), path(), headers, Json(changed))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["error"]["code"], "idempotency_conflict");
+    }
+
+    #[tokio::test]
+    async fn conditional_requests_use_etags() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage.put(doc("a", "notes", &[])).await.unwrap();
+        let state = AppState::new(storage);
+        let path = || Path(("demo".to_string(), "notes".to_string()));
+        let with_if_match = |tag: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("if-match", HeaderValue::from_str(tag).unwrap());
+            headers
+        };
+        let request = || PutDocument {
+            body_markdown: "revised".to_string(),
+            tags: Vec::new(),
+            namespace: None,
+            title: None,
+        };
+
+        let (headers, Json(current)) = get_document(State(state.clone()), path()).await.unwrap();
+        let tag = headers[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(tag, format!("\"{}.1\"", current.id.0));
+
+        let (headers, Json(revised)) = put_document(
+            State(state.clone()),
+            path(),
+            with_if_match(&tag),
+            Json(request()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(revised.version, 2);
+        assert_ne!(headers[header::ETAG].to_str().unwrap(), tag);
+
+        // The ETag read before the revision is stale now.
+        let (status, Json(body)) = put_document(
+            State(state.clone()),
+            path(),
+            with_if_match(&tag),
+            Json(request()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(body["error"]["code"], "precondition_failed");
+        let (status, _) = delete_document(State(state.clone()), path(), with_if_match(&tag))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+
+        let Json(deleted) = delete_document(State(state.clone()), path(), with_if_match("*"))
+            .await
+            .unwrap();
+        assert_eq!(deleted["status"], "deleted");
+        let (status, _) = get_document(State(state), path()).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn ids_that_are_not_header_text_are_served_without_an_etag() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage.put(doc("line\nbreak", "notes", &[])).await.unwrap();
+        let state = AppState::new(storage);
+
+        let (headers, Json(served)) = get_document(
+            State(state),
+            Path(("demo".to_string(), "notes".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(served.id.0, "line\nbreak");
+        assert!(headers.get(header::ETAG).is_none());
+    }
+
+    #[tokio::test]
+    async fn list_endpoint_filters_and_pages() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        for (id, key, tags) in [
+            ("a", "auth/tokens", &["auth"][..]),
+            ("b", "auth/sessions", &["auth", "draft"][..]),
+            ("c", "deploy", &[][..]),
+        ] {
+            storage.put(doc(id, key, tags)).await.unwrap();
+        }
+        let state = AppState::new(storage);
+        // Parse the way axum does, so comma-separated lists are covered too.
+        let query = |raw: &str| Query::<ListQuery>::try_from_uri(&raw.parse().unwrap()).unwrap();
+
+        let Json(page) = list_documents(
+            State(state.clone()),
+            Path("demo".to_string()),
+            query("/?key_prefix=auth/&not_tags=draft"),
+        )
+        .await
+        .unwrap();
+        let keys: Vec<_> = page
+            .documents
+            .iter()
+            .map(|d| d.key.clone().unwrap())
+            .collect();
+        assert_eq!(keys, vec!["auth/tokens"]);
+        assert!(page.next_cursor.is_none());
+
+        let mut seen = Vec::new();
+        let mut uri = "/?limit=2".to_string();
+        loop {
+            let Json(page) =
+                list_documents(State(state.clone()), Path("demo".to_string()), query(&uri))
+                    .await
+                    .unwrap();
+            seen.extend(page.documents.into_iter().map(|d| d.id.0));
+            match page.next_cursor {
+                Some(next) => uri = format!("/?limit=2&cursor={next}"),
+                None => break,
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, vec!["a", "b", "c"]);
+
+        let (status, _) =
+            list_documents(State(state), Path("demo".to_string()), query("/?cursor=zz"))
+                .await
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn private_documents_are_never_served() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage.put(doc("a", "plan", &[])).await.unwrap();
+        storage
+            .put(Document {
+                visibility: Some(Visibility::Private),
+                ..doc("b", "scratch", &[])
+            })
+            .await
+            .unwrap();
+        let state = AppState::new(storage.clone());
+        let path = |key: &str| Path(("demo".to_string(), key.to_string()));
+
+        let (status, _) = get_document(State(state.clone()), path("scratch"))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let Json(page) = list_documents(
+            State(state.clone()),
+            Path("demo".to_string()),
+            Query(ListQuery::default()),
+        )
+        .await
+        .unwrap();
+        let keys: Vec<_> = page
+            .documents
+            .iter()
+            .filter_map(|d| d.key.clone())
+            .collect();
+        assert_eq!(keys, ["plan"]);
+
+        let request: PutDocument =
+            serde_json::from_value(serde_json::json!({ "body_markdown": "overwritten" })).unwrap();
+        let (status, _) = put_document(
+            State(state.clone()),
+            path("scratch"),
+            HeaderMap::new(),
+            Json(request),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = delete_document(State(state.clone()), path("scratch"), HeaderMap::new())
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let scratch = storage
+            .get_by_key(&"demo".to_string(), "scratch")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(scratch.body_markdown, "body of scratch");
+
+        let query = r#"{
+            document(project: "demo", key: "scratch") { key }
+            documents(project: "demo") { documents { key } }
+            search(text: "body", project: "demo") { document { key } }
+        }"#;
+        let Json(response) = graphql(State(state), Json(query.into())).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = serde_json::to_value(&response.data).unwrap();
+        assert_eq!(
+            data,
+            serde_json::json!({
+                "document": null,
+                "documents": { "documents": [{ "key": "plan" }] },
+                "search": [{ "document": { "key": "plan" } }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn project_totals_leave_out_private_documents() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage.put(doc("a", "plan", &[])).await.unwrap();
+        storage
+            .put(Document {
+                visibility: Some(Visibility::Private),
+                ..doc("b", "scratch", &[])
+            })
+            .await
+            .unwrap();
+        let state = AppState::new(storage);
+
+        let Json(stats) = projects(State(state.clone())).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].documents, 1);
+        assert_eq!(stats[0].bytes, "body of plan".len() as u64);
+
+        let Json(response) =
+            graphql(State(state), Json("{ projects { id documents } }".into())).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            serde_json::to_value(&response.data).unwrap(),
+            serde_json::json!({ "projects": [{ "id": "demo", "documents": 1 }] })
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_follow_the_alias_a_rename_leaves() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let plan = storage.put(doc("a", "plan", &[])).await.unwrap();
+        storage.rename_key(plan, "roadmap", true).await.unwrap();
+        let state = AppState::new(storage);
+
+        let (_, Json(document)) = get_document(
+            State(state.clone()),
+            Path(("demo".to_string(), "plan".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(document.key.as_deref(), Some("roadmap"));
+
+        let query = r#"{ document(project: "demo", key: "plan") { key } }"#;
+        let Json(response) = graphql(State(state), Json(query.into())).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            serde_json::to_value(&response.data).unwrap(),
+            serde_json::json!({ "document": { "key": "roadmap" } })
+        );
+    }
+
+    #[tokio::test]
+    async fn limits_answer_with_json_errors() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let alice = Tenant {
+            name: "alice".to_string(),
+            home: temp.path().join("alice"),
+            token: Some("alice-token".to_string()),
+        };
+        let alice_state = open_home(&alice.home, None).await.unwrap();
+        let app = router(
+            AppState::new(storage),
+            vec![(alice, alice_state)],
+            LimitConfig {
+                requests_per_minute: 2,
+                max_body_bytes: 64,
+                ..LimitConfig::default()
+            },
+            None,
+        );
+        let put = |token: &str, body: String| {
+            Request::put("/api/projects/demo/docs/notes")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let error_code = |response: axum::response::Response| async move {
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let body: Value = serde_json::from_slice(&bytes).unwrap();
+            body["error"]["code"].as_str().unwrap().to_string()
+        };
+
+        let oversized = serde_json::json!({ "body_markdown": "x".repeat(100) }).to_string();
+        let response = app.clone().oneshot(put("a", oversized)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error_code(response).await, "payload_too_large");
+
+        let small = serde_json::json!({ "body_markdown": "hi" }).to_string();
+        let response = app.clone().oneshot(put("a", small.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.clone().oneshot(put("a", small.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+        assert_eq!(error_code(response).await, "rate_limited");
+
+        // Made-up tokens are not callers of their own, so rotating them gains nothing.
+        for token in ["b", "c", "d"] {
+            let response = app
+                .clone()
+                .oneshot(put(token, small.clone()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        }
+
+        // A tenant's token has its own allowance.
+        let response = app.oneshot(put("alice-token", small)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn calendar_feed_lists_reminders_and_expirations() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let rollout = storage.put(doc("a", "rollout", &[])).await.unwrap();
+        storage
+            .put(Document {
+                ttl_seconds: Some(86_400),
+                ..doc("b", "scratch", &[])
+            })
+            .await
+            .unwrap();
+        storage
+            .add_reminder(
+                &rollout.id,
+                chrono::Utc::now() + chrono::Duration::days(3),
+                Some("check rates".to_string()),
+            )
+            .await
+            .unwrap();
+        let app = router(
+            AppState::new(storage),
+            Vec::new(),
+            LimitConfig::default(),
+            None,
+        );
+
+        let feed = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(
+                    response.headers()[header::CONTENT_TYPE],
+                    "text/calendar; charset=utf-8"
+                );
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                String::from_utf8(bytes.to_vec()).unwrap()
+            }
+        };
+        let ics = feed("/api/calendar.ics?project=demo").await;
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("SUMMARY:Expires: demo/scratch\r\n"));
+        assert!(ics.contains("SUMMARY:Follow up: demo/rollout\r\nDESCRIPTION:check rates\r\n"));
+        assert_eq!(
+            feed("/api/calendar.ics")
+                .await
+                .matches("BEGIN:VEVENT")
+                .count(),
+            2
+        );
+        let elsewhere = feed("/api/calendar.ics?project=other").await;
+        assert!(!elsewhere.contains("BEGIN:VEVENT"));
+    }
+
+    #[tokio::test]
+    async fn jobs_endpoint_reports_runs() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let state = AppState::new(storage.clone());
+        let config: ServerConfig = serde_json::from_str(
+            r#"{"current": null, "jobs": [{"kind": "gc", "every": "1h"}, {"kind": "wal_checkpoint", "every": "10m"}]}"#,
+        )
+        .unwrap();
+        state.jobs.start(storage.clone(), config.jobs).unwrap();
+        state.jobs.run(0, &storage).await;
+
+        let Json(statuses) = jobs(State(state)).await;
+        let statuses = serde_json::to_value(statuses).unwrap();
+        assert_eq!(statuses[0]["kind"], "gc");
+        assert_eq!(statuses[0]["runs"], 1);
+        assert_eq!(statuses[0]["last_outcome"], "ok");
+        assert_eq!(statuses[0]["last_message"], "deleted 0 expired document(s)");
+        assert_eq!(statuses[1]["kind"], "wal_checkpoint");
+        assert_eq!(statuses[1]["runs"], 0);
+        assert!(statuses[1]["last_run_at"].is_null());
+    }
+
+    #[tokio::test]
+    async fn invalid_job_config_fails_at_startup() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let config: ServerConfig =
+            serde_json::from_str(r#"{"jobs": [{"kind": "backup", "every": "0m"}]}"#).unwrap();
+        let board = JobBoard::default();
+        let err = board.start(storage, config.jobs).unwrap_err();
+        assert!(err.to_string().contains("shortest interval is 1m"));
+        assert!(board.snapshot().is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_endpoint_skips_private_documents_and_needs_the_home_token() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage.put(doc("a", "deploy", &[])).await.unwrap();
+        storage
+            .put(Document {
+                visibility: Some(Visibility::Private),
+                ..doc("b", "deploy-secrets", &[])
+            })
+            .await
+            .unwrap();
+        let app = router(
+            AppState::new(storage),
+            Vec::new(),
+            LimitConfig::default(),
+            Some("home-token".to_string()),
+        );
+        let search = |token: Option<&str>| {
+            let mut request = Request::post("/api/projects/demo/search")
+                .header(header::CONTENT_TYPE, "application/json");
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            app.clone()
+                .oneshot(request.body(Body::from(r#"{"text": "body"}"#)).unwrap())
+        };
+
+        let response = search(Some("home-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let hits: Vec<SearchHit> = serde_json::from_slice(&bytes).unwrap();
+        let keys: Vec<_> = hits
+            .iter()
+            .map(|hit| hit.document.key.as_deref().unwrap_or_default())
+            .collect();
+        assert_eq!(keys, vec!["deploy"]);
+
+        for token in [None, Some("wrong")] {
+            let response = search(token).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app
+            .oneshot(Request::get("/healthz").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn events_stream_shared_changes_in_the_project() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use futures::StreamExt;
+        use tower::ServiceExt;
+
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let state = AppState::new(storage);
+        let app = router(state.clone(), Vec::new(), LimitConfig::default(), None);
+        let response = app
+            .oneshot(
+                Request::get("/api/events?project=demo")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let mut body = response.into_body().into_data_stream();
+
+        state
+            .storage
+            .put(Document {
+                visibility: Some(Visibility::Private),
+                ..doc("a", "secret", &[])
+            })
+            .await
+            .unwrap();
+        state
+            .storage
+            .put(Document {
+                project: "other".to_string(),
+                ..doc("b", "elsewhere", &[])
+            })
+            .await
+            .unwrap();
+        state.storage.put(doc("c", "deploy", &[])).await.unwrap();
+        state
+            .storage
+            .delete_by_id(&DocumentId("c".to_string()), DeleteMode::Soft)
+            .await
+            .unwrap();
+
+        let mut frames = Vec::new();
+        while frames.len() < 2 {
+            let chunk = body.next().await.unwrap().unwrap();
+            frames.push(String::from_utf8(chunk.to_vec()).unwrap());
+        }
+        assert!(frames[0].starts_with("event: created\n"), "{}", frames[0]);
+        assert!(frames[0].contains(r#""key":"deploy""#), "{}", frames[0]);
+        assert!(frames[1].starts_with("event: deleted\n"), "{}", frames[1]);
+        assert!(!frames.concat().contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn tenants_are_selected_by_token_or_path_prefix() {
+        use axum::body::{to_bytes, Body};
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let temp = tempdir().unwrap();
+        let primary = open_home(&temp.path().join("primary"), None).await.unwrap();
+        let alice = Tenant {
+            name: "alice".to_string(),
+            home: temp.path().join("alice"),
+            token: Some("alice-token".to_string()),
+        };
+        let bob = Tenant {
+            name: "bob".to_string(),
+            home: temp.path().join("bob"),
+            token: None,
+        };
+        let tenant_states = vec![
+            (alice.clone(), open_home(&alice.home, None).await.unwrap()),
+            (bob.clone(), open_home(&bob.home, None).await.unwrap()),
+        ];
+        let app = router(primary, tenant_states, LimitConfig::default(), None);
+        let send = |method: &str, uri: &str, token: Option<&str>, body: Option<&str>| {
+            let mut request = Request::builder().method(method).uri(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let body = match body {
+                Some(body) => {
+                    request = request.header(header::CONTENT_TYPE, "application/json");
+                    Body::from(body.to_string())
+                }
+                None => Body::empty(),
+            };
+            app.clone().oneshot(request.body(body).unwrap())
+        };
+
+        let put = r#"{"body_markdown": "alice's notes"}"#;
+        let response = send(
+            "PUT",
+            "/api/projects/demo/docs/notes",
+            Some("alice-token"),
+            Some(put),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Same tenant by path prefix; the token is still required there.
+        let response = send(
+            "GET",
+            "/t/alice/api/projects/demo/docs/notes",
+            Some("alice-token"),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let doc: Document = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(doc.body_markdown, "alice's notes");
+        let response = send("GET", "/t/alice/api/projects/demo/docs/notes", None, None)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Other homes have their own databases.
+        for uri in [
+            "/api/projects/demo/docs/notes",
+            "/t/bob/api/projects/demo/docs/notes",
+        ] {
+            let response = send("GET", uri, None, None).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+        }
+        assert!(alice.home.join("db.sqlite").exists());
+
+        let response = send("GET", "/t/carol/healthz", None, None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "unknown_tenant");
+    }
+
+    #[tokio::test]
+    async fn graphql_resolves_nested_selections() {
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        storage
+            .put(doc("a", "auth/tokens", &["auth"]))
+            .await
+            .unwrap();
+        storage
+            .put(doc("b", "auth/sessions", &["auth"]))
+            .await
+            .unwrap();
+        let state = AppState::new(storage);
+
+        let query = r#"{
+            document(project: "demo", key: "auth/tokens") {
+                key
+                versions { version }
+                related { document { key } }
+            }
+            documents(project: "demo", filter: { keyPrefix: "auth/" }, limit: 1) {
+                documents { key }
+                nextCursor
+            }
+            search(text: "body", project: "demo") { document { id } }
+        }"#;
+        let Json(response) = graphql(State(state.clone()), Json(query.into())).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = serde_json::to_value(&response.data).unwrap();
+        assert_eq!(
+            data["document"],
+            serde_json::json!({
+                "key": "auth/tokens",
+                "versions": [{ "version": 1 }],
+                "related": [{ "document": { "key": "auth/sessions" } }],
+            })
+        );
+        assert_eq!(data["documents"]["documents"].as_array().unwrap().len(), 1);
+        assert!(data["documents"]["nextCursor"].is_string());
+        assert_eq!(data["search"].as_array().unwrap().len(), 2);
+
+        let Json(response) = graphql(
+            State(state),
+            Json(r#"{ documents(project: "demo", cursor: "zz") { nextCursor } }"#.into()),
+        )
+        .await;
+        assert_eq!(response.errors.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serves_http_on_an_owner_only_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let temp = tempdir().unwrap();
+        let storage = SqliteStorage::open(&temp.path().join("db.sqlite"))
+            .await
+            .unwrap();
+        let app = router(
+            AppState::new(storage),
+            Vec::new(),
+            LimitConfig::default(),
+            None,
+        );
+        let config = unix::SocketConfig {
+            path: temp.path().join("web.sock"),
+            mode: context_core::socket::DEFAULT_MODE,
+        };
+        // A socket left behind by a server that died does not block the next one.
+        std::fs::write(&config.path, "").unwrap();
+        let listener = unix::bind(&config).unwrap();
+        let mode = std::fs::metadata(&config.path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        tokio::spawn(unix::serve(listener, app));
+
+        let mut stream = tokio::net::UnixStream::connect(&config.path).await.unwrap();
+        stream
+            .write_all(b"GET /healthz HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let err = unix::bind(&config).unwrap_err();
+        assert!(err.to_string().contains("already listening"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn config_reload_reschedules_jobs() {
+        let temp = tempdir().unwrap();
+        let state = open_home(temp.path(), None).await.unwrap();
+        assert!(state.jobs.snapshot().is_empty());
+
+        let config = temp.path().join("config.json");
+        std::fs::write(
+            &config,
+            r#"{"jobs": [{"kind": "wal_checkpoint", "every": "1h"}]}"#,
+        )
+        .unwrap();
+        apply_config(temp.path(), &state, None).unwrap();
+        assert_eq!(state.jobs.snapshot().len(), 1);
+
+        // An unchanged schedule keeps its timers; a broken one is rejected as a whole.
+        let scheduled = state.jobs.snapshot()[0].next_run_at;
+        apply_config(temp.path(), &state, None).unwrap();
+        assert_eq!(state.jobs.snapshot()[0].next_run_at, scheduled);
+        std::fs::write(&config, r#"{"jobs": [{"kind": "gc", "every": "0m"}]}"#).unwrap();
+        assert!(apply_config(temp.path(), &state, None).is_err());
+        assert_eq!(state.jobs.snapshot().len(), 1);
+
+        std::fs::write(&config, "{}").unwrap();
+        apply_config(temp.path(), &state, None).unwrap();
+        assert!(state.jobs.snapshot().is_empty());
+    }
 }
@@ -0,0 +1,241 @@
+use std::{collections::HashMap, env, path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+
+use crate::api_error;
+
+/// Path prefix under which a tenant's API is served: `/t/<name>/api/...`.
+pub const PREFIX: &str = "/t/";
+
+/// One context home served by this process, e.g.
+/// `{"name": "alice", "home": "/srv/context/alice", "token": "..."}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Tenant {
+    pub name: String,
+    pub home: PathBuf,
+    /// Bearer token that selects this tenant and is required under its path prefix.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Tenants listed in the JSON file named by `CONTEXT_WEB_TENANTS`; none when it is unset.
+pub fn load() -> Result<Vec<Tenant>> {
+    let Ok(path) = env::var("CONTEXT_WEB_TENANTS") else {
+        return Ok(Vec::new());
+    };
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read tenants file {path}"))?;
+    let tenants: Vec<Tenant> =
+        serde_json::from_str(&raw).with_context(|| format!("Failed to parse {path}"))?;
+    validate(&tenants)?;
+    Ok(tenants)
+}
+
+fn validate(tenants: &[Tenant]) -> Result<()> {
+    let mut names = HashMap::new();
+    let mut tokens = HashMap::new();
+    for tenant in tenants {
+        let valid_name = !tenant.name.is_empty()
+            && tenant
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            bail!(
+                "Tenant name {:?} must be letters, digits, '-' or '_'.",
+                tenant.name
+            );
+        }
+        if names.insert(tenant.name.as_str(), ()).is_some() {
+            bail!("Tenant {:?} is listed twice.", tenant.name);
+        }
+        if let Some(token) = &tenant.token {
+            if token.trim().is_empty() {
+                bail!("Tenant {:?} has an empty token.", tenant.name);
+            }
+            if let Some(other) = tokens.insert(token.as_str(), tenant.name.as_str()) {
+                bail!(
+                    "Tenants {other:?} and {:?} share a token; each needs its own.",
+                    tenant.name
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The bearer token `CONTEXT_WEB_TOKEN` sets for the server's own context home; `None`
+/// leaves it open.
+pub fn home_token() -> Result<Option<String>> {
+    match env::var("CONTEXT_WEB_TOKEN") {
+        Ok(token) if token.trim().is_empty() => bail!("CONTEXT_WEB_TOKEN is set but empty."),
+        Ok(token) => Ok(Some(token.trim().to_string())),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Which tenant a request is for, by path prefix or bearer token.
+#[derive(Clone, Default)]
+pub struct TenantDirectory {
+    /// Tenant name to its token, if it has one.
+    tokens_by_name: Arc<HashMap<String, Option<String>>>,
+    names_by_token: Arc<HashMap<String, String>>,
+    /// Required on the API of the server's own home, when set.
+    home_token: Option<Arc<str>>,
+}
+
+impl TenantDirectory {
+    pub fn new(tenants: &[Tenant]) -> Self {
+        Self {
+            tokens_by_name: Arc::new(
+                tenants
+                    .iter()
+                    .map(|tenant| (tenant.name.clone(), tenant.token.clone()))
+                    .collect(),
+            ),
+            names_by_token: Arc::new(
+                tenants
+                    .iter()
+                    .filter_map(|tenant| Some((tenant.token.clone()?, tenant.name.clone())))
+                    .collect(),
+            ),
+            home_token: None,
+        }
+    }
+
+    /// Require `token` on `/api/` and `/graphql` requests for the server's own home.
+    pub fn with_home_token(mut self, token: Option<String>) -> Self {
+        self.home_token = token.map(Arc::from);
+        self
+    }
+}
+
+/// Whose token a request carried, once `select` has checked it; request limits are
+/// counted per caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Caller {
+    /// The server's own home, by `CONTEXT_WEB_TOKEN`.
+    Home,
+    /// A tenant, by its own token.
+    Tenant(String),
+}
+
+/// Paths that read or write documents; `/healthz` and `/agent-doc` stay open.
+fn is_api(path: &str) -> bool {
+    path.starts_with("/api/") || path == "/graphql"
+}
+
+fn bearer(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// Send a request to its tenant before routing.
+///
+/// Under `/t/<name>/` the tenant must exist and, if it has a token, the request must
+/// carry it. Elsewhere a tenant's token moves the request under that tenant's prefix;
+/// requests with neither are served from the server's own context home, whose API
+/// needs the home token when one is set.
+pub async fn select(
+    State(directory): State<TenantDirectory>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    if let Some(rest) = path.strip_prefix(PREFIX) {
+        let name = rest.split('/').next().unwrap_or_default();
+        let Some(token) = directory.tokens_by_name.get(name) else {
+            return api_error(
+                StatusCode::NOT_FOUND,
+                "unknown_tenant",
+                format!("No tenant named {name:?}."),
+            )
+            .into_response();
+        };
+        if token.is_some() {
+            if bearer(&request) != token.as_deref() {
+                return api_error(
+                    StatusCode::UNAUTHORIZED,
+                    "unauthorized",
+                    format!("Tenant {name:?} requires its bearer token."),
+                )
+                .into_response();
+            }
+            request
+                .extensions_mut()
+                .insert(Caller::Tenant(name.to_string()));
+        }
+        return next.run(request).await;
+    }
+
+    let tenant = bearer(&request).and_then(|token| directory.names_by_token.get(token));
+    if let (None, Some(token)) = (tenant, &directory.home_token) {
+        if is_api(&path) && bearer(&request) != Some(&**token) {
+            return api_error(
+                StatusCode::UNAUTHORIZED,
+                "unauthorized",
+                "This server requires a bearer token.",
+            )
+            .into_response();
+        }
+    }
+    if tenant.is_none()
+        && directory.home_token.is_some()
+        && bearer(&request) == directory.home_token.as_deref()
+    {
+        request.extensions_mut().insert(Caller::Home);
+    }
+    if let Some(name) = tenant {
+        request
+            .extensions_mut()
+            .insert(Caller::Tenant(name.clone()));
+        let query = request
+            .uri()
+            .query()
+            .map(|query| format!("?{query}"))
+            .unwrap_or_default();
+        let rewritten = format!("{PREFIX}{name}{path}{query}");
+        match rewritten.parse::<Uri>() {
+            Ok(uri) => *request.uri_mut() = uri,
+            Err(err) => {
+                return api_error(StatusCode::BAD_REQUEST, "bad_request", err.to_string())
+                    .into_response()
+            }
+        }
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant(name: &str, token: Option<&str>) -> Tenant {
+        Tenant {
+            name: name.to_string(),
+            home: PathBuf::from(format!("/srv/{name}")),
+            token: token.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn tenant_lists_are_validated() {
+        assert!(validate(&[tenant("alice", Some("a")), tenant("bob", None)]).is_ok());
+        assert!(validate(&[tenant("alice", None), tenant("alice", None)]).is_err());
+        assert!(validate(&[tenant("alice", Some("x")), tenant("bob", Some("x"))]).is_err());
+        assert!(validate(&[tenant("a/b", None)]).is_err());
+        assert!(validate(&[tenant("alice", Some(" "))]).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use std::{env, path::PathBuf};
+
+use anyhow::{Context, Result};
+use axum::Router;
+use context_core::socket;
+use hyper_util::{rt::TokioIo, service::TowerToHyperService};
+use tokio::net::UnixListener;
+
+/// Where to listen instead of TCP, from `CONTEXT_WEB_SOCKET`, with the permission bits
+/// from `CONTEXT_WEB_SOCKET_MODE` (octal, default `600`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketConfig {
+    pub path: PathBuf,
+    pub mode: u32,
+}
+
+impl SocketConfig {
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(path) = env::var_os("CONTEXT_WEB_SOCKET") else {
+            return Ok(None);
+        };
+        let mode = match env::var("CONTEXT_WEB_SOCKET_MODE") {
+            Ok(raw) => socket::parse_mode(&raw).context("Invalid CONTEXT_WEB_SOCKET_MODE")?,
+            Err(_) => socket::DEFAULT_MODE,
+        };
+        Ok(Some(Self {
+            path: path.into(),
+            mode,
+        }))
+    }
+}
+
+/// Bind the socket, restricted to `config.mode`, ready for `serve`.
+pub fn bind(config: &SocketConfig) -> Result<UnixListener> {
+    Ok(UnixListener::from_std(socket::bind(
+        &config.path,
+        config.mode,
+    )?)?)
+}
+
+/// Answer HTTP/1.1 on `listener` until the process is stopped.
+pub async fn serve(listener: UnixListener, app: Router) -> Result<()> {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            // Running out of file descriptors and the like passes; keep serving.
+            Err(err) => {
+                tracing::warn!(error = %err, "Failed to accept a socket connection");
+                tokio::time::sleep(socket::ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            let connection = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            if let Err(err) = connection.await {
+                tracing::warn!(error = %err, "Socket connection failed");
+            }
+        });
+    }
+}
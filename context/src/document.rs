@@ -0,0 +1,205 @@
+//! What the store hands back. Each type is `#[non_exhaustive]`: read its fields freely,
+//! but only this crate builds one, so a field added later breaks nobody.
+
+use chrono::{DateTime, Utc};
+
+/// Who wrote a document.
+///
+/// New sources may be added in minor releases; match with a `_` arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SourceType {
+    Agent,
+    User,
+    Import,
+    System,
+    /// A tool integration, named in [`Document::tool`].
+    Tool,
+    Web,
+    Mcp,
+    /// A source recorded by a newer release than the one this crate was built against.
+    Unknown,
+}
+
+impl SourceType {
+    pub(crate) fn from_core(source: context_core::SourceType) -> Self {
+        use context_core::SourceType as Core;
+        match source {
+            Core::Agent => Self::Agent,
+            Core::User => Self::User,
+            Core::Import => Self::Import,
+            Core::System => Self::System,
+            Core::Tool => Self::Tool,
+            Core::Web => Self::Web,
+            Core::Mcp => Self::Mcp,
+            Core::Unknown => Self::Unknown,
+        }
+    }
+
+    pub(crate) fn into_core(self) -> context_core::SourceType {
+        use context_core::SourceType as Core;
+        match self {
+            Self::Agent => Core::Agent,
+            Self::User => Core::User,
+            Self::Import => Core::Import,
+            Self::System => Core::System,
+            Self::Tool => Core::Tool,
+            Self::Web => Core::Web,
+            Self::Mcp => Core::Mcp,
+            Self::Unknown => Core::Unknown,
+        }
+    }
+}
+
+/// Who a document is for.
+///
+/// New levels may be added in minor releases; match with a `_` arm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Visibility {
+    /// Personal notes, never served over the web or exported.
+    Private,
+    /// Anyone the project is shared with.
+    #[default]
+    Project,
+    /// Fit to publish.
+    Public,
+}
+
+impl Visibility {
+    pub(crate) fn from_core(visibility: context_core::Visibility) -> Self {
+        use context_core::Visibility as Core;
+        match visibility {
+            Core::Private => Self::Private,
+            Core::Project => Self::Project,
+            Core::Public => Self::Public,
+        }
+    }
+
+    pub(crate) fn into_core(self) -> context_core::Visibility {
+        use context_core::Visibility as Core;
+        match self {
+            Self::Private => Core::Private,
+            Self::Project => Core::Project,
+            Self::Public => Core::Public,
+        }
+    }
+}
+
+/// One revision of a document.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Document {
+    pub id: String,
+    pub project: String,
+    pub key: Option<String>,
+    pub namespace: Option<String>,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub body_markdown: String,
+    /// Structured fields beyond tags, such as `ticket` or `url`.
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub source: SourceType,
+    /// The tool that wrote it, when `source` is [`SourceType::Tool`] or the writer named one.
+    pub tool: Option<String>,
+    /// Starts at 1 and goes up by one with each revision.
+    pub version: u64,
+    /// Seconds after `created_at` that the document expires.
+    pub ttl_seconds: Option<i64>,
+    pub visibility: Visibility,
+    /// When it was deleted; only revisions from `Store::history` can be deleted.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl Document {
+    pub(crate) fn from_core(doc: context_core::Document) -> Self {
+        let visibility = Visibility::from_core(doc.visibility());
+        Self {
+            id: doc.id.0,
+            project: doc.project,
+            key: doc.key,
+            namespace: doc.namespace,
+            title: doc.title,
+            tags: doc.tags,
+            body_markdown: doc.body_markdown,
+            metadata: doc.metadata,
+            created_at: doc.created_at,
+            updated_at: doc.updated_at,
+            source: SourceType::from_core(doc.source),
+            tool: doc.source_meta.and_then(|meta| meta.tool),
+            version: doc.version,
+            ttl_seconds: doc.ttl_seconds,
+            visibility,
+            deleted_at: doc.deleted_at,
+        }
+    }
+}
+
+/// A search result.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SearchHit {
+    pub document: Document,
+    pub score: f32,
+    /// The parts `score` is the sum of.
+    pub breakdown: ScoreBreakdown,
+}
+
+impl SearchHit {
+    pub(crate) fn from_core(hit: context_core::SearchHit) -> Self {
+        Self {
+            document: Document::from_core(hit.document),
+            score: hit.score,
+            breakdown: ScoreBreakdown::from_core(hit.breakdown),
+        }
+    }
+}
+
+/// The components of a search score; more may be added as the ranking grows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ScoreBreakdown {
+    /// Negated FTS5 bm25 rank: higher means a closer text match.
+    pub bm25: f32,
+    /// `1 / (1 + hours since the last update)`.
+    pub recency: f32,
+    /// 0.5 for each tag that equals a query term.
+    pub tag_bonus: f32,
+    /// What pinning adds; always 0 until documents can be pinned.
+    pub pin_boost: f32,
+}
+
+impl ScoreBreakdown {
+    fn from_core(breakdown: context_core::ScoreBreakdown) -> Self {
+        Self {
+            bm25: breakdown.bm25,
+            recency: breakdown.recency,
+            tag_bonus: breakdown.tag_bonus,
+            pin_boost: breakdown.pin_boost,
+        }
+    }
+}
+
+/// One page of a listing; `next_cursor` is set when more documents follow.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ListPage {
+    pub documents: Vec<Document>,
+    /// Pass to [`ListQuery::cursor`](crate::ListQuery::cursor) for the next page.
+    pub next_cursor: Option<String>,
+}
+
+impl ListPage {
+    pub(crate) fn from_core(page: context_core::ListPage) -> Self {
+        Self {
+            documents: page
+                .documents
+                .into_iter()
+                .map(Document::from_core)
+                .collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
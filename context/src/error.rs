@@ -0,0 +1,88 @@
+use std::{error::Error as _, fmt};
+
+use context_core::{Conflict, ContextError};
+
+/// What went wrong, for callers that handle some failures differently.
+///
+/// New kinds may be added in minor releases; match with a `_` arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The document, revision, or project asked for does not exist.
+    NotFound,
+    /// `Store::store_expecting` found the document at another version.
+    VersionConflict {
+        expected: u64,
+        /// The live version now, or `None` when there is no live document.
+        actual: Option<u64>,
+    },
+    /// The write collided with the store some other way, such as a key another live
+    /// document holds.
+    Conflict,
+    /// The input was refused: a malformed key, name, query, or duration.
+    Validation,
+    /// The store could not be opened, read, or written.
+    Storage,
+    /// Exchanging data with another store failed.
+    Sync,
+    /// The write is larger than the store accepts.
+    Quota,
+    /// A search or count ran past [`Store::with_query_timeout`](crate::Store::with_query_timeout)
+    /// and was stopped.
+    Timeout,
+}
+
+/// Any failure from this crate. Its message is meant for people; branch on [`Error::kind`].
+pub struct Error {
+    kind: ErrorKind,
+    inner: ContextError,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub(crate) fn wrap(inner: ContextError) -> Self {
+        let kind = match &inner {
+            ContextError::NotFound(_) => ErrorKind::NotFound,
+            ContextError::Conflict(Conflict::Version(conflict)) => ErrorKind::VersionConflict {
+                expected: conflict.expected,
+                actual: conflict.actual,
+            },
+            ContextError::Conflict(_) => ErrorKind::Conflict,
+            ContextError::Validation(_) => ErrorKind::Validation,
+            ContextError::Storage(_) => ErrorKind::Storage,
+            ContextError::Sync(_) => ErrorKind::Sync,
+            ContextError::Quota(_) => ErrorKind::Quota,
+            ContextError::Timeout(_) => ErrorKind::Timeout,
+        };
+        Self { kind, inner }
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Error")
+            .field("kind", &self.kind)
+            .field("message", &format_args!("{self}"))
+            .finish()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The causes too, e.g. "Failed to open ...: unable to open database file".
+        write!(f, "{}", self.inner)?;
+        let mut source = self.inner.source();
+        while let Some(cause) = source {
+            write!(f, ": {cause}")?;
+            source = cause.source();
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,226 @@
+//! Embed a Context store in your own Rust tool.
+//!
+//! This crate is the supported API for programs other than the ones in this workspace.
+//! It follows semver: a release that breaks code written against it bumps the major
+//! version. `context-core` behind it is free to change whenever the CLI or server needs
+//! it to, so depend on this crate rather than on `context-core`.
+//!
+//! The guarantees come from what is left out:
+//! - No database types appear in a signature; [`Store::open`] takes a file path.
+//! - [`Error`] is opaque. Branch on [`Error::kind`], whose [`ErrorKind`] is
+//!   `#[non_exhaustive]`.
+//! - No `context-core` type appears either. [`Document`], [`SearchHit`], and the other
+//!   results are this crate's own and `#[non_exhaustive]`: read their fields freely, but
+//!   only the store builds one, so a field added later breaks nobody.
+//! - Documents are written from a [`Draft`], and [`ListQuery`] and [`SearchQuery`] are
+//!   built with methods, so new options arrive as new methods.
+//!
+//! ```no_run
+//! # async fn demo() -> context::Result<()> {
+//! let store = context::Store::open("/tmp/context/db.sqlite").await?;
+//! let doc = store
+//!     .store(context::Draft::new("acme", "Deploys go out behind a canary.").key("deploy"))
+//!     .await?;
+//! assert_eq!(doc.version, 1);
+//! let hits = store
+//!     .search(context::SearchQuery::new("canary").project("acme"))
+//!     .await?;
+//! # Ok(()) }
+//! ```
+
+mod document;
+mod error;
+mod query;
+
+use std::{path::Path, time::Duration};
+
+use chrono::Utc;
+use context_core::{sqlite::SqliteStorage, DocumentId, SourceMeta, Storage};
+use uuid::Uuid;
+
+pub use document::{Document, ListPage, ScoreBreakdown, SearchHit, SourceType, Visibility};
+pub use error::{Error, ErrorKind, Result};
+pub use query::{ListQuery, ListSort, SearchQuery};
+
+/// A document to write: a body in a project, plus whatever else the caller sets.
+#[derive(Debug, Clone)]
+pub struct Draft {
+    doc: context_core::Document,
+}
+
+impl Draft {
+    pub fn new(project: impl Into<String>, body_markdown: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            doc: context_core::Document {
+                id: DocumentId(Uuid::new_v4().to_string()),
+                project: project.into(),
+                key: None,
+                namespace: None,
+                title: None,
+                tags: Vec::new(),
+                body_markdown: body_markdown.into(),
+                created_at: now,
+                updated_at: now,
+                source: context_core::SourceType::Tool,
+                source_meta: None,
+                version: 1,
+                ttl_seconds: None,
+                deleted_at: None,
+                visibility: None,
+                metadata: Default::default(),
+            },
+        }
+    }
+
+    /// Writing a draft whose key is taken makes it the next revision of that document.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.doc.key = Some(key.into());
+        self
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.doc.namespace = Some(namespace.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.doc.title = Some(title.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.doc.tags.push(tag.into());
+        self
+    }
+
+    /// A structured field such as `ticket` or `url`; see [`Document::metadata`].
+    pub fn meta(mut self, name: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.doc.metadata.insert(name.into(), value.into());
+        self
+    }
+
+    /// Expire the document this many seconds after it was first created.
+    pub fn ttl_seconds(mut self, ttl_seconds: i64) -> Self {
+        self.doc.ttl_seconds = Some(ttl_seconds);
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.doc.visibility = Some(visibility.into_core());
+        self
+    }
+
+    /// Who wrote it; defaults to [`SourceType::Tool`] named by `tool`.
+    pub fn source(mut self, source: SourceType, tool: Option<String>) -> Self {
+        self.doc.source = source.into_core();
+        self.doc.source_meta = tool.map(|tool| SourceMeta {
+            tool: Some(tool),
+            session: None,
+            fetched_from: None,
+        });
+        self
+    }
+}
+
+/// A Context store: one SQLite file, shared safely with the CLI and server using it.
+#[derive(Clone)]
+pub struct Store {
+    inner: SqliteStorage,
+}
+
+impl Store {
+    /// Open the store at `path`, creating it and bringing its schema up to date as needed.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let inner = SqliteStorage::open(path.as_ref())
+            .await
+            .map_err(Error::wrap)?;
+        Ok(Self { inner })
+    }
+
+    /// Stop a search or count still running after `limit`, failing it with
+    /// [`ErrorKind::Timeout`]. Without this they run to completion.
+    pub fn with_query_timeout(self, limit: Duration) -> Self {
+        Self {
+            inner: self.inner.with_query_timeout(limit),
+        }
+    }
+
+    /// Write `draft`, as the next revision of the live document with its key if there is one.
+    pub async fn store(&self, draft: Draft) -> Result<Document> {
+        self.inner
+            .store(draft.doc)
+            .await
+            .map(Document::from_core)
+            .map_err(Error::wrap)
+    }
+
+    /// `store`, but only while the live document at the draft's key is at
+    /// `expected_version`; otherwise fails with [`ErrorKind::VersionConflict`].
+    pub async fn store_expecting(&self, draft: Draft, expected_version: u64) -> Result<Document> {
+        self.inner
+            .store_expecting(draft.doc, expected_version)
+            .await
+            .map(Document::from_core)
+            .map_err(Error::wrap)
+    }
+
+    /// The live document at `key`, or at the key it redirects to after a rename or merge.
+    pub async fn get(&self, project: &str, key: &str) -> Result<Option<Document>> {
+        self.inner
+            .resolve_key(&project.to_string(), key)
+            .await
+            .map(|found| found.map(|(doc, _)| Document::from_core(doc)))
+            .map_err(Error::wrap)
+    }
+
+    pub async fn get_by_id(&self, id: &str) -> Result<Option<Document>> {
+        self.inner
+            .get_by_id(&DocumentId(id.to_string()))
+            .await
+            .map(|doc| doc.map(Document::from_core))
+            .map_err(Error::wrap)
+    }
+
+    /// Every revision of a document, oldest first.
+    pub async fn history(&self, id: &str) -> Result<Vec<Document>> {
+        self.inner
+            .history(&DocumentId(id.to_string()))
+            .await
+            .map(|docs| docs.into_iter().map(Document::from_core).collect())
+            .map_err(Error::wrap)
+    }
+
+    pub async fn search(&self, query: SearchQuery) -> Result<Vec<SearchHit>> {
+        self.inner
+            .search(query.into_core())
+            .await
+            .map(|hits| hits.into_iter().map(SearchHit::from_core).collect())
+            .map_err(Error::wrap)
+    }
+
+    /// One page of a project's documents, in the query's sort.
+    pub async fn list(&self, project: &str, query: &ListQuery) -> Result<ListPage> {
+        self.inner
+            .list_page(&project.to_string(), query.as_core())
+            .await
+            .map(ListPage::from_core)
+            .map_err(Error::wrap)
+    }
+
+    pub async fn count(&self, project: &str, query: &ListQuery) -> Result<u64> {
+        self.inner
+            .count(&project.to_string(), query.as_core())
+            .await
+            .map_err(Error::wrap)
+    }
+
+    /// Soft-delete the live document at `key`; `None` when there is none.
+    pub async fn delete(&self, project: &str, key: &str) -> Result<Option<Document>> {
+        self.inner
+            .delete_expecting(&project.to_string(), key, None)
+            .await
+            .map(|doc| doc.map(Document::from_core))
+            .map_err(Error::wrap)
+    }
+}
@@ -0,0 +1,176 @@
+//! What to ask the store for. Built with methods, so a filter added later leaves existing
+//! queries compiling and meaning what they did.
+
+use chrono::{DateTime, Utc};
+
+/// A full-text search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    inner: context_core::SearchQuery,
+}
+
+impl SearchQuery {
+    /// Search every project for `text`.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            inner: context_core::SearchQuery {
+                text: text.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn project(mut self, project: impl Into<String>) -> Self {
+        self.inner.project = Some(project.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.inner.limit = Some(limit);
+        self
+    }
+
+    /// Drop documents carrying this tag.
+    pub fn exclude_tag(mut self, tag: impl Into<String>) -> Self {
+        self.inner.exclude_tags.push(tag.into());
+        self
+    }
+
+    /// Drop documents in this namespace.
+    pub fn exclude_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.inner.exclude_namespaces.push(namespace.into());
+        self
+    }
+
+    /// Only search documents whose key starts with `prefix`.
+    pub fn key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.inner.key_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Only search this key; call again to add more.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.inner.keys.push(key.into());
+        self
+    }
+
+    /// Match terms against identifiers in fenced code blocks only.
+    pub fn code(mut self) -> Self {
+        self.inner.code = true;
+        self
+    }
+
+    pub(crate) fn into_core(self) -> context_core::SearchQuery {
+        self.inner
+    }
+}
+
+/// The order of a listing. Ties break by document id.
+///
+/// New orders may be added in minor releases; match with a `_` arm.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ListSort {
+    /// Most recently updated first.
+    #[default]
+    Updated,
+    /// Most recently created first.
+    Created,
+    /// By key, A to Z; documents without a key come first.
+    Key,
+}
+
+/// Filters and paging for listing a project's documents. The default lists every live
+/// document, most recently updated first.
+#[derive(Debug, Clone, Default)]
+pub struct ListQuery {
+    inner: context_core::ListQuery,
+}
+
+impl ListQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only documents carrying this tag; call again to require more.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.inner.tags.push(tag.into());
+        self
+    }
+
+    /// Drop documents carrying this tag.
+    pub fn not_tag(mut self, tag: impl Into<String>) -> Self {
+        self.inner.not_tags.push(tag.into());
+        self
+    }
+
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.inner.namespace = Some(namespace.into());
+        self
+    }
+
+    pub fn exclude_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.inner.exclude_namespaces.push(namespace.into());
+        self
+    }
+
+    pub fn key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.inner.key_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Updated at or after `since`.
+    pub fn since(mut self, since: DateTime<Utc>) -> Self {
+        self.inner.since = Some(since);
+        self
+    }
+
+    /// Updated before `until`.
+    pub fn until(mut self, until: DateTime<Utc>) -> Self {
+        self.inner.until = Some(until);
+        self
+    }
+
+    /// Also list deleted and expired documents.
+    pub fn include_deleted(mut self) -> Self {
+        self.inner.include_deleted = true;
+        self
+    }
+
+    /// List only deleted documents.
+    pub fn only_deleted(mut self) -> Self {
+        self.inner.only_deleted = true;
+        self
+    }
+
+    /// Only documents whose metadata field `name` reads `value`, compared as text.
+    pub fn meta(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.inner.metadata.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: ListSort) -> Self {
+        self.inner.sort = match sort {
+            ListSort::Updated => context_core::ListSort::Updated,
+            ListSort::Created => context_core::ListSort::Created,
+            ListSort::Key => context_core::ListSort::Key,
+        };
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.inner.limit = Some(limit);
+        self
+    }
+
+    /// Continue from [`ListPage::next_cursor`](crate::ListPage::next_cursor), listed in the
+    /// same sort.
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.inner.cursor = Some(cursor.into());
+        self
+    }
+
+    pub(crate) fn as_core(&self) -> &context_core::ListQuery {
+        &self.inner
+    }
+}
@@ -0,0 +1,54 @@
+use context::{Draft, ErrorKind, ListQuery, SearchQuery, Store};
+use tempfile::tempdir;
+
+#[tokio::test]
+async fn a_tool_can_write_read_and_search_through_the_facade() -> context::Result<()> {
+    let dir = tempdir().unwrap();
+    let store = Store::open(dir.path().join("db.sqlite")).await?;
+
+    let first = store
+        .store(
+            Draft::new("acme", "Deploys go out behind a canary.")
+                .key("deploy")
+                .tag("ops"),
+        )
+        .await?;
+    let second = store
+        .store(Draft::new("acme", "Canary for an hour, then everyone.").key("deploy"))
+        .await?;
+    assert_eq!((second.id.as_str(), second.version), (first.id.as_str(), 2));
+    assert_eq!(store.history(&first.id).await?.len(), 2);
+
+    let err = store
+        .store_expecting(Draft::new("acme", "stale").key("deploy"), 1)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        ErrorKind::VersionConflict {
+            expected: 1,
+            actual: Some(2)
+        }
+    );
+
+    let hits = store
+        .search(SearchQuery::new("everyone").project("acme"))
+        .await?;
+    assert_eq!(hits[0].document.key.as_deref(), Some("deploy"));
+    assert_eq!(store.count("acme", &ListQuery::new()).await?, 1);
+    assert_eq!(
+        store.count("acme", &ListQuery::new().tag("ops")).await?,
+        0,
+        "the second revision dropped the tag"
+    );
+
+    let err = store
+        .store(Draft::new("acme", "no namespace").namespace(" "))
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Validation);
+
+    store.delete("acme", "deploy").await?;
+    assert!(store.get("acme", "deploy").await?.is_none());
+    Ok(())
+}
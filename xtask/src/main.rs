@@ -0,0 +1,344 @@
+//! Workspace automation, run as `cargo xtask <task>`.
+//!
+//! `dist` builds the release binaries for each target and packages them with shell
+//! completions and the agent guide into checksummed archives under `dist/`.
+
+use std::{
+    env, fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use sha2::{Digest, Sha256};
+
+/// Binaries shipped in every archive: (cargo package, name in the archive).
+const BINARIES: &[(&str, &str)] = &[
+    ("context-cli", "context"),
+    ("context-web", "context-web"),
+    ("context-grpc", "context-grpc"),
+];
+
+/// Completion scripts: (shell, file name in `completions/`).
+const COMPLETIONS: &[(&str, &str)] = &[
+    ("bash", "context.bash"),
+    ("zsh", "_context"),
+    ("fish", "context.fish"),
+    ("powershell", "_context.ps1"),
+];
+
+#[derive(Parser)]
+#[command(name = "xtask")]
+struct Xtask {
+    #[command(subcommand)]
+    task: Task,
+}
+
+#[derive(Subcommand)]
+enum Task {
+    /// Build release archives of all binaries
+    Dist {
+        /// Target triple to build for (repeatable; default: this machine)
+        #[arg(long = "target")]
+        targets: Vec<String>,
+        /// Where archives and SHA256SUMS are written
+        #[arg(long, default_value = "dist")]
+        out_dir: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    match Xtask::parse().task {
+        Task::Dist { targets, out_dir } => dist(targets, &out_dir),
+    }
+}
+
+fn dist(targets: Vec<String>, out_dir: &Path) -> Result<()> {
+    let root = workspace_root()?;
+    let out_dir = root.join(out_dir);
+    let version = package_version(&root, "context-cli")?;
+    let host = host_target()?;
+    let targets = if targets.is_empty() {
+        vec![host.clone()]
+    } else {
+        targets
+    };
+
+    // Completions and the agent guide come from a CLI that runs here, whatever the targets.
+    cargo(&root, &["build", "--release", "-p", "context-cli"])?;
+    let host_cli = root
+        .join("target/release")
+        .join(exe_name("context-cli", &host));
+
+    fs::create_dir_all(&out_dir)?;
+    let mut archives = Vec::new();
+    for target in &targets {
+        let packages: Vec<&str> = BINARIES
+            .iter()
+            .flat_map(|(package, _)| ["-p", package])
+            .collect();
+        let mut args = vec!["build", "--release", "--target", target];
+        args.extend(packages);
+        cargo(&root, &args)?;
+
+        let name = format!("context-{version}-{target}");
+        let stage = out_dir.join(&name);
+        if stage.exists() {
+            fs::remove_dir_all(&stage)?;
+        }
+        fs::create_dir_all(stage.join("completions"))?;
+        fs::create_dir_all(stage.join("docs"))?;
+
+        let release = root.join("target").join(target).join("release");
+        for (package, shipped) in BINARIES {
+            fs::copy(
+                release.join(exe_name(package, target)),
+                stage.join(exe_name(shipped, target)),
+            )
+            .with_context(|| format!("Missing {package} build for {target}"))?;
+        }
+        for (shell, file) in COMPLETIONS {
+            let script = run_captured(&host_cli, &["completions", shell])?;
+            fs::write(stage.join("completions").join(file), script)?;
+        }
+        let guide = run_captured(&host_cli, &["agent-doc", "--format", "markdown"])?;
+        fs::write(stage.join("docs/agent-doc.md"), guide)?;
+        fs::copy(root.join("README.md"), stage.join("README.md"))?;
+
+        let archive = out_dir.join(archive_name(&name, target));
+        if target.contains("windows") {
+            write_zip(&stage, &name, &archive)?;
+        } else {
+            write_tar_gz(&stage, &name, &archive)?;
+        }
+        fs::remove_dir_all(&stage)?;
+        println!("packaged {}", archive.display());
+        archives.push(archive);
+    }
+
+    let mut sums = String::new();
+    for archive in &archives {
+        sums.push_str(&checksum_line(archive)?);
+    }
+    let sums_path = out_dir.join("SHA256SUMS");
+    fs::write(&sums_path, sums)?;
+    println!("wrote {}", sums_path.display());
+    Ok(())
+}
+
+fn workspace_root() -> Result<PathBuf> {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").context("Run this through `cargo xtask`")?;
+    Path::new(&manifest_dir)
+        .parent()
+        .map(Path::to_path_buf)
+        .context("xtask has no parent directory")
+}
+
+fn package_version(root: &Path, package: &str) -> Result<String> {
+    let pkgid = run_captured_in(root, "cargo", &["pkgid", "-p", package])?;
+    version_from_pkgid(&pkgid)
+        .map(str::to_string)
+        .with_context(|| format!("Unexpected `cargo pkgid` output: {pkgid}"))
+}
+
+/// `path+file:///src/context-cli#0.1.0` or `...#context-cli@0.1.0` → `0.1.0`.
+fn version_from_pkgid(pkgid: &str) -> Option<&str> {
+    let fragment = pkgid.trim().rsplit_once('#')?.1;
+    let version = fragment.rsplit_once('@').map_or(fragment, |(_, v)| v);
+    (!version.is_empty()).then_some(version)
+}
+
+fn host_target() -> Result<String> {
+    let info = run_captured_in(Path::new("."), "rustc", &["-vV"])?;
+    info.lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::to_string)
+        .context("`rustc -vV` did not report a host target")
+}
+
+fn exe_name(name: &str, target: &str) -> String {
+    if target.contains("windows") {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    }
+}
+
+fn archive_name(name: &str, target: &str) -> String {
+    if target.contains("windows") {
+        format!("{name}.zip")
+    } else {
+        format!("{name}.tar.gz")
+    }
+}
+
+fn cargo(root: &Path, args: &[&str]) -> Result<()> {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(&cargo)
+        .args(args)
+        .current_dir(root)
+        .status()
+        .with_context(|| format!("Failed to run {cargo}"))?;
+    if !status.success() {
+        bail!("`cargo {}` failed", args.join(" "));
+    }
+    Ok(())
+}
+
+fn run_captured(program: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new(program)
+        .args(args)
+        // Keep the packager's own store and logs out of it: these runs only print text.
+        .env("CONTEXT_HOME", env::temp_dir().join("context-xtask"))
+        .env_remove("CONTEXT_LOG_DIR")
+        .output()
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+    if !output.status.success() {
+        bail!(
+            "`{} {}` failed: {}",
+            program.display(),
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+fn run_captured_in(dir: &Path, program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("Failed to run {program}"))?;
+    if !output.status.success() {
+        bail!(
+            "`{program} {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Files under `dir`, relative to it, sorted so archives are reproducible.
+fn staged_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path.strip_prefix(dir)?.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn write_tar_gz(stage: &Path, name: &str, archive: &Path) -> Result<()> {
+    let encoder =
+        flate2::write::GzEncoder::new(fs::File::create(archive)?, flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for file in staged_files(stage)? {
+        builder.append_path_with_name(stage.join(&file), Path::new(name).join(&file))?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn write_zip(stage: &Path, name: &str, archive: &Path) -> Result<()> {
+    let mut writer = zip::ZipWriter::new(fs::File::create(archive)?);
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for file in staged_files(stage)? {
+        let entry = Path::new(name).join(&file);
+        writer.start_file(entry.to_string_lossy().replace('\\', "/"), options)?;
+        let mut contents = Vec::new();
+        fs::File::open(stage.join(&file))?.read_to_end(&mut contents)?;
+        writer.write_all(&contents)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// A `sha256sum`-compatible line for `path`: `<hex>  <file name>`.
+fn checksum_line(path: &Path) -> Result<String> {
+    let digest = Sha256::digest(fs::read(path)?);
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    let file_name = path
+        .file_name()
+        .context("checksummed path has no file name")?
+        .to_string_lossy();
+    Ok(format!("{hex}  {file_name}\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_come_from_either_pkgid_form() {
+        assert_eq!(
+            version_from_pkgid("path+file:///src/context/context-cli#0.1.0\n"),
+            Some("0.1.0")
+        );
+        assert_eq!(
+            version_from_pkgid("path+file:///src/context/cli#context-cli@1.2.3"),
+            Some("1.2.3")
+        );
+        assert_eq!(version_from_pkgid("context-cli"), None);
+    }
+
+    #[test]
+    fn windows_targets_get_exe_names_and_zips() {
+        assert_eq!(exe_name("context", "x86_64-pc-windows-msvc"), "context.exe");
+        assert_eq!(
+            archive_name(
+                "context-0.1.0-x86_64-pc-windows-msvc",
+                "x86_64-pc-windows-msvc"
+            ),
+            "context-0.1.0-x86_64-pc-windows-msvc.zip"
+        );
+        assert_eq!(
+            archive_name("context-0.1.0-aarch64-apple-darwin", "aarch64-apple-darwin"),
+            "context-0.1.0-aarch64-apple-darwin.tar.gz"
+        );
+    }
+
+    #[test]
+    fn archives_hold_the_staged_tree_and_a_checksum() {
+        let temp = tempfile::tempdir().unwrap();
+        let stage = temp.path().join("stage");
+        fs::create_dir_all(stage.join("completions")).unwrap();
+        fs::write(stage.join("context"), "binary").unwrap();
+        fs::write(stage.join("completions/_context"), "#compdef context").unwrap();
+
+        let archive = temp.path().join("context-0.1.0-test.tar.gz");
+        write_tar_gz(&stage, "context-0.1.0-test", &archive).unwrap();
+        let mut tarball = tar::Archive::new(flate2::read::GzDecoder::new(
+            fs::File::open(&archive).unwrap(),
+        ));
+        let names: Vec<String> = tarball
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "context-0.1.0-test/completions/_context",
+                "context-0.1.0-test/context"
+            ]
+        );
+
+        let line = checksum_line(&archive).unwrap();
+        let (hex, file) = line.trim_end().split_once("  ").unwrap();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(file, "context-0.1.0-test.tar.gz");
+    }
+}